@@ -0,0 +1,231 @@
+use crate::fixtures::{FixtureRecorder, request_key};
+use crate::{ChronEntities, ChronStreamError};
+use log::debug;
+use reqwest::header::{
+    ETAG, HeaderMap, HeaderValue, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+};
+use reqwest::{Request, StatusCode};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+struct CachedResponse {
+    etag: Option<HeaderValue>,
+    last_modified: Option<HeaderValue>,
+    body: Arc<ChronEntities<serde_json::Value>>,
+}
+
+#[derive(Default)]
+struct CacheInner {
+    entries: HashMap<String, Arc<CachedResponse>>,
+    // Oldest first. Used to evict entries once the cache is full.
+    order: VecDeque<String>,
+}
+
+/// Remembers the validators (`ETag` and `Last-Modified`) and the parsed body
+/// of the most recent responses from Chron, keyed by URL. Re-fetching one of
+/// those URLs sends the validators, and if Chron says nothing changed the
+/// remembered body is reused instead of downloading and deserializing it
+/// again. Bodies are shared rather than copied, so a hit costs no more than
+/// cloning an `Arc`. Clones share the same cache.
+#[derive(Clone)]
+pub(crate) struct ResponseCache {
+    inner: Arc<Mutex<CacheInner>>,
+    capacity: usize,
+    recorder: Option<FixtureRecorder>,
+}
+
+impl ResponseCache {
+    /// A `capacity` of 0 disables the cache
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(CacheInner::default())),
+            capacity,
+            recorder: None,
+        }
+    }
+
+    /// Everything this fetches is also given to `recorder`, if there is one
+    pub fn with_recorder(mut self, recorder: Option<FixtureRecorder>) -> Self {
+        self.recorder = recorder;
+        self
+    }
+
+    pub fn recorder(&self) -> Option<&FixtureRecorder> {
+        self.recorder.as_ref()
+    }
+
+    fn get(&self, url: &str) -> Option<Arc<CachedResponse>> {
+        let inner = self
+            .inner
+            .lock()
+            .expect("Chron response cache lock was poisoned");
+        inner.entries.get(url).cloned()
+    }
+
+    /// Drops whatever is remembered for `url`, so the next fetch of it
+    /// downloads the whole response
+    pub fn forget(&self, url: &str) {
+        let mut inner = self
+            .inner
+            .lock()
+            .expect("Chron response cache lock was poisoned");
+        if inner.entries.remove(url).is_some() {
+            inner.order.retain(|entry_url| entry_url != url);
+        }
+    }
+
+    fn insert(
+        &self,
+        url: String,
+        headers: &HeaderMap,
+        body: Arc<ChronEntities<serde_json::Value>>,
+    ) {
+        let etag = headers.get(ETAG).cloned();
+        let last_modified = headers.get(LAST_MODIFIED).cloned();
+        // Responses without validators can't be revalidated, so there's no
+        // point keeping them
+        if self.capacity == 0 || (etag.is_none() && last_modified.is_none()) {
+            return;
+        }
+
+        let mut inner = self
+            .inner
+            .lock()
+            .expect("Chron response cache lock was poisoned");
+        let entry = Arc::new(CachedResponse {
+            etag,
+            last_modified,
+            body,
+        });
+        if inner.entries.insert(url.clone(), entry).is_none() {
+            inner.order.push_back(url);
+        }
+        while inner.order.len() > self.capacity {
+            if let Some(oldest) = inner.order.pop_front() {
+                inner.entries.remove(&oldest);
+            }
+        }
+    }
+
+    /// Executes `request`, sending validators if this URL was fetched
+    /// before, and deserializes the response (or reuses the previous one if
+    /// it was unchanged). The result is shared with the cache, so callers
+    /// that need to own it should only copy it once they have to.
+    pub async fn fetch(
+        &self,
+        client: &reqwest::Client,
+        mut request: Request,
+    ) -> Result<Arc<ChronEntities<serde_json::Value>>, ChronStreamError> {
+        let url = request.url().to_string();
+        let fixture_request = self
+            .recorder
+            .as_ref()
+            .map(|recorder| (recorder, request_key(request.url())));
+
+        // This has to be kept until the response arrives, because the entry
+        // could be evicted in the meantime
+        let cached = self.get(&url);
+        if let Some(cached) = &cached {
+            if let Some(etag) = &cached.etag {
+                request.headers_mut().insert(IF_NONE_MATCH, etag.clone());
+            }
+            if let Some(last_modified) = &cached.last_modified {
+                request
+                    .headers_mut()
+                    .insert(IF_MODIFIED_SINCE, last_modified.clone());
+            }
+        }
+
+        let response = client
+            .execute(request)
+            .await
+            .map_err(ChronStreamError::RequestExecuteError)?
+            .error_for_status()
+            .map_err(ChronStreamError::ChronStatusError)?;
+
+        if let (StatusCode::NOT_MODIFIED, Some(cached)) = (response.status(), cached) {
+            debug!("{url} is unchanged since it was last fetched");
+            if let Some((recorder, fixture_request)) = &fixture_request {
+                recorder.record(fixture_request, &cached.body);
+            }
+            return Ok(Arc::clone(&cached.body));
+        }
+
+        let headers = response.headers().clone();
+        let result = response
+            .text()
+            .await
+            .map_err(ChronStreamError::RequestBodyError)?;
+
+        let items: ChronEntities<serde_json::Value> =
+            serde_json::from_str(&result).map_err(ChronStreamError::DeserializeError)?;
+        let items = Arc::new(items);
+
+        if let Some((recorder, fixture_request)) = &fixture_request {
+            recorder.record(fixture_request, &items);
+        }
+
+        self.insert(url, &headers, Arc::clone(&items));
+
+        Ok(items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(next_page: &str) -> Arc<ChronEntities<serde_json::Value>> {
+        Arc::new(ChronEntities {
+            items: Vec::new(),
+            next_page: Some(next_page.to_string()),
+        })
+    }
+
+    fn headers_with_etag(etag: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(ETAG, HeaderValue::from_static(etag));
+        headers
+    }
+
+    #[test]
+    fn oldest_entries_are_evicted() {
+        let cache = ResponseCache::new(2);
+        cache.insert("a".to_string(), &headers_with_etag("\"1\""), page("a"));
+        cache.insert("b".to_string(), &headers_with_etag("\"2\""), page("b"));
+        cache.insert("c".to_string(), &headers_with_etag("\"3\""), page("c"));
+
+        assert!(cache.get("a").is_none());
+        assert!(cache.get("b").is_some());
+        assert_eq!(cache.get("c").unwrap().body.next_page.as_deref(), Some("c"));
+    }
+
+    #[test]
+    fn cached_bodies_are_shared_rather_than_copied() {
+        let cache = ResponseCache::new(2);
+        let body = page("a");
+        cache.insert(
+            "a".to_string(),
+            &headers_with_etag("\"1\""),
+            Arc::clone(&body),
+        );
+
+        assert!(Arc::ptr_eq(&cache.get("a").unwrap().body, &body));
+    }
+
+    #[test]
+    fn a_cache_without_capacity_is_off() {
+        let cache = ResponseCache::new(0);
+        cache.insert("a".to_string(), &headers_with_etag("\"1\""), page("a"));
+
+        assert!(cache.get("a").is_none());
+    }
+
+    #[test]
+    fn responses_without_validators_are_not_cached() {
+        let cache = ResponseCache::new(2);
+        cache.insert("a".to_string(), &HeaderMap::new(), page("a"));
+
+        assert!(cache.get("a").is_none());
+    }
+}
@@ -0,0 +1,164 @@
+use crate::{ChronEntity, ChronStreamError};
+use chrono::{DateTime, Utc};
+use futures::{Stream, StreamExt};
+use log::debug;
+use std::collections::{HashSet, VecDeque};
+use std::future;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+type VersionKey = (String, DateTime<Utc>);
+
+/// The most recent `(entity_id, valid_from)` pairs that a stream has
+/// yielded. Once it's full, the oldest pair is forgotten to make room.
+struct RecentVersions {
+    seen: HashSet<VersionKey>,
+    // Oldest first. Used to evict pairs once the set is full.
+    order: VecDeque<VersionKey>,
+    capacity: usize,
+}
+
+impl RecentVersions {
+    fn new(capacity: usize) -> Self {
+        Self {
+            seen: HashSet::with_capacity(capacity),
+            order: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Returns false if this pair was already seen. `capacity` must not be
+    /// 0.
+    fn insert(&mut self, entity_id: &str, valid_from: DateTime<Utc>) -> bool {
+        let key = (entity_id.to_string(), valid_from);
+        if self.seen.contains(&key) {
+            return false;
+        }
+
+        if self.order.len() >= self.capacity {
+            let oldest = self
+                .order
+                .pop_front()
+                .expect("A full set of recent versions can't be empty");
+            self.seen.remove(&oldest);
+        }
+        self.seen.insert(key.clone());
+        self.order.push_back(key);
+        true
+    }
+}
+
+/// How many duplicate versions have been dropped from Chron streams. Clones
+/// share the same count.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DuplicateCounter {
+    suppressed: Arc<AtomicU64>,
+}
+
+impl DuplicateCounter {
+    pub fn suppressed(&self) -> u64 {
+        self.suppressed.load(Ordering::Relaxed)
+    }
+
+    fn record(&self) {
+        self.suppressed.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Drops any item whose `(entity_id, valid_from)` matches one of the last
+/// `capacity` items. Streams are sorted by `valid_from`, so duplicates are
+/// always close together. Errors are passed through untouched. A `capacity`
+/// of 0 turns deduplication off.
+pub(crate) fn dedup_versions<S>(
+    stream: S,
+    capacity: usize,
+    counter: DuplicateCounter,
+) -> impl Stream<Item = Result<ChronEntity<serde_json::Value>, ChronStreamError>> + use<S>
+where
+    S: Stream<Item = Result<ChronEntity<serde_json::Value>, ChronStreamError>>,
+{
+    let mut recent = RecentVersions::new(capacity);
+    stream.filter(move |result| {
+        let keep = match result {
+            Ok(entity) if capacity > 0 => {
+                let is_new = recent.insert(&entity.entity_id, entity.valid_from);
+                if !is_new {
+                    debug!(
+                        "Dropping duplicate {} version of {} valid from {}",
+                        entity.kind, entity.entity_id, entity.valid_from,
+                    );
+                    counter.record();
+                }
+                is_new
+            }
+            _ => true,
+        };
+        future::ready(keep)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::stream;
+
+    fn version(entity_id: &str, valid_from: &str) -> ChronEntity<serde_json::Value> {
+        ChronEntity {
+            kind: "player".to_string(),
+            entity_id: entity_id.to_string(),
+            valid_from: valid_from.parse().unwrap(),
+            valid_to: None,
+            data: serde_json::Value::Null,
+        }
+    }
+
+    fn dedup_all(
+        versions: Vec<ChronEntity<serde_json::Value>>,
+        capacity: usize,
+    ) -> (Vec<(String, DateTime<Utc>)>, u64) {
+        let counter = DuplicateCounter::default();
+        let versions = stream::iter(versions.into_iter().map(Ok));
+        let stream = dedup_versions(versions, capacity, counter.clone());
+        let kept = futures::executor::block_on(stream.collect::<Vec<_>>())
+            .into_iter()
+            .map(|result| {
+                let entity = result.unwrap();
+                (entity.entity_id, entity.valid_from)
+            })
+            .collect();
+        (kept, counter.suppressed())
+    }
+
+    #[test]
+    fn duplicates_at_a_segment_boundary_are_dropped() {
+        let (kept, suppressed) = dedup_all(
+            vec![
+                version("a", "2025-09-13T22:02:43Z"),
+                version("b", "2025-09-13T22:02:43Z"),
+                // The next segment starts where the last one ended
+                version("a", "2025-09-13T22:02:43Z"),
+                version("b", "2025-09-13T22:02:43Z"),
+                version("a", "2025-09-14T00:00:00Z"),
+            ],
+            10,
+        );
+
+        assert_eq!(kept.len(), 3);
+        assert_eq!(suppressed, 2);
+    }
+
+    #[test]
+    fn forgotten_versions_are_not_dropped() {
+        let (kept, suppressed) = dedup_all(
+            vec![
+                version("a", "2025-09-13T22:02:43Z"),
+                version("b", "2025-09-13T22:02:43Z"),
+                version("a", "2025-09-13T22:02:43Z"),
+            ],
+            1,
+        );
+
+        assert_eq!(kept.len(), 3);
+        assert_eq!(suppressed, 0);
+    }
+}
@@ -0,0 +1,318 @@
+use crate::{Chron, ChronEntities};
+use log::warn;
+use miette::Diagnostic;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::num::NonZero;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use thiserror::Error;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+#[derive(Debug, Error, Diagnostic)]
+pub enum FixtureError {
+    #[error("error creating fixture directory {path}")]
+    CreateDirError {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("error reading fixture {path}")]
+    ReadError {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("error parsing fixture {path}")]
+    ParseError {
+        path: String,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    #[error("error starting fixture server")]
+    BindError(#[source] std::io::Error),
+}
+
+/// One response from Chron, as it's stored on disk
+#[derive(Serialize, Deserialize)]
+struct RecordedResponse {
+    /// See [`request_key`]
+    request: String,
+    body: ChronEntities<serde_json::Value>,
+}
+
+/// What identifies a request to Chron regardless of which mirror it went
+/// to: the endpoint and the query string, e.g.
+/// `entities?kind=game&count=100&order=asc`
+pub(crate) fn request_key(url: &reqwest::Url) -> String {
+    let endpoint = url
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .unwrap_or_default();
+    match url.query() {
+        Some(query) => format!("{endpoint}?{query}"),
+        None => endpoint.to_string(),
+    }
+}
+
+/// Saves every response [`Chron`] receives to a directory, one file each,
+/// so they can be served to tests by [`FixtureServer`]. Turn it on with
+/// [`Chron::with_fixture_recording`]. Clones write to the same directory.
+#[derive(Clone)]
+pub struct FixtureRecorder {
+    dir: PathBuf,
+    next_index: Arc<AtomicUsize>,
+}
+
+impl FixtureRecorder {
+    /// Creates `dir` if it doesn't exist. Responses that are already in it
+    /// are kept, and new ones are numbered after them.
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self, FixtureError> {
+        let dir = dir.into();
+        let create_dir_error = |source| FixtureError::CreateDirError {
+            path: dir.display().to_string(),
+            source,
+        };
+        std::fs::create_dir_all(&dir).map_err(create_dir_error)?;
+        let num_existing = std::fs::read_dir(&dir).map_err(create_dir_error)?.count();
+
+        Ok(Self {
+            dir,
+            next_index: Arc::new(AtomicUsize::new(num_existing)),
+        })
+    }
+
+    /// Failing to record a response shouldn't fail the fetch, so errors are
+    /// only logged
+    pub(crate) fn record(&self, request: &str, body: &ChronEntities<serde_json::Value>) {
+        let endpoint = request.split('?').next().unwrap_or_default();
+        // Another recorder (e.g. for another kind) could be writing to the
+        // same directory, so skip over any numbers it has already used
+        let (path, file) = loop {
+            let index = self.next_index.fetch_add(1, Ordering::Relaxed);
+            let path = self.dir.join(format!("{index:05}-{endpoint}.json"));
+            match File::create_new(&path) {
+                Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                file => break (path, file),
+            }
+        };
+
+        let result = file.map_err(serde_json::Error::io).and_then(|file| {
+            serde_json::to_writer_pretty(
+                file,
+                &RecordedResponse {
+                    request: request.to_string(),
+                    body: body.clone(),
+                },
+            )
+        });
+        if let Err(err) = result {
+            warn!(
+                "Error recording Chron response to {}: {err}",
+                path.display()
+            );
+        }
+    }
+}
+
+/// Reads every response in `dir`. If a request was recorded more than
+/// once, the last recording wins.
+fn read_responses(dir: &Path) -> Result<HashMap<String, String>, FixtureError> {
+    let read_error = |path: &Path, source| FixtureError::ReadError {
+        path: path.display().to_string(),
+        source,
+    };
+
+    let mut paths = std::fs::read_dir(dir)
+        .map_err(|err| read_error(dir, err))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| read_error(dir, err))?;
+    // Files are numbered in the order they were recorded
+    paths.sort();
+
+    let mut responses = HashMap::new();
+    for path in paths {
+        if path.extension().is_none_or(|extension| extension != "json") {
+            continue;
+        }
+
+        let file = File::open(&path).map_err(|err| read_error(&path, err))?;
+        let parse_error = |source| FixtureError::ParseError {
+            path: path.display().to_string(),
+            source,
+        };
+        let recorded: RecordedResponse =
+            serde_json::from_reader(BufReader::new(file)).map_err(parse_error)?;
+        let body = serde_json::to_string(&recorded.body).map_err(parse_error)?;
+        responses.insert(recorded.request, body);
+    }
+
+    Ok(responses)
+}
+
+/// A local HTTP server that answers Chron requests with responses recorded
+/// by [`FixtureRecorder`], so tests can exercise [`Chron`] without the
+/// network. Requests that weren't recorded get a 404. Requests include the
+/// page size, so a [`Chron`] replaying a recording has to use the same page
+/// size it was recorded with. The server stops when this is dropped.
+pub struct FixtureServer {
+    base_url: String,
+    task: JoinHandle<()>,
+}
+
+impl FixtureServer {
+    /// Serves the responses recorded in `dir` on a random local port. Must
+    /// be called from within a Tokio runtime.
+    pub async fn start(dir: impl AsRef<Path>) -> Result<Self, FixtureError> {
+        let responses = Arc::new(read_responses(dir.as_ref())?);
+        let listener = TcpListener::bind(("127.0.0.1", 0))
+            .await
+            .map_err(FixtureError::BindError)?;
+        let address = listener.local_addr().map_err(FixtureError::BindError)?;
+
+        let task = tokio::spawn(async move {
+            loop {
+                let stream = match listener.accept().await {
+                    Ok((stream, _)) => stream,
+                    Err(err) => {
+                        warn!("Fixture server couldn't accept a connection: {err}");
+                        continue;
+                    }
+                };
+                let responses = responses.clone();
+                tokio::spawn(async move {
+                    if let Err(err) = respond(stream, &responses).await {
+                        warn!("Fixture server couldn't respond to a request: {err}");
+                    }
+                });
+            }
+        });
+
+        Ok(Self {
+            base_url: format!("http://{address}"),
+            task,
+        })
+    }
+
+    /// Pass this to [`Chron::with_base_url`] to send requests here
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// A [`Chron`] that sends every request here
+    pub fn chron(&self, page_size: NonZero<usize>) -> Chron {
+        Chron::new(page_size).with_base_url(self.base_url.clone())
+    }
+}
+
+impl Drop for FixtureServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Just enough HTTP/1.1 to answer one GET request and close the connection
+async fn respond(
+    mut stream: TcpStream,
+    responses: &HashMap<String, String>,
+) -> std::io::Result<()> {
+    let (read, mut write) = stream.split();
+    let mut reader = tokio::io::BufReader::new(read);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+    // The headers don't matter, but they have to be read before responding
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).await? == 0 || header.trim().is_empty() {
+            break;
+        }
+    }
+
+    // The request line is `GET /entities?kind=... HTTP/1.1`
+    let target = request_line.split_whitespace().nth(1).unwrap_or_default();
+    let request = target.trim_start_matches('/');
+    let (status, body) = match responses.get(request) {
+        Some(body) => ("200 OK", body.clone()),
+        None => {
+            warn!("No recorded Chron response for {request}");
+            (
+                "404 Not Found",
+                format!("No recorded response for {request}"),
+            )
+        }
+    };
+
+    let head = format!(
+        "HTTP/1.1 {status}\r\n\
+        Content-Type: application/json\r\n\
+        Content-Length: {}\r\n\
+        Connection: close\r\n\r\n",
+        body.len(),
+    );
+    write.write_all(head.as_bytes()).await?;
+    write.write_all(body.as_bytes()).await?;
+    write.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChronEntity, ChronProvider};
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn request_key_ignores_the_mirror() {
+        let free_cashews = reqwest::Url::parse(
+            "https://freecashe.ws/api/chron/v0/versions?kind=game&count=10&order=asc",
+        )
+        .unwrap();
+        let cheap_cashews = reqwest::Url::parse(
+            "https://cheapcashews.beiju.me/chron/v0/versions?kind=game&count=10&order=asc",
+        )
+        .unwrap();
+
+        assert_eq!(
+            request_key(&free_cashews),
+            "versions?kind=game&count=10&order=asc"
+        );
+        assert_eq!(request_key(&free_cashews), request_key(&cheap_cashews));
+    }
+
+    #[tokio::test]
+    async fn recorded_responses_are_replayed() {
+        let dir = std::env::temp_dir().join(format!("chron-fixtures-{}", std::process::id()));
+        let recorder = FixtureRecorder::new(&dir).unwrap();
+        let body = ChronEntities {
+            items: vec![ChronEntity {
+                kind: "game".to_string(),
+                entity_id: "abc".to_string(),
+                valid_from: Utc.with_ymd_and_hms(2025, 7, 1, 0, 0, 0).unwrap(),
+                valid_to: None,
+                data: serde_json::json!({ "State": "Complete" }),
+            }],
+            next_page: None,
+        };
+        recorder.record("entities?kind=game&id=abc&order=asc", &body);
+
+        let server = FixtureServer::start(&dir).await.unwrap();
+        let chron = server.chron(NonZero::new(100).unwrap());
+        let entities = chron.entities_by_id("game", &["abc"]).await.unwrap();
+        let not_recorded = chron.entities_by_id("game", &["def"]).await;
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(entities.items.len(), 1);
+        assert_eq!(entities.items[0].entity_id, "abc");
+        assert_eq!(entities.items[0].data, body.items[0].data);
+        assert!(not_recorded.is_err());
+    }
+}
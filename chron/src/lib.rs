@@ -1,12 +1,32 @@
 use chrono::{DateTime, Utc};
-use futures::{Stream, StreamExt, TryStreamExt, stream};
+use futures::future::BoxFuture;
+use futures::stream::BoxStream;
+use futures::{FutureExt, Stream, StreamExt, TryStreamExt, stream};
 use log::{debug, warn};
 use miette::Diagnostic;
 use serde::{Deserialize, Serialize};
 use std::future;
+use std::iter;
 use std::num::NonZero;
+use std::sync::Arc;
 use thiserror::Error;
 
+mod conditional;
+mod dedup;
+mod fixtures;
+mod local;
+mod mirrors;
+mod verify;
+
+use conditional::ResponseCache;
+use dedup::{DuplicateCounter, dedup_versions};
+pub use fixtures::{FixtureError, FixtureRecorder, FixtureServer};
+pub use local::LocalChron;
+use mirrors::MirrorHealth;
+pub use mirrors::{ChronMirror, SharedMirrorRange};
+pub use verify::{AnomalyHandler, ChronAnomaly};
+use verify::{PageExpectation, check_page};
+
 // TODO use const datetime_from_parts function defined... somewhere
 const CUTOVER_DATE: &str = "2025-09-13T22:02:43.355548Z";
 const CUTBACK_DATE: &str = "2025-10-27T11:16:00.000Z";
@@ -39,15 +59,46 @@ pub enum ChronStreamError {
 
     #[error("error deserializing Chron response structure")]
     DeserializeError(#[source] serde_json::Error),
+
+    #[error("error reading local Chron data from {path}")]
+    LocalReadError {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("chron returned a bad page twice in a row: {0}")]
+    PageAnomaly(String),
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl ChronStreamError {
+    /// Whether this error is likely to go away if the request is retried,
+    /// like a dropped connection or a 5xx from Chron. Errors that mean the
+    /// request or the response is malformed are not transient.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            ChronStreamError::RequestExecuteError(_)
+            | ChronStreamError::RequestBodyError(_)
+            | ChronStreamError::PageAnomaly(_) => true,
+            ChronStreamError::ChronStatusError(e) => e
+                .status()
+                .is_none_or(|status| status.is_server_error() || status.as_u16() == 429),
+            ChronStreamError::JoinFailure(_)
+            | ChronStreamError::RequestBuildError(_)
+            | ChronStreamError::RequestDeserializeError(_)
+            | ChronStreamError::DeserializeError(_)
+            | ChronStreamError::LocalReadError { .. } => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChronEntities<EntityT> {
     pub items: Vec<ChronEntity<EntityT>>,
     pub next_page: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChronEntity<EntityT> {
     pub kind: String,
     pub entity_id: String,
@@ -56,116 +107,199 @@ pub struct ChronEntity<EntityT> {
     pub data: EntityT,
 }
 
+/// Anything that can serve the Chron API. The ingest only talks to Chron
+/// through this, so it can run against [`LocalChron`] fixture data as
+/// easily as the real thing.
+pub trait ChronProvider: Send + Sync {
+    /// Every version of every entity of this kind whose `valid_from` is at
+    /// or after `start_at`, in ascending order of `valid_from`
+    fn versions(
+        &self,
+        kind: &'static str,
+        start_at: Option<DateTime<Utc>>,
+    ) -> BoxStream<'static, Result<ChronEntity<serde_json::Value>, ChronStreamError>>;
+
+    /// The latest version of every entity of this kind whose `valid_from`
+    /// is at or after `start_at`, in ascending order of `valid_from`
+    fn entities(
+        &self,
+        kind: &'static str,
+        start_at: Option<DateTime<Utc>>,
+    ) -> BoxStream<'static, Result<ChronEntity<serde_json::Value>, ChronStreamError>>;
+
+    /// The latest version of each of these entities
+    fn entities_by_id<'a>(
+        &'a self,
+        kind: &'static str,
+        ids: &'a [&'a str],
+    ) -> BoxFuture<'a, Result<ChronEntities<serde_json::Value>, ChronStreamError>>;
+
+    /// How many duplicate versions have been dropped from this provider's
+    /// streams so far
+    fn duplicates_suppressed(&self) -> u64 {
+        0
+    }
+}
+
+/// The HTTP Chron API, as served by freecashe.ws and cheapcashews
 pub struct Chron {
     client: reqwest::Client,
     page_size: NonZero<usize>,
+    max_retries: usize,
+    use_local_cheap_cashews: bool,
+    response_cache: ResponseCache,
+    anomaly_handler: Option<AnomalyHandler>,
+    mirror_health: MirrorHealth,
+    shared_mirror_ranges: Vec<SharedMirrorRange>,
+    dedup_capacity: usize,
+    duplicates: DuplicateCounter,
+    base_url: Option<String>,
 }
 
+/// How many of the most recent versions [`Chron`] remembers to drop
+/// duplicates by default. Duplicates come from overlapping mirror segments,
+/// so they're never far apart.
+const DEFAULT_DEDUP_CAPACITY: usize = 10_000;
+
 impl Chron {
     pub fn new(page_size: NonZero<usize>) -> Self {
         Self {
             client: reqwest::Client::new(),
             page_size,
+            max_retries: 3,
+            use_local_cheap_cashews: false,
+            response_cache: ResponseCache::new(0),
+            anomaly_handler: None,
+            mirror_health: MirrorHealth::default(),
+            shared_mirror_ranges: Vec::new(),
+            dedup_capacity: DEFAULT_DEDUP_CAPACITY,
+            duplicates: DuplicateCounter::default(),
+            base_url: None,
         }
     }
 
-    pub fn versions(
-        &self,
-        kind: &'static str,
-        start_at: Option<DateTime<Utc>>,
-        max_retries: usize,
-        use_local_cheap_cashews: bool,
-    ) -> impl Stream<Item = Result<ChronEntity<serde_json::Value>, ChronStreamError>> {
-        let free_cashews_url = "https://freecashe.ws/api/chron/v0/versions";
-        let cheap_cashews_url = if use_local_cheap_cashews {
-            "http://10.0.0.71:3001/chron/v0/versions"
-        } else {
-            "https://cheapcashews.beiju.me/chron/v0/versions"
-        };
+    /// Sends every request to `base_url` (e.g. `http://127.0.0.1:8080`)
+    /// instead of the Chron mirrors. This is for tests (see
+    /// [`FixtureServer`]) and for other Chron-compatible APIs, like ones
+    /// serving test universes.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
 
-        self.chained_api_call(
-            kind,
-            start_at,
-            max_retries,
-            free_cashews_url,
-            cheap_cashews_url,
-        )
+    /// Saves every response to disk with `recorder`, so they can be
+    /// replayed in tests by [`FixtureServer`]
+    pub fn with_fixture_recording(mut self, recorder: FixtureRecorder) -> Self {
+        self.response_cache = self.response_cache.with_recorder(Some(recorder));
+        self
     }
 
-    pub fn entities(
-        &self,
-        kind: &'static str,
-        start_at: Option<DateTime<Utc>>,
-        max_retries: usize,
-        use_local_cheap_cashews: bool,
-    ) -> impl Stream<Item = Result<ChronEntity<serde_json::Value>, ChronStreamError>> {
-        let free_cashews_url = "https://freecashe.ws/api/chron/v0/entities";
-        let cheap_cashews_url = if use_local_cheap_cashews {
-            "http://10.0.0.71:3001/chron/v0/entities"
+    /// Declares spans of time that both mirrors can serve. Those spans are
+    /// fetched from whichever mirror is fastest and healthy (see
+    /// [`Chron::probe_mirrors`]), falling back to the other one on errors.
+    /// Everything else is fetched from the mirror that's known to have it.
+    pub fn with_shared_mirror_ranges(mut self, ranges: Vec<SharedMirrorRange>) -> Self {
+        self.shared_mirror_ranges = ranges;
+        self
+    }
+
+    /// Checks that each mirror is up and how long it takes to respond. Only
+    /// affects spans declared with [`Chron::with_shared_mirror_ranges`].
+    pub async fn probe_mirrors(&self) {
+        self.mirror_health
+            .probe(&self.client, |mirror| self.mirror_url(mirror, "entities"))
+            .await
+    }
+
+    /// Checks every page for signs that it was corrupted, like too many
+    /// items or items out of order. A bad page is fetched once more, and
+    /// `handler` is called with what was wrong with it. If the second copy
+    /// is also bad, the fetch fails with [`ChronStreamError::PageAnomaly`].
+    pub fn with_page_verification(mut self, handler: AnomalyHandler) -> Self {
+        self.anomaly_handler = Some(handler);
+        self
+    }
+
+    /// Sets how many responses to remember so that re-fetching them can be
+    /// a conditional request. Pages can be large, so this is off (0) by
+    /// default. It's only worth turning on for callers that re-fetch the
+    /// same pages, like ones polling for new versions.
+    pub fn with_response_cache_size(mut self, size: usize) -> Self {
+        self.response_cache =
+            ResponseCache::new(size).with_recorder(self.response_cache.recorder().cloned());
+        self
+    }
+
+    /// Sets how many recent versions to remember so that the same
+    /// `(entity_id, valid_from)` is never yielded twice. This happens around
+    /// the cutover and cutback dates, where the streams from each mirror
+    /// overlap. 0 turns deduplication off.
+    pub fn with_dedup_capacity(mut self, capacity: usize) -> Self {
+        self.dedup_capacity = capacity;
+        self
+    }
+
+    pub fn with_local_cheap_cashews(mut self, use_local_cheap_cashews: bool) -> Self {
+        self.use_local_cheap_cashews = use_local_cheap_cashews;
+        self
+    }
+
+    fn cheap_cashews_url(&self, endpoint: &str) -> String {
+        if self.use_local_cheap_cashews {
+            format!("http://10.0.0.71:3001/chron/v0/{endpoint}")
         } else {
-            "https://cheapcashews.beiju.me/chron/v0/entities"
-        };
+            format!("https://cheapcashews.beiju.me/chron/v0/{endpoint}")
+        }
+    }
 
-        self.chained_api_call(
-            kind,
-            start_at,
-            max_retries,
-            free_cashews_url,
-            cheap_cashews_url,
-        )
+    fn mirror_url(&self, mirror: ChronMirror, endpoint: &str) -> String {
+        if let Some(base_url) = &self.base_url {
+            return format!("{base_url}/{endpoint}");
+        }
+
+        match mirror {
+            ChronMirror::FreeCashews => format!("https://freecashe.ws/api/chron/v0/{endpoint}"),
+            ChronMirror::CheapCashews => self.cheap_cashews_url(endpoint),
+        }
     }
 
-    pub async fn entities_by_id(
+    async fn fetch_entities_by_id(
         &self,
         kind: &'static str,
         ids: &[&str],
     ) -> Result<ChronEntities<serde_json::Value>, ChronStreamError> {
         debug!("Fetching {} {kind} entities", ids.len());
-        let client = self.client.clone(); // This is internally reference counted
+        let client = &self.client;
 
         let request_builder = client
-            .get("https://freecashe.ws/api/chron/v0/entities")
+            .get(self.mirror_url(ChronMirror::FreeCashews, "entities"))
             .query(&[("kind", kind), ("id", &ids.join(",")), ("order", "asc")]);
 
         let request = request_builder
             .build()
             .map_err(ChronStreamError::RequestBuildError)?;
 
-        let response = client
-            .execute(request)
-            .await
-            .map_err(ChronStreamError::RequestExecuteError)?
-            .error_for_status()
-            .map_err(ChronStreamError::ChronStatusError)?;
-
-        let result = response
-            .text()
+        self.response_cache
+            .fetch(client, request)
             .await
-            .map_err(ChronStreamError::RequestBodyError)?;
-
-        let items: ChronEntities<serde_json::Value> =
-            serde_json::from_str(&result).map_err(ChronStreamError::DeserializeError)?;
-
-        Ok(items)
+            .map(Arc::unwrap_or_clone)
     }
 
     fn chained_api_call(
         &self,
         kind: &'static str,
         start_at: Option<DateTime<Utc>>,
-        max_retries: usize,
-        free_cashews_url: &'static str,
-        cheap_cashews_url: &'static str,
-    ) -> impl Stream<Item = Result<ChronEntity<serde_json::Value>, ChronStreamError>> {
+        endpoint: &str,
+    ) -> impl Stream<Item = Result<ChronEntity<serde_json::Value>, ChronStreamError>> + use<> {
         let segments = vec![
             // Start with freecashews and stick with it until CUTOVER_DATE
-            (free_cashews_url, Some(CUTOVER_DATE)),
+            (ChronMirror::FreeCashews, Some(CUTOVER_DATE)),
             // Then go with cheapcashews until CUTBACK_DATE
-            (cheap_cashews_url, Some(CUTBACK_DATE)),
+            (ChronMirror::CheapCashews, Some(CUTBACK_DATE)),
             // Then back to freecashews until CUTOVER_DATE_2
-            (free_cashews_url, Some(CUTOVER_DATE_2)),
+            (ChronMirror::FreeCashews, Some(CUTOVER_DATE_2)),
             // Finally (for now) back to cheapcashews with no end date
-            (cheap_cashews_url, None),
+            (ChronMirror::CheapCashews, None),
         ];
 
         // The for loop below requires that segment_end not be None except for the last. That's
@@ -185,15 +319,45 @@ impl Chron {
                 .all(|(_, end_time)| end_time.is_none())
         );
 
+        let segments = segments
+            .into_iter()
+            .map(|(mirror, segment_end_str)| {
+                let segment_end = segment_end_str.map(|segment_end_str| {
+                    DateTime::parse_from_rfc3339(segment_end_str)
+                        .expect("Hard-coded cutover or cutback date must parse")
+                        .with_timezone(&Utc)
+                });
+                (mirror, segment_end)
+            })
+            .collect::<Vec<_>>();
+
+        // Segments are split wherever a shared range starts or ends, so each
+        // piece is either entirely shared or not shared at all
+        let mut piece_ends = segments
+            .iter()
+            .filter_map(|(_, end)| *end)
+            .chain(
+                self.shared_mirror_ranges
+                    .iter()
+                    .flat_map(|range| iter::once(range.start).chain(range.end)),
+            )
+            .collect::<Vec<_>>();
+        piece_ends.sort();
+        piece_ends.dedup();
+        let pieces = piece_ends.into_iter().map(Some).chain(iter::once(None));
+
         // I tried to write this with combinators but it bounced off my brain
         let mut streams = Vec::new();
         let mut segment_start = start_at;
-        for (url, segment_end_str) in segments {
-            let segment_end = segment_end_str.map(|segment_end_str| {
-                DateTime::parse_from_rfc3339(segment_end_str)
-                    .expect("Hard-coded cutover or cutback date must parse")
-                    .with_timezone(&Utc)
-            });
+        for segment_end in pieces {
+            // The mirror that's known to have this piece is the one whose
+            // segment it falls in
+            let (primary, _) = segments
+                .iter()
+                .find(|(_, end)| {
+                    end.is_none_or(|end| segment_end.is_some_and(|segment_end| segment_end <= end))
+                })
+                .expect("The last segment has no end, so it contains every piece");
 
             // If this segment starts after it ends, it's gotta be empty
             if segment_start.is_some_and(|segment_start| {
@@ -202,11 +366,25 @@ impl Chron {
                 continue;
             }
 
+            let mut mirrors = vec![*primary];
+            if self
+                .shared_mirror_ranges
+                .iter()
+                .any(|range| range.contains(segment_start, segment_end))
+            {
+                mirrors.push(primary.other());
+                self.mirror_health.rank(&mut mirrors);
+            }
+
             // Otherwise, we should do some API calls about it
             debug!(
-                "Making paginated Chron API call for kind={kind} to {url} from date {segment_start:?} to {segment_end:?}"
+                "Making paginated Chron API call for kind={kind} to {mirrors:?} from date {segment_start:?} to {segment_end:?}"
             );
-            streams.push(self.items(url, kind, max_retries, segment_start, segment_end));
+            let mirror_urls = mirrors
+                .into_iter()
+                .map(|mirror| (mirror, self.mirror_url(mirror, endpoint)))
+                .collect();
+            streams.push(self.items(mirror_urls, kind, segment_start, segment_end));
 
             // Next segment starts when this one ends. Note that this assignment does not happen if
             // the start date is after the end date due to the continue; above. That's important.
@@ -215,18 +393,21 @@ impl Chron {
             segment_start = segment_end;
         }
 
-        stream::iter(streams).flatten()
+        dedup_versions(
+            stream::iter(streams).flatten(),
+            self.dedup_capacity,
+            self.duplicates.clone(),
+        )
     }
 
     fn items(
         &self,
-        url: &'static str,
+        mirrors: Vec<(ChronMirror, String)>,
         kind: &'static str,
-        max_retries: usize,
         start_at: Option<DateTime<Utc>>,
         end_at: Option<DateTime<Utc>>,
-    ) -> impl Stream<Item = Result<ChronEntity<serde_json::Value>, ChronStreamError>> {
-        self.pages(url, kind, max_retries, start_at, end_at)
+    ) -> impl Stream<Item = Result<ChronEntity<serde_json::Value>, ChronStreamError>> + use<> {
+        self.pages(mirrors, kind, start_at, end_at)
             .flat_map(|val| match val {
                 Ok(vec) => {
                     // Turn Vec<T> into a stream of Result<T, E>
@@ -250,36 +431,40 @@ impl Chron {
             })
     }
 
+    /// `mirrors` are the mirrors that can serve this span, most preferred
+    /// first, along with the URL to fetch from each one
     fn pages(
         &self,
-        url: &'static str,
+        mirrors: Vec<(ChronMirror, String)>,
         kind: &'static str,
-        max_retries: usize,
         start_at: Option<DateTime<Utc>>,
         end_at: Option<DateTime<Utc>>,
-    ) -> impl Stream<Item = Result<Vec<ChronEntity<serde_json::Value>>, ChronStreamError>> {
+    ) -> impl Stream<Item = Result<Vec<ChronEntity<serde_json::Value>>, ChronStreamError>> + use<>
+    {
         // For lifetimes
-        let page_size = self.page_size;
-        let client = self.client.clone(); // This is internally reference counted
+        let fetcher = PageFetcher {
+            client: self.client.clone(), // This is internally reference counted
+            response_cache: self.response_cache.clone(), // So is this
+            anomaly_handler: self.anomaly_handler.clone(), // And this
+            mirror_health: self.mirror_health.clone(), // And this
+            kind,
+            max_retries: self.max_retries,
+            page_size: self.page_size,
+            start_at,
+            end_at,
+        };
 
         // Use tokio::spawn to eagerly fetch the next page while the caller is doing other work
-        let start_at_for_first_fetch = start_at;
-        let next_page = tokio::spawn(async move {
-            get_next_page_with_retries(
-                client,
-                url,
-                kind,
-                max_retries,
-                page_size,
-                start_at_for_first_fetch,
-                end_at,
-                None,
-            )
-        });
+        let first_fetcher = fetcher.clone();
+        let next_page =
+            tokio::spawn(
+                async move { get_next_page_with_retries(first_fetcher, mirrors, None, None) },
+            );
 
         // I do not understand why a non-async closure with an async block inside works,
         // but an async closure does not. Nevertheless, that's the situation.
         stream::unfold(Some(next_page), move |next_page| {
+            let fetcher = fetcher.clone();
             async move {
                 let Some(next_page) = next_page else {
                     // next_page being None indicates that we've finished. We couldn't
@@ -292,7 +477,12 @@ impl Chron {
                 // Can't use ? in here because the closure must return an Option.
                 // Note the double nesting is because the join can fail, and the
                 // join can succeed but the underlying task produced an error.
-                let (client, page) = match next_page.await {
+                let FetchedPage {
+                    mirrors,
+                    entities,
+                    num_fetched,
+                    last_item,
+                } = match next_page.await {
                     Ok(fut) => match fut.await {
                         Ok(page) => page,
                         Err(err) => {
@@ -308,19 +498,18 @@ impl Chron {
                     }
                 };
 
+                // Only copies the page if the response cache is holding on
+                // to it too
+                let page = Arc::unwrap_or_clone(entities);
                 if let Some(next_page_token) = page.next_page {
-                    if page.items.len() >= page_size.into() {
+                    if num_fetched >= fetcher.page_size.into() {
                         // Then there are more pages
                         let next_page_fut = tokio::spawn(async move {
                             get_next_page_with_retries(
-                                client,
-                                url,
-                                kind,
-                                max_retries,
-                                page_size,
-                                start_at_for_first_fetch,
-                                end_at,
+                                fetcher,
+                                mirrors,
                                 Some(next_page_token),
+                                last_item,
                             )
                         });
 
@@ -342,31 +531,182 @@ impl Chron {
     }
 }
 
-async fn get_next_page_with_retries(
+impl ChronProvider for Chron {
+    fn versions(
+        &self,
+        kind: &'static str,
+        start_at: Option<DateTime<Utc>>,
+    ) -> BoxStream<'static, Result<ChronEntity<serde_json::Value>, ChronStreamError>> {
+        self.chained_api_call(kind, start_at, "versions").boxed()
+    }
+
+    fn entities(
+        &self,
+        kind: &'static str,
+        start_at: Option<DateTime<Utc>>,
+    ) -> BoxStream<'static, Result<ChronEntity<serde_json::Value>, ChronStreamError>> {
+        self.chained_api_call(kind, start_at, "entities").boxed()
+    }
+
+    fn entities_by_id<'a>(
+        &'a self,
+        kind: &'static str,
+        ids: &'a [&'a str],
+    ) -> BoxFuture<'a, Result<ChronEntities<serde_json::Value>, ChronStreamError>> {
+        self.fetch_entities_by_id(kind, ids).boxed()
+    }
+
+    fn duplicates_suppressed(&self) -> u64 {
+        self.duplicates.suppressed()
+    }
+}
+
+/// What every page of one stream is fetched with
+#[derive(Clone)]
+struct PageFetcher {
     client: reqwest::Client,
-    url: &str,
-    kind: &str,
+    response_cache: ResponseCache,
+    anomaly_handler: Option<AnomalyHandler>,
+    mirror_health: MirrorHealth,
+    kind: &'static str,
     max_retries: usize,
     page_size: NonZero<usize>,
     start_at: Option<DateTime<Utc>>,
     end_at: Option<DateTime<Utc>>,
-    page: Option<String>,
-) -> Result<(reqwest::Client, ChronEntities<serde_json::Value>), ChronStreamError> {
+}
+
+/// A page fetched by [`get_next_page_with_retries`]
+struct FetchedPage {
+    /// The mirrors, reordered so the one that served this page is first
+    mirrors: Vec<(ChronMirror, String)>,
+    entities: Arc<ChronEntities<serde_json::Value>>,
+    /// How many items the mirror returned, including any that were dropped
+    /// because they'd already been yielded. Only a full page can have more
+    /// pages after it.
+    num_fetched: usize,
+    /// The (valid_from, entity_id) of the last item yielded so far, which
+    /// is where a switch to another mirror resumes from
+    last_item: Option<(DateTime<Utc>, String)>,
+}
+
+/// Fetches a page from the first of `mirrors`, moving on to the next one
+/// whenever a request fails. The next page is fetched from whichever mirror
+/// served this one. Page tokens are only meaningful to the mirror that
+/// issued them, so after a switch the new mirror is asked for everything
+/// from the valid_from of `last_item` (the last item already yielded)
+/// onward, and whatever is at or before `last_item` is dropped.
+async fn get_next_page_with_retries(
+    fetcher: PageFetcher,
+    mut mirrors: Vec<(ChronMirror, String)>,
+    mut page: Option<String>,
+    last_item: Option<(DateTime<Utc>, String)>,
+) -> Result<FetchedPage, ChronStreamError> {
+    let PageFetcher {
+        kind,
+        max_retries,
+        page_size,
+        end_at,
+        ..
+    } = fetcher;
     let mut retries = 0;
+    let mut mirror_index = 0;
+    let mut start_at = fetcher.start_at;
+    // Set once this page is being fetched from the last yielded item
+    // rather than with a page token
+    let mut resumed = false;
+    // The anomaly found in the first copy of this page, if any
+    let mut first_anomaly: Option<String> = None;
     loop {
-        match get_next_page(
-            &client,
-            url,
-            kind,
-            page_size,
-            start_at,
-            end_at,
-            page.as_deref(),
-        )
-        .await
-        {
-            Ok(next_page) => return Ok((client, next_page)),
+        let (mirror, url) = &mirrors[mirror_index];
+        let result = fetcher
+            .get_next_page(url, start_at, page.as_deref(), first_anomaly.is_some())
+            .await;
+
+        if let Some(handler) = &fetcher.anomaly_handler {
+            let anomaly = match &result {
+                Ok(entities) => check_page(
+                    entities,
+                    &PageExpectation {
+                        kind,
+                        page_size,
+                        start_at,
+                        end_at,
+                        previous_valid_from: last_item.as_ref().map(|(valid_from, _)| *valid_from),
+                    },
+                ),
+                // A truncated response usually shows up as invalid JSON
+                Err(ChronStreamError::DeserializeError(err)) => {
+                    Some(format!("response didn't deserialize: {err}"))
+                }
+                Err(_) => None,
+            };
+
+            let report = |description: String, resolved_by_refetch: bool| {
+                handler(ChronAnomaly {
+                    kind: kind.to_string(),
+                    url: url.clone(),
+                    page: page.clone(),
+                    description,
+                    resolved_by_refetch,
+                })
+            };
+            match (anomaly, first_anomaly.take()) {
+                (Some(anomaly), None) => {
+                    warn!("Chron returned a bad {kind} page, fetching it again: {anomaly}");
+                    first_anomaly = Some(anomaly);
+                    continue;
+                }
+                (Some(anomaly), Some(first)) => {
+                    report(first, false);
+                    return Err(ChronStreamError::PageAnomaly(anomaly));
+                }
+                (None, Some(first)) if result.is_ok() => report(first, true),
+                // The refetch failed for some other reason, so it's still
+                // unknown whether the anomaly goes away. The retry is
+                // still a refetch.
+                (None, Some(first)) => first_anomaly = Some(first),
+                (None, None) => {}
+            }
+        }
+
+        match result {
+            Ok(mut entities) => {
+                let num_fetched = entities.items.len();
+                if let Some((last_valid_from, last_entity_id)) =
+                    last_item.as_ref().filter(|_| resumed)
+                {
+                    Arc::make_mut(&mut entities).items.retain(|item| {
+                        (item.valid_from, &item.entity_id) > (*last_valid_from, last_entity_id)
+                    });
+                }
+                let last_item = entities
+                    .items
+                    .last()
+                    .map(|item| (item.valid_from, item.entity_id.clone()))
+                    .or(last_item);
+                mirrors.rotate_left(mirror_index);
+                return Ok(FetchedPage {
+                    mirrors,
+                    entities,
+                    num_fetched,
+                    last_item,
+                });
+            }
             Err(e) => {
+                if mirrors.len() > 1 {
+                    fetcher.mirror_health.mark_failed(*mirror);
+                    mirror_index = (mirror_index + 1) % mirrors.len();
+                    warn!(
+                        "Chron mirror {mirror:?} failed, switching to {:?}",
+                        mirrors[mirror_index].0,
+                    );
+                    // The other mirror can't use this mirror's page token
+                    if let Some((last_valid_from, _)) = &last_item {
+                        start_at = Some(*last_valid_from);
+                        page = None;
+                        resumed = true;
+                    }
+                }
                 if retries < max_retries {
                     warn!(
                         "Chron encountered an error, will try again up to {} more times: {:?}",
@@ -382,56 +722,45 @@ async fn get_next_page_with_retries(
     }
 }
 
-async fn get_next_page(
-    client: &reqwest::Client,
-    url: &str,
-    kind: &str,
-    page_size: NonZero<usize>,
-    start_at: Option<DateTime<Utc>>,
-    end_at: Option<DateTime<Utc>>,
-    page: Option<&str>,
-) -> Result<ChronEntities<serde_json::Value>, ChronStreamError> {
-    debug!("Fetching {kind} page {page:?} starting at {start_at:?}");
-
-    let page_size_string = page_size.to_string();
+impl PageFetcher {
+    async fn get_next_page(
+        &self,
+        url: &str,
+        start_at: Option<DateTime<Utc>>,
+        page: Option<&str>,
+        bypass_cache: bool,
+    ) -> Result<Arc<ChronEntities<serde_json::Value>>, ChronStreamError> {
+        let kind = self.kind;
+        debug!("Fetching {kind} page {page:?} starting at {start_at:?}");
+
+        let page_size_string = self.page_size.to_string();
+
+        let mut request_builder = self.client.get(url).query(&[
+            ("kind", kind),
+            ("count", &page_size_string),
+            ("order", "asc"),
+        ]);
+
+        if let Some(start_at) = start_at {
+            request_builder = request_builder.query(&[("after", &start_at.to_rfc3339())]);
+        }
 
-    let mut request_builder = client.get(url).query(&[
-        ("kind", kind),
-        ("count", &page_size_string),
-        ("order", "asc"),
-    ]);
+        if let Some(end_at) = self.end_at {
+            request_builder = request_builder.query(&[("before", &end_at.to_rfc3339())]);
+        }
 
-    if let Some(start_at) = start_at {
-        request_builder = request_builder.query(&[("after", &start_at.to_rfc3339())]);
-    }
+        if let Some(page) = page {
+            request_builder = request_builder.query(&[("page", page)]);
+        }
 
-    if let Some(end_at) = end_at {
-        request_builder = request_builder.query(&[("before", &end_at.to_rfc3339())]);
-    }
+        let request = request_builder
+            .build()
+            .map_err(ChronStreamError::RequestBuildError)?;
 
-    if let Some(page) = page {
-        request_builder = request_builder.query(&[("page", page)]);
+        debug!("Requesting {}", request.url());
+        if bypass_cache {
+            self.response_cache.forget(request.url().as_str());
+        }
+        self.response_cache.fetch(&self.client, request).await
     }
-
-    let request = request_builder
-        .build()
-        .map_err(ChronStreamError::RequestBuildError)?;
-
-    debug!("Requesting {}", request.url());
-    let response = client
-        .execute(request)
-        .await
-        .map_err(ChronStreamError::RequestExecuteError)?
-        .error_for_status()
-        .map_err(ChronStreamError::ChronStatusError)?;
-
-    let result = response
-        .text()
-        .await
-        .map_err(ChronStreamError::RequestBodyError)?;
-
-    let items: ChronEntities<serde_json::Value> =
-        serde_json::from_str(&result).map_err(ChronStreamError::DeserializeError)?;
-
-    Ok(items)
 }
@@ -0,0 +1,178 @@
+use crate::{ChronEntities, ChronEntity, ChronProvider, ChronStreamError};
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use futures::stream::BoxStream;
+use futures::{FutureExt, StreamExt, stream};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+/// Serves Chron data from a directory instead of over HTTP, for tests and
+/// offline development. The directory holds one file per endpoint and kind,
+/// `<dir>/versions/<kind>.ndjson` and `<dir>/entities/<kind>.ndjson`, each
+/// with one serialized [`ChronEntity`] per line. A missing file means there
+/// is no data of that kind. Lines don't need to be in any order.
+pub struct LocalChron {
+    dir: PathBuf,
+}
+
+impl LocalChron {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn read(
+        &self,
+        endpoint: &str,
+        kind: &str,
+    ) -> Result<Vec<ChronEntity<serde_json::Value>>, ChronStreamError> {
+        let path = self.dir.join(endpoint).join(format!("{kind}.ndjson"));
+        let mut items = read_ndjson(&path)?;
+        items.sort_by(|a, b| (a.valid_from, &a.entity_id).cmp(&(b.valid_from, &b.entity_id)));
+        Ok(items)
+    }
+
+    fn read_after(
+        &self,
+        endpoint: &str,
+        kind: &str,
+        start_at: Option<DateTime<Utc>>,
+    ) -> BoxStream<'static, Result<ChronEntity<serde_json::Value>, ChronStreamError>> {
+        match self.read(endpoint, kind) {
+            Ok(items) => {
+                // Chron's `after` parameter is inclusive, so this is too
+                let items = items
+                    .into_iter()
+                    .filter(move |item| start_at.is_none_or(|start| item.valid_from >= start))
+                    .map(Ok);
+                stream::iter(items).boxed()
+            }
+            Err(err) => stream::once(async { Err(err) }).boxed(),
+        }
+    }
+}
+
+fn read_ndjson(path: &Path) -> Result<Vec<ChronEntity<serde_json::Value>>, ChronStreamError> {
+    let read_error = |source| ChronStreamError::LocalReadError {
+        path: path.display().to_string(),
+        source,
+    };
+
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(read_error(err)),
+    };
+
+    let mut items = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(read_error)?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        items.push(serde_json::from_str(&line).map_err(ChronStreamError::DeserializeError)?);
+    }
+
+    Ok(items)
+}
+
+impl ChronProvider for LocalChron {
+    fn versions(
+        &self,
+        kind: &'static str,
+        start_at: Option<DateTime<Utc>>,
+    ) -> BoxStream<'static, Result<ChronEntity<serde_json::Value>, ChronStreamError>> {
+        self.read_after("versions", kind, start_at)
+    }
+
+    fn entities(
+        &self,
+        kind: &'static str,
+        start_at: Option<DateTime<Utc>>,
+    ) -> BoxStream<'static, Result<ChronEntity<serde_json::Value>, ChronStreamError>> {
+        self.read_after("entities", kind, start_at)
+    }
+
+    fn entities_by_id<'a>(
+        &'a self,
+        kind: &'static str,
+        ids: &'a [&'a str],
+    ) -> BoxFuture<'a, Result<ChronEntities<serde_json::Value>, ChronStreamError>> {
+        async move {
+            let items = self
+                .read("entities", kind)?
+                .into_iter()
+                .filter(|item| ids.contains(&item.entity_id.as_str()))
+                .collect();
+
+            Ok(ChronEntities {
+                items,
+                next_page: None,
+            })
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::TryStreamExt;
+    use futures::executor::block_on;
+
+    fn fixture_dir(name: &str, versions: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("local-chron-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("versions")).unwrap();
+        let lines = versions
+            .iter()
+            .map(|(entity_id, valid_from)| {
+                serde_json::json!({
+                    "kind": "team",
+                    "entity_id": entity_id,
+                    "valid_from": valid_from,
+                    "valid_to": null,
+                    "data": {},
+                })
+                .to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        std::fs::write(dir.join("versions").join("team.ndjson"), lines).unwrap();
+        dir
+    }
+
+    #[test]
+    fn versions_are_sorted_and_start_inclusively() {
+        let dir = fixture_dir(
+            "sorted",
+            &[
+                ("b", "2025-01-03T00:00:00Z"),
+                ("a", "2025-01-01T00:00:00Z"),
+                ("c", "2025-01-02T00:00:00Z"),
+            ],
+        );
+        let chron = LocalChron::new(&dir);
+        let start_at = "2025-01-02T00:00:00Z".parse().unwrap();
+
+        let ids = block_on(
+            chron
+                .versions("team", Some(start_at))
+                .map_ok(|v| v.entity_id)
+                .try_collect::<Vec<_>>(),
+        )
+        .unwrap();
+
+        assert_eq!(ids, vec!["c", "b"]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn missing_kind_is_empty() {
+        let dir = fixture_dir("missing", &[]);
+        let chron = LocalChron::new(&dir);
+
+        let versions = block_on(chron.versions("player", None).try_collect::<Vec<_>>()).unwrap();
+
+        assert!(versions.is_empty());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,191 @@
+use chrono::{DateTime, Utc};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How long a health check waits for a mirror before deciding it's down
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The servers that serve the Chron API
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ChronMirror {
+    FreeCashews,
+    CheapCashews,
+}
+
+impl ChronMirror {
+    const ALL: [ChronMirror; 2] = [ChronMirror::FreeCashews, ChronMirror::CheapCashews];
+
+    fn index(self) -> usize {
+        match self {
+            ChronMirror::FreeCashews => 0,
+            ChronMirror::CheapCashews => 1,
+        }
+    }
+
+    pub(crate) fn other(self) -> ChronMirror {
+        match self {
+            ChronMirror::FreeCashews => ChronMirror::CheapCashews,
+            ChronMirror::CheapCashews => ChronMirror::FreeCashews,
+        }
+    }
+}
+
+/// A span of time that every mirror has complete data for, so any of them
+/// can serve it. `end` of None means the span has no end.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SharedMirrorRange {
+    pub start: DateTime<Utc>,
+    pub end: Option<DateTime<Utc>>,
+}
+
+impl SharedMirrorRange {
+    /// Whether the span from `start` to `end` is entirely inside this range
+    pub(crate) fn contains(
+        &self,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    ) -> bool {
+        let starts_inside = start.is_some_and(|start| start >= self.start);
+        let ends_inside = match (end, self.end) {
+            (_, None) => true,
+            (Some(end), Some(range_end)) => end <= range_end,
+            (None, Some(_)) => false,
+        };
+        starts_inside && ends_inside
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+struct MirrorStatus {
+    /// How long the last successful health check took. None if the mirror
+    /// hasn't been checked, or the last check failed.
+    latency: Option<Duration>,
+    /// Set when a request to this mirror fails, and cleared by the next
+    /// successful health check
+    failed: bool,
+}
+
+/// What's known about how healthy and how fast each mirror is. Clones share
+/// the same state.
+#[derive(Clone, Default)]
+pub(crate) struct MirrorHealth {
+    statuses: Arc<Mutex<[MirrorStatus; 2]>>,
+}
+
+impl MirrorHealth {
+    fn status(&self, mirror: ChronMirror) -> MirrorStatus {
+        self.statuses
+            .lock()
+            .expect("Chron mirror health lock was poisoned")[mirror.index()]
+    }
+
+    fn update(&self, mirror: ChronMirror, f: impl FnOnce(&mut MirrorStatus)) {
+        let mut statuses = self
+            .statuses
+            .lock()
+            .expect("Chron mirror health lock was poisoned");
+        f(&mut statuses[mirror.index()]);
+    }
+
+    /// Records that a request to `mirror` failed, so it's tried last until
+    /// it passes another health check
+    pub fn mark_failed(&self, mirror: ChronMirror) {
+        self.update(mirror, |status| status.failed = true);
+    }
+
+    /// Sorts `mirrors` with the fastest healthy mirror first. Mirrors that
+    /// haven't been checked come after healthy ones, and mirrors that failed
+    /// come last. The sort is stable, so mirrors nobody knows anything about
+    /// stay in the order they were given.
+    pub fn rank(&self, mirrors: &mut [ChronMirror]) {
+        mirrors.sort_by_key(|mirror| {
+            let status = self.status(*mirror);
+            (status.failed, status.latency.is_none(), status.latency)
+        });
+    }
+
+    /// Times a minimal request to each mirror. Mirrors that don't respond
+    /// successfully are marked as failed.
+    pub async fn probe(&self, client: &reqwest::Client, url_for: impl Fn(ChronMirror) -> String) {
+        for mirror in ChronMirror::ALL {
+            let url = url_for(mirror);
+            let started = Instant::now();
+            let result = client
+                .get(&url)
+                .query(&[("kind", "game"), ("count", "1")])
+                .timeout(PROBE_TIMEOUT)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            let elapsed = started.elapsed();
+
+            match result {
+                Ok(_) => {
+                    debug!("Chron mirror {mirror:?} responded in {elapsed:?}");
+                    self.update(mirror, |status| {
+                        status.latency = Some(elapsed);
+                        status.failed = false;
+                    });
+                }
+                Err(err) => {
+                    warn!("Chron mirror {mirror:?} failed its health check: {err}");
+                    self.update(mirror, |status| {
+                        status.latency = None;
+                        status.failed = true;
+                    });
+                }
+            }
+        }
+
+        let mut ranked = ChronMirror::ALL;
+        self.rank(&mut ranked);
+        info!("Chron mirrors in order of preference: {ranked:?}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fastest_healthy_mirror_is_preferred() {
+        let health = MirrorHealth::default();
+        health.update(ChronMirror::FreeCashews, |status| {
+            status.latency = Some(Duration::from_millis(300))
+        });
+        health.update(ChronMirror::CheapCashews, |status| {
+            status.latency = Some(Duration::from_millis(40))
+        });
+
+        let mut mirrors = ChronMirror::ALL;
+        health.rank(&mut mirrors);
+        assert_eq!(
+            mirrors,
+            [ChronMirror::CheapCashews, ChronMirror::FreeCashews]
+        );
+
+        health.mark_failed(ChronMirror::CheapCashews);
+        health.rank(&mut mirrors);
+        assert_eq!(
+            mirrors,
+            [ChronMirror::FreeCashews, ChronMirror::CheapCashews]
+        );
+    }
+
+    #[test]
+    fn shared_range_containment() {
+        let range = SharedMirrorRange {
+            start: "2025-10-27T00:00:00Z".parse().unwrap(),
+            end: Some("2025-12-01T00:00:00Z".parse().unwrap()),
+        };
+        let inside_start = Some("2025-11-01T00:00:00Z".parse().unwrap());
+        let inside_end = Some("2025-11-15T00:00:00Z".parse().unwrap());
+
+        assert!(range.contains(inside_start, inside_end));
+        assert!(!range.contains(None, inside_end));
+        assert!(!range.contains(inside_start, None));
+        assert!(!range.contains(inside_start, Some("2025-12-02T00:00:00Z".parse().unwrap())));
+    }
+}
@@ -0,0 +1,188 @@
+use crate::ChronEntities;
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+use std::num::NonZero;
+use std::sync::Arc;
+
+/// A page from Chron that didn't look right, e.g. because the response was
+/// cut off or its items were out of order
+#[derive(Debug, Clone)]
+pub struct ChronAnomaly {
+    pub kind: String,
+    pub url: String,
+    /// The page token, or None for the first page
+    pub page: Option<String>,
+    pub description: String,
+    /// Whether fetching the page a second time produced a page without
+    /// anomalies. If it didn't, the fetch fails.
+    pub resolved_by_refetch: bool,
+}
+
+/// Called with every anomaly found while verifying pages. It's called from
+/// async code, so it mustn't block.
+pub type AnomalyHandler = Arc<dyn Fn(ChronAnomaly) + Send + Sync>;
+
+/// What a page is expected to look like, based on the request that
+/// fetched it
+pub(crate) struct PageExpectation<'a> {
+    pub kind: &'a str,
+    pub page_size: NonZero<usize>,
+    pub start_at: Option<DateTime<Utc>>,
+    pub end_at: Option<DateTime<Utc>>,
+    /// The `valid_from` of the last item on the previous page
+    pub previous_valid_from: Option<DateTime<Utc>>,
+}
+
+/// Returns a description of the first problem with `page`, or None if it
+/// looks fine
+pub(crate) fn check_page(
+    page: &ChronEntities<serde_json::Value>,
+    expected: &PageExpectation,
+) -> Option<String> {
+    if page.items.len() > expected.page_size.get() {
+        return Some(format!(
+            "page has {} items, but only {} were requested",
+            page.items.len(),
+            expected.page_size,
+        ));
+    }
+
+    // Chron only stops short of a full page on the last page, so a short
+    // page that still links to another one was most likely cut off
+    if page.items.len() < expected.page_size.get() && page.next_page.is_some() {
+        return Some(format!(
+            "page has {} items, fewer than the {} requested, but has a next page",
+            page.items.len(),
+            expected.page_size,
+        ));
+    }
+
+    let mut previous_valid_from = expected.previous_valid_from;
+    let mut seen = HashSet::new();
+    for (index, item) in page.items.iter().enumerate() {
+        if item.kind != expected.kind {
+            return Some(format!(
+                "item {index} ({}) has kind {:?}, but {:?} was requested",
+                item.entity_id, item.kind, expected.kind,
+            ));
+        }
+
+        if let Some(start_at) = expected
+            .start_at
+            .filter(|start_at| item.valid_from < *start_at)
+        {
+            return Some(format!(
+                "item {index} ({}) is valid from {}, which is before the requested start {start_at}",
+                item.entity_id, item.valid_from,
+            ));
+        }
+
+        if let Some(end_at) = expected.end_at.filter(|end_at| item.valid_from > *end_at) {
+            return Some(format!(
+                "item {index} ({}) is valid from {}, which is after the requested end {end_at}",
+                item.entity_id, item.valid_from,
+            ));
+        }
+
+        if let Some(previous) = previous_valid_from.filter(|previous| item.valid_from < *previous) {
+            return Some(format!(
+                "item {index} ({}) is valid from {}, which is before the previous item ({previous})",
+                item.entity_id, item.valid_from,
+            ));
+        }
+        previous_valid_from = Some(item.valid_from);
+
+        if !seen.insert((&item.entity_id, item.valid_from)) {
+            return Some(format!(
+                "item {index} ({}) valid from {} appears more than once",
+                item.entity_id, item.valid_from,
+            ));
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ChronEntity;
+
+    fn item(entity_id: &str, valid_from: &str) -> ChronEntity<serde_json::Value> {
+        ChronEntity {
+            kind: "game".to_string(),
+            entity_id: entity_id.to_string(),
+            valid_from: valid_from.parse().unwrap(),
+            valid_to: None,
+            data: serde_json::Value::Null,
+        }
+    }
+
+    fn expectation() -> PageExpectation<'static> {
+        PageExpectation {
+            kind: "game",
+            page_size: NonZero::new(3).unwrap(),
+            start_at: None,
+            end_at: None,
+            previous_valid_from: Some("2025-06-01T00:00:00Z".parse().unwrap()),
+        }
+    }
+
+    #[test]
+    fn ordered_page_passes() {
+        let page = ChronEntities {
+            items: vec![
+                item("a", "2025-06-01T00:00:00Z"),
+                item("b", "2025-06-01T00:00:00Z"),
+                item("c", "2025-06-02T00:00:00Z"),
+            ],
+            next_page: Some("next".to_string()),
+        };
+
+        assert_eq!(check_page(&page, &expectation()), None);
+    }
+
+    #[test]
+    fn anomalies_are_detected() {
+        let too_long = ChronEntities {
+            items: vec![
+                item("a", "2025-06-01T00:00:00Z"),
+                item("b", "2025-06-01T00:00:00Z"),
+                item("c", "2025-06-02T00:00:00Z"),
+                item("d", "2025-06-02T00:00:00Z"),
+            ],
+            next_page: None,
+        };
+        assert!(check_page(&too_long, &expectation()).is_some());
+
+        let before_previous_page = ChronEntities {
+            items: vec![item("a", "2025-05-31T00:00:00Z")],
+            next_page: None,
+        };
+        assert!(check_page(&before_previous_page, &expectation()).is_some());
+
+        let duplicated = ChronEntities {
+            items: vec![
+                item("a", "2025-06-01T00:00:00Z"),
+                item("a", "2025-06-01T00:00:00Z"),
+            ],
+            next_page: None,
+        };
+        assert!(check_page(&duplicated, &expectation()).is_some());
+
+        let short_with_next_page = ChronEntities {
+            items: vec![
+                item("a", "2025-06-01T00:00:00Z"),
+                item("b", "2025-06-02T00:00:00Z"),
+            ],
+            next_page: Some("next".to_string()),
+        };
+        assert!(check_page(&short_with_next_page, &expectation()).is_some());
+
+        let short_last_page = ChronEntities {
+            next_page: None,
+            ..short_with_next_page
+        };
+        assert_eq!(check_page(&short_last_page, &expectation()), None);
+    }
+}
@@ -0,0 +1,3 @@
+drop index data.games_started_at_idx;
+
+alter table data.games drop column started_at;
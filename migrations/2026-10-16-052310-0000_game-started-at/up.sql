@@ -0,0 +1,15 @@
+alter table data.games add column started_at timestamp with time zone;
+
+-- MMOLB game ids are Mongo ObjectIds, whose first 8 hex digits are the unix
+-- timestamp of when the game was created. In the unlikely event that a game
+-- id doesn't look like that, fall back to the first version we saw of it.
+update data.games
+set started_at = case
+    when mmolb_game_id ~ '^[0-9a-f]{8}'
+        then to_timestamp(('x' || substr(mmolb_game_id, 1, 8))::bit(32)::bigint)
+    else from_version at time zone 'UTC'
+end;
+
+alter table data.games alter column started_at set not null;
+
+create index games_started_at_idx on data.games (started_at);
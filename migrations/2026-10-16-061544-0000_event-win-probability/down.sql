@@ -0,0 +1 @@
+drop table data.event_win_probability;
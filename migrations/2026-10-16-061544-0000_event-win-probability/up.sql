@@ -0,0 +1,14 @@
+-- Win probability and leverage index for each event, computed during game
+-- ingest. See the docs for data.event_win_probability for the model.
+create table data.event_win_probability (
+    id bigserial primary key not null,
+    event_id bigint references data.events on delete cascade not null,
+    home_win_probability_before double precision not null,
+    home_win_probability_after double precision not null,
+    batting_team_win_probability_added double precision not null,
+    leverage_index double precision not null,
+    unique (event_id)
+);
+
+-- Every event needs a row in the new table, so reingest all games
+delete from data.games;
@@ -0,0 +1 @@
+drop table info.feed_event_failures;
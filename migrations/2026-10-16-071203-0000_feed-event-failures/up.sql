@@ -0,0 +1,16 @@
+-- Dead-letter queue for feed events that couldn't be deserialized or whose
+-- text couldn't be parsed. Ingest re-drives these on every run so they get
+-- processed once mmolb_parsing learns how to handle them.
+create table info.feed_event_failures (
+    id bigserial primary key not null,
+    kind text not null,
+    entity_id text not null,
+    feed_event_index int not null,
+    valid_from timestamp with time zone not null,
+    raw_data jsonb not null,
+    error_message text not null,
+    retry_count int not null default 0,
+    first_failed_at timestamp with time zone not null default now(),
+    last_attempt_at timestamp with time zone not null default now(),
+    unique (kind, entity_id, feed_event_index, valid_from)
+);
@@ -0,0 +1 @@
+drop materialized view data.catcher_called_strikes;
@@ -0,0 +1,72 @@
+-- Called strikes vs. balls on taken pitches, per catcher per season. Events
+-- don't record who was catching, so the catcher is resolved from the
+-- defending team's roster at the time the game started.
+create materialized view data.catcher_called_strikes as
+with pitch_counts as (
+    -- Aggregate to (game, half, pitcher) first so the roster join below
+    -- doesn't have to touch every event
+    select
+        e.game_id,
+        e.top_of_inning,
+        e.pitcher_name,
+        count(*) filter (where not et.is_ball) as called_strikes,
+        count(*) filter (where et.is_ball) as balls
+    from data.events e
+    join taxa.event_type et on et.id=e.event_type
+    where not et.batter_swung
+        and not et.is_in_play
+        and (et.is_ball or et.is_strike)
+        and et.name <> 'HitByPitch'
+    group by e.game_id, e.top_of_inning, e.pitcher_name
+),
+game_pitch_counts as (
+    select
+        pc.*,
+        g.season,
+        g.started_at,
+        case when pc.top_of_inning then g.home_team_mmolb_id else g.away_team_mmolb_id end as defending_team_mmolb_id
+    from pitch_counts pc
+    join data.games g on g.id=pc.game_id
+),
+pitcher_rates as (
+    -- Pitchers are identified by name within a team and season because
+    -- that's all events record
+    select
+        season,
+        defending_team_mmolb_id,
+        pitcher_name,
+        sum(called_strikes)::double precision / sum(called_strikes + balls) as called_strike_rate
+    from game_pitch_counts
+    group by season, defending_team_mmolb_id, pitcher_name
+),
+catcher_pitch_counts as (
+    select
+        gpc.*,
+        tpv.mmolb_player_id as catcher_mmolb_id,
+        tpv.first_name || ' ' || tpv.last_name as catcher_name
+    from game_pitch_counts gpc
+    join data.team_player_versions tpv
+        on tpv.mmolb_team_id=gpc.defending_team_mmolb_id
+        and tpv.slot=(select id from taxa.slot where name='Catcher')
+        and tpv.valid_from <= gpc.started_at at time zone 'UTC'
+        and (tpv.valid_until is null or tpv.valid_until > gpc.started_at at time zone 'UTC')
+    where tpv.mmolb_player_id is not null
+)
+select
+    cpc.season,
+    cpc.catcher_mmolb_id,
+    (array_agg(cpc.catcher_name order by cpc.started_at desc))[1] as catcher_name,
+    sum(cpc.called_strikes + cpc.balls)::bigint as taken_pitches,
+    sum(cpc.called_strikes)::bigint as called_strikes,
+    sum(cpc.balls)::bigint as balls,
+    sum(cpc.called_strikes)::double precision / sum(cpc.called_strikes + cpc.balls) as called_strike_rate,
+    sum(cpc.called_strikes - (cpc.called_strikes + cpc.balls) * pr.called_strike_rate) as called_strikes_above_expected
+from catcher_pitch_counts cpc
+join pitcher_rates pr
+    on pr.season=cpc.season
+    and pr.defending_team_mmolb_id=cpc.defending_team_mmolb_id
+    and pr.pitcher_name=cpc.pitcher_name
+group by cpc.season, cpc.catcher_mmolb_id;
+
+create unique index catcher_called_strikes_season_catcher_idx
+    on data.catcher_called_strikes (season, catcher_mmolb_id);
@@ -0,0 +1 @@
+drop table data.event_defense;
@@ -0,0 +1,19 @@
+-- The defending team's alignment for each event, tracked by the sim from
+-- lineups and substitutions during game ingest.
+create table data.event_defense (
+    id bigserial primary key not null,
+    event_id bigint references data.events on delete cascade not null,
+    catcher_name text,
+    first_base_name text,
+    second_base_name text,
+    third_base_name text,
+    shortstop_name text,
+    left_field_name text,
+    center_field_name text,
+    right_field_name text,
+    unique (event_id)
+);
+
+-- Defensive alignment is only computed during ingest, so existing games
+-- need to be reingested to populate it
+delete from data.games;
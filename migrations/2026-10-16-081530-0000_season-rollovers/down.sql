@@ -0,0 +1,3 @@
+drop table data.season_awards;
+drop table data.season_final_standings;
+drop table info.season_rollovers;
@@ -0,0 +1,32 @@
+-- Marks the one-time jobs that have already been run for a finished season,
+-- so the ingest doesn't repeat them
+create table info.season_rollovers (
+    season int not null,
+    task text not null,
+    completed_at timestamp with time zone not null default now(),
+    primary key (season, task)
+);
+
+create table data.season_final_standings (
+    id bigserial primary key not null,
+    season int not null,
+    team_mmolb_id text not null,
+    team_name text not null,
+    team_emoji text not null,
+    games_played int not null,
+    wins int not null,
+    losses int not null,
+    runs_scored int not null,
+    runs_allowed int not null,
+    unique (season, team_mmolb_id)
+);
+
+create table data.season_awards (
+    id bigserial primary key not null,
+    season int not null,
+    award text not null,
+    player_name text not null,
+    team_mmolb_id text not null,
+    value int not null,
+    unique (season, award, player_name, team_mmolb_id)
+);
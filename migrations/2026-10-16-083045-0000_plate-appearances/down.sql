@@ -0,0 +1,2 @@
+drop materialized view data.pitcher_tto_splits;
+drop table data.plate_appearances;
@@ -0,0 +1,36 @@
+-- One row per plate appearance, attached to the event that ended it. The
+-- pitcher's workload is tracked by the sim during game ingest.
+create table data.plate_appearances (
+    id bigserial primary key not null,
+    event_id bigint references data.events on delete cascade not null,
+    pitcher_batters_faced_before int not null,
+    times_through_order int not null,
+    pitcher_pitches_before int not null,
+    pitches int not null,
+    unique (event_id)
+);
+
+-- Outcomes per pitcher per season, split by how many times the pitcher had
+-- been through the batting order
+create materialized view data.pitcher_tto_splits as
+select
+    g.season,
+    case when e.top_of_inning then g.home_team_mmolb_id else g.away_team_mmolb_id end as team_mmolb_id,
+    e.pitcher_name,
+    pa.times_through_order,
+    count(*) as plate_appearances,
+    count(*) filter (where et.is_hit) as hits,
+    count(*) filter (where et.name='HomeRun') as home_runs,
+    count(*) filter (where et.name='Walk') as walks,
+    count(*) filter (where et.is_strikeout) as strikeouts,
+    sum(pa.pitches)::bigint as pitches
+from data.plate_appearances pa
+join data.events e on e.id=pa.event_id
+join data.games g on g.id=e.game_id
+join taxa.event_type et on et.id=e.event_type
+group by g.season, team_mmolb_id, e.pitcher_name, pa.times_through_order;
+
+create unique index pitcher_tto_splits_idx
+    on data.pitcher_tto_splits (season, team_mmolb_id, pitcher_name, times_through_order);
+
+-- Existing games are populated with `mmoldb-cli rebuild-table plate_appearances`
@@ -0,0 +1,5 @@
+alter table data.games drop column mmolb_stadium_id;
+drop trigger on_insert_stadium_version_trigger on data.stadium_versions;
+drop function data.on_insert_stadium_version;
+drop table data.stadium_versions;
+drop table data.stadiums;
@@ -0,0 +1,74 @@
+-- Every stadium that has ever had a version ingested. This exists so that
+-- games can reference a stadium before or after its versions change.
+create table data.stadiums (
+    mmolb_stadium_id text primary key not null
+);
+
+create table data.stadium_versions (
+    -- bookkeeping
+    id bigserial primary key not null,
+    mmolb_stadium_id text references data.stadiums not null,
+    valid_from timestamptz not null,
+    valid_until timestamptz, -- null means that it is currently valid
+    duplicates int not null default 0,
+
+    -- data
+    name text not null,
+    mmolb_team_id text, -- null = not provided on the object
+    modifications text[] not null,
+    capacity int, -- null = not provided on the object
+
+    unique (mmolb_stadium_id, valid_from),
+    unique nulls not distinct (mmolb_stadium_id, valid_until)
+);
+
+create function data.on_insert_stadium_version()
+    returns trigger as $$
+begin
+    insert into data.stadiums (mmolb_stadium_id)
+    values (NEW.mmolb_stadium_id)
+    on conflict do nothing;
+
+    -- check if the currently-valid version is exactly identical to the new version
+    -- the list of columns must exactly match the ones in data.stadium_versions or
+    -- we'll miss changes
+    perform 1
+    from data.stadium_versions sv
+    where sv.mmolb_stadium_id = NEW.mmolb_stadium_id
+      and sv.valid_until is null
+      and sv.name is not distinct from NEW.name
+      and sv.mmolb_team_id is not distinct from NEW.mmolb_team_id
+      and sv.modifications is not distinct from NEW.modifications
+      and sv.capacity is not distinct from NEW.capacity;
+
+    -- if there was an exact match, suppress this insert
+    if FOUND then
+        update data.stadium_versions
+        set duplicates = duplicates + 1
+        where mmolb_stadium_id = NEW.mmolb_stadium_id and valid_until is null;
+
+        return null;
+    end if;
+
+    -- otherwise, close out the currently-valid version...
+    update data.stadium_versions
+    set valid_until = NEW.valid_from
+    where mmolb_stadium_id = NEW.mmolb_stadium_id and valid_until is null;
+
+    -- ...and return the new row so it gets inserted as normal
+    return NEW;
+end;
+$$ language plpgsql;
+
+create trigger on_insert_stadium_version_trigger
+    before insert on data.stadium_versions
+    for each row
+execute function data.on_insert_stadium_version();
+
+-- Games only record the stadium's name. This is filled in by matching that
+-- name against the home team's stadium versions, whenever either stadiums
+-- or games are ingested.
+alter table data.games
+    add column mmolb_stadium_id text references data.stadiums;
+
+create index games_mmolb_stadium_id_idx on data.games (mmolb_stadium_id);
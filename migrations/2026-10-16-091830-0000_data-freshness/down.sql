@@ -0,0 +1 @@
+drop table info.data_freshness;
@@ -0,0 +1,9 @@
+-- How current the data for each ingest kind is. Updated at the end of every
+-- processing pass for that kind.
+create table info.data_freshness (
+    kind text primary key not null,
+    -- the latest valid_from of any version or entity that's been processed.
+    -- null if nothing of this kind has been processed yet
+    last_valid_from timestamp with time zone,
+    last_ingest_at timestamp with time zone not null
+);
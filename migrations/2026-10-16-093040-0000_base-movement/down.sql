@@ -0,0 +1 @@
+drop table taxa.base_movement;
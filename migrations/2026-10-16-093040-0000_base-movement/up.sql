@@ -0,0 +1,11 @@
+-- How a runner who was already on base moved during an event. Rows are
+-- filled in by the application from TaxaBaseMovement.
+create table taxa.base_movement (
+    id bigserial primary key not null,
+    name text not null,
+    display_name text not null,
+    is_steal_attempt boolean not null,
+    is_out boolean not null,
+    is_advance boolean not null,
+    unique (name)
+);
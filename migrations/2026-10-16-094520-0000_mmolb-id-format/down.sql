@@ -0,0 +1,13 @@
+drop index data.team_games_played_mmolb_game_id_idx;
+drop index data.stadium_versions_mmolb_team_id_idx;
+drop index data.team_player_versions_mmolb_player_id_idx;
+drop index data.player_versions_mmolb_team_id_idx;
+drop index data.games_home_team_mmolb_id_idx;
+drop index data.games_away_team_mmolb_id_idx;
+
+alter table data.stadium_versions drop constraint stadium_versions_mmolb_stadium_id_format;
+alter table data.team_versions drop constraint team_versions_mmolb_team_id_format;
+alter table data.player_versions drop constraint player_versions_mmolb_player_id_format;
+alter table data.games drop constraint games_mmolb_game_id_format;
+
+drop function data.is_mmolb_id(text);
@@ -0,0 +1,35 @@
+-- MMOLB entity ids are 24-character lowercase hex strings (Mongo object ids).
+create function data.is_mmolb_id(id text) returns boolean as
+$$
+select id ~ '^[0-9a-f]{24}$';
+$$ language sql immutable;
+
+-- Only the columns that hold each table's own entity id are checked. Those
+-- come straight from Chron's entity_id. Columns that refer to other
+-- entities come from inside the entity data and aren't trusted as much.
+--
+-- The constraints are added `not valid` so this migration doesn't have to
+-- scan every existing row. New rows are checked either way. Once the
+-- existing data has been checked, run `alter table ... validate constraint`
+-- to let the planner rely on them.
+alter table data.games
+    add constraint games_mmolb_game_id_format
+    check (data.is_mmolb_id(mmolb_game_id)) not valid;
+alter table data.player_versions
+    add constraint player_versions_mmolb_player_id_format
+    check (data.is_mmolb_id(mmolb_player_id)) not valid;
+alter table data.team_versions
+    add constraint team_versions_mmolb_team_id_format
+    check (data.is_mmolb_id(mmolb_team_id)) not valid;
+alter table data.stadium_versions
+    add constraint stadium_versions_mmolb_stadium_id_format
+    check (data.is_mmolb_id(mmolb_stadium_id)) not valid;
+
+-- Every entity id column that's used for lookups but isn't already the
+-- leading column of a unique constraint
+create index games_away_team_mmolb_id_idx on data.games (away_team_mmolb_id);
+create index games_home_team_mmolb_id_idx on data.games (home_team_mmolb_id);
+create index player_versions_mmolb_team_id_idx on data.player_versions (mmolb_team_id);
+create index team_player_versions_mmolb_player_id_idx on data.team_player_versions (mmolb_player_id);
+create index stadium_versions_mmolb_team_id_idx on data.stadium_versions (mmolb_team_id);
+create index team_games_played_mmolb_game_id_idx on data.team_games_played (mmolb_game_id);
@@ -0,0 +1 @@
+drop materialized view data.weather_outcomes;
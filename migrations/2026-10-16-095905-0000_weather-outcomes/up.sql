@@ -0,0 +1,19 @@
+-- How often each event type happened under each weather, per season
+create materialized view data.weather_outcomes as
+select
+    g.season,
+    g.weather,
+    w.name as weather_name,
+    w.emoji as weather_emoji,
+    e.event_type,
+    et.name as event_type_name,
+    count(distinct g.id) as games,
+    count(*) as events
+from data.events e
+join data.games g on g.id=e.game_id
+join data.weather w on w.id=g.weather
+join taxa.event_type et on et.id=e.event_type
+group by g.season, g.weather, w.name, w.emoji, e.event_type, et.name;
+
+create unique index weather_outcomes_idx
+    on data.weather_outcomes (season, weather, event_type);
@@ -0,0 +1,7 @@
+drop trigger on_delete_games_mark_weeks_dirty on data.games;
+drop trigger on_insert_games_mark_weeks_dirty on data.games;
+drop function data.on_change_games_mark_weeks_dirty();
+drop function data.game_week_start(timestamp with time zone);
+drop table data.weekly_rollups_dirty;
+drop table data.player_weekly_rollups;
+drop table data.team_weekly_rollups;
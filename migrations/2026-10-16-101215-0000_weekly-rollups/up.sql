@@ -0,0 +1,66 @@
+-- Week-level totals for long-horizon charts. Weeks start on Monday, UTC,
+-- and a game belongs to the week it started in. Only weeks listed in
+-- data.weekly_rollups_dirty are recomputed, after each game ingest pass.
+create table data.team_weekly_rollups (
+    week_start date not null,
+    team_mmolb_id text not null,
+    games bigint not null,
+    wins bigint not null,
+    runs_scored bigint not null,
+    runs_allowed bigint not null,
+    primary key (week_start, team_mmolb_id)
+);
+
+create index team_weekly_rollups_team_idx on data.team_weekly_rollups (team_mmolb_id, week_start);
+
+create table data.player_weekly_rollups (
+    week_start date not null,
+    team_mmolb_id text not null,
+    batter_name text not null,
+    plate_appearances bigint not null,
+    hits bigint not null,
+    home_runs bigint not null,
+    walks bigint not null,
+    strikeouts bigint not null,
+    primary key (week_start, team_mmolb_id, batter_name)
+);
+
+create index player_weekly_rollups_player_idx
+    on data.player_weekly_rollups (team_mmolb_id, batter_name, week_start);
+
+create table data.weekly_rollups_dirty (
+    week_start date primary key not null
+);
+
+create function data.game_week_start(started_at timestamp with time zone) returns date as
+$$
+select date_trunc('week', started_at at time zone 'utc')::date;
+$$ language sql immutable;
+
+-- Any inserted or deleted game makes its week dirty. Statement-level so
+-- bulk inserts only touch the dirty table once per week.
+create function data.on_change_games_mark_weeks_dirty()
+    returns trigger as $$
+begin
+    insert into data.weekly_rollups_dirty (week_start)
+    select distinct data.game_week_start(g.started_at) from changed_games g
+    on conflict do nothing;
+    return null;
+end;
+$$ language plpgsql;
+
+create trigger on_insert_games_mark_weeks_dirty
+    after insert on data.games
+    referencing new table as changed_games
+    for each statement
+    execute function data.on_change_games_mark_weeks_dirty();
+
+create trigger on_delete_games_mark_weeks_dirty
+    after delete on data.games
+    referencing old table as changed_games
+    for each statement
+    execute function data.on_change_games_mark_weeks_dirty();
+
+-- Every existing week needs to be computed once
+insert into data.weekly_rollups_dirty (week_start)
+select distinct data.game_week_start(started_at) from data.games;
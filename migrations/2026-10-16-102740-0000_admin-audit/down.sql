@@ -0,0 +1 @@
+drop table info.admin_audit;
@@ -0,0 +1,16 @@
+-- One row per admin operation (rollbacks, rebuilds, etc.), written in the
+-- same transaction as the operation itself
+create table info.admin_audit (
+    id bigserial primary key not null,
+    at timestamp with time zone not null default now(),
+    -- who ran it. free text, e.g. the OS user for CLI tools
+    actor text not null,
+    action text not null,
+    -- the operation's parameters and results
+    details jsonb not null,
+    -- snapshots of what the operation changed, where that's feasible
+    before jsonb,
+    after jsonb
+);
+
+create index admin_audit_at_idx on info.admin_audit (at);
@@ -0,0 +1 @@
+drop table data.pitcher_appearances;
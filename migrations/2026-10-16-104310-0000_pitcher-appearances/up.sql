@@ -0,0 +1,26 @@
+-- One row per pitcher per game, computed from the game's events and
+-- pitcher changes during game ingest
+create table data.pitcher_appearances (
+    id bigserial primary key not null,
+    game_id bigint references data.games on delete cascade not null,
+    is_home_team boolean not null,
+    pitcher_name text not null,
+    -- 1 for the starter, 2 for the first reliever, and so on
+    pitcher_count int not null,
+    first_game_event_index int not null,
+    last_game_event_index int not null,
+    -- Every event in data.events (including balks) counts as a pitch, same
+    -- as data.plate_appearances
+    pitches int not null,
+    batters_faced int not null,
+    -- Innings pitched is outs_recorded / 3
+    outs_recorded int not null,
+    -- Null if the pitcher finished the game
+    replaced_by_source bigint references taxa.pitcher_change_source,
+    replaced_by_name text,
+    unique (game_id, is_home_team, pitcher_name)
+);
+
+create index pitcher_appearances_pitcher_name_idx on data.pitcher_appearances (pitcher_name);
+
+-- Existing games are populated with `mmoldb-cli rebuild-table pitcher_appearances`
@@ -0,0 +1,13 @@
+-- Offloaded data can't be brought back from inside Postgres, so refuse to
+-- drop the pointers. Run `mmoldb-cli restore-games` first.
+do $$
+begin
+    if exists (select 1 from data.entities where offloaded_to is not null) then
+        raise exception 'data.entities has offloaded rows. Restore them before reverting this migration.';
+    end if;
+end
+$$;
+
+alter table data.entities
+    drop constraint entities_offloaded_data_is_null,
+    drop column offloaded_to;
@@ -0,0 +1,6 @@
+-- Raw entities can be moved to object storage to keep the database small.
+-- The row stays behind as a pointer, with its data replaced by JSON null.
+alter table data.entities
+    add column offloaded_to text null,
+    add constraint entities_offloaded_data_is_null
+        check (offloaded_to is null or data = 'null'::jsonb);
@@ -0,0 +1 @@
+drop table info.chron_anomalies;
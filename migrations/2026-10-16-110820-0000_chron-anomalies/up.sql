@@ -0,0 +1,16 @@
+-- One row per bad page from Chron (too many items, items out of order,
+-- truncated JSON, etc.)
+create table info.chron_anomalies (
+    id bigserial primary key not null,
+    detected_at timestamp with time zone not null default now(),
+    kind text not null,
+    url text not null,
+    -- the page token, or null for the first page
+    page text,
+    description text not null,
+    -- whether fetching the page again fixed it. if it didn't, the fetch
+    -- failed
+    resolved_by_refetch boolean not null
+);
+
+create index chron_anomalies_detected_at_idx on info.chron_anomalies (detected_at);
@@ -0,0 +1,2 @@
+drop table data.defense_outcomes;
+drop table data.offense_outcomes;
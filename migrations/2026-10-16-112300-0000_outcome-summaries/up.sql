@@ -0,0 +1,57 @@
+-- Counts of event outcomes, split by season and team. These used to be
+-- materialized views, but refreshing them meant recounting every event in
+-- the database. Now they're kept up to date as games are inserted and
+-- deleted, in the same transaction.
+create table data.offense_outcomes (
+    season int not null,
+    -- the batting team
+    team_mmolb_id text not null,
+    fair_ball_direction bigint references taxa.fielder_location,
+    event_type bigint references taxa.event_type not null,
+    hit_base bigint references taxa.base,
+    fielding_error_type bigint references taxa.fielding_error_type,
+    count bigint not null,
+    constraint offense_outcomes_key unique nulls not distinct (
+        season, team_mmolb_id, fair_ball_direction, event_type, hit_base, fielding_error_type
+    )
+);
+
+create table data.defense_outcomes (
+    season int not null,
+    -- the fielding team
+    team_mmolb_id text not null,
+    fair_ball_direction bigint references taxa.fielder_location,
+    event_type bigint references taxa.event_type not null,
+    hit_base bigint references taxa.base,
+    fielding_error_type bigint references taxa.fielding_error_type,
+    count bigint not null,
+    constraint defense_outcomes_key unique nulls not distinct (
+        season, team_mmolb_id, fair_ball_direction, event_type, hit_base, fielding_error_type
+    )
+);
+
+insert into data.offense_outcomes
+select
+    g.season,
+    case when e.top_of_inning then g.away_team_mmolb_id else g.home_team_mmolb_id end,
+    e.fair_ball_direction,
+    e.event_type,
+    e.hit_base,
+    e.fielding_error_type,
+    count(1)
+from data.events e
+join data.games g on g.id=e.game_id
+group by 1, 2, 3, 4, 5, 6;
+
+insert into data.defense_outcomes
+select
+    g.season,
+    case when e.top_of_inning then g.home_team_mmolb_id else g.away_team_mmolb_id end,
+    e.fair_ball_direction,
+    e.event_type,
+    e.hit_base,
+    e.fielding_error_type,
+    count(1)
+from data.events e
+join data.games g on g.id=e.game_id
+group by 1, 2, 3, 4, 5, 6;
@@ -0,0 +1,14 @@
+alter table data.event_fielders
+    drop constraint event_fielders_identity_confident_with_id,
+    drop column fielder_identity_confident,
+    drop column fielder_mmolb_id;
+
+alter table data.events
+    drop constraint events_pitcher_identity_confident_with_id,
+    drop constraint events_batter_identity_confident_with_id,
+    drop column pitcher_identity_confident,
+    drop column pitcher_mmolb_id,
+    drop column batter_identity_confident,
+    drop column batter_mmolb_id;
+
+drop table data.player_identity_resolution;
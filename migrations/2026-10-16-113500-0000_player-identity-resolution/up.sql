@@ -0,0 +1,36 @@
+-- Which roster player each name in a game refers to. Events only record
+-- players by name, so the name is looked up in the team's roster as of the
+-- start of the game.
+create table data.player_identity_resolution (
+    id bigserial primary key not null,
+    game_id bigint references data.games on delete cascade not null,
+    mmolb_team_id text not null,
+    player_name text not null,
+    -- 'Batter' or 'Pitcher', matching taxa.slot.role. Roster players in this
+    -- role are preferred over players with the same name in the other role.
+    role text not null,
+    mmolb_player_id text,
+    -- How many roster players matched. The resolution is only certain if
+    -- this is 1.
+    num_candidates int not null,
+    unique (game_id, mmolb_team_id, player_name, role)
+);
+
+alter table data.events
+    add column batter_mmolb_id text,
+    add column batter_identity_confident bool,
+    add column pitcher_mmolb_id text,
+    add column pitcher_identity_confident bool,
+    add constraint events_batter_identity_confident_with_id check (
+        (batter_mmolb_id is null) = (batter_identity_confident is null)
+    ),
+    add constraint events_pitcher_identity_confident_with_id check (
+        (pitcher_mmolb_id is null) = (pitcher_identity_confident is null)
+    );
+
+alter table data.event_fielders
+    add column fielder_mmolb_id text,
+    add column fielder_identity_confident bool,
+    add constraint event_fielders_identity_confident_with_id check (
+        (fielder_mmolb_id is null) = (fielder_identity_confident is null)
+    );
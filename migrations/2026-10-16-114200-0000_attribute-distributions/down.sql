@@ -0,0 +1 @@
+drop table data.attribute_distributions;
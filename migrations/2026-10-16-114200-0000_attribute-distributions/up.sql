@@ -0,0 +1,15 @@
+-- Percentiles of each attribute across players who are on a team, taken
+-- periodically by the ingest. Each week's snapshot is replaced every time
+-- it's retaken, so it reflects the last snapshot taken that week.
+create table data.attribute_distributions (
+    id bigserial primary key not null,
+    season int not null,
+    week_start date not null,
+    attribute bigint references taxa.attribute not null,
+    num_players int not null,
+    mean double precision not null,
+    -- percentiles[i + 1] is the i-th percentile, for i from 0 to 100
+    percentiles double precision[] not null,
+    taken_at timestamp with time zone not null,
+    unique (season, week_start, attribute)
+);
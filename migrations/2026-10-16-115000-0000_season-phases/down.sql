@@ -0,0 +1 @@
+drop table data.season_phases;
@@ -0,0 +1,17 @@
+-- When each phase of each season began and ended, from the `phase_times`
+-- of the MMOLB `time` entity. Each version of `time` replaces every phase
+-- of its season, because MMOLB sometimes reschedules phases. A phase can
+-- appear more than once in a season (e.g. the regular season resuming
+-- after the superstar break), so rows aren't unique on (season, day_type).
+create table data.season_phases (
+    id bigserial primary key not null,
+    season int not null,
+    day_type bigint references taxa.day_type not null,
+    started_at timestamp with time zone not null,
+    ended_at timestamp with time zone, -- null means this is the latest known phase
+    -- valid_from of the `time` version these phases came from
+    from_version timestamp with time zone not null
+);
+
+create index season_phases_season_idx on data.season_phases (season);
+create index season_phases_started_at_ended_at_idx on data.season_phases (started_at, ended_at);
@@ -0,0 +1,20 @@
+alter table taxa.attribute drop column description;
+alter table taxa.attribute_category drop column description;
+alter table taxa.attribute_effect_phase drop column description;
+alter table taxa.attribute_effect_type drop column description;
+alter table taxa.base drop column description;
+alter table taxa.base_description_format drop column description;
+alter table taxa.base_movement drop column description;
+alter table taxa.day_type drop column description;
+alter table taxa.event_type drop column description;
+alter table taxa.fair_ball_type drop column description;
+alter table taxa.fielder_location drop column description;
+alter table taxa.fielding_error_type drop column description;
+alter table taxa.handedness drop column description;
+alter table taxa.leagues drop column description;
+alter table taxa.modification_type drop column description;
+alter table taxa.pitch_category drop column description;
+alter table taxa.pitch_type drop column description;
+alter table taxa.pitcher_change_source drop column description;
+alter table taxa.slot drop column description;
+alter table taxa.slot_type drop column description;
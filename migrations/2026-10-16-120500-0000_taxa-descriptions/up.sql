@@ -0,0 +1,22 @@
+-- Human-readable description of each taxa row, filled in by the app from the
+-- taxa! definitions. null means that row doesn't have a description yet.
+alter table taxa.attribute add column description text;
+alter table taxa.attribute_category add column description text;
+alter table taxa.attribute_effect_phase add column description text;
+alter table taxa.attribute_effect_type add column description text;
+alter table taxa.base add column description text;
+alter table taxa.base_description_format add column description text;
+alter table taxa.base_movement add column description text;
+alter table taxa.day_type add column description text;
+alter table taxa.event_type add column description text;
+alter table taxa.fair_ball_type add column description text;
+alter table taxa.fielder_location add column description text;
+alter table taxa.fielding_error_type add column description text;
+alter table taxa.handedness add column description text;
+alter table taxa.leagues add column description text;
+alter table taxa.modification_type add column description text;
+alter table taxa.pitch_category add column description text;
+alter table taxa.pitch_type add column description text;
+alter table taxa.pitcher_change_source add column description text;
+alter table taxa.slot add column description text;
+alter table taxa.slot_type add column description text;
@@ -0,0 +1,3 @@
+alter table info.version_ingest_log drop column error_code;
+alter table info.event_ingest_log drop column error_code;
+drop table taxa.ingest_error_code;
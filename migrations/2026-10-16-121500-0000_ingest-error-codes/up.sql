@@ -0,0 +1,19 @@
+-- Categories of ingest log messages, so they can be counted. Rows are filled
+-- in by the application from TaxaIngestErrorCode.
+create table taxa.ingest_error_code (
+    id bigserial primary key not null,
+    name text not null,
+    display_name text not null,
+    description text,
+    unique (name)
+);
+
+alter table info.event_ingest_log
+    add column error_code bigint references taxa.ingest_error_code; -- null = uncategorized
+alter table info.version_ingest_log
+    add column error_code bigint references taxa.ingest_error_code; -- null = uncategorized
+
+create index event_ingest_log_error_code_idx on info.event_ingest_log (error_code)
+    where error_code is not null;
+create index version_ingest_log_error_code_idx on info.version_ingest_log (error_code)
+    where error_code is not null;
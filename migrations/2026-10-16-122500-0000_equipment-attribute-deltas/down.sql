@@ -0,0 +1 @@
+drop materialized view data.player_equipment_attribute_deltas;
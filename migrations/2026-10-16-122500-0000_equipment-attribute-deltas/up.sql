@@ -0,0 +1,42 @@
+-- Total equipment bonus to each attribute, per player, over each time range
+-- where the player's equipment effects didn't change. Zone- and
+-- Phase-specific effects only apply some of the time, so they're left out.
+create materialized view data.player_equipment_attribute_deltas as
+with unconditional_effects as (
+    select *
+    from data.player_equipment_effect_versions
+    where zone is null and phase is null
+),
+breakpoints as (
+    select mmolb_player_id, valid_from as at from unconditional_effects
+    union
+    select mmolb_player_id, valid_until as at from unconditional_effects
+    where valid_until is not null
+),
+intervals as (
+    select
+        mmolb_player_id,
+        at as valid_from,
+        lead(at) over (partition by mmolb_player_id order by at) as valid_until
+    from breakpoints
+)
+select
+    i.mmolb_player_id,
+    i.valid_from,
+    i.valid_until,
+    e.attribute,
+    a.name as attribute_name,
+    coalesce(sum(e.value) filter (where et.name='Flat'), 0) as flat_bonus,
+    coalesce(sum(e.value) filter (where et.name='Multiplier'), 0) as multiplier_bonus,
+    count(*) as num_effects
+from intervals i
+join unconditional_effects e
+    on e.mmolb_player_id=i.mmolb_player_id
+    and e.valid_from <= i.valid_from
+    and (e.valid_until is null or e.valid_until > i.valid_from)
+join taxa.attribute a on a.id=e.attribute
+join taxa.attribute_effect_type et on et.id=e.effect_type
+group by i.mmolb_player_id, i.valid_from, i.valid_until, e.attribute, a.name;
+
+create unique index player_equipment_attribute_deltas_idx
+    on data.player_equipment_attribute_deltas (mmolb_player_id, attribute, valid_from);
@@ -0,0 +1 @@
+drop table data.game_innings;
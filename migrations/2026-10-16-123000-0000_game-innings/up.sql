@@ -0,0 +1,16 @@
+-- Line score for each game: one row per half-inning, computed from the
+-- game's events during game ingest
+create table data.game_innings (
+    id bigserial primary key not null,
+    game_id bigint references data.games on delete cascade not null,
+    inning int not null,
+    top_of_inning boolean not null,
+    -- Runs and hits are for the batting team
+    runs int not null,
+    hits int not null,
+    -- Errors are for the fielding team
+    errors int not null,
+    unique (game_id, inning, top_of_inning)
+);
+
+-- Existing games are populated with `mmoldb-cli rebuild-table game_innings`
@@ -0,0 +1 @@
+drop table info.slow_queries;
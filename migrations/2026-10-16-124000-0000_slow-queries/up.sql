@@ -0,0 +1,13 @@
+-- Statements that took longer than the configured threshold, captured by
+-- the optional query logging on the connection pool
+create table info.slow_queries (
+    id bigserial primary key not null,
+    captured_at timestamp with time zone not null default now(),
+    duration_seconds double precision not null,
+    -- the statement with its bind parameters, truncated if it's very long
+    query text not null,
+    -- null if the statement succeeded
+    error text
+);
+
+create index slow_queries_captured_at_idx on info.slow_queries (captured_at);
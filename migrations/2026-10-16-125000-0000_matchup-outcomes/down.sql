@@ -0,0 +1 @@
+drop materialized view data.matchup_outcomes;
@@ -0,0 +1,32 @@
+-- Plate appearance outcomes for each batter against each pitcher, per
+-- season. Players are identified by their resolved MMOLB ids, so plate
+-- appearances where either name couldn't be resolved are left out.
+create materialized view data.matchup_outcomes as
+select
+    g.season,
+    e.batter_mmolb_id,
+    e.pitcher_mmolb_id,
+    -- Names as of the most recent plate appearance, since players can be
+    -- renamed
+    (array_agg(e.batter_name order by g.started_at desc, e.game_event_index desc))[1] as batter_name,
+    (array_agg(e.pitcher_name order by g.started_at desc, e.game_event_index desc))[1] as pitcher_name,
+    count(*) as plate_appearances,
+    count(*) filter (where et.is_hit) as hits,
+    count(*) filter (where et.name='HomeRun') as home_runs,
+    count(*) filter (where et.name='Walk') as walks,
+    count(*) filter (where et.is_strikeout) as strikeouts,
+    count(*) filter (
+        where not (e.batter_identity_confident and e.pitcher_identity_confident)
+    ) as uncertain_plate_appearances
+from data.events e
+join data.games g on g.id=e.game_id
+join taxa.event_type et on et.id=e.event_type
+where et.ends_plate_appearance
+    and e.batter_mmolb_id is not null
+    and e.pitcher_mmolb_id is not null
+group by g.season, e.batter_mmolb_id, e.pitcher_mmolb_id;
+
+create unique index matchup_outcomes_idx
+    on data.matchup_outcomes (batter_mmolb_id, pitcher_mmolb_id, season);
+create index matchup_outcomes_pitcher_idx
+    on data.matchup_outcomes (pitcher_mmolb_id, season);
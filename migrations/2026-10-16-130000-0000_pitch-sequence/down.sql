@@ -0,0 +1,2 @@
+drop table data.event_pitch_sequence;
+drop table taxa.count;
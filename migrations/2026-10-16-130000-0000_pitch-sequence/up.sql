@@ -0,0 +1,29 @@
+-- The ball-strike count. Rows are filled in by the application from
+-- TaxaCount.
+create table taxa.count (
+    id bigserial primary key not null,
+    name text not null,
+    display_name text not null,
+    balls int not null,
+    strikes int not null,
+    description text,
+    unique (name)
+);
+
+-- Each pitch's place in its plate appearance. Computed by the sim during game
+-- ingest, so pitch sequences can be queried without window functions over
+-- data.events. As with data.plate_appearances, every event counts as a pitch.
+create table data.event_pitch_sequence (
+    id bigserial primary key not null,
+    event_id bigint references data.events on delete cascade not null,
+    -- 1 for the first pitch of the plate appearance
+    pitch_of_plate_appearance int not null,
+    previous_pitch_event_id bigint references data.events on delete cascade, -- null = first pitch of the plate appearance
+    count_before bigint references taxa.count, -- null = the count isn't a normal count
+    unique (event_id)
+);
+
+create index event_pitch_sequence_previous_pitch_idx
+    on data.event_pitch_sequence (previous_pitch_event_id)
+    where previous_pitch_event_id is not null;
+create index event_pitch_sequence_count_before_idx on data.event_pitch_sequence (count_before);
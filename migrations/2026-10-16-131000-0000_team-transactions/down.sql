@@ -0,0 +1 @@
+drop table data.team_transactions;
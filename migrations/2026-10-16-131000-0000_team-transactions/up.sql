@@ -0,0 +1,17 @@
+-- Coins a team earned or spent, from its feed. One row per feed event that
+-- moved coins.
+create table data.team_transactions (
+    id bigserial primary key not null,
+    mmolb_team_id text not null,
+    feed_event_index int not null,
+    time timestamptz not null,
+    -- positive = the team earned coins, negative = the team spent them
+    amount int not null,
+    source text not null,
+    mmolb_game_id text, -- null = not tied to a game
+    unique (mmolb_team_id, feed_event_index)
+);
+
+create index team_transactions_time_idx on data.team_transactions (mmolb_team_id, time);
+
+-- Existing team feeds are filled in with `mmoldb-cli rebuild-team-transactions`
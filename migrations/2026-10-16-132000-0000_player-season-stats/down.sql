@@ -0,0 +1 @@
+drop materialized view data.player_season_stats;
@@ -0,0 +1,110 @@
+-- Per-season batting and pitching totals for each player, for
+-- leaderboards. Players are identified by their resolved MMOLB ids, so
+-- anything credited to a name that couldn't be resolved is left out.
+create materialized view data.player_season_stats as
+with contributions as (
+    -- Plate appearances, credited to the batter
+    select
+        g.season,
+        e.batter_mmolb_id as mmolb_player_id,
+        e.batter_name as player_name,
+        g.started_at,
+        e.game_event_index,
+        1 as plate_appearances,
+        et.is_hit::int as hits,
+        (et.name='HomeRun')::int as home_runs,
+        (et.name='Walk')::int as walks,
+        0 as stolen_bases,
+        0 as strikeouts,
+        0 as outs_recorded,
+        0 as earned_runs,
+        null::float8 as pitch_speed
+    from data.events e
+    join data.games g on g.id=e.game_id
+    join taxa.event_type et on et.id=e.event_type
+    where et.ends_plate_appearance
+        and e.batter_mmolb_id is not null
+    union all
+    -- Every pitch-like event, credited to the pitcher. Outs are counted the
+    -- same way as data.pitcher_appearances counts them.
+    select
+        g.season,
+        e.pitcher_mmolb_id,
+        e.pitcher_name,
+        g.started_at,
+        e.game_event_index,
+        0, 0, 0, 0, 0,
+        (et.ends_plate_appearance and et.is_strikeout)::int,
+        greatest(e.outs_after - e.outs_before, 0),
+        0,
+        e.pitch_speed
+    from data.events e
+    join data.games g on g.id=e.game_id
+    join taxa.event_type et on et.id=e.event_type
+    where e.pitcher_mmolb_id is not null
+    union all
+    -- Successful steals, credited to the batter of the event where the
+    -- runner reached base
+    select
+        g.season,
+        src.batter_mmolb_id,
+        src.batter_name,
+        g.started_at,
+        e.game_event_index,
+        0, 0, 0, 0,
+        1,
+        0, 0, 0,
+        null
+    from data.event_baserunners r
+    join data.events e on e.id=r.event_id
+    join data.games g on g.id=e.game_id
+    join data.events src on src.game_id=e.game_id
+        and src.game_event_index=r.source_event_index
+    where r.steal
+        and not r.is_out
+        and src.batter_mmolb_id is not null
+    union all
+    -- Earned runs, charged to the pitcher of the event where the runner
+    -- reached base
+    select
+        g.season,
+        src.pitcher_mmolb_id,
+        src.pitcher_name,
+        g.started_at,
+        e.game_event_index,
+        0, 0, 0, 0, 0, 0, 0,
+        1,
+        null
+    from data.event_baserunners r
+    join taxa.base b on b.id=r.base_after
+    join data.events e on e.id=r.event_id
+    join data.games g on g.id=e.game_id
+    join data.events src on src.game_id=e.game_id
+        and src.game_event_index=r.source_event_index
+    where b.name='Home'
+        and not r.is_out
+        and r.is_earned
+        and src.pitcher_mmolb_id is not null
+)
+select
+    season,
+    mmolb_player_id,
+    -- Name as of the player's latest contribution, since players can be
+    -- renamed
+    (array_agg(player_name order by started_at desc, game_event_index desc))[1] as player_name,
+    sum(plate_appearances)::bigint as plate_appearances,
+    sum(hits)::bigint as hits,
+    sum(home_runs)::bigint as home_runs,
+    sum(walks)::bigint as walks,
+    sum(stolen_bases)::bigint as stolen_bases,
+    sum(strikeouts)::bigint as strikeouts,
+    sum(outs_recorded)::bigint as outs_recorded,
+    sum(earned_runs)::bigint as earned_runs,
+    max(pitch_speed) as max_pitch_speed
+from contributions
+group by season, mmolb_player_id;
+
+create unique index player_season_stats_idx
+    on data.player_season_stats (mmolb_player_id, season);
+create index player_season_stats_season_idx
+    on data.player_season_stats (season);
@@ -0,0 +1,2 @@
+alter table data.games
+    drop extra;
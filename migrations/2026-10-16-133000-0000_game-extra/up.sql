@@ -0,0 +1,4 @@
+alter table data.games
+    -- values set aside so the rest of the game could be deserialized, keyed by
+    -- JSON pointer. null = nothing was set aside
+    add extra jsonb;
@@ -0,0 +1 @@
+drop table data.feed_event_texts;
@@ -0,0 +1,28 @@
+-- The text of the latest version of each player and team feed event, for
+-- full-text search
+create table data.feed_event_texts (
+    kind text not null, -- 'player_feed' or 'team_feed'
+    entity_id text not null,
+    feed_event_index int not null,
+    -- the version this text came from. only newer versions replace it.
+    valid_from timestamptz not null,
+    season int not null,
+    time timestamptz not null,
+    text text not null,
+    text_search tsvector generated always as (to_tsvector('english', text)) stored,
+    primary key (kind, entity_id, feed_event_index)
+);
+
+create index feed_event_texts_search_idx on data.feed_event_texts using gin (text_search);
+create index feed_event_texts_season_idx on data.feed_event_texts (kind, season);
+
+-- Backfill from the raw versions that were already ingested, rather than
+-- reprocessing every feed event
+insert into data.feed_event_texts (kind, entity_id, feed_event_index, valid_from, season, time, text)
+select kind, entity_id, feed_event_index, valid_from, (data->>'season')::int, (data->>'ts')::timestamptz, data->>'text'
+from data.feed_event_versions
+where valid_until is null
+    and kind in ('player_feed', 'team_feed')
+    and jsonb_typeof(data->'season') = 'number'
+    and data->>'ts' is not null
+    and data->>'text' <> '';
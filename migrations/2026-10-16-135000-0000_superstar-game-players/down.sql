@@ -0,0 +1 @@
+drop table data.superstar_game_players;
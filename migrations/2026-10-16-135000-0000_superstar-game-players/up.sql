@@ -0,0 +1,23 @@
+-- Links each player in a superstar game back to the lesser or greater league
+-- player they are, and the team they came from. Each league's superstar team
+-- is the league's parent team (taxa.leagues.parent_team_id), and stats
+-- recorded against it are otherwise cut off from the rest of the player's
+-- career.
+create table data.superstar_game_players (
+    id bigserial primary key not null,
+    game_id bigint references data.games on delete cascade not null,
+    superstar_team_mmolb_id text not null,
+    -- The league whose superstar team this is
+    superstar_league bigint references taxa.leagues,
+    player_name text not null,
+    mmolb_player_id text,
+    -- How many players matched. The link is only certain if this is 1.
+    num_candidates int not null,
+    -- The player's own team and its league as of the start of the game
+    source_team_mmolb_id text,
+    source_league bigint references taxa.leagues,
+    unique (game_id, superstar_team_mmolb_id, player_name)
+);
+
+create index superstar_game_players_mmolb_player_id_idx
+    on data.superstar_game_players (mmolb_player_id);
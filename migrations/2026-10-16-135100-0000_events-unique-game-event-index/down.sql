@@ -0,0 +1,2 @@
+alter table data.events
+    drop constraint events_game_id_game_event_index_key;
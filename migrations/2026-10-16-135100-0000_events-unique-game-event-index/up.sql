@@ -0,0 +1,6 @@
+-- Event ids are matched back to events by (game_id, game_event_index) after
+-- they're inserted, which is only safe if that pair is unique. Every game's
+-- events are deleted and reinserted together, so there shouldn't be any
+-- duplicates to clean up first.
+alter table data.events
+    add constraint events_game_id_game_event_index_key unique (game_id, game_event_index);
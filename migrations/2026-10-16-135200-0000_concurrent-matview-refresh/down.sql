@@ -0,0 +1,2 @@
+drop table info.matview_refreshes;
+drop index info.entities_count_unique_kind;
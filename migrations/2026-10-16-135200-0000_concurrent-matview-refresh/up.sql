@@ -0,0 +1,17 @@
+-- entities_count was the only matview without a unique index, which it needs
+-- to be refreshed concurrently. kind is unique because each branch of its
+-- union counts a different kind.
+create unique index entities_count_unique_kind on info.entities_count (kind);
+
+-- How long the latest refresh of each materialized view took
+create table info.matview_refreshes (
+    -- schema-qualified, e.g. 'data.weather_outcomes'
+    matview text primary key not null,
+    refreshed_at timestamp with time zone not null,
+    duration_seconds double precision not null,
+    -- false if the matview couldn't be refreshed concurrently and a plain
+    -- refresh, which blocks reads, was used instead
+    concurrent boolean not null,
+    -- null if the refresh succeeded
+    error text
+);
@@ -0,0 +1,72 @@
+-- A feature vector for each player, for finding similar players. Features
+-- are the player's current report stars for each attribute and their career
+-- batting and pitching rates, each standardized across all players so no
+-- one feature dominates. A player who's missing a feature (e.g. has never
+-- pitched) gets the average for it.
+create materialized view data.player_similarity_features as
+with raw_features as (
+    select
+        rav.mmolb_player_id,
+        'stars_' || a.name as feature,
+        avg(rav.base_stars)::float8 as value
+    from data.player_report_attribute_versions rav
+    join taxa.attribute a on a.id=rav.attribute
+    where rav.valid_until is null
+        and rav.base_stars is not null
+    group by rav.mmolb_player_id, a.name
+    union all
+    select mmolb_player_id, rate.feature, rate.value
+    from (
+        select
+            mmolb_player_id,
+            sum(plate_appearances) as plate_appearances,
+            sum(hits) as hits,
+            sum(home_runs) as home_runs,
+            sum(walks) as walks,
+            sum(stolen_bases) as stolen_bases,
+            sum(strikeouts) as strikeouts,
+            sum(outs_recorded) as outs_recorded,
+            sum(earned_runs) as earned_runs
+        from data.player_season_stats
+        group by mmolb_player_id
+    ) career
+    cross join lateral (
+        values
+            ('hits_per_pa', career.hits::float8 / nullif(career.plate_appearances, 0)),
+            ('home_runs_per_pa', career.home_runs::float8 / nullif(career.plate_appearances, 0)),
+            ('walks_per_pa', career.walks::float8 / nullif(career.plate_appearances, 0)),
+            ('stolen_bases_per_pa', career.stolen_bases::float8 / nullif(career.plate_appearances, 0)),
+            ('strikeouts_per_out', career.strikeouts::float8 / nullif(career.outs_recorded, 0)),
+            ('earned_runs_per_out', career.earned_runs::float8 / nullif(career.outs_recorded, 0))
+    ) as rate(feature, value)
+    where rate.value is not null
+),
+feature_stats as (
+    select feature, avg(value) as mean, stddev_pop(value) as stddev
+    from raw_features
+    group by feature
+),
+players as (
+    select distinct mmolb_player_id from raw_features
+),
+standardized as (
+    select
+        p.mmolb_player_id,
+        fs.feature,
+        coalesce((rf.value - fs.mean) / nullif(fs.stddev, 0), 0) as value
+    from players p
+    cross join feature_stats fs
+    left join raw_features rf on rf.mmolb_player_id=p.mmolb_player_id
+        and rf.feature=fs.feature
+)
+select
+    mmolb_player_id,
+    -- Every player has every feature, in the same order, so the vectors
+    -- can be compared element by element
+    array_agg(value order by feature) as features,
+    sqrt(sum(value * value)) as norm
+from standardized
+group by mmolb_player_id;
+
+create unique index player_similarity_features_idx
+    on data.player_similarity_features (mmolb_player_id);
@@ -0,0 +1,5 @@
+drop index data.player_attribute_augments_feed_event_idx;
+
+-- Drops the foreign key with it. Texts added by the backfill are left in
+-- place, since they're valid feed event texts either way.
+alter table data.player_attribute_augments drop column feed_event_kind;
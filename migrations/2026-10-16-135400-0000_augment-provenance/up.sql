@@ -0,0 +1,41 @@
+-- Link each augment to the text of the feed event it came from. Augments are
+-- always in a player's feed, but the kind has to be a column for the foreign
+-- key to use it. It's null for augments whose feed event has no stored text,
+-- which Postgres doesn't check against the foreign key.
+alter table data.player_attribute_augments
+    add column feed_event_kind text check (feed_event_kind = 'player_feed');
+
+-- Older augments can come from feed events whose text was never stored, if
+-- the feed event changed after the augment was ingested. Fill those in from
+-- the version the augment was ingested from.
+insert into data.feed_event_texts (kind, entity_id, feed_event_index, valid_from, season, time, text)
+select distinct on (fev.entity_id, fev.feed_event_index)
+    fev.kind, fev.entity_id, fev.feed_event_index, fev.valid_from,
+    (fev.data->>'season')::int, (fev.data->>'ts')::timestamptz, fev.data->>'text'
+from data.player_attribute_augments paa
+join data.feed_event_versions fev
+    on fev.kind='player_feed'
+    and fev.entity_id=paa.mmolb_player_id
+    and fev.feed_event_index=paa.feed_event_index
+where jsonb_typeof(fev.data->'season') = 'number'
+    and fev.data->>'ts' is not null
+    and fev.data->>'text' <> ''
+order by fev.entity_id, fev.feed_event_index, fev.valid_from desc
+on conflict (kind, entity_id, feed_event_index) do nothing;
+
+update data.player_attribute_augments paa
+set feed_event_kind='player_feed'
+where exists(
+    select 1 from data.feed_event_texts fet
+    where fet.kind='player_feed'
+        and fet.entity_id=paa.mmolb_player_id
+        and fet.feed_event_index=paa.feed_event_index
+);
+
+alter table data.player_attribute_augments
+    add constraint player_attribute_augments_feed_event_fkey
+    foreign key (feed_event_kind, mmolb_player_id, feed_event_index)
+    references data.feed_event_texts (kind, entity_id, feed_event_index);
+
+create index player_attribute_augments_feed_event_idx
+    on data.player_attribute_augments (mmolb_player_id, feed_event_index);
@@ -0,0 +1 @@
+drop table info.ingest_task_state;
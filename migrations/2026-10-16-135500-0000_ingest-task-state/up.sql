@@ -0,0 +1,13 @@
+-- The last known state of each ingest stage, so the next ingest can tell
+-- whether the previous one exited cleanly
+create table info.ingest_task_state (
+    -- e.g. 'game_processing'. see IngestStage::name
+    stage text primary key not null,
+    state text not null check (state in ('running', 'stopped', 'failed', 'aborted')),
+    -- when the stage was last launched
+    started_at timestamp with time zone not null,
+    -- when state last changed
+    updated_at timestamp with time zone not null,
+    -- null unless state is 'failed' or 'aborted'
+    error text
+);
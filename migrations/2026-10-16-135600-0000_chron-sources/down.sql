@@ -0,0 +1,91 @@
+-- Rows from other sources can't be kept once there's nothing to tell them
+-- apart from main MMOLB's
+delete from data.entities where source is not null;
+delete from data.versions where source is not null;
+delete from data.feed_event_versions where source is not null;
+
+create or replace view info.raw_events as select
+    e.valid_from,
+    e.entity_id as mmolb_game_id,
+    ev.game_event_index,
+    ev.event_raw->>'message' as event_text,
+    e.data as game_raw,
+    ev.event_raw
+from data.entities e
+         cross join lateral jsonb_array_elements(e.data->'EventLog') with ordinality as ev(event_raw, game_event_index)
+where e.kind='game';
+
+create or replace function data.on_insert_entity()
+    returns trigger as $$
+begin
+    -- When we get a new entity, delete the old one
+    delete from data.entities en
+    where en.kind = NEW.kind
+      and en.entity_id = NEW.entity_id;
+
+    return NEW;
+end;
+$$ language plpgsql;
+
+create or replace function data.on_insert_version()
+    returns trigger as $$
+begin
+    -- When we get a new version, update the old one's valid_to.
+    -- This should only
+    update data.versions
+        set valid_to = NEW.valid_from
+        where kind = NEW.kind
+            and entity_id = NEW.entity_id
+            and valid_to is null;
+
+    return NEW;
+end;
+$$ language plpgsql;
+
+create or replace function data.on_insert_feed_event_version()
+    returns trigger as $$
+begin
+    -- check if the currently-valid version is exactly identical to the new version
+    -- the list of columns must exactly match the ones in data.feed_event_versions or
+    -- we'll miss events
+    perform 1
+    from data.feed_event_versions fev
+    where fev.kind = NEW.kind
+      and fev.entity_id = NEW.entity_id
+      and fev.feed_event_index = NEW.feed_event_index
+      and fev.valid_until is null
+      -- note: "is not distinct from" is like "=" except for how it treats nulls.
+      -- in postgres, NULL = NULL is false but NULL is not distinct from NULL is true
+      and fev.data is not distinct from NEW.data;
+
+    -- if there was an exact match, suppress this insert
+    if FOUND then
+        return null;
+    end if;
+
+    -- otherwise, close out the currently-valid version...
+    update data.feed_event_versions
+    set valid_until = NEW.valid_from
+    where kind = NEW.kind
+      and entity_id = NEW.entity_id
+      and feed_event_index = NEW.feed_event_index
+      and valid_until is null;
+
+    -- ...and return the new row so it gets inserted as normal
+    return NEW;
+end;
+$$ language plpgsql;
+
+drop index data.feed_event_versions_source_key_index;
+alter table data.feed_event_versions
+    add primary key (kind, entity_id, feed_event_index, valid_from);
+
+drop index data.versions_source_key_index;
+alter table data.versions add primary key (kind, entity_id, valid_from);
+
+drop index data.entities_source_key_index;
+alter table data.entities add primary key (kind, entity_id);
+
+alter table data.feed_event_versions drop column source;
+alter table data.versions drop column source;
+alter table data.entities drop column source;
@@ -0,0 +1,100 @@
+-- Raw data can come from Chron-compatible APIs other than the main MMOLB
+-- one, e.g. test universes. Each such source gets a name, and its rows are
+-- tagged with it so its ids can't collide with MMOLB's. Null means main
+-- MMOLB, which is everything that was ingested before this.
+alter table data.entities add column source text null;
+alter table data.versions add column source text null;
+alter table data.feed_event_versions add column source text null;
+
+-- Primary key columns can't be null, so the keys become unique indexes.
+-- "nulls not distinct" makes them treat all the main-source rows as the
+-- same source.
+alter table data.entities drop constraint entities_pkey;
+create unique index entities_source_key_index
+    on data.entities (kind, entity_id, source) nulls not distinct;
+
+alter table data.versions drop constraint versions_pkey;
+create unique index versions_source_key_index
+    on data.versions (kind, entity_id, valid_from, source) nulls not distinct;
+
+alter table data.feed_event_versions drop constraint feed_event_versions_pkey;
+create unique index feed_event_versions_source_key_index
+    on data.feed_event_versions (kind, entity_id, feed_event_index, valid_from, source)
+    nulls not distinct;
+
+-- The triggers have to stay within the new row's source
+create or replace function data.on_insert_entity()
+    returns trigger as $$
+begin
+    -- When we get a new entity, delete the old one
+    delete from data.entities en
+    where en.kind = NEW.kind
+      and en.entity_id = NEW.entity_id
+      and en.source is not distinct from NEW.source;
+
+    return NEW;
+end;
+$$ language plpgsql;
+
+create or replace function data.on_insert_version()
+    returns trigger as $$
+begin
+    -- When we get a new version, update the old one's valid_to.
+    update data.versions
+        set valid_to = NEW.valid_from
+        where kind = NEW.kind
+            and entity_id = NEW.entity_id
+            and source is not distinct from NEW.source
+            and valid_to is null;
+
+    return NEW;
+end;
+$$ language plpgsql;
+
+create or replace function data.on_insert_feed_event_version()
+    returns trigger as $$
+begin
+    -- check if the currently-valid version is exactly identical to the new version
+    -- the list of columns must exactly match the ones in data.feed_event_versions or
+    -- we'll miss events
+    perform 1
+    from data.feed_event_versions fev
+    where fev.kind = NEW.kind
+      and fev.entity_id = NEW.entity_id
+      and fev.feed_event_index = NEW.feed_event_index
+      and fev.source is not distinct from NEW.source
+      and fev.valid_until is null
+      -- note: "is not distinct from" is like "=" except for how it treats nulls.
+      -- in postgres, NULL = NULL is false but NULL is not distinct from NULL is true
+      and fev.data is not distinct from NEW.data;
+
+    -- if there was an exact match, suppress this insert
+    if FOUND then
+        return null;
+    end if;
+
+    -- otherwise, close out the currently-valid version...
+    update data.feed_event_versions
+    set valid_until = NEW.valid_from
+    where kind = NEW.kind
+      and entity_id = NEW.entity_id
+      and feed_event_index = NEW.feed_event_index
+      and source is not distinct from NEW.source
+      and valid_until is null;
+
+    -- ...and return the new row so it gets inserted as normal
+    return NEW;
+end;
+$$ language plpgsql;
+
+-- Only main-source games are processed, so only their events are exposed
+create or replace view info.raw_events as select
+    e.valid_from,
+    e.entity_id as mmolb_game_id,
+    ev.game_event_index,
+    ev.event_raw->>'message' as event_text,
+    e.data as game_raw,
+    ev.event_raw
+from data.entities e
+         cross join lateral jsonb_array_elements(e.data->'EventLog') with ordinality as ev(event_raw, game_event_index)
+where e.kind='game' and e.source is null;
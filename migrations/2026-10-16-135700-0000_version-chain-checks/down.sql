@@ -0,0 +1 @@
+drop index info.version_ingest_log_version_idx;
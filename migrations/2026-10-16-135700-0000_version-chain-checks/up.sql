@@ -0,0 +1,4 @@
+-- The version chain check looks up each broken version's existing logs to
+-- find a free log_index
+create index version_ingest_log_version_idx
+    on info.version_ingest_log (kind, entity_id, valid_from);
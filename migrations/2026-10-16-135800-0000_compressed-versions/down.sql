@@ -0,0 +1,11 @@
+-- zstd can't be decompressed in SQL, so compressed versions would be lost
+do $$
+begin
+    if exists (select 1 from data.versions where data_zstd is not null) then
+        raise exception 'data.versions has compressed versions, which would be lost by reverting';
+    end if;
+end $$;
+
+alter table data.versions drop constraint versions_data_stored;
+alter table data.versions drop column data_zstd;
+alter table data.versions alter column data set not null;
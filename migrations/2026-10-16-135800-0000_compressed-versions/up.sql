@@ -0,0 +1,6 @@
+-- The retention pass can compress the data of old raw versions, which moves
+-- it from data to data_zstd. Exactly one of the two is set.
+alter table data.versions alter column data drop not null;
+alter table data.versions add column data_zstd bytea null;
+alter table data.versions add constraint versions_data_stored
+    check ((data is null) <> (data_zstd is null));
@@ -0,0 +1,8 @@
+delete from info.season_rollovers where completed_at is null;
+
+alter table info.season_rollovers
+    drop column last_error,
+    drop column last_attempted_at,
+    drop column attempts,
+    alter column completed_at set default now(),
+    alter column completed_at set not null;
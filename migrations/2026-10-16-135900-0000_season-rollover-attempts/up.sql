@@ -0,0 +1,10 @@
+-- Failed season rollover jobs are recorded too, so they can be seen and
+-- retried. Only rows with completed_at set stop a job from running again.
+alter table info.season_rollovers
+    alter column completed_at drop not null,
+    alter column completed_at drop default,
+    add column attempts int not null default 1,
+    add column last_attempted_at timestamp with time zone not null default now(),
+    add column last_error text;
+
+update info.season_rollovers set last_attempted_at=completed_at;
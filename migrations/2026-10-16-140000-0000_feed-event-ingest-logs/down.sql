@@ -0,0 +1,2 @@
+alter table info.version_ingest_log
+    drop column feed_event_index;
@@ -0,0 +1,5 @@
+-- Every event in a feed version shares its entity id and valid_from, so the
+-- feed event index is what tells their logs apart. It's null for kinds
+-- that aren't feeds, and for logs from before this column existed.
+alter table info.version_ingest_log
+    add column feed_event_index int;
@@ -0,0 +1 @@
+drop materialized view data.pitcher_pitch_count_splits;
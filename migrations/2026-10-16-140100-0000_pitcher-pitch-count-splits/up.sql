@@ -0,0 +1,23 @@
+-- Outcomes per pitcher per season, split by how many pitches the pitcher had
+-- thrown before the plate appearance, in buckets of 25. The last bucket is
+-- 100 pitches and up.
+create materialized view data.pitcher_pitch_count_splits as
+select
+    g.season,
+    case when e.top_of_inning then g.home_team_mmolb_id else g.away_team_mmolb_id end as team_mmolb_id,
+    e.pitcher_name,
+    least(pa.pitcher_pitches_before / 25, 4) * 25 as pitch_count_min,
+    count(*) as plate_appearances,
+    count(*) filter (where et.is_hit) as hits,
+    count(*) filter (where et.name='HomeRun') as home_runs,
+    count(*) filter (where et.name='Walk') as walks,
+    count(*) filter (where et.is_strikeout) as strikeouts,
+    sum(pa.pitches)::bigint as pitches
+from data.plate_appearances pa
+join data.events e on e.id=pa.event_id
+join data.games g on g.id=e.game_id
+join taxa.event_type et on et.id=e.event_type
+group by g.season, team_mmolb_id, e.pitcher_name, pitch_count_min;
+
+create unique index pitcher_pitch_count_splits_idx
+    on data.pitcher_pitch_count_splits (season, team_mmolb_id, pitcher_name, pitch_count_min);
@@ -0,0 +1 @@
+alter table info.matview_refreshes drop column inputs;
@@ -0,0 +1,4 @@
+-- A fingerprint of the tables an expensive matview is computed from, as of
+-- its last successful refresh. The refresh is skipped while the fingerprint
+-- hasn't changed.
+alter table info.matview_refreshes add column inputs text;
@@ -0,0 +1 @@
+drop materialized view data.player_similar_players;
@@ -0,0 +1,35 @@
+-- Each player's most similar players, so a player page doesn't have to
+-- compare the player against every other player. Comparing every pair is
+-- expensive, so this is only refreshed when its inputs change.
+create materialized view data.player_similar_players as
+select
+    target.mmolb_player_id,
+    nearest.rank,
+    nearest.similar_mmolb_player_id,
+    nearest.similarity
+from data.player_similarity_features target
+cross join lateral (
+    select
+        row_number() over (order by s.similarity desc, s.mmolb_player_id) as rank,
+        s.mmolb_player_id as similar_mmolb_player_id,
+        s.similarity
+    from (
+        select
+            other.mmolb_player_id,
+            (
+                select sum(a * b)
+                from unnest(target.features, other.features) as elements(a, b)
+            ) / (target.norm * other.norm) as similarity
+        from data.player_similarity_features other
+        where other.mmolb_player_id<>target.mmolb_player_id
+            and other.norm > 0
+    ) s
+    order by rank
+    -- Keep in sync with SIMILAR_PLAYERS_STORED in mmoldb-db
+    limit 10
+) nearest
+-- A player with exactly average everything has no direction to compare
+where target.norm > 0;
+
+create unique index player_similar_players_idx
+    on data.player_similar_players (mmolb_player_id, rank);
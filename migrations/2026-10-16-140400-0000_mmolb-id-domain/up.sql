@@ -0,0 +1,277 @@
+-- MMOLB ids are Mongo object ids: 12 bytes, written as 24 hex characters.
+-- Stored as bytes they take half the space they do as text, which adds up
+-- in the biggest tables.
+create domain data.mmolb_id as bytea check (octet_length(value) = 12);
+
+create function data.mmolb_id_from_text(id text) returns data.mmolb_id as
+$$
+select decode(id, 'hex')::data.mmolb_id;
+$$ language sql immutable strict;
+
+create function data.mmolb_id_to_text(id data.mmolb_id) returns text as
+$$
+select encode(id, 'hex');
+$$ language sql immutable strict;
+
+-- data.events is by far the biggest table, and its resolved player ids are
+-- only read by these matviews and a few queries. The matviews still show
+-- the ids as text. A column's type can't be changed while a view reads it,
+-- so they're recreated. The similarity matviews read player_season_stats
+-- and are recreated unchanged.
+drop materialized view data.player_similar_players;
+drop materialized view data.player_similarity_features;
+drop materialized view data.player_season_stats;
+drop materialized view data.matchup_outcomes;
+
+alter table data.events
+    alter column batter_mmolb_id type data.mmolb_id
+        using data.mmolb_id_from_text(batter_mmolb_id),
+    alter column pitcher_mmolb_id type data.mmolb_id
+        using data.mmolb_id_from_text(pitcher_mmolb_id);
+
+create materialized view data.matchup_outcomes as
+select
+    g.season,
+    data.mmolb_id_to_text(e.batter_mmolb_id) as batter_mmolb_id,
+    data.mmolb_id_to_text(e.pitcher_mmolb_id) as pitcher_mmolb_id,
+    -- Names as of the most recent plate appearance, since players can be
+    -- renamed
+    (array_agg(e.batter_name order by g.started_at desc, e.game_event_index desc))[1] as batter_name,
+    (array_agg(e.pitcher_name order by g.started_at desc, e.game_event_index desc))[1] as pitcher_name,
+    count(*) as plate_appearances,
+    count(*) filter (where et.is_hit) as hits,
+    count(*) filter (where et.name='HomeRun') as home_runs,
+    count(*) filter (where et.name='Walk') as walks,
+    count(*) filter (where et.is_strikeout) as strikeouts,
+    count(*) filter (
+        where not (e.batter_identity_confident and e.pitcher_identity_confident)
+    ) as uncertain_plate_appearances
+from data.events e
+join data.games g on g.id=e.game_id
+join taxa.event_type et on et.id=e.event_type
+where et.ends_plate_appearance
+    and e.batter_mmolb_id is not null
+    and e.pitcher_mmolb_id is not null
+group by g.season, e.batter_mmolb_id, e.pitcher_mmolb_id;
+
+create unique index matchup_outcomes_idx
+    on data.matchup_outcomes (batter_mmolb_id, pitcher_mmolb_id, season);
+create index matchup_outcomes_pitcher_idx
+    on data.matchup_outcomes (pitcher_mmolb_id, season);
+
+create materialized view data.player_season_stats as
+with contributions as (
+    -- Plate appearances, credited to the batter
+    select
+        g.season,
+        e.batter_mmolb_id as mmolb_player_id,
+        e.batter_name as player_name,
+        g.started_at,
+        e.game_event_index,
+        1 as plate_appearances,
+        et.is_hit::int as hits,
+        (et.name='HomeRun')::int as home_runs,
+        (et.name='Walk')::int as walks,
+        0 as stolen_bases,
+        0 as strikeouts,
+        0 as outs_recorded,
+        0 as earned_runs,
+        null::float8 as pitch_speed
+    from data.events e
+    join data.games g on g.id=e.game_id
+    join taxa.event_type et on et.id=e.event_type
+    where et.ends_plate_appearance
+        and e.batter_mmolb_id is not null
+    union all
+    -- Every pitch-like event, credited to the pitcher. Outs are counted the
+    -- same way as data.pitcher_appearances counts them.
+    select
+        g.season,
+        e.pitcher_mmolb_id,
+        e.pitcher_name,
+        g.started_at,
+        e.game_event_index,
+        0, 0, 0, 0, 0,
+        (et.ends_plate_appearance and et.is_strikeout)::int,
+        greatest(e.outs_after - e.outs_before, 0),
+        0,
+        e.pitch_speed
+    from data.events e
+    join data.games g on g.id=e.game_id
+    join taxa.event_type et on et.id=e.event_type
+    where e.pitcher_mmolb_id is not null
+    union all
+    -- Successful steals, credited to the batter of the event where the
+    -- runner reached base
+    select
+        g.season,
+        src.batter_mmolb_id,
+        src.batter_name,
+        g.started_at,
+        e.game_event_index,
+        0, 0, 0, 0,
+        1,
+        0, 0, 0,
+        null
+    from data.event_baserunners r
+    join data.events e on e.id=r.event_id
+    join data.games g on g.id=e.game_id
+    join data.events src on src.game_id=e.game_id
+        and src.game_event_index=r.source_event_index
+    where r.steal
+        and not r.is_out
+        and src.batter_mmolb_id is not null
+    union all
+    -- Earned runs, charged to the pitcher of the event where the runner
+    -- reached base
+    select
+        g.season,
+        src.pitcher_mmolb_id,
+        src.pitcher_name,
+        g.started_at,
+        e.game_event_index,
+        0, 0, 0, 0, 0, 0, 0,
+        1,
+        null
+    from data.event_baserunners r
+    join taxa.base b on b.id=r.base_after
+    join data.events e on e.id=r.event_id
+    join data.games g on g.id=e.game_id
+    join data.events src on src.game_id=e.game_id
+        and src.game_event_index=r.source_event_index
+    where b.name='Home'
+        and not r.is_out
+        and r.is_earned
+        and src.pitcher_mmolb_id is not null
+)
+select
+    season,
+    data.mmolb_id_to_text(mmolb_player_id) as mmolb_player_id,
+    -- Name as of the player's latest contribution, since players can be
+    -- renamed
+    (array_agg(player_name order by started_at desc, game_event_index desc))[1] as player_name,
+    sum(plate_appearances)::bigint as plate_appearances,
+    sum(hits)::bigint as hits,
+    sum(home_runs)::bigint as home_runs,
+    sum(walks)::bigint as walks,
+    sum(stolen_bases)::bigint as stolen_bases,
+    sum(strikeouts)::bigint as strikeouts,
+    sum(outs_recorded)::bigint as outs_recorded,
+    sum(earned_runs)::bigint as earned_runs,
+    max(pitch_speed) as max_pitch_speed
+from contributions
+group by season, mmolb_player_id;
+
+create unique index player_season_stats_idx
+    on data.player_season_stats (mmolb_player_id, season);
+create index player_season_stats_season_idx
+    on data.player_season_stats (season);
+
+-- A feature vector for each player, for finding similar players. Features
+-- are the player's current report stars for each attribute and their career
+-- batting and pitching rates, each standardized across all players so no
+-- one feature dominates. A player who's missing a feature (e.g. has never
+-- pitched) gets the average for it.
+create materialized view data.player_similarity_features as
+with raw_features as (
+    select
+        rav.mmolb_player_id,
+        'stars_' || a.name as feature,
+        avg(rav.base_stars)::float8 as value
+    from data.player_report_attribute_versions rav
+    join taxa.attribute a on a.id=rav.attribute
+    where rav.valid_until is null
+        and rav.base_stars is not null
+    group by rav.mmolb_player_id, a.name
+    union all
+    select mmolb_player_id, rate.feature, rate.value
+    from (
+        select
+            mmolb_player_id,
+            sum(plate_appearances) as plate_appearances,
+            sum(hits) as hits,
+            sum(home_runs) as home_runs,
+            sum(walks) as walks,
+            sum(stolen_bases) as stolen_bases,
+            sum(strikeouts) as strikeouts,
+            sum(outs_recorded) as outs_recorded,
+            sum(earned_runs) as earned_runs
+        from data.player_season_stats
+        group by mmolb_player_id
+    ) career
+    cross join lateral (
+        values
+            ('hits_per_pa', career.hits::float8 / nullif(career.plate_appearances, 0)),
+            ('home_runs_per_pa', career.home_runs::float8 / nullif(career.plate_appearances, 0)),
+            ('walks_per_pa', career.walks::float8 / nullif(career.plate_appearances, 0)),
+            ('stolen_bases_per_pa', career.stolen_bases::float8 / nullif(career.plate_appearances, 0)),
+            ('strikeouts_per_out', career.strikeouts::float8 / nullif(career.outs_recorded, 0)),
+            ('earned_runs_per_out', career.earned_runs::float8 / nullif(career.outs_recorded, 0))
+    ) as rate(feature, value)
+    where rate.value is not null
+),
+feature_stats as (
+    select feature, avg(value) as mean, stddev_pop(value) as stddev
+    from raw_features
+    group by feature
+),
+players as (
+    select distinct mmolb_player_id from raw_features
+),
+standardized as (
+    select
+        p.mmolb_player_id,
+        fs.feature,
+        coalesce((rf.value - fs.mean) / nullif(fs.stddev, 0), 0) as value
+    from players p
+    cross join feature_stats fs
+    left join raw_features rf on rf.mmolb_player_id=p.mmolb_player_id
+        and rf.feature=fs.feature
+)
+select
+    mmolb_player_id,
+    -- Every player has every feature, in the same order, so the vectors
+    -- can be compared element by element
+    array_agg(value order by feature) as features,
+    sqrt(sum(value * value)) as norm
+from standardized
+group by mmolb_player_id;
+
+create unique index player_similarity_features_idx
+    on data.player_similarity_features (mmolb_player_id);
+
+-- Each player's most similar players, so a player page doesn't have to
+-- compare the player against every other player. Comparing every pair is
+-- expensive, so this is only refreshed when its inputs change.
+create materialized view data.player_similar_players as
+select
+    target.mmolb_player_id,
+    nearest.rank,
+    nearest.similar_mmolb_player_id,
+    nearest.similarity
+from data.player_similarity_features target
+cross join lateral (
+    select
+        row_number() over (order by s.similarity desc, s.mmolb_player_id) as rank,
+        s.mmolb_player_id as similar_mmolb_player_id,
+        s.similarity
+    from (
+        select
+            other.mmolb_player_id,
+            (
+                select sum(a * b)
+                from unnest(target.features, other.features) as elements(a, b)
+            ) / (target.norm * other.norm) as similarity
+        from data.player_similarity_features other
+        where other.mmolb_player_id<>target.mmolb_player_id
+            and other.norm > 0
+    ) s
+    order by rank
+    -- Keep in sync with SIMILAR_PLAYERS_STORED in mmoldb-db
+    limit 10
+) nearest
+-- A player with exactly average everything has no direction to compare
+where target.norm > 0;
+
+create unique index player_similar_players_idx
+    on data.player_similar_players (mmolb_player_id, rank);
@@ -0,0 +1,119 @@
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome};
+use rocket::{Request, State};
+use serde::Deserialize;
+
+/// Admin settings from Rocket.toml (or `ROCKET_`-prefixed environment
+/// variables). Admin pages are disabled unless `admin_token` is set.
+/// Authenticated API routes are disabled unless `admin_token` or
+/// `api_tokens` is set.
+#[derive(Debug, Default, Deserialize)]
+pub struct AdminConfig {
+    #[serde(default)]
+    pub admin_token: Option<String>,
+    #[serde(default)]
+    pub api_tokens: Vec<String>,
+}
+
+/// Request guard for admin-only routes. Requests must have an
+/// `Authorization: Bearer <admin_token>` header.
+pub struct Admin;
+
+#[derive(Debug)]
+pub enum AdminError {
+    Disabled,
+    MissingToken,
+    WrongToken,
+}
+
+/// Compares without returning early, so response timing doesn't reveal
+/// how much of the token was right
+fn tokens_match(provided: &str, expected: &str) -> bool {
+    provided.len() == expected.len()
+        && provided
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+/// Checks the request's bearer token against every token in `accepted`.
+/// Empty tokens are never accepted.
+fn check_bearer_token<'a>(
+    req: &Request<'_>,
+    accepted: impl IntoIterator<Item = &'a str>,
+) -> Result<(), (Status, AdminError)> {
+    let accepted = accepted
+        .into_iter()
+        .filter(|token| !token.is_empty())
+        .collect::<Vec<_>>();
+    if accepted.is_empty() {
+        // Pretend the routes don't exist if they're disabled
+        return Err((Status::NotFound, AdminError::Disabled));
+    }
+
+    let Some(provided) = req
+        .headers()
+        .get_one("Authorization")
+        .and_then(|header| header.strip_prefix("Bearer "))
+    else {
+        return Err((Status::Unauthorized, AdminError::MissingToken));
+    };
+
+    // Check every token, even after a match, for the same reason as in
+    // tokens_match
+    let matched = accepted
+        .into_iter()
+        .fold(false, |matched, expected| matched | tokens_match(provided, expected));
+    if matched {
+        Ok(())
+    } else {
+        Err((Status::Unauthorized, AdminError::WrongToken))
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Admin {
+    type Error = AdminError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let expected = match req.guard::<&State<AdminConfig>>().await {
+            Outcome::Success(config) => config.admin_token.as_deref(),
+            _ => None,
+        };
+
+        match check_bearer_token(req, expected) {
+            Ok(()) => Outcome::Success(Admin),
+            Err(err) => Outcome::Error(err),
+        }
+    }
+}
+
+/// Request guard for authenticated API routes. Requests must have an
+/// `Authorization: Bearer <token>` header, where the token is one of
+/// `api_tokens` or the `admin_token`.
+pub struct ApiUser;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ApiUser {
+    type Error = AdminError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let config = match req.guard::<&State<AdminConfig>>().await {
+            Outcome::Success(config) => Some(config.inner()),
+            _ => None,
+        };
+        let accepted = config.into_iter().flat_map(|config| {
+            config
+                .api_tokens
+                .iter()
+                .map(String::as_str)
+                .chain(config.admin_token.as_deref())
+        });
+
+        match check_bearer_token(req, accepted) {
+            Ok(()) => Outcome::Success(ApiUser),
+            Err(err) => Outcome::Error(err),
+        }
+    }
+}
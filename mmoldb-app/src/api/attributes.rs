@@ -0,0 +1,183 @@
+use crate::Db;
+use crate::api::error::ApiError;
+use crate::params::{self, MmolbId, ParamError, Season};
+use chrono::{DateTime, NaiveDate, Utc};
+use mmoldb_db::taxa::{Taxa, TaxaAttribute};
+use rocket::serde::Serialize;
+use rocket::serde::json::Json;
+use rocket::{FromForm, State, get};
+use strum::IntoEnumIterator;
+
+#[derive(Serialize)]
+pub struct ApiAttributeDistribution {
+    pub season: i32,
+    /// The Monday of the week this snapshot is for
+    pub week_start: NaiveDate,
+    pub attribute: TaxaAttribute,
+    /// How many players on a team had a value for this attribute
+    pub num_players: i32,
+    pub mean: f64,
+    /// 101 values. `percentiles[n]` is the nth percentile.
+    pub percentiles: Vec<Option<f64>>,
+    pub taken_at: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+pub struct ApiAttributeDistributions {
+    pub distributions: Vec<ApiAttributeDistribution>,
+}
+
+/// Query parameters for [`attribute_distributions`]. These are kept as raw
+/// strings so bad values are reported instead of ignored.
+#[derive(FromForm)]
+pub struct AttributeDistributionsQuery<'r> {
+    season: Option<&'r str>,
+    /// e.g. `2025-11-03`
+    week_start: Option<&'r str>,
+    /// e.g. `Muscle`
+    attribute: Option<&'r str>,
+}
+
+fn parse_attribute(name: &str) -> Result<TaxaAttribute, ParamError> {
+    TaxaAttribute::iter()
+        .find(|attribute| <&str>::from(attribute).eq_ignore_ascii_case(name))
+        .ok_or_else(|| ParamError::InvalidValue {
+            param: "attribute",
+            value: name.to_string(),
+        })
+}
+
+/// Percentiles of each attribute across players who are on a team, as of
+/// each week. With no `season` or `week_start`, returns the latest week.
+#[get("/attribute_distributions?<query..>")]
+pub async fn attribute_distributions(
+    query: AttributeDistributionsQuery<'_>,
+    db: Db,
+    taxa: &State<Taxa>,
+) -> Result<Json<ApiAttributeDistributions>, ApiError> {
+    let season = params::parse_form_field::<Season>("season", query.season)?.map(Season::get);
+    let week_start = params::parse_form_field::<NaiveDate>("week_start", query.week_start)?;
+    let attribute = query
+        .attribute
+        .filter(|name| !name.is_empty())
+        .map(parse_attribute)
+        .transpose()?
+        .map(|attribute| taxa.attribute_id(attribute));
+
+    let rows = db
+        .run(move |conn| {
+            mmoldb_db::db::attribute_distributions(conn, season, week_start, attribute)
+        })
+        .await?;
+
+    Ok(Json(ApiAttributeDistributions {
+        distributions: rows
+            .into_iter()
+            .map(|row| {
+                Ok::<_, ApiError>(ApiAttributeDistribution {
+                    season: row.season,
+                    week_start: row.week_start,
+                    attribute: taxa.attribute_from_id(row.attribute)?,
+                    num_players: row.num_players,
+                    mean: row.mean,
+                    percentiles: row.percentiles,
+                    taken_at: row.taken_at,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+    }))
+}
+
+/// What changed an attribute's value
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiAttributeChangeCause {
+    Augment,
+    ParadigmShift,
+    Recomposition,
+    Equipment,
+}
+
+impl ApiAttributeChangeCause {
+    fn from_db(cause: &str) -> Option<Self> {
+        match cause {
+            "augment" => Some(Self::Augment),
+            "paradigm_shift" => Some(Self::ParadigmShift),
+            "recomposition" => Some(Self::Recomposition),
+            "equipment" => Some(Self::Equipment),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ApiAttributeProgressionPoint {
+    pub time: DateTime<Utc>,
+    pub attribute: TaxaAttribute,
+    pub base_stars: Option<i32>,
+    pub base_total: Option<f64>,
+    pub modified_stars: Option<i32>,
+    pub modified_total: Option<f64>,
+    /// Null for each attribute's first value, and for changes nothing in the
+    /// database explains
+    pub cause: Option<ApiAttributeChangeCause>,
+    /// When the cause happened, which is usually a little before `time`
+    pub cause_time: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize)]
+pub struct ApiAttributeProgression {
+    pub player_id: String,
+    /// Ordered by attribute, then by time
+    pub points: Vec<ApiAttributeProgressionPoint>,
+}
+
+#[derive(FromForm)]
+pub struct AttributeProgressionQuery<'r> {
+    /// e.g. `Muscle`
+    attribute: Option<&'r str>,
+}
+
+/// Every value each of a player's attributes has had, from their reports,
+/// along with what most likely caused each change
+#[get("/players/<player_id>/attributes/progression?<query..>")]
+pub async fn attribute_progression(
+    player_id: Result<MmolbId, ParamError>,
+    query: AttributeProgressionQuery<'_>,
+    db: Db,
+    taxa: &State<Taxa>,
+) -> Result<Json<ApiAttributeProgression>, ApiError> {
+    let player_id = player_id?.into_inner();
+    let attribute = query
+        .attribute
+        .filter(|name| !name.is_empty())
+        .map(parse_attribute)
+        .transpose()?
+        .map(|attribute| taxa.attribute_id(attribute));
+
+    let mmolb_player_id = player_id.clone();
+    let rows = db
+        .run(move |conn| {
+            mmoldb_db::db::attribute_progression(conn, &mmolb_player_id, attribute)
+        })
+        .await?;
+
+    Ok(Json(ApiAttributeProgression {
+        player_id,
+        points: rows
+            .into_iter()
+            .map(|row| {
+                Ok::<_, ApiError>(ApiAttributeProgressionPoint {
+                    time: row.valid_from,
+                    attribute: taxa.attribute_from_id(row.attribute)?,
+                    base_stars: row.base_stars,
+                    base_total: row.base_total,
+                    modified_stars: row.modified_stars,
+                    modified_total: row.modified_total,
+                    cause: row.cause.as_deref().and_then(ApiAttributeChangeCause::from_db),
+                    cause_time: row.cause_time,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+    }))
+}
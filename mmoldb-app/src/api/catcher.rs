@@ -0,0 +1,46 @@
+use crate::Db;
+use crate::api::error::ApiError;
+use crate::params::{self, ParamError, Season};
+use itertools::Itertools;
+use mmoldb_client::types::{ApiCatcherCalledStrikes, ApiSeasonCatcherCalledStrikes};
+use rocket::get;
+use rocket::serde::json::Json;
+
+/// Catchers with fewer taken pitches than this are left out by default,
+/// because their rates are mostly noise
+const DEFAULT_MIN_TAKEN_PITCHES: i64 = 100;
+
+#[get("/catcher_called_strikes/<season>?<min_taken_pitches>")]
+pub async fn catcher_called_strikes(
+    season: Result<Season, ParamError>,
+    min_taken_pitches: Option<&str>,
+    db: Db,
+) -> Result<Json<ApiSeasonCatcherCalledStrikes>, ApiError> {
+    let season = season?.get();
+    let min_taken_pitches =
+        params::parse_form_field("min_taken_pitches", min_taken_pitches)?
+            .unwrap_or(DEFAULT_MIN_TAKEN_PITCHES);
+
+    let rows = db
+        .run(move |conn| {
+            mmoldb_db::db::catcher_called_strikes_for_season(conn, season, min_taken_pitches)
+        })
+        .await?;
+
+    Ok(Json(ApiSeasonCatcherCalledStrikes {
+        season,
+        min_taken_pitches,
+        catchers: rows
+            .into_iter()
+            .map(|row| ApiCatcherCalledStrikes {
+                catcher_id: row.catcher_mmolb_id,
+                catcher_name: row.catcher_name,
+                taken_pitches: row.taken_pitches,
+                called_strikes: row.called_strikes,
+                balls: row.balls,
+                called_strike_rate: row.called_strike_rate,
+                called_strikes_above_expected: row.called_strikes_above_expected,
+            })
+            .collect_vec(),
+    }))
+}
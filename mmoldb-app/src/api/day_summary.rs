@@ -0,0 +1,180 @@
+use crate::Db;
+use crate::api::ejections::api_ejection;
+use crate::api::error::ApiError;
+use itertools::Itertools;
+use mmoldb_client::types::{ApiDayFastestPitch, ApiDayGame, ApiDaySummary};
+use mmoldb_db::db::{self, EjectionFilter};
+use rocket::get;
+use rocket::serde::json::Json;
+use std::fmt::Write;
+
+/// How many ejections to list in a day summary. The count covers all of
+/// them.
+const DAY_SUMMARY_EJECTIONS_LIMIT: i64 = 10;
+
+async fn latest_day_summary(db: Db) -> Result<Option<ApiDaySummary>, ApiError> {
+    db.run(move |conn| {
+        let Some(day) = db::latest_finished_game_day(conn)? else {
+            return Ok(None);
+        };
+
+        let num_games = db::num_games_on_day(conn, &day)?;
+        let biggest_blowout = db::biggest_blowout_on_day(conn, &day)?;
+        let fastest_pitch = db::fastest_pitch_on_day(conn, &day)?;
+        let ejection_filter = EjectionFilter {
+            season: Some(day.season),
+            day: day.day,
+            superstar_day: day.superstar_day,
+            ..Default::default()
+        };
+        let ejection_counts = db::ejection_counts(conn, &ejection_filter)?;
+        let ejections = db::list_ejections(conn, &ejection_filter, DAY_SUMMARY_EJECTIONS_LIMIT)?;
+
+        Ok::<_, ApiError>(Some(ApiDaySummary {
+            season: day.season,
+            day: day.day,
+            superstar_day: day.superstar_day,
+            num_games,
+            biggest_blowout: biggest_blowout.and_then(|game| {
+                Some(ApiDayGame {
+                    away_team_final_score: game.away_team_final_score?,
+                    home_team_final_score: game.home_team_final_score?,
+                    game_id: game.mmolb_game_id,
+                    away_team_id: game.away_team_mmolb_id,
+                    away_team_emoji: game.away_team_emoji,
+                    away_team_name: game.away_team_name,
+                    home_team_id: game.home_team_mmolb_id,
+                    home_team_emoji: game.home_team_emoji,
+                    home_team_name: game.home_team_name,
+                })
+            }),
+            fastest_pitch: fastest_pitch.map(|pitch| ApiDayFastestPitch {
+                game_id: pitch.mmolb_game_id,
+                game_event_index: pitch.game_event_index,
+                player_id: pitch.mmolb_player_id,
+                player_name: pitch.player_name,
+                team_id: pitch.mmolb_team_id,
+                team_emoji: pitch.team_emoji,
+                team_name: format!("{} {}", pitch.team_location, pitch.team_name),
+                pitch_speed: pitch.pitch_speed,
+            }),
+            num_ejections: ejection_counts.ejections,
+            ejections: ejections.into_iter().map(api_ejection).collect_vec(),
+        }))
+    })
+    .await
+}
+
+/// Summary of the latest day whose games have all finished: how many games
+/// were played, the biggest blowout, the fastest pitch, and ejections
+#[get("/day_summary")]
+pub async fn day_summary(db: Db) -> Result<Option<Json<ApiDaySummary>>, ApiError> {
+    Ok(latest_day_summary(db).await?.map(Json))
+}
+
+/// The same summary as [`day_summary`], as plain text that can be posted
+/// to Discord as-is
+#[get("/day_summary/text")]
+pub async fn day_summary_text(db: Db) -> Result<Option<String>, ApiError> {
+    Ok(latest_day_summary(db).await?.as_ref().map(summary_text))
+}
+
+fn summary_text(summary: &ApiDaySummary) -> String {
+    let day = match (summary.day, summary.superstar_day) {
+        (Some(day), _) => format!("Day {day}"),
+        (None, Some(superstar_day)) => format!("Superstar Day {superstar_day}"),
+        (None, None) => "Unknown day".to_string(),
+    };
+
+    // Writing to a String can't fail
+    let mut text = String::new();
+    let games = if summary.num_games == 1 {
+        "game"
+    } else {
+        "games"
+    };
+    writeln!(
+        text,
+        "**Season {}, {day}**: {} {games}",
+        summary.season, summary.num_games
+    )
+    .unwrap();
+
+    if let Some(game) = &summary.biggest_blowout {
+        writeln!(
+            text,
+            "Biggest blowout: {} {} {}, {} {} {}",
+            game.away_team_emoji,
+            game.away_team_name,
+            game.away_team_final_score,
+            game.home_team_emoji,
+            game.home_team_name,
+            game.home_team_final_score,
+        )
+        .unwrap();
+    }
+
+    if let Some(pitch) = &summary.fastest_pitch {
+        writeln!(
+            text,
+            "Fastest pitch: {:.1} mph by {} ({} {})",
+            pitch.pitch_speed, pitch.player_name, pitch.team_emoji, pitch.team_name,
+        )
+        .unwrap();
+    }
+
+    writeln!(text, "Ejections: {}", summary.num_ejections).unwrap();
+    for ejection in &summary.ejections {
+        writeln!(
+            text,
+            "- {} {} {} for {}",
+            ejection.team_emoji,
+            ejection.ejected_player_name,
+            ejection.violation_type,
+            ejection.reason,
+        )
+        .unwrap();
+    }
+    let unlisted = summary.num_ejections - summary.ejections.len() as i64;
+    if unlisted > 0 {
+        writeln!(text, "- ...and {unlisted} more").unwrap();
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary_text_of_a_quiet_superstar_day() {
+        let summary = ApiDaySummary {
+            season: 7,
+            day: None,
+            superstar_day: Some(2),
+            num_games: 1,
+            biggest_blowout: Some(ApiDayGame {
+                game_id: "g".to_string(),
+                away_team_id: "a".to_string(),
+                away_team_emoji: "🐸".to_string(),
+                away_team_name: "Frogs".to_string(),
+                away_team_final_score: 9,
+                home_team_id: "h".to_string(),
+                home_team_emoji: "🦆".to_string(),
+                home_team_name: "Ducks".to_string(),
+                home_team_final_score: 1,
+            }),
+            fastest_pitch: None,
+            num_ejections: 0,
+            ejections: Vec::new(),
+        };
+
+        assert_eq!(
+            summary_text(&summary),
+            "**Season 7, Superstar Day 2**: 1 game\n\
+             Biggest blowout: 🐸 Frogs 9, 🦆 Ducks 1\n\
+             Ejections: 0\n",
+        );
+    }
+}
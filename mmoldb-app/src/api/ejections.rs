@@ -0,0 +1,98 @@
+use crate::Db;
+use crate::api::error::ApiError;
+use crate::params::{self, MmolbId, ParamError, Season};
+use itertools::Itertools;
+use mmoldb_client::types::{ApiEjection, ApiEjections, ApiFailedEjection};
+use mmoldb_db::db::{DbEjectionListing, EjectionFilter};
+use rocket::serde::json::Json;
+use rocket::{FromForm, get};
+
+const DEFAULT_EJECTIONS_LIMIT: i64 = 100;
+const MAX_EJECTIONS_LIMIT: i64 = 1000;
+
+/// Query parameters for [`ejections`]. These are kept as raw strings so
+/// bad values are reported instead of ignored.
+#[derive(FromForm)]
+pub struct EjectionsQuery<'r> {
+    season: Option<&'r str>,
+    team: Option<&'r str>,
+    player: Option<&'r str>,
+    limit: Option<&'r str>,
+}
+
+pub(super) fn api_ejection(row: DbEjectionListing) -> ApiEjection {
+    ApiEjection {
+        game_id: row.mmolb_game_id,
+        season: row.season,
+        day: row.day,
+        superstar_day: row.superstar_day,
+        game_started_at: row.started_at,
+        game_event_index: row.game_event_index,
+        team_id: row.team_mmolb_id,
+        team_emoji: row.team_emoji,
+        team_name: row.team_name,
+        ejected_player_name: row.ejected_player_name,
+        ejected_player_slot: row.ejected_player_slot,
+        violation_type: row.violation_type,
+        reason: row.reason,
+        replacement_player_name: row.replacement_player_name,
+        replacement_player_slot: row.replacement_player_slot,
+    }
+}
+
+/// Ejections and failed ejections, most recent first. `team` limits
+/// ejections to that team's players and failed ejections to that team's
+/// games. `player` matches the ejected player, their replacement, or
+/// either player in a failed ejection. `limit` applies to each list
+/// separately.
+#[get("/ejections?<query..>")]
+pub async fn ejections(query: EjectionsQuery<'_>, db: Db) -> Result<Json<ApiEjections>, ApiError> {
+    let filter = EjectionFilter {
+        season: params::parse_form_field::<Season>("season", query.season)?.map(Season::get),
+        team_mmolb_id: params::parse_form_field::<MmolbId>("team", query.team)?
+            .map(MmolbId::into_inner),
+        player_name: query
+            .player
+            .filter(|name| !name.is_empty())
+            .map(str::to_string),
+        ..Default::default()
+    };
+    let limit = match params::parse_form_field::<i64>("limit", query.limit)? {
+        None => DEFAULT_EJECTIONS_LIMIT,
+        Some(limit) if (1..=MAX_EJECTIONS_LIMIT).contains(&limit) => limit,
+        Some(limit) => {
+            return Err(ParamError::InvalidValue {
+                param: "limit",
+                value: limit.to_string(),
+            }
+            .into());
+        }
+    };
+
+    let (counts, ejections, failed_ejections) = db
+        .run(move |conn| {
+            let counts = mmoldb_db::db::ejection_counts(conn, &filter)?;
+            let ejections = mmoldb_db::db::list_ejections(conn, &filter, limit)?;
+            let failed_ejections = mmoldb_db::db::list_failed_ejections(conn, &filter, limit)?;
+            Ok::<_, ApiError>((counts, ejections, failed_ejections))
+        })
+        .await?;
+
+    Ok(Json(ApiEjections {
+        num_ejections: counts.ejections,
+        num_failed_ejections: counts.failed_ejections,
+        ejections: ejections.into_iter().map(api_ejection).collect_vec(),
+        failed_ejections: failed_ejections
+            .into_iter()
+            .map(|row| ApiFailedEjection {
+                game_id: row.mmolb_game_id,
+                season: row.season,
+                day: row.day,
+                superstar_day: row.superstar_day,
+                game_started_at: row.started_at,
+                game_event_index: row.game_event_index,
+                player_names: [row.player_name_1, row.player_name_2],
+            })
+            .collect_vec(),
+    }))
+}
@@ -0,0 +1,433 @@
+use crate::Db;
+use crate::api::error::ApiError;
+use crate::params::{MmolbId, ParamError};
+use chrono::{DateTime, Utc};
+use hashbrown::HashMap;
+use itertools::Itertools;
+use mmoldb_db::models::{DbPlayerEquipmentEffectVersion, DbPlayerEquipmentVersion};
+use mmoldb_db::taxa::{Taxa, TaxaAttribute, TaxaEffectType, UnknownTaxaId};
+use rocket::serde::Serialize;
+use rocket::serde::json::Json;
+use rocket::{State, get};
+
+/// The combined value of all of an item's effects on one attribute, over
+/// the course of a wear period
+#[derive(Serialize)]
+pub struct ApiEquipmentEffectTotal {
+    pub attribute: TaxaAttribute,
+    pub effect_type: TaxaEffectType,
+    pub initial_value: f64,
+    pub final_value: f64,
+    pub min_value: f64,
+    pub max_value: f64,
+}
+
+/// A span of time that a player wore the same item in the same slot,
+/// within a single season
+#[derive(Serialize)]
+pub struct ApiEquipmentWearPeriod {
+    pub equipment_slot: String,
+    /// The season whose first game was the latest one before `start`, so
+    /// the offseason counts as part of the season before it. Null for
+    /// periods from before the first game.
+    pub season: Option<i32>,
+    pub start: DateTime<Utc>,
+    /// Null if the player is still wearing the item
+    pub end: Option<DateTime<Utc>>,
+    pub emoji: String,
+    pub name: String,
+    pub special_type: Option<String>,
+    pub rare_name: Option<String>,
+    pub prefixes: Vec<Option<String>>,
+    pub suffixes: Vec<Option<String>>,
+    pub rarity: Option<String>,
+    /// How many rows of `data.player_equipment_versions` were merged into
+    /// this wear period. A period that spans seasons is split after
+    /// merging, so each part has the same count.
+    pub num_versions: usize,
+    pub effect_totals: Vec<ApiEquipmentEffectTotal>,
+}
+
+#[derive(Serialize)]
+pub struct ApiEquipmentTimeline {
+    pub player_id: String,
+    /// Ordered by start time, then by slot
+    pub periods: Vec<ApiEquipmentWearPeriod>,
+}
+
+/// Consecutive equipment versions for the same slot that describe the same
+/// item
+struct WearPeriod<'a> {
+    item: &'a DbPlayerEquipmentVersion,
+    start: DateTime<Utc>,
+    end: Option<DateTime<Utc>>,
+    num_versions: usize,
+}
+
+/// Whether two equipment versions are the same item. Other fields, like
+/// durability, can change while the item is being worn.
+fn is_same_item(a: &DbPlayerEquipmentVersion, b: &DbPlayerEquipmentVersion) -> bool {
+    a.emoji == b.emoji
+        && a.name == b.name
+        && a.special_type == b.special_type
+        && a.rare_name == b.rare_name
+        && a.prefixes == b.prefixes
+        && a.suffixes == b.suffixes
+        && a.rarity == b.rarity
+}
+
+/// `versions` must be ordered by `valid_from`
+fn merge_wear_periods(versions: &[DbPlayerEquipmentVersion]) -> Vec<WearPeriod<'_>> {
+    let mut open_periods: HashMap<&str, WearPeriod> = HashMap::new();
+    let mut periods = Vec::new();
+
+    for version in versions {
+        let continues = open_periods
+            .get(version.equipment_slot.as_str())
+            .is_some_and(|period| {
+                period.end == Some(version.valid_from) && is_same_item(period.item, version)
+            });
+
+        if continues {
+            let period = open_periods
+                .get_mut(version.equipment_slot.as_str())
+                .expect("Period was just found");
+            period.end = version.valid_until;
+            period.num_versions += 1;
+        } else {
+            let new_period = WearPeriod {
+                item: version,
+                start: version.valid_from,
+                end: version.valid_until,
+                num_versions: 1,
+            };
+            if let Some(finished) = open_periods.insert(&version.equipment_slot, new_period) {
+                periods.push(finished);
+            }
+        }
+    }
+
+    periods.extend(open_periods.into_values());
+    periods
+}
+
+/// Splits the span from `start` to `end` wherever a season starts.
+/// `season_starts` must be ordered.
+fn split_by_season(
+    start: DateTime<Utc>,
+    end: Option<DateTime<Utc>>,
+    season_starts: &[(i32, DateTime<Utc>)],
+) -> Vec<(Option<i32>, DateTime<Utc>, Option<DateTime<Utc>>)> {
+    let mut season = season_starts
+        .iter()
+        .take_while(|(_, season_start)| *season_start <= start)
+        .last()
+        .map(|(season, _)| *season);
+    let mut piece_start = start;
+    let mut pieces = Vec::new();
+
+    for (next_season, season_start) in season_starts.iter().filter(|(_, season_start)| {
+        start < *season_start && end.is_none_or(|end| *season_start < end)
+    }) {
+        pieces.push((season, piece_start, Some(*season_start)));
+        season = Some(*next_season);
+        piece_start = *season_start;
+    }
+    pieces.push((season, piece_start, end));
+
+    pieces
+}
+
+/// Totals the effects of the item in `slot` at the start of the span and at
+/// every point within it where one of the effects changed
+fn effect_totals(
+    taxa: &Taxa,
+    effects: &[DbPlayerEquipmentEffectVersion],
+    slot: &str,
+    start: DateTime<Utc>,
+    end: Option<DateTime<Utc>>,
+) -> Result<Vec<ApiEquipmentEffectTotal>, UnknownTaxaId> {
+    let is_valid_at = |effect: &DbPlayerEquipmentEffectVersion, time: DateTime<Utc>| {
+        effect.valid_from <= time && effect.valid_until.is_none_or(|until| time < until)
+    };
+
+    let effects = effects
+        .iter()
+        .filter(|effect| {
+            effect.equipment_slot == slot
+                && end.is_none_or(|end| effect.valid_from < end)
+                && effect.valid_until.is_none_or(|until| start < until)
+        })
+        .collect_vec();
+
+    let change_points = std::iter::once(start)
+        .chain(
+            effects
+                .iter()
+                .map(|effect| effect.valid_from)
+                .filter(|time| start < *time),
+        )
+        .sorted()
+        .dedup()
+        .collect_vec();
+
+    let totals_at_each_point = change_points
+        .iter()
+        .map(|time| {
+            let mut totals: HashMap<(i64, i64), f64> = HashMap::new();
+            for effect in effects.iter().filter(|effect| is_valid_at(effect, *time)) {
+                *totals
+                    .entry((effect.attribute, effect.effect_type))
+                    .or_default() += effect.value;
+            }
+            totals
+        })
+        .collect_vec();
+
+    effects
+        .iter()
+        .map(|effect| (effect.attribute, effect.effect_type))
+        .unique()
+        .sorted()
+        .map(|key| {
+            // An attribute the item didn't affect at some point counts as 0
+            let values = totals_at_each_point
+                .iter()
+                .map(|totals| totals.get(&key).copied().unwrap_or(0.0))
+                .collect_vec();
+            let (attribute, effect_type) = key;
+            Ok(ApiEquipmentEffectTotal {
+                attribute: taxa.attribute_from_id(attribute)?,
+                effect_type: taxa.effect_type_from_id(effect_type)?,
+                initial_value: values.first().copied().unwrap_or(0.0),
+                final_value: values.last().copied().unwrap_or(0.0),
+                min_value: values.iter().copied().fold(f64::INFINITY, f64::min),
+                max_value: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            })
+        })
+        .collect()
+}
+
+/// Every item a player has worn, merged from the raw equipment versions
+/// into one period per item per season
+#[get("/players/<player_id>/equipment/timeline")]
+pub async fn player_equipment_timeline(
+    player_id: Result<MmolbId, ParamError>,
+    db: Db,
+    taxa: &State<Taxa>,
+) -> Result<Json<ApiEquipmentTimeline>, ApiError> {
+    let player_id = player_id?.into_inner();
+    let mmolb_player_id = player_id.clone();
+    let (equipment_versions, effect_versions, season_starts) = db
+        .run(move |conn| {
+            let equipment_versions =
+                mmoldb_db::db::get_player_equipment_versions(conn, &mmolb_player_id)?;
+            let effect_versions =
+                mmoldb_db::db::get_player_equipment_effect_versions(conn, &mmolb_player_id)?;
+            let season_starts = mmoldb_db::db::season_start_times(conn)?;
+            Ok::<_, ApiError>((equipment_versions, effect_versions, season_starts))
+        })
+        .await?;
+
+    let periods = merge_wear_periods(&equipment_versions)
+        .into_iter()
+        .flat_map(|period| {
+            split_by_season(period.start, period.end, &season_starts)
+                .into_iter()
+                .map(move |(season, start, end)| {
+                    (period.item, period.num_versions, season, start, end)
+                })
+        })
+        .sorted_by(|(a_item, _, _, a_start, _), (b_item, _, _, b_start, _)| {
+            a_start
+                .cmp(b_start)
+                .then_with(|| a_item.equipment_slot.cmp(&b_item.equipment_slot))
+        })
+        .map(|(item, num_versions, season, start, end)| {
+            Ok::<_, UnknownTaxaId>(ApiEquipmentWearPeriod {
+                equipment_slot: item.equipment_slot.clone(),
+                season,
+                start,
+                end,
+                emoji: item.emoji.clone(),
+                name: item.name.clone(),
+                special_type: item.special_type.clone(),
+                rare_name: item.rare_name.clone(),
+                prefixes: item.prefixes.clone(),
+                suffixes: item.suffixes.clone(),
+                rarity: item.rarity.clone(),
+                num_versions,
+                effect_totals: effect_totals(
+                    taxa,
+                    &effect_versions,
+                    &item.equipment_slot,
+                    start,
+                    end,
+                )?,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Json(ApiEquipmentTimeline { player_id, periods }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use mmoldb_db::{Connection, PgConnection, QueryError, postgres_url_from_environment};
+
+    fn day(d: u32) -> DateTime<Utc> {
+        NaiveDate::from_ymd_opt(2025, 6, d)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc()
+    }
+
+    fn item(
+        slot: &str,
+        name: &str,
+        valid_from: DateTime<Utc>,
+        valid_until: Option<DateTime<Utc>>,
+    ) -> DbPlayerEquipmentVersion {
+        DbPlayerEquipmentVersion {
+            id: 0,
+            mmolb_player_id: "player".to_string(),
+            equipment_slot: slot.to_string(),
+            valid_from,
+            valid_until,
+            emoji: "🧢".to_string(),
+            name: name.to_string(),
+            special_type: None,
+            description: None,
+            rare_name: None,
+            cost: None,
+            prefixes: Vec::new(),
+            suffixes: Vec::new(),
+            rarity: None,
+            num_effects: 0,
+            durability: None,
+            prefix_position_type: None,
+            specialized: None,
+            corrupted: false,
+        }
+    }
+
+    fn effect(
+        attribute: i64,
+        effect_type: i64,
+        value: f64,
+        valid_from: DateTime<Utc>,
+        valid_until: Option<DateTime<Utc>>,
+    ) -> DbPlayerEquipmentEffectVersion {
+        DbPlayerEquipmentEffectVersion {
+            id: 0,
+            mmolb_player_id: "player".to_string(),
+            equipment_slot: "Head".to_string(),
+            effect_index: 0,
+            valid_from,
+            valid_until,
+            attribute,
+            effect_type,
+            value,
+            tier: None,
+            implicit: false,
+            zone: None,
+            phase: None,
+        }
+    }
+
+    #[test]
+    fn consecutive_versions_of_the_same_item_are_merged() {
+        let mut worn_down = item("Head", "Cap", day(2), Some(day(3)));
+        worn_down.durability = Some(1);
+        let versions = [
+            item("Head", "Cap", day(1), Some(day(2))),
+            item("Body", "Jersey", day(1), None),
+            worn_down,
+            item("Head", "Helmet", day(3), Some(day(5))),
+            // The same item again, after a gap, is a new period
+            item("Head", "Helmet", day(6), None),
+        ];
+
+        let periods = merge_wear_periods(&versions)
+            .into_iter()
+            .map(|p| (p.item.name.as_str(), p.start, p.end, p.num_versions))
+            .sorted()
+            .collect_vec();
+        assert_eq!(
+            periods,
+            [
+                ("Cap", day(1), Some(day(3)), 2),
+                ("Helmet", day(3), Some(day(5)), 1),
+                ("Helmet", day(6), None, 1),
+                ("Jersey", day(1), None, 1),
+            ],
+        );
+    }
+
+    #[test]
+    fn spans_are_split_where_each_season_starts() {
+        let season_starts = [(1, day(2)), (2, day(5)), (3, day(9))];
+
+        assert_eq!(
+            split_by_season(day(1), Some(day(7)), &season_starts),
+            [
+                (None, day(1), Some(day(2))),
+                (Some(1), day(2), Some(day(5))),
+                (Some(2), day(5), Some(day(7))),
+            ],
+        );
+        // A span that ends right as a season starts isn't split
+        assert_eq!(
+            split_by_season(day(3), Some(day(5)), &season_starts),
+            [(Some(1), day(3), Some(day(5)))],
+        );
+        // An open span runs through every later season
+        assert_eq!(
+            split_by_season(day(6), None, &season_starts),
+            [(Some(2), day(6), Some(day(9))), (Some(3), day(9), None)],
+        );
+    }
+
+    #[test]
+    fn effect_totals_track_changes_within_the_span() {
+        let url = postgres_url_from_environment();
+        let mut conn = PgConnection::establish(&url)
+            .expect("postgres_url_from_environment should return a valid connection string");
+
+        conn.test_transaction(|conn| {
+            let taxa = Taxa::new(conn)?;
+            let contact = taxa.attribute_id(TaxaAttribute::Contact);
+            let flat = taxa.effect_type_id(TaxaEffectType::Flat);
+
+            let effects = [
+                effect(contact, flat, 10.0, day(1), Some(day(3))),
+                effect(contact, flat, 5.0, day(2), None),
+                effect(contact, flat, 20.0, day(3), None),
+                // Ends before the span starts
+                effect(contact, flat, 100.0, day(1), Some(day(2))),
+            ];
+
+            let totals = effect_totals(&taxa, &effects, "Head", day(2), None).unwrap();
+            assert_eq!(totals.len(), 1);
+            let total = &totals[0];
+            assert_eq!(total.attribute, TaxaAttribute::Contact);
+            assert_eq!(total.effect_type, TaxaEffectType::Flat);
+            assert_eq!(total.initial_value, 15.0);
+            assert_eq!(total.final_value, 25.0);
+            assert_eq!(total.min_value, 15.0);
+            assert_eq!(total.max_value, 25.0);
+
+            // Other slots' effects are left out
+            assert!(
+                effect_totals(&taxa, &effects, "Body", day(2), None)
+                    .unwrap()
+                    .is_empty()
+            );
+
+            Ok::<_, QueryError>(())
+        });
+    }
+}
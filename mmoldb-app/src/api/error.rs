@@ -1,4 +1,8 @@
+use crate::params::ParamError;
 use miette::Diagnostic;
+use mmoldb_db::ObjectStoreError;
+use mmoldb_db::db::{EventFilterError, ExportError, FilterEventsError};
+use mmoldb_db::taxa::UnknownTaxaId;
 use rocket::http::Status;
 use rocket::response::Responder;
 use rocket::{Request, Response};
@@ -8,6 +12,54 @@ use thiserror::Error;
 pub enum ApiError {
     #[error(transparent)]
     DbError(#[from] diesel::result::Error),
+
+    #[error(transparent)]
+    BadRequest(#[from] ParamError),
+
+    #[error(transparent)]
+    ExportError(#[from] ExportError),
+
+    #[error(transparent)]
+    EventFilterError(#[from] EventFilterError),
+
+    #[error(transparent)]
+    UnknownTaxaId(#[from] UnknownTaxaId),
+
+    #[error(
+        "The raw data for this game has been moved to object storage ({0}), which this server \
+        isn't configured to read."
+    )]
+    GameOffloaded(String),
+
+    #[error(transparent)]
+    ObjectStoreError(#[from] ObjectStoreError),
+}
+
+impl From<FilterEventsError> for ApiError {
+    fn from(err: FilterEventsError) -> Self {
+        match err {
+            FilterEventsError::Db(err) => ApiError::DbError(err),
+            FilterEventsError::Filter(err) => ApiError::EventFilterError(err),
+        }
+    }
+}
+
+impl ApiError {
+    fn status(&self) -> Status {
+        match self {
+            ApiError::BadRequest(_) => Status::BadRequest,
+            ApiError::DbError(_) => Status::InternalServerError,
+            ApiError::ExportError(ExportError::Db(_) | ExportError::Copy(_)) => {
+                Status::InternalServerError
+            }
+            ApiError::ExportError(ExportError::UnknownTable { .. }) => Status::NotFound,
+            ApiError::ExportError(_) => Status::BadRequest,
+            ApiError::EventFilterError(_) => Status::BadRequest,
+            ApiError::UnknownTaxaId(_) => Status::InternalServerError,
+            ApiError::GameOffloaded(_) => Status::Gone,
+            ApiError::ObjectStoreError(_) => Status::BadGateway,
+        }
+    }
 }
 
 impl<'r, 'o: 'r> Responder<'r, 'o> for ApiError {
@@ -15,7 +67,7 @@ impl<'r, 'o: 'r> Responder<'r, 'o> for ApiError {
         let rendered = self.to_string();
 
         Response::build()
-            .status(Status::InternalServerError)
+            .status(self.status())
             .header(rocket::http::ContentType::JSON)
             .sized_body(rendered.len(), std::io::Cursor::new(rendered))
             .ok()
@@ -0,0 +1,141 @@
+use crate::Db;
+use crate::api::error::ApiError;
+use crate::params::{self, ParamError};
+use itertools::Itertools;
+use mmoldb_client::types::{ApiEvent, ApiEvents};
+use mmoldb_db::db::{self, DbFilteredEvent, EventFilter, taxa_filter_name};
+use mmoldb_db::taxa::Taxa;
+use rocket::request::{FromRequest, Outcome};
+use rocket::serde::json::Json;
+use rocket::{Request, State, get};
+use std::convert::Infallible;
+
+const DEFAULT_EVENTS_LIMIT: i64 = 100;
+const MAX_EVENTS_LIMIT: i64 = 1000;
+
+/// Every query parameter of a request, in order. Event filters can have
+/// any field name, and their operators end up in the parameter names, so
+/// they can't be declared in the route.
+pub struct RawQuery(Vec<(String, String)>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for RawQuery {
+    type Error = Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let params = match req.uri().query() {
+            Some(query) => query
+                .segments()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            None => Vec::new(),
+        };
+
+        Outcome::Success(RawQuery(params))
+    }
+}
+
+fn api_event(taxa: &Taxa, row: DbFilteredEvent) -> ApiEvent {
+    let event = row.event;
+    ApiEvent {
+        id: event.id,
+        game_id: row.mmolb_game_id,
+        season: row.season,
+        day: row.day,
+        superstar_day: row.superstar_day,
+        game_event_index: event.game_event_index,
+        inning: event.inning,
+        top_of_inning: event.top_of_inning,
+        event_type: taxa
+            .event_type_from_id(event.event_type)
+            .map(taxa_filter_name),
+        hit_base: event
+            .hit_base
+            .and_then(|id| taxa.base_from_id(id).ok())
+            .map(taxa_filter_name),
+        fair_ball_type: event
+            .fair_ball_type
+            .and_then(|id| taxa.fair_ball_type_from_id(id).ok())
+            .map(taxa_filter_name),
+        fair_ball_direction: event
+            .fair_ball_direction
+            .and_then(|id| taxa.fielder_location_from_id(id).ok())
+            .map(taxa_filter_name),
+        fielding_error_type: event
+            .fielding_error_type
+            .and_then(|id| taxa.fielding_error_type_from_id(id).ok())
+            .map(taxa_filter_name),
+        pitch_type: event
+            .pitch_type
+            .and_then(|id| taxa.pitch_type_from_id(id).ok())
+            .map(taxa_filter_name),
+        pitch_speed: event.pitch_speed,
+        pitch_zone: event.pitch_zone,
+        balls_before: event.balls_before,
+        strikes_before: event.strikes_before,
+        outs_before: event.outs_before,
+        outs_after: event.outs_after,
+        away_team_score_before: event.away_team_score_before,
+        away_team_score_after: event.away_team_score_after,
+        home_team_score_before: event.home_team_score_before,
+        home_team_score_after: event.home_team_score_after,
+        home_run_distance: event.home_run_distance,
+        described_as_sacrifice: event.described_as_sacrifice,
+        is_toasty: event.is_toasty,
+        pitcher_name: event.pitcher_name,
+        batter_name: event.batter_name,
+    }
+}
+
+/// Events matching every filter in the query string, in id order. Filters
+/// are written `field=value`, `field!=value`, `field<value`,
+/// `field<=value`, `field>value`, or `field>=value`, e.g.
+/// `?season=5&event_type=home_run&inning>=9&pitch_speed>=100`. One of them
+/// has to be `season=` or `game_id=`. `after` and `limit` page through the
+/// results.
+#[get("/events")]
+pub async fn events(
+    query: RawQuery,
+    taxa: &State<Taxa>,
+    db: Db,
+) -> Result<Json<ApiEvents>, ApiError> {
+    let mut after = None;
+    let mut limit = DEFAULT_EVENTS_LIMIT;
+    let mut filters = Vec::new();
+    for (name, value) in &query.0 {
+        match name.as_str() {
+            "after" => after = params::parse_form_field::<i64>("after", Some(value))?,
+            "limit" => {
+                limit = match params::parse_form_field::<i64>("limit", Some(value))? {
+                    None => DEFAULT_EVENTS_LIMIT,
+                    Some(limit) if (1..=MAX_EVENTS_LIMIT).contains(&limit) => limit,
+                    Some(limit) => {
+                        return Err(ParamError::InvalidValue {
+                            param: "limit",
+                            value: limit.to_string(),
+                        }
+                        .into());
+                    }
+                }
+            }
+            _ => filters.push(EventFilter::parse(name, value)?),
+        }
+    }
+
+    let taxa = taxa.inner().clone();
+    let (taxa, page) = db
+        .run(move |conn| {
+            let page = db::filter_events(conn, &taxa, &filters, after, limit)?;
+            Ok::<_, ApiError>((taxa, page))
+        })
+        .await?;
+
+    Ok(Json(ApiEvents {
+        events: page
+            .items
+            .into_iter()
+            .map(|row| api_event(&taxa, row))
+            .collect_vec(),
+        next_after: page.next_page,
+    }))
+}
@@ -0,0 +1,266 @@
+use crate::Db;
+use crate::admin::ApiUser;
+use crate::api::error::ApiError;
+use crate::params::{self, ParamError, parse_optional};
+use chrono::{DateTime, Utc};
+use itertools::Itertools;
+use log::error;
+use mmoldb_db::db::{self, ExportError, ExportFilter};
+use mmoldb_db::{async_db, postgres_url_from_environment};
+use rocket::fs::NamedFile;
+use rocket::http::{ContentType, Header};
+use rocket::response::Responder;
+use rocket::response::stream::TextStream;
+use rocket::serde::json::json;
+use rocket::{Request, Response, State, get};
+use serde::Deserialize;
+use std::path::PathBuf;
+
+const DEFAULT_EXPORT_PAGE_SIZE: i64 = 10_000;
+const MAX_EXPORT_PAGE_SIZE: i64 = 100_000;
+
+/// Feed events can be large, so they're fetched in smaller pages
+const FEED_EXPORT_PAGE_SIZE: i64 = 1_000;
+
+/// Filters look like `column.op.value`, e.g. `season.gte.3`. The value may
+/// contain dots.
+fn parse_filter(filter: &str) -> Result<ExportFilter, ParamError> {
+    let invalid = || ParamError::InvalidValue {
+        param: "filter",
+        value: filter.to_string(),
+    };
+
+    let (column, op, value) = filter
+        .splitn(3, '.')
+        .collect_tuple()
+        .ok_or_else(invalid)?;
+
+    Ok(ExportFilter {
+        column: column.to_string(),
+        op: op.parse().map_err(|_| invalid())?,
+        value: value.to_string(),
+    })
+}
+
+/// One page of a CSV export. When there are more pages, the cursor for the
+/// next one is in the `X-Next-After` header.
+pub struct CsvPage {
+    csv: Vec<u8>,
+    next_after: Option<String>,
+}
+
+impl<'r, 'o: 'r> Responder<'r, 'o> for CsvPage {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'o> {
+        let mut response = Response::build_from(self.csv.respond_to(req)?);
+        response.header(ContentType::CSV);
+        if let Some(next_after) = self.next_after {
+            response.header(Header::new("X-Next-After", next_after));
+        }
+        response.ok()
+    }
+}
+
+/// Exports a table as CSV, one page at a time, in primary key order.
+/// `columns` is a comma-separated list of columns to include (default all).
+/// Each `filter` is `column.op.value`, where op is one of `eq`, `ne`, `lt`,
+/// `lte`, `gt`, or `gte`, and only indexed columns can be filtered on.
+/// `limit` is the number of rows per page, and `after` is the cursor from
+/// the previous page's `X-Next-After` header.
+#[get("/export/<schema>/<file>?<columns>&<filter>&<after>&<limit>")]
+pub async fn export_csv(
+    _user: ApiUser,
+    schema: &str,
+    file: &str,
+    columns: Option<&str>,
+    filter: Vec<&str>,
+    after: Option<String>,
+    limit: Option<&str>,
+    db: Db,
+) -> Result<CsvPage, ApiError> {
+    let table = file
+        .strip_suffix(".csv")
+        .ok_or_else(|| ParamError::InvalidValue {
+            param: "file",
+            value: file.to_string(),
+        })?
+        .to_string();
+    let schema = schema.to_string();
+    let columns = columns.map(|c| c.split(',').map(str::to_string).collect_vec());
+    let filters = filter
+        .into_iter()
+        .map(parse_filter)
+        .collect::<Result<Vec<_>, _>>()?;
+    let limit = match params::parse_form_field::<i64>("limit", limit)? {
+        None => DEFAULT_EXPORT_PAGE_SIZE,
+        Some(limit) if (1..=MAX_EXPORT_PAGE_SIZE).contains(&limit) => limit,
+        Some(limit) => {
+            return Err(ParamError::InvalidValue {
+                param: "limit",
+                value: limit.to_string(),
+            }
+            .into());
+        }
+    };
+
+    let page = db
+        .run(move |conn| {
+            let plan = db::plan_export(conn, &schema, &table, columns, filters)?;
+            db::plan_csv_page(conn, &plan, after.as_deref(), limit)
+        })
+        .await?;
+
+    // The whole page is copied before responding, so a failure partway
+    // through is an error response rather than a truncated file
+    let csv = async_db::copy_out(&postgres_url_from_environment(), &page.copy_sql)
+        .await
+        .map_err(ExportError::from)?;
+
+    Ok(CsvPage {
+        csv,
+        next_after: page.next_page,
+    })
+}
+
+/// Analytics export settings from Rocket.toml (or `ROCKET_`-prefixed
+/// environment variables)
+#[derive(Debug, Default, Deserialize)]
+pub struct AnalyticsExportConfig {
+    /// Where mmoldb-ingest writes its SQLite analytics export. The export
+    /// can't be downloaded unless this is set.
+    #[serde(default)]
+    pub analytics_export_path: Option<PathBuf>,
+}
+
+/// Downloads the SQLite file written by mmoldb-ingest's analytics export.
+/// Returns 404 if the export isn't configured or hasn't been written yet.
+#[get("/export/analytics.sqlite")]
+pub async fn export_analytics_sqlite(config: &State<AnalyticsExportConfig>) -> Option<NamedFile> {
+    let path = config.analytics_export_path.as_ref()?;
+    NamedFile::open(path).await.ok()
+}
+
+fn parse_timestamp(
+    param: &'static str,
+    value: Option<&str>,
+) -> Result<Option<DateTime<Utc>>, ParamError> {
+    parse_optional::<DateTime<Utc>>(value).map_err(|_| ParamError::InvalidValue {
+        param,
+        value: value.unwrap_or_default().to_string(),
+    })
+}
+
+/// The last line of a feed export that failed partway through. By then the
+/// response has already started, so the failure can't be reported through
+/// the status code. Every other line is a feed event, which never has an
+/// `error` key.
+fn feed_export_error_line(err: impl std::fmt::Display) -> String {
+    let mut line = json!({ "error": err.to_string() }).to_string();
+    line.push('\n');
+    line
+}
+
+/// Streams every version of every raw feed event of one kind as
+/// newline-delimited JSON, oldest first. `since` and `until` are RFC 3339
+/// timestamps that limit the versions to ones first observed in
+/// `[since, until)`.
+///
+/// If the export fails after the response has started, the stream ends with
+/// a `{"error": "..."}` line (see [`feed_export_error_line`]) instead of
+/// stopping silently, so a client can tell a failed export from a complete
+/// one.
+///
+/// This is ranked ahead of [`export_csv`] so `feed` isn't mistaken for a
+/// schema name.
+#[get("/export/feed/<file>?<since>&<until>", rank = 1)]
+pub async fn export_feed_ndjson(
+    file: &str,
+    since: Option<&str>,
+    until: Option<&str>,
+    db: Db,
+) -> Result<(ContentType, TextStream![String]), ApiError> {
+    let kind = file
+        .strip_suffix(".ndjson")
+        .ok_or_else(|| ParamError::InvalidValue {
+            param: "file",
+            value: file.to_string(),
+        })?
+        .to_string();
+    let since = parse_timestamp("since", since)?;
+    let until = parse_timestamp("until", until)?;
+
+    // Fetch the first page before starting the stream so that a bad kind
+    // is reported as an error instead of an empty stream
+    let first_kind = kind.clone();
+    let first_page = db
+        .run(move |conn| {
+            db::export_feed_events_page(
+                conn,
+                &first_kind,
+                since,
+                until,
+                None,
+                FEED_EXPORT_PAGE_SIZE,
+            )
+        })
+        .await?;
+
+    let stream = TextStream! {
+        let mut page = first_page;
+        loop {
+            let mut lines = String::new();
+            let mut failed = false;
+            for event in &page {
+                match rocket::serde::json::to_string(event) {
+                    Ok(line) => {
+                        lines.push_str(&line);
+                        lines.push('\n');
+                    }
+                    Err(err) => {
+                        error!("Error serializing feed event: {err}");
+                        lines.push_str(&feed_export_error_line(format!(
+                            "error serializing feed event: {err}"
+                        )));
+                        failed = true;
+                        break;
+                    }
+                }
+            }
+            yield lines;
+            if failed {
+                break;
+            }
+
+            if (page.len() as i64) < FEED_EXPORT_PAGE_SIZE {
+                break;
+            }
+            let Some(after) = page.last().map(|event| event.cursor()) else {
+                break;
+            };
+
+            let page_kind = kind.clone();
+            let next_page = db
+                .run(move |conn| {
+                    db::export_feed_events_page(
+                        conn,
+                        &page_kind,
+                        since,
+                        until,
+                        Some(&after),
+                        FEED_EXPORT_PAGE_SIZE,
+                    )
+                })
+                .await;
+
+            match next_page {
+                Ok(next_page) => page = next_page,
+                Err(err) => {
+                    error!("Error exporting feed events page: {err}");
+                    yield feed_export_error_line(format!("error exporting feed events: {err}"));
+                    break;
+                }
+            }
+        }
+    };
+
+    Ok((ContentType::new("application", "x-ndjson"), stream))
+}
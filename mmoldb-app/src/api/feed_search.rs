@@ -0,0 +1,135 @@
+use crate::Db;
+use crate::api::error::ApiError;
+use crate::params::{self, MmolbId, ParamError, Season};
+use chrono::{DateTime, Utc};
+use mmoldb_db::db::{self, FeedEventTextSearch};
+use rocket::serde::Serialize;
+use rocket::serde::json::Json;
+use rocket::{FromForm, get};
+
+const DEFAULT_SEARCH_LIMIT: i64 = 50;
+const MAX_SEARCH_LIMIT: i64 = 500;
+
+#[derive(Serialize)]
+pub struct ApiFeedEventMatch {
+    pub kind: String,
+    pub entity_id: String,
+    pub feed_event_index: i32,
+    pub season: i32,
+    pub time: DateTime<Utc>,
+    pub text: String,
+}
+
+#[derive(Serialize)]
+pub struct ApiFeedEventSearchPage {
+    pub matches: Vec<ApiFeedEventMatch>,
+    /// Pass this as `offset` to get the next page. Null on the last page.
+    pub next_offset: Option<i64>,
+}
+
+/// Query parameters for feed event search. These are kept as raw strings
+/// so bad values are reported instead of ignored.
+#[derive(FromForm)]
+pub struct FeedSearchQuery<'r> {
+    q: Option<&'r str>,
+    kind: Option<&'r str>,
+    entity_id: Option<&'r str>,
+    season: Option<&'r str>,
+    offset: Option<&'r str>,
+    limit: Option<&'r str>,
+}
+
+impl FeedSearchQuery<'_> {
+    fn to_search(&self) -> Result<FeedEventTextSearch, ParamError> {
+        let query =
+            self.q
+                .filter(|q| !q.trim().is_empty())
+                .ok_or_else(|| ParamError::InvalidValue {
+                    param: "q",
+                    value: self.q.unwrap_or_default().to_string(),
+                })?;
+        let kind = match self.kind.filter(|kind| !kind.is_empty()) {
+            None => None,
+            Some(kind @ ("player_feed" | "team_feed")) => Some(kind.to_string()),
+            Some(kind) => {
+                return Err(ParamError::InvalidValue {
+                    param: "kind",
+                    value: kind.to_string(),
+                });
+            }
+        };
+        let entity_id = params::parse_form_field::<MmolbId>("entity_id", self.entity_id)?;
+        let season = params::parse_form_field::<Season>("season", self.season)?.map(Season::get);
+        let offset = match params::parse_form_field::<i64>("offset", self.offset)? {
+            None => 0,
+            Some(offset) if offset >= 0 => offset,
+            Some(offset) => {
+                return Err(ParamError::InvalidValue {
+                    param: "offset",
+                    value: offset.to_string(),
+                });
+            }
+        };
+        let limit = match params::parse_form_field::<i64>("limit", self.limit)? {
+            None => DEFAULT_SEARCH_LIMIT,
+            Some(limit) if (1..=MAX_SEARCH_LIMIT).contains(&limit) => limit,
+            Some(limit) => {
+                return Err(ParamError::InvalidValue {
+                    param: "limit",
+                    value: limit.to_string(),
+                });
+            }
+        };
+
+        Ok(FeedEventTextSearch {
+            query: query.to_string(),
+            kind,
+            entity_id: entity_id.map(MmolbId::into_inner),
+            season,
+            offset,
+            // Get one extra to find out whether there's another page
+            limit: limit + 1,
+        })
+    }
+}
+
+/// Full-text search over player and team feed events, newest first. `q`
+/// uses web search syntax: put a phrase in double quotes to match it
+/// exactly, use `or` between alternatives, and put `-` before a word to
+/// exclude it. `kind` is `player_feed` or `team_feed`, and `entity_id` is
+/// the id of the player or team.
+#[get("/feed_events/search?<query..>")]
+pub async fn search_feed_events(
+    query: FeedSearchQuery<'_>,
+    db: Db,
+) -> Result<Json<ApiFeedEventSearchPage>, ApiError> {
+    let search = query.to_search()?;
+
+    let db_search = search.clone();
+    let mut matches = db
+        .run(move |conn| db::search_feed_event_texts(conn, &db_search))
+        .await?;
+
+    let page_size = (search.limit - 1) as usize;
+    let next_offset = if matches.len() > page_size {
+        matches.truncate(page_size);
+        Some(search.offset + page_size as i64)
+    } else {
+        None
+    };
+
+    Ok(Json(ApiFeedEventSearchPage {
+        matches: matches
+            .into_iter()
+            .map(|m| ApiFeedEventMatch {
+                kind: m.kind,
+                entity_id: m.entity_id,
+                feed_event_index: m.feed_event_index,
+                season: m.season,
+                time: m.time,
+                text: m.text,
+            })
+            .collect(),
+        next_offset,
+    }))
+}
@@ -0,0 +1,176 @@
+use crate::Db;
+use crate::api::error::ApiError;
+use crate::params::{MmolbId, ParamError, parse_form_field};
+use chrono::{DateTime, Utc};
+use hashbrown::HashMap;
+use itertools::Itertools;
+use mmoldb_client::types::{ApiHalfInning, ApiLinescore, ApiLinescoreTotals};
+use mmoldb_db::db::{GameReplay, GameStatus};
+use mmoldb_db::taxa::Taxa;
+use mmoldb_db::{ObjectStore, ObjectStoreConfig};
+use rocket::http::ContentType;
+use rocket::serde::json::Json;
+use rocket::{Build, Rocket, State, get};
+use serde::Deserialize;
+use std::sync::Arc;
+
+/// Returns 404 if the game hasn't been ingested
+#[get("/games/<game_id>/replay")]
+pub async fn game_replay(
+    game_id: Result<MmolbId, ParamError>,
+    taxa: &State<Taxa>,
+    db: Db,
+) -> Result<Option<Json<GameReplay>>, ApiError> {
+    let game_id = game_id?.into_inner();
+    let taxa = taxa.inner().clone();
+
+    let replay = db
+        .run(move |conn| mmoldb_db::db::game_replay(conn, &taxa, &game_id))
+        .await?;
+
+    Ok(replay.map(Json))
+}
+
+/// Offloaded game settings from Rocket.toml (or `ROCKET_`-prefixed
+/// environment variables)
+#[derive(Debug, Default, Deserialize)]
+pub struct GameOffloadConfig {
+    /// The object storage mmoldb-ingest offloads old games to (the
+    /// `game_offload` setting in MMOLDB.toml). Offloaded games' raw JSON
+    /// can't be served unless this is set.
+    #[serde(default)]
+    pub game_offload: Option<ObjectStoreConfig>,
+}
+
+/// Where offloaded games are fetched back from, if anywhere
+pub struct OffloadedGameStore {
+    store: Option<Arc<ObjectStore>>,
+}
+
+pub async fn init_offloaded_game_store(rocket: Rocket<Build>) -> Rocket<Build> {
+    let config = rocket
+        .figment()
+        .extract::<GameOffloadConfig>()
+        .expect("game_offload config should be valid");
+    let store = config.game_offload.map(|config| {
+        let store = ObjectStore::new(&config).expect("game_offload config should be valid");
+        Arc::new(store)
+    });
+
+    rocket.manage(OffloadedGameStore { store })
+}
+
+/// The raw game JSON exactly as it was received from Chron. If the game has
+/// been offloaded, it's fetched back from object storage. Only the latest
+/// version of each game is stored, so `valid_from` (an RFC 3339 timestamp)
+/// doesn't select an older version. It makes this return 404 unless the
+/// stored version is the one that was asked for.
+///
+/// Returns 404 if the game hasn't been ingested, and 410 if it's been
+/// offloaded and this server has no object storage configured.
+#[get("/games/<game_id>/raw.json?<valid_from>")]
+pub async fn game_raw_json(
+    game_id: Result<MmolbId, ParamError>,
+    valid_from: Option<&str>,
+    offloaded_games: &State<OffloadedGameStore>,
+    db: Db,
+) -> Result<Option<(ContentType, String)>, ApiError> {
+    let game_id = game_id?.into_inner();
+    let valid_from = parse_form_field::<DateTime<Utc>>("valid_from", valid_from)?;
+
+    let Some(entity) = db
+        .run(move |conn| mmoldb_db::db::get_stored_entity(conn, "game", &game_id))
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    if valid_from.is_some_and(|valid_from| valid_from != entity.valid_from) {
+        return Ok(None);
+    }
+
+    let data = match entity.offloaded_to {
+        None => entity.data,
+        Some(key) => {
+            let Some(store) = offloaded_games.store.clone() else {
+                return Err(ApiError::GameOffloaded(key));
+            };
+            // Object storage requests block
+            tokio::task::spawn_blocking(move || store.download_json(&key))
+                .await
+                .expect("Error joining offloaded game download task")?
+        }
+    };
+
+    Ok(Some((ContentType::JSON, data.to_string())))
+}
+
+/// Returns 404 if the game hasn't been ingested
+#[get("/games/<game_id>/linescore")]
+pub async fn game_linescore(
+    game_id: Result<MmolbId, ParamError>,
+    db: Db,
+) -> Result<Option<Json<ApiLinescore>>, ApiError> {
+    let game_id = game_id?.into_inner();
+
+    let lookup_id = game_id.clone();
+    let Some(innings) = db
+        .run(move |conn| mmoldb_db::db::game_innings(conn, &lookup_id))
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    let mut away = ApiLinescoreTotals::default();
+    let mut home = ApiLinescoreTotals::default();
+    for inning in &innings {
+        // The away team bats in the top of the inning
+        let (batting, fielding) = if inning.top_of_inning {
+            (&mut away, &mut home)
+        } else {
+            (&mut home, &mut away)
+        };
+        batting.runs += inning.runs;
+        batting.hits += inning.hits;
+        fielding.errors += inning.errors;
+    }
+
+    Ok(Some(Json(ApiLinescore {
+        game_id,
+        half_innings: innings
+            .into_iter()
+            .map(|inning| ApiHalfInning {
+                inning: inning.inning,
+                top_of_inning: inning.top_of_inning,
+                runs: inning.runs,
+                hits: inning.hits,
+                errors: inning.errors,
+            })
+            .collect_vec(),
+        away,
+        home,
+    })))
+}
+
+/// Whether each game in `id` (which may be repeated) is ongoing or
+/// finished, keyed by game id. Games that haven't been ingested are
+/// `NotFound` rather than left out.
+#[get("/games/status?<id>")]
+pub async fn games_status(
+    id: Vec<&str>,
+    db: Db,
+) -> Result<Json<HashMap<String, GameStatus>>, ApiError> {
+    let ids = id
+        .into_iter()
+        .map(|id| id.parse::<MmolbId>().map(MmolbId::into_inner))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let statuses = db
+        .run(move |conn| {
+            let ids = ids.iter().map(String::as_str).collect_vec();
+            mmoldb_db::db::games_status_map(conn, &ids)
+        })
+        .await?;
+
+    Ok(Json(statuses))
+}
@@ -0,0 +1,101 @@
+use crate::Db;
+use crate::api::error::ApiError;
+use crate::params::{self, MmolbId, ParamError, Season};
+use itertools::Itertools;
+use mmoldb_client::types::{
+    ApiParkFactor, ApiPlayerHomeAwaySplit, ApiSeasonParkFactors, ApiSeasonPlayerHomeAwaySplits,
+    ApiSeasonTeamHomeAwaySplits, ApiTeamHomeAwaySplit,
+};
+use rocket::get;
+use rocket::serde::json::Json;
+
+#[get("/team_home_away_splits/<season>?<team>")]
+pub async fn team_home_away_splits(
+    season: Result<Season, ParamError>,
+    team: Option<&str>,
+    db: Db,
+) -> Result<Json<ApiSeasonTeamHomeAwaySplits>, ApiError> {
+    let season = season?.get();
+    let team = params::parse_optional::<MmolbId>(team)?.map(MmolbId::into_inner);
+
+    let rows = db
+        .run(move |conn| mmoldb_db::db::team_home_away_splits(conn, season, team.as_deref()))
+        .await?;
+
+    Ok(Json(ApiSeasonTeamHomeAwaySplits {
+        season,
+        splits: rows
+            .into_iter()
+            .map(|row| ApiTeamHomeAwaySplit {
+                team_id: row.team_mmolb_id,
+                team_name: row.team_name,
+                team_emoji: row.team_emoji,
+                is_home: row.is_home,
+                games: row.games,
+                wins: row.wins,
+                runs_scored: row.runs_scored,
+                runs_allowed: row.runs_allowed,
+            })
+            .collect_vec(),
+    }))
+}
+
+#[get("/player_home_away_splits/<season>?<player>")]
+pub async fn player_home_away_splits(
+    season: Result<Season, ParamError>,
+    player: Option<&str>,
+    db: Db,
+) -> Result<Json<ApiSeasonPlayerHomeAwaySplits>, ApiError> {
+    let season = season?.get();
+    let player = params::parse_optional::<MmolbId>(player)?.map(MmolbId::into_inner);
+
+    let rows = db
+        .run(move |conn| mmoldb_db::db::player_home_away_splits(conn, season, player.as_deref()))
+        .await?;
+
+    Ok(Json(ApiSeasonPlayerHomeAwaySplits {
+        season,
+        splits: rows
+            .into_iter()
+            .map(|row| ApiPlayerHomeAwaySplit {
+                player_id: row.mmolb_player_id,
+                player_name: row.player_name,
+                is_home: row.is_home,
+                games: row.games,
+                plate_appearances: row.plate_appearances,
+                hits: row.hits,
+                home_runs: row.home_runs,
+                walks: row.walks,
+                strikeouts: row.strikeouts,
+            })
+            .collect_vec(),
+    }))
+}
+
+#[get("/park_factors/<season>")]
+pub async fn park_factors(
+    season: Result<Season, ParamError>,
+    db: Db,
+) -> Result<Json<ApiSeasonParkFactors>, ApiError> {
+    let season = season?.get();
+
+    let rows = db
+        .run(move |conn| mmoldb_db::db::park_factors(conn, season))
+        .await?;
+
+    Ok(Json(ApiSeasonParkFactors {
+        season,
+        parks: rows
+            .into_iter()
+            .map(|row| ApiParkFactor {
+                stadium_id: row.mmolb_stadium_id,
+                stadium_name: row.stadium_name,
+                games: row.games,
+                runs: row.runs,
+                runs_per_game: row.runs_per_game,
+                road_runs_per_game: row.road_runs_per_game,
+                park_factor: row.park_factor,
+            })
+            .collect_vec(),
+    }))
+}
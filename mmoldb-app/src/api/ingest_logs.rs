@@ -0,0 +1,148 @@
+use crate::Db;
+use crate::api::error::ApiError;
+use crate::params::{self, MmolbId, ParamError};
+use chrono::{DateTime, Utc};
+use mmoldb_db::db::{self, IngestLogFilter};
+use rocket::serde::Serialize;
+use rocket::serde::json::Json;
+use rocket::{FromForm, get};
+
+const DEFAULT_LOGS_LIMIT: i64 = 100;
+const MAX_LOGS_LIMIT: i64 = 1000;
+
+#[derive(Serialize)]
+pub struct ApiEventIngestLog {
+    pub id: i64,
+    pub mmolb_game_id: String,
+    /// Null for logs about the whole game
+    pub game_event_index: Option<i32>,
+    pub log_index: i32,
+    pub level: &'static str,
+    pub text: String,
+}
+
+#[derive(Serialize)]
+pub struct ApiVersionIngestLog {
+    pub id: i64,
+    pub kind: String,
+    pub entity_id: String,
+    pub valid_from: DateTime<Utc>,
+    pub log_index: i32,
+    pub level: &'static str,
+    pub text: String,
+}
+
+#[derive(Serialize)]
+pub struct ApiIngestLogsPage<T> {
+    pub logs: Vec<T>,
+    /// Pass this as `after` to get the next page. Null on the last page.
+    pub next_after: Option<i64>,
+}
+
+/// Query parameters for the ingest log endpoints. These are kept as raw
+/// strings so bad values are reported instead of ignored.
+#[derive(FromForm)]
+pub struct IngestLogsQuery<'r> {
+    /// The least severe level to include. Defaults to warning.
+    level: Option<&'r str>,
+    kind: Option<&'r str>,
+    entity_id: Option<&'r str>,
+    after: Option<&'r str>,
+    limit: Option<&'r str>,
+}
+
+impl IngestLogsQuery<'_> {
+    fn to_filter(&self) -> Result<IngestLogFilter, ParamError> {
+        let max_level = match self.level.filter(|level| !level.is_empty()) {
+            None => 2,
+            Some(level) => {
+                db::log_level_from_name(level).ok_or_else(|| ParamError::InvalidValue {
+                    param: "level",
+                    value: level.to_string(),
+                })?
+            }
+        };
+        let entity_id = params::parse_form_field::<MmolbId>("entity_id", self.entity_id)?;
+        let after_id = params::parse_form_field::<i64>("after", self.after)?;
+        let limit = match params::parse_form_field::<i64>("limit", self.limit)? {
+            None => DEFAULT_LOGS_LIMIT,
+            Some(limit) if (1..=MAX_LOGS_LIMIT).contains(&limit) => limit,
+            Some(limit) => {
+                return Err(ParamError::InvalidValue {
+                    param: "limit",
+                    value: limit.to_string(),
+                });
+            }
+        };
+
+        Ok(IngestLogFilter {
+            max_level,
+            kind: self
+                .kind
+                .filter(|kind| !kind.is_empty())
+                .map(str::to_string),
+            entity_id: entity_id.map(MmolbId::into_inner),
+            after_id,
+            limit,
+        })
+    }
+}
+
+/// Logs from processing games' events. Their kind is always `game`.
+#[get("/ingest_logs/events?<query..>")]
+pub async fn event_ingest_logs(
+    query: IngestLogsQuery<'_>,
+    db: Db,
+) -> Result<Json<ApiIngestLogsPage<ApiEventIngestLog>>, ApiError> {
+    let filter = query.to_filter()?;
+
+    let page = db
+        .run(move |conn| db::page_of_event_ingest_logs(conn, &filter))
+        .await?;
+
+    Ok(Json(ApiIngestLogsPage {
+        logs: page
+            .items
+            .into_iter()
+            .map(|log| ApiEventIngestLog {
+                id: log.log.id,
+                mmolb_game_id: log.mmolb_game_id,
+                game_event_index: log.log.game_event_index,
+                log_index: log.log.log_index,
+                level: db::log_level_name(log.log.log_level),
+                text: log.log.log_text,
+            })
+            .collect(),
+        next_after: page.next_page,
+    }))
+}
+
+/// Logs from processing versioned entities like players and teams
+#[get("/ingest_logs/versions?<query..>")]
+pub async fn version_ingest_logs(
+    query: IngestLogsQuery<'_>,
+    db: Db,
+) -> Result<Json<ApiIngestLogsPage<ApiVersionIngestLog>>, ApiError> {
+    let filter = query.to_filter()?;
+
+    let page = db
+        .run(move |conn| db::page_of_version_ingest_logs(conn, &filter))
+        .await?;
+
+    Ok(Json(ApiIngestLogsPage {
+        logs: page
+            .items
+            .into_iter()
+            .map(|log| ApiVersionIngestLog {
+                id: log.id,
+                kind: log.kind,
+                entity_id: log.entity_id,
+                valid_from: log.valid_from,
+                log_index: log.log_index,
+                level: db::log_level_name(log.log_level),
+                text: log.log_text,
+            })
+            .collect(),
+        next_after: page.next_page,
+    }))
+}
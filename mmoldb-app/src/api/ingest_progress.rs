@@ -0,0 +1,32 @@
+use crate::ingest_progress::IngestProgress;
+use rocket::response::stream::{Event, EventStream};
+use rocket::tokio::select;
+use rocket::tokio::sync::broadcast::error::RecvError;
+use rocket::{Shutdown, State, get};
+
+/// Server-sent events for each ingest stage starting and finishing and
+/// each batch the ingest writes. Each event's name is its `type`. If a
+/// client falls too far behind, it gets a `lagged` event with the number
+/// of events it missed.
+#[get("/ingest/progress")]
+pub fn ingest_progress(progress: &State<IngestProgress>, mut shutdown: Shutdown) -> EventStream![] {
+    let mut events = progress.subscribe();
+
+    EventStream! {
+        loop {
+            let event = select! {
+                event = events.recv() => match event {
+                    Ok(event) => event,
+                    Err(RecvError::Closed) => break,
+                    Err(RecvError::Lagged(num_missed)) => {
+                        yield Event::data(num_missed.to_string()).event("lagged");
+                        continue;
+                    }
+                },
+                _ = &mut shutdown => break,
+            };
+
+            yield Event::json(&event).event(event.name());
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use crate::Db;
+use crate::api::error::ApiError;
+use crate::params::{self, MmolbId, ParamError};
+use mmoldb_db::db::{self, KeysetPage};
+use rocket::serde::Serialize;
+use rocket::serde::json::Json;
+use rocket::{FromForm, get};
+
+const DEFAULT_LIST_LIMIT: usize = 100;
+const MAX_LIST_LIMIT: usize = 1000;
+
+#[derive(Serialize)]
+pub struct ApiListPage<T> {
+    pub items: Vec<T>,
+    /// Pass this as `after` to get the next page. Null on the last page.
+    pub next_after: Option<String>,
+    pub has_previous_page: bool,
+    /// Pass this as `after` to get the previous page. Null if there is no
+    /// previous page or if the previous page is the first page, which is
+    /// fetched without `after`.
+    pub previous_after: Option<String>,
+}
+
+impl<T> ApiListPage<T> {
+    fn from_page<U>(page: KeysetPage<U, String>, f: impl FnMut(U) -> T) -> Self {
+        let page = page.map(f);
+        Self {
+            items: page.items,
+            next_after: page.next_page,
+            has_previous_page: page.previous_page.is_some(),
+            previous_after: page.previous_page.flatten(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ApiPlayerListEntry {
+    pub mmolb_player_id: String,
+    pub name: String,
+    /// Null for players who aren't on a team
+    pub mmolb_team_id: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ApiTeamListEntry {
+    pub mmolb_team_id: String,
+    pub name: String,
+    pub emoji: String,
+    pub location: String,
+    pub mmolb_league_id: Option<String>,
+}
+
+/// Query parameters for the list endpoints. These are kept as raw strings
+/// so bad values are reported instead of ignored.
+#[derive(FromForm)]
+pub struct ListQuery<'r> {
+    after: Option<&'r str>,
+    limit: Option<&'r str>,
+    /// Only players on this team. Only used by the players list.
+    team_id: Option<&'r str>,
+    /// Only teams in this league. Only used by the teams list.
+    league_id: Option<&'r str>,
+}
+
+impl ListQuery<'_> {
+    fn after(&self) -> Result<Option<String>, ParamError> {
+        Ok(params::parse_form_field::<MmolbId>("after", self.after)?.map(MmolbId::into_inner))
+    }
+
+    fn limit(&self) -> Result<usize, ParamError> {
+        match params::parse_form_field::<usize>("limit", self.limit)? {
+            None => Ok(DEFAULT_LIST_LIMIT),
+            Some(limit) if (1..=MAX_LIST_LIMIT).contains(&limit) => Ok(limit),
+            Some(limit) => Err(ParamError::InvalidValue {
+                param: "limit",
+                value: limit.to_string(),
+            }),
+        }
+    }
+}
+
+/// The current version of every player, in MMOLB id order
+#[get("/players?<query..>")]
+pub async fn players_list(
+    query: ListQuery<'_>,
+    db: Db,
+) -> Result<Json<ApiListPage<ApiPlayerListEntry>>, ApiError> {
+    let after = query.after()?;
+    let limit = query.limit()?;
+    let team_id =
+        params::parse_form_field::<MmolbId>("team_id", query.team_id)?.map(MmolbId::into_inner);
+
+    let page = db
+        .run(move |conn| db::page_of_players(conn, limit, after.as_deref(), team_id.as_deref()))
+        .await?;
+
+    Ok(Json(ApiListPage::from_page(page, |player| {
+        ApiPlayerListEntry {
+            mmolb_player_id: player.mmolb_player_id,
+            name: player.name,
+            mmolb_team_id: player.mmolb_team_id,
+        }
+    })))
+}
+
+/// The current version of every team, in MMOLB id order
+#[get("/teams?<query..>")]
+pub async fn teams_list(
+    query: ListQuery<'_>,
+    db: Db,
+) -> Result<Json<ApiListPage<ApiTeamListEntry>>, ApiError> {
+    let after = query.after()?;
+    let limit = query.limit()?;
+    let league_id =
+        params::parse_form_field::<MmolbId>("league_id", query.league_id)?.map(MmolbId::into_inner);
+
+    let page = db
+        .run(move |conn| db::page_of_teams(conn, limit, after.as_deref(), league_id.as_deref()))
+        .await?;
+
+    Ok(Json(ApiListPage::from_page(page, |team| {
+        ApiTeamListEntry {
+            mmolb_team_id: team.mmolb_team_id,
+            name: team.name,
+            emoji: team.emoji,
+            location: team.location,
+            mmolb_league_id: team.mmolb_league_id,
+        }
+    })))
+}
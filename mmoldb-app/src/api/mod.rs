@@ -1,5 +1,23 @@
+mod attributes;
+mod catcher;
+mod day_summary;
+mod ejections;
+mod equipment;
 mod error;
+mod events;
+pub mod export;
+mod feed_search;
+pub mod game;
+mod home_away;
+mod ingest_logs;
+mod ingest_progress;
+mod lists;
+mod pitcher;
 mod player;
+mod status;
+mod taxa;
+mod weather;
+mod weekly_rollups;
 
 #[rocket::get("/")]
 pub async fn index() -> &'static str {
@@ -7,5 +25,44 @@ pub async fn index() -> &'static str {
 }
 
 pub fn routes() -> Vec<rocket::Route> {
-    rocket::routes![index, player::player_versions,]
+    rocket::routes![
+        index,
+        player::player_versions,
+        player::player_versions_page,
+        equipment::player_equipment_timeline,
+        catcher::catcher_called_strikes,
+        pitcher::pitcher_tto_splits,
+        pitcher::pitcher_pitch_count_splits,
+        ejections::ejections,
+        events::events,
+        day_summary::day_summary,
+        day_summary::day_summary_text,
+        status::db_health,
+        status::freshness,
+        status::quality,
+        export::export_csv,
+        export::export_feed_ndjson,
+        export::export_analytics_sqlite,
+        feed_search::search_feed_events,
+        game::game_replay,
+        game::game_raw_json,
+        game::game_linescore,
+        game::games_status,
+        ingest_logs::event_ingest_logs,
+        ingest_logs::version_ingest_logs,
+        ingest_progress::ingest_progress,
+        weather::weather_outcomes,
+        weather::weather_game_counts,
+        weekly_rollups::team_weekly_rollups,
+        weekly_rollups::player_weekly_rollups,
+        home_away::team_home_away_splits,
+        home_away::player_home_away_splits,
+        home_away::park_factors,
+        attributes::attribute_distributions,
+        attributes::attribute_progression,
+        lists::players_list,
+        lists::teams_list,
+        taxa::taxa_tables,
+        taxa::taxa_table,
+    ]
 }
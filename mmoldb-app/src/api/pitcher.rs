@@ -0,0 +1,78 @@
+use crate::Db;
+use crate::api::error::ApiError;
+use crate::params::{self, MmolbId, ParamError, Season};
+use itertools::Itertools;
+use mmoldb_client::types::{
+    ApiPitcherPitchCountSplit, ApiPitcherTtoSplit, ApiSeasonPitcherPitchCountSplits,
+    ApiSeasonPitcherTtoSplits,
+};
+use rocket::get;
+use rocket::serde::json::Json;
+
+#[get("/pitcher_tto_splits/<season>?<team>")]
+pub async fn pitcher_tto_splits(
+    season: Result<Season, ParamError>,
+    team: Option<&str>,
+    db: Db,
+) -> Result<Json<ApiSeasonPitcherTtoSplits>, ApiError> {
+    let season = season?.get();
+    let team = params::parse_optional::<MmolbId>(team)?.map(MmolbId::into_inner);
+
+    let rows = db
+        .run(move |conn| {
+            mmoldb_db::db::pitcher_tto_splits_for_season(conn, season, team.as_deref())
+        })
+        .await?;
+
+    Ok(Json(ApiSeasonPitcherTtoSplits {
+        season,
+        splits: rows
+            .into_iter()
+            .map(|row| ApiPitcherTtoSplit {
+                team_id: row.team_mmolb_id,
+                pitcher_name: row.pitcher_name,
+                times_through_order: row.times_through_order,
+                plate_appearances: row.plate_appearances,
+                hits: row.hits,
+                home_runs: row.home_runs,
+                walks: row.walks,
+                strikeouts: row.strikeouts,
+                pitches: row.pitches,
+            })
+            .collect_vec(),
+    }))
+}
+
+#[get("/pitcher_pitch_count_splits/<season>?<team>")]
+pub async fn pitcher_pitch_count_splits(
+    season: Result<Season, ParamError>,
+    team: Option<&str>,
+    db: Db,
+) -> Result<Json<ApiSeasonPitcherPitchCountSplits>, ApiError> {
+    let season = season?.get();
+    let team = params::parse_optional::<MmolbId>(team)?.map(MmolbId::into_inner);
+
+    let rows = db
+        .run(move |conn| {
+            mmoldb_db::db::pitcher_pitch_count_splits_for_season(conn, season, team.as_deref())
+        })
+        .await?;
+
+    Ok(Json(ApiSeasonPitcherPitchCountSplits {
+        season,
+        splits: rows
+            .into_iter()
+            .map(|row| ApiPitcherPitchCountSplit {
+                team_id: row.team_mmolb_id,
+                pitcher_name: row.pitcher_name,
+                pitch_count_min: row.pitch_count_min,
+                plate_appearances: row.plate_appearances,
+                hits: row.hits,
+                home_runs: row.home_runs,
+                walks: row.walks,
+                strikeouts: row.strikeouts,
+                pitches: row.pitches,
+            })
+            .collect_vec(),
+    }))
+}
@@ -1,114 +1,24 @@
 use crate::Db;
+use crate::admin::ApiUser;
 use crate::api::error::ApiError;
+use crate::params::{self, MmolbId, ParamError};
 use chrono::{DateTime, Utc};
-use hashbrown::HashMap;
 use itertools::Itertools;
 use log::warn;
-use mmoldb_db::taxa::{
-    AsInsertable, Taxa, TaxaAttribute, TaxaAttributeCategory, TaxaDayType, TaxaEffectType,
-    TaxaHandedness, TaxaSlot,
+use mmoldb_client::types::{
+    ApiEquipment, ApiEquipmentEffect, ApiModification, ApiPlayerEvent, ApiPlayerVersion,
+    ApiPlayerVersionRow, ApiPlayerVersions, ApiPlayerVersionsPage, ApiReport, ApiReportAttribute,
 };
-use rocket::serde::Serialize;
+use mmoldb_db::taxa::{AsInsertable, Taxa, UnknownTaxaId};
 use rocket::serde::json::Json;
-use rocket::{State, get};
-
-#[derive(Clone, Serialize)]
-pub struct ApiModification {
-    pub name: String,
-    pub emoji: String,
-    pub description: String,
-}
-
-#[derive(Clone, Serialize)]
-pub struct ApiEquipmentEffect {
-    pub attribute: TaxaAttribute,
-    pub effect_type: TaxaEffectType,
-    pub value: f64,
-}
-
-#[derive(Clone, Serialize)]
-pub struct ApiEquipment {
-    pub emoji: String,
-    pub name: String,
-    pub special_type: Option<String>,
-    pub description: Option<String>,
-    pub rare_name: Option<String>,
-    pub cost: Option<i32>,
-    pub prefixes: Vec<Option<String>>,
-    pub suffixes: Vec<Option<String>>,
-    pub rarity: Option<String>,
-    pub effects: Vec<Option<ApiEquipmentEffect>>,
-}
-
-#[derive(Clone, Serialize)]
-pub struct ApiReportAttribute {
-    pub base_stars: Option<i32>,
-    pub base_total: Option<f64>,
-    pub modified_stars: Option<i32>,
-    pub modified_total: Option<f64>,
-}
-
-#[derive(Clone, Serialize)]
-pub struct ApiReport {
-    pub season: Option<i32>,
-    pub day_type: Option<TaxaDayType>,
-    pub day: Option<i32>,
-    pub superstar_day: Option<i32>,
-    pub quote: Option<String>,
-    pub attributes: HashMap<TaxaAttribute, Option<ApiReportAttribute>>,
-}
-
-#[derive(Clone, Serialize)]
-pub struct ApiPlayerVersion {
-    pub id: String,
-    pub valid_from: DateTime<Utc>,
-    pub valid_until: Option<DateTime<Utc>>,
-    pub first_name: String,
-    pub last_name: String,
-    pub batting_handedness: Option<TaxaHandedness>,
-    pub pitching_handedness: Option<TaxaHandedness>,
-    pub home: String,
-    pub birthseason: i32,
-    pub birthday_type: Option<TaxaDayType>,
-    pub birthday_day: Option<i32>,
-    pub birthday_superstar_day: Option<i32>,
-    pub likes: String,
-    pub dislikes: String,
-    pub number: i32,
-    pub mmolb_team_id: Option<String>,
-    pub slot: Option<TaxaSlot>,
-    pub durability: Option<f64>,
-    pub modifications: Vec<Option<ApiModification>>,
-    pub equipment: HashMap<String, Option<ApiEquipment>>,
-    pub reports: HashMap<TaxaAttributeCategory, Option<ApiReport>>,
-    pub events: Vec<ApiPlayerEvent>,
-}
-
-#[derive(Clone, Serialize)]
-#[serde(tag = "event_type")]
-pub enum ApiPlayerEvent {
-    Recomposition {
-        time: DateTime<Utc>,
-        new_name: String,
-        reverts_recomposition: Option<DateTime<Utc>>,
-    },
-    AttributeAugment {
-        time: DateTime<Utc>,
-        category: TaxaAttributeCategory,
-        attribute: TaxaAttribute,
-        value: i32,
-    },
-    Party {
-        category: TaxaAttributeCategory,
-        attribute: TaxaAttribute,
-        value: i32,
-    },
-}
-
-#[derive(Serialize)]
-pub struct ApiPlayerVersions<'a> {
-    pub player_id: &'a str,
-    pub versions: Vec<ApiPlayerVersion>,
+use rocket::{FromForm, State, get};
+use std::collections::HashMap;
+
+/// The name a taxa value is sent as. The response types are shared with
+/// mmoldb-client, which doesn't know about the taxa enums, so they hold
+/// these names instead.
+fn taxa_name(taxa: impl Into<&'static str>) -> String {
+    taxa.into().to_string()
 }
 
 struct NextChangeTime(Option<DateTime<Utc>>);
@@ -140,12 +50,13 @@ impl NextChangeTime {
 }
 
 #[get("/player_versions/<player_id>")]
-pub async fn player_versions<'a>(
-    player_id: &'a str,
+pub async fn player_versions(
+    player_id: Result<MmolbId, ParamError>,
     db: Db,
     taxa: &State<Taxa>,
-) -> Result<Json<ApiPlayerVersions<'a>>, ApiError> {
-    let mmolb_player_id = player_id.to_string();
+) -> Result<Json<ApiPlayerVersions>, ApiError> {
+    let player_id = player_id?.into_inner();
+    let mmolb_player_id = player_id.clone();
     let (
         player_versions,
         player_modification_versions,
@@ -226,35 +137,28 @@ pub async fn player_versions<'a>(
     let mut active_player = None;
     let mut modifications: Vec<Option<ApiModification>> = Default::default();
     let mut equipment: HashMap<String, Option<ApiEquipment>> = Default::default();
-    let mut reports: HashMap<TaxaAttributeCategory, Option<ApiReport>> = Default::default();
+    let mut reports: HashMap<String, Option<ApiReport>> = Default::default();
     loop {
         let mut next_change_time = NextChangeTime::new();
-        next_change_time
-            .with_possible_change(next_player_version.peek().map(|v| v.valid_from.and_utc()));
+        next_change_time.with_possible_change(next_player_version.peek().map(|v| v.valid_from));
         next_change_time.with_possible_change(
             next_player_modification_version
                 .peek()
-                .map(|v| v.valid_from.and_utc()),
-        );
-        next_change_time.with_possible_change(
-            next_player_equipment_version
-                .peek()
-                .map(|v| v.valid_from.and_utc()),
+                .map(|v| v.valid_from),
         );
+        next_change_time
+            .with_possible_change(next_player_equipment_version.peek().map(|v| v.valid_from));
         next_change_time.with_possible_change(
             next_player_equipment_effect_version
                 .peek()
-                .map(|v| v.valid_from.and_utc()),
-        );
-        next_change_time.with_possible_change(
-            next_player_report_version
-                .peek()
-                .map(|v| v.valid_from.and_utc()),
+                .map(|v| v.valid_from),
         );
+        next_change_time
+            .with_possible_change(next_player_report_version.peek().map(|v| v.valid_from));
         next_change_time.with_possible_change(
             next_player_report_attribute_version
                 .peek()
-                .map(|v| v.valid_from.and_utc()),
+                .map(|v| v.valid_from),
         );
 
         let Some(time) = next_change_time.into_inner() else {
@@ -265,7 +169,7 @@ pub async fn player_versions<'a>(
         // effects because player.num_modifications, player.occupied_equipment_slots, and
         // equipment.num_effects will do that for us.
 
-        while let Some(player) = next_player_version.next_if(|p| p.valid_from.and_utc() == time) {
+        while let Some(player) = next_player_version.next_if(|p| p.valid_from == time) {
             active_player = Some(player);
         }
 
@@ -275,9 +179,7 @@ pub async fn player_versions<'a>(
         };
 
         modifications.resize(player.num_modifications as usize, None);
-        while let Some(modif) =
-            next_player_modification_version.next_if(|p| p.valid_from.and_utc() == time)
-        {
+        while let Some(modif) = next_player_modification_version.next_if(|p| p.valid_from == time) {
             if let Some(elem) = modifications.get_mut(modif.modification_index as usize) {
                 if let Some(api_mod) = modifications_table.get(&modif.modification_id) {
                     *elem = Some(api_mod.clone());
@@ -321,9 +223,7 @@ pub async fn player_versions<'a>(
             })
             .collect();
 
-        while let Some(eq) =
-            next_player_equipment_version.next_if(|e| e.valid_from.and_utc() == time)
-        {
+        while let Some(eq) = next_player_equipment_version.next_if(|e| e.valid_from == time) {
             if let Some(elem) = equipment.get_mut(&eq.equipment_slot) {
                 // Updated and new effects will be filled in by the next step
                 let effects = if let Some(mut elem) = elem.take() {
@@ -353,14 +253,14 @@ pub async fn player_versions<'a>(
             }
         }
         while let Some(effect) =
-            next_player_equipment_effect_version.next_if(|e| e.valid_from.and_utc() == time)
+            next_player_equipment_effect_version.next_if(|e| e.valid_from == time)
         {
             if let Some(eq) = equipment.get_mut(&effect.equipment_slot) {
                 if let Some(eq) = eq {
                     if let Some(effect_slot) = eq.effects.get_mut(effect.effect_index as usize) {
                         *effect_slot = Some(ApiEquipmentEffect {
-                            attribute: taxa.attribute_from_id(effect.attribute),
-                            effect_type: taxa.effect_type_from_id(effect.effect_type),
+                            attribute: taxa_name(taxa.attribute_from_id(effect.attribute)?),
+                            effect_type: taxa_name(taxa.effect_type_from_id(effect.effect_type)?),
                             value: effect.value,
                         })
                     } else {
@@ -396,26 +296,24 @@ pub async fn player_versions<'a>(
                     panic!("Included category should never be None");
                 };
 
-                let category = taxa.attribute_category_from_id(*category);
+                let category = taxa_name(taxa.attribute_category_from_id(*category)?);
                 match reports.remove_entry(&category) {
                     None => {
                         // This is a new category, populate it with None and it will get overwritten
                         // in the next step
-                        (category, None)
+                        Ok((category, None))
                     }
                     Some((category, report)) => {
                         // This is a previously occupied category, carry over its value and it may
                         // get overwritten in the next step
-                        (category, report)
+                        Ok((category, report))
                     }
                 }
             })
-            .collect();
+            .collect::<Result<_, UnknownTaxaId>>()?;
 
-        while let Some(report) =
-            next_player_report_version.next_if(|e| e.valid_from.and_utc() == time)
-        {
-            let category = taxa.attribute_category_from_id(report.category);
+        while let Some(report) = next_player_report_version.next_if(|e| e.valid_from == time) {
+            let category = taxa_name(taxa.attribute_category_from_id(report.category)?);
             if let Some(elem) = reports.get_mut(&category) {
                 // Updated and new attributes will be filled in by the next step
                 let attributes = if let Some(mut elem) = elem.take() {
@@ -430,21 +328,21 @@ pub async fn player_versions<'a>(
                                 panic!("Included attribute should never be None");
                             };
 
-                            let attr = taxa.attribute_from_id(*attr);
+                            let attr = taxa_name(taxa.attribute_from_id(*attr)?);
                             match elem.attributes.remove_entry(&attr) {
                                 None => {
                                     // This is a new attribute, populate it with None and it will get overwritten
                                     // in the next step
-                                    (attr, None)
+                                    Ok((attr, None))
                                 }
                                 Some((attr, value)) => {
                                     // This is a previously occupied attribute, carry over its value and it may
                                     // get overwritten in the next step
-                                    (attr, value)
+                                    Ok((attr, value))
                                 }
                             }
                         })
-                        .collect()
+                        .collect::<Result<_, UnknownTaxaId>>()?
                 } else {
                     report
                         .included_attributes
@@ -457,16 +355,19 @@ pub async fn player_versions<'a>(
                                 panic!("Included attribute should never be None");
                             };
 
-                            let attr = taxa.attribute_from_id(*attr);
+                            let attr = taxa_name(taxa.attribute_from_id(*attr)?);
                             // This is a new attribute because there is no previous report
-                            (attr, None)
+                            Ok((attr, None))
                         })
-                        .collect()
+                        .collect::<Result<_, UnknownTaxaId>>()?
                 };
 
                 *elem = Some(ApiReport {
                     season: report.season,
-                    day_type: report.day_type.map(|d| taxa.day_type_from_id(d)),
+                    day_type: report
+                        .day_type
+                        .map(|d| taxa.day_type_from_id(d).map(taxa_name))
+                        .transpose()?,
                     day: report.day,
                     superstar_day: report.superstar_day,
                     quote: report.quote,
@@ -480,18 +381,25 @@ pub async fn player_versions<'a>(
             }
         }
         while let Some(attribute) =
-            next_player_report_attribute_version.next_if(|e| e.valid_from.and_utc() == time)
+            next_player_report_attribute_version.next_if(|e| e.valid_from == time)
         {
-            let category = taxa.attribute_category_from_id(attribute.category);
-            let attr = taxa.attribute_from_id(attribute.attribute);
-            if let Some(report) = reports.get_mut(&category) {
+            let category = taxa.attribute_category_from_id(attribute.category)?;
+            let attr = taxa.attribute_from_id(attribute.attribute)?;
+            if let Some(report) = reports.get_mut(&taxa_name(category)) {
                 if let Some(report) = report {
-                    if let Some(effect_slot) = report.attributes.get_mut(&attr) {
+                    let season = report.season;
+                    if let Some(effect_slot) = report.attributes.get_mut(&taxa_name(attr)) {
                         *effect_slot = Some(ApiReportAttribute {
                             base_stars: attribute.base_stars,
                             base_total: attribute.base_total,
                             modified_stars: attribute.modified_stars,
                             modified_total: attribute.modified_total,
+                            computed_base_stars: attribute
+                                .base_total
+                                .map(|total| category.stars_for_total(total, season)),
+                            computed_modified_stars: attribute
+                                .modified_total
+                                .map(|total| category.stars_for_total(total, season)),
                         })
                     } else {
                         warn!(
@@ -536,11 +444,13 @@ pub async fn player_versions<'a>(
         // For now, assume that events belong to the earliest version whose valid_until
         // is after they occurred
         while let Some(augment) = next_attribute_augment.next_if(|e| e.time.and_utc() <= time) {
-            let attribute = taxa.attribute_from_id(augment.attribute);
+            let attribute = taxa.attribute_from_id(augment.attribute)?;
             events.push(ApiPlayerEvent::AttributeAugment {
                 time: augment.time.and_utc(),
-                category: taxa.attribute_category_from_id(attribute.as_insertable().category),
-                attribute,
+                category: taxa_name(
+                    taxa.attribute_category_from_id(attribute.as_insertable().category)?,
+                ),
+                attribute: taxa_name(attribute),
                 value: augment.value,
             })
         }
@@ -548,11 +458,14 @@ pub async fn player_versions<'a>(
         // For now, assume that parties belong to the earliest version whose valid_until
         // is after their game started. I think this still might be accurate enough because
         // players can't change in any other way during party weather.
-        while let Some(party) = next_player_party.next_if(|p| p.game_start_time.and_utc() <= time) {
-            let attribute = taxa.attribute_from_id(party.attribute);
+        while let Some(party) = next_player_party.next_if(|p| p.game_start_time <= time) {
+            let attribute = taxa.attribute_from_id(party.attribute)?;
             events.push(ApiPlayerEvent::Party {
-                category: taxa.attribute_category_from_id(attribute.as_insertable().category),
-                attribute,
+                time: party.game_start_time,
+                category: taxa_name(
+                    taxa.attribute_category_from_id(attribute.as_insertable().category)?,
+                ),
+                attribute: taxa_name(attribute),
                 value: party.value,
             })
         }
@@ -568,20 +481,28 @@ pub async fn player_versions<'a>(
             last_name: player.last_name.clone(),
             batting_handedness: player
                 .batting_handedness
-                .map(|h| taxa.handedness_from_id(h)),
+                .map(|h| taxa.handedness_from_id(h).map(taxa_name))
+                .transpose()?,
             pitching_handedness: player
                 .pitching_handedness
-                .map(|h| taxa.handedness_from_id(h)),
+                .map(|h| taxa.handedness_from_id(h).map(taxa_name))
+                .transpose()?,
             home: player.home.clone(),
             birthseason: player.birthseason,
-            birthday_type: player.birthday_type.map(|d| taxa.day_type_from_id(d)),
+            birthday_type: player
+                .birthday_type
+                .map(|d| taxa.day_type_from_id(d).map(taxa_name))
+                .transpose()?,
             birthday_day: player.birthday_day,
             birthday_superstar_day: player.birthday_superstar_day,
             likes: player.likes.clone(),
             dislikes: player.dislikes.clone(),
             number: player.number,
             mmolb_team_id: player.mmolb_team_id.clone(),
-            slot: player.slot.map(|s| taxa.slot_from_id(s)),
+            slot: player
+                .slot
+                .map(|s| taxa.slot_from_id(s).map(taxa_name))
+                .transpose()?,
             durability: player.durability,
             // TODO Separate out fields for both boon types and mods
             modifications: modifications.clone(),
@@ -596,3 +517,327 @@ pub async fn player_versions<'a>(
         versions,
     }))
 }
+
+/// Default and maximum for the `limit` parameter of
+/// [`player_versions_page`]
+const DEFAULT_VERSIONS_LIMIT: i64 = 100;
+const MAX_VERSIONS_LIMIT: i64 = 1000;
+
+/// Child data that can be embedded in each version with `?include=`
+#[derive(Debug, Default, Copy, Clone)]
+struct VersionIncludes {
+    modifications: bool,
+    equipment: bool,
+}
+
+impl VersionIncludes {
+    fn parse(include: Option<&str>) -> Result<Self, ParamError> {
+        let mut includes = VersionIncludes::default();
+        for item in include.into_iter().flat_map(|s| s.split(',')) {
+            match item.trim() {
+                "" => {}
+                "modifications" => includes.modifications = true,
+                "equipment" => includes.equipment = true,
+                other => {
+                    return Err(ParamError::InvalidValue {
+                        param: "include",
+                        value: other.to_string(),
+                    });
+                }
+            }
+        }
+        Ok(includes)
+    }
+}
+
+fn is_valid_at(
+    valid_from: DateTime<Utc>,
+    valid_until: Option<DateTime<Utc>>,
+    time: DateTime<Utc>,
+) -> bool {
+    valid_from <= time && valid_until.is_none_or(|until| time < until)
+}
+
+/// Query parameters for [`player_versions_page`]. These are kept as raw
+/// strings so invalid values are reported instead of ignored.
+#[derive(FromForm)]
+pub struct PlayerVersionsQuery<'r> {
+    since: Option<&'r str>,
+    until: Option<&'r str>,
+    limit: Option<&'r str>,
+    include: Option<&'r str>,
+}
+
+#[get("/players/<player_id>/versions?<query..>")]
+pub async fn player_versions_page(
+    _user: ApiUser,
+    player_id: Result<MmolbId, ParamError>,
+    query: PlayerVersionsQuery<'_>,
+    db: Db,
+    taxa: &State<Taxa>,
+) -> Result<Json<ApiPlayerVersionsPage>, ApiError> {
+    let player_id = player_id?.into_inner();
+    let since = params::parse_form_field::<DateTime<Utc>>("since", query.since)?;
+    let until = params::parse_form_field::<DateTime<Utc>>("until", query.until)?;
+    let limit = match params::parse_form_field::<i64>("limit", query.limit)? {
+        None => DEFAULT_VERSIONS_LIMIT,
+        Some(limit) if (1..=MAX_VERSIONS_LIMIT).contains(&limit) => limit,
+        Some(limit) => {
+            return Err(ParamError::InvalidValue {
+                param: "limit",
+                value: limit.to_string(),
+            }
+            .into());
+        }
+    };
+    let includes = VersionIncludes::parse(query.include)?;
+
+    let mmolb_player_id = player_id.clone();
+    let (
+        mut player_versions,
+        modification_versions,
+        modifications,
+        equipment_versions,
+        effect_versions,
+    ) = db
+        .run(move |conn| {
+            // Get one extra to find out whether there's another page
+            let player_versions = mmoldb_db::db::get_player_versions_page(
+                conn,
+                &mmolb_player_id,
+                since,
+                until,
+                limit + 1,
+            )?;
+
+            let range = player_versions
+                .first()
+                .zip(player_versions.iter().take(limit as usize).last())
+                .map(|(first, last)| (first.valid_from, last.valid_from));
+
+            let (modification_versions, modifications) = match range {
+                Some((first, last)) if includes.modifications => {
+                    let modification_versions =
+                        mmoldb_db::db::get_player_modification_versions_between(
+                            conn,
+                            &mmolb_player_id,
+                            first,
+                            last,
+                        )?;
+                    let mod_ids = modification_versions
+                        .iter()
+                        .map(|pm| pm.modification_id)
+                        .unique()
+                        .collect_vec();
+                    let modifications = mmoldb_db::db::get_modifications(conn, &mod_ids)?;
+                    (modification_versions, modifications)
+                }
+                _ => (Vec::new(), Vec::new()),
+            };
+
+            let (equipment_versions, effect_versions) = match range {
+                Some((first, last)) if includes.equipment => (
+                    mmoldb_db::db::get_player_equipment_versions_between(
+                        conn,
+                        &mmolb_player_id,
+                        first,
+                        last,
+                    )?,
+                    mmoldb_db::db::get_player_equipment_effect_versions_between(
+                        conn,
+                        &mmolb_player_id,
+                        first,
+                        last,
+                    )?,
+                ),
+                _ => (Vec::new(), Vec::new()),
+            };
+
+            Ok::<_, ApiError>((
+                player_versions,
+                modification_versions,
+                modifications,
+                equipment_versions,
+                effect_versions,
+            ))
+        })
+        .await?;
+
+    let next_since = if player_versions.len() > limit as usize {
+        player_versions.pop().map(|extra| extra.valid_from)
+    } else {
+        None
+    };
+
+    let modifications_table: HashMap<_, _> = modifications
+        .into_iter()
+        .map(|m| {
+            (
+                m.id,
+                ApiModification {
+                    name: m.name,
+                    emoji: m.emoji,
+                    description: m.description,
+                },
+            )
+        })
+        .collect();
+
+    let versions = player_versions
+        .into_iter()
+        .map(|player| {
+            let time = player.valid_from;
+
+            let modifications = includes.modifications.then(|| {
+                let mut modifications = vec![None; player.num_modifications as usize];
+                for version in modification_versions
+                    .iter()
+                    .filter(|m| is_valid_at(m.valid_from, m.valid_until, time))
+                {
+                    match modifications.get_mut(version.modification_index as usize) {
+                        Some(slot) => {
+                            *slot = modifications_table.get(&version.modification_id).cloned()
+                        }
+                        None => warn!(
+                            "player_modification_versions table had more modifications than \
+                            player_versions indicated"
+                        ),
+                    }
+                }
+                modifications
+            });
+
+            let equipment = includes.equipment.then(|| {
+                let mut equipment: HashMap<String, Option<ApiEquipment>> = player
+                    .occupied_equipment_slots
+                    .iter()
+                    .flatten()
+                    .map(|slot| (slot.clone(), None))
+                    .collect();
+                for eq in equipment_versions
+                    .iter()
+                    .filter(|e| is_valid_at(e.valid_from, e.valid_until, time))
+                {
+                    let effects = effect_versions
+                        .iter()
+                        .filter(|e| {
+                            e.equipment_slot == eq.equipment_slot
+                                && is_valid_at(e.valid_from, e.valid_until, time)
+                        })
+                        .try_fold(
+                            vec![None; eq.num_effects as usize],
+                            |mut effects, effect| {
+                                if let Some(effect_slot) =
+                                    effects.get_mut(effect.effect_index as usize)
+                                {
+                                    *effect_slot = Some(ApiEquipmentEffect {
+                                        attribute: taxa_name(
+                                            taxa.attribute_from_id(effect.attribute)?,
+                                        ),
+                                        effect_type: taxa_name(
+                                            taxa.effect_type_from_id(effect.effect_type)?,
+                                        ),
+                                        value: effect.value,
+                                    });
+                                }
+                                Ok::<_, UnknownTaxaId>(effects)
+                            },
+                        )?;
+
+                    equipment.insert(
+                        eq.equipment_slot.clone(),
+                        Some(ApiEquipment {
+                            emoji: eq.emoji.clone(),
+                            name: eq.name.clone(),
+                            special_type: eq.special_type.clone(),
+                            description: eq.description.clone(),
+                            rare_name: eq.rare_name.clone(),
+                            cost: eq.cost,
+                            prefixes: eq.prefixes.clone(),
+                            suffixes: eq.suffixes.clone(),
+                            rarity: eq.rarity.clone(),
+                            effects,
+                        }),
+                    );
+                }
+                Ok::<_, UnknownTaxaId>(equipment)
+            });
+            let equipment = equipment.transpose()?;
+
+            Ok::<_, UnknownTaxaId>(ApiPlayerVersionRow {
+                valid_from: player.valid_from,
+                valid_until: player.valid_until,
+                first_name: player.first_name,
+                last_name: player.last_name,
+                batting_handedness: player
+                    .batting_handedness
+                    .map(|h| taxa.handedness_from_id(h).map(taxa_name))
+                    .transpose()?,
+                pitching_handedness: player
+                    .pitching_handedness
+                    .map(|h| taxa.handedness_from_id(h).map(taxa_name))
+                    .transpose()?,
+                home: player.home,
+                birthseason: player.birthseason,
+                birthday_type: player
+                    .birthday_type
+                    .map(|d| taxa.day_type_from_id(d).map(taxa_name))
+                    .transpose()?,
+                birthday_day: player.birthday_day,
+                birthday_superstar_day: player.birthday_superstar_day,
+                likes: player.likes,
+                dislikes: player.dislikes,
+                number: player.number,
+                mmolb_team_id: player.mmolb_team_id,
+                slot: player
+                    .slot
+                    .map(|s| taxa.slot_from_id(s).map(taxa_name))
+                    .transpose()?,
+                durability: player.durability,
+                modifications,
+                equipment,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Json(ApiPlayerVersionsPage {
+        player_id,
+        versions,
+        next_since,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mmoldb_db::taxa::{
+        TaxaAttribute, TaxaAttributeCategory, TaxaDayType, TaxaEffectType, TaxaHandedness, TaxaSlot,
+    };
+    use rocket::serde::Serialize;
+    use rocket::serde::json::serde_json;
+    use strum::IntoEnumIterator;
+
+    fn assert_names_match_serialized<T>()
+    where
+        T: IntoEnumIterator + Into<&'static str> + Serialize + Copy,
+    {
+        for taxa in T::iter() {
+            assert_eq!(
+                serde_json::to_value(taxa).unwrap(),
+                serde_json::Value::String(taxa_name(taxa)),
+            );
+        }
+    }
+
+    /// These routes used to serialize the taxa enums themselves, so the
+    /// names they send now have to be the same
+    #[test]
+    fn taxa_names_match_their_serialized_form() {
+        assert_names_match_serialized::<TaxaAttribute>();
+        assert_names_match_serialized::<TaxaAttributeCategory>();
+        assert_names_match_serialized::<TaxaEffectType>();
+        assert_names_match_serialized::<TaxaDayType>();
+        assert_names_match_serialized::<TaxaHandedness>();
+        assert_names_match_serialized::<TaxaSlot>();
+    }
+}
@@ -0,0 +1,30 @@
+use crate::Db;
+use crate::api::error::ApiError;
+use mmoldb_db::db::{self, DbHealth, QualitySummary};
+use mmoldb_db::models::DbDataFreshness;
+use rocket::get;
+use rocket::serde::json::Json;
+
+/// The same database health numbers shown on `/status`, for monitoring
+#[get("/db_health")]
+pub async fn db_health(db: Db) -> Result<Json<DbHealth>, ApiError> {
+    let health = db.run(move |conn| db::db_health(conn)).await?;
+
+    Ok(Json(health))
+}
+
+/// How current each ingest kind's data is
+#[get("/freshness")]
+pub async fn freshness(db: Db) -> Result<Json<Vec<DbDataFreshness>>, ApiError> {
+    let freshness = db.run(move |conn| db::data_freshness(conn)).await?;
+
+    Ok(Json(freshness))
+}
+
+/// The same red/yellow/green statuses shown on `/quality`, for alerting
+#[get("/quality")]
+pub async fn quality(db: Db) -> Result<Json<QualitySummary>, ApiError> {
+    let summary = db.run(move |conn| db::quality_summary(conn)).await?;
+
+    Ok(Json(summary))
+}
@@ -0,0 +1,41 @@
+use itertools::Itertools;
+use mmoldb_client::types::{ApiTaxaRow, ApiTaxaTable, ApiTaxaTables};
+use mmoldb_db::taxa::taxa_table_definitions;
+use rocket::get;
+use rocket::serde::json::Json;
+
+/// Names of every taxa table, for `/api/taxa/<table>`
+#[get("/taxa")]
+pub async fn taxa_tables() -> Json<ApiTaxaTables> {
+    Json(ApiTaxaTables {
+        tables: taxa_table_definitions()
+            .into_iter()
+            .map(|definition| definition.table.to_string())
+            .collect_vec(),
+    })
+}
+
+/// Every row of `taxa.<table>`. These come from the same definitions the
+/// ingest writes the tables from, so new rows show up here as soon as
+/// they're added. Returns 404 if there's no such taxa table.
+#[get("/taxa/<table>")]
+pub async fn taxa_table(table: &str) -> Option<Json<ApiTaxaTable>> {
+    let definition = taxa_table_definitions()
+        .into_iter()
+        .find(|definition| definition.table == table)?;
+
+    Some(Json(ApiTaxaTable {
+        table: definition.table.to_string(),
+        rows: definition
+            .rows
+            .into_iter()
+            .sorted_by_key(|row| row.id)
+            .map(|row| ApiTaxaRow {
+                id: row.id,
+                name: row.name.to_string(),
+                description: row.description,
+                columns: row.columns,
+            })
+            .collect_vec(),
+    }))
+}
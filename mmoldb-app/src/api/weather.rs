@@ -0,0 +1,62 @@
+use crate::Db;
+use crate::api::error::ApiError;
+use crate::params::{ParamError, Season};
+use itertools::Itertools;
+use mmoldb_client::types::{
+    ApiSeasonWeatherGameCounts, ApiSeasonWeatherOutcomes, ApiWeatherGameCount, ApiWeatherOutcome,
+};
+use rocket::get;
+use rocket::serde::json::Json;
+
+#[get("/weather_outcomes/<season>?<weather>")]
+pub async fn weather_outcomes(
+    season: Result<Season, ParamError>,
+    weather: Option<String>,
+    db: Db,
+) -> Result<Json<ApiSeasonWeatherOutcomes>, ApiError> {
+    let season = season?.get();
+
+    let rows = db
+        .run(move |conn| mmoldb_db::db::weather_outcomes(conn, Some(season), weather.as_deref()))
+        .await?;
+
+    Ok(Json(ApiSeasonWeatherOutcomes {
+        season,
+        outcomes: rows
+            .into_iter()
+            .map(|row| ApiWeatherOutcome {
+                weather_id: row.weather,
+                weather_name: row.weather_name,
+                weather_emoji: row.weather_emoji,
+                event_type: row.event_type_name,
+                games: row.games,
+                events: row.events,
+            })
+            .collect_vec(),
+    }))
+}
+
+/// How many games were played under each weather in a season, most first
+#[get("/weather_game_counts/<season>")]
+pub async fn weather_game_counts(
+    season: Result<Season, ParamError>,
+    db: Db,
+) -> Result<Json<ApiSeasonWeatherGameCounts>, ApiError> {
+    let season = season?.get();
+
+    let rows = db
+        .run(move |conn| mmoldb_db::db::weather_game_counts(conn, Some(season)))
+        .await?;
+
+    Ok(Json(ApiSeasonWeatherGameCounts {
+        season,
+        counts: rows
+            .into_iter()
+            .map(|row| ApiWeatherGameCount {
+                weather_name: row.weather_name,
+                weather_emoji: row.weather_emoji,
+                games: row.games,
+            })
+            .collect_vec(),
+    }))
+}
@@ -0,0 +1,70 @@
+use crate::Db;
+use crate::api::error::ApiError;
+use itertools::Itertools;
+use mmoldb_client::types::{
+    ApiPlayerWeeklyRollup, ApiPlayerWeeklyRollups, ApiTeamWeeklyRollup, ApiTeamWeeklyRollups,
+};
+use rocket::get;
+use rocket::serde::json::Json;
+
+/// A team's totals for every week it finished a game in, for charts that
+/// span many seasons
+#[get("/team_weekly_rollups/<team_id>")]
+pub async fn team_weekly_rollups(
+    team_id: String,
+    db: Db,
+) -> Result<Json<ApiTeamWeeklyRollups>, ApiError> {
+    let rows = db
+        .run({
+            let team_id = team_id.clone();
+            move |conn| mmoldb_db::db::team_weekly_rollups(conn, &team_id)
+        })
+        .await?;
+
+    Ok(Json(ApiTeamWeeklyRollups {
+        team_id,
+        weeks: rows
+            .into_iter()
+            .map(|row| ApiTeamWeeklyRollup {
+                week_start: row.week_start,
+                games: row.games,
+                wins: row.wins,
+                runs_scored: row.runs_scored,
+                runs_allowed: row.runs_allowed,
+            })
+            .collect_vec(),
+    }))
+}
+
+/// A batter's totals for every week they batted for a team. Batters are
+/// identified by name and team because that's all the events record.
+#[get("/player_weekly_rollups/<team_id>?<batter>")]
+pub async fn player_weekly_rollups(
+    team_id: String,
+    batter: String,
+    db: Db,
+) -> Result<Json<ApiPlayerWeeklyRollups>, ApiError> {
+    let rows = db
+        .run({
+            let team_id = team_id.clone();
+            let batter = batter.clone();
+            move |conn| mmoldb_db::db::player_weekly_rollups(conn, &team_id, &batter)
+        })
+        .await?;
+
+    Ok(Json(ApiPlayerWeeklyRollups {
+        team_id,
+        batter_name: batter,
+        weeks: rows
+            .into_iter()
+            .map(|row| ApiPlayerWeeklyRollup {
+                week_start: row.week_start,
+                plate_appearances: row.plate_appearances,
+                hits: row.hits,
+                home_runs: row.home_runs,
+                walks: row.walks,
+                strikeouts: row.strikeouts,
+            })
+            .collect_vec(),
+    }))
+}
@@ -0,0 +1,71 @@
+use log::{error, info};
+use mmoldb_db::db::IngestProgressEvent;
+use mmoldb_db::{AsyncConnection, AsyncPgConnection, ConnectionError, QueryError, async_db};
+use rocket::futures::{StreamExt, pin_mut};
+use rocket::{Build, Rocket};
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::broadcast;
+
+/// How many events each subscriber can fall behind by before it starts
+/// missing events
+const PROGRESS_BUFFER_SIZE: usize = 256;
+const RECONNECT_DELAY: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Error)]
+enum IngestProgressBridgeError {
+    #[error(transparent)]
+    ConnectionError(#[from] ConnectionError),
+
+    #[error(transparent)]
+    QueryError(#[from] QueryError),
+}
+
+/// Re-broadcasts the ingest progress mmoldb-ingest publishes through
+/// Postgres to every open `/api/ingest/progress` stream
+pub struct IngestProgress {
+    sender: broadcast::Sender<IngestProgressEvent>,
+}
+
+impl IngestProgress {
+    pub fn subscribe(&self) -> broadcast::Receiver<IngestProgressEvent> {
+        self.sender.subscribe()
+    }
+}
+
+pub async fn init_ingest_progress(rocket: Rocket<Build>) -> Rocket<Build> {
+    let (sender, _) = broadcast::channel(PROGRESS_BUFFER_SIZE);
+
+    tokio::spawn(bridge_task(sender.clone()));
+
+    rocket.manage(IngestProgress { sender })
+}
+
+/// Forwards notifications to the broadcast channel forever, reconnecting
+/// whenever the listening connection fails
+async fn bridge_task(sender: broadcast::Sender<IngestProgressEvent>) {
+    loop {
+        if let Err(err) = bridge(&sender).await {
+            error!("Ingest progress listener failed: {err}");
+        }
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+async fn bridge(
+    sender: &broadcast::Sender<IngestProgressEvent>,
+) -> Result<(), IngestProgressBridgeError> {
+    let url = mmoldb_db::postgres_url_from_environment();
+    let mut conn = AsyncPgConnection::establish(&url).await?;
+
+    let events = async_db::listen_for_ingest_progress(&mut conn).await?;
+    pin_mut!(events);
+    info!("Listening for ingest progress");
+
+    while let Some(event) = events.next().await {
+        // A send error just means nobody is subscribed right now
+        let _ = sender.send(event?);
+    }
+
+    Ok(())
+}
@@ -1,4 +1,7 @@
+mod admin;
 mod api;
+mod ingest_progress;
+mod params;
 mod records_cache;
 mod web;
 
@@ -72,6 +75,16 @@ fn rocket() -> _ {
         .attach(Db::fairing())
         .attach(AdHoc::on_ignite("Migrations", run_migrations))
         .attach(AdHoc::on_ignite("Records", init_records))
+        .attach(AdHoc::on_ignite(
+            "Ingest progress",
+            ingest_progress::init_ingest_progress,
+        ))
+        .attach(AdHoc::on_ignite(
+            "Offloaded games",
+            api::game::init_offloaded_game_store,
+        ))
+        .attach(AdHoc::config::<admin::AdminConfig>())
+        .attach(AdHoc::config::<api::export::AnalyticsExportConfig>())
 }
 
 #[cfg(test)]
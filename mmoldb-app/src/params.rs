@@ -0,0 +1,172 @@
+use miette::Diagnostic;
+use rocket::http::uri::fmt::{Formatter, FromUriParam, Path, UriDisplay};
+use rocket::http::impl_from_uri_param_identity;
+use rocket::request::FromParam;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// MMOLB ids (games, players, teams, etc.) are 24-character hex strings
+const MMOLB_ID_LEN: usize = 24;
+
+/// Latest season we will accept in a url. This is well past any season
+/// that exists, it's just here to catch obvious typos.
+const MAX_SEASON: i32 = 1000;
+
+#[derive(Debug, Error, Diagnostic)]
+pub enum ParamError {
+    #[error(
+        "{0:?} is not a valid MMOLB id. MMOLB ids are {MMOLB_ID_LEN} hexadecimal characters, \
+        like 6805db0cac48194de3cd3fe4."
+    )]
+    InvalidMmolbId(String),
+
+    #[error("{0:?} is not a valid season. Seasons are whole numbers between 0 and {MAX_SEASON}.")]
+    InvalidSeason(String),
+
+    #[error("{value:?} is not a valid value for {param}")]
+    InvalidValue { param: &'static str, value: String },
+}
+
+/// An id for any MMOLB entity. Also used as the page cursor for
+/// paginated lists, which are keyed on MMOLB ids.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MmolbId(String);
+
+impl MmolbId {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl FromStr for MmolbId {
+    type Err = ParamError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() == MMOLB_ID_LEN && s.bytes().all(|b| b.is_ascii_hexdigit()) {
+            // Ids are always lowercase in the database
+            Ok(MmolbId(s.to_ascii_lowercase()))
+        } else {
+            Err(ParamError::InvalidMmolbId(s.to_string()))
+        }
+    }
+}
+
+impl<'a> FromParam<'a> for MmolbId {
+    type Error = ParamError;
+
+    fn from_param(param: &'a str) -> Result<Self, Self::Error> {
+        param.parse()
+    }
+}
+
+impl UriDisplay<Path> for MmolbId {
+    fn fmt(&self, f: &mut Formatter<'_, Path>) -> fmt::Result {
+        UriDisplay::fmt(self.as_str(), f)
+    }
+}
+
+impl_from_uri_param_identity!([Path] MmolbId);
+
+impl<'a> FromUriParam<Path, &'a str> for MmolbId {
+    type Target = &'a str;
+
+    fn from_uri_param(param: &'a str) -> &'a str {
+        param
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Season(i32);
+
+impl Season {
+    pub fn get(self) -> i32 {
+        self.0
+    }
+}
+
+impl FromStr for Season {
+    type Err = ParamError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse::<i32>() {
+            Ok(season) if (0..=MAX_SEASON).contains(&season) => Ok(Season(season)),
+            _ => Err(ParamError::InvalidSeason(s.to_string())),
+        }
+    }
+}
+
+impl<'a> FromParam<'a> for Season {
+    type Error = ParamError;
+
+    fn from_param(param: &'a str) -> Result<Self, Self::Error> {
+        param.parse()
+    }
+}
+
+/// Rocket's `Option<T>` query params silently turn parse failures into
+/// `None`, which would show the wrong page instead of an error. Routes
+/// take the raw value and parse it with this instead.
+pub fn parse_optional<T: FromStr>(param: Option<&str>) -> Result<Option<T>, T::Err> {
+    param.map(str::parse).transpose()
+}
+
+/// Like [`parse_optional`], for values that come from an HTML form.
+/// Forms submit empty inputs as empty strings, so those are treated the
+/// same as a missing value.
+pub fn parse_form_field<T: FromStr>(
+    param: &'static str,
+    value: Option<&str>,
+) -> Result<Option<T>, ParamError> {
+    value
+        .filter(|value| !value.is_empty())
+        .map(|value| {
+            value.parse().map_err(|_| ParamError::InvalidValue {
+                param,
+                value: value.to_string(),
+            })
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mmolb_id_validation() {
+        assert!("6805db0cac48194de3cd3fe4".parse::<MmolbId>().is_ok());
+        assert_eq!(
+            "6805DB0CAC48194DE3CD3FE4".parse::<MmolbId>().unwrap().as_str(),
+            "6805db0cac48194de3cd3fe4",
+        );
+        assert!("6805db0cac48194de3cd3fe".parse::<MmolbId>().is_err());
+        assert!("6805db0cac48194de3cd3fez".parse::<MmolbId>().is_err());
+        assert!("".parse::<MmolbId>().is_err());
+    }
+
+    #[test]
+    fn season_validation() {
+        assert_eq!("0".parse::<Season>().unwrap().get(), 0);
+        assert_eq!("13".parse::<Season>().unwrap().get(), 13);
+        assert!("-1".parse::<Season>().is_err());
+        assert!("thirteen".parse::<Season>().is_err());
+        assert!(parse_optional::<Season>(None).unwrap().is_none());
+        assert!(parse_optional::<Season>(Some("x")).is_err());
+    }
+
+    #[test]
+    fn form_field_parsing() {
+        assert_eq!(parse_form_field::<i32>("day_min", None).unwrap(), None);
+        assert_eq!(parse_form_field::<i32>("day_min", Some("")).unwrap(), None);
+        assert_eq!(parse_form_field::<i32>("day_min", Some("12")).unwrap(), Some(12));
+        assert!(matches!(
+            parse_form_field::<i32>("day_min", Some("twelve")),
+            Err(ParamError::InvalidValue { param: "day_min", .. }),
+        ));
+    }
+}
@@ -3,8 +3,10 @@ use diesel::Connection;
 use itertools::Itertools;
 use log::error;
 use mmoldb_db::ConnectionPool;
+use mmoldb_db::db::TeamIdentityPolicy;
 use mmoldb_db::taxa::TaxaAttribute;
 use serde::Serialize;
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use strum::IntoEnumIterator;
 use thiserror::Error;
@@ -63,6 +65,11 @@ pub enum RecordHolder {
         mmolb_game_id: String,
         game_event_index: i32,
     },
+    WeatherInSeason {
+        season: i32,
+        weather_name: String,
+        weather_emoji: String,
+    },
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -99,7 +106,20 @@ enum RecordsCacheUpdate {
     Error(ComputeRecordsError),
 }
 
-fn update_all_records(pool: ConnectionPool) -> Result<Records, ComputeRecordsError> {
+/// Records are computed once for every team identity policy, so the page
+/// can switch between them without waiting for a recompute
+type RecordsByPolicy = HashMap<TeamIdentityPolicy, Records>;
+
+fn update_all_records(pool: ConnectionPool) -> Result<RecordsByPolicy, ComputeRecordsError> {
+    TeamIdentityPolicy::iter()
+        .map(|policy| Ok((policy, update_records(&pool, policy)?)))
+        .collect()
+}
+
+fn update_records(
+    pool: &ConnectionPool,
+    team_identity: TeamIdentityPolicy,
+) -> Result<Records, ComputeRecordsError> {
     let mut conn = pool.get()?;
 
     let latest_game = (*conn).transaction(mmoldb_db::db::latest_game)?.map(
@@ -112,7 +132,7 @@ fn update_all_records(pool: ConnectionPool) -> Result<Records, ComputeRecordsErr
     );
 
     let fastest_pitch = (*conn)
-        .transaction(mmoldb_db::db::fastest_pitch)?
+        .transaction(|c| mmoldb_db::db::fastest_pitch(c, team_identity))?
         .map(|r| Record {
             title: "Fastest Pitch".to_string(),
             description: None,
@@ -130,7 +150,7 @@ fn update_all_records(pool: ConnectionPool) -> Result<Records, ComputeRecordsErr
         });
 
     let most_pitches_by_player_in_one_game = (*conn)
-        .transaction(mmoldb_db::db::most_pitches_by_player_in_one_game)?
+        .transaction(|c| mmoldb_db::db::most_pitches_by_player_in_one_game(c, team_identity))?
         .map(|r| Record {
             title: "Most pitches by a pitcher in one game".to_string(),
             description: Some("Including balks as pitches"),
@@ -231,7 +251,9 @@ fn update_all_records(pool: ConnectionPool) -> Result<Records, ComputeRecordsErr
     let attribute_records = TaxaAttribute::iter()
         .map(|attr| {
             (*conn)
-                .transaction(|c| mmoldb_db::db::highest_reported_attribute(c, attr.into()))
+                .transaction(|c| {
+                    mmoldb_db::db::highest_reported_attribute(c, attr.into(), team_identity)
+                })
                 .map(|r| {
                     r.map(|r| Record {
                         title: format!("Highest reported {attr} stars"),
@@ -253,6 +275,29 @@ fn update_all_records(pool: ConnectionPool) -> Result<Records, ComputeRecordsErr
         .filter_map_ok(|record| record)
         .collect::<Result<Vec<_>, _>>()?;
 
+    let weather_records = (*conn)
+        .transaction(|c| mmoldb_db::db::weather_game_counts(c, None))?
+        .into_iter()
+        // Ties go to the earliest season
+        .sorted_by(|a, b| {
+            (&a.weather_name, b.games, a.season).cmp(&(&b.weather_name, a.games, b.season))
+        })
+        .dedup_by(|a, b| a.weather_name == b.weather_name)
+        .map(|r| Record {
+            title: format!(
+                "Most games under {} {} in a season",
+                r.weather_emoji, r.weather_name
+            ),
+            description: None,
+            record: format!("{} games", r.games),
+            holder: RecordHolder::WeatherInSeason {
+                season: r.season,
+                weather_name: r.weather_name,
+                weather_emoji: r.weather_emoji,
+            },
+        })
+        .collect_vec();
+
     let records = [
         fastest_pitch,
         most_pitches_by_player_in_one_game,
@@ -268,12 +313,16 @@ fn update_all_records(pool: ConnectionPool) -> Result<Records, ComputeRecordsErr
             .into_iter()
             .filter_map(|record| record)
             .chain(attribute_records)
+            .chain(weather_records)
             .collect(),
     })
 }
 
 impl RecordsCacheUpdate {
-    pub fn new(pool: ConnectionPool, records: Arc<Mutex<Option<Records>>>) -> Arc<Mutex<Self>> {
+    pub fn new(
+        pool: ConnectionPool,
+        records: Arc<Mutex<Option<RecordsByPolicy>>>,
+    ) -> Arc<Mutex<Self>> {
         let update = Arc::new(Mutex::new(RecordsCacheUpdate::None));
         let update_for_task = update.clone();
         let task = tokio::task::spawn_blocking(move || {
@@ -325,7 +374,7 @@ impl RecordsCacheUpdate {
 
 pub struct RecordsCache {
     active_update: Arc<Mutex<RecordsCacheUpdate>>,
-    latest_records: Arc<Mutex<Option<Records>>>,
+    latest_records: Arc<Mutex<Option<RecordsByPolicy>>>,
 }
 
 impl RecordsCache {
@@ -338,9 +387,12 @@ impl RecordsCache {
         }
     }
 
-    pub fn latest(&self) -> Option<Records> {
+    pub fn latest(&self, team_identity: TeamIdentityPolicy) -> Option<Records> {
         let records = self.latest_records.lock().expect("Error locking records");
-        records.clone()
+        records
+            .as_ref()
+            .and_then(|records| records.get(&team_identity))
+            .cloned()
     }
 
     pub fn update_date(&self) -> Option<DateTime<Utc>> {
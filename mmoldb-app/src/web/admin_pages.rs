@@ -0,0 +1,108 @@
+use super::pages::*;
+use crate::Db;
+use crate::admin::Admin;
+use crate::web::error::AppError;
+use crate::web::utility_contexts::FormattedDateContext;
+use mmoldb_db::db;
+use rocket::{get, uri};
+use rocket_dyn_templates::{Template, context};
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct ExplainableQueryContext {
+    name: &'static str,
+    params: &'static [&'static str],
+    sql: &'static str,
+}
+
+impl From<&'static db::ExplainableQuery> for ExplainableQueryContext {
+    fn from(query: &'static db::ExplainableQuery) -> Self {
+        Self {
+            name: query.name,
+            params: query.params,
+            sql: query.sql,
+        }
+    }
+}
+
+/// How many entries the audit page shows
+const ADMIN_AUDIT_PAGE_SIZE: i64 = 200;
+
+#[derive(Serialize)]
+struct AdminAuditContext {
+    at: FormattedDateContext,
+    actor: String,
+    action: String,
+    details: String,
+    before: Option<String>,
+    after: Option<String>,
+}
+
+#[get("/admin/audit")]
+pub async fn audit_page(_admin: Admin, db: Db) -> Result<Template, AppError> {
+    let entries = db
+        .run(move |conn| db::admin_audit_log(conn, ADMIN_AUDIT_PAGE_SIZE))
+        .await?
+        .into_iter()
+        .map(|entry| AdminAuditContext {
+            at: FormattedDateContext::from(&entry.at.naive_utc()),
+            actor: entry.actor,
+            action: entry.action,
+            details: entry.details.to_string(),
+            before: entry.before.map(|v| v.to_string()),
+            after: entry.after.map(|v| v.to_string()),
+        })
+        .collect::<Vec<_>>();
+
+    Ok(Template::render(
+        "admin_audit",
+        context! {
+            index_url: uri!(index_page()),
+            entries: entries,
+        },
+    ))
+}
+
+#[get("/admin/explain")]
+pub async fn explain_index_page(_admin: Admin) -> Result<Template, AppError> {
+    let queries = db::EXPLAINABLE_QUERIES
+        .iter()
+        .map(ExplainableQueryContext::from)
+        .collect::<Vec<_>>();
+
+    Ok(Template::render(
+        "admin_explain",
+        context! {
+            index_url: uri!(index_page()),
+            queries: queries,
+        },
+    ))
+}
+
+/// Parameters are given in order as repeated `param` query params, e.g.
+/// `/admin/explain/highest_reported_attribute?param=Contact&param=latest`
+#[get("/admin/explain/<query_name>?<param>")]
+pub async fn explain_page(
+    _admin: Admin,
+    query_name: String,
+    param: Vec<String>,
+    db: Db,
+) -> Result<Template, AppError> {
+    let query = db::explainable_query(&query_name)
+        .ok_or_else(|| db::ExplainError::UnknownQuery(query_name.clone()))?;
+
+    let params = param.clone();
+    let plan = db
+        .run(move |conn| db::explain_query(conn, &query_name, &params))
+        .await?;
+
+    Ok(Template::render(
+        "admin_explain",
+        context! {
+            index_url: uri!(index_page()),
+            query: ExplainableQueryContext::from(query),
+            param_values: param,
+            plan: plan.join("\n"),
+        },
+    ))
+}
@@ -5,9 +5,13 @@ use include_dir::{Dir, include_dir};
 use itertools::Itertools;
 use miette::Diagnostic;
 use mmoldb_db::db;
+use mmoldb_db::taxa::taxa_table_definitions;
+use rocket::serde::json::{Json, serde_json};
 use rocket::{get, uri};
 use rocket_dyn_templates::{Template, context};
 use serde::{Deserialize, Serialize};
+use diesel::PgConnection;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use thiserror::Error;
 
@@ -66,15 +70,7 @@ pub async fn docs_page() -> Result<Template, AppError> {
                 .to_str()
                 .ok_or(DocsError::NonUnicodeFileName(entry.path().to_path_buf()))?;
 
-            let file_contents = entry
-                .as_file()
-                .ok_or_else(|| DocsError::DocsFileMissing(entry.path().to_path_buf()))?
-                .contents();
-
-            let docs: SchemaDocs =
-                toml::from_slice(file_contents).map_err(DocsError::CouldntDeserializeDocsFile)?;
-
-            Ok::<_, DocsError>((name, docs))
+            Ok::<_, DocsError>((name, load_schema_docs(name)?))
         })
         .map_ok(|(name, schema)| Schema {
             display_order: schema.display_order,
@@ -111,6 +107,7 @@ pub async fn docs_page() -> Result<Template, AppError> {
         context! {
             index_url: uri!(index_page()),
             pages: &*PAGES,
+            reference_url: uri!(docs_reference_page()),
             schemata: schemata,
         },
     ))
@@ -150,6 +147,169 @@ pub async fn docs_debug_page(table_name: String, db: Db) -> Result<Template, App
     ))
 }
 
+/// Name of the database catalog that holds MMOLDB's schemas
+const CATALOG_NAME: &str = "mmoldb";
+
+/// Loads a schema's hand-written docs. For the `taxa` schema, columns that
+/// the taxa definitions describe (including the `description` column every
+/// taxa table has) are added to the docs unless they're already written out.
+fn load_schema_docs(schema_name: &str) -> Result<SchemaDocs, DocsError> {
+    let filename = format!("{schema_name}.toml");
+    let file = SCHEMA_DOCS_DIR
+        .get_file(&filename)
+        .ok_or_else(|| DocsError::DocsFileMissing(filename.into()))?;
+
+    let mut docs: SchemaDocs =
+        toml::from_slice(file.contents()).map_err(DocsError::CouldntDeserializeDocsFile)?;
+    if schema_name == "taxa" {
+        add_taxa_column_docs(&mut docs);
+    }
+    Ok(docs)
+}
+
+fn add_taxa_column_docs(docs: &mut SchemaDocs) {
+    for definition in taxa_table_definitions() {
+        let Some(table_docs) = docs.tables.iter_mut().find(|t| t.name == definition.table) else {
+            continue;
+        };
+
+        for column in definition.columns {
+            let Some(description) = column.description else {
+                continue;
+            };
+            if table_docs.columns.iter().any(|c| c.name == column.name) {
+                continue;
+            }
+
+            table_docs.columns.push(ColumnDocs {
+                name: column.name.to_string(),
+                r#type: column.sql_type.to_string(),
+                description: description.to_string(),
+                nullable_explanation: column.nullable_explanation.map(str::to_string),
+                is_non_nullable_view_field: false,
+            });
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ColumnReference {
+    pub name: String,
+    pub r#type: String,
+    pub is_nullable: bool,
+    /// Markdown
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TableReference {
+    pub name: String,
+    /// Markdown
+    pub description: Option<String>,
+    pub columns: Vec<ColumnReference>,
+    /// Every row of the table. Only present for tables in the `taxa`
+    /// schema, which are small and rarely change.
+    pub rows: Option<Vec<serde_json::Value>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SchemaReference {
+    pub name: String,
+    /// Markdown
+    pub description: String,
+    pub tables: Vec<TableReference>,
+}
+
+/// Merges the hand-written docs for every documented schema with the
+/// columns the database actually has, plus the contents of every taxa
+/// table. Tables that only exist in the docs (i.e. materialized views)
+/// are left out.
+fn schema_reference(conn: &mut PgConnection) -> Result<Vec<SchemaReference>, AppError> {
+    let mut schema_names = SCHEMA_DOCS_DIR
+        .files()
+        .filter_map(|file| file.path().file_stem()?.to_str())
+        .map(|name| Ok::<_, DocsError>((name, load_schema_docs(name)?)))
+        .collect::<Result<Vec<_>, _>>()?;
+    schema_names.sort_by_key(|(_, docs)| docs.display_order.unwrap_or(usize::MAX));
+
+    let mut taxa_rows = db::taxa_table_rows(conn, CATALOG_NAME)?
+        .into_iter()
+        .collect::<HashMap<_, _>>();
+
+    schema_names
+        .into_iter()
+        .map(|(schema_name, docs)| {
+            let tables = db::tables_for_schema(conn, CATALOG_NAME, schema_name)?
+                .into_iter()
+                .map(|table| {
+                    let table_docs = docs.tables.iter().find(|t| t.name == table.name);
+                    let rows = if schema_name == "taxa" {
+                        taxa_rows.remove(&table.name)
+                    } else {
+                        None
+                    };
+
+                    TableReference {
+                        description: table_docs.map(|t| t.description.clone()),
+                        columns: table
+                            .columns
+                            .into_iter()
+                            .map(|column| ColumnReference {
+                                description: table_docs
+                                    .and_then(|t| t.columns.iter().find(|c| c.name == column.name))
+                                    .map(|c| c.description.clone()),
+                                name: column.name,
+                                r#type: column.r#type,
+                                is_nullable: column.is_nullable,
+                            })
+                            .collect(),
+                        name: table.name,
+                        rows,
+                    }
+                })
+                .collect();
+
+            Ok(SchemaReference {
+                name: schema_name.to_string(),
+                description: docs.description,
+                tables,
+            })
+        })
+        .collect()
+}
+
+#[get("/docs/schema")]
+pub async fn docs_reference_page(db: Db) -> Result<Template, AppError> {
+    let mut schemata = db.run(schema_reference).await?;
+
+    for schema in &mut schemata {
+        schema.description = markdown::to_html(&schema.description);
+        for table in &mut schema.tables {
+            table.description = table.description.as_deref().map(markdown::to_html);
+            for column in &mut table.columns {
+                column.description = column.description.as_deref().map(markdown::to_html);
+            }
+        }
+    }
+
+    Ok(Template::render(
+        "docs_reference",
+        context! {
+            index_url: uri!(index_page()),
+            pages: &*PAGES,
+            json_url: uri!(docs_reference_json()),
+            schemata: schemata,
+        },
+    ))
+}
+
+/// Machine-readable version of [`docs_reference_page`]. Descriptions are
+/// left as markdown.
+#[get("/docs/schema.json")]
+pub async fn docs_reference_json(db: Db) -> Result<Json<Vec<SchemaReference>>, AppError> {
+    Ok(Json(db.run(schema_reference).await?))
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ColumnDocs {
     pub name: String,
@@ -225,21 +385,41 @@ mod tests {
         };
     }
 
+    #[test]
+    fn taxa_docs_include_the_columns_the_definitions_describe() {
+        let docs = load_schema_docs("taxa").expect("Couldn't load taxa docs file");
+
+        for definition in taxa_table_definitions() {
+            let table = docs
+                .tables
+                .iter()
+                .find(|t| t.name == definition.table)
+                .expect("every taxa table should be documented");
+            let description = table
+                .columns
+                .iter()
+                .find(|c| c.name == "description")
+                .expect("every taxa table should document its description column");
+            assert_eq!(description.r#type, "text");
+            assert!(description.nullable_explanation.is_some());
+        }
+
+        let event_type = docs.tables.iter().find(|t| t.name == "event_type").unwrap();
+        assert!(
+            event_type
+                .columns
+                .iter()
+                .any(|c| c.name == "is_basic_strike")
+        );
+    }
+
     test_schema_docs!(taxa);
     test_schema_docs!(data);
     test_schema_docs!(info);
 
     // The lifetime could be relaxed from 'static but i'm not sure how
     async fn check_schema_docs(schema_name: &'static str) {
-        let filename = format!("{}.toml", schema_name);
-        let file = SCHEMA_DOCS_DIR
-            .get_file(&filename)
-            .expect(&format!("Missing docs file for schema {schema_name}"));
-
-        let file_contents = file.contents();
-
-        let docs: SchemaDocs =
-            toml::from_slice(file_contents).expect("Couldn't deserialize schema docs file");
+        let docs = load_schema_docs(schema_name).expect("Couldn't load schema docs file");
 
         let pool = mmoldb_db::get_pool(1).expect("failed to initialize database pool");
         let mut conn = pool
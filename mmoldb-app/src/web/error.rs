@@ -1,8 +1,10 @@
+use crate::params::ParamError;
 use crate::web::docs_pages::DocsError;
 use crate::web::pages::rocket_uri_macro_index_page;
 use log::error;
 use miette::Diagnostic;
-use mmoldb_db::db::QueryDeserializeError;
+use mmoldb_db::db::{ExplainError, QueryDeserializeError};
+use mmoldb_db::taxa::UnknownTaxaId;
 use rocket::http::Status;
 use rocket::response::Responder;
 use rocket::serde::json::serde_json;
@@ -27,6 +29,30 @@ pub enum AppError {
 
     #[error(transparent)]
     DocsError(#[from] DocsError),
+
+    #[error(transparent)]
+    BadRequest(#[from] ParamError),
+
+    #[error(transparent)]
+    ExplainError(#[from] ExplainError),
+
+    #[error(transparent)]
+    UnknownTaxaId(#[from] UnknownTaxaId),
+
+    #[error("The raw data for this game has been moved to object storage ({0}).")]
+    GameOffloaded(String),
+}
+
+impl AppError {
+    fn status(&self) -> Status {
+        match self {
+            AppError::BadRequest(_) => Status::BadRequest,
+            AppError::ExplainError(ExplainError::UnknownQuery(_)) => Status::NotFound,
+            AppError::ExplainError(ExplainError::WrongNumberOfParams { .. }) => Status::BadRequest,
+            AppError::GameOffloaded(_) => Status::Gone,
+            _ => Status::InternalServerError,
+        }
+    }
 }
 
 impl From<QueryDeserializeError> for AppError {
@@ -34,13 +60,17 @@ impl From<QueryDeserializeError> for AppError {
         match value {
             QueryDeserializeError::Query(err) => AppError::DbError(err),
             QueryDeserializeError::Deserialize(err) => AppError::SerdeError(err),
+            QueryDeserializeError::Offloaded(key) => AppError::GameOffloaded(key),
         }
     }
 }
 
 impl<'r, 'o: 'r> Responder<'r, 'o> for AppError {
     fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'o> {
-        error!("{:#?}", self);
+        let status = self.status();
+        if status.class().is_server_error() {
+            error!("{:#?}", self);
+        }
 
         let is_debug = req.rocket().config().profile == "debug";
 
@@ -56,7 +86,7 @@ impl<'r, 'o: 'r> Responder<'r, 'o> for AppError {
         .unwrap();
 
         Response::build()
-            .status(Status::InternalServerError)
+            .status(status)
             .header(rocket::http::ContentType::HTML)
             .sized_body(rendered.len(), std::io::Cursor::new(rendered))
             .ok()
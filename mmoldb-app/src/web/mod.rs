@@ -1,7 +1,9 @@
+mod admin_pages;
 mod docs_pages;
 mod error;
 mod pages;
 mod player_pages;
+mod team_pages;
 mod utility_contexts;
 mod plots;
 
@@ -10,22 +12,34 @@ pub fn routes() -> Vec<rocket::Route> {
         pages::index_page,
         pages::status_page,
         pages::health_page,
+        pages::quality_page,
+        pages::version_chains_page,
         docs_pages::docs_page,
+        docs_pages::docs_reference_page,
+        docs_pages::docs_reference_json,
         docs_pages::docs_schema_page,
         docs_pages::docs_debug_page,
         pages::games_page,
         pages::paginated_games_page,
         pages::games_with_issues_page,
         pages::paginated_games_with_issues_page,
+        pages::games_search_page,
         pages::debug_no_games_page,
         pages::game_page,
         pages::debug_always_error_page,
         pages::records_page,
+        pages::leaderboards_page,
         pages::games_progress_plot,
         pages::player_versions_progress_plot,
         pages::player_feed_event_versions_progress_plot,
         pages::team_versions_progress_plot,
         pages::team_feed_event_versions_progress_plot,
         player_pages::player,
+        player_pages::player_matchups,
+        team_pages::team_roster,
+        team_pages::team_head_to_head,
+        admin_pages::audit_page,
+        admin_pages::explain_index_page,
+        admin_pages::explain_page,
     ]
 }
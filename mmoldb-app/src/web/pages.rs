@@ -1,6 +1,8 @@
 use std::fmt::Debug;
+use std::ops::Range;
 use super::docs_pages::*;
 use crate::Db;
+use crate::params::{self, MmolbId, ParamError, Season};
 use crate::records_cache::{Record, RecordsCache};
 use crate::web::error::AppError;
 use crate::web::utility_contexts::{DayContext, FormattedDateContext, GameContext};
@@ -13,10 +15,13 @@ use mmoldb_db::db::{GamesStats, PlayersStats, TeamsStats};
 use mmoldb_db::models::DbEventIngestLog;
 use num_format::{Locale, ToFormattedString};
 use rocket::http::uri::Origin;
-use rocket::{State, get, uri};
+use itertools::Itertools;
+use rocket::http::RawStr;
+use rocket::{FromForm, State, get, uri};
 use rocket::http::ContentType;
 use rocket_dyn_templates::{Template, context};
 use serde::Serialize;
+use strum::IntoEnumIterator;
 
 const PAGE_OF_GAMES_SIZE: usize = 100;
 
@@ -33,17 +38,46 @@ impl NavPage<'_> {
 }
 
 lazy_static! {
-    pub static ref PAGES: [NavPage<'static>; 5] = [
+    pub static ref PAGES: [NavPage<'static>; 7] = [
         NavPage::new("Home", uri!(index_page())),
         NavPage::new("Status", uri!(status_page())),
         NavPage::new("Health", uri!(health_page())),
+        NavPage::new("Quality", uri!(quality_page())),
         NavPage::new("Docs", uri!(docs_page())),
-        NavPage::new("Records", uri!(records_page())),
+        NavPage::new("Records", uri!(records_page(team_names = _))),
+        NavPage::new("Leaderboards", uri!(leaderboards_page(season = _))),
     ];
 }
 
-#[get("/game/<mmolb_game_id>")]
-pub async fn game_page(mmolb_game_id: String, db: Db) -> Result<Template, AppError> {
+/// How many events are shown on either side of the linked event
+const GAME_EVENT_WINDOW_RADIUS: usize = 50;
+/// Games with more events than this are shown one window at a time even
+/// when no event is linked
+const MAX_GAME_EVENTS_WITHOUT_WINDOW: usize = 600;
+
+/// The events to show, if the game is shown one window at a time
+fn game_event_window(event: Option<usize>, num_events: usize) -> Option<Range<usize>> {
+    if event.is_none() && num_events <= MAX_GAME_EVENTS_WITHOUT_WINDOW {
+        return None;
+    }
+
+    let center = event.unwrap_or(0).min(num_events.saturating_sub(1));
+    let start = center.saturating_sub(GAME_EVENT_WINDOW_RADIUS);
+    let end = (center + GAME_EVENT_WINDOW_RADIUS + 1).min(num_events);
+    Some(start..end)
+}
+
+/// Renders the whole game, or only the events around `event` if it's given
+/// or the game is very long. Links to a specific event look like
+/// `/game/<id>?event=<n>#event-<n>`.
+#[get("/game/<mmolb_game_id>?<event>")]
+pub async fn game_page(
+    mmolb_game_id: Result<MmolbId, ParamError>,
+    event: Option<usize>,
+    db: Db,
+) -> Result<Template, AppError> {
+    let mmolb_game_id = mmolb_game_id?.into_inner();
+
     #[derive(Serialize)]
     struct LogContext {
         level: &'static str,
@@ -53,15 +87,7 @@ pub async fn game_page(mmolb_game_id: String, db: Db) -> Result<Template, AppErr
     impl From<DbEventIngestLog> for LogContext {
         fn from(value: DbEventIngestLog) -> Self {
             LogContext {
-                level: match value.log_level {
-                    0 => "critical",
-                    1 => "error",
-                    2 => "warning",
-                    3 => "info",
-                    4 => "debug",
-                    5 => "trace",
-                    _ => "unknown",
-                },
+                level: db::log_level_name(value.log_level),
                 text: value.log_text,
             }
         }
@@ -70,15 +96,28 @@ pub async fn game_page(mmolb_game_id: String, db: Db) -> Result<Template, AppErr
     #[derive(Serialize)]
     struct EventContext {
         game_event_index: usize,
+        permalink: String,
         text: String,
         logs: Vec<LogContext>,
     }
 
+    #[derive(Serialize)]
+    struct EventWindowContext {
+        first_event_index: usize,
+        last_event_index: usize,
+        num_events: usize,
+        /// None if the game is too long to show all at once
+        full_game_url: Option<String>,
+        previous_window_url: Option<String>,
+        next_window_url: Option<String>,
+    }
+
     #[derive(Serialize)]
     struct GameContext {
         id: String,
         watch_uri: String,
         api_uri: String,
+        raw_json_uri: String,
         season: i32,
         day: DayContext,
         away_team_emoji: String,
@@ -89,20 +128,58 @@ pub async fn game_page(mmolb_game_id: String, db: Db) -> Result<Template, AppErr
         home_team_mmolb_id: String,
         game_wide_logs: Vec<LogContext>,
         events: Vec<EventContext>,
+        window: Option<EventWindowContext>,
     }
 
+    // Only the events in the window are loaded
     let full_game = db
-        .run(move |conn| db::game_and_raw_events(conn, &mmolb_game_id))
+        .run(move |conn| {
+            db::game_and_raw_events(conn, &mmolb_game_id, |num_events| {
+                game_event_window(event, num_events).unwrap_or(0..num_events)
+            })
+        })
         .await?;
     let watch_uri = format!("https://mmolb.com/watch/{}", full_game.game.mmolb_game_id);
     let api_uri = format!(
         "https://mmolb.com/api/game/{}",
         full_game.game.mmolb_game_id
     );
+    let raw_json_uri = format!("/api/games/{}/raw.json", full_game.game.mmolb_game_id);
+    let game_id = full_game.game.mmolb_game_id;
+    let window_url = |center: usize| uri!(game_page(game_id.as_str(), Some(center))).to_string();
+    let event_url = |index: usize| format!("{}#event-{index}", window_url(index));
+
+    let num_events = full_game.num_events;
+    let events = full_game
+        .events
+        .clone()
+        .zip(full_game.raw_events_with_logs)
+        .map(|(game_event_index, (raw_event, logs))| EventContext {
+            game_event_index,
+            permalink: event_url(game_event_index),
+            text: raw_event.message,
+            logs: logs.into_iter().map(Into::into).collect(),
+        })
+        .collect();
+
+    let window = game_event_window(event, num_events).map(|w| EventWindowContext {
+        first_event_index: w.start,
+        last_event_index: w.end.saturating_sub(1),
+        num_events,
+        full_game_url: (num_events <= MAX_GAME_EVENTS_WITHOUT_WINDOW)
+            .then(|| uri!(game_page(game_id.as_str(), _)).to_string()),
+        // Each adjacent window is centered so that it ends (or starts) right
+        // next to this one
+        previous_window_url: (w.start > 0)
+            .then(|| window_url(w.start.saturating_sub(GAME_EVENT_WINDOW_RADIUS + 1))),
+        next_window_url: (w.end < num_events).then(|| window_url(w.end + GAME_EVENT_WINDOW_RADIUS)),
+    });
+
     let game = GameContext {
-        id: full_game.game.mmolb_game_id,
+        id: game_id.clone(),
         watch_uri,
         api_uri,
+        raw_json_uri,
         season: full_game.game.season,
         day: (full_game.game.day, full_game.game.superstar_day).into(),
         away_team_emoji: full_game.game.away_team_emoji,
@@ -116,16 +193,8 @@ pub async fn game_page(mmolb_game_id: String, db: Db) -> Result<Template, AppErr
             .into_iter()
             .map(Into::into)
             .collect(),
-        events: full_game
-            .raw_events_with_logs
-            .into_iter()
-            .enumerate()
-            .map(|(game_event_index, (raw_event, logs))| EventContext {
-                game_event_index,
-                text: raw_event.message,
-                logs: logs.into_iter().map(Into::into).collect(),
-            })
-            .collect(),
+        events,
+        window,
     };
 
     Ok(Template::render(
@@ -138,8 +207,11 @@ pub async fn game_page(mmolb_game_id: String, db: Db) -> Result<Template, AppErr
 }
 
 #[get("/games/page/<after_game_id>")]
-pub async fn paginated_games_page(after_game_id: String, db: Db) -> Result<Template, AppError> {
-    paginated_games(Some(after_game_id), db).await
+pub async fn paginated_games_page(
+    after_game_id: Result<MmolbId, ParamError>,
+    db: Db,
+) -> Result<Template, AppError> {
+    paginated_games(Some(after_game_id?.into_inner()), db).await
 }
 
 #[get("/games")]
@@ -173,6 +245,75 @@ struct PaginatedGamesContext<'a> {
     games: Vec<GameContext>,
     next_page_url: Option<String>,
     previous_page_url: Option<String>,
+    /// Only shown on the games with issues page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error_code_counts: Option<Vec<db::IngestErrorCodeCount>>,
+    /// Only shown on the games with issues page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error_code_seasons: Option<ErrorCodeSeasonsContext>,
+}
+
+/// Ingest log counts per error code and season, for spotting which season's
+/// games or entities a kind of issue comes from
+#[derive(Serialize)]
+struct ErrorCodeSeasonsContext {
+    /// Latest first. `None` is for entity versions from before the first
+    /// season MMOLDB knows about.
+    seasons: Vec<Option<i32>>,
+    codes: Vec<ErrorCodeSeasonsRowContext>,
+}
+
+#[derive(Serialize)]
+struct ErrorCodeSeasonsRowContext {
+    display_name: String,
+    description: Option<String>,
+    /// One per season, in the same order as [`ErrorCodeSeasonsContext::seasons`]
+    counts: Vec<ErrorCodeSeasonCountContext>,
+}
+
+#[derive(Serialize)]
+struct ErrorCodeSeasonCountContext {
+    num_event_logs: i64,
+    num_version_logs: i64,
+}
+
+impl ErrorCodeSeasonsContext {
+    fn from_db(counts: Vec<db::IngestErrorCodeSeasonCount>) -> Self {
+        let seasons = counts
+            .iter()
+            .map(|count| count.season)
+            .unique()
+            // Latest season first, with the unknown season last
+            .sorted_by(|a, b| b.cmp(a))
+            .collect_vec();
+
+        // The counts are already grouped by error code
+        let codes = counts
+            .into_iter()
+            .chunk_by(|count| count.name.clone())
+            .into_iter()
+            .map(|(_, code_counts)| {
+                let code_counts = code_counts.collect_vec();
+                let first = &code_counts[0];
+                ErrorCodeSeasonsRowContext {
+                    display_name: first.display_name.clone(),
+                    description: first.description.clone(),
+                    counts: seasons
+                        .iter()
+                        .map(|season| {
+                            let count = code_counts.iter().find(|count| count.season == *season);
+                            ErrorCodeSeasonCountContext {
+                                num_event_logs: count.map_or(0, |count| count.num_event_logs),
+                                num_version_logs: count.map_or(0, |count| count.num_version_logs),
+                            }
+                        })
+                        .collect(),
+                }
+            })
+            .collect();
+
+        Self { seasons, codes }
+    }
 }
 
 fn paginated_games_context(
@@ -183,21 +324,140 @@ fn paginated_games_context(
     PaginatedGamesContext {
         index_url: uri!(index_page()).to_string(),
         subhead: "Games",
-        games: GameContext::from_db(page.games, |game_id| uri!(game_page(game_id)).to_string()),
+        games: GameContext::from_db(page.games, |game_id| uri!(game_page(game_id, _)).to_string()),
         next_page_url: page.next_page.as_deref().map(&paginated_uri_builder),
         previous_page_url: page.previous_page.map(|previous_page| match previous_page {
             Some(page) => paginated_uri_builder(&page),
             None => non_paginated_uri_builder(),
         }),
+        error_code_counts: None,
+        error_code_seasons: None,
+    }
+}
+
+/// Query params for the game search page. These are kept as raw strings
+/// because Rocket would turn a value that fails to parse into `None`, and
+/// because the form submits empty inputs as empty strings.
+#[derive(Debug, FromForm, Serialize)]
+pub struct GameSearchForm<'r> {
+    season: Option<&'r str>,
+    day_min: Option<&'r str>,
+    day_max: Option<&'r str>,
+    team: Option<&'r str>,
+    weather: Option<&'r str>,
+    stadium: Option<&'r str>,
+    min_margin: Option<&'r str>,
+    max_margin: Option<&'r str>,
+    has_issues: Option<&'r str>,
+}
+
+impl GameSearchForm<'_> {
+    fn to_search(&self) -> Result<db::GameSearch, ParamError> {
+        fn non_empty(value: Option<&str>) -> Option<&str> {
+            value.filter(|value| !value.is_empty())
+        }
+
+        Ok(db::GameSearch {
+            season: params::parse_optional::<Season>(non_empty(self.season))?.map(Season::get),
+            day_min: params::parse_form_field("day_min", self.day_min)?,
+            day_max: params::parse_form_field("day_max", self.day_max)?,
+            team_mmolb_id: params::parse_optional::<MmolbId>(non_empty(self.team))?
+                .map(MmolbId::into_inner),
+            weather_name: non_empty(self.weather).map(str::to_string),
+            stadium_name: non_empty(self.stadium).map(str::to_string),
+            min_score_margin: params::parse_form_field("min_margin", self.min_margin)?,
+            max_score_margin: params::parse_form_field("max_margin", self.max_margin)?,
+            has_issues: params::parse_form_field("has_issues", self.has_issues)?,
+        })
+    }
+
+    /// Url of the search page with these same filters, starting after
+    /// `after_game_id`
+    fn page_uri(&self, after_game_id: Option<&str>) -> String {
+        let fields = [
+            ("season", self.season),
+            ("day_min", self.day_min),
+            ("day_max", self.day_max),
+            ("team", self.team),
+            ("weather", self.weather),
+            ("stadium", self.stadium),
+            ("min_margin", self.min_margin),
+            ("max_margin", self.max_margin),
+            ("has_issues", self.has_issues),
+            ("after", after_game_id),
+        ];
+
+        let query = fields
+            .into_iter()
+            .filter_map(|(name, value)| {
+                value
+                    .filter(|value| !value.is_empty())
+                    .map(|value| format!("{name}={}", RawStr::new(value).percent_encode()))
+            })
+            .join("&");
+
+        // uri! can't build the search params from this struct, so this
+        // has to match the path in the route attribute
+        if query.is_empty() {
+            GAMES_SEARCH_PATH.to_string()
+        } else {
+            format!("{GAMES_SEARCH_PATH}?{query}")
+        }
     }
 }
 
+const GAMES_SEARCH_PATH: &str = "/games/search";
+
+#[get("/games/search?<search..>&<after>")]
+pub async fn games_search_page(
+    search: GameSearchForm<'_>,
+    after: Option<&str>,
+    db: Db,
+) -> Result<Template, AppError> {
+    let game_search = search.to_search()?;
+    let after_game_id = params::parse_optional::<MmolbId>(after)?.map(MmolbId::into_inner);
+
+    let (page, weathers) = db
+        .run(move |conn| {
+            conn.transaction(|conn| {
+                let page = db::search_games(
+                    conn,
+                    PAGE_OF_GAMES_SIZE,
+                    after_game_id.as_deref(),
+                    &game_search,
+                )?;
+                let weathers = db::weathers_with_games(conn)?;
+                Ok::<_, diesel::result::Error>((page, weathers))
+            })
+        })
+        .await?;
+
+    let games = paginated_games_context(
+        page,
+        |game_id| search.page_uri(Some(game_id)),
+        || search.page_uri(None),
+    );
+
+    Ok(Template::render(
+        "games_search",
+        context! {
+            index_url: games.index_url,
+            subhead: "Search games",
+            games: games.games,
+            next_page_url: games.next_page_url,
+            previous_page_url: games.previous_page_url,
+            search: search,
+            weathers: weathers,
+        },
+    ))
+}
+
 #[get("/games-with-issues/page/<after_game_id>")]
 pub async fn paginated_games_with_issues_page(
-    after_game_id: String,
+    after_game_id: Result<MmolbId, ParamError>,
     db: Db,
 ) -> Result<Template, AppError> {
-    paginated_games_with_issues(Some(after_game_id), db).await
+    paginated_games_with_issues(Some(after_game_id?.into_inner()), db).await
 }
 
 #[get("/games-with-issues")]
@@ -209,22 +469,30 @@ async fn paginated_games_with_issues(
     after_game_id: Option<String>,
     db: Db,
 ) -> Result<Template, AppError> {
-    let page = db
+    let (page, error_code_counts, error_code_seasons) = db
         .run(move |conn| {
             conn.transaction(|conn| {
-                db::page_of_games_with_issues(conn, PAGE_OF_GAMES_SIZE, after_game_id.as_deref())
+                let page = db::page_of_games_with_issues(
+                    conn,
+                    PAGE_OF_GAMES_SIZE,
+                    after_game_id.as_deref(),
+                )?;
+                let error_code_counts = db::ingest_error_code_counts(conn)?;
+                let error_code_seasons = db::ingest_error_code_counts_by_season(conn)?;
+                Ok::<_, diesel::result::Error>((page, error_code_counts, error_code_seasons))
             })
         })
         .await?;
 
-    Ok(Template::render(
-        "games",
-        paginated_games_context(
-            page,
-            |game_id| uri!(paginated_games_with_issues_page(game_id)).to_string(),
-            || uri!(games_with_issues_page()).to_string(),
-        ),
-    ))
+    let mut context = paginated_games_context(
+        page,
+        |game_id| uri!(paginated_games_with_issues_page(game_id)).to_string(),
+        || uri!(games_with_issues_page()).to_string(),
+    );
+    context.error_code_counts = Some(error_code_counts);
+    context.error_code_seasons = Some(ErrorCodeSeasonsContext::from_db(error_code_seasons));
+
+    Ok(Template::render("games", context))
 }
 
 #[get("/debug-no-games")]
@@ -236,19 +504,18 @@ pub async fn debug_no_games_page() -> Result<Template, AppError> {
         context! {
             index_url: uri!(index_page()),
             subhead: "[debug] No games",
-            games: GameContext::from_db(games, |game_id| uri!(game_page(game_id)).to_string()),
+            games: GameContext::from_db(games, |game_id| uri!(game_page(game_id, _)).to_string()),
         },
     ))
 }
 
 #[get("/status")]
 pub async fn status_page(db: Db) -> Result<Template, AppError> {
-    let counts = db
+    let (counts, db_health) = db
         .run(move |conn| {
-            conn.transaction(|conn| {
-                let counts = db::entity_counts(conn)?;
-                Ok::<_, AppError>(counts)
-            })
+            let counts = conn.transaction(|conn| db::entity_counts(conn))?;
+            let db_health = db::db_health(conn)?;
+            Ok::<_, AppError>((counts, db_health))
         })
         .await?;
 
@@ -344,6 +611,7 @@ pub async fn status_page(db: Db) -> Result<Template, AppError> {
             index_url: uri!(index_page()),
             pages: &*PAGES,
             ingestibles: ingestible_counts,
+            db_health: db_health,
         },
     ))
 }
@@ -476,13 +744,63 @@ pub async fn health_page(db: Db) -> Result<Template, AppError> {
     ))
 }
 
-#[get("/records")]
-pub async fn records_page(records: &State<RecordsCache>) -> Result<Template, AppError> {
+#[get("/quality")]
+pub async fn quality_page(db: Db) -> Result<Template, AppError> {
+    let summary = db.run(|conn| db::quality_summary(conn)).await?;
+
+    Ok(Template::render(
+        "quality",
+        context! {
+            index_url: uri!(index_page()),
+            pages: &*PAGES,
+            summary: summary,
+        },
+    ))
+}
+
+/// How many version chain breaks are listed on the version chains page
+const VERSION_CHAIN_BREAKS_SHOWN: i64 = 500;
+
+#[get("/version-chains")]
+pub async fn version_chains_page(db: Db) -> Result<Template, AppError> {
+    let ((gaps, overlaps), breaks) = db
+        .run(|conn| {
+            conn.transaction(|conn| {
+                let counts = db::count_version_chain_breaks(conn)?;
+                let breaks = db::version_chain_break_logs(conn, VERSION_CHAIN_BREAKS_SHOWN)?;
+                Ok::<_, diesel::result::Error>((counts, breaks))
+            })
+        })
+        .await?;
+
+    Ok(Template::render(
+        "version_chains",
+        context! {
+            index_url: uri!(index_page()),
+            pages: &*PAGES,
+            gaps: gaps,
+            overlaps: overlaps,
+            breaks: breaks,
+            max_shown: VERSION_CHAIN_BREAKS_SHOWN,
+        },
+    ))
+}
+
+/// `team_names` is `latest` (the default) to show every team's current
+/// name, or `as_of_game` to show the name the team had when it set the
+/// record
+#[get("/records?<team_names>")]
+pub async fn records_page(
+    team_names: Option<&str>,
+    records: &State<RecordsCache>,
+) -> Result<Template, AppError> {
+    let team_identity = params::parse_form_field::<db::TeamIdentityPolicy>("team_names", team_names)?
+        .unwrap_or_default();
     let update = records
         .update_date()
         .map(|d| FormattedDateContext::from(&d.naive_utc()));
     let error = records.update_error();
-    let records = records.latest();
+    let records = records.latest(team_identity);
 
     #[derive(Serialize)]
     struct LatestGameContext {
@@ -524,6 +842,76 @@ pub async fn records_page(records: &State<RecordsCache>) -> Result<Template, App
             error: error,
             update: update,
             records: records_context,
+            team_names: team_identity.to_string(),
+            latest_team_names_url: uri!(records_page(team_names = Some("latest"))),
+            as_of_game_team_names_url: uri!(records_page(team_names = Some("as_of_game"))),
+        },
+    ))
+}
+
+/// How many players each leaderboard shows
+const LEADERBOARD_SIZE: i64 = 10;
+
+/// Top players for each leaderboard stat, for one season or all time
+#[get("/leaderboards?<season>")]
+pub async fn leaderboards_page(season: Option<&str>, db: Db) -> Result<Template, AppError> {
+    // Comes from a form, so an empty season means all time
+    let season = params::parse_form_field::<Season>("season", season)?.map(Season::get);
+    let season_param = season.map(|s| s.to_string());
+
+    let leaderboards = db
+        .run(move |conn| {
+            db::LeaderboardStat::iter()
+                .map(|stat| Ok((stat, db::leaderboard(conn, stat, season, LEADERBOARD_SIZE)?)))
+                .collect::<Result<Vec<_>, diesel::result::Error>>()
+        })
+        .await?;
+
+    #[derive(Serialize)]
+    struct LeaderboardEntryContext {
+        player_url: String,
+        player_name: String,
+        value: String,
+    }
+
+    #[derive(Serialize)]
+    struct LeaderboardContext {
+        title: &'static str,
+        entries: Vec<LeaderboardEntryContext>,
+    }
+
+    let leaderboards = leaderboards
+        .into_iter()
+        .map(|(stat, entries)| LeaderboardContext {
+            title: stat.title(),
+            entries: entries
+                .into_iter()
+                .map(|entry| LeaderboardEntryContext {
+                    player_url: uri!(super::player_pages::player(
+                        player_id = entry.mmolb_player_id.as_str(),
+                        season = season_param.as_deref(),
+                    ))
+                    .to_string(),
+                    player_name: entry.player_name,
+                    value: match stat {
+                        db::LeaderboardStat::EarnedRunAverage => format!("{:.2}", entry.value),
+                        db::LeaderboardStat::FastestPitch => format!("{:.1} mph", entry.value),
+                        _ => format!("{}", entry.value as i64),
+                    },
+                })
+                .collect_vec(),
+        })
+        .collect_vec();
+
+    Ok(Template::render(
+        "leaderboards",
+        context! {
+            index_url: uri!(index_page()),
+            pages: &*PAGES,
+            season,
+            all_seasons_url: uri!(leaderboards_page(season = _)),
+            era_qualifying_outs: db::ERA_QUALIFYING_OUTS_PER_TEAM_GAME,
+            leaderboards,
         },
     ))
 }
@@ -592,18 +980,39 @@ pub async fn team_feed_event_versions_progress_plot(db: Db) -> (ContentType, Str
 }
 
 #[get("/")]
-pub async fn index_page() -> Template {
-    Template::render(
+pub async fn index_page(db: Db) -> Result<Template, AppError> {
+    #[derive(Serialize)]
+    struct FreshnessContext {
+        kind: String,
+        last_valid_from: Option<FormattedDateContext>,
+        last_ingest_at: FormattedDateContext,
+    }
+
+    let freshness = db
+        .run(move |conn| db::data_freshness(conn))
+        .await?
+        .into_iter()
+        .map(|f| FreshnessContext {
+            kind: f.kind,
+            last_valid_from: f
+                .last_valid_from
+                .map(|d| FormattedDateContext::from(&d.naive_utc())),
+            last_ingest_at: FormattedDateContext::from(&f.last_ingest_at.naive_utc()),
+        })
+        .collect_vec();
+
+    Ok(Template::render(
         "index",
         context! {
             index_url: uri!(index_page()),
             pages: &*PAGES,
+            freshness: freshness,
             // This markdown conversion could be cached
             changelog: markdown::to_html(
                 include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/../changelog.md")),
             ),
         },
-    )
+    ))
 }
 
 #[get("/debug-always-error")]
@@ -1,11 +1,12 @@
 use super::pages::*;
 use crate::Db;
+use crate::params::{self, MmolbId, ParamError, Season};
 use crate::web::error::AppError;
 use itertools::Itertools;
 use mmoldb_db::db;
 use mmoldb_db::db::Outcome;
 use mmoldb_db::models::DbPlayerVersion;
-use mmoldb_db::taxa::{AsInsertable, Taxa, TaxaDayType, TaxaEventType, TaxaSlot};
+use mmoldb_db::taxa::{AsInsertable, Taxa, TaxaDayType, TaxaEventType, TaxaSlot, UnknownTaxaId};
 use rocket::{State, get, uri};
 use rocket_dyn_templates::{Template, context};
 use serde::Serialize;
@@ -26,10 +27,13 @@ pub struct PlayerContext<'r, 't> {
 }
 
 impl<'r, 't> PlayerContext<'r, 't> {
-    fn from_db(raw: &'r DbPlayerVersion, taxa: &'t Taxa) -> PlayerContext<'r, 't> {
+    fn from_db(
+        raw: &'r DbPlayerVersion,
+        taxa: &'t Taxa,
+    ) -> Result<PlayerContext<'r, 't>, UnknownTaxaId> {
         let birthday_day = match raw.birthday_type {
             None => "Error storing player's birthday".to_string(),
-            Some(birthday_type) => match taxa.day_type_from_id(birthday_type) {
+            Some(birthday_type) => match taxa.day_type_from_id(birthday_type)? {
                 TaxaDayType::Preseason => "Preseason".to_string(),
                 TaxaDayType::RegularDay => match raw.birthday_day {
                     None => "Unknown regular day".to_string(),
@@ -53,27 +57,51 @@ impl<'r, 't> PlayerContext<'r, 't> {
             },
         };
 
-        Self {
+        Ok(Self {
             home: &raw.home,
             first_name: &raw.first_name,
             last_name: &raw.last_name,
             birthday: format!("Season {} {}", raw.birthseason, birthday_day),
             batting_handedness: raw
                 .batting_handedness
-                .map(|h| taxa.handedness_from_id(h).as_insertable().name),
+                .map(|h| taxa.handedness_from_id(h).map(|h| h.as_insertable().name))
+                .transpose()?,
             pitching_handedness: raw
                 .pitching_handedness
-                .map(|h| taxa.handedness_from_id(h).as_insertable().name),
+                .map(|h| taxa.handedness_from_id(h).map(|h| h.as_insertable().name))
+                .transpose()?,
             likes: &raw.likes,
             dislikes: &raw.dislikes,
             durability: raw.durability,
             slot: raw
                 .slot
-                .map(|id| taxa.slot_from_id(id).as_insertable().display_name),
-        }
+                .map(|id| {
+                    taxa.slot_from_id(id)
+                        .map(|slot| slot.as_insertable().display_name)
+                })
+                .transpose()?,
+        })
     }
 }
 
+#[derive(Serialize)]
+struct SimilarPlayerContext {
+    url: String,
+    name: String,
+    similarity: f64,
+}
+
+const NUM_SIMILAR_PLAYERS: i64 = 10;
+
+#[derive(Serialize)]
+struct AttributeAugmentContext {
+    season: i32,
+    attribute: &'static str,
+    value: i32,
+    /// The feed event the augment came from
+    text: Option<String>,
+}
+
 #[derive(Serialize)]
 struct OutcomeStats {
     outcome: &'static str,
@@ -151,19 +179,55 @@ fn outcomes(
 
 #[get("/player/<player_id>?<season>")]
 pub async fn player(
-    player_id: String,
-    season: Option<i32>,
+    player_id: Result<MmolbId, ParamError>,
+    season: Option<&str>,
     db: Db,
     taxa: &State<Taxa>,
 ) -> Result<Template, AppError> {
-    let (player_all, averages) = db
+    let player_id = player_id?.into_inner();
+    let season = params::parse_optional::<Season>(season)?.map(Season::get);
+    let season_param = season.map(|s| s.to_string());
+    let matchups_url = uri!(player_matchups(
+        player_id = player_id.as_str(),
+        season = season_param.as_deref(),
+    ))
+    .to_string();
+
+    let (player_all, averages, similar_players, augments) = db
         .run(move |conn| {
             let player_all = db::player_all(conn, &player_id, season)?;
             let averages = db::season_averages(conn, season)?;
-            Ok::<_, AppError>((player_all, averages))
+            let similar_players = db::similar_players(conn, &player_id, NUM_SIMILAR_PLAYERS)?;
+            let augments = db::player_attribute_augment_sources(conn, &player_id, season)?;
+            Ok::<_, AppError>((player_all, averages, similar_players, augments))
         })
         .await?;
 
+    let augments = augments
+        .into_iter()
+        .map(|augment| {
+            Ok::<_, UnknownTaxaId>(AttributeAugmentContext {
+                season: augment.season,
+                attribute: taxa.attribute_from_id(augment.attribute)?.into(),
+                value: augment.value,
+                text: augment.text,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let similar_players = similar_players
+        .into_iter()
+        .map(|similar| SimilarPlayerContext {
+            url: uri!(player(
+                player_id = similar.mmolb_player_id.as_str(),
+                season = _,
+            ))
+            .to_string(),
+            name: format!("{} {}", similar.first_name, similar.last_name),
+            similarity: similar.similarity,
+        })
+        .collect_vec();
+
     let averages: HashMap<_, HashMap<_, _>> = averages
         .into_iter()
         .chunk_by(|stat| stat.event_type)
@@ -179,7 +243,7 @@ pub async fn player(
         .collect();
 
     let raw_clone = player_all.player.clone();
-    let player = PlayerContext::from_db(&raw_clone, &taxa);
+    let player = PlayerContext::from_db(&raw_clone, &taxa)?;
 
     let total_events = player_all
         .pitch_types
@@ -216,20 +280,21 @@ pub async fn player(
         total_events.unwrap_or(0) - total_pitches.unwrap_or(0) - total_balks.unwrap_or(0);
 
     let pitch_types = player_all.pitch_types.as_ref().map(|pitches| {
-        let chunks = pitches
+        let pitches = pitches
             .iter()
             .filter_map(|info| {
                 info.pitch_type.map(|ty| {
-                    (
-                        taxa.pitch_type_from_id(ty),
+                    Ok::<_, UnknownTaxaId>((
+                        taxa.pitch_type_from_id(ty)?,
                         info.min_speed,
                         info.max_speed,
                         info.count,
-                    )
+                    ))
                 })
             })
-            .chunk_by(|(ty, _, _, _)| *ty);
-        chunks
+            .collect::<Result<Vec<_>, _>>()?;
+        let chunks = pitches.into_iter().chunk_by(|(ty, _, _, _)| *ty);
+        let pitch_types = chunks
             .into_iter()
             .map(|(ty, chunk)| {
                 chunk.into_iter().reduce(
@@ -250,15 +315,21 @@ pub async fn player(
                     },
                 )
             })
-            .collect_vec()
+            .collect_vec();
+        Ok::<_, UnknownTaxaId>(pitch_types)
     });
+    let pitch_types = pitch_types.transpose()?;
 
     let pitching_outcomes = outcomes(player_all.pitching_outcomes, taxa, &averages, None);
     let fielding_outcomes = outcomes(
         player_all.fielding_outcomes,
         taxa,
         &averages,
-        player_all.player.slot.map(|id| taxa.slot_from_id(id)),
+        player_all
+            .player
+            .slot
+            .map(|id| taxa.slot_from_id(id))
+            .transpose()?,
     );
     let batting_outcomes = outcomes(player_all.batting_outcomes, taxa, &averages, None);
 
@@ -277,6 +348,81 @@ pub async fn player(
             pitching_outcomes,
             fielding_outcomes,
             batting_outcomes,
+            matchups_url,
+            similar_players,
+            augments,
+        },
+    ))
+}
+
+#[derive(Serialize)]
+struct MatchupContext {
+    /// This same page, for the opponent
+    opponent_url: String,
+    opponent_name: String,
+    plate_appearances: i64,
+    hits: i64,
+    home_runs: i64,
+    walks: i64,
+    strikeouts: i64,
+    uncertain_plate_appearances: i64,
+}
+
+fn matchup_contexts(
+    matchups: Vec<db::DbMatchupOutcome>,
+    season: Option<&str>,
+) -> Vec<MatchupContext> {
+    matchups
+        .into_iter()
+        .map(|m| MatchupContext {
+            opponent_url: uri!(player_matchups(
+                player_id = m.opponent_mmolb_id.as_str(),
+                season = season,
+            ))
+            .to_string(),
+            opponent_name: m.opponent_name,
+            plate_appearances: m.plate_appearances,
+            hits: m.hits,
+            home_runs: m.home_runs,
+            walks: m.walks,
+            strikeouts: m.strikeouts,
+            uncertain_plate_appearances: m.uncertain_plate_appearances,
+        })
+        .collect_vec()
+}
+
+/// How a player has done against each batter or pitcher they've faced,
+/// optionally limited to one season
+#[get("/player/<player_id>/matchups?<season>")]
+pub async fn player_matchups(
+    player_id: Result<MmolbId, ParamError>,
+    season: Option<&str>,
+    db: Db,
+) -> Result<Template, AppError> {
+    let player_id = player_id?.into_inner();
+    let season = params::parse_optional::<Season>(season)?.map(Season::get);
+
+    let season_param = season.map(|s| s.to_string());
+
+    let db_player_id = player_id.clone();
+    let matchups = db
+        .run(move |conn| db::player_matchups(conn, &db_player_id, season))
+        .await?;
+
+    Ok(Template::render(
+        "player_matchups",
+        context! {
+            index_url: uri!(index_page()),
+            player_url: uri!(player(
+                player_id = player_id.as_str(),
+                season = season_param.as_deref(),
+            )),
+            all_seasons_url: uri!(player_matchups(player_id = player_id.as_str(), season = _)),
+            player_id: &player_id,
+            player_name: matchups.player_name,
+            season,
+            as_batter: matchup_contexts(matchups.as_batter, season_param.as_deref()),
+            as_pitcher: matchup_contexts(matchups.as_pitcher, season_param.as_deref()),
         },
     ))
 }
@@ -0,0 +1,211 @@
+use super::pages::*;
+use super::player_pages::*;
+use crate::Db;
+use crate::params::{self, MmolbId, ParamError, Season};
+use crate::web::error::AppError;
+use crate::web::utility_contexts::{DayContext, FormattedDateContext};
+use chrono::{DateTime, SecondsFormat, Utc};
+use itertools::Itertools;
+use mmoldb_db::db;
+use mmoldb_db::taxa::{AsInsertable, Taxa, UnknownTaxaId};
+use rocket::{State, get, uri};
+use rocket_dyn_templates::{Template, context};
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct RosterPlayerContext {
+    name: String,
+    number: i32,
+    slot: Option<&'static str>,
+    player_url: Option<String>,
+}
+
+#[derive(Serialize)]
+struct RosterChangeContext {
+    date: FormattedDateContext,
+    /// Link to the roster as it was right after this change
+    roster_url: String,
+    change: String,
+    player_name: String,
+    previous_player_name: Option<String>,
+    slot: Option<&'static str>,
+    previous_slot: Option<&'static str>,
+    player_url: String,
+}
+
+fn slot_name(taxa: &Taxa, slot: Option<i64>) -> Result<Option<&'static str>, UnknownTaxaId> {
+    slot.map(|id| {
+        taxa.slot_from_id(id)
+            .map(|slot| slot.as_insertable().display_name)
+    })
+    .transpose()
+}
+
+/// `at` is an RFC 3339 timestamp. The roster is shown as of now if it's
+/// omitted.
+#[get("/team/<team_id>/roster?<at>")]
+pub async fn team_roster(
+    team_id: Result<MmolbId, ParamError>,
+    at: Option<&str>,
+    db: Db,
+    taxa: &State<Taxa>,
+) -> Result<Template, AppError> {
+    let team_id = team_id?.into_inner();
+    let at = params::parse_form_field::<DateTime<Utc>>("at", at)?;
+    let roster_time = at.unwrap_or_else(Utc::now);
+
+    let db_team_id = team_id.clone();
+    let (roster, timeline) = db
+        .run(move |conn| {
+            let roster = db::team_roster_at(conn, &db_team_id, roster_time)?;
+            let timeline = db::team_roster_timeline(conn, &db_team_id)?;
+            Ok::<_, AppError>((roster, timeline))
+        })
+        .await?;
+
+    let players = roster
+        .players
+        .into_iter()
+        .map(|p| {
+            Ok::<_, UnknownTaxaId>(RosterPlayerContext {
+                name: match &p.name_suffix {
+                    Some(suffix) => format!("{} {} {}", p.first_name, p.last_name, suffix),
+                    None => format!("{} {}", p.first_name, p.last_name),
+                },
+                number: p.number,
+                slot: slot_name(taxa, p.slot)?,
+                player_url: p
+                    .mmolb_player_id
+                    .as_deref()
+                    .map(|id| uri!(player(player_id = id, season = _)).to_string()),
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Newest first, which is the order people usually want to read it in
+    let timeline = timeline
+        .into_iter()
+        .rev()
+        .map(|c| {
+            let change_time = c.at.to_rfc3339_opts(SecondsFormat::Micros, true);
+            Ok::<_, UnknownTaxaId>(RosterChangeContext {
+                date: FormattedDateContext::from(&c.at.naive_utc()),
+                roster_url: uri!(team_roster(
+                    team_id = team_id.as_str(),
+                    at = Some(change_time.as_str())
+                ))
+                .to_string(),
+                change: c.change,
+                player_name: c.player_name,
+                previous_player_name: c.previous_player_name,
+                slot: slot_name(taxa, c.slot)?,
+                previous_slot: slot_name(taxa, c.previous_slot)?,
+                player_url: uri!(player(player_id = c.mmolb_player_id.as_str(), season = _))
+                    .to_string(),
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Template::render(
+        "team_roster",
+        context! {
+            index_url: uri!(index_page()),
+            now_url: uri!(team_roster(team_id = team_id.as_str(), at = _)),
+            team: roster.team,
+            team_id: &team_id,
+            at: at.map(|at| FormattedDateContext::from(&at.naive_utc())),
+            players,
+            timeline,
+        },
+    ))
+}
+
+#[derive(Serialize)]
+struct HeadToHeadGameContext {
+    game_url: String,
+    season: i32,
+    day: DayContext,
+    team_a_was_home: bool,
+    team_a_score: i32,
+    team_b_score: i32,
+    team_a_won: bool,
+}
+
+#[derive(Serialize)]
+struct HeadToHeadTeamContext {
+    url: String,
+    name: Option<String>,
+    emoji: Option<String>,
+    wins: usize,
+    runs: i64,
+}
+
+/// The record between two teams, optionally limited to one season
+#[get("/team/<team_id>/vs/<other_team_id>?<season>")]
+pub async fn team_head_to_head(
+    team_id: Result<MmolbId, ParamError>,
+    other_team_id: Result<MmolbId, ParamError>,
+    season: Option<&str>,
+    db: Db,
+) -> Result<Template, AppError> {
+    let team_id = team_id?.into_inner();
+    let other_team_id = other_team_id?.into_inner();
+    let season = params::parse_form_field::<Season>("season", season)?.map(Season::get);
+
+    let db_team_id = team_id.clone();
+    let db_other_team_id = other_team_id.clone();
+    let head_to_head = db
+        .run(move |conn| db::head_to_head(conn, &db_team_id, &db_other_team_id, season))
+        .await?;
+
+    // Use the names from the most recent game
+    let latest_game = head_to_head.games.last();
+    let team_a = HeadToHeadTeamContext {
+        url: uri!(team_roster(team_id = team_id.as_str(), at = _)).to_string(),
+        name: latest_game.map(|g| g.team_a_name.clone()),
+        emoji: latest_game.map(|g| g.team_a_emoji.clone()),
+        wins: head_to_head.team_a_wins,
+        runs: head_to_head.team_a_runs,
+    };
+    let team_b = HeadToHeadTeamContext {
+        url: uri!(team_roster(team_id = other_team_id.as_str(), at = _)).to_string(),
+        name: latest_game.map(|g| g.team_b_name.clone()),
+        emoji: latest_game.map(|g| g.team_b_emoji.clone()),
+        wins: head_to_head.team_b_wins,
+        runs: head_to_head.team_b_runs,
+    };
+
+    // Newest first, to match the roster timeline
+    let games = head_to_head
+        .games
+        .into_iter()
+        .rev()
+        .map(|g| HeadToHeadGameContext {
+            game_url: uri!(game_page(g.mmolb_game_id.as_str(), _)).to_string(),
+            season: g.season,
+            day: (g.day, g.superstar_day).into(),
+            team_a_was_home: g.team_a_was_home,
+            team_a_score: g.team_a_score,
+            team_b_score: g.team_b_score,
+            team_a_won: g.team_a_score > g.team_b_score,
+        })
+        .collect_vec();
+
+    Ok(Template::render(
+        "team_head_to_head",
+        context! {
+            index_url: uri!(index_page()),
+            all_seasons_url: uri!(team_head_to_head(
+                team_id = team_id.as_str(),
+                other_team_id = other_team_id.as_str(),
+                season = _,
+            )),
+            team_id: &team_id,
+            other_team_id: &other_team_id,
+            season,
+            team_a,
+            team_b,
+            games,
+        },
+    ))
+}
@@ -67,6 +67,8 @@ pub struct GameContext {
     home_team_emoji: String,
     home_team_name: String,
     home_team_mmolb_id: String,
+    weather_name: String,
+    weather_emoji: String,
     num_warnings: i64,
     num_errors: i64,
     num_critical: i64,
@@ -89,6 +91,8 @@ impl GameContext {
                 home_team_emoji: g.game.home_team_emoji,
                 home_team_name: g.game.home_team_name,
                 home_team_mmolb_id: g.game.home_team_mmolb_id,
+                weather_name: g.weather_name,
+                weather_emoji: g.weather_emoji,
                 num_warnings: g.warnings_count,
                 num_errors: g.errors_count,
                 num_critical: g.critical_count,
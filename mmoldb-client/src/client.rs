@@ -0,0 +1,307 @@
+use crate::types::{
+    ApiDaySummary, ApiEjections, ApiEvents, ApiLinescore, ApiPlayerVersions, ApiPlayerVersionsPage,
+    ApiPlayerWeeklyRollups,
+    ApiSeasonCatcherCalledStrikes, ApiSeasonParkFactors, ApiSeasonPitcherPitchCountSplits,
+    ApiSeasonPitcherTtoSplits,
+    ApiSeasonPlayerHomeAwaySplits, ApiSeasonTeamHomeAwaySplits, ApiSeasonWeatherGameCounts,
+    ApiSeasonWeatherOutcomes, ApiTaxaTable, ApiTaxaTables, ApiTeamWeeklyRollups, GameReplay,
+};
+use chrono::{DateTime, SecondsFormat, Utc};
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+/// The public MMOLDB instance's API
+pub const DEFAULT_BASE_URL: &str = "https://mmoldb.beiju.me/api";
+
+#[derive(Debug, Error)]
+pub enum ClientError {
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+
+    #[error("MMOLDB returned {status}: {body}")]
+    Status { status: StatusCode, body: String },
+}
+
+/// Async client for MMOLDB's JSON API
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: String,
+    token: Option<String>,
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::new(DEFAULT_BASE_URL)
+    }
+}
+
+impl Client {
+    /// `base_url` is the URL of the API root, e.g. `http://localhost:42424/api`
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            token: None,
+        }
+    }
+
+    /// Sends `token` as a bearer token with every request. Some routes
+    /// (e.g. [`Client::player_versions_page`]) require one.
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Returns None on a 404
+    async fn get<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<Option<T>, ClientError> {
+        let mut request = self
+            .http
+            .get(format!("{}{}", self.base_url, path))
+            .query(query);
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        if status == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(ClientError::Status { status, body });
+        }
+
+        Ok(Some(response.json().await?))
+    }
+
+    /// Like [`Client::get`], for routes that never 404 on a valid request
+    async fn get_required<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<T, ClientError> {
+        self.get(path, query)
+            .await?
+            .ok_or_else(|| ClientError::Status {
+                status: StatusCode::NOT_FOUND,
+                body: format!("{path} not found"),
+            })
+    }
+
+    /// Every version of a player, merged with their other versioned data
+    pub async fn player_versions(&self, player_id: &str) -> Result<ApiPlayerVersions, ClientError> {
+        self.get_required(&format!("/player_versions/{player_id}"), &[]).await
+    }
+
+    /// One page of a player's rows from `data.player_versions`. `include`
+    /// may contain `"modifications"` and/or `"equipment"`. Requires a
+    /// token.
+    pub async fn player_versions_page(
+        &self,
+        player_id: &str,
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+        limit: Option<i64>,
+        include: &[&str],
+    ) -> Result<ApiPlayerVersionsPage, ClientError> {
+        let mut query = Vec::new();
+        if let Some(since) = since {
+            query.push(("since", since.to_rfc3339_opts(SecondsFormat::AutoSi, true)));
+        }
+        if let Some(until) = until {
+            query.push(("until", until.to_rfc3339_opts(SecondsFormat::AutoSi, true)));
+        }
+        if let Some(limit) = limit {
+            query.push(("limit", limit.to_string()));
+        }
+        if !include.is_empty() {
+            query.push(("include", include.join(",")));
+        }
+
+        self.get_required(&format!("/players/{player_id}/versions"), &query).await
+    }
+
+    /// Returns None if the game hasn't been ingested
+    pub async fn game_replay(&self, game_id: &str) -> Result<Option<GameReplay>, ClientError> {
+        self.get(&format!("/games/{game_id}/replay"), &[]).await
+    }
+
+    /// Returns None if the game hasn't been ingested
+    pub async fn game_linescore(&self, game_id: &str) -> Result<Option<ApiLinescore>, ClientError> {
+        self.get(&format!("/games/{game_id}/linescore"), &[]).await
+    }
+
+    pub async fn catcher_called_strikes(
+        &self,
+        season: i32,
+        min_taken_pitches: Option<i64>,
+    ) -> Result<ApiSeasonCatcherCalledStrikes, ClientError> {
+        let query = min_taken_pitches
+            .map(|min| ("min_taken_pitches", min.to_string()))
+            .into_iter()
+            .collect::<Vec<_>>();
+        self.get_required(&format!("/catcher_called_strikes/{season}"), &query).await
+    }
+
+    pub async fn pitcher_tto_splits(
+        &self,
+        season: i32,
+        team_id: Option<&str>,
+    ) -> Result<ApiSeasonPitcherTtoSplits, ClientError> {
+        let query = team_id
+            .map(|team| ("team", team.to_string()))
+            .into_iter()
+            .collect::<Vec<_>>();
+        self.get_required(&format!("/pitcher_tto_splits/{season}"), &query).await
+    }
+
+    pub async fn pitcher_pitch_count_splits(
+        &self,
+        season: i32,
+        team_id: Option<&str>,
+    ) -> Result<ApiSeasonPitcherPitchCountSplits, ClientError> {
+        let query = team_id
+            .map(|team| ("team", team.to_string()))
+            .into_iter()
+            .collect::<Vec<_>>();
+        self.get_required(&format!("/pitcher_pitch_count_splits/{season}"), &query).await
+    }
+
+    pub async fn weather_outcomes(
+        &self,
+        season: i32,
+        weather_name: Option<&str>,
+    ) -> Result<ApiSeasonWeatherOutcomes, ClientError> {
+        let query = weather_name
+            .map(|weather| ("weather", weather.to_string()))
+            .into_iter()
+            .collect::<Vec<_>>();
+        self.get_required(&format!("/weather_outcomes/{season}"), &query).await
+    }
+
+    pub async fn weather_game_counts(
+        &self,
+        season: i32,
+    ) -> Result<ApiSeasonWeatherGameCounts, ClientError> {
+        self.get_required(&format!("/weather_game_counts/{season}"), &[]).await
+    }
+
+    pub async fn team_home_away_splits(
+        &self,
+        season: i32,
+        team_id: Option<&str>,
+    ) -> Result<ApiSeasonTeamHomeAwaySplits, ClientError> {
+        let query = team_id
+            .map(|team| ("team", team.to_string()))
+            .into_iter()
+            .collect::<Vec<_>>();
+        self.get_required(&format!("/team_home_away_splits/{season}"), &query).await
+    }
+
+    pub async fn player_home_away_splits(
+        &self,
+        season: i32,
+        player_id: Option<&str>,
+    ) -> Result<ApiSeasonPlayerHomeAwaySplits, ClientError> {
+        let query = player_id
+            .map(|player| ("player", player.to_string()))
+            .into_iter()
+            .collect::<Vec<_>>();
+        self.get_required(&format!("/player_home_away_splits/{season}"), &query).await
+    }
+
+    pub async fn park_factors(&self, season: i32) -> Result<ApiSeasonParkFactors, ClientError> {
+        self.get_required(&format!("/park_factors/{season}"), &[]).await
+    }
+
+    /// A team's totals for every week it finished a game in, oldest first
+    pub async fn team_weekly_rollups(
+        &self,
+        team_id: &str,
+    ) -> Result<ApiTeamWeeklyRollups, ClientError> {
+        self.get_required(&format!("/team_weekly_rollups/{team_id}"), &[]).await
+    }
+
+    /// A batter's totals for every week they batted for a team, oldest first
+    pub async fn player_weekly_rollups(
+        &self,
+        team_id: &str,
+        batter_name: &str,
+    ) -> Result<ApiPlayerWeeklyRollups, ClientError> {
+        let query = [("batter", batter_name.to_string())];
+        self.get_required(&format!("/player_weekly_rollups/{team_id}"), &query).await
+    }
+
+    /// Names of every taxa table
+    pub async fn taxa_tables(&self) -> Result<ApiTaxaTables, ClientError> {
+        self.get_required("/taxa", &[]).await
+    }
+
+    /// Every row of `taxa.<table>`, or None if there's no such table
+    pub async fn taxa_table(&self, table: &str) -> Result<Option<ApiTaxaTable>, ClientError> {
+        self.get(&format!("/taxa/{table}"), &[]).await
+    }
+
+    /// Ejections and failed ejections, most recent first. `limit` applies
+    /// to each list separately.
+    pub async fn ejections(
+        &self,
+        season: Option<i32>,
+        team_id: Option<&str>,
+        player_name: Option<&str>,
+        limit: Option<i64>,
+    ) -> Result<ApiEjections, ClientError> {
+        let mut query = Vec::new();
+        if let Some(season) = season {
+            query.push(("season", season.to_string()));
+        }
+        if let Some(team_id) = team_id {
+            query.push(("team", team_id.to_string()));
+        }
+        if let Some(player_name) = player_name {
+            query.push(("player", player_name.to_string()));
+        }
+        if let Some(limit) = limit {
+            query.push(("limit", limit.to_string()));
+        }
+
+        self.get_required("/ejections", &query).await
+    }
+
+    /// Games, records, and ejections from the day of the most recently
+    /// finished game. Returns None if no games have finished.
+    pub async fn day_summary(&self) -> Result<Option<ApiDaySummary>, ClientError> {
+        self.get("/day_summary", &[]).await
+    }
+
+    /// Events matching every one of `filters`, in id order. Each filter is
+    /// written like `event_type=home_run` or `inning>=9`, and one of them
+    /// has to be `season=<season>` or `game_id=<game id>`.
+    pub async fn events(
+        &self,
+        filters: &[&str],
+        after: Option<i64>,
+        limit: Option<i64>,
+    ) -> Result<ApiEvents, ClientError> {
+        let mut query = filters
+            .iter()
+            .map(|filter| filter.split_once('=').unwrap_or((*filter, "")))
+            .map(|(name, value)| (name, value.to_string()))
+            .collect::<Vec<_>>();
+        if let Some(after) = after {
+            query.push(("after", after.to_string()));
+        }
+        if let Some(limit) = limit {
+            query.push(("limit", limit.to_string()));
+        }
+
+        self.get_required("/events", &query).await
+    }
+}
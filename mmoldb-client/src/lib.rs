@@ -0,0 +1,18 @@
+//! A typed client for MMOLDB's JSON API.
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), mmoldb_client::ClientError> {
+//! let client = mmoldb_client::Client::default();
+//! if let Some(linescore) = client.game_linescore("some-game-id").await? {
+//!     println!("{} - {}", linescore.away.runs, linescore.home.runs);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+#[cfg(feature = "http")]
+mod client;
+pub mod types;
+
+#[cfg(feature = "http")]
+pub use client::*;
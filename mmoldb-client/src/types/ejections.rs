@@ -0,0 +1,48 @@
+//! Responses from the `/api/ejections` route
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiEjection {
+    pub game_id: String,
+    pub season: i32,
+    pub day: Option<i32>,
+    pub superstar_day: Option<i32>,
+    pub game_started_at: DateTime<Utc>,
+    pub game_event_index: i32,
+    /// Null if the ejected team's name didn't match either team in the game
+    pub team_id: Option<String>,
+    pub team_emoji: String,
+    pub team_name: String,
+    pub ejected_player_name: String,
+    pub ejected_player_slot: String,
+    pub violation_type: String,
+    pub reason: String,
+    pub replacement_player_name: String,
+    pub replacement_player_slot: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiFailedEjection {
+    pub game_id: String,
+    pub season: i32,
+    pub day: Option<i32>,
+    pub superstar_day: Option<i32>,
+    pub game_started_at: DateTime<Utc>,
+    pub game_event_index: i32,
+    pub player_names: [String; 2],
+}
+
+/// Response of `/api/ejections`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiEjections {
+    /// Number of matching ejections, including any past `limit`
+    pub num_ejections: i64,
+    /// Number of matching failed ejections, including any past `limit`
+    pub num_failed_ejections: i64,
+    /// Most recent first
+    pub ejections: Vec<ApiEjection>,
+    /// Most recent first
+    pub failed_ejections: Vec<ApiFailedEjection>,
+}
@@ -0,0 +1,47 @@
+//! Responses from the `/api/events` route
+
+use serde::{Deserialize, Serialize};
+
+/// One event from `data.events`. Taxa are written in snake_case, the same
+/// way `/api/events` filters accept them, e.g. `home_run`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiEvent {
+    pub id: i64,
+    pub game_id: String,
+    pub season: i32,
+    pub day: Option<i32>,
+    pub superstar_day: Option<i32>,
+    pub game_event_index: i32,
+    pub inning: i32,
+    pub top_of_inning: bool,
+    pub event_type: Option<String>,
+    pub hit_base: Option<String>,
+    pub fair_ball_type: Option<String>,
+    pub fair_ball_direction: Option<String>,
+    pub fielding_error_type: Option<String>,
+    pub pitch_type: Option<String>,
+    pub pitch_speed: Option<f64>,
+    pub pitch_zone: Option<i32>,
+    pub balls_before: i32,
+    pub strikes_before: i32,
+    pub outs_before: i32,
+    pub outs_after: i32,
+    pub away_team_score_before: i32,
+    pub away_team_score_after: i32,
+    pub home_team_score_before: i32,
+    pub home_team_score_after: i32,
+    pub home_run_distance: Option<i32>,
+    pub described_as_sacrifice: Option<bool>,
+    pub is_toasty: Option<bool>,
+    pub pitcher_name: String,
+    pub batter_name: String,
+}
+
+/// Response of `/api/events`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiEvents {
+    /// In id order
+    pub events: Vec<ApiEvent>,
+    /// Pass this as `after` to get the next page. Null on the last page.
+    pub next_after: Option<i64>,
+}
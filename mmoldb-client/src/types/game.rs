@@ -0,0 +1,167 @@
+//! Responses from the `/api/games` routes
+
+use serde::{Deserialize, Serialize};
+
+/// Everything that happened in a game, in the order it happened, in a form
+/// that's convenient for a front-end replay viewer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameReplay {
+    pub mmolb_game_id: String,
+    pub season: i32,
+    pub day: Option<i32>,
+    pub superstar_day: Option<i32>,
+    pub away_team_emoji: String,
+    pub away_team_name: String,
+    pub away_team_mmolb_id: String,
+    pub home_team_emoji: String,
+    pub home_team_name: String,
+    pub home_team_mmolb_id: String,
+    pub stadium_name: Option<String>,
+    pub is_ongoing: bool,
+    pub away_team_final_score: Option<i32>,
+    pub home_team_final_score: Option<i32>,
+    pub items: Vec<ReplayItem>,
+    /// Events, parties, and pitcher changes that couldn't be reconstructed
+    /// from the database. These are left out of `items`.
+    pub event_errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ReplayItem {
+    Event(ReplayEvent),
+    Party(ReplayParty),
+    PitcherChange(ReplayPitcherChange),
+}
+
+impl ReplayItem {
+    pub fn game_event_index(&self) -> i32 {
+        match self {
+            ReplayItem::Event(event) => event.game_event_index,
+            ReplayItem::Party(party) => party.game_event_index,
+            ReplayItem::PitcherChange(change) => change.game_event_index,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayEvent {
+    pub game_event_index: i32,
+    pub inning: i32,
+    pub top_of_inning: bool,
+    pub event_type: String,
+    pub batter_name: String,
+    pub pitcher_name: String,
+    pub balls_before: i32,
+    pub strikes_before: i32,
+    pub outs_before: i32,
+    pub outs_after: i32,
+    pub away_team_score_before: i32,
+    pub away_team_score_after: i32,
+    pub home_team_score_before: i32,
+    pub home_team_score_after: i32,
+    /// Whether first, second, and third base were occupied before the event
+    pub bases_occupied_before: [bool; 3],
+    /// Whether first, second, and third base were occupied after the event
+    pub bases_occupied_after: [bool; 3],
+    pub runners: Vec<ReplayRunner>,
+    pub ejection: Option<ReplayEjection>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayRunner {
+    pub name: String,
+    /// None for the batter
+    pub base_before: Option<String>,
+    pub base_after: String,
+    pub is_out: bool,
+    pub is_steal: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ReplayEjection {
+    Ejection {
+        team_name: String,
+        ejected_player_name: String,
+        violation_type: String,
+        reason: String,
+        replacement_player_name: String,
+    },
+    FailedEjection {
+        player_names: [String; 2],
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayParty {
+    pub game_event_index: i32,
+    pub top_of_inning: bool,
+    pub is_pitcher: bool,
+    pub player_name: String,
+    pub attribute: String,
+    pub value: i32,
+    pub durability_loss: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayPitcherChange {
+    pub game_event_index: i32,
+    pub inning: i32,
+    pub top_of_inning: bool,
+    pub source: String,
+    pub pitcher_name: String,
+    pub new_pitcher_name: Option<String>,
+}
+
+/// One half-inning of a game's line score
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiHalfInning {
+    pub inning: i32,
+    pub top_of_inning: bool,
+    /// Scored by the batting team
+    pub runs: i32,
+    /// Hit by the batting team
+    pub hits: i32,
+    /// Committed by the fielding team
+    pub errors: i32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ApiLinescoreTotals {
+    pub runs: i32,
+    pub hits: i32,
+    pub errors: i32,
+}
+
+/// Response of `/api/games/<game_id>/linescore`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiLinescore {
+    pub game_id: String,
+    pub half_innings: Vec<ApiHalfInning>,
+    pub away: ApiLinescoreTotals,
+    pub home: ApiLinescoreTotals,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay_items_are_tagged_by_type() {
+        let item = ReplayItem::Party(ReplayParty {
+            game_event_index: 3,
+            top_of_inning: true,
+            is_pitcher: false,
+            player_name: "Player".to_string(),
+            attribute: "Muscle".to_string(),
+            value: 10,
+            durability_loss: None,
+        });
+
+        let json = serde_json::to_value(&item).unwrap();
+        assert_eq!(json["type"], "party");
+        let item: ReplayItem = serde_json::from_value(json).unwrap();
+        assert_eq!(item.game_event_index(), 3);
+    }
+}
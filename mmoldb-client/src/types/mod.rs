@@ -0,0 +1,18 @@
+//! The bodies of MMOLDB's JSON API responses. mmoldb-app serializes these
+//! exact types, so they can't drift from what the API actually returns.
+
+mod ejections;
+mod events;
+mod game;
+mod player;
+mod stats;
+mod summary;
+mod taxa;
+
+pub use ejections::*;
+pub use events::*;
+pub use game::*;
+pub use player::*;
+pub use stats::*;
+pub use summary::*;
+pub use taxa::*;
@@ -0,0 +1,155 @@
+//! Responses from the `/api/players` routes. Taxa values (attributes,
+//! slots, handedness, etc.) are sent as their names, so they're strings
+//! here.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiModification {
+    pub name: String,
+    pub emoji: String,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiEquipmentEffect {
+    pub attribute: String,
+    pub effect_type: String,
+    pub value: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiEquipment {
+    pub emoji: String,
+    pub name: String,
+    pub special_type: Option<String>,
+    pub description: Option<String>,
+    pub rare_name: Option<String>,
+    pub cost: Option<i32>,
+    pub prefixes: Vec<Option<String>>,
+    pub suffixes: Vec<Option<String>>,
+    pub rarity: Option<String>,
+    pub effects: Vec<Option<ApiEquipmentEffect>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiReportAttribute {
+    pub base_stars: Option<i32>,
+    pub base_total: Option<f64>,
+    pub modified_stars: Option<i32>,
+    pub modified_total: Option<f64>,
+    /// Stars computed from `base_total`, for reports that have a total but
+    /// didn't show stars (or to check the ones they did show)
+    pub computed_base_stars: Option<i32>,
+    /// Stars computed from `modified_total`
+    pub computed_modified_stars: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiReport {
+    pub season: Option<i32>,
+    pub day_type: Option<String>,
+    pub day: Option<i32>,
+    pub superstar_day: Option<i32>,
+    pub quote: Option<String>,
+    /// Keyed by attribute name
+    pub attributes: HashMap<String, Option<ApiReportAttribute>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event_type")]
+pub enum ApiPlayerEvent {
+    Recomposition {
+        time: DateTime<Utc>,
+        new_name: String,
+        reverts_recomposition: Option<DateTime<Utc>>,
+    },
+    AttributeAugment {
+        time: DateTime<Utc>,
+        category: String,
+        attribute: String,
+        value: i32,
+    },
+    Party {
+        /// Start time of the game the party happened in
+        time: DateTime<Utc>,
+        category: String,
+        attribute: String,
+        value: i32,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiPlayerVersion {
+    pub id: String,
+    pub valid_from: DateTime<Utc>,
+    pub valid_until: Option<DateTime<Utc>>,
+    pub first_name: String,
+    pub last_name: String,
+    pub batting_handedness: Option<String>,
+    pub pitching_handedness: Option<String>,
+    pub home: String,
+    pub birthseason: i32,
+    pub birthday_type: Option<String>,
+    pub birthday_day: Option<i32>,
+    pub birthday_superstar_day: Option<i32>,
+    pub likes: String,
+    pub dislikes: String,
+    pub number: i32,
+    pub mmolb_team_id: Option<String>,
+    pub slot: Option<String>,
+    pub durability: Option<f64>,
+    pub modifications: Vec<Option<ApiModification>>,
+    /// Keyed by equipment slot
+    pub equipment: HashMap<String, Option<ApiEquipment>>,
+    /// Keyed by attribute category
+    pub reports: HashMap<String, Option<ApiReport>>,
+    pub events: Vec<ApiPlayerEvent>,
+}
+
+/// Response of `/api/player_versions/<player_id>`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiPlayerVersions {
+    pub player_id: String,
+    pub versions: Vec<ApiPlayerVersion>,
+}
+
+/// One row of `data.player_versions`. Unlike [`ApiPlayerVersion`], these
+/// are not merged with the player's other versioned tables.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiPlayerVersionRow {
+    pub valid_from: DateTime<Utc>,
+    pub valid_until: Option<DateTime<Utc>>,
+    pub first_name: String,
+    pub last_name: String,
+    pub batting_handedness: Option<String>,
+    pub pitching_handedness: Option<String>,
+    pub home: String,
+    pub birthseason: i32,
+    pub birthday_type: Option<String>,
+    pub birthday_day: Option<i32>,
+    pub birthday_superstar_day: Option<i32>,
+    pub likes: String,
+    pub dislikes: String,
+    pub number: i32,
+    pub mmolb_team_id: Option<String>,
+    pub slot: Option<String>,
+    pub durability: Option<f64>,
+    /// Only present when requested with `include=modifications`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modifications: Option<Vec<Option<ApiModification>>>,
+    /// Only present when requested with `include=equipment`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub equipment: Option<HashMap<String, Option<ApiEquipment>>>,
+}
+
+/// Response of `/api/players/<player_id>/versions`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiPlayerVersionsPage {
+    pub player_id: String,
+    pub versions: Vec<ApiPlayerVersionRow>,
+    /// Pass this as `since` to get the next page. Null on the last page.
+    pub next_since: Option<DateTime<Utc>>,
+}
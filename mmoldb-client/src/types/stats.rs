@@ -0,0 +1,203 @@
+//! Responses from the per-season stats routes
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiCatcherCalledStrikes {
+    pub catcher_id: String,
+    pub catcher_name: String,
+    pub taken_pitches: i64,
+    pub called_strikes: i64,
+    pub balls: i64,
+    pub called_strike_rate: f64,
+    /// Called strikes compared to what the same pitchers got on average
+    pub called_strikes_above_expected: f64,
+}
+
+/// Response of `/api/catcher_called_strikes/<season>`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiSeasonCatcherCalledStrikes {
+    pub season: i32,
+    pub min_taken_pitches: i64,
+    pub catchers: Vec<ApiCatcherCalledStrikes>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiPitcherTtoSplit {
+    pub team_id: String,
+    pub pitcher_name: String,
+    /// 1 the first time through the batting order, 2 the second, etc.
+    pub times_through_order: i32,
+    pub plate_appearances: i64,
+    pub hits: i64,
+    pub home_runs: i64,
+    pub walks: i64,
+    pub strikeouts: i64,
+    pub pitches: i64,
+}
+
+/// Response of `/api/pitcher_tto_splits/<season>`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiSeasonPitcherTtoSplits {
+    pub season: i32,
+    pub splits: Vec<ApiPitcherTtoSplit>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiPitcherPitchCountSplit {
+    pub team_id: String,
+    pub pitcher_name: String,
+    /// The fewest pitches the pitcher had thrown before the plate
+    /// appearances in this split. Splits are 25 pitches wide, except the
+    /// last one, which is 100 and up.
+    pub pitch_count_min: i32,
+    pub plate_appearances: i64,
+    pub hits: i64,
+    pub home_runs: i64,
+    pub walks: i64,
+    pub strikeouts: i64,
+    pub pitches: i64,
+}
+
+/// Response of `/api/pitcher_pitch_count_splits/<season>`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiSeasonPitcherPitchCountSplits {
+    pub season: i32,
+    pub splits: Vec<ApiPitcherPitchCountSplit>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiWeatherOutcome {
+    pub weather_id: i64,
+    pub weather_name: String,
+    pub weather_emoji: String,
+    pub event_type: String,
+    /// Games in this weather that had at least one event of this type
+    pub games: i64,
+    pub events: i64,
+}
+
+/// Response of `/api/weather_outcomes/<season>`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiSeasonWeatherOutcomes {
+    pub season: i32,
+    pub outcomes: Vec<ApiWeatherOutcome>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiWeatherGameCount {
+    pub weather_name: String,
+    pub weather_emoji: String,
+    pub games: i64,
+}
+
+/// Response of `/api/weather_game_counts/<season>`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiSeasonWeatherGameCounts {
+    pub season: i32,
+    /// Most games first
+    pub counts: Vec<ApiWeatherGameCount>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiTeamHomeAwaySplit {
+    pub team_id: String,
+    pub team_name: String,
+    pub team_emoji: String,
+    pub is_home: bool,
+    pub games: i64,
+    pub wins: i64,
+    pub runs_scored: i64,
+    pub runs_allowed: i64,
+}
+
+/// Response of `/api/team_home_away_splits/<season>`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiSeasonTeamHomeAwaySplits {
+    pub season: i32,
+    /// Superstar games aren't counted
+    pub splits: Vec<ApiTeamHomeAwaySplit>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiPlayerHomeAwaySplit {
+    pub player_id: String,
+    pub player_name: String,
+    pub is_home: bool,
+    pub games: i64,
+    pub plate_appearances: i64,
+    pub hits: i64,
+    pub home_runs: i64,
+    pub walks: i64,
+    pub strikeouts: i64,
+}
+
+/// Response of `/api/player_home_away_splits/<season>`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiSeasonPlayerHomeAwaySplits {
+    pub season: i32,
+    /// Batting only. Superstar games aren't counted.
+    pub splits: Vec<ApiPlayerHomeAwaySplit>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiParkFactor {
+    /// None if the games couldn't be linked to a stadium
+    pub stadium_id: Option<String>,
+    pub stadium_name: String,
+    pub games: i64,
+    pub runs: i64,
+    pub runs_per_game: f64,
+    /// Runs per game in the home teams' road games
+    pub road_runs_per_game: Option<f64>,
+    /// `runs_per_game / road_runs_per_game`. Above 1 favors hitters.
+    pub park_factor: Option<f64>,
+}
+
+/// Response of `/api/park_factors/<season>`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiSeasonParkFactors {
+    pub season: i32,
+    /// Highest park factor first
+    pub parks: Vec<ApiParkFactor>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiTeamWeeklyRollup {
+    /// The Monday the week starts on
+    pub week_start: NaiveDate,
+    pub games: i64,
+    pub wins: i64,
+    pub runs_scored: i64,
+    pub runs_allowed: i64,
+}
+
+/// Response of `/api/team_weekly_rollups/<team_id>`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiTeamWeeklyRollups {
+    pub team_id: String,
+    /// Oldest first. Weeks the team didn't finish a game in are left out.
+    pub weeks: Vec<ApiTeamWeeklyRollup>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiPlayerWeeklyRollup {
+    /// The Monday the week starts on
+    pub week_start: NaiveDate,
+    pub plate_appearances: i64,
+    pub hits: i64,
+    pub home_runs: i64,
+    pub walks: i64,
+    pub strikeouts: i64,
+}
+
+/// Response of `/api/player_weekly_rollups/<team_id>?batter=<name>`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiPlayerWeeklyRollups {
+    pub team_id: String,
+    pub batter_name: String,
+    /// Batting only, oldest first. Weeks without a plate appearance are left
+    /// out.
+    pub weeks: Vec<ApiPlayerWeeklyRollup>,
+}
@@ -0,0 +1,48 @@
+//! Responses from the `/api/day_summary` route
+
+use crate::types::ApiEjection;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiDayGame {
+    pub game_id: String,
+    pub away_team_id: String,
+    pub away_team_emoji: String,
+    pub away_team_name: String,
+    pub away_team_final_score: i32,
+    pub home_team_id: String,
+    pub home_team_emoji: String,
+    pub home_team_name: String,
+    pub home_team_final_score: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiDayFastestPitch {
+    pub game_id: String,
+    pub game_event_index: i32,
+    pub player_id: String,
+    pub player_name: String,
+    /// The pitcher's team as of the game
+    pub team_id: String,
+    pub team_emoji: String,
+    pub team_name: String,
+    pub pitch_speed: f64,
+}
+
+/// Response of `/api/day_summary`, which covers the latest day whose games
+/// have all finished
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiDaySummary {
+    pub season: i32,
+    /// Exactly one of `day` and `superstar_day` is set
+    pub day: Option<i32>,
+    pub superstar_day: Option<i32>,
+    pub num_games: i64,
+    /// The finished game with the largest margin of victory
+    pub biggest_blowout: Option<ApiDayGame>,
+    pub fastest_pitch: Option<ApiDayFastestPitch>,
+    /// Number of ejections, including any past the end of `ejections`
+    pub num_ejections: i64,
+    /// Most recent first
+    pub ejections: Vec<ApiEjection>,
+}
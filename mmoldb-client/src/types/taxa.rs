@@ -0,0 +1,30 @@
+//! Responses from the `/api/taxa` routes
+
+use serde::{Deserialize, Serialize};
+
+/// One row of a taxa table. Other tables refer to rows by `id`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiTaxaRow {
+    pub id: i64,
+    /// e.g. `HomeRun`
+    pub name: String,
+    pub description: Option<String>,
+    /// Every other column of the table, e.g. `display_name` or `is_hit`
+    #[serde(flatten)]
+    pub columns: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Response of `/api/taxa/<table>`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiTaxaTable {
+    /// e.g. `event_type` for `taxa.event_type`
+    pub table: String,
+    /// In id order
+    pub rows: Vec<ApiTaxaRow>,
+}
+
+/// Response of `/api/taxa`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiTaxaTables {
+    pub tables: Vec<String>,
+}
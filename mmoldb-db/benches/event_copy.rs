@@ -0,0 +1,135 @@
+//! A benchmark for the binary `COPY` that inserts event rows into
+//! `data.events`, including encoding them. It needs the database from
+//! `postgres_url_from_environment`, and rolls back everything it inserts.
+
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+use diesel::sql_types::BigInt;
+use diesel::{Connection, PgConnection, QueryableByName, RunQueryDsl, sql_query};
+use mmoldb_db::db::copy_event_rows;
+use mmoldb_db::taxa::{Taxa, TaxaEventType};
+use mmoldb_db::{EventDetail, postgres_url_from_environment};
+
+/// Roughly how many events a typical game has
+const EVENTS_PER_GAME: usize = 300;
+
+const GAMES_PER_COPY: usize = 100;
+
+#[derive(QueryableByName)]
+struct GameId {
+    #[diesel(sql_type = BigInt)]
+    id: i64,
+}
+
+fn game_events() -> Vec<EventDetail<&'static str>> {
+    let event_types = [
+        TaxaEventType::Ball,
+        TaxaEventType::CalledStrike,
+        TaxaEventType::FoulBall,
+        TaxaEventType::Hit,
+        TaxaEventType::Walk,
+    ];
+    (0..EVENTS_PER_GAME)
+        .map(|game_event_index| EventDetail {
+            game_event_index,
+            fair_ball_event_index: None,
+            inning: (game_event_index / 35 + 1) as u8,
+            top_of_inning: game_event_index % 35 < 18,
+            balls_before: (game_event_index % 4) as u8,
+            strikes_before: (game_event_index % 3) as u8,
+            outs_before: 0,
+            outs_after: 0,
+            errors_before: 0,
+            errors_after: 0,
+            away_team_score_before: 0,
+            away_team_score_after: 0,
+            home_team_score_before: 0,
+            home_team_score_after: 0,
+            pitcher_name: "Benchmark Pitcher",
+            batter_name: "Benchmark Batter",
+            fielders: Vec::new(),
+            detail_type: event_types[game_event_index % event_types.len()],
+            hit_base: None,
+            fair_ball_type: None,
+            fair_ball_direction: None,
+            fair_ball_fielder_name: None,
+            fielding_error_type: None,
+            pitch_type: None,
+            pitch_speed: Some(95.5),
+            pitch_zone: Some(5),
+            described_as_sacrifice: None,
+            is_toasty: None,
+            home_run_distance: None,
+            balk_reason: None,
+            baserunners: Vec::new(),
+            pitcher_count: (game_event_index / 4) as i32,
+            batter_count: (game_event_index / 4) as i32,
+            batter_subcount: (game_event_index % 4) as i32,
+            cheer: None,
+            aurora_photos: None,
+            ejection: None,
+            door_prizes: Vec::new(),
+            wither: None,
+            efflorescences: Vec::new(),
+            is_surprise_strike: None,
+        })
+        .collect()
+}
+
+fn bench_event_copy(c: &mut Criterion) {
+    let mut conn = PgConnection::establish(&postgres_url_from_environment())
+        .expect("the benchmarks need a database");
+    let taxa = Taxa::new(&mut conn).unwrap();
+    let events = game_events();
+
+    let mut group = c.benchmark_group("event_copy");
+
+    // Nothing inserted here is committed
+    conn.begin_test_transaction().unwrap();
+    let game_ids = sql_query(
+        "
+        with w as (
+            insert into data.weather (name, emoji, tooltip)
+            values ('Benchmark Weather', '', '')
+            returning id
+        )
+        insert into data.games
+            (mmolb_game_id, weather, season, superstar_day, away_team_emoji,
+             away_team_name, away_team_mmolb_id, home_team_emoji, home_team_name,
+             home_team_mmolb_id, is_ongoing, from_version, started_at)
+        select 'eeeeeeeeeeeeeeeeee' || lpad(n::text, 6, '0'), w.id, -1, null, '', 'Away',
+            'event-copy-bench-away', '', 'Home', 'event-copy-bench-home', false,
+            '2025-02-15', '2025-02-15T00:00:00Z'
+        from w, generate_series(1, $1) n
+        returning id
+        ",
+    )
+    .bind::<BigInt, _>(GAMES_PER_COPY as i64)
+    .get_results::<GameId>(&mut conn)
+    .unwrap();
+    let games = game_ids
+        .iter()
+        .map(|game| (game.id, events.as_slice()))
+        .collect::<Vec<_>>();
+
+    group.throughput(Throughput::Elements(
+        (GAMES_PER_COPY * EVENTS_PER_GAME) as u64,
+    ));
+    group.bench_function("copy_event_rows", |b| {
+        b.iter(|| {
+            // Each COPY is rolled back afterwards, so every iteration inserts
+            // into the same table
+            sql_query("savepoint event_copy_bench")
+                .execute(&mut conn)
+                .unwrap();
+            copy_event_rows(&mut conn, &taxa, &games).unwrap();
+            sql_query("rollback to savepoint event_copy_bench")
+                .execute(&mut conn)
+                .unwrap();
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_event_copy);
+criterion_main!(benches);
@@ -1,19 +1,49 @@
+use crate::db::{INGEST_PROGRESS_CHANNEL, IngestProgressEvent};
 use crate::models::DbVersion;
 use chron::ChronEntity;
-use chrono::NaiveDateTime;
+use chrono::{DateTime, Utc};
 use diesel::prelude::*;
+use diesel::sql_types::{Text, Timestamp, Timestamptz};
 use diesel_async::{AsyncPgConnection, RunQueryDsl};
-use futures::{Stream, TryStreamExt};
+use futures::{Stream, TryStreamExt, future};
+use tracing::warn;
+
+define_sql_function! {
+    /// Interprets a `timestamp without time zone` as UTC. Used to compare
+    /// tables that haven't been migrated to `timestamptz` with ones that have.
+    #[sql_name = "timezone"]
+    fn timezone_utc(zone: Text, ts: Timestamp) -> Timestamptz;
+}
+
+/// Which unprocessed items a processing pass streams. An ingest with
+/// prioritization runs `RecentlyActive` and then `Inactive` with the same
+/// cutoff, so the freshest data shows up first during a long ingest. The
+/// two passes never overlap, which keeps the second from picking up versions
+/// the first sent to a worker that hasn't saved them yet.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ProcessingPass {
+    /// Entities with a version from at least this time. Every unprocessed
+    /// version of those entities is included, not just the recent ones, so
+    /// each entity's versions are still processed in order.
+    RecentlyActive(DateTime<Utc>),
+    /// Entities with no version from at least this time
+    Inactive(DateTime<Utc>),
+    /// Everything that hasn't been processed yet
+    All,
+}
 
 pub async fn stream_unprocessed_versions(
     conn: &mut AsyncPgConnection,
     kind: &str,
+    pass: ProcessingPass,
 ) -> QueryResult<impl Stream<Item = QueryResult<ChronEntity<serde_json::Value>>>> {
     use crate::schema::data_schema::data::versions::dsl as v_dsl;
     use crate::schema::data_schema::data::versions_processed::dsl as vp_dsl;
 
-    let stream = v_dsl::versions
+    let mut query = v_dsl::versions
         .filter(v_dsl::kind.eq(kind))
+        // Only main MMOLB versions are processed
+        .filter(v_dsl::source.is_null())
         .filter(diesel::dsl::not(diesel::dsl::exists(
             // This subquery is meant to check if there is a corresponding entry in versions_processed
             vp_dsl::versions_processed
@@ -21,6 +51,29 @@ pub async fn stream_unprocessed_versions(
                 .filter(vp_dsl::entity_id.eq(v_dsl::entity_id))
                 .filter(vp_dsl::valid_from.eq(v_dsl::valid_from))
         )))
+        .into_boxed();
+    let recent_version =
+        diesel::alias!(crate::schema::data_schema::data::versions as recent_version);
+    let has_recent_version = |since: DateTime<Utc>| {
+        diesel::dsl::exists(
+            recent_version
+                .filter(recent_version.field(v_dsl::kind).eq(v_dsl::kind))
+                .filter(recent_version.field(v_dsl::entity_id).eq(v_dsl::entity_id))
+                .filter(recent_version.field(v_dsl::source).is_null())
+                .filter(recent_version.field(v_dsl::valid_from).ge(since)),
+        )
+    };
+    match pass {
+        ProcessingPass::RecentlyActive(since) => {
+            query = query.filter(has_recent_version(since));
+        }
+        ProcessingPass::Inactive(since) => {
+            query = query.filter(diesel::dsl::not(has_recent_version(since)));
+        }
+        ProcessingPass::All => {}
+    }
+
+    let stream = query
         // Callers of this function rely on the results being sorted by
         // (valid_from, entity_id) with the highest id last
         .order_by((
@@ -30,15 +83,18 @@ pub async fn stream_unprocessed_versions(
         .select(DbVersion::as_select())
         .load_stream::<DbVersion>(conn)
         .await?
-        .map_ok(|v| {
-            ChronEntity {
+        .and_then(|v| {
+            // Versions that were compressed by the retention task are
+            // streamed again when they're reprocessed
+            let data = crate::db::decompress_version_data(v.data, v.data_zstd.as_deref());
+            future::ready(data.map(|data| ChronEntity {
                 kind: v.kind,
                 entity_id: v.entity_id,
-                valid_from: v.valid_from.and_utc(),
-                valid_to: v.valid_to.map(|dt| dt.and_utc()),
+                valid_from: v.valid_from,
+                valid_to: v.valid_to,
                 // Kind of a hack to smuggle extra data through the machinery
-                data: v.data,
-            }
+                data,
+            }))
         });
 
     Ok(stream)
@@ -47,6 +103,7 @@ pub async fn stream_unprocessed_versions(
 pub async fn stream_unprocessed_feed_event_versions(
     conn: &mut AsyncPgConnection,
     kind: &str,
+    pass: ProcessingPass,
 ) -> QueryResult<impl Stream<Item = QueryResult<ChronEntity<serde_json::Value>>>> {
     use crate::schema::data_schema::data::feed_event_versions::dsl as fev_dsl;
     use crate::schema::data_schema::data::feed_events_processed::dsl as fep_dsl;
@@ -54,8 +111,10 @@ pub async fn stream_unprocessed_feed_event_versions(
     let prev_version =
         diesel::alias!(crate::schema::data_schema::data::feed_event_versions as prev_version1);
 
-    let stream = fev_dsl::feed_event_versions
+    let mut query = fev_dsl::feed_event_versions
         .filter(fev_dsl::kind.eq(kind))
+        // Only main MMOLB versions are processed
+        .filter(fev_dsl::source.is_null())
         .filter(diesel::dsl::not(diesel::dsl::exists(
             // This subquery is meant to check if there is a corresponding entry in feed_events_processed
             fep_dsl::feed_events_processed
@@ -70,11 +129,38 @@ pub async fn stream_unprocessed_feed_event_versions(
                 fev_dsl::kind.eq(prev_version.field(fev_dsl::kind))
                     .and(fev_dsl::entity_id.eq(prev_version.field(fev_dsl::entity_id)))
                     .and(fev_dsl::feed_event_index.eq(prev_version.field(fev_dsl::feed_event_index)))
+                    .and(prev_version.field(fev_dsl::source).is_null())
                     // This is the line that makes the association between
                     // one entry's valid_from and the other's valid_until
                     .and(fev_dsl::valid_from.nullable().eq(prev_version.field(fev_dsl::valid_until)))
             )
         )
+        .into_boxed();
+    // Feed events are processed in order within an entity, so recency is
+    // decided per entity here too
+    let recent_version = diesel::alias!(
+        crate::schema::data_schema::data::feed_event_versions as recent_feed_event_version
+    );
+    let has_recent_version = |since: DateTime<Utc>| {
+        diesel::dsl::exists(
+            recent_version
+                .filter(recent_version.field(fev_dsl::kind).eq(fev_dsl::kind))
+                .filter(recent_version.field(fev_dsl::entity_id).eq(fev_dsl::entity_id))
+                .filter(recent_version.field(fev_dsl::source).is_null())
+                .filter(recent_version.field(fev_dsl::valid_from).ge(since)),
+        )
+    };
+    match pass {
+        ProcessingPass::RecentlyActive(since) => {
+            query = query.filter(has_recent_version(since));
+        }
+        ProcessingPass::Inactive(since) => {
+            query = query.filter(diesel::dsl::not(has_recent_version(since)));
+        }
+        ProcessingPass::All => {}
+    }
+
+    let stream = query
         // Callers of this function rely on the results being sorted by
         // (valid_from, entity_id) with the highest id last
         .order_by((
@@ -101,11 +187,11 @@ pub async fn stream_unprocessed_feed_event_versions(
             String,
             String,
             i32,
-            NaiveDateTime,
-            Option<NaiveDateTime>,
+            DateTime<Utc>,
+            Option<DateTime<Utc>>,
             serde_json::Value,
             // This is the previous version, nullable because it may not exist
-            Option<NaiveDateTime>,
+            Option<DateTime<Utc>>,
             Option<serde_json::Value>,
         )>(conn)
         .await?
@@ -122,8 +208,8 @@ pub async fn stream_unprocessed_feed_event_versions(
             ChronEntity {
                 kind,
                 entity_id,
-                valid_from: valid_from.and_utc(),
-                valid_to: valid_until.map(|dt| dt.and_utc()),
+                valid_from,
+                valid_to: valid_until,
                 // Kind of a hack to smuggle extra data through the machinery
                 data: serde_json::json!({
                     "feed_event_index": feed_event_index,
@@ -131,7 +217,7 @@ pub async fn stream_unprocessed_feed_event_versions(
                     // All other prev_* fields are constrained to be equal to the
                     // corresponding field from the current version, except
                     // prev_valid_until is the current version's valid_from
-                    "prev_valid_from": prev_valid_from.map(|dt| dt.and_utc()),
+                    "prev_valid_from": prev_valid_from,
                     "prev_data": prev_data,
                 }),
             }
@@ -146,26 +232,43 @@ pub async fn stream_unprocessed_feed_event_versions(
 pub(crate) struct DbEntity {
     pub kind: String,
     pub entity_id: String,
-    pub valid_from: NaiveDateTime,
+    pub valid_from: DateTime<Utc>,
     pub data: serde_json::Value,
 }
 
 pub async fn stream_unprocessed_game_versions(
     conn: &mut AsyncPgConnection,
+    pass: ProcessingPass,
 ) -> QueryResult<impl Stream<Item = QueryResult<ChronEntity<serde_json::Value>>>> {
     use crate::schema::data_schema::data::entities::dsl as entities_dsl;
     use crate::schema::data_schema::data::games::dsl as games_dsl;
 
-    let stream = entities_dsl::entities
+    let mut query = entities_dsl::entities
         .filter(entities_dsl::kind.eq("game"))
+        // Only main MMOLB games are processed
+        .filter(entities_dsl::source.is_null())
         .filter(diesel::dsl::not(diesel::dsl::exists(
             // This subquery is meant to check if there is a corresponding entry in games
             games_dsl::games
                 .filter(games_dsl::mmolb_game_id.eq(entities_dsl::entity_id))
                 // We want to consider this entity processed if there exists a game
                 // from its valid_from *or any later valid_from*
-                .filter(games_dsl::from_version.ge(entities_dsl::valid_from)),
+                .filter(timezone_utc("UTC", games_dsl::from_version).ge(entities_dsl::valid_from)),
         )))
+        .into_boxed();
+    // Only the latest version of a game is stored, so a game is recent if
+    // that version is
+    match pass {
+        ProcessingPass::RecentlyActive(since) => {
+            query = query.filter(entities_dsl::valid_from.ge(since));
+        }
+        ProcessingPass::Inactive(since) => {
+            query = query.filter(entities_dsl::valid_from.lt(since));
+        }
+        ProcessingPass::All => {}
+    }
+
+    let stream = query
         // I don't actually know if return order matters for this one
         .order_by((
             entities_dsl::valid_from.asc(),
@@ -177,10 +280,80 @@ pub async fn stream_unprocessed_game_versions(
         .map_ok(|v| ChronEntity {
             kind: v.kind,
             entity_id: v.entity_id,
-            valid_from: v.valid_from.and_utc(),
+            valid_from: v.valid_from,
             valid_to: None, // Anything in `entities` by definition is the latest value
             data: v.data,
         });
 
     Ok(stream)
 }
+
+/// When the last processing pass for `kind` finished, if one ever has
+pub async fn last_ingest_at(
+    conn: &mut AsyncPgConnection,
+    kind: &str,
+) -> QueryResult<Option<DateTime<Utc>>> {
+    use crate::info_schema::info::data_freshness::dsl as df_dsl;
+
+    df_dsl::data_freshness
+        .filter(df_dsl::kind.eq(kind))
+        .select(df_dsl::last_ingest_at)
+        .first(conn)
+        .await
+        .optional()
+}
+
+/// Starts listening for ingest progress on `conn`, and returns a stream of
+/// the events published with `db::publish_ingest_progress`. Notifications
+/// that can't be deserialized (e.g. ones from a newer mmoldb-ingest) are
+/// skipped. The connection shouldn't be used for anything else afterwards.
+pub async fn listen_for_ingest_progress(
+    conn: &mut AsyncPgConnection,
+) -> QueryResult<impl Stream<Item = QueryResult<IngestProgressEvent>> + '_> {
+    diesel::sql_query(format!("listen {INGEST_PROGRESS_CHANNEL}"))
+        .execute(conn)
+        .await?;
+
+    let stream = conn
+        .notifications_stream()
+        .try_filter_map(|notification| {
+            future::ready(Ok(
+                match serde_json::from_str::<IngestProgressEvent>(&notification.payload) {
+                    Ok(event) => Some(event),
+                    Err(err) => {
+                        warn!("Skipping unrecognized ingest progress notification: {err}");
+                        None
+                    }
+                },
+            ))
+        });
+
+    Ok(stream)
+}
+
+/// Runs a `copy ... to stdout` statement on a new connection to `url` and
+/// returns everything it copied. Diesel has no way to `COPY` a query, so
+/// this goes through tokio-postgres directly.
+pub async fn copy_out(url: &str, copy_sql: &str) -> Result<Vec<u8>, tokio_postgres::Error> {
+    let (client, connection) = tokio_postgres::connect(url, tokio_postgres::NoTls).await?;
+    let copy = async move {
+        client
+            .copy_out(copy_sql)
+            .await?
+            .try_fold(Vec::new(), |mut out, chunk| async move {
+                out.extend_from_slice(&chunk);
+                Ok(out)
+            })
+            .await
+    };
+
+    // The connection has to be polled for the copy to make progress. It
+    // only finishes on its own if the connection is lost.
+    match future::select(Box::pin(copy), connection).await {
+        future::Either::Left((copied, _)) => copied,
+        future::Either::Right((connection_result, copy)) => {
+            connection_result?;
+            copy.await
+        }
+    }
+}
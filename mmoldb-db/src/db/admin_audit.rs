@@ -0,0 +1,31 @@
+use diesel::{PgConnection, prelude::*};
+
+use crate::models::{DbAdminAudit, NewAdminAudit};
+
+/// Records an admin operation. Every admin db function calls this inside
+/// the same transaction as its changes, so an operation is audited if and
+/// only if it took effect.
+pub(crate) fn record_admin_action(
+    conn: &mut PgConnection,
+    new_audit: &NewAdminAudit,
+) -> QueryResult<()> {
+    use crate::info_schema::info::admin_audit::dsl as aa_dsl;
+
+    diesel::insert_into(aa_dsl::admin_audit)
+        .values(new_audit)
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// The most recent admin operations, newest first
+pub fn admin_audit_log(conn: &mut PgConnection, limit: i64) -> QueryResult<Vec<DbAdminAudit>> {
+    use crate::info_schema::info::admin_audit::dsl as aa_dsl;
+
+    aa_dsl::admin_audit
+        .order_by(aa_dsl::at.desc())
+        .then_order_by(aa_dsl::id.desc())
+        .limit(limit)
+        .select(DbAdminAudit::as_select())
+        .get_results(conn)
+}
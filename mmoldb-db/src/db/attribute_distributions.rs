@@ -0,0 +1,184 @@
+use chrono::NaiveDate;
+use diesel::{PgConnection, prelude::*, sql_query, sql_types::*};
+
+use crate::models::DbAttributeDistribution;
+
+/// Snapshots the distribution of every attribute across players who are
+/// currently on a team, replacing this week's snapshot if there already is
+/// one. Attribute values are the `base_total` from each player's current
+/// reports. Returns how many attributes were snapshotted.
+pub fn snapshot_attribute_distributions(conn: &mut PgConnection) -> QueryResult<usize> {
+    let Some(season) = super::latest_game_season(conn)? else {
+        return Ok(0);
+    };
+
+    // 0 through 1 in steps of 0.01, so there's one value per percentile
+    let fractions = (0..=100).map(|i| i as f64 / 100.0).collect::<Vec<_>>();
+
+    sql_query(
+        "
+        with active_players as (
+            select pv.mmolb_player_id
+            from data.player_versions pv
+            where pv.valid_until is null and pv.mmolb_team_id is not null
+        ),
+        attribute_values as (
+            select rav.attribute, rav.base_total as value
+            from data.player_report_attribute_versions rav
+            join active_players ap on ap.mmolb_player_id=rav.mmolb_player_id
+            where rav.valid_until is null and rav.base_total is not null
+        )
+        insert into data.attribute_distributions
+            (season, week_start, attribute, num_players, mean, percentiles, taken_at)
+        select
+            $1,
+            data.game_week_start(now()),
+            av.attribute,
+            count(1)::int,
+            avg(av.value),
+            percentile_cont($2) within group (order by av.value),
+            now()
+        from attribute_values av
+        group by av.attribute
+        on conflict (season, week_start, attribute) do update set
+            num_players=excluded.num_players,
+            mean=excluded.mean,
+            percentiles=excluded.percentiles,
+            taken_at=excluded.taken_at
+    ",
+    )
+    .bind::<Int4, _>(season)
+    .bind::<Array<Float8>, _>(&fractions)
+    .execute(conn)
+}
+
+/// Attribute distribution snapshots, ordered by week and then attribute.
+/// If neither `season` nor `week_start` is given, only the latest week's
+/// snapshots are returned.
+pub fn attribute_distributions(
+    conn: &mut PgConnection,
+    season: Option<i32>,
+    week_start: Option<NaiveDate>,
+    attribute: Option<i64>,
+) -> QueryResult<Vec<DbAttributeDistribution>> {
+    use crate::data_schema::data::attribute_distributions::dsl as ad_dsl;
+
+    let mut query = ad_dsl::attribute_distributions.into_boxed();
+    if let Some(season) = season {
+        query = query.filter(ad_dsl::season.eq(season));
+    }
+    if let Some(week_start) = week_start {
+        query = query.filter(ad_dsl::week_start.eq(week_start));
+    }
+    if season.is_none() && week_start.is_none() {
+        let latest_week = ad_dsl::attribute_distributions
+            .select(diesel::dsl::max(ad_dsl::week_start))
+            .get_result::<Option<NaiveDate>>(conn)?;
+        let Some(latest_week) = latest_week else {
+            return Ok(Vec::new());
+        };
+        query = query.filter(ad_dsl::week_start.eq(latest_week));
+    }
+    if let Some(attribute) = attribute {
+        query = query.filter(ad_dsl::attribute.eq(attribute));
+    }
+
+    query
+        .order_by((ad_dsl::week_start.asc(), ad_dsl::attribute.asc()))
+        .select(DbAttributeDistribution::as_select())
+        .get_results(conn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{QueryError, postgres_url_from_environment};
+
+    #[test]
+    fn snapshots_only_count_players_on_a_team_and_replace_this_weeks() {
+        let url = postgres_url_from_environment();
+        let mut conn = PgConnection::establish(&url)
+            .expect("postgres_url_from_environment should return a valid connection string");
+
+        conn.test_transaction(|conn| {
+            // A season later than any real one, so it's the latest
+            sql_query(
+                "
+                with w as (
+                    insert into data.weather (name, emoji, tooltip)
+                    values ('Test Weather', '', '')
+                    returning id
+                )
+                insert into data.games
+                    (mmolb_game_id, weather, season, superstar_day, away_team_emoji,
+                     away_team_name, away_team_mmolb_id, home_team_emoji, home_team_name,
+                     home_team_mmolb_id, is_ongoing, from_version, started_at)
+                select 'ffffffffffffffffffff0001', w.id, 9999, null, '', 'Away',
+                    'distribution-test-away', '', 'Home', 'distribution-test-home', false,
+                    '2025-02-15', '2025-02-15T00:00:00Z'
+                from w
+            ",
+            )
+            .execute(conn)?;
+
+            // Only the test players should have a value for this attribute
+            let attribute: i64 = {
+                use crate::taxa_schema::taxa::attribute::dsl as attr_dsl;
+                attr_dsl::attribute
+                    .select(diesel::dsl::min(attr_dsl::id))
+                    .get_result::<Option<i64>>(conn)?
+                    .expect("taxa.attribute should have rows")
+            };
+            sql_query("delete from data.player_report_attribute_versions where attribute=$1")
+                .bind::<Int8, _>(attribute)
+                .execute(conn)?;
+
+            // The last player isn't on a team, so its value is left out
+            sql_query(
+                "
+                with players as (
+                    select * from (
+                        values ('ffffffffffffffffffff0001', 'distribution-test-team', 0.1),
+                            ('ffffffffffffffffffff0002', 'distribution-test-team', 0.2),
+                            ('ffffffffffffffffffff0003', 'distribution-test-team', 0.3),
+                            ('ffffffffffffffffffff0004', null, 0.9)
+                    ) as p(mmolb_player_id, mmolb_team_id, value)
+                ),
+                versions as (
+                    insert into data.player_versions
+                        (mmolb_player_id, valid_from, first_name, last_name, home, birthseason,
+                         likes, dislikes, number, mmolb_team_id, num_modifications,
+                         occupied_equipment_slots, included_report_categories)
+                    select p.mmolb_player_id, '2025-01-01', 'Test', p.mmolb_player_id, 'Here',
+                        0, '', '', 0, p.mmolb_team_id, 0, '{}', '{}'
+                    from players p
+                )
+                insert into data.player_report_attribute_versions
+                    (mmolb_player_id, category, attribute, valid_from, base_total)
+                select p.mmolb_player_id, (select min(id) from taxa.attribute_category), $1,
+                    '2025-01-01', p.value
+                from players p
+            ",
+            )
+            .bind::<Int8, _>(attribute)
+            .execute(conn)?;
+
+            // Taking the snapshot again this week replaces the first one
+            snapshot_attribute_distributions(conn)?;
+            snapshot_attribute_distributions(conn)?;
+
+            let distributions = attribute_distributions(conn, Some(9999), None, Some(attribute))?;
+            assert_eq!(distributions.len(), 1);
+            let distribution = &distributions[0];
+            assert_eq!(distribution.num_players, 3);
+            assert!((distribution.mean - 0.2).abs() < 1e-9);
+            assert_eq!(distribution.percentiles.len(), 101);
+            let percentile = |n: usize| distribution.percentiles[n].unwrap();
+            assert!((percentile(0) - 0.1).abs() < 1e-9);
+            assert!((percentile(50) - 0.2).abs() < 1e-9);
+            assert!((percentile(100) - 0.3).abs() < 1e-9);
+
+            Ok::<_, QueryError>(())
+        });
+    }
+}
@@ -0,0 +1,191 @@
+use chrono::{DateTime, Utc};
+use diesel::{PgConnection, prelude::*, sql_query, sql_types::*};
+
+/// One value of one of a player's attributes, from a single row of
+/// `data.player_report_attribute_versions`
+#[derive(Debug, QueryableByName)]
+pub struct DbAttributeProgressionPoint {
+    #[diesel(sql_type = Int8)]
+    pub attribute: i64,
+    #[diesel(sql_type = Timestamptz)]
+    pub valid_from: DateTime<Utc>,
+    #[diesel(sql_type = Nullable<Int4>)]
+    pub base_stars: Option<i32>,
+    #[diesel(sql_type = Nullable<Float8>)]
+    pub base_total: Option<f64>,
+    #[diesel(sql_type = Nullable<Int4>)]
+    pub modified_stars: Option<i32>,
+    #[diesel(sql_type = Nullable<Float8>)]
+    pub modified_total: Option<f64>,
+    /// `augment`, `paradigm_shift`, `recomposition`, or `equipment`. Null
+    /// for each attribute's first value, and for changes nothing in the
+    /// database explains.
+    #[diesel(sql_type = Nullable<Text>)]
+    pub cause: Option<String>,
+    /// When the cause happened. Usually a little before `valid_from`, since
+    /// reports are only seen when the player is next fetched.
+    #[diesel(sql_type = Nullable<Timestamptz>)]
+    pub cause_time: Option<DateTime<Utc>>,
+}
+
+/// The values of a player's attributes over time, ordered by attribute and
+/// then time, with the likeliest cause of each change.
+///
+/// Each change is attributed to the latest augment, paradigm shift, or
+/// recomposition that affected that attribute since the previous value. If
+/// only the modified value changed, it's attributed to the latest change to
+/// the player's equipment effects on that attribute instead.
+pub fn attribute_progression(
+    conn: &mut PgConnection,
+    mmolb_player_id: &str,
+    attribute: Option<i64>,
+) -> QueryResult<Vec<DbAttributeProgressionPoint>> {
+    sql_query(
+        "
+        with points as (
+            select
+                rav.attribute,
+                rav.valid_from,
+                rav.base_stars,
+                rav.base_total,
+                rav.modified_stars,
+                rav.modified_total,
+                lag(rav.valid_from) over w as previous_valid_from,
+                lag(rav.base_total) over w as previous_base_total
+            from data.player_report_attribute_versions rav
+            where rav.mmolb_player_id=$1 and ($2 is null or rav.attribute=$2)
+            window w as (partition by rav.attribute order by rav.valid_from)
+        ),
+        causes as (
+            -- Feed event times are stored without a time zone, but are UTC
+            select attribute, time at time zone 'UTC' as time, 'augment' as cause
+            from data.player_attribute_augments
+            where mmolb_player_id=$1
+            union all
+            select attribute, time at time zone 'UTC', 'paradigm_shift'
+            from data.player_paradigm_shifts
+            where mmolb_player_id=$1
+            union all
+            -- Recompositions replace every attribute
+            select null, time at time zone 'UTC', 'recomposition'
+            from data.player_recompositions
+            where mmolb_player_id=$1
+            union all
+            select attribute, valid_from, 'equipment'
+            from data.player_equipment_effect_versions
+            where mmolb_player_id=$1
+        )
+        select
+            p.attribute,
+            p.valid_from,
+            p.base_stars,
+            p.base_total,
+            p.modified_stars,
+            p.modified_total,
+            c.cause,
+            c.time as cause_time
+        from points p
+        left join lateral (
+            select c.cause, c.time
+            from causes c
+            where p.previous_valid_from is not null
+                and (c.attribute is null or c.attribute=p.attribute)
+                and p.previous_valid_from < c.time and c.time <= p.valid_from
+                and (c.cause='equipment') = (p.base_total is not distinct from p.previous_base_total)
+            order by c.time desc
+            limit 1
+        ) c on true
+        order by p.attribute, p.valid_from
+    ",
+    )
+    .bind::<Text, _>(mmolb_player_id)
+    .bind::<Nullable<Int8>, _>(attribute)
+    .get_results(conn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::taxa::{Taxa, TaxaAttribute, TaxaAttributeCategory, TaxaEffectType};
+    use crate::{QueryError, postgres_url_from_environment};
+
+    #[test]
+    fn changes_are_attributed_to_the_latest_matching_cause() {
+        let url = postgres_url_from_environment();
+        let mut conn = PgConnection::establish(&url)
+            .expect("postgres_url_from_environment should return a valid connection string");
+
+        conn.test_transaction(|conn| {
+            let taxa = Taxa::new(conn)?;
+            let luck = taxa.attribute_id(TaxaAttribute::Luck);
+            let priority = taxa.attribute_id(TaxaAttribute::Priority);
+
+            // Luck's base goes up after an augment, then only its modified
+            // value goes up after an equipment change. The later augment to
+            // Priority doesn't explain anything about Luck.
+            sql_query(
+                "
+                insert into data.player_report_attribute_versions
+                    (mmolb_player_id, category, attribute, valid_from, base_total, modified_total)
+                values
+                    ('attribute-progression-test-player', $1, $2, '2026-01-01 01:00Z', 1.0, 1.0),
+                    ('attribute-progression-test-player', $1, $2, '2026-01-01 02:00Z', 1.5, 1.5),
+                    ('attribute-progression-test-player', $1, $2, '2026-01-01 03:00Z', 1.5, 2.0)
+                ",
+            )
+            .bind::<Int8, _>(taxa.attribute_category_id(TaxaAttributeCategory::Batting))
+            .bind::<Int8, _>(luck)
+            .execute(conn)?;
+            sql_query(
+                "
+                insert into data.player_attribute_augments
+                    (mmolb_player_id, feed_event_index, time, attribute, value, season)
+                values
+                    ('attribute-progression-test-player', 0, '2026-01-01 01:30', $1, 5, 1),
+                    ('attribute-progression-test-player', 1, '2026-01-01 01:45', $2, 5, 1)
+                ",
+            )
+            .bind::<Int8, _>(luck)
+            .bind::<Int8, _>(priority)
+            .execute(conn)?;
+            sql_query(
+                "
+                insert into data.player_equipment_effect_versions
+                    (mmolb_player_id, equipment_slot, effect_index, valid_from, duplicates,
+                     attribute, effect_type, value, implicit)
+                values
+                    ('attribute-progression-test-player', 'Head', 0, '2026-01-01 02:30Z', 0,
+                     $1, $2, 0.5, false)
+                ",
+            )
+            .bind::<Int8, _>(luck)
+            .bind::<Int8, _>(taxa.effect_type_id(TaxaEffectType::Flat))
+            .execute(conn)?;
+
+            let points =
+                attribute_progression(conn, "attribute-progression-test-player", Some(luck))?;
+            let causes = points
+                .iter()
+                .map(|point| {
+                    (
+                        point.cause.as_deref(),
+                        point.cause_time.map(|time| time.to_string()),
+                    )
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(
+                causes,
+                [
+                    (None, None),
+                    (Some("augment"), Some("2026-01-01 01:30:00 UTC".to_string())),
+                    (
+                        Some("equipment"),
+                        Some("2026-01-01 02:30:00 UTC".to_string())
+                    ),
+                ],
+            );
+
+            Ok::<_, QueryError>(())
+        });
+    }
+}
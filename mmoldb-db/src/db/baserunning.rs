@@ -0,0 +1,145 @@
+use diesel::{PgConnection, prelude::*, sql_query, sql_types::*};
+use serde::Serialize;
+
+// Classifies each event_baserunners row as a taxa.base_movement id. This
+// must agree with the flags on TaxaBaseMovement. Rows with a null
+// base_before are the batter, whose outcome is already on the event, so
+// they're excluded.
+const BASE_MOVEMENT_CTE: &str = "
+    base_movements as (
+        select
+            eb.id as event_baserunner_id,
+            case
+                when eb.steal and not eb.is_out then 1
+                when eb.steal and eb.is_out then 2
+                when eb.is_out then 5
+                when bb.bases_achieved < ba.bases_achieved then 3
+                else 4
+            end as base_movement
+        from data.event_baserunners eb
+        join taxa.base bb on bb.id=eb.base_before
+        join taxa.base ba on ba.id=eb.base_after
+    )
+";
+
+#[derive(Debug, Clone, QueryableByName, Serialize)]
+pub struct DbBaserunningOutcome {
+    #[diesel(sql_type = Text)]
+    pub mmolb_game_id: String,
+    #[diesel(sql_type = Int4)]
+    pub season: i32,
+    #[diesel(sql_type = Nullable<Int4>)]
+    pub day: Option<i32>,
+    #[diesel(sql_type = Int4)]
+    pub game_event_index: i32,
+    #[diesel(sql_type = Int4)]
+    pub inning: i32,
+    #[diesel(sql_type = Bool)]
+    pub top_of_inning: bool,
+    #[diesel(sql_type = Int4)]
+    pub outs_before: i32,
+    #[diesel(sql_type = Text)]
+    pub baserunner_name: String,
+    #[diesel(sql_type = Text)]
+    pub base_before: String,
+    #[diesel(sql_type = Text)]
+    pub base_after: String,
+    /// The name of the `taxa.base_movement` this runner made
+    #[diesel(sql_type = Text)]
+    pub movement: String,
+    #[diesel(sql_type = Bool)]
+    pub is_steal_attempt: bool,
+    #[diesel(sql_type = Bool)]
+    pub is_out: bool,
+    #[diesel(sql_type = Bool)]
+    pub is_advance: bool,
+    #[diesel(sql_type = Bool)]
+    pub scored: bool,
+}
+
+/// Every movement by `baserunner_name` while they were on base, in game
+/// order, optionally limited to one season. Baserunners are only recorded
+/// by name, so players who share a name are combined.
+pub fn baserunning_outcomes(
+    conn: &mut PgConnection,
+    baserunner_name: &str,
+    season: Option<i32>,
+) -> QueryResult<Vec<DbBaserunningOutcome>> {
+    sql_query(format!(
+        "
+        with {BASE_MOVEMENT_CTE}
+        select
+            g.mmolb_game_id,
+            g.season,
+            g.day,
+            ev.game_event_index,
+            ev.inning,
+            ev.top_of_inning,
+            ev.outs_before,
+            eb.baserunner_name,
+            bb.name as base_before,
+            ba.name as base_after,
+            bm.name as movement,
+            bm.is_steal_attempt,
+            bm.is_out,
+            bm.is_advance,
+            (bm.is_advance and ba.bases_achieved=4) as scored
+        from data.event_baserunners eb
+        join base_movements m on m.event_baserunner_id=eb.id
+        join taxa.base_movement bm on bm.id=m.base_movement
+        join taxa.base bb on bb.id=eb.base_before
+        join taxa.base ba on ba.id=eb.base_after
+        join data.events ev on ev.id=eb.event_id
+        join data.games g on g.id=ev.game_id
+        where eb.baserunner_name=$1
+            and ($2 is null or g.season=$2)
+        order by g.season, g.day nulls last, g.id, ev.game_event_index, eb.id
+    "
+    ))
+    .bind::<Text, _>(baserunner_name)
+    .bind::<Nullable<Int4>, _>(season)
+    .get_results(conn)
+}
+
+#[derive(Debug, Clone, QueryableByName, Serialize)]
+pub struct DbBaserunningTotals {
+    #[diesel(sql_type = Text)]
+    pub baserunner_name: String,
+    #[diesel(sql_type = Int8)]
+    pub stolen_bases: i64,
+    #[diesel(sql_type = Int8)]
+    pub caught_stealing: i64,
+    #[diesel(sql_type = Int8)]
+    pub advances: i64,
+    #[diesel(sql_type = Int8)]
+    pub outs_on_bases: i64,
+}
+
+/// Stolen base, caught stealing, and other baserunning counts for every
+/// baserunner in a season, most stolen bases first.
+pub fn baserunning_totals(
+    conn: &mut PgConnection,
+    season: i32,
+) -> QueryResult<Vec<DbBaserunningTotals>> {
+    sql_query(format!(
+        "
+        with {BASE_MOVEMENT_CTE}
+        select
+            eb.baserunner_name,
+            count(*) filter (where bm.is_steal_attempt and not bm.is_out) as stolen_bases,
+            count(*) filter (where bm.is_steal_attempt and bm.is_out) as caught_stealing,
+            count(*) filter (where not bm.is_steal_attempt and bm.is_advance) as advances,
+            count(*) filter (where not bm.is_steal_attempt and bm.is_out) as outs_on_bases
+        from data.event_baserunners eb
+        join base_movements m on m.event_baserunner_id=eb.id
+        join taxa.base_movement bm on bm.id=m.base_movement
+        join data.events ev on ev.id=eb.event_id
+        join data.games g on g.id=ev.game_id
+        where g.season=$1
+        group by eb.baserunner_name
+        order by stolen_bases desc, eb.baserunner_name
+    "
+    ))
+    .bind::<Int4, _>(season)
+    .get_results(conn)
+}
@@ -0,0 +1,16 @@
+use diesel::{PgConnection, prelude::*};
+
+use crate::models::NewChronAnomaly;
+
+pub fn insert_chron_anomaly(
+    conn: &mut PgConnection,
+    new_anomaly: &NewChronAnomaly,
+) -> QueryResult<()> {
+    use crate::info_schema::info::chron_anomalies::dsl as ca_dsl;
+
+    diesel::insert_into(ca_dsl::chron_anomalies)
+        .values(new_anomaly)
+        .execute(conn)?;
+
+    Ok(())
+}
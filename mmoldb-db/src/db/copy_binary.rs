@@ -0,0 +1,306 @@
+//! An encoder for Postgres' binary `COPY` format, for the tables that are
+//! large enough that building every row struct up front is a noticeable
+//! share of ingest time and memory. Event rows are built and encoded one at
+//! a time, straight from each game's events, and sent in chunks, so at most
+//! one chunk of encoded rows is held at a time.
+//!
+//! See https://www.postgresql.org/docs/current/sql-copy.html#id-1.9.3.55.9.4
+
+use super::to_db_format;
+use crate::EventDetail;
+use crate::data_schema::data::events::dsl as events_dsl;
+use crate::models::NewEvent;
+use crate::taxa::Taxa;
+use chrono::Utc;
+use diesel::PgConnection;
+use diesel::prelude::*;
+use std::cell::Cell;
+use std::io::Write;
+
+const SIGNATURE: &[u8] = b"PGCOPY\n\xff\r\n\0";
+
+/// Encoded rows are sent once the buffer grows past this size
+const CHUNK_SIZE_BYTES: usize = 1 << 20;
+
+/// A value that can be written as one field of a binary `COPY` row. The
+/// encoding must match the column's Postgres type exactly, since the server
+/// doesn't convert anything in binary mode.
+trait CopyBinaryField {
+    fn encode(&self, buf: &mut Vec<u8>);
+}
+
+impl CopyBinaryField for bool {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&1i32.to_be_bytes());
+        buf.push(*self as u8);
+    }
+}
+
+impl CopyBinaryField for i32 {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&4i32.to_be_bytes());
+        buf.extend_from_slice(&self.to_be_bytes());
+    }
+}
+
+impl CopyBinaryField for i64 {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&8i32.to_be_bytes());
+        buf.extend_from_slice(&self.to_be_bytes());
+    }
+}
+
+impl CopyBinaryField for f64 {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&8i32.to_be_bytes());
+        buf.extend_from_slice(&self.to_be_bytes());
+    }
+}
+
+impl CopyBinaryField for &str {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        let len = i32::try_from(self.len()).expect("text fields must be shorter than 2GiB");
+        buf.extend_from_slice(&len.to_be_bytes());
+        buf.extend_from_slice(self.as_bytes());
+    }
+}
+
+impl<T: CopyBinaryField> CopyBinaryField for Option<T> {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        match self {
+            None => buf.extend_from_slice(&(-1i32).to_be_bytes()),
+            Some(value) => value.encode(buf),
+        }
+    }
+}
+
+/// How many columns each event row has
+const EVENT_NUM_FIELDS: i16 = 33;
+
+// Must match the column order in `copy_event_rows`
+fn encode_event_fields(event: &NewEvent, buf: &mut Vec<u8>) {
+    event.game_id.encode(buf);
+    event.game_event_index.encode(buf);
+    event.fair_ball_event_index.encode(buf);
+    event.inning.encode(buf);
+    event.top_of_inning.encode(buf);
+    event.event_type.encode(buf);
+    event.hit_base.encode(buf);
+    event.fair_ball_type.encode(buf);
+    event.fair_ball_direction.encode(buf);
+    event.fair_ball_fielder_name.encode(buf);
+    event.fielding_error_type.encode(buf);
+    event.pitch_type.encode(buf);
+    event.pitch_speed.encode(buf);
+    event.pitch_zone.encode(buf);
+    event.described_as_sacrifice.encode(buf);
+    event.is_toasty.encode(buf);
+    event.balls_before.encode(buf);
+    event.strikes_before.encode(buf);
+    event.outs_before.encode(buf);
+    event.outs_after.encode(buf);
+    event.errors_before.encode(buf);
+    event.errors_after.encode(buf);
+    event.away_team_score_before.encode(buf);
+    event.away_team_score_after.encode(buf);
+    event.home_team_score_before.encode(buf);
+    event.home_team_score_after.encode(buf);
+    event.pitcher_name.encode(buf);
+    event.pitcher_count.encode(buf);
+    event.batter_name.encode(buf);
+    event.batter_count.encode(buf);
+    event.batter_subcount.encode(buf);
+    event.home_run_distance.encode(buf);
+    event.is_surprise_strike.encode(buf);
+}
+
+/// Time spent in each phase of [`copy_event_rows`], in seconds
+#[derive(Debug, Default, Clone, Copy)]
+pub struct EventCopyTimings {
+    /// Building and encoding each event's row
+    pub encode_duration: f64,
+    /// Writing the rows to the connection. The rest of the `COPY` is
+    /// Postgres finishing it.
+    pub send_duration: f64,
+}
+
+/// [`EventCopyTimings`] as Cells, so they can be filled in from inside
+/// diesel's `from_raw_data` callback
+#[derive(Default)]
+struct CopyBinaryTimings {
+    encode_duration: Cell<f64>,
+    send_duration: Cell<f64>,
+}
+
+fn add_seconds_since(total: &Cell<f64>, start: chrono::DateTime<Utc>) {
+    total.set(total.get() + (Utc::now() - start).as_seconds_f64());
+}
+
+fn encode_header(buf: &mut Vec<u8>) {
+    buf.extend_from_slice(SIGNATURE);
+    buf.extend_from_slice(&0i32.to_be_bytes()); // flags
+    buf.extend_from_slice(&0i32.to_be_bytes()); // header extension length
+}
+
+fn encode_trailer(buf: &mut Vec<u8>) {
+    buf.extend_from_slice(&(-1i16).to_be_bytes());
+}
+
+/// Encodes every game's events with `encode_fields` and writes them to
+/// `copy` in chunks, so at most one chunk of encoded rows exists at a time.
+/// Meant to be called from diesel's `from_raw_data` callback with the
+/// `CopyFormat::Binary` format.
+fn write_event_rows<E>(
+    copy: &mut dyn Write,
+    games: &[(i64, &[E])],
+    encode_fields: impl Fn(i64, &E, &mut Vec<u8>),
+    timings: &CopyBinaryTimings,
+) -> diesel::QueryResult<()> {
+    let mut send = |buf: &mut Vec<u8>| {
+        let send_start = Utc::now();
+        copy.write_all(buf)
+            .map_err(|err| diesel::result::Error::SerializationError(Box::new(err)))?;
+        buf.clear();
+        add_seconds_since(&timings.send_duration, send_start);
+        Ok::<_, diesel::result::Error>(())
+    };
+
+    let mut buf = Vec::with_capacity(CHUNK_SIZE_BYTES);
+    let mut encode_start = Utc::now();
+    encode_header(&mut buf);
+    for (game_id, events) in games {
+        for event in *events {
+            buf.extend_from_slice(&EVENT_NUM_FIELDS.to_be_bytes());
+            encode_fields(*game_id, event, &mut buf);
+            if buf.len() >= CHUNK_SIZE_BYTES {
+                add_seconds_since(&timings.encode_duration, encode_start);
+                send(&mut buf)?;
+                encode_start = Utc::now();
+            }
+        }
+    }
+    encode_trailer(&mut buf);
+    add_seconds_since(&timings.encode_duration, encode_start);
+    send(&mut buf)
+}
+
+/// Inserts each game's events into `data.events` with a binary `COPY`.
+/// Returns how many rows were inserted.
+pub fn copy_event_rows(
+    conn: &mut PgConnection,
+    taxa: &Taxa,
+    games: &[(i64, &[EventDetail<&str>])],
+) -> QueryResult<(usize, EventCopyTimings)> {
+    let timings = CopyBinaryTimings::default();
+    let num_inserted = diesel::copy_from(events_dsl::events)
+        .from_raw_data(
+            // Must match the field order in `encode_event_fields`
+            (
+                events_dsl::game_id,
+                events_dsl::game_event_index,
+                events_dsl::fair_ball_event_index,
+                events_dsl::inning,
+                events_dsl::top_of_inning,
+                events_dsl::event_type,
+                events_dsl::hit_base,
+                events_dsl::fair_ball_type,
+                events_dsl::fair_ball_direction,
+                events_dsl::fair_ball_fielder_name,
+                events_dsl::fielding_error_type,
+                events_dsl::pitch_type,
+                events_dsl::pitch_speed,
+                events_dsl::pitch_zone,
+                events_dsl::described_as_sacrifice,
+                events_dsl::is_toasty,
+                events_dsl::balls_before,
+                events_dsl::strikes_before,
+                events_dsl::outs_before,
+                events_dsl::outs_after,
+                events_dsl::errors_before,
+                events_dsl::errors_after,
+                events_dsl::away_team_score_before,
+                events_dsl::away_team_score_after,
+                events_dsl::home_team_score_before,
+                events_dsl::home_team_score_after,
+                events_dsl::pitcher_name,
+                events_dsl::pitcher_count,
+                events_dsl::batter_name,
+                events_dsl::batter_count,
+                events_dsl::batter_subcount,
+                events_dsl::home_run_distance,
+                events_dsl::is_surprise_strike,
+            ),
+            |copy: &mut dyn Write| {
+                write_event_rows(
+                    copy,
+                    games,
+                    |game_id, event, buf| {
+                        encode_event_fields(&to_db_format::event_to_row(taxa, game_id, event), buf)
+                    },
+                    &timings,
+                )
+            },
+        )
+        .with_format(diesel::pg::CopyFormat::Binary)
+        .execute(conn)?;
+
+    Ok((
+        num_inserted,
+        EventCopyTimings {
+            encode_duration: timings.encode_duration.get(),
+            send_duration: timings.send_duration.get(),
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_fields_with_their_lengths() {
+        let mut buf = Vec::new();
+        1i64.encode(&mut buf);
+        "ab".encode(&mut buf);
+        "".encode(&mut buf);
+        Some(-2i32).encode(&mut buf);
+        None::<i32>.encode(&mut buf);
+
+        let mut expected = Vec::new();
+        expected.extend_from_slice(&[0, 0, 0, 8, 0, 0, 0, 0, 0, 0, 0, 1]);
+        expected.extend_from_slice(&[0, 0, 0, 2, b'a', b'b']);
+        expected.extend_from_slice(&[0, 0, 0, 0]);
+        expected.extend_from_slice(&[0, 0, 0, 4, 0xff, 0xff, 0xff, 0xfe]);
+        expected.extend_from_slice(&[0xff, 0xff, 0xff, 0xff]);
+
+        assert_eq!(buf, expected);
+    }
+
+    #[test]
+    fn encodes_each_games_events_between_header_and_trailer() {
+        // `write_event_rows` only adds the field count, so these don't have
+        // to be real events
+        let first_game = [1u8, 2];
+        let second_game = [3u8];
+
+        let mut out = Vec::new();
+        write_event_rows(
+            &mut out,
+            &[(7, &first_game[..]), (8, &[][..]), (9, &second_game[..])],
+            |game_id, event, buf| buf.extend_from_slice(&[game_id as u8, *event]),
+            &CopyBinaryTimings::default(),
+        )
+        .unwrap();
+
+        let mut expected = Vec::new();
+        expected.extend_from_slice(b"PGCOPY\n\xff\r\n\0");
+        expected.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 0]);
+        for (game_id, event) in [(7u8, 1u8), (7, 2), (9, 3)] {
+            expected.extend_from_slice(&[0, 33, game_id, event]);
+        }
+        // Trailer
+        expected.extend_from_slice(&[0xff, 0xff]);
+
+        assert_eq!(out, expected);
+    }
+}
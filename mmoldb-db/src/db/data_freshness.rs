@@ -0,0 +1,34 @@
+use diesel::{PgConnection, prelude::*, sql_query, sql_types::*};
+
+use crate::models::DbDataFreshness;
+
+/// Records that a processing pass for `kind` just finished, along with the
+/// latest `valid_from` that kind has processed. Versioned and feed kinds
+/// are measured from their processed markers and games from their source
+/// versions.
+pub fn record_data_freshness(conn: &mut PgConnection, kind: &str) -> QueryResult<usize> {
+    sql_query(
+        "
+        insert into info.data_freshness (kind, last_valid_from, last_ingest_at)
+        select $1, greatest(
+            (select max(vp.valid_from) from data.versions_processed vp where vp.kind=$1),
+            (select max(fep.valid_from) from data.feed_events_processed fep where fep.kind=$1),
+            (select max(g.from_version) at time zone 'utc' from data.games g where $1='game')
+        ), now()
+        on conflict (kind) do update
+            set last_valid_from=excluded.last_valid_from,
+                last_ingest_at=excluded.last_ingest_at
+    ",
+    )
+    .bind::<Text, _>(kind)
+    .execute(conn)
+}
+
+pub fn data_freshness(conn: &mut PgConnection) -> QueryResult<Vec<DbDataFreshness>> {
+    use crate::info_schema::info::data_freshness::dsl as df_dsl;
+
+    df_dsl::data_freshness
+        .order_by(df_dsl::kind.asc())
+        .select(DbDataFreshness::as_select())
+        .get_results(conn)
+}
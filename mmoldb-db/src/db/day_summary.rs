@@ -0,0 +1,181 @@
+use chrono::NaiveDateTime;
+use diesel::{PgConnection, prelude::*, sql_query, sql_types::*};
+
+use crate::db::{PitchSpeedRecord, PlayerNameResolution, resolve_player_names};
+use crate::models::DbGame;
+
+/// One day of games. Exactly one of `day` and `superstar_day` is set.
+#[derive(Debug, Clone, QueryableByName)]
+pub struct DbGameDay {
+    #[diesel(sql_type = Int4)]
+    pub season: i32,
+    #[diesel(sql_type = Nullable<Int4>)]
+    pub day: Option<i32>,
+    #[diesel(sql_type = Nullable<Int4>)]
+    pub superstar_day: Option<i32>,
+}
+
+/// The latest day whose games have all finished. A day that still has a
+/// game in progress is skipped, so the summary never covers part of a day.
+pub fn latest_finished_game_day(conn: &mut PgConnection) -> QueryResult<Option<DbGameDay>> {
+    sql_query(
+        "
+        select g.season, g.day, g.superstar_day
+        from data.games g
+        group by g.season, g.day, g.superstar_day
+        having bool_and(not g.is_ongoing)
+        order by g.season desc, max(g.started_at) desc
+        limit 1
+    ",
+    )
+    .get_result(conn)
+    .optional()
+}
+
+// These all take the day as binds $1 (season), $2 (day), and $3
+// (superstar_day)
+const ON_DAY: &str = "
+    g.season=$1
+    and g.day is not distinct from $2
+    and g.superstar_day is not distinct from $3
+";
+
+#[derive(QueryableByName)]
+struct GameCount {
+    #[diesel(sql_type = Int8)]
+    num_games: i64,
+}
+
+pub fn num_games_on_day(conn: &mut PgConnection, day: &DbGameDay) -> QueryResult<i64> {
+    let count = sql_query(format!(
+        "
+        select count(*) as num_games
+        from data.games g
+        where {ON_DAY}
+    "
+    ))
+    .bind::<Int4, _>(day.season)
+    .bind::<Nullable<Int4>, _>(day.day)
+    .bind::<Nullable<Int4>, _>(day.superstar_day)
+    .get_result::<GameCount>(conn)?;
+    Ok(count.num_games)
+}
+
+/// The finished game on `day` with the largest difference between the
+/// final scores
+pub fn biggest_blowout_on_day(
+    conn: &mut PgConnection,
+    day: &DbGameDay,
+) -> QueryResult<Option<DbGame>> {
+    sql_query(format!(
+        "
+        select g.*
+        from data.games g
+        where {ON_DAY}
+            and not g.is_ongoing
+            and g.away_team_final_score is not null
+            and g.home_team_final_score is not null
+        order by abs(g.away_team_final_score - g.home_team_final_score) desc,
+            g.mmolb_game_id asc
+        limit 1
+    "
+    ))
+    .bind::<Int4, _>(day.season)
+    .bind::<Nullable<Int4>, _>(day.day)
+    .bind::<Nullable<Int4>, _>(day.superstar_day)
+    .get_result(conn)
+    .optional()
+}
+
+#[derive(QueryableByName)]
+struct DayFastestPitch {
+    #[diesel(sql_type = Text)]
+    mmolb_team_id: String,
+    #[diesel(sql_type = Text)]
+    team_emoji: String,
+    #[diesel(sql_type = Text)]
+    team_location: String,
+    #[diesel(sql_type = Text)]
+    team_name: String,
+    #[diesel(sql_type = Text)]
+    pitcher_name: String,
+    #[diesel(sql_type = Timestamp)]
+    game_end_time: NaiveDateTime,
+    #[diesel(sql_type = Text)]
+    mmolb_game_id: String,
+    #[diesel(sql_type = Integer)]
+    game_event_index: i32,
+    #[diesel(sql_type = Float8)]
+    pitch_speed: f64,
+}
+
+/// The fastest pitch thrown on `day`, with the pitcher's team as it was at
+/// the time. A pitcher who wasn't on their team's roster under that name
+/// isn't credited with the record, and if several players on the roster had
+/// the name, the one with the lowest id is.
+pub fn fastest_pitch_on_day(
+    conn: &mut PgConnection,
+    day: &DbGameDay,
+) -> QueryResult<Option<PitchSpeedRecord>> {
+    let Some(pitch) = sql_query(
+        "
+        select
+            tv.mmolb_team_id,
+            tv.emoji as team_emoji,
+            tv.location as team_location,
+            tv.name as team_name,
+            ee.pitcher_name,
+            ee.game_end_time,
+            ee.mmolb_game_id,
+            ee.game_event_index,
+            ee.pitch_speed
+        from data.events_extended ee
+        inner join data.team_versions tv on tv.mmolb_team_id=ee.defending_team_mmolb_id
+            and tv.valid_from <= ee.game_end_time and ee.game_end_time < coalesce(tv.valid_until, 'infinity')
+        where ee.pitch_speed is not null
+            and ee.season=$1
+            and ee.day is not distinct from $2
+            and ee.superstar_day is not distinct from $3
+        order by ee.pitch_speed desc, ee.mmolb_game_id asc, ee.game_event_index asc
+        limit 1
+    ",
+    )
+    .bind::<Int4, _>(day.season)
+    .bind::<Nullable<Int4>, _>(day.day)
+    .bind::<Nullable<Int4>, _>(day.superstar_day)
+    .get_result::<DayFastestPitch>(conn)
+    .optional()?
+    else {
+        return Ok(None);
+    };
+
+    let resolution = resolve_player_names(
+        conn,
+        &[(
+            &pitch.pitcher_name,
+            &pitch.mmolb_team_id,
+            pitch.game_end_time.and_utc(),
+        )],
+    )?;
+    let mmolb_player_id = match resolution.into_iter().next() {
+        Some(PlayerNameResolution::Resolved(id)) => Some(id),
+        // Ids are sorted, so this is the lowest
+        Some(PlayerNameResolution::Ambiguous(ids)) => ids.into_iter().next(),
+        Some(PlayerNameResolution::Unknown) | None => None,
+    };
+    let Some(mmolb_player_id) = mmolb_player_id else {
+        return Ok(None);
+    };
+
+    Ok(Some(PitchSpeedRecord {
+        mmolb_team_id: pitch.mmolb_team_id,
+        team_emoji: pitch.team_emoji,
+        team_location: pitch.team_location,
+        team_name: pitch.team_name,
+        mmolb_player_id,
+        player_name: pitch.pitcher_name,
+        mmolb_game_id: pitch.mmolb_game_id,
+        game_event_index: pitch.game_event_index,
+        pitch_speed: pitch.pitch_speed,
+    }))
+}
@@ -0,0 +1,127 @@
+use diesel::{PgConnection, prelude::*, sql_query, sql_types::*};
+use serde::Serialize;
+
+/// A snapshot of how the database server itself is doing, as opposed to
+/// the data in it
+#[derive(Debug, Clone, QueryableByName, Serialize)]
+pub struct DbHealth {
+    /// Connections to this database, in any state
+    #[diesel(sql_type = BigInt)]
+    pub total_connections: i64,
+    #[diesel(sql_type = BigInt)]
+    pub active_connections: i64,
+    /// Connections holding a transaction open without running a query
+    #[diesel(sql_type = BigInt)]
+    pub idle_in_transaction_connections: i64,
+    /// The server-wide connection limit
+    #[diesel(sql_type = Integer)]
+    pub max_connections: i32,
+    /// Age of the oldest open transaction, not counting this one. Long-lived
+    /// transactions hold back vacuum and are a common cause of slow ingests.
+    #[diesel(sql_type = Nullable<Double>)]
+    pub oldest_transaction_age_seconds: Option<f64>,
+    /// Whether this connection is to a read replica
+    #[diesel(sql_type = Bool)]
+    pub is_replica: bool,
+    /// On a replica, how far behind the primary it is. On a primary, how far
+    /// behind its slowest replica is. Null if there's no replication.
+    #[diesel(sql_type = Nullable<Double>)]
+    pub replication_lag_seconds: Option<f64>,
+    /// Tables with enough dead tuples that autovacuum should pick them up
+    #[diesel(sql_type = BigInt)]
+    pub tables_needing_vacuum: i64,
+    #[diesel(sql_type = BigInt)]
+    pub dead_tuples: i64,
+    #[diesel(sql_type = BigInt)]
+    pub autovacuum_workers_running: i64,
+}
+
+pub fn db_health(conn: &mut PgConnection) -> QueryResult<DbHealth> {
+    sql_query(
+        "
+        with connections as (
+            select
+                count(1) as total_connections,
+                count(1) filter (where state='active') as active_connections,
+                count(1) filter (where state like 'idle in transaction%')
+                    as idle_in_transaction_connections,
+                extract(epoch from max(now() - xact_start)
+                    filter (where pid <> pg_backend_pid()))::float8
+                    as oldest_transaction_age_seconds
+            from pg_stat_activity
+            where datname=current_database()
+        ), vacuum_backlog as (
+            select
+                count(1) filter (
+                    where n_dead_tup > current_setting('autovacuum_vacuum_threshold')::int8
+                        + current_setting('autovacuum_vacuum_scale_factor')::float8 * n_live_tup
+                ) as tables_needing_vacuum,
+                coalesce(sum(n_dead_tup), 0)::int8 as dead_tuples
+            from pg_stat_user_tables
+        )
+        select
+            c.total_connections,
+            c.active_connections,
+            c.idle_in_transaction_connections,
+            current_setting('max_connections')::int4 as max_connections,
+            c.oldest_transaction_age_seconds,
+            pg_is_in_recovery() as is_replica,
+            case
+                when pg_is_in_recovery()
+                    then extract(epoch from now() - pg_last_xact_replay_timestamp())::float8
+                else (select extract(epoch from max(replay_lag))::float8 from pg_stat_replication)
+            end as replication_lag_seconds,
+            vb.tables_needing_vacuum,
+            vb.dead_tuples,
+            (select count(1) from pg_stat_activity where backend_type='autovacuum worker')
+                as autovacuum_workers_running
+        from connections c
+        cross join vacuum_backlog vb
+    ",
+    )
+    .get_result(conn)
+}
+
+#[derive(QueryableByName)]
+struct BackendPid {
+    #[diesel(sql_type = Integer)]
+    pid: i32,
+}
+
+/// The process id of the server backend `conn` is connected to
+pub fn backend_pid(conn: &mut PgConnection) -> QueryResult<i32> {
+    sql_query("select pg_backend_pid() as pid")
+        .get_result::<BackendPid>(conn)
+        .map(|row| row.pid)
+}
+
+/// Terminates each of the `pids` sessions that has been waiting on its
+/// client for at least `stalled_for_seconds`, and returns the ones it
+/// terminated. A session is waiting on its client when it's idle (in a
+/// transaction or not) or blocked sending it results. Sessions that are
+/// running a query are left alone, however long it takes.
+pub fn terminate_stalled_backends(
+    conn: &mut PgConnection,
+    pids: &[i32],
+    stalled_for_seconds: f64,
+) -> QueryResult<Vec<i32>> {
+    let terminated = sql_query(
+        "
+        -- Materialized so no session is terminated before it's been
+        -- checked against every condition
+        with stalled as materialized (
+            select pid
+            from pg_stat_activity
+            where pid = any($1)
+                and state_change < now() - make_interval(secs => $2)
+                and (state like 'idle%' or (state = 'active' and wait_event_type = 'Client'))
+        )
+        select pid from stalled where pg_terminate_backend(pid)
+    ",
+    )
+    .bind::<Array<Integer>, _>(pids)
+    .bind::<Double, _>(stalled_for_seconds)
+    .get_results::<BackendPid>(conn)?;
+
+    Ok(terminated.into_iter().map(|row| row.pid).collect())
+}
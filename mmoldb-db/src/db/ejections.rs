@@ -0,0 +1,216 @@
+use chrono::{DateTime, Utc};
+use diesel::{PgConnection, prelude::*, sql_query, sql_types::*};
+
+/// Which ejections to look at. Every field that's set must match.
+#[derive(Debug, Clone, Default)]
+pub struct EjectionFilter {
+    pub season: Option<i32>,
+    /// Ejections of this team's players, or failed ejections in this
+    /// team's games
+    pub team_mmolb_id: Option<String>,
+    /// The ejected player or their replacement, or either player named in a
+    /// failed ejection
+    pub player_name: Option<String>,
+    /// Regular season day. Games on superstar days never match.
+    pub day: Option<i32>,
+    pub superstar_day: Option<i32>,
+}
+
+#[derive(Debug, QueryableByName)]
+pub struct DbEjectionListing {
+    #[diesel(sql_type = Text)]
+    pub mmolb_game_id: String,
+    #[diesel(sql_type = Int4)]
+    pub season: i32,
+    #[diesel(sql_type = Nullable<Int4>)]
+    pub day: Option<i32>,
+    #[diesel(sql_type = Nullable<Int4>)]
+    pub superstar_day: Option<i32>,
+    #[diesel(sql_type = Timestamptz)]
+    pub started_at: DateTime<Utc>,
+    #[diesel(sql_type = Int4)]
+    pub game_event_index: i32,
+    /// Null if the team's name didn't match either team in the game
+    #[diesel(sql_type = Nullable<Text>)]
+    pub team_mmolb_id: Option<String>,
+    #[diesel(sql_type = Text)]
+    pub team_emoji: String,
+    #[diesel(sql_type = Text)]
+    pub team_name: String,
+    #[diesel(sql_type = Text)]
+    pub ejected_player_name: String,
+    #[diesel(sql_type = Text)]
+    pub ejected_player_slot: String,
+    #[diesel(sql_type = Text)]
+    pub violation_type: String,
+    #[diesel(sql_type = Text)]
+    pub reason: String,
+    #[diesel(sql_type = Text)]
+    pub replacement_player_name: String,
+    #[diesel(sql_type = Nullable<Text>)]
+    pub replacement_player_slot: Option<String>,
+}
+
+#[derive(Debug, QueryableByName)]
+pub struct DbFailedEjectionListing {
+    #[diesel(sql_type = Text)]
+    pub mmolb_game_id: String,
+    #[diesel(sql_type = Int4)]
+    pub season: i32,
+    #[diesel(sql_type = Nullable<Int4>)]
+    pub day: Option<i32>,
+    #[diesel(sql_type = Nullable<Int4>)]
+    pub superstar_day: Option<i32>,
+    #[diesel(sql_type = Timestamptz)]
+    pub started_at: DateTime<Utc>,
+    #[diesel(sql_type = Int4)]
+    pub game_event_index: i32,
+    #[diesel(sql_type = Text)]
+    pub player_name_1: String,
+    #[diesel(sql_type = Text)]
+    pub player_name_2: String,
+}
+
+#[derive(Debug, QueryableByName)]
+pub struct DbEjectionCounts {
+    #[diesel(sql_type = Int8)]
+    pub ejections: i64,
+    #[diesel(sql_type = Int8)]
+    pub failed_ejections: i64,
+}
+
+/// Ejections matching `filter`, most recent first
+pub fn list_ejections(
+    conn: &mut PgConnection,
+    filter: &EjectionFilter,
+    limit: i64,
+) -> QueryResult<Vec<DbEjectionListing>> {
+    sql_query(
+        "
+        with ejections as (
+            select
+                g.mmolb_game_id,
+                g.season,
+                g.day,
+                g.superstar_day,
+                g.started_at,
+                ev.game_event_index,
+                case
+                    when ej.team_name=g.home_team_name then g.home_team_mmolb_id
+                    when ej.team_name=g.away_team_name then g.away_team_mmolb_id
+                end as team_mmolb_id,
+                ej.team_emoji,
+                ej.team_name,
+                ej.ejected_player_name,
+                ejected_slot.name as ejected_player_slot,
+                ej.violation_type,
+                ej.reason,
+                ej.replacement_player_name,
+                replacement_slot.name as replacement_player_slot
+            from data.ejections ej
+            join data.events ev on ev.id=ej.event_id
+            join data.games g on g.id=ev.game_id
+            join taxa.slot ejected_slot on ejected_slot.id=ej.ejected_player_slot
+            left join taxa.slot replacement_slot on replacement_slot.id=ej.replacement_player_slot
+            where ($1 is null or g.season=$1)
+                and ($4 is null or g.day=$4)
+                and ($5 is null or g.superstar_day=$5)
+        )
+        select *
+        from ejections
+        where ($2 is null or team_mmolb_id=$2)
+            and ($3 is null or ejected_player_name=$3 or replacement_player_name=$3)
+        order by started_at desc, game_event_index
+        limit $6
+    ",
+    )
+    .bind::<Nullable<Int4>, _>(filter.season)
+    .bind::<Nullable<Text>, _>(filter.team_mmolb_id.as_deref())
+    .bind::<Nullable<Text>, _>(filter.player_name.as_deref())
+    .bind::<Nullable<Int4>, _>(filter.day)
+    .bind::<Nullable<Int4>, _>(filter.superstar_day)
+    .bind::<Int8, _>(limit)
+    .get_results(conn)
+}
+
+/// Failed ejections matching `filter`, most recent first
+pub fn list_failed_ejections(
+    conn: &mut PgConnection,
+    filter: &EjectionFilter,
+    limit: i64,
+) -> QueryResult<Vec<DbFailedEjectionListing>> {
+    sql_query(
+        "
+        select
+            g.mmolb_game_id,
+            g.season,
+            g.day,
+            g.superstar_day,
+            g.started_at,
+            ev.game_event_index,
+            fe.player_name_1,
+            fe.player_name_2
+        from data.failed_ejections fe
+        join data.events ev on ev.id=fe.event_id
+        join data.games g on g.id=ev.game_id
+        where ($1 is null or g.season=$1)
+            and ($2 is null or g.home_team_mmolb_id=$2 or g.away_team_mmolb_id=$2)
+            and ($3 is null or fe.player_name_1=$3 or fe.player_name_2=$3)
+            and ($4 is null or g.day=$4)
+            and ($5 is null or g.superstar_day=$5)
+        order by g.started_at desc, ev.game_event_index
+        limit $6
+    ",
+    )
+    .bind::<Nullable<Int4>, _>(filter.season)
+    .bind::<Nullable<Text>, _>(filter.team_mmolb_id.as_deref())
+    .bind::<Nullable<Text>, _>(filter.player_name.as_deref())
+    .bind::<Nullable<Int4>, _>(filter.day)
+    .bind::<Nullable<Int4>, _>(filter.superstar_day)
+    .bind::<Int8, _>(limit)
+    .get_results(conn)
+}
+
+/// How many ejections and failed ejections match `filter`, ignoring any
+/// limit
+pub fn ejection_counts(
+    conn: &mut PgConnection,
+    filter: &EjectionFilter,
+) -> QueryResult<DbEjectionCounts> {
+    sql_query(
+        "
+        select
+            (
+                select count(*)
+                from data.ejections ej
+                join data.events ev on ev.id=ej.event_id
+                join data.games g on g.id=ev.game_id
+                where ($1 is null or g.season=$1)
+                    and ($2 is null or $2 = case
+                        when ej.team_name=g.home_team_name then g.home_team_mmolb_id
+                        when ej.team_name=g.away_team_name then g.away_team_mmolb_id
+                    end)
+                    and ($3 is null or ej.ejected_player_name=$3 or ej.replacement_player_name=$3)
+                    and ($4 is null or g.day=$4)
+                    and ($5 is null or g.superstar_day=$5)
+            ) as ejections,
+            (
+                select count(*)
+                from data.failed_ejections fe
+                join data.events ev on ev.id=fe.event_id
+                join data.games g on g.id=ev.game_id
+                where ($1 is null or g.season=$1)
+                    and ($2 is null or g.home_team_mmolb_id=$2 or g.away_team_mmolb_id=$2)
+                    and ($3 is null or fe.player_name_1=$3 or fe.player_name_2=$3)
+                    and ($4 is null or g.day=$4)
+                    and ($5 is null or g.superstar_day=$5)
+            ) as failed_ejections
+    ",
+    )
+    .bind::<Nullable<Int4>, _>(filter.season)
+    .bind::<Nullable<Text>, _>(filter.team_mmolb_id.as_deref())
+    .bind::<Nullable<Text>, _>(filter.player_name.as_deref())
+    .bind::<Nullable<Int4>, _>(filter.day)
+    .bind::<Nullable<Int4>, _>(filter.superstar_day)
+    .get_result(conn)
+}
@@ -3,7 +3,7 @@
 // database-layer functions that treat them differently.
 
 use chron::ChronEntity;
-use chrono::NaiveDateTime;
+use chrono::{DateTime, Utc};
 use diesel::{PgConnection, prelude::*};
 use itertools::Itertools;
 use tracing::warn;
@@ -13,9 +13,11 @@ use crate::data_schema::data::entities::dsl as entities_dsl;
 pub fn get_latest_entity_valid_from(
     conn: &mut PgConnection,
     kind: &str,
-) -> QueryResult<Option<NaiveDateTime>> {
+    source: Option<&str>,
+) -> QueryResult<Option<DateTime<Utc>>> {
     entities_dsl::entities
         .filter(entities_dsl::kind.eq(kind))
+        .filter(entities_dsl::source.is_not_distinct_from(source))
         .select(entities_dsl::valid_from)
         .order_by(entities_dsl::valid_from.desc())
         .limit(1)
@@ -29,13 +31,18 @@ pub fn get_latest_entity_valid_from(
 struct NewEntity<'a> {
     pub kind: &'a str,
     pub entity_id: &'a str,
-    pub valid_from: NaiveDateTime,
+    pub valid_from: DateTime<Utc>,
     pub data: &'a serde_json::Value,
+    // None for main MMOLB
+    pub source: Option<&'a str>,
 }
 
+/// Inserts `entities` fetched from `source`, or from main MMOLB if it's
+/// `None`
 pub fn insert_entities(
     conn: &mut PgConnection,
     entities: Vec<ChronEntity<serde_json::Value>>,
+    source: Option<&str>,
 ) -> QueryResult<usize> {
     let new_entities = entities
         .iter()
@@ -50,8 +57,9 @@ pub fn insert_entities(
             NewEntity {
                 kind: &v.kind,
                 entity_id: &v.entity_id,
-                valid_from: v.valid_from.naive_utc(),
+                valid_from: v.valid_from,
                 data: &v.data,
+                source,
             }
         })
         .collect_vec();
@@ -67,20 +75,22 @@ pub fn insert_entities(
 struct Entity {
     pub kind: String,
     pub entity_id: String,
-    pub valid_from: NaiveDateTime,
+    pub valid_from: DateTime<Utc>,
     pub data: serde_json::Value,
 }
 
 #[diesel::dsl::auto_type]
 fn entity_cursor_query_diesel<'a, 'b>(
     kind: &'a str,
-    cursor_date: NaiveDateTime,
+    cursor_date: DateTime<Utc>,
     cursor_id: &'b str,
 ) -> _ {
     entities_dsl::entities
         .filter(
             entities_dsl::kind
                 .eq(kind)
+                // Only main MMOLB entities are processed
+                .and(entities_dsl::source.is_null())
                 // Select entities that are after the cursor time, or from the
                 // same time and with higher ids
                 .and(
@@ -101,11 +111,11 @@ fn entity_cursor_query_diesel<'a, 'b>(
 
 fn entity_cursor_query<'a, 'b>(
     kind: &'a str,
-    cursor: Option<(NaiveDateTime, &'b str)>,
+    cursor: Option<(DateTime<Utc>, &'b str)>,
 ) -> entity_cursor_query_diesel<'a, 'b> {
     // The default values need to be some value that compares less than all
     // valid values in the database
-    let cursor_date = cursor.map_or(NaiveDateTime::default(), |(dt, _)| dt);
+    let cursor_date = cursor.map_or(DateTime::<Utc>::default(), |(dt, _)| dt);
     let cursor_id = cursor.map_or("", |(_, id)| id);
 
     entity_cursor_query_diesel(kind, cursor_date, cursor_id)
@@ -114,9 +124,9 @@ fn entity_cursor_query<'a, 'b>(
 pub fn advance_entity_cursor(
     conn: &mut PgConnection,
     kind: &str,
-    cursor: Option<(NaiveDateTime, &str)>,
+    cursor: Option<(DateTime<Utc>, &str)>,
     advance_by: usize,
-) -> QueryResult<Option<(NaiveDateTime, String)>> {
+) -> QueryResult<Option<(DateTime<Utc>, String)>> {
     // There may be a way to use offset() here, but it needs to return the last
     // value even if there are fewer than advance_by values. I don't think
     // offset() does that. And the amount of data transferred is hopefully
@@ -124,7 +134,7 @@ pub fn advance_entity_cursor(
     entity_cursor_query(kind, cursor)
         .select((entities_dsl::valid_from, entities_dsl::entity_id))
         .limit(advance_by as i64)
-        .get_results::<(NaiveDateTime, String)>(conn)
+        .get_results::<(DateTime<Utc>, String)>(conn)
         .map(|vec| vec.into_iter().last())
 }
 
@@ -132,7 +142,7 @@ pub fn get_entities_at_cursor(
     conn: &mut PgConnection,
     kind: &str,
     batch_size: usize,
-    cursor: Option<(NaiveDateTime, &str)>,
+    cursor: Option<(DateTime<Utc>, &str)>,
 ) -> QueryResult<Vec<ChronEntity<serde_json::Value>>> {
     entity_cursor_query(kind, cursor)
         .select(Entity::as_select())
@@ -144,10 +154,192 @@ pub fn get_entities_at_cursor(
                 .map(|e| ChronEntity {
                     kind: e.kind,
                     entity_id: e.entity_id,
-                    valid_from: e.valid_from.and_utc(),
+                    valid_from: e.valid_from,
                     valid_to: None,
                     data: e.data,
                 })
                 .collect_vec()
         })
 }
+
+/// Stored raw games, ordered by id, for rebuilding data derived from them.
+/// Ongoing games are skipped because they have no derived data. If
+/// `mmolb_game_ids` is given, only those games are returned.
+pub fn get_game_entities_for_rebuild(
+    conn: &mut PgConnection,
+    season: Option<i32>,
+    mmolb_game_ids: Option<&[String]>,
+    after_game_id: Option<&str>,
+    batch_size: usize,
+) -> QueryResult<Vec<ChronEntity<serde_json::Value>>> {
+    use crate::data_schema::data::games::dsl as games_dsl;
+
+    let mut finished_games = games_dsl::games
+        .filter(games_dsl::is_ongoing.eq(false))
+        .select(games_dsl::mmolb_game_id)
+        .into_boxed();
+    if let Some(season) = season {
+        finished_games = finished_games.filter(games_dsl::season.eq(season));
+    }
+    if let Some(mmolb_game_ids) = mmolb_game_ids {
+        finished_games = finished_games.filter(games_dsl::mmolb_game_id.eq_any(mmolb_game_ids));
+    }
+
+    let mut query = entities_dsl::entities
+        .filter(entities_dsl::kind.eq("game"))
+        .filter(entities_dsl::source.is_null())
+        .filter(entities_dsl::entity_id.eq_any(finished_games))
+        .into_boxed();
+    if let Some(after_game_id) = after_game_id {
+        query = query.filter(entities_dsl::entity_id.gt(after_game_id));
+    }
+
+    query
+        .order_by(entities_dsl::entity_id.asc())
+        .select(Entity::as_select())
+        .limit(batch_size as i64)
+        .get_results(conn)
+        .map(|entities| {
+            entities
+                .into_iter()
+                .map(|e| ChronEntity {
+                    kind: e.kind,
+                    entity_id: e.entity_id,
+                    valid_from: e.valid_from,
+                    valid_to: None,
+                    data: e.data,
+                })
+                .collect_vec()
+        })
+}
+
+/// A raw entity as it's stored, for looking at exactly what Chron sent
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = crate::data_schema::data::entities)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct StoredEntity {
+    pub valid_from: DateTime<Utc>,
+    pub data: serde_json::Value,
+    /// If this is set, `data` has been moved to object storage under this
+    /// key and is null here
+    pub offloaded_to: Option<String>,
+}
+
+/// The stored version of one main MMOLB entity, or None if there isn't
+/// one. Only the latest version of each entity is kept.
+pub fn get_stored_entity(
+    conn: &mut PgConnection,
+    kind: &str,
+    entity_id: &str,
+) -> QueryResult<Option<StoredEntity>> {
+    entities_dsl::entities
+        .filter(entities_dsl::kind.eq(kind))
+        .filter(entities_dsl::entity_id.eq(entity_id))
+        .filter(entities_dsl::source.is_null())
+        .select(StoredEntity::as_select())
+        .get_result(conn)
+        .optional()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{QueryError, postgres_url_from_environment};
+    use chrono::TimeZone;
+
+    #[test]
+    fn a_new_entity_only_replaces_the_same_sources_entity() {
+        let url = postgres_url_from_environment();
+        let mut conn = PgConnection::establish(&url)
+            .expect("postgres_url_from_environment should return a valid connection string");
+
+        conn.test_transaction(|conn| {
+            let kind = "source_test";
+            let entity = |hour| ChronEntity {
+                kind: kind.to_string(),
+                entity_id: "source-test-entity".to_string(),
+                valid_from: Utc.with_ymd_and_hms(2025, 7, 1, hour, 0, 0).unwrap(),
+                valid_to: None,
+                data: serde_json::json!({ "hour": hour }),
+            };
+
+            insert_entities(conn, vec![entity(1)], None)?;
+            insert_entities(conn, vec![entity(2)], Some("test-universe"))?;
+            insert_entities(conn, vec![entity(3)], Some("test-universe"))?;
+
+            let main = get_stored_entity(conn, kind, "source-test-entity")?;
+            assert_eq!(main.map(|e| e.data), Some(serde_json::json!({ "hour": 1 })));
+            assert_eq!(
+                get_latest_entity_valid_from(conn, kind, Some("test-universe"))?,
+                Some(entity(3).valid_from),
+            );
+            let num_entities = entities_dsl::entities
+                .filter(entities_dsl::kind.eq(kind))
+                .count()
+                .get_result::<i64>(conn)?;
+            assert_eq!(num_entities, 2);
+
+            Ok::<_, QueryError>(())
+        });
+    }
+    #[test]
+    fn rebuilds_select_finished_games_by_season_and_id() {
+        let url = postgres_url_from_environment();
+        let mut conn = PgConnection::establish(&url)
+            .expect("postgres_url_from_environment should return a valid connection string");
+
+        conn.test_transaction(|conn| {
+            // Game 1 is finished in season -1, game 2 is ongoing in season -1,
+            // and game 3 is finished in season -2
+            diesel::sql_query(
+                "
+                with w as (
+                    insert into data.weather (name, emoji, tooltip)
+                    values ('Test Weather', '', '')
+                    returning id
+                )
+                insert into data.games
+                    (mmolb_game_id, weather, season, superstar_day, away_team_emoji,
+                     away_team_name, away_team_mmolb_id, home_team_emoji, home_team_name,
+                     home_team_mmolb_id, is_ongoing, from_version, started_at)
+                select 'dddddddddddddddddd' || lpad(n::text, 6, '0'), w.id,
+                    case when n = 3 then -2 else -1 end, null, '', 'Away',
+                    'rebuild-test-away', '', 'Home', 'rebuild-test-home', n = 2,
+                    '2025-02-15', '2025-02-15T00:00:00Z'
+                from w, generate_series(1, 3) n
+                ",
+            )
+            .execute(conn)?;
+            let game_id = |n: i32| format!("dddddddddddddddddd{n:06}");
+            let entities = (1..=3)
+                .map(|n| ChronEntity {
+                    kind: "game".to_string(),
+                    entity_id: game_id(n),
+                    valid_from: Utc.with_ymd_and_hms(2025, 2, 15, 0, 0, 0).unwrap(),
+                    valid_to: None,
+                    data: serde_json::json!({ "n": n }),
+                })
+                .collect_vec();
+            insert_entities(conn, entities, None)?;
+
+            let selected_ids = |entities: Vec<ChronEntity<serde_json::Value>>| {
+                entities.into_iter().map(|e| e.entity_id).collect_vec()
+            };
+
+            let season = get_game_entities_for_rebuild(conn, Some(-1), None, None, 10)?;
+            assert_eq!(selected_ids(season), [game_id(1)]);
+
+            let ids = [game_id(1), game_id(2), game_id(3)];
+            let selected = get_game_entities_for_rebuild(conn, None, Some(&ids), None, 10)?;
+            assert_eq!(selected_ids(selected), [game_id(1), game_id(3)]);
+
+            let first_page = get_game_entities_for_rebuild(conn, None, Some(&ids), None, 1)?;
+            assert_eq!(selected_ids(first_page), [game_id(1)]);
+            let second_page =
+                get_game_entities_for_rebuild(conn, None, Some(&ids), Some(&game_id(1)), 1)?;
+            assert_eq!(selected_ids(second_page), [game_id(3)]);
+
+            Ok::<_, QueryError>(())
+        });
+    }
+}
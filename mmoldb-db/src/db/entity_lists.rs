@@ -0,0 +1,92 @@
+use diesel::{PgConnection, prelude::*, sql_query, sql_types::*};
+
+use crate::db::{Keyset, KeysetPage, page_by_keyset};
+
+/// The latest version of a player, as it appears in the players list
+#[derive(Debug, QueryableByName)]
+pub struct DbPlayerListEntry {
+    #[diesel(sql_type = Text)]
+    pub mmolb_player_id: String,
+    #[diesel(sql_type = Text)]
+    pub name: String,
+    #[diesel(sql_type = Nullable<Text>)]
+    pub mmolb_team_id: Option<String>,
+}
+
+/// Lists the current version of every player in MMOLB id order, optionally
+/// only those on one team
+pub fn page_of_players(
+    conn: &mut PgConnection,
+    page_size: usize,
+    after_player_id: Option<&str>,
+    mmolb_team_id: Option<&str>,
+) -> QueryResult<KeysetPage<DbPlayerListEntry, String>> {
+    page_by_keyset(
+        conn,
+        Keyset(&["pv.mmolb_player_id"]),
+        page_size,
+        after_player_id.map(str::to_string),
+        || {
+            let query = sql_query(
+                "
+                select
+                    pv.mmolb_player_id,
+                    player_full_name(pv.first_name, pv.last_name, pv.name_suffix) as name,
+                    pv.mmolb_team_id
+                from data.player_versions pv
+                where pv.valid_until is null
+                    and ($1 is null or pv.mmolb_team_id=$1)
+            ",
+            )
+            .into_boxed()
+            .bind::<Nullable<Text>, _>(mmolb_team_id.map(str::to_string));
+            (query, 2)
+        },
+        |player| player.mmolb_player_id.clone(),
+    )
+}
+
+/// The latest version of a team, as it appears in the teams list
+#[derive(Debug, QueryableByName)]
+pub struct DbTeamListEntry {
+    #[diesel(sql_type = Text)]
+    pub mmolb_team_id: String,
+    #[diesel(sql_type = Text)]
+    pub name: String,
+    #[diesel(sql_type = Text)]
+    pub emoji: String,
+    #[diesel(sql_type = Text)]
+    pub location: String,
+    #[diesel(sql_type = Nullable<Text>)]
+    pub mmolb_league_id: Option<String>,
+}
+
+/// Lists the current version of every team in MMOLB id order, optionally
+/// only those in one league
+pub fn page_of_teams(
+    conn: &mut PgConnection,
+    page_size: usize,
+    after_team_id: Option<&str>,
+    mmolb_league_id: Option<&str>,
+) -> QueryResult<KeysetPage<DbTeamListEntry, String>> {
+    page_by_keyset(
+        conn,
+        Keyset(&["tv.mmolb_team_id"]),
+        page_size,
+        after_team_id.map(str::to_string),
+        || {
+            let query = sql_query(
+                "
+                select tv.mmolb_team_id, tv.name, tv.emoji, tv.location, tv.mmolb_league_id
+                from data.team_versions tv
+                where tv.valid_until is null
+                    and ($1 is null or tv.mmolb_league_id=$1)
+            ",
+            )
+            .into_boxed()
+            .bind::<Nullable<Text>, _>(mmolb_league_id.map(str::to_string));
+            (query, 2)
+        },
+        |team| team.mmolb_team_id.clone(),
+    )
+}
@@ -0,0 +1,568 @@
+use diesel::{PgConnection, prelude::*};
+use strum::IntoEnumIterator;
+use thiserror::Error;
+
+use crate::db::KeysetPage;
+use crate::models::DbEvent;
+use crate::taxa::{
+    Taxa, TaxaBase, TaxaEventType, TaxaFairBallType, TaxaFielderLocation, TaxaFieldingErrorType,
+    TaxaPitchType,
+};
+
+#[derive(Debug, Error, PartialEq)]
+pub enum EventFilterError {
+    #[error("Events can't be filtered on {0:?}")]
+    UnknownField(String),
+
+    #[error("{field} can only be compared with = or !=, not {op}")]
+    UnorderedField {
+        field: EventFilterField,
+        op: EventFilterOp,
+    },
+
+    #[error("{value:?} isn't a valid {field}")]
+    InvalidValue {
+        field: EventFilterField,
+        value: String,
+    },
+
+    #[error(
+        "Event filters must include season=<season> or game_id=<game id>. Filtering every event \
+        ever played would take too long."
+    )]
+    TooBroad,
+}
+
+/// The columns of `data.events` (and a few of its game's columns) that
+/// events can be filtered on
+#[derive(Debug, Copy, Clone, PartialEq, Eq, strum::EnumString, strum::Display, strum::EnumIter)]
+#[strum(serialize_all = "snake_case")]
+pub enum EventFilterField {
+    /// The game's MMOLB id
+    GameId,
+    Season,
+    Day,
+    GameEventIndex,
+    Inning,
+    TopOfInning,
+    EventType,
+    HitBase,
+    FairBallType,
+    FairBallDirection,
+    FieldingErrorType,
+    PitchType,
+    PitchSpeed,
+    PitchZone,
+    BallsBefore,
+    StrikesBefore,
+    OutsBefore,
+    OutsAfter,
+    HomeRunDistance,
+    DescribedAsSacrifice,
+    IsToasty,
+    PitcherName,
+    BatterName,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, strum::Display)]
+pub enum EventFilterOp {
+    #[strum(serialize = "=")]
+    Eq,
+    #[strum(serialize = "!=")]
+    Ne,
+    #[strum(serialize = "<")]
+    Lt,
+    #[strum(serialize = "<=")]
+    Lte,
+    #[strum(serialize = ">")]
+    Gt,
+    #[strum(serialize = ">=")]
+    Gte,
+}
+
+/// A filter value, already checked against its field's type
+#[derive(Debug, Clone, PartialEq)]
+pub enum EventFilterValue {
+    Int(i32),
+    Float(f64),
+    Bool(bool),
+    Text(String),
+    EventType(TaxaEventType),
+    Base(TaxaBase),
+    FairBallType(TaxaFairBallType),
+    FielderLocation(TaxaFielderLocation),
+    FieldingErrorType(TaxaFieldingErrorType),
+    PitchType(TaxaPitchType),
+}
+
+/// Whether `name` is the name of `variant` written in snake_case, ignoring
+/// case. This is how taxa are written in filters, e.g. `home_run`.
+fn is_taxa_name<T: Into<&'static str>>(variant: T, name: &str) -> bool {
+    let variant: &'static str = variant.into();
+    variant.eq_ignore_ascii_case(&name.replace('_', ""))
+}
+
+fn parse_taxa<T: IntoEnumIterator + Into<&'static str>>(name: &str) -> Option<T> {
+    T::iter().find(|variant| is_taxa_name(*variant, name))
+}
+
+/// Writes a taxa variant the way filters accept it, e.g. `HomeRun` as
+/// `home_run`
+pub fn taxa_filter_name<T: Into<&'static str>>(variant: T) -> String {
+    let variant: &'static str = variant.into();
+    let mut name = String::with_capacity(variant.len() + 4);
+    for (i, c) in variant.char_indices() {
+        if c.is_ascii_uppercase() && i > 0 {
+            name.push('_');
+        }
+        name.push(c.to_ascii_lowercase());
+    }
+    name
+}
+
+impl EventFilterField {
+    /// Whether `<`, `<=`, `>`, and `>=` make sense for this field
+    fn is_ordered(self) -> bool {
+        match self {
+            EventFilterField::Season
+            | EventFilterField::Day
+            | EventFilterField::GameEventIndex
+            | EventFilterField::Inning
+            | EventFilterField::PitchSpeed
+            | EventFilterField::PitchZone
+            | EventFilterField::BallsBefore
+            | EventFilterField::StrikesBefore
+            | EventFilterField::OutsBefore
+            | EventFilterField::OutsAfter
+            | EventFilterField::HomeRunDistance => true,
+            EventFilterField::GameId
+            | EventFilterField::TopOfInning
+            | EventFilterField::EventType
+            | EventFilterField::HitBase
+            | EventFilterField::FairBallType
+            | EventFilterField::FairBallDirection
+            | EventFilterField::FieldingErrorType
+            | EventFilterField::PitchType
+            | EventFilterField::DescribedAsSacrifice
+            | EventFilterField::IsToasty
+            | EventFilterField::PitcherName
+            | EventFilterField::BatterName => false,
+        }
+    }
+
+    fn parse_value(self, value: &str) -> Option<EventFilterValue> {
+        match self {
+            EventFilterField::Season
+            | EventFilterField::Day
+            | EventFilterField::GameEventIndex
+            | EventFilterField::Inning
+            | EventFilterField::PitchZone
+            | EventFilterField::BallsBefore
+            | EventFilterField::StrikesBefore
+            | EventFilterField::OutsBefore
+            | EventFilterField::OutsAfter
+            | EventFilterField::HomeRunDistance => value.parse().ok().map(EventFilterValue::Int),
+            EventFilterField::PitchSpeed => value
+                .parse()
+                .ok()
+                .filter(|speed: &f64| speed.is_finite())
+                .map(EventFilterValue::Float),
+            EventFilterField::TopOfInning
+            | EventFilterField::DescribedAsSacrifice
+            | EventFilterField::IsToasty => value.parse().ok().map(EventFilterValue::Bool),
+            EventFilterField::GameId
+            | EventFilterField::PitcherName
+            | EventFilterField::BatterName => Some(EventFilterValue::Text(value.to_string())),
+            EventFilterField::EventType => parse_taxa(value).map(EventFilterValue::EventType),
+            EventFilterField::HitBase => parse_taxa(value).map(EventFilterValue::Base),
+            EventFilterField::FairBallType => parse_taxa(value).map(EventFilterValue::FairBallType),
+            EventFilterField::FairBallDirection => {
+                parse_taxa(value).map(EventFilterValue::FielderLocation)
+            }
+            EventFilterField::FieldingErrorType => {
+                parse_taxa(value).map(EventFilterValue::FieldingErrorType)
+            }
+            EventFilterField::PitchType => parse_taxa(value).map(EventFilterValue::PitchType),
+        }
+    }
+}
+
+/// One condition on events, e.g. `inning>=9`. These can only be made by
+/// [`EventFilter::parse`], which guarantees the value has the right type
+/// for the field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventFilter {
+    field: EventFilterField,
+    op: EventFilterOp,
+    value: EventFilterValue,
+}
+
+impl EventFilter {
+    /// Parses one query parameter. Filters are written `field=value`,
+    /// `field!=value`, `field<value`, `field<=value`, `field>value`, or
+    /// `field>=value`. Query strings are split at the first `=`, so
+    /// `inning>=9` arrives as the name `inning>` and the value `9`, and
+    /// `inning>9` arrives as the name `inning>9` with an empty value. This
+    /// takes both halves and puts them back together.
+    pub fn parse(name: &str, value: &str) -> Result<Self, EventFilterError> {
+        let (field, op, value) = if let Some(field) = name.strip_suffix('!') {
+            (field, EventFilterOp::Ne, value)
+        } else if let Some(field) = name.strip_suffix('<') {
+            (field, EventFilterOp::Lte, value)
+        } else if let Some(field) = name.strip_suffix('>') {
+            (field, EventFilterOp::Gte, value)
+        } else if let Some((field, value)) = name.split_once('<').filter(|_| value.is_empty()) {
+            (field, EventFilterOp::Lt, value)
+        } else if let Some((field, value)) = name.split_once('>').filter(|_| value.is_empty()) {
+            (field, EventFilterOp::Gt, value)
+        } else {
+            (name, EventFilterOp::Eq, value)
+        };
+
+        let field: EventFilterField = field
+            .parse()
+            .map_err(|_| EventFilterError::UnknownField(field.to_string()))?;
+        if !field.is_ordered() && !matches!(op, EventFilterOp::Eq | EventFilterOp::Ne) {
+            return Err(EventFilterError::UnorderedField { field, op });
+        }
+        let value = field
+            .parse_value(value)
+            .ok_or_else(|| EventFilterError::InvalidValue {
+                field,
+                value: value.to_string(),
+            })?;
+
+        Ok(Self { field, op, value })
+    }
+
+    /// Whether this filter alone limits the query to a small enough set of
+    /// games to be cheap
+    fn is_narrow(&self) -> bool {
+        self.op == EventFilterOp::Eq
+            && matches!(
+                self.field,
+                EventFilterField::Season | EventFilterField::GameId
+            )
+    }
+}
+
+/// An event that matched a filter, with a little information about its game
+#[derive(Queryable, Selectable)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct DbFilteredEvent {
+    #[diesel(embed)]
+    pub event: DbEvent,
+    #[diesel(select_expression = crate::data_schema::data::games::mmolb_game_id)]
+    pub mmolb_game_id: String,
+    #[diesel(select_expression = crate::data_schema::data::games::season)]
+    pub season: i32,
+    #[diesel(select_expression = crate::data_schema::data::games::day)]
+    pub day: Option<i32>,
+    #[diesel(select_expression = crate::data_schema::data::games::superstar_day)]
+    pub superstar_day: Option<i32>,
+}
+
+macro_rules! compare {
+    ($query:ident, $column:expr, $op:expr, $value:expr) => {
+        match $op {
+            EventFilterOp::Eq => $query.filter($column.eq($value)),
+            // Plain != would leave out events where the column is null
+            EventFilterOp::Ne => $query.filter($column.is_distinct_from($value)),
+            EventFilterOp::Lt => $query.filter($column.lt($value)),
+            EventFilterOp::Lte => $query.filter($column.le($value)),
+            EventFilterOp::Gt => $query.filter($column.gt($value)),
+            EventFilterOp::Gte => $query.filter($column.ge($value)),
+        }
+    };
+}
+
+/// Up to `limit` events matching every one of `filters`, in id order,
+/// starting after the event with id `after_id`. At least one filter has to
+/// narrow the query to one season or one game. The previous page isn't
+/// looked up, so `previous_page` is always None.
+pub fn filter_events(
+    conn: &mut PgConnection,
+    taxa: &Taxa,
+    filters: &[EventFilter],
+    after_id: Option<i64>,
+    limit: i64,
+) -> Result<KeysetPage<DbFilteredEvent, i64>, FilterEventsError> {
+    use crate::data_schema::data::events::dsl as ev;
+    use crate::data_schema::data::games::dsl as g;
+
+    if !filters.iter().any(EventFilter::is_narrow) {
+        return Err(EventFilterError::TooBroad.into());
+    }
+
+    let mut query = ev::events
+        .inner_join(g::games)
+        .select(DbFilteredEvent::as_select())
+        .order_by(ev::id.asc())
+        // Get N + 1 rows so we know if this is the last page or not
+        .limit(limit + 1)
+        .into_boxed();
+
+    if let Some(after_id) = after_id {
+        query = query.filter(ev::id.gt(after_id));
+    }
+
+    for filter in filters {
+        use EventFilterField as F;
+        use EventFilterValue as V;
+
+        let op = filter.op;
+        query = match (filter.field, &filter.value) {
+            (F::GameId, V::Text(id)) => compare!(query, g::mmolb_game_id, op, id),
+            (F::Season, V::Int(season)) => compare!(query, g::season, op, season),
+            (F::Day, V::Int(day)) => compare!(query, g::day, op, day),
+            (F::GameEventIndex, V::Int(index)) => compare!(query, ev::game_event_index, op, index),
+            (F::Inning, V::Int(inning)) => compare!(query, ev::inning, op, inning),
+            (F::TopOfInning, V::Bool(top)) => compare!(query, ev::top_of_inning, op, top),
+            (F::EventType, V::EventType(ty)) => {
+                compare!(query, ev::event_type, op, taxa.event_type_id(*ty))
+            }
+            (F::HitBase, V::Base(base)) => compare!(query, ev::hit_base, op, taxa.base_id(*base)),
+            (F::FairBallType, V::FairBallType(ty)) => {
+                compare!(query, ev::fair_ball_type, op, taxa.fair_ball_type_id(*ty))
+            }
+            (F::FairBallDirection, V::FielderLocation(location)) => compare!(
+                query,
+                ev::fair_ball_direction,
+                op,
+                taxa.fielder_location_id(*location)
+            ),
+            (F::FieldingErrorType, V::FieldingErrorType(ty)) => compare!(
+                query,
+                ev::fielding_error_type,
+                op,
+                taxa.fielding_error_type_id(*ty)
+            ),
+            (F::PitchType, V::PitchType(ty)) => {
+                compare!(query, ev::pitch_type, op, taxa.pitch_type_id(*ty))
+            }
+            (F::PitchSpeed, V::Float(speed)) => compare!(query, ev::pitch_speed, op, speed),
+            (F::PitchZone, V::Int(zone)) => compare!(query, ev::pitch_zone, op, zone),
+            (F::BallsBefore, V::Int(balls)) => compare!(query, ev::balls_before, op, balls),
+            (F::StrikesBefore, V::Int(strikes)) => compare!(query, ev::strikes_before, op, strikes),
+            (F::OutsBefore, V::Int(outs)) => compare!(query, ev::outs_before, op, outs),
+            (F::OutsAfter, V::Int(outs)) => compare!(query, ev::outs_after, op, outs),
+            (F::HomeRunDistance, V::Int(distance)) => {
+                compare!(query, ev::home_run_distance, op, distance)
+            }
+            (F::DescribedAsSacrifice, V::Bool(sacrifice)) => {
+                compare!(query, ev::described_as_sacrifice, op, sacrifice)
+            }
+            (F::IsToasty, V::Bool(toasty)) => compare!(query, ev::is_toasty, op, toasty),
+            (F::PitcherName, V::Text(name)) => compare!(query, ev::pitcher_name, op, name),
+            (F::BatterName, V::Text(name)) => compare!(query, ev::batter_name, op, name),
+            (field, value) => unreachable!("EventFilter::parse gave {field} a {value:?}"),
+        };
+    }
+
+    let mut items = query.get_results::<DbFilteredEvent>(conn)?;
+    let next_page = if items.len() as i64 > limit {
+        // Then this is not the last page
+        items.truncate(limit as usize);
+        items.last().map(|row| row.event.id)
+    } else {
+        None
+    };
+
+    Ok(KeysetPage {
+        items,
+        next_page,
+        previous_page: None,
+    })
+}
+
+#[derive(Debug, Error)]
+pub enum FilterEventsError {
+    #[error(transparent)]
+    Db(#[from] diesel::result::Error),
+
+    #[error(transparent)]
+    Filter(#[from] EventFilterError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_every_operator() {
+        let parse = |name, value| EventFilter::parse(name, value).map(|f| (f.field, f.op, f.value));
+
+        assert_eq!(
+            parse("inning", "9"),
+            Ok((
+                EventFilterField::Inning,
+                EventFilterOp::Eq,
+                EventFilterValue::Int(9)
+            )),
+        );
+        assert_eq!(
+            parse("inning!", "9"),
+            Ok((
+                EventFilterField::Inning,
+                EventFilterOp::Ne,
+                EventFilterValue::Int(9)
+            )),
+        );
+        assert_eq!(
+            parse("inning<", "9"),
+            Ok((
+                EventFilterField::Inning,
+                EventFilterOp::Lte,
+                EventFilterValue::Int(9)
+            )),
+        );
+        assert_eq!(
+            parse("inning>", "9"),
+            Ok((
+                EventFilterField::Inning,
+                EventFilterOp::Gte,
+                EventFilterValue::Int(9)
+            )),
+        );
+        assert_eq!(
+            parse("inning<9", ""),
+            Ok((
+                EventFilterField::Inning,
+                EventFilterOp::Lt,
+                EventFilterValue::Int(9)
+            )),
+        );
+        assert_eq!(
+            parse("pitch_speed>100", ""),
+            Ok((
+                EventFilterField::PitchSpeed,
+                EventFilterOp::Gt,
+                EventFilterValue::Float(100.0),
+            )),
+        );
+    }
+
+    #[test]
+    fn parses_taxa_by_snake_case_name() {
+        assert_eq!(
+            EventFilter::parse("event_type", "home_run").map(|f| f.value),
+            Ok(EventFilterValue::EventType(TaxaEventType::HomeRun)),
+        );
+        assert_eq!(taxa_filter_name(TaxaEventType::HomeRun), "home_run");
+        assert_eq!(
+            EventFilter::parse("event_type", "dinger"),
+            Err(EventFilterError::InvalidValue {
+                field: EventFilterField::EventType,
+                value: "dinger".to_string(),
+            }),
+        );
+    }
+
+    #[test]
+    fn rejects_orderings_of_unordered_fields() {
+        assert_eq!(
+            EventFilter::parse("event_type>", "home_run"),
+            Err(EventFilterError::UnorderedField {
+                field: EventFilterField::EventType,
+                op: EventFilterOp::Gte,
+            }),
+        );
+        assert_eq!(
+            EventFilter::parse("runs", "3"),
+            Err(EventFilterError::UnknownField("runs".to_string())),
+        );
+    }
+
+    fn insert_test_game(conn: &mut PgConnection, taxa: &Taxa) -> QueryResult<()> {
+        use diesel::sql_query;
+        use diesel::sql_types::Int8;
+
+        // The first event has no pitch zone
+        sql_query(
+            "
+            with w as (
+                insert into data.weather (name, emoji, tooltip)
+                values ('Test Weather', '', '')
+                returning id
+            ), g as (
+                insert into data.games
+                    (mmolb_game_id, weather, season, superstar_day, away_team_emoji,
+                     away_team_name, away_team_mmolb_id, home_team_emoji, home_team_name,
+                     home_team_mmolb_id, is_ongoing, from_version, started_at)
+                select 'dddddddddddddddddd000001', w.id, -1, null, '', 'Away',
+                    'event-filter-test-away', '', 'Home', 'event-filter-test-home', false,
+                    '2025-02-15', '2025-02-15T00:00:00Z'
+                from w
+                returning id
+            )
+            insert into data.events
+                (game_id, game_event_index, inning, top_of_inning, event_type, pitch_zone,
+                 balls_before, strikes_before, outs_before, outs_after,
+                 away_team_score_before, away_team_score_after, home_team_score_before,
+                 home_team_score_after, pitcher_name, pitcher_count, batter_name,
+                 batter_count, batter_subcount, errors_before, errors_after)
+            select g.id, n, 1, true, $1, nullif(n + 4, 4), 0, 0, 0, 0, 0, 0, 0, 0,
+                'Test Pitcher', 0, 'Test Batter', n, 0, 0, 0
+            from g, generate_series(0, 2) n
+            ",
+        )
+        .bind::<Int8, _>(taxa.event_type_id(TaxaEventType::Ball))
+        .execute(conn)?;
+        Ok(())
+    }
+
+    #[test]
+    fn not_equal_keeps_events_where_the_field_is_null() {
+        let url = crate::postgres_url_from_environment();
+        let mut conn = PgConnection::establish(&url)
+            .expect("postgres_url_from_environment should return a valid connection string");
+
+        conn.test_transaction(|conn| {
+            let taxa = Taxa::new(conn)?;
+            insert_test_game(conn, &taxa)?;
+
+            let filters = [
+                EventFilter::parse("game_id", "dddddddddddddddddd000001").unwrap(),
+                EventFilter::parse("pitch_zone!", "5").unwrap(),
+            ];
+            let page = filter_events(conn, &taxa, &filters, None, 10)?;
+            let indices = page
+                .items
+                .iter()
+                .map(|row| row.event.game_event_index)
+                .collect::<Vec<_>>();
+            assert_eq!(indices, [0, 2]);
+
+            Ok::<_, FilterEventsError>(())
+        });
+    }
+
+    #[test]
+    fn only_pages_with_more_events_after_them_have_a_next_page() {
+        let url = crate::postgres_url_from_environment();
+        let mut conn = PgConnection::establish(&url)
+            .expect("postgres_url_from_environment should return a valid connection string");
+
+        conn.test_transaction(|conn| {
+            let taxa = Taxa::new(conn)?;
+            insert_test_game(conn, &taxa)?;
+            let filters = [EventFilter::parse("game_id", "dddddddddddddddddd000001").unwrap()];
+
+            let first_page = filter_events(conn, &taxa, &filters, None, 2)?;
+            assert_eq!(first_page.items.len(), 2);
+            let next_page = first_page.next_page.expect("there is a third event");
+            assert_eq!(next_page, first_page.items[1].event.id);
+
+            let last_page = filter_events(conn, &taxa, &filters, Some(next_page), 2)?;
+            assert_eq!(last_page.items.len(), 1);
+            assert_eq!(last_page.next_page, None);
+
+            // A page that ends exactly at the last event is the last page
+            let whole_game = filter_events(conn, &taxa, &filters, None, 3)?;
+            assert_eq!(whole_game.items.len(), 3);
+            assert_eq!(whole_game.next_page, None);
+
+            Ok::<_, FilterEventsError>(())
+        });
+    }
+}
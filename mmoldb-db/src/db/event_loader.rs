@@ -0,0 +1,601 @@
+use chrono::Utc;
+use diesel::{PgConnection, prelude::*};
+use diesel_async::AsyncPgConnection;
+use itertools::Itertools;
+use std::ops::DerefMut;
+
+use super::to_db_format::{self, RowToEventError};
+use crate::event_detail::EventDetail;
+use crate::models::{
+    DbAuroraPhoto, DbDoorPrize, DbDoorPrizeItem, DbEfflorescence, DbEfflorescenceGrowth,
+    DbEjection, DbEvent, DbFailedEjection, DbFielder, DbRunner, DbWither,
+};
+use crate::taxa::Taxa;
+
+#[derive(Default)]
+pub struct EventsForGameTimings {
+    pub get_game_ids_duration: f64,
+    pub get_events_duration: f64,
+    pub group_events_duration: f64,
+    /// All the child tables together
+    pub get_children_duration: f64,
+    pub group_children_duration: f64,
+    pub post_process_duration: f64,
+}
+
+pub fn group_child_table_results<'a, ChildT>(
+    games_events: impl IntoIterator<Item = &'a Vec<DbEvent>>,
+    child_results: Vec<ChildT>,
+    event_id_for_child: impl Fn(&ChildT) -> i64,
+) -> Vec<Vec<Vec<ChildT>>> {
+    let mut child_results_iter = child_results.into_iter().peekable();
+
+    let results = games_events
+        .into_iter()
+        .map(|game_events| {
+            game_events
+                .iter()
+                .map(|game_event| {
+                    let mut children = Vec::new();
+                    while let Some(child) =
+                        child_results_iter.next_if(|f| event_id_for_child(f) == game_event.id)
+                    {
+                        children.push(child);
+                    }
+                    children
+                })
+                .collect_vec()
+        })
+        .collect_vec();
+
+    assert_eq!(child_results_iter.count(), 0);
+
+    results
+}
+
+pub fn group_wither_table_results<'a>(
+    games_events: impl IntoIterator<Item = &'a Vec<DbEvent>>,
+    withers: Vec<DbWither>,
+) -> Vec<Vec<Vec<DbWither>>> {
+    let mut wither_iter = withers.into_iter().peekable();
+
+    let results = games_events
+        .into_iter()
+        .map(|game_events| {
+            game_events
+                .iter()
+                .map(|game_event| {
+                    let mut children = Vec::new();
+                    while let Some(child) = wither_iter.next_if(|f| {
+                        f.game_id == game_event.game_id
+                            && f.attempt_game_event_index == game_event.game_event_index
+                    }) {
+                        children.push(child);
+                    }
+                    children
+                })
+                .collect_vec()
+        })
+        .collect_vec();
+
+    assert_eq!(wither_iter.count(), 0);
+
+    results
+}
+
+pub fn group_cheer_table_results<'a>(
+    games_events: impl IntoIterator<Item = &'a Vec<DbEvent>>,
+    cheers: Vec<(i64, Option<String>)>,
+) -> Vec<Vec<Vec<Option<String>>>> {
+    let mut cheer_iter = cheers.into_iter().peekable();
+
+    let results = games_events
+        .into_iter()
+        .map(|game_events| {
+            game_events
+                .iter()
+                .map(|game_event| {
+                    let mut children = Vec::new();
+                    while let Some((_, message)) =
+                        cheer_iter.next_if(|(event_id, _)| *event_id == game_event.id)
+                    {
+                        children.push(message);
+                    }
+                    children
+                })
+                .collect_vec()
+        })
+        .collect_vec();
+
+    assert_eq!(cheer_iter.count(), 0);
+
+    results
+}
+
+pub fn group_balk_reason_table_results<'a>(
+    games_events: impl IntoIterator<Item = &'a Vec<DbEvent>>,
+    balk_reasons: Vec<(i64, Option<String>)>,
+) -> Vec<Vec<Vec<Option<String>>>> {
+    let mut balk_reason_iter = balk_reasons.into_iter().peekable();
+
+    let results = games_events
+        .into_iter()
+        .map(|game_events| {
+            game_events
+                .iter()
+                .map(|game_event| {
+                    let mut children = Vec::new();
+                    while let Some((_, message)) =
+                        balk_reason_iter.next_if(|(event_id, _)| *event_id == game_event.id)
+                    {
+                        children.push(message);
+                    }
+                    children
+                })
+                .collect_vec()
+        })
+        .collect_vec();
+
+    assert_eq!(balk_reason_iter.count(), 0);
+
+    results
+}
+
+/// The queries [`events_for_games`] and [`events_for_games_concurrently`]
+/// run, in the order their results are grouped in. It's a macro so the same
+/// query can be run with either diesel's or diesel_async's `RunQueryDsl`.
+macro_rules! events_for_games_query {
+    (game_ids, $mmolb_game_ids:expr) => {{
+        use crate::data_schema::data::games::dsl as games_dsl;
+        games_dsl::games
+            .filter(games_dsl::mmolb_game_id.eq_any($mmolb_game_ids))
+            .select(games_dsl::id)
+            .order_by(games_dsl::id.asc())
+    }};
+    (events, $game_ids:expr) => {{
+        use crate::data_schema::data::events::dsl as events_dsl;
+        events_dsl::events
+            .filter(events_dsl::game_id.eq_any($game_ids))
+            .order_by(events_dsl::game_id.asc())
+            .then_order_by(events_dsl::game_event_index.asc())
+            .select(DbEvent::as_select())
+    }};
+    (runners, $event_ids:expr) => {{
+        use crate::data_schema::data::event_baserunners::dsl as runner_dsl;
+        runner_dsl::event_baserunners
+            .filter(runner_dsl::event_id.eq_any($event_ids))
+            .order_by((
+                runner_dsl::event_id.asc(),
+                runner_dsl::base_before.desc().nulls_last(),
+            ))
+            .select(DbRunner::as_select())
+    }};
+    (fielders, $event_ids:expr) => {{
+        use crate::data_schema::data::event_fielders::dsl as fielder_dsl;
+        fielder_dsl::event_fielders
+            .filter(fielder_dsl::event_id.eq_any($event_ids))
+            .order_by((fielder_dsl::event_id, fielder_dsl::play_order))
+            .select(DbFielder::as_select())
+    }};
+    (aurora_photos, $event_ids:expr) => {{
+        use crate::data_schema::data::aurora_photos::dsl as aurora_photo_dsl;
+        aurora_photo_dsl::aurora_photos
+            .filter(aurora_photo_dsl::event_id.eq_any($event_ids))
+            .order_by((
+                aurora_photo_dsl::event_id,
+                aurora_photo_dsl::is_listed_first.desc(),
+            ))
+            .select(DbAuroraPhoto::as_select())
+    }};
+    (ejections, $event_ids:expr) => {{
+        use crate::data_schema::data::ejections::dsl as ejection_dsl;
+        ejection_dsl::ejections
+            .filter(ejection_dsl::event_id.eq_any($event_ids))
+            .order_by(ejection_dsl::event_id)
+            .select(DbEjection::as_select())
+    }};
+    (failed_ejections, $event_ids:expr) => {{
+        use crate::data_schema::data::failed_ejections::dsl as failed_ejection_dsl;
+        failed_ejection_dsl::failed_ejections
+            .filter(failed_ejection_dsl::event_id.eq_any($event_ids))
+            .order_by(failed_ejection_dsl::event_id)
+            .select(DbFailedEjection::as_select())
+    }};
+    (door_prizes, $event_ids:expr) => {{
+        use crate::data_schema::data::door_prizes::dsl as door_prize_dsl;
+        door_prize_dsl::door_prizes
+            .filter(door_prize_dsl::event_id.eq_any($event_ids))
+            .order_by((door_prize_dsl::event_id, door_prize_dsl::door_prize_index))
+            .select(DbDoorPrize::as_select())
+    }};
+    (door_prize_items, $event_ids:expr) => {{
+        use crate::data_schema::data::door_prize_items::dsl as door_prize_item_dsl;
+        door_prize_item_dsl::door_prize_items
+            .filter(door_prize_item_dsl::event_id.eq_any($event_ids))
+            .order_by((
+                door_prize_item_dsl::event_id,
+                door_prize_item_dsl::door_prize_index,
+                door_prize_item_dsl::item_index,
+            ))
+            .select(DbDoorPrizeItem::as_select())
+    }};
+    (efflorescences, $event_ids:expr) => {{
+        use crate::data_schema::data::efflorescence::dsl as efflorescence_dsl;
+        efflorescence_dsl::efflorescence
+            .filter(efflorescence_dsl::event_id.eq_any($event_ids))
+            .order_by((
+                efflorescence_dsl::event_id,
+                efflorescence_dsl::efflorescence_index,
+            ))
+            .select(DbEfflorescence::as_select())
+    }};
+    (efflorescence_growths, $event_ids:expr) => {{
+        use crate::data_schema::data::efflorescence_growth::dsl as efflorescence_growth_dsl;
+        efflorescence_growth_dsl::efflorescence_growth
+            .filter(efflorescence_growth_dsl::event_id.eq_any($event_ids))
+            .order_by((
+                efflorescence_growth_dsl::event_id,
+                efflorescence_growth_dsl::efflorescence_index,
+                efflorescence_growth_dsl::growth_index,
+            ))
+            .select(DbEfflorescenceGrowth::as_select())
+    }};
+    // Wither is attached to games, not events
+    (wither, $game_ids:expr) => {{
+        use crate::data_schema::data::wither::dsl as wither_dsl;
+        wither_dsl::wither
+            .filter(wither_dsl::game_id.eq_any($game_ids))
+            .order_by((wither_dsl::game_id, wither_dsl::attempt_game_event_index))
+            .select(DbWither::as_select())
+    }};
+    (cheers, $event_ids:expr) => {{
+        use crate::data_schema::data::cheers::dsl as cheers_dsl;
+        use crate::data_schema::data::event_cheers::dsl as event_cheers_dsl;
+        event_cheers_dsl::event_cheers
+            .left_join(cheers_dsl::cheers.on(event_cheers_dsl::cheer_id.eq(cheers_dsl::id)))
+            .filter(event_cheers_dsl::event_id.eq_any($event_ids))
+            .order_by(event_cheers_dsl::event_id)
+            .select((event_cheers_dsl::event_id, cheers_dsl::cheer.nullable()))
+    }};
+    (balk_reasons, $event_ids:expr) => {{
+        use crate::data_schema::data::balk_reasons::dsl as balk_reasons_dsl;
+        use crate::data_schema::data::event_balk_reasons::dsl as event_balk_reasons_dsl;
+        event_balk_reasons_dsl::event_balk_reasons
+            .left_join(
+                balk_reasons_dsl::balk_reasons
+                    .on(event_balk_reasons_dsl::balk_reason_id.eq(balk_reasons_dsl::id)),
+            )
+            .filter(event_balk_reasons_dsl::event_id.eq_any($event_ids))
+            .order_by(event_balk_reasons_dsl::event_id)
+            .select((
+                event_balk_reasons_dsl::event_id,
+                balk_reasons_dsl::balk_reason.nullable(),
+            ))
+    }};
+}
+
+/// Every row of every child table of `data.events` for one batch of games,
+/// each ordered by event
+struct EventChildRows {
+    runners: Vec<DbRunner>,
+    fielders: Vec<DbFielder>,
+    aurora_photos: Vec<DbAuroraPhoto>,
+    ejections: Vec<DbEjection>,
+    failed_ejections: Vec<DbFailedEjection>,
+    door_prizes: Vec<DbDoorPrize>,
+    door_prize_items: Vec<DbDoorPrizeItem>,
+    efflorescences: Vec<DbEfflorescence>,
+    efflorescence_growths: Vec<DbEfflorescenceGrowth>,
+    wither: Vec<DbWither>,
+    cheers: Vec<(i64, Option<String>)>,
+    balk_reasons: Vec<(i64, Option<String>)>,
+}
+
+type GameEvents = (i64, Vec<Result<EventDetail<String>, RowToEventError>>);
+
+/// Every event of each of these games, with the game's database id, in
+/// order of database id. Each of the child tables is its own query, run one
+/// after another. See [`events_for_games_concurrently`] for a version that
+/// runs them at the same time.
+pub fn events_for_games(
+    conn: &mut PgConnection,
+    taxa: &Taxa,
+    for_game_ids: &[&str],
+) -> QueryResult<(Vec<GameEvents>, EventsForGameTimings)> {
+    let mut timings = EventsForGameTimings::default();
+
+    let get_game_ids_start = Utc::now();
+    let game_ids: Vec<i64> = events_for_games_query!(game_ids, for_game_ids).load(conn)?;
+    timings.get_game_ids_duration = (Utc::now() - get_game_ids_start).as_seconds_f64();
+
+    let get_events_start = Utc::now();
+    let db_events: Vec<DbEvent> = events_for_games_query!(events, &game_ids).load(conn)?;
+    let event_ids = db_events.iter().map(|event| event.id).collect_vec();
+    timings.get_events_duration = (Utc::now() - get_events_start).as_seconds_f64();
+
+    let get_children_start = Utc::now();
+    let children = EventChildRows {
+        runners: events_for_games_query!(runners, &event_ids).load(conn)?,
+        fielders: events_for_games_query!(fielders, &event_ids).load(conn)?,
+        aurora_photos: events_for_games_query!(aurora_photos, &event_ids).load(conn)?,
+        ejections: events_for_games_query!(ejections, &event_ids).load(conn)?,
+        failed_ejections: events_for_games_query!(failed_ejections, &event_ids).load(conn)?,
+        door_prizes: events_for_games_query!(door_prizes, &event_ids).load(conn)?,
+        door_prize_items: events_for_games_query!(door_prize_items, &event_ids).load(conn)?,
+        efflorescences: events_for_games_query!(efflorescences, &event_ids).load(conn)?,
+        efflorescence_growths: events_for_games_query!(efflorescence_growths, &event_ids)
+            .load(conn)?,
+        wither: events_for_games_query!(wither, &game_ids).load(conn)?,
+        cheers: events_for_games_query!(cheers, &event_ids).load(conn)?,
+        balk_reasons: events_for_games_query!(balk_reasons, &event_ids).load(conn)?,
+    };
+    timings.get_children_duration = (Utc::now() - get_children_start).as_seconds_f64();
+
+    let result = assemble_events(taxa, game_ids, db_events, children, &mut timings);
+    Ok((result, timings))
+}
+
+/// Like [`events_for_games`], but the child table queries all run at once.
+/// They're spread over `conns`, and the ones that share a connection are
+/// pipelined, so the whole batch takes about as long as its slowest query
+/// instead of the sum of all of them. Every connection has to be able to
+/// see the games, so they can't have been inserted in a transaction that
+/// hasn't committed yet. The connections can be plain or checked out of a
+/// pool.
+pub async fn events_for_games_concurrently(
+    conns: &mut [impl DerefMut<Target = AsyncPgConnection>],
+    taxa: &Taxa,
+    for_game_ids: &[&str],
+) -> QueryResult<(Vec<GameEvents>, EventsForGameTimings)> {
+    use diesel_async::RunQueryDsl as AsyncRunQueryDsl;
+
+    assert!(
+        !conns.is_empty(),
+        "events_for_games_concurrently needs at least one connection",
+    );
+    let num_conns = conns.len();
+    let conn_index = |i: usize| i % num_conns;
+    let mut timings = EventsForGameTimings::default();
+
+    let get_game_ids_start = Utc::now();
+    let game_ids: Vec<i64> = AsyncRunQueryDsl::load(
+        events_for_games_query!(game_ids, for_game_ids),
+        &mut *conns[0],
+    )
+    .await?;
+    timings.get_game_ids_duration = (Utc::now() - get_game_ids_start).as_seconds_f64();
+
+    let get_events_start = Utc::now();
+    let db_events: Vec<DbEvent> =
+        AsyncRunQueryDsl::load(events_for_games_query!(events, &game_ids), &mut *conns[0]).await?;
+    let event_ids = db_events.iter().map(|event| event.id).collect_vec();
+    timings.get_events_duration = (Utc::now() - get_events_start).as_seconds_f64();
+
+    // The futures don't borrow their connection once they're created, which
+    // is what lets queries be pipelined on one connection
+    let get_children_start = Utc::now();
+    let (
+        runners,
+        fielders,
+        aurora_photos,
+        ejections,
+        failed_ejections,
+        door_prizes,
+        door_prize_items,
+        efflorescences,
+        efflorescence_growths,
+        wither,
+        cheers,
+        balk_reasons,
+    ) = futures::try_join!(
+        AsyncRunQueryDsl::load(
+            events_for_games_query!(runners, &event_ids),
+            &mut *conns[conn_index(0)],
+        ),
+        AsyncRunQueryDsl::load(
+            events_for_games_query!(fielders, &event_ids),
+            &mut *conns[conn_index(1)],
+        ),
+        AsyncRunQueryDsl::load(
+            events_for_games_query!(aurora_photos, &event_ids),
+            &mut *conns[conn_index(2)],
+        ),
+        AsyncRunQueryDsl::load(
+            events_for_games_query!(ejections, &event_ids),
+            &mut *conns[conn_index(3)],
+        ),
+        AsyncRunQueryDsl::load(
+            events_for_games_query!(failed_ejections, &event_ids),
+            &mut *conns[conn_index(4)],
+        ),
+        AsyncRunQueryDsl::load(
+            events_for_games_query!(door_prizes, &event_ids),
+            &mut *conns[conn_index(5)],
+        ),
+        AsyncRunQueryDsl::load(
+            events_for_games_query!(door_prize_items, &event_ids),
+            &mut *conns[conn_index(6)],
+        ),
+        AsyncRunQueryDsl::load(
+            events_for_games_query!(efflorescences, &event_ids),
+            &mut *conns[conn_index(7)],
+        ),
+        AsyncRunQueryDsl::load(
+            events_for_games_query!(efflorescence_growths, &event_ids),
+            &mut *conns[conn_index(8)],
+        ),
+        AsyncRunQueryDsl::load(
+            events_for_games_query!(wither, &game_ids),
+            &mut *conns[conn_index(9)],
+        ),
+        AsyncRunQueryDsl::load(
+            events_for_games_query!(cheers, &event_ids),
+            &mut *conns[conn_index(10)],
+        ),
+        AsyncRunQueryDsl::load(
+            events_for_games_query!(balk_reasons, &event_ids),
+            &mut *conns[conn_index(11)],
+        ),
+    )?;
+    timings.get_children_duration = (Utc::now() - get_children_start).as_seconds_f64();
+
+    let children = EventChildRows {
+        runners,
+        fielders,
+        aurora_photos,
+        ejections,
+        failed_ejections,
+        door_prizes,
+        door_prize_items,
+        efflorescences,
+        efflorescence_growths,
+        wither,
+        cheers,
+        balk_reasons,
+    };
+    let result = assemble_events(taxa, game_ids, db_events, children, &mut timings);
+    Ok((result, timings))
+}
+
+/// Groups `db_events` and each of the child tables' rows by game and event,
+/// then turns each event and its children back into an [`EventDetail`]
+fn assemble_events(
+    taxa: &Taxa,
+    game_ids: Vec<i64>,
+    db_events: Vec<DbEvent>,
+    children: EventChildRows,
+    timings: &mut EventsForGameTimings,
+) -> Vec<GameEvents> {
+    let group_events_start = Utc::now();
+    let mut db_events_iter = db_events.into_iter().peekable();
+    let db_games_events = game_ids
+        .iter()
+        .map(|id| {
+            let mut game_events = Vec::new();
+            while let Some(event) = db_events_iter.next_if(|e| e.game_id == *id) {
+                game_events.push(event);
+            }
+            game_events
+        })
+        .collect_vec();
+    timings.group_events_duration = (Utc::now() - group_events_start).as_seconds_f64();
+
+    let group_children_start = Utc::now();
+    let db_runners = group_child_table_results(&db_games_events, children.runners, |r| r.event_id);
+    let db_fielders =
+        group_child_table_results(&db_games_events, children.fielders, |r| r.event_id);
+    let db_aurora_photos =
+        group_child_table_results(&db_games_events, children.aurora_photos, |r| r.event_id);
+    let db_ejections =
+        group_child_table_results(&db_games_events, children.ejections, |r| r.event_id);
+    let db_failed_ejections =
+        group_child_table_results(&db_games_events, children.failed_ejections, |r| r.event_id);
+    let db_door_prizes =
+        group_child_table_results(&db_games_events, children.door_prizes, |r| r.event_id);
+    let db_door_prize_items =
+        group_child_table_results(&db_games_events, children.door_prize_items, |r| r.event_id);
+    let db_efflorescences =
+        group_child_table_results(&db_games_events, children.efflorescences, |r| r.event_id);
+    let db_efflorescence_growths =
+        group_child_table_results(&db_games_events, children.efflorescence_growths, |r| {
+            r.event_id
+        });
+    let db_wither = group_wither_table_results(&db_games_events, children.wither);
+    let db_cheer = group_cheer_table_results(&db_games_events, children.cheers);
+    let db_balk_reason = group_balk_reason_table_results(&db_games_events, children.balk_reasons);
+    timings.group_children_duration = (Utc::now() - group_children_start).as_seconds_f64();
+
+    let post_process_start = Utc::now();
+    let result = itertools::izip!(
+        game_ids,
+        db_games_events,
+        db_runners,
+        db_fielders,
+        db_aurora_photos,
+        db_ejections,
+        db_failed_ejections,
+        db_door_prizes,
+        db_door_prize_items,
+        db_efflorescences,
+        db_efflorescence_growths,
+        db_wither,
+        db_cheer,
+        db_balk_reason,
+    )
+    .map(
+        |(
+            game_id,
+            events,
+            runners,
+            fielders,
+            aurora_photos,
+            ejections,
+            failed_ejections,
+            door_prizes,
+            door_prize_items,
+            efflorescence,
+            efflorescence_growths,
+            wither,
+            cheer,
+            balk_reason,
+        )| {
+            // Note: This should stay a vec of results. The individual results for each
+            // entry are semantically meaningful.
+            let detail_events = itertools::izip!(
+                events,
+                runners,
+                fielders,
+                aurora_photos,
+                ejections,
+                failed_ejections,
+                door_prizes,
+                door_prize_items,
+                efflorescence,
+                efflorescence_growths,
+                wither,
+                cheer,
+                balk_reason,
+            )
+            .map(
+                |(
+                    event,
+                    runners,
+                    fielders,
+                    aurora_photo,
+                    ejection,
+                    failed_ejection,
+                    door_prizes,
+                    door_prize_items,
+                    efflorescence,
+                    efflorescence_growths,
+                    wither,
+                    cheer,
+                    balk_reason,
+                )| {
+                    to_db_format::row_to_event(
+                        taxa,
+                        event,
+                        runners,
+                        fielders,
+                        aurora_photo,
+                        ejection,
+                        failed_ejection,
+                        door_prizes,
+                        door_prize_items,
+                        efflorescence,
+                        efflorescence_growths,
+                        wither,
+                        cheer,
+                        balk_reason,
+                    )
+                },
+            )
+            .collect_vec();
+            (game_id, detail_events)
+        },
+    )
+    .collect_vec();
+    timings.post_process_duration = (Utc::now() - post_process_start).as_seconds_f64();
+
+    result
+}
@@ -0,0 +1,119 @@
+use diesel::prelude::*;
+use diesel::sql_types::Text;
+use diesel::{PgConnection, sql_query};
+use thiserror::Error;
+
+use super::{
+    FASTEST_PITCH_SQL, HIGHEST_REPORTED_ATTRIBUTE_SQL, HIGHEST_SCORE_IN_A_GAME_SQL,
+    HIGHEST_SCORING_GAME_SQL, LONGEST_GAME_BY_EVENTS_SQL, LONGEST_GAME_BY_INNINGS_SQL,
+    MOST_PITCHES_BY_PLAYER_IN_ONE_GAME_SQL,
+};
+
+/// A query that admins are allowed to run `explain` on. Only queries in
+/// [`EXPLAINABLE_QUERIES`] can be explained, so there's no way to get
+/// arbitrary SQL through this.
+#[derive(Debug)]
+pub struct ExplainableQuery {
+    pub name: &'static str,
+    pub sql: &'static str,
+    /// Names of the query's bind parameters, in order. All parameters are
+    /// bound as text.
+    pub params: &'static [&'static str],
+}
+
+pub const EXPLAINABLE_QUERIES: &[ExplainableQuery] = &[
+    ExplainableQuery {
+        name: "fastest_pitch",
+        sql: FASTEST_PITCH_SQL,
+        params: &["team_identity"],
+    },
+    ExplainableQuery {
+        name: "most_pitches_by_player_in_one_game",
+        sql: MOST_PITCHES_BY_PLAYER_IN_ONE_GAME_SQL,
+        params: &["team_identity"],
+    },
+    ExplainableQuery {
+        name: "highest_scoring_game",
+        sql: HIGHEST_SCORING_GAME_SQL,
+        params: &[],
+    },
+    ExplainableQuery {
+        name: "highest_score_in_a_game",
+        sql: HIGHEST_SCORE_IN_A_GAME_SQL,
+        params: &[],
+    },
+    ExplainableQuery {
+        name: "longest_game_by_events",
+        sql: LONGEST_GAME_BY_EVENTS_SQL,
+        params: &[],
+    },
+    ExplainableQuery {
+        name: "longest_game_by_innings",
+        sql: LONGEST_GAME_BY_INNINGS_SQL,
+        params: &[],
+    },
+    ExplainableQuery {
+        name: "highest_reported_attribute",
+        sql: HIGHEST_REPORTED_ATTRIBUTE_SQL,
+        params: &["attribute_name", "team_identity"],
+    },
+];
+
+pub fn explainable_query(name: &str) -> Option<&'static ExplainableQuery> {
+    EXPLAINABLE_QUERIES.iter().find(|query| query.name == name)
+}
+
+#[derive(Debug, Error)]
+pub enum ExplainError {
+    #[error("There's no explainable query named {0:?}")]
+    UnknownQuery(String),
+
+    #[error("Query {query} takes {expected} parameter(s) but {actual} were provided")]
+    WrongNumberOfParams {
+        query: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+
+    #[error(transparent)]
+    Db(#[from] diesel::result::Error),
+}
+
+#[derive(QueryableByName)]
+struct QueryPlanLine {
+    #[diesel(sql_type = Text)]
+    #[diesel(column_name = "QUERY PLAN")]
+    line: String,
+}
+
+/// Runs `explain (analyze, buffers)` on the named query and returns the
+/// plan, one line per entry. Note that `analyze` really executes the
+/// query, so this takes as long as the query itself. It's run in a
+/// read-only transaction for safety.
+pub fn explain_query(
+    conn: &mut PgConnection,
+    name: &str,
+    params: &[String],
+) -> Result<Vec<String>, ExplainError> {
+    let query =
+        explainable_query(name).ok_or_else(|| ExplainError::UnknownQuery(name.to_string()))?;
+
+    if params.len() != query.params.len() {
+        return Err(ExplainError::WrongNumberOfParams {
+            query: query.name,
+            expected: query.params.len(),
+            actual: params.len(),
+        });
+    }
+
+    let lines = conn.build_transaction().read_only().run(|conn| {
+        let mut explain = sql_query(format!("explain (analyze, buffers) {}", query.sql))
+            .into_boxed::<diesel::pg::Pg>();
+        for param in params {
+            explain = explain.bind::<Text, _>(param.clone());
+        }
+        explain.get_results::<QueryPlanLine>(conn)
+    })?;
+
+    Ok(lines.into_iter().map(|line| line.line).collect())
+}
@@ -0,0 +1,684 @@
+use chrono::{DateTime, Utc};
+use diesel::{PgConnection, prelude::*, sql_query, sql_types::*};
+use itertools::Itertools;
+use serde::Serialize;
+use thiserror::Error;
+
+/// Only these schemas can be exported. The rest are internal, and `info`
+/// has things like query text and admin actions that API token holders
+/// shouldn't see.
+pub const EXPORTABLE_SCHEMAS: &[&str] = &["data", "taxa"];
+
+/// The `kind`s in `data.feed_event_versions`
+pub const FEED_EVENT_KINDS: &[&str] = &["player_feed", "team_feed"];
+
+#[derive(Debug, Error)]
+pub enum ExportError {
+    #[error(transparent)]
+    Db(#[from] diesel::result::Error),
+
+    #[error("couldn't copy the export out of postgres")]
+    Copy(#[from] tokio_postgres::Error),
+
+    #[error("{schema}.{table} is not an exportable table")]
+    UnknownTable { schema: String, table: String },
+
+    #[error("{table} has no column named {column:?}")]
+    UnknownColumn { table: String, column: String },
+
+    #[error(
+        "{table}.{column} can't be filtered on because it isn't the first column of any index. \
+        Filtering on it would require scanning the whole table."
+    )]
+    ColumnNotIndexed { table: String, column: String },
+
+    #[error("{schema}.{table} can't be exported because it has no primary key to page by")]
+    NoPrimaryKey { schema: String, table: String },
+
+    #[error("{value:?} isn't a valid {sql_type}, so {column} can't be filtered on it")]
+    InvalidFilterValue {
+        column: String,
+        sql_type: String,
+        value: String,
+    },
+
+    #[error("{0:?} isn't a page of this export")]
+    InvalidCursor(String),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, strum::EnumString, strum::Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum ExportFilterOp {
+    Eq,
+    Ne,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+}
+
+impl ExportFilterOp {
+    fn as_sql(self) -> &'static str {
+        match self {
+            ExportFilterOp::Eq => "=",
+            ExportFilterOp::Ne => "<>",
+            ExportFilterOp::Lt => "<",
+            ExportFilterOp::Lte => "<=",
+            ExportFilterOp::Gt => ">",
+            ExportFilterOp::Gte => ">=",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExportFilter {
+    pub column: String,
+    pub op: ExportFilterOp,
+    /// Parsed by postgres as the column's type
+    pub value: String,
+}
+
+#[derive(Debug, Clone)]
+struct ExportColumn {
+    name: String,
+    sql_type: String,
+}
+
+/// A validated export of one table. Every identifier in here came from the
+/// database catalog, not from the user, and every value was parsed and
+/// quoted by postgres, so it's safe to build SQL from.
+#[derive(Debug)]
+pub struct ExportPlan {
+    schema: String,
+    table: String,
+    columns: Vec<ExportColumn>,
+    /// The primary key, which pages are ordered and keyed by
+    key: Vec<ExportColumn>,
+    /// Each filter's value is a SQL literal
+    filters: Vec<(ExportColumn, ExportFilterOp, String)>,
+}
+
+fn quote_ident(ident: &str) -> String {
+    format!("\"{}\"", ident.replace('"', "\"\""))
+}
+
+/// Has postgres parse `value` as `sql_type` and quote it, so it can be used
+/// in statements that can't take parameters, like `COPY`. Returns None if
+/// `value` isn't a valid `sql_type`.
+fn sql_literal(
+    conn: &mut PgConnection,
+    value: &str,
+    sql_type: &str,
+) -> QueryResult<Option<String>> {
+    #[derive(QueryableByName)]
+    struct Literal {
+        #[diesel(sql_type = Text)]
+        literal: String,
+    }
+
+    // The savepoint keeps a bad value from aborting the caller's
+    // transaction
+    let result = conn.transaction(|conn| {
+        sql_query(format!(
+            "select quote_literal($1::text::{sql_type}) as literal"
+        ))
+        .bind::<Text, _>(value)
+        .get_result::<Literal>(conn)
+    });
+    match result {
+        Ok(literal) => Ok(Some(format!("{}::{sql_type}", literal.literal))),
+        Err(diesel::result::Error::DatabaseError(_, _)) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+fn table_columns(
+    conn: &mut PgConnection,
+    schema: &str,
+    table: &str,
+) -> QueryResult<Vec<ExportColumn>> {
+    #[derive(QueryableByName)]
+    struct CatalogColumn {
+        #[diesel(sql_type = Text)]
+        name: String,
+        #[diesel(sql_type = Text)]
+        sql_type: String,
+    }
+
+    sql_query(
+        "
+        select a.attname::text as name, format_type(a.atttypid, a.atttypmod) as sql_type
+        from pg_attribute a
+        join pg_class c on c.oid=a.attrelid
+        join pg_namespace n on n.oid=c.relnamespace
+        where n.nspname=$1 and c.relname=$2 and c.relkind in ('r', 'p', 'm')
+            and a.attnum > 0 and not a.attisdropped
+        order by a.attnum
+    ",
+    )
+    .bind::<Text, _>(schema)
+    .bind::<Text, _>(table)
+    .get_results::<CatalogColumn>(conn)
+    .map(|columns| {
+        columns
+            .into_iter()
+            .map(|c| ExportColumn {
+                name: c.name,
+                sql_type: c.sql_type,
+            })
+            .collect()
+    })
+}
+
+/// Columns that lead at least one index. Only these can be filtered on
+/// cheaply.
+fn leading_index_columns(
+    conn: &mut PgConnection,
+    schema: &str,
+    table: &str,
+) -> QueryResult<Vec<String>> {
+    #[derive(QueryableByName)]
+    struct IndexedColumn {
+        #[diesel(sql_type = Text)]
+        name: String,
+    }
+
+    sql_query(
+        "
+        select distinct a.attname::text as name
+        from pg_index i
+        join pg_class c on c.oid=i.indrelid
+        join pg_namespace n on n.oid=c.relnamespace
+        join pg_attribute a on a.attrelid=i.indrelid and a.attnum=i.indkey[0]
+        where n.nspname=$1 and c.relname=$2
+    ",
+    )
+    .bind::<Text, _>(schema)
+    .bind::<Text, _>(table)
+    .get_results::<IndexedColumn>(conn)
+    .map(|columns| columns.into_iter().map(|c| c.name).collect())
+}
+
+/// The primary key's columns, in key order. Empty if there's no primary key.
+fn primary_key_columns(
+    conn: &mut PgConnection,
+    schema: &str,
+    table: &str,
+) -> QueryResult<Vec<String>> {
+    #[derive(QueryableByName)]
+    struct KeyColumn {
+        #[diesel(sql_type = Text)]
+        name: String,
+    }
+
+    sql_query(
+        "
+        select a.attname::text as name
+        from pg_index i
+        join pg_class c on c.oid=i.indrelid
+        join pg_namespace n on n.oid=c.relnamespace
+        cross join lateral unnest(i.indkey::int2[]) with ordinality as k(attnum, position)
+        join pg_attribute a on a.attrelid=i.indrelid and a.attnum=k.attnum
+        where n.nspname=$1 and c.relname=$2 and i.indisprimary
+        order by k.position
+    ",
+    )
+    .bind::<Text, _>(schema)
+    .bind::<Text, _>(table)
+    .get_results::<KeyColumn>(conn)
+    .map(|columns| columns.into_iter().map(|c| c.name).collect())
+}
+
+/// Checks that the table, columns, and filters all exist and are allowed.
+/// `columns` of None means every column, in table order. Only tables with
+/// a primary key can be exported.
+pub fn plan_export(
+    conn: &mut PgConnection,
+    schema: &str,
+    table: &str,
+    columns: Option<Vec<String>>,
+    filters: Vec<ExportFilter>,
+) -> Result<ExportPlan, ExportError> {
+    let unknown_table = || ExportError::UnknownTable {
+        schema: schema.to_string(),
+        table: table.to_string(),
+    };
+
+    if !EXPORTABLE_SCHEMAS.contains(&schema) {
+        return Err(unknown_table());
+    }
+
+    let all_columns = table_columns(conn, schema, table)?;
+    if all_columns.is_empty() {
+        return Err(unknown_table());
+    }
+
+    let find_column = |name: &str| {
+        all_columns
+            .iter()
+            .find(|c| c.name == name)
+            .cloned()
+            .ok_or_else(|| ExportError::UnknownColumn {
+                table: table.to_string(),
+                column: name.to_string(),
+            })
+    };
+
+    let columns = match columns {
+        None => all_columns.clone(),
+        Some(names) => names
+            .iter()
+            .map(|name| find_column(name))
+            .collect::<Result<_, _>>()?,
+    };
+
+    let key = primary_key_columns(conn, schema, table)?
+        .iter()
+        .map(|name| find_column(name))
+        .collect::<Result<Vec<_>, _>>()?;
+    if key.is_empty() {
+        return Err(ExportError::NoPrimaryKey {
+            schema: schema.to_string(),
+            table: table.to_string(),
+        });
+    }
+
+    let indexed = leading_index_columns(conn, schema, table)?;
+    let mut planned_filters = Vec::with_capacity(filters.len());
+    for filter in filters {
+        let column = find_column(&filter.column)?;
+        if !indexed.contains(&column.name) {
+            return Err(ExportError::ColumnNotIndexed {
+                table: table.to_string(),
+                column: column.name,
+            });
+        }
+        let literal = sql_literal(conn, &filter.value, &column.sql_type)?.ok_or_else(|| {
+            ExportError::InvalidFilterValue {
+                column: column.name.clone(),
+                sql_type: column.sql_type.clone(),
+                value: filter.value.clone(),
+            }
+        })?;
+        planned_filters.push((column, filter.op, literal));
+    }
+
+    Ok(ExportPlan {
+        schema: schema.to_string(),
+        table: table.to_string(),
+        columns,
+        key,
+        filters: planned_filters,
+    })
+}
+
+impl ExportPlan {
+    /// The exported columns' names and postgres types, in order
+    pub fn columns(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.columns
+            .iter()
+            .map(|c| (c.name.as_str(), c.sql_type.as_str()))
+    }
+
+    fn key_sql(&self) -> String {
+        let columns = self
+            .key
+            .iter()
+            .map(|c| format!("t.{}", quote_ident(&c.name)))
+            .join(", ");
+        format!("({columns})")
+    }
+
+    /// Each key column as text, which is how cursors store them
+    fn key_text_sql(&self) -> String {
+        let columns = self
+            .key
+            .iter()
+            .map(|c| format!("t.{}::text", quote_ident(&c.name)))
+            .join(", ");
+        format!("array[{columns}]")
+    }
+
+    /// Selects from the table, aliased `t`, with the filters applied and
+    /// only the rows with keys after `after` and up to `through`
+    fn from_where_sql(&self, after: Option<&[String]>, through: Option<&[String]>) -> String {
+        let mut clauses = self
+            .filters
+            .iter()
+            .map(|(column, op, literal)| {
+                format!("t.{} {} {literal}", quote_ident(&column.name), op.as_sql())
+            })
+            .collect_vec();
+        if let Some(after) = after {
+            clauses.push(format!("{} > ({})", self.key_sql(), after.join(", ")));
+        }
+        if let Some(through) = through {
+            clauses.push(format!("{} <= ({})", self.key_sql(), through.join(", ")));
+        }
+        let where_sql = if clauses.is_empty() {
+            "true".to_string()
+        } else {
+            clauses.join(" and ")
+        };
+
+        format!(
+            "from {}.{} t where {where_sql} order by {}",
+            quote_ident(&self.schema),
+            quote_ident(&self.table),
+            self.key
+                .iter()
+                .map(|c| format!("t.{}", quote_ident(&c.name)))
+                .join(", "),
+        )
+    }
+
+    /// Turns a cursor from a previous page back into SQL literals, one for
+    /// each key column
+    fn parse_cursor(
+        &self,
+        conn: &mut PgConnection,
+        cursor: &str,
+    ) -> Result<Vec<String>, ExportError> {
+        let invalid = || ExportError::InvalidCursor(cursor.to_string());
+        let values = serde_json::from_str::<Vec<String>>(cursor).map_err(|_| invalid())?;
+        if values.len() != self.key.len() {
+            return Err(invalid());
+        }
+
+        self.key
+            .iter()
+            .zip(&values)
+            .map(|(column, value)| sql_literal(conn, value, &column.sql_type)?.ok_or_else(invalid))
+            .collect()
+    }
+}
+
+fn cursor(key_values: Vec<String>) -> String {
+    serde_json::Value::from(key_values).to_string()
+}
+
+#[derive(QueryableByName)]
+struct RowKey {
+    #[diesel(sql_type = Array<Text>)]
+    row_key: Vec<String>,
+}
+
+/// One page of a CSV export, as a `COPY` statement for
+/// [`crate::async_db::copy_out`]. Diesel can only `COPY` whole tables, so
+/// the statement is run separately.
+pub struct ExportCsvPage {
+    pub copy_sql: String,
+    /// Pass this as `after` to get the next page. None if this is the last
+    /// page.
+    pub next_page: Option<String>,
+}
+
+/// Plans the page of up to `page_size` rows that comes after the `after`
+/// cursor (or the first page, if it's None). Pages are ordered by primary
+/// key, so rows that are added or changed while an export is in progress
+/// never make it skip or repeat other rows. The page's end is found before
+/// it's copied, so the next page's cursor is known up front.
+pub fn plan_csv_page(
+    conn: &mut PgConnection,
+    plan: &ExportPlan,
+    after: Option<&str>,
+    page_size: i64,
+) -> Result<ExportCsvPage, ExportError> {
+    let after = after
+        .map(|after| plan.parse_cursor(conn, after))
+        .transpose()?;
+
+    // The last row of this page, and the first row of the next one if
+    // there is one
+    let boundary = sql_query(format!(
+        "select {} as row_key {} offset $1 limit 2",
+        plan.key_text_sql(),
+        plan.from_where_sql(after.as_deref(), None),
+    ))
+    .bind::<BigInt, _>(page_size - 1)
+    .get_results::<RowKey>(conn)?;
+
+    let (through, next_page) = match <[RowKey; 2]>::try_from(boundary) {
+        Ok([last, _]) => {
+            let next_page = cursor(last.row_key);
+            (Some(plan.parse_cursor(conn, &next_page)?), Some(next_page))
+        }
+        // This is the last page
+        Err(_) => (None, None),
+    };
+
+    let columns = plan
+        .columns
+        .iter()
+        .map(|c| format!("t.{}", quote_ident(&c.name)))
+        .join(", ");
+    // Nulls become empty fields and empty strings become "", so they stay
+    // distinguishable
+    let copy_sql = format!(
+        "copy (select {columns} {}) to stdout with (format csv, header true)",
+        plan.from_where_sql(after.as_deref(), through.as_deref()),
+    );
+
+    Ok(ExportCsvPage {
+        copy_sql,
+        next_page,
+    })
+}
+
+/// One page of an export, with each row as a JSON array of its values
+pub struct ExportJsonPage {
+    pub rows: Vec<Vec<serde_json::Value>>,
+    /// Pass this as `after` to get the next page. None once the export is
+    /// finished.
+    pub next_page: Option<String>,
+}
+
+/// Gets up to `page_size` rows after the `after` cursor, ordered by
+/// primary key, with each row's values converted to JSON by postgres, for
+/// exporting into other databases. Timestamps and other values without a
+/// JSON equivalent become strings.
+pub fn export_json_page(
+    conn: &mut PgConnection,
+    plan: &ExportPlan,
+    after: Option<&str>,
+    page_size: i64,
+) -> Result<ExportJsonPage, ExportError> {
+    #[derive(QueryableByName)]
+    struct JsonLine {
+        #[diesel(sql_type = Array<Text>)]
+        row_key: Vec<String>,
+        #[diesel(sql_type = Jsonb)]
+        line: serde_json::Value,
+    }
+
+    let after = after
+        .map(|after| plan.parse_cursor(conn, after))
+        .transpose()?;
+
+    // Postgres functions can take at most 100 arguments, so wide tables
+    // are built from several arrays concatenated together
+    let line_expr = plan
+        .columns
+        .chunks(100)
+        .map(|columns| {
+            let fields = columns
+                .iter()
+                .map(|c| format!("t.{}", quote_ident(&c.name)))
+                .join(", ");
+            format!("jsonb_build_array({fields})")
+        })
+        .join(" || ");
+    // One extra row says whether there's another page
+    let mut rows = sql_query(format!(
+        "select {} as row_key, {line_expr} as line {} limit $1",
+        plan.key_text_sql(),
+        plan.from_where_sql(after.as_deref(), None),
+    ))
+    .bind::<BigInt, _>(page_size + 1)
+    .get_results::<JsonLine>(conn)?;
+
+    let next_page = if rows.len() as i64 > page_size {
+        rows.truncate(page_size as usize);
+        rows.last().map(|row| cursor(row.row_key.clone()))
+    } else {
+        None
+    };
+    let rows = rows
+        .into_iter()
+        .map(|row| match row.line {
+            serde_json::Value::Array(values) => values,
+            // jsonb_build_array always builds an array
+            other => vec![other],
+        })
+        .collect();
+
+    Ok(ExportJsonPage { rows, next_page })
+}
+
+/// One version of one raw feed event, as it's exported
+#[derive(Debug, Clone, QueryableByName, Serialize)]
+pub struct DbFeedEventExport {
+    #[diesel(sql_type = Text)]
+    pub kind: String,
+    #[diesel(sql_type = Text)]
+    pub entity_id: String,
+    #[diesel(sql_type = Int4)]
+    pub feed_event_index: i32,
+    #[diesel(sql_type = Timestamptz)]
+    pub valid_from: DateTime<Utc>,
+    #[diesel(sql_type = Nullable<Timestamptz>)]
+    pub valid_until: Option<DateTime<Utc>>,
+    #[diesel(sql_type = Jsonb)]
+    pub data: serde_json::Value,
+}
+
+/// Where a feed event export page ends. Pass it back to get the next page.
+#[derive(Debug, Clone)]
+pub struct FeedEventCursor {
+    pub valid_from: DateTime<Utc>,
+    pub entity_id: String,
+    pub feed_event_index: i32,
+}
+
+impl DbFeedEventExport {
+    pub fn cursor(&self) -> FeedEventCursor {
+        FeedEventCursor {
+            valid_from: self.valid_from,
+            entity_id: self.entity_id.clone(),
+            feed_event_index: self.feed_event_index,
+        }
+    }
+}
+
+/// Gets up to `page_size` versions of `kind` feed events whose
+/// `valid_from` is in `[since, until)`, ordered by `valid_from`. Every
+/// version is included, not just the current ones, so consumers can see
+/// when an event changed.
+pub fn export_feed_events_page(
+    conn: &mut PgConnection,
+    kind: &str,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    after: Option<&FeedEventCursor>,
+    page_size: i64,
+) -> Result<Vec<DbFeedEventExport>, ExportError> {
+    if !FEED_EVENT_KINDS.contains(&kind) {
+        return Err(ExportError::UnknownTable {
+            schema: "feed".to_string(),
+            table: kind.to_string(),
+        });
+    }
+
+    let rows = sql_query(
+        "
+        select fev.kind, fev.entity_id, fev.feed_event_index, fev.valid_from,
+            fev.valid_until, fev.data
+        from data.feed_event_versions fev
+        where fev.kind=$1
+            and fev.source is null
+            and ($2 is null or fev.valid_from >= $2)
+            and ($3 is null or fev.valid_from < $3)
+            and ($4 is null or (fev.valid_from, fev.entity_id, fev.feed_event_index) > ($4, $5, $6))
+        order by fev.valid_from, fev.entity_id, fev.feed_event_index
+        limit $7
+    ",
+    )
+    .bind::<Text, _>(kind)
+    .bind::<Nullable<Timestamptz>, _>(since)
+    .bind::<Nullable<Timestamptz>, _>(until)
+    .bind::<Nullable<Timestamptz>, _>(after.map(|a| a.valid_from))
+    .bind::<Nullable<Text>, _>(after.map(|a| a.entity_id.as_str()))
+    .bind::<Nullable<Int4>, _>(after.map(|a| a.feed_event_index))
+    .bind::<BigInt, _>(page_size)
+    .get_results(conn)?;
+
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::postgres_url_from_environment;
+
+    #[test]
+    fn exports_are_paged_by_primary_key() {
+        let url = postgres_url_from_environment();
+        let mut conn = PgConnection::establish(&url)
+            .expect("postgres_url_from_environment should return a valid connection string");
+
+        conn.test_transaction(|conn| {
+            sql_query(
+                "
+                create table data.export_test (season int, id int, name text, primary key (season, id));
+                insert into data.export_test (season, id, name)
+                select s, i, 'row ' || i from generate_series(1, 2) s, generate_series(1, 3) i;
+            ",
+            )
+            .execute(conn)?;
+
+            assert!(matches!(
+                plan_export(conn, "info", "admin_audit", None, Vec::new()),
+                Err(ExportError::UnknownTable { .. }),
+            ));
+
+            let season_filter = |value: &str| ExportFilter {
+                column: "season".to_string(),
+                op: ExportFilterOp::Eq,
+                value: value.to_string(),
+            };
+            assert!(matches!(
+                plan_export(conn, "data", "export_test", None, vec![season_filter("two")]),
+                Err(ExportError::InvalidFilterValue { .. }),
+            ));
+            // Not indexed
+            let name_filter = ExportFilter {
+                column: "name".to_string(),
+                ..season_filter("x")
+            };
+            assert!(matches!(
+                plan_export(conn, "data", "export_test", None, vec![name_filter]),
+                Err(ExportError::ColumnNotIndexed { .. }),
+            ));
+
+            let plan = plan_export(conn, "data", "export_test", None, vec![season_filter("2")])?;
+            assert!(matches!(
+                plan_csv_page(conn, &plan, Some("[\"2\"]"), 2),
+                Err(ExportError::InvalidCursor(_)),
+            ));
+
+            let first = plan_csv_page(conn, &plan, None, 2)?;
+            assert_eq!(first.next_page.as_deref(), Some("[\"2\",\"2\"]"));
+            assert!(first.copy_sql.starts_with("copy ("));
+
+            // Exactly one page left, so there's no empty page after it
+            let second = plan_csv_page(conn, &plan, first.next_page.as_deref(), 1)?;
+            assert_eq!(second.next_page, None);
+
+            let json = export_json_page(conn, &plan, first.next_page.as_deref(), 1)?;
+            assert_eq!(json.rows, vec![vec![serde_json::json!(2), serde_json::json!(3), serde_json::json!("row 3")]]);
+            assert_eq!(json.next_page, None);
+
+            Ok::<_, ExportError>(())
+        });
+    }
+}
@@ -0,0 +1,279 @@
+use chrono::{DateTime, Utc};
+use diesel::{PgConnection, prelude::*, sql_query, sql_types::*};
+use itertools::Itertools;
+
+use super::record_admin_action;
+use crate::models::{DbFeedEventFailure, NewAdminAudit, NewFeedEventFailure};
+
+/// Adds feed event versions to the dead-letter queue. The raw event is
+/// copied from `data.feed_event_versions`. If a version is already in the
+/// queue, its error message is replaced with the new one.
+pub fn insert_feed_event_failures(
+    conn: &mut PgConnection,
+    new_failures: Vec<&NewFeedEventFailure>,
+) -> QueryResult<usize> {
+    if new_failures.is_empty() {
+        return Ok(0);
+    }
+
+    let (kinds, entity_ids, feed_event_indices, valid_froms, error_messages): (
+        Vec<&str>,
+        Vec<&str>,
+        Vec<i32>,
+        Vec<DateTime<Utc>>,
+        Vec<&str>,
+    ) = itertools::multiunzip(new_failures.into_iter().map(|failure| {
+        (
+            failure.kind,
+            failure.entity_id,
+            failure.feed_event_index,
+            failure.valid_from,
+            failure.error_message.as_str(),
+        )
+    }));
+
+    sql_query(
+        "
+        insert into info.feed_event_failures
+            (kind, entity_id, feed_event_index, valid_from, raw_data, error_message)
+        select f.kind, f.entity_id, f.feed_event_index, f.valid_from, fev.data, f.error_message
+        from unnest($1, $2, $3, $4, $5)
+            as f(kind, entity_id, feed_event_index, valid_from, error_message)
+        join data.feed_event_versions fev
+            on fev.kind=f.kind
+            and fev.entity_id=f.entity_id
+            and fev.feed_event_index=f.feed_event_index
+            and fev.valid_from=f.valid_from
+            and fev.source is null
+        on conflict (kind, entity_id, feed_event_index, valid_from) do update
+            set error_message=excluded.error_message,
+                last_attempt_at=now()
+    ",
+    )
+    .bind::<Array<Text>, _>(kinds)
+    .bind::<Array<Text>, _>(entity_ids)
+    .bind::<Array<Int4>, _>(feed_event_indices)
+    .bind::<Array<Timestamptz>, _>(valid_froms)
+    .bind::<Array<Text>, _>(error_messages)
+    .execute(conn)
+}
+
+pub fn feed_event_failures_for_kind(
+    conn: &mut PgConnection,
+    kind: &str,
+) -> QueryResult<Vec<DbFeedEventFailure>> {
+    use crate::info_schema::info::feed_event_failures::dsl as fef_dsl;
+
+    fef_dsl::feed_event_failures
+        .filter(fef_dsl::kind.eq(kind))
+        .order_by((fef_dsl::valid_from.asc(), fef_dsl::id.asc()))
+        .select(DbFeedEventFailure::as_select())
+        .get_results(conn)
+}
+
+/// Removes these failures from the dead-letter queue and marks their
+/// versions as unprocessed, so the next processing pass ingests them
+/// normally. Their ingest logs are deleted too, since processing them again
+/// writes new ones. If they fail again they'll be re-added to the queue.
+pub fn requeue_feed_event_failures(
+    conn: &mut PgConnection,
+    failures: &[&DbFeedEventFailure],
+    actor: &str,
+) -> QueryResult<usize> {
+    use crate::data_schema::data::feed_events_processed::dsl as fep_dsl;
+    use crate::info_schema::info::feed_event_failures::dsl as fef_dsl;
+    use crate::info_schema::info::version_ingest_log::dsl as vil_dsl;
+
+    if failures.is_empty() {
+        return Ok(0);
+    }
+
+    conn.transaction(|conn| {
+        let mut processed_deleted = 0;
+        let mut logs_deleted = 0;
+        for failure in failures {
+            processed_deleted += diesel::delete(
+                fep_dsl::feed_events_processed
+                    .filter(fep_dsl::kind.eq(&failure.kind))
+                    .filter(fep_dsl::entity_id.eq(&failure.entity_id))
+                    .filter(fep_dsl::feed_event_index.eq(failure.feed_event_index))
+                    .filter(fep_dsl::valid_from.eq(failure.valid_from)),
+            )
+            .execute(conn)?;
+
+            logs_deleted += diesel::delete(
+                vil_dsl::version_ingest_log
+                    .filter(vil_dsl::kind.eq(&failure.kind))
+                    .filter(vil_dsl::entity_id.eq(&failure.entity_id))
+                    .filter(vil_dsl::valid_from.eq(failure.valid_from))
+                    .filter(vil_dsl::feed_event_index.eq(failure.feed_event_index)),
+            )
+            .execute(conn)?;
+        }
+
+        let requeued = diesel::delete(
+            fef_dsl::feed_event_failures
+                .filter(fef_dsl::id.eq_any(failures.iter().map(|f| f.id).collect_vec())),
+        )
+        .execute(conn)?;
+
+        record_admin_action(
+            conn,
+            &NewAdminAudit {
+                actor,
+                action: "requeue_feed_event_failures",
+                details: serde_json::json!({
+                    "feed_events": failures
+                        .iter()
+                        .map(|f| {
+                            serde_json::json!({
+                                "kind": f.kind,
+                                "entity_id": f.entity_id,
+                                "feed_event_index": f.feed_event_index,
+                                "valid_from": f.valid_from,
+                            })
+                        })
+                        .collect_vec(),
+                }),
+                before: Some(serde_json::json!({
+                    "rows": {
+                        "info.feed_event_failures": requeued,
+                        "data.feed_events_processed": processed_deleted,
+                        "info.version_ingest_log": logs_deleted,
+                    },
+                })),
+                after: Some(serde_json::json!({
+                    "rows": {
+                        "info.feed_event_failures": 0,
+                        "data.feed_events_processed": 0,
+                        "info.version_ingest_log": 0,
+                    },
+                })),
+            },
+        )?;
+
+        Ok(requeued)
+    })
+}
+
+/// Records a re-drive attempt that failed again
+pub fn record_feed_event_failure_retry(
+    conn: &mut PgConnection,
+    failure_id: i64,
+    error_message: &str,
+) -> QueryResult<usize> {
+    use crate::info_schema::info::feed_event_failures::dsl as fef_dsl;
+
+    diesel::update(fef_dsl::feed_event_failures.filter(fef_dsl::id.eq(failure_id)))
+        .set((
+            fef_dsl::retry_count.eq(fef_dsl::retry_count + 1),
+            fef_dsl::error_message.eq(error_message),
+            fef_dsl::last_attempt_at.eq(diesel::dsl::now),
+        ))
+        .execute(conn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{NewFeedEventProcessed, NewVersionIngestLog};
+    use crate::{QueryError, db, postgres_url_from_environment};
+
+    #[test]
+    fn requeued_failures_are_unprocessed_and_their_logs_cleared() {
+        let url = postgres_url_from_environment();
+        let mut conn = PgConnection::establish(&url)
+            .expect("postgres_url_from_environment should return a valid connection string");
+
+        conn.test_transaction(|conn| {
+            use crate::data_schema::data::feed_events_processed::dsl as fep_dsl;
+            use crate::info_schema::info::version_ingest_log::dsl as vil_dsl;
+
+            let kind = "player_feed";
+            let entity_id = "feed-failure-test-player";
+            let valid_from = Utc::now();
+            // Both events come from the same version of the feed
+            let versions = [0, 1]
+                .map(|index| {
+                    (
+                        entity_id.to_string(),
+                        index,
+                        valid_from,
+                        serde_json::json!({ "text": format!("event {index}") }),
+                    )
+                })
+                .to_vec();
+            db::insert_feed_event_versions(conn, kind, &versions, None)?;
+
+            let mut logs = Vec::new();
+            for index in [0, 1] {
+                super::insert_feed_events_processed(
+                    conn,
+                    vec![&NewFeedEventProcessed {
+                        kind,
+                        entity_id,
+                        feed_event_index: index,
+                        valid_from,
+                        skipped: false,
+                        fatal_error: false,
+                    }],
+                )?;
+                logs.push(NewVersionIngestLog {
+                    kind,
+                    entity_id,
+                    valid_from,
+                    log_index: 0,
+                    log_level: 1,
+                    log_text: format!("Error parsing event {index}"),
+                    error_code: None,
+                    feed_event_index: Some(index),
+                });
+            }
+            db::insert_ingest_logs(conn, logs)?;
+
+            let failure = NewFeedEventFailure {
+                kind,
+                entity_id,
+                feed_event_index: 1,
+                valid_from,
+                error_message: "Error parsing event 1".to_string(),
+            };
+            assert_eq!(insert_feed_event_failures(conn, vec![&failure])?, 1);
+            // Failing again replaces the message rather than adding a row
+            assert_eq!(insert_feed_event_failures(conn, vec![&failure])?, 1);
+
+            let failures = feed_event_failures_for_kind(conn, kind)?
+                .into_iter()
+                .filter(|failure| failure.entity_id == entity_id)
+                .collect_vec();
+            assert_eq!(failures.len(), 1);
+            assert_eq!(failures[0].raw_data["text"], "event 1");
+
+            assert_eq!(
+                requeue_feed_event_failures(conn, &[&failures[0]], "test")?,
+                1
+            );
+
+            let processed = fep_dsl::feed_events_processed
+                .filter(fep_dsl::entity_id.eq(entity_id))
+                .select(fep_dsl::feed_event_index)
+                .get_results::<i32>(conn)?;
+            assert_eq!(processed, vec![0]);
+
+            // Only the requeued event's logs are cleared
+            let logged = vil_dsl::version_ingest_log
+                .filter(vil_dsl::entity_id.eq(entity_id))
+                .select(vil_dsl::feed_event_index)
+                .get_results::<Option<i32>>(conn)?;
+            assert_eq!(logged, vec![Some(0)]);
+
+            assert!(
+                feed_event_failures_for_kind(conn, kind)?
+                    .iter()
+                    .all(|failure| failure.entity_id != entity_id)
+            );
+
+            Ok::<_, QueryError>(())
+        });
+    }
+}
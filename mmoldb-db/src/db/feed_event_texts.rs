@@ -0,0 +1,159 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use diesel::{PgConnection, prelude::*, sql_query, sql_types::*};
+use itertools::Itertools;
+
+/// The text of one version of a feed event, to be made searchable
+pub struct NewFeedEventText<'a> {
+    pub kind: &'a str,
+    pub entity_id: &'a str,
+    pub feed_event_index: i32,
+    pub valid_from: DateTime<Utc>,
+    pub season: i32,
+    pub time: DateTime<Utc>,
+    pub text: &'a str,
+}
+
+/// Stores feed event texts, replacing the stored text of each event unless
+/// it came from a newer version than the one being inserted
+pub fn insert_feed_event_texts(
+    conn: &mut PgConnection,
+    texts: &[NewFeedEventText],
+) -> QueryResult<usize> {
+    if texts.is_empty() {
+        return Ok(0);
+    }
+
+    // A batch can have more than one version of the same event, and `on
+    // conflict do update` can't update a row twice in one statement. Only
+    // the newest version would survive anyway.
+    let texts = texts
+        .iter()
+        .sorted_by_key(|t| (t.kind, t.entity_id, t.feed_event_index, t.valid_from))
+        .rev()
+        .unique_by(|t| (t.kind, t.entity_id, t.feed_event_index))
+        .collect_vec();
+
+    sql_query(
+        "
+        insert into data.feed_event_texts
+            (kind, entity_id, feed_event_index, valid_from, season, time, text)
+        select * from unnest($1, $2, $3, $4, $5, $6, $7)
+        on conflict (kind, entity_id, feed_event_index) do update
+            set valid_from=excluded.valid_from,
+                season=excluded.season,
+                time=excluded.time,
+                text=excluded.text
+            where data.feed_event_texts.valid_from <= excluded.valid_from
+    ",
+    )
+    .bind::<Array<Text>, _>(texts.iter().map(|t| t.kind).collect_vec())
+    .bind::<Array<Text>, _>(texts.iter().map(|t| t.entity_id).collect_vec())
+    .bind::<Array<Int4>, _>(texts.iter().map(|t| t.feed_event_index).collect_vec())
+    .bind::<Array<Timestamptz>, _>(texts.iter().map(|t| t.valid_from).collect_vec())
+    .bind::<Array<Int4>, _>(texts.iter().map(|t| t.season).collect_vec())
+    .bind::<Array<Timestamptz>, _>(texts.iter().map(|t| t.time).collect_vec())
+    .bind::<Array<Text>, _>(texts.iter().map(|t| t.text).collect_vec())
+    .execute(conn)
+}
+
+/// A full-text search over feed event texts. Results are newest first.
+#[derive(Debug, Clone)]
+pub struct FeedEventTextSearch {
+    /// In Postgres' web search syntax, so `"quoted phrases"`, `or`, and
+    /// `-excluded` words all work
+    pub query: String,
+    /// `player_feed` or `team_feed`
+    pub kind: Option<String>,
+    /// The MMOLB id of the player or team whose feed to search
+    pub entity_id: Option<String>,
+    pub season: Option<i32>,
+    /// How many matches to skip, for paging
+    pub offset: i64,
+    pub limit: i64,
+}
+
+#[derive(Debug, QueryableByName)]
+pub struct DbFeedEventTextMatch {
+    #[diesel(sql_type = Text)]
+    pub kind: String,
+    #[diesel(sql_type = Text)]
+    pub entity_id: String,
+    #[diesel(sql_type = Int4)]
+    pub feed_event_index: i32,
+    #[diesel(sql_type = Int4)]
+    pub season: i32,
+    #[diesel(sql_type = Timestamptz)]
+    pub time: DateTime<Utc>,
+    #[diesel(sql_type = Text)]
+    pub text: String,
+}
+
+pub fn search_feed_event_texts(
+    conn: &mut PgConnection,
+    search: &FeedEventTextSearch,
+) -> QueryResult<Vec<DbFeedEventTextMatch>> {
+    sql_query(
+        "
+        select kind, entity_id, feed_event_index, season, time, text
+        from data.feed_event_texts
+        where text_search @@ websearch_to_tsquery('english', $1)
+            and ($2 is null or kind=$2)
+            and ($3 is null or entity_id=$3)
+            and ($4 is null or season=$4)
+        order by time desc, kind, entity_id, feed_event_index
+        offset $5
+        limit $6
+    ",
+    )
+    .bind::<Text, _>(search.query.as_str())
+    .bind::<Nullable<Text>, _>(search.kind.as_deref())
+    .bind::<Nullable<Text>, _>(search.entity_id.as_deref())
+    .bind::<Nullable<Int4>, _>(search.season)
+    .bind::<Int8, _>(search.offset)
+    .bind::<Int8, _>(search.limit)
+    .get_results(conn)
+}
+
+/// One of a player's attribute augments, with the feed event it came from
+#[derive(Debug, QueryableByName)]
+pub struct DbAttributeAugmentSource {
+    #[diesel(sql_type = Int4)]
+    pub feed_event_index: i32,
+    #[diesel(sql_type = Timestamp)]
+    pub time: NaiveDateTime,
+    #[diesel(sql_type = Int4)]
+    pub season: i32,
+    /// References `taxa.attribute`
+    #[diesel(sql_type = Int8)]
+    pub attribute: i64,
+    #[diesel(sql_type = Int4)]
+    pub value: i32,
+    /// Null if the feed event's text wasn't stored
+    #[diesel(sql_type = Nullable<Text>)]
+    pub text: Option<String>,
+}
+
+/// Every attribute augment `player_id` has received, optionally only in
+/// `season`, in feed order
+pub fn player_attribute_augment_sources(
+    conn: &mut PgConnection,
+    player_id: &str,
+    season: Option<i32>,
+) -> QueryResult<Vec<DbAttributeAugmentSource>> {
+    sql_query(
+        "
+        select paa.feed_event_index, paa.time, paa.season, paa.attribute, paa.value, fet.text
+        from data.player_attribute_augments paa
+        left join data.feed_event_texts fet
+            on fet.kind=paa.feed_event_kind
+            and fet.entity_id=paa.mmolb_player_id
+            and fet.feed_event_index=paa.feed_event_index
+        where paa.mmolb_player_id=$1
+            and ($2 is null or paa.season=$2)
+        order by paa.feed_event_index
+    ",
+    )
+    .bind::<Text, _>(player_id)
+    .bind::<Nullable<Int4>, _>(season)
+    .get_results(conn)
+}
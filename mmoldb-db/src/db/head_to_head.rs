@@ -0,0 +1,101 @@
+use chrono::{DateTime, Utc};
+use diesel::{PgConnection, prelude::*, sql_query, sql_types::*};
+use serde::Serialize;
+
+/// One finished game between two teams, from the point of view of the
+/// first team
+#[derive(Debug, Clone, QueryableByName, Serialize)]
+pub struct DbHeadToHeadGame {
+    #[diesel(sql_type = Text)]
+    pub mmolb_game_id: String,
+    #[diesel(sql_type = Int4)]
+    pub season: i32,
+    #[diesel(sql_type = Nullable<Int4>)]
+    pub day: Option<i32>,
+    #[diesel(sql_type = Nullable<Int4>)]
+    pub superstar_day: Option<i32>,
+    #[diesel(sql_type = Timestamptz)]
+    pub started_at: DateTime<Utc>,
+    #[diesel(sql_type = Bool)]
+    pub team_a_was_home: bool,
+    /// Team names and emojis as of this game
+    #[diesel(sql_type = Text)]
+    pub team_a_name: String,
+    #[diesel(sql_type = Text)]
+    pub team_a_emoji: String,
+    #[diesel(sql_type = Text)]
+    pub team_b_name: String,
+    #[diesel(sql_type = Text)]
+    pub team_b_emoji: String,
+    #[diesel(sql_type = Int4)]
+    pub team_a_score: i32,
+    #[diesel(sql_type = Int4)]
+    pub team_b_score: i32,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HeadToHead {
+    pub team_a_wins: usize,
+    pub team_b_wins: usize,
+    pub team_a_runs: i64,
+    pub team_b_runs: i64,
+    /// Oldest first
+    pub games: Vec<DbHeadToHeadGame>,
+}
+
+/// Every finished game between `team_a` and `team_b` (in either home/away
+/// arrangement), and the record between them. If `season` is given, only
+/// games from that season are included.
+pub fn head_to_head(
+    conn: &mut PgConnection,
+    team_a: &str,
+    team_b: &str,
+    season: Option<i32>,
+) -> QueryResult<HeadToHead> {
+    let games = sql_query(
+        "
+        select
+            g.mmolb_game_id,
+            g.season,
+            g.day,
+            g.superstar_day,
+            g.started_at,
+            g.home_team_mmolb_id=$1 as team_a_was_home,
+            case when g.home_team_mmolb_id=$1 then g.home_team_name else g.away_team_name end as team_a_name,
+            case when g.home_team_mmolb_id=$1 then g.home_team_emoji else g.away_team_emoji end as team_a_emoji,
+            case when g.home_team_mmolb_id=$1 then g.away_team_name else g.home_team_name end as team_b_name,
+            case when g.home_team_mmolb_id=$1 then g.away_team_emoji else g.home_team_emoji end as team_b_emoji,
+            case when g.home_team_mmolb_id=$1 then g.home_team_final_score else g.away_team_final_score end as team_a_score,
+            case when g.home_team_mmolb_id=$1 then g.away_team_final_score else g.home_team_final_score end as team_b_score
+        from data.games g
+        where (
+                (g.home_team_mmolb_id=$1 and g.away_team_mmolb_id=$2)
+                or (g.away_team_mmolb_id=$1 and g.home_team_mmolb_id=$2)
+            )
+            and not g.is_ongoing
+            and g.home_team_final_score is not null
+            and g.away_team_final_score is not null
+            and ($3 is null or g.season=$3)
+        order by g.started_at, g.mmolb_game_id
+    ",
+    )
+    .bind::<Text, _>(team_a)
+    .bind::<Text, _>(team_b)
+    .bind::<Nullable<Int4>, _>(season)
+    .get_results::<DbHeadToHeadGame>(conn)?;
+
+    let mut head_to_head = HeadToHead::default();
+    for game in &games {
+        head_to_head.team_a_runs += game.team_a_score as i64;
+        head_to_head.team_b_runs += game.team_b_score as i64;
+        // MMOLB games can't end in a tie
+        if game.team_a_score > game.team_b_score {
+            head_to_head.team_a_wins += 1;
+        } else {
+            head_to_head.team_b_wins += 1;
+        }
+    }
+    head_to_head.games = games;
+
+    Ok(head_to_head)
+}
@@ -0,0 +1,258 @@
+use diesel::{PgConnection, prelude::*, sql_query, sql_types::*};
+
+use crate::MmolbId;
+
+// Every query here only counts finished regular games. Superstar games are
+// played at a neutral site, so neither team is really at home.
+const SPLIT_GAMES: &str = "
+    split_games as (
+        select g.*
+        from data.games g
+        where g.season=$1
+            and not g.is_ongoing
+            and g.superstar_day is null
+            and g.home_team_final_score is not null
+            and g.away_team_final_score is not null
+    )";
+
+/// One team's record at home or on the road in a season
+#[derive(Debug, Clone, QueryableByName)]
+pub struct DbTeamHomeAwaySplit {
+    #[diesel(sql_type = Text)]
+    pub team_mmolb_id: String,
+    /// As of the team's latest game in the season
+    #[diesel(sql_type = Text)]
+    pub team_name: String,
+    #[diesel(sql_type = Text)]
+    pub team_emoji: String,
+    #[diesel(sql_type = Bool)]
+    pub is_home: bool,
+    #[diesel(sql_type = Int8)]
+    pub games: i64,
+    #[diesel(sql_type = Int8)]
+    pub wins: i64,
+    #[diesel(sql_type = Int8)]
+    pub runs_scored: i64,
+    #[diesel(sql_type = Int8)]
+    pub runs_allowed: i64,
+}
+
+/// Each team's home and road records in `season`, optionally only for
+/// `team`. Each game is counted exactly once for each side.
+pub fn team_home_away_splits(
+    conn: &mut PgConnection,
+    season: i32,
+    team: Option<&str>,
+) -> QueryResult<Vec<DbTeamHomeAwaySplit>> {
+    sql_query(format!(
+        "
+        with {SPLIT_GAMES},
+        team_games as (
+            select
+                g.home_team_mmolb_id as team_mmolb_id,
+                g.home_team_name as team_name,
+                g.home_team_emoji as team_emoji,
+                g.started_at,
+                true as is_home,
+                g.home_team_final_score as runs_scored,
+                g.away_team_final_score as runs_allowed
+            from split_games g
+            union all
+            select
+                g.away_team_mmolb_id,
+                g.away_team_name,
+                g.away_team_emoji,
+                g.started_at,
+                false,
+                g.away_team_final_score,
+                g.home_team_final_score
+            from split_games g
+        )
+        select
+            tg.team_mmolb_id,
+            (array_agg(tg.team_name order by tg.started_at desc))[1] as team_name,
+            (array_agg(tg.team_emoji order by tg.started_at desc))[1] as team_emoji,
+            tg.is_home,
+            count(1) as games,
+            -- MMOLB games can't end in a tie
+            count(1) filter (where tg.runs_scored > tg.runs_allowed) as wins,
+            sum(tg.runs_scored)::bigint as runs_scored,
+            sum(tg.runs_allowed)::bigint as runs_allowed
+        from team_games tg
+        where ($2 is null or tg.team_mmolb_id=$2)
+        group by tg.team_mmolb_id, tg.is_home
+        order by tg.team_mmolb_id, tg.is_home desc
+    "
+    ))
+    .bind::<Int4, _>(season)
+    .bind::<Nullable<Text>, _>(team)
+    .get_results(conn)
+}
+
+/// One player's batting at home or on the road in a season
+#[derive(Debug, Clone, QueryableByName)]
+pub struct DbPlayerHomeAwaySplit {
+    #[diesel(sql_type = Text)]
+    pub mmolb_player_id: String,
+    /// As of the player's latest plate appearance in the season
+    #[diesel(sql_type = Text)]
+    pub player_name: String,
+    #[diesel(sql_type = Bool)]
+    pub is_home: bool,
+    #[diesel(sql_type = Int8)]
+    pub games: i64,
+    #[diesel(sql_type = Int8)]
+    pub plate_appearances: i64,
+    #[diesel(sql_type = Int8)]
+    pub hits: i64,
+    #[diesel(sql_type = Int8)]
+    pub home_runs: i64,
+    #[diesel(sql_type = Int8)]
+    pub walks: i64,
+    #[diesel(sql_type = Int8)]
+    pub strikeouts: i64,
+}
+
+/// Each batter's home and road batting in `season`, optionally only for
+/// `player`. Batters are identified by their resolved MMOLB ids, like in
+/// `data.player_season_stats`. The home team bats in the bottom of the
+/// inning.
+pub fn player_home_away_splits(
+    conn: &mut PgConnection,
+    season: i32,
+    player: Option<&str>,
+) -> QueryResult<Vec<DbPlayerHomeAwaySplit>> {
+    let player = match player.map(str::parse::<MmolbId>).transpose() {
+        Ok(player) => player,
+        // No batter has an id that isn't an MMOLB id
+        Err(_) => return Ok(Vec::new()),
+    };
+
+    sql_query(format!(
+        "
+        with {SPLIT_GAMES}
+        select
+            data.mmolb_id_to_text(e.batter_mmolb_id) as mmolb_player_id,
+            (array_agg(e.batter_name order by g.started_at desc, e.game_event_index desc))[1]
+                as player_name,
+            not e.top_of_inning as is_home,
+            -- A batter has many plate appearances per game
+            count(distinct e.game_id) as games,
+            count(1) as plate_appearances,
+            count(1) filter (where et.is_hit) as hits,
+            count(1) filter (where et.name='HomeRun') as home_runs,
+            count(1) filter (where et.name='Walk') as walks,
+            count(1) filter (where et.is_strikeout) as strikeouts
+        from data.events e
+        join split_games g on g.id=e.game_id
+        join taxa.event_type et on et.id=e.event_type
+        where et.ends_plate_appearance
+            and e.batter_mmolb_id is not null
+            and ($2 is null or e.batter_mmolb_id=$2)
+        group by e.batter_mmolb_id, not e.top_of_inning
+        order by e.batter_mmolb_id, is_home desc
+    "
+    ))
+    .bind::<Int4, _>(season)
+    .bind::<Nullable<Bytea>, _>(player)
+    .get_results(conn)
+}
+
+/// How many runs are scored in one stadium compared to how many its home
+/// teams score and allow on the road
+#[derive(Debug, Clone, QueryableByName)]
+pub struct DbParkFactor {
+    /// Null if none of the games could be linked to a stadium in
+    /// `data.stadiums`
+    #[diesel(sql_type = Nullable<Text>)]
+    pub mmolb_stadium_id: Option<String>,
+    /// As of the latest game in the season
+    #[diesel(sql_type = Text)]
+    pub stadium_name: String,
+    #[diesel(sql_type = Int8)]
+    pub games: i64,
+    /// Both teams' runs
+    #[diesel(sql_type = Int8)]
+    pub runs: i64,
+    #[diesel(sql_type = Double)]
+    pub runs_per_game: f64,
+    /// Runs per game (by both teams) in the home teams' road games,
+    /// weighted by how many home games each one played here. Null if
+    /// they haven't played any road games.
+    #[diesel(sql_type = Nullable<Double>)]
+    pub road_runs_per_game: Option<f64>,
+    /// `runs_per_game / road_runs_per_game`. Above 1 means this stadium is
+    /// easier to score in than average.
+    #[diesel(sql_type = Nullable<Double>)]
+    pub park_factor: Option<f64>,
+}
+
+/// The run environment of every stadium that hosted a game in `season`,
+/// highest park factor first. Stadiums are identified by their MMOLB id
+/// where the game has been linked to one, and by name otherwise.
+pub fn park_factors(conn: &mut PgConnection, season: i32) -> QueryResult<Vec<DbParkFactor>> {
+    sql_query(format!(
+        "
+        with {SPLIT_GAMES},
+        park_games as (
+            select
+                coalesce(g.mmolb_stadium_id, g.stadium_name) as stadium_key,
+                g.mmolb_stadium_id,
+                g.stadium_name,
+                g.home_team_mmolb_id,
+                g.started_at,
+                g.home_team_final_score + g.away_team_final_score as runs
+            from split_games g
+            where g.mmolb_stadium_id is not null or g.stadium_name is not null
+        ),
+        parks as (
+            select
+                pg.stadium_key,
+                max(pg.mmolb_stadium_id) as mmolb_stadium_id,
+                (array_agg(pg.stadium_name order by pg.started_at desc)
+                    filter (where pg.stadium_name is not null))[1] as stadium_name,
+                count(1) as games,
+                sum(pg.runs)::bigint as runs
+            from park_games pg
+            group by pg.stadium_key
+        ),
+        -- Stadiums can change hands, so there can be more than one home team
+        park_home_teams as (
+            select pg.stadium_key, pg.home_team_mmolb_id, count(1) as home_games
+            from park_games pg
+            group by pg.stadium_key, pg.home_team_mmolb_id
+        ),
+        road_games as (
+            select
+                g.away_team_mmolb_id as team_mmolb_id,
+                count(1) as games,
+                sum(g.home_team_final_score + g.away_team_final_score) as runs
+            from split_games g
+            group by g.away_team_mmolb_id
+        ),
+        road_rates as (
+            select
+                pht.stadium_key,
+                sum(pht.home_games * rg.runs::float8 / rg.games) / sum(pht.home_games)
+                    as road_runs_per_game
+            from park_home_teams pht
+            join road_games rg on rg.team_mmolb_id=pht.home_team_mmolb_id
+            group by pht.stadium_key
+        )
+        select
+            p.mmolb_stadium_id,
+            -- Linked games should always have a name, but just in case
+            coalesce(p.stadium_name, p.stadium_key) as stadium_name,
+            p.games,
+            p.runs,
+            p.runs::float8 / p.games as runs_per_game,
+            rr.road_runs_per_game,
+            (p.runs::float8 / p.games) / nullif(rr.road_runs_per_game, 0) as park_factor
+        from parks p
+        left join road_rates rr on rr.stadium_key=p.stadium_key
+        order by park_factor desc nulls last, stadium_name
+    "
+    ))
+    .bind::<Int4, _>(season)
+    .get_results(conn)
+}
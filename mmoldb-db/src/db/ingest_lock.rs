@@ -0,0 +1,98 @@
+use chrono::{DateTime, Utc};
+use diesel::{PgConnection, prelude::*, sql_query, sql_types::*};
+
+/// The advisory lock key that only one ingest process may hold at a time.
+/// It's arbitrary but must never change, or an old and a new ingest could
+/// run at the same time.
+const INGEST_ADVISORY_LOCK_KEY: i64 = 0x6d6d6f6c6462; // "mmoldb" in ASCII
+
+/// The lock key for a source, given the main key as $1 and the source (or
+/// null for main MMOLB) as $2
+const INGEST_LOCK_KEY_SQL: &str =
+    "case when $2 is null then $1 else $1 # hashtextextended($2, 0) end";
+
+/// Tries to take the ingest lock for `source` (None for main MMOLB) without
+/// waiting. Returns whether it was taken. Each source has its own lock,
+/// since ingests of different sources never write the same rows. The lock
+/// belongs to the session, so it's held until `conn` is closed. Use a
+/// connection that isn't shared with a pool.
+pub fn try_acquire_ingest_lock(conn: &mut PgConnection, source: Option<&str>) -> QueryResult<bool> {
+    #[derive(QueryableByName)]
+    struct LockResult {
+        #[diesel(sql_type = Bool)]
+        acquired: bool,
+    }
+
+    sql_query(format!(
+        "select pg_try_advisory_lock({INGEST_LOCK_KEY_SQL}) as acquired"
+    ))
+    .bind::<BigInt, _>(INGEST_ADVISORY_LOCK_KEY)
+    .bind::<Nullable<Text>, _>(source)
+    .get_result::<LockResult>(conn)
+    .map(|r| r.acquired)
+}
+
+/// Names the session, so [`ingest_lock_holder`] can say who holds the lock
+pub fn set_application_name(conn: &mut PgConnection, name: &str) -> QueryResult<()> {
+    sql_query("select set_config('application_name', $1, false)")
+        .bind::<Text, _>(name)
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Runs a trivial query on the connection that holds the ingest lock. Every
+/// query updates the session's `state_change`, which is how
+/// [`ingest_lock_holder`] tells a live holder from a dead one.
+pub fn ingest_lock_heartbeat(conn: &mut PgConnection) -> QueryResult<()> {
+    sql_query("select 1").execute(conn)?;
+    Ok(())
+}
+
+#[derive(Debug, QueryableByName)]
+pub struct IngestLockHolder {
+    #[diesel(sql_type = Integer)]
+    pub pid: i32,
+    #[diesel(sql_type = Text)]
+    pub application_name: String,
+    #[diesel(sql_type = Nullable<Text>)]
+    pub client_addr: Option<String>,
+    #[diesel(sql_type = Nullable<Timestamptz>)]
+    pub backend_start: Option<DateTime<Utc>>,
+    /// Seconds since the session last ran a query, measured by the
+    /// database's clock
+    #[diesel(sql_type = Nullable<BigInt>)]
+    pub idle_seconds: Option<i64>,
+}
+
+/// The session that holds the ingest lock for `source`, if any session
+/// does. Sessions belonging to another database user are visible, but
+/// Postgres hides their details unless this user has `pg_read_all_stats`.
+pub fn ingest_lock_holder(
+    conn: &mut PgConnection,
+    source: Option<&str>,
+) -> QueryResult<Option<IngestLockHolder>> {
+    // Advisory locks on a bigint key are stored with the high half of the
+    // key in classid and the low half in objid
+    sql_query(format!(
+        "
+        select
+            a.pid,
+            coalesce(a.application_name, '') as application_name,
+            host(a.client_addr) as client_addr,
+            a.backend_start,
+            extract(epoch from now() - a.state_change)::bigint as idle_seconds
+        from pg_locks l
+        join pg_stat_activity a on a.pid = l.pid
+        where l.locktype = 'advisory'
+            and l.granted
+            and l.objsubid = 1
+            and l.database = (select oid from pg_database where datname = current_database())
+            and ((l.classid::bigint << 32) | l.objid::bigint) = {INGEST_LOCK_KEY_SQL}
+        limit 1
+    "
+    ))
+    .bind::<BigInt, _>(INGEST_ADVISORY_LOCK_KEY)
+    .bind::<Nullable<Text>, _>(source)
+    .get_result::<IngestLockHolder>(conn)
+    .optional()
+}
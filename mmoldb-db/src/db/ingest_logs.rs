@@ -0,0 +1,225 @@
+use diesel::{PgConnection, prelude::*, sql_query, sql_types::*};
+use serde::Serialize;
+
+use crate::db::{Keyset, KeysetPage, page_by_keyset};
+use crate::models::{DbEventIngestLog, DbVersionIngestLog};
+
+/// Names of the ingest log levels, indexed by level number. Lower numbers
+/// are more severe.
+const LOG_LEVEL_NAMES: [&str; 6] = ["critical", "error", "warning", "info", "debug", "trace"];
+
+pub fn log_level_name(log_level: i32) -> &'static str {
+    usize::try_from(log_level)
+        .ok()
+        .and_then(|level| LOG_LEVEL_NAMES.get(level))
+        .copied()
+        .unwrap_or("unknown")
+}
+
+pub fn log_level_from_name(name: &str) -> Option<i32> {
+    LOG_LEVEL_NAMES
+        .iter()
+        .position(|level_name| level_name.eq_ignore_ascii_case(name))
+        .map(|level| level as i32)
+}
+
+/// Filters for listing ingest logs. Results are ordered by id and paged
+/// with `after_id`.
+#[derive(Debug, Clone)]
+pub struct IngestLogFilter {
+    /// Only logs at this level or more severe
+    pub max_level: i32,
+    /// Only logs for this kind of entity. Event logs always have kind
+    /// `"game"`.
+    pub kind: Option<String>,
+    /// Only logs for the entity with this MMOLB id
+    pub entity_id: Option<String>,
+    /// Only logs with ids greater than this
+    pub after_id: Option<i64>,
+    /// The page size
+    pub limit: i64,
+}
+
+/// An event ingest log along with the MMOLB id of its game
+#[derive(QueryableByName)]
+pub struct EventIngestLogWithGame {
+    #[diesel(embed)]
+    pub log: DbEventIngestLog,
+    #[diesel(sql_type = Text)]
+    pub mmolb_game_id: String,
+}
+
+pub fn page_of_event_ingest_logs(
+    conn: &mut PgConnection,
+    filter: &IngestLogFilter,
+) -> QueryResult<KeysetPage<EventIngestLogWithGame, i64>> {
+    if filter.kind.as_deref().is_some_and(|kind| kind != "game") {
+        return Ok(KeysetPage {
+            items: Vec::new(),
+            next_page: None,
+            previous_page: None,
+        });
+    }
+
+    page_by_keyset(
+        conn,
+        Keyset(&["l.id"]),
+        filter.limit as usize,
+        filter.after_id,
+        || {
+            let query = sql_query(
+                "
+                select l.id, l.game_id, l.game_event_index, l.log_index, l.log_level,
+                    l.log_text, l.error_code, g.mmolb_game_id
+                from info.event_ingest_log l
+                join data.games g on g.id=l.game_id
+                where l.log_level <= $1
+                    and ($2 is null or g.mmolb_game_id=$2)
+            ",
+            )
+            .into_boxed()
+            .bind::<Int4, _>(filter.max_level)
+            .bind::<Nullable<Text>, _>(filter.entity_id.clone());
+            (query, 3)
+        },
+        |log| log.log.id,
+    )
+}
+
+pub fn page_of_version_ingest_logs(
+    conn: &mut PgConnection,
+    filter: &IngestLogFilter,
+) -> QueryResult<KeysetPage<DbVersionIngestLog, i64>> {
+    page_by_keyset(
+        conn,
+        Keyset(&["l.id"]),
+        filter.limit as usize,
+        filter.after_id,
+        || {
+            let query = sql_query(
+                "
+                select l.id, l.kind, l.entity_id, l.valid_from, l.log_index, l.log_level,
+                    l.log_text, l.error_code
+                from info.version_ingest_log l
+                where l.log_level <= $1
+                    and ($2 is null or l.kind=$2)
+                    and ($3 is null or l.entity_id=$3)
+            ",
+            )
+            .into_boxed()
+            .bind::<Int4, _>(filter.max_level)
+            .bind::<Nullable<Text>, _>(filter.kind.clone())
+            .bind::<Nullable<Text>, _>(filter.entity_id.clone());
+            (query, 4)
+        },
+        |log| log.id,
+    )
+}
+
+/// The number of ingest logs with one error code
+#[derive(Debug, Clone, QueryableByName, Serialize)]
+pub struct IngestErrorCodeCount {
+    #[diesel(sql_type = Text)]
+    pub name: String,
+    #[diesel(sql_type = Text)]
+    pub display_name: String,
+    #[diesel(sql_type = Nullable<Text>)]
+    pub description: Option<String>,
+    #[diesel(sql_type = Int8)]
+    pub num_event_logs: i64,
+    #[diesel(sql_type = Int8)]
+    pub num_version_logs: i64,
+}
+
+/// Counts of event and version ingest logs for every error code, in taxa
+/// order. Uncategorized logs aren't counted.
+pub fn ingest_error_code_counts(conn: &mut PgConnection) -> QueryResult<Vec<IngestErrorCodeCount>> {
+    sql_query(
+        "
+        select
+            c.name,
+            c.display_name,
+            c.description,
+            (select count(*) from info.event_ingest_log l where l.error_code=c.id) as num_event_logs,
+            (select count(*) from info.version_ingest_log l where l.error_code=c.id) as num_version_logs
+        from taxa.ingest_error_code c
+        order by c.id
+    ",
+    )
+    .get_results(conn)
+}
+
+/// The number of ingest logs with one error code in one season
+#[derive(Debug, Clone, QueryableByName, Serialize)]
+pub struct IngestErrorCodeSeasonCount {
+    #[diesel(sql_type = Text)]
+    pub name: String,
+    #[diesel(sql_type = Text)]
+    pub display_name: String,
+    #[diesel(sql_type = Nullable<Text>)]
+    pub description: Option<String>,
+    #[diesel(sql_type = Nullable<Int4>)]
+    pub season: Option<i32>,
+    #[diesel(sql_type = Int8)]
+    pub num_event_logs: i64,
+    #[diesel(sql_type = Int8)]
+    pub num_version_logs: i64,
+}
+
+/// Counts of event and version ingest logs for every error code and season
+/// that has any, in taxa order and then latest season first. Event logs
+/// take their game's season. Version logs take the season that had started
+/// when the version was fetched, or `None` if it's from before the first
+/// known season.
+pub fn ingest_error_code_counts_by_season(
+    conn: &mut PgConnection,
+) -> QueryResult<Vec<IngestErrorCodeSeasonCount>> {
+    sql_query(
+        "
+        with counts as (
+            select g.season, l.error_code, count(*) as num_event_logs, 0::int8 as num_version_logs
+            from info.event_ingest_log l
+            join data.games g on g.id=l.game_id
+            where l.error_code is not null
+            group by g.season, l.error_code
+            union all
+            select s.season, l.error_code, 0::int8 as num_event_logs, count(*) as num_version_logs
+            from info.version_ingest_log l
+            left join lateral (
+                select max(p.season) as season
+                from data.season_phases p
+                where p.started_at <= l.valid_from
+            ) s on true
+            where l.error_code is not null
+            group by s.season, l.error_code
+        )
+        select
+            c.name,
+            c.display_name,
+            c.description,
+            counts.season,
+            sum(counts.num_event_logs)::int8 as num_event_logs,
+            sum(counts.num_version_logs)::int8 as num_version_logs
+        from counts
+        join taxa.ingest_error_code c on c.id=counts.error_code
+        group by c.id, counts.season
+        order by c.id, counts.season desc nulls last
+    ",
+    )
+    .get_results(conn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_level_names_round_trip() {
+        for level in 0..6 {
+            assert_eq!(log_level_from_name(log_level_name(level)), Some(level));
+        }
+        assert_eq!(log_level_from_name("Warning"), Some(2));
+        assert_eq!(log_level_from_name("loud"), None);
+        assert_eq!(log_level_name(17), "unknown");
+    }
+}
@@ -0,0 +1,154 @@
+use chrono::{DateTime, Utc};
+use diesel::{PgConnection, prelude::*, sql_query, sql_types::*};
+use serde::{Deserialize, Serialize};
+
+/// The Postgres NOTIFY channel ingest progress is published on. This has to
+/// be a valid unquoted identifier because LISTEN can't take a bind
+/// parameter.
+pub const INGEST_PROGRESS_CHANNEL: &str = "mmoldb_ingest_progress";
+
+/// pg_notify rejects payloads of 8000 bytes or more
+const MAX_PAYLOAD_BYTES: usize = 7999;
+
+/// Appended to an error that was cut short to fit in a notification
+const TRUNCATED_MARKER: &str = " [truncated]";
+
+/// One step of ingest progress, as published by mmoldb-ingest
+#[derive(Debug, Clone, Serialize, Deserialize, strum::IntoStaticStr)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum IngestProgressEvent {
+    /// A fetch or processing pass for `kind` began
+    StageStarted {
+        kind: String,
+        stage: String,
+        at: DateTime<Utc>,
+    },
+    /// A fetch or processing pass for `kind` ended. `error` is set if it
+    /// ended because of an error.
+    StageFinished {
+        kind: String,
+        stage: String,
+        at: DateTime<Utc>,
+        error: Option<String>,
+    },
+    /// A batch of `kind` was written to the database. For games, the row
+    /// counts are counts of games.
+    BatchProcessed {
+        kind: String,
+        num_items: usize,
+        num_rows: usize,
+        num_rows_inserted: usize,
+        /// The latest valid_from in the batch, to show how far behind the
+        /// ingest is
+        latest_valid_from: Option<DateTime<Utc>>,
+        at: DateTime<Utc>,
+    },
+}
+
+impl IngestProgressEvent {
+    /// Name of this event type, for use as the server-sent event name
+    pub fn name(&self) -> &'static str {
+        self.into()
+    }
+}
+
+/// Publishes `event` to everyone listening on [`INGEST_PROGRESS_CHANNEL`].
+/// Notifications are delivered when the current transaction commits, so
+/// `conn` should be a connection that's never in a transaction, and in
+/// particular not the one a batch is being written on. A stage's error is
+/// cut short if it would make the notification too long for pg_notify.
+pub fn publish_ingest_progress(
+    conn: &mut PgConnection,
+    event: &IngestProgressEvent,
+) -> QueryResult<()> {
+    sql_query("select pg_notify($1, $2)")
+        .bind::<Text, _>(INGEST_PROGRESS_CHANNEL)
+        .bind::<Text, _>(ingest_progress_payload(event))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+fn serialize_ingest_progress(event: &IngestProgressEvent) -> String {
+    // Serializing this enum can't fail
+    serde_json::to_string(event).expect("Ingest progress event should serialize")
+}
+
+/// Serializes `event`, truncating its error until it fits in
+/// [`MAX_PAYLOAD_BYTES`]. Escaping can make the serialized error longer
+/// than the error itself, so this may take more than one cut.
+fn ingest_progress_payload(event: &IngestProgressEvent) -> String {
+    let mut payload = serialize_ingest_progress(event);
+    if payload.len() <= MAX_PAYLOAD_BYTES {
+        return payload;
+    }
+
+    let mut event = event.clone();
+    while payload.len() > MAX_PAYLOAD_BYTES {
+        // Only a stage's error can get long enough to matter. Once there's
+        // nothing left of it, give up and let pg_notify report the error.
+        let IngestProgressEvent::StageFinished {
+            error: Some(error), ..
+        } = &mut event
+        else {
+            break;
+        };
+        if error.len() <= TRUNCATED_MARKER.len() {
+            break;
+        }
+
+        let excess = payload.len() - MAX_PAYLOAD_BYTES;
+        let mut keep = error.len().saturating_sub(excess + TRUNCATED_MARKER.len());
+        while !error.is_char_boundary(keep) {
+            keep -= 1;
+        }
+        error.truncate(keep);
+        error.push_str(TRUNCATED_MARKER);
+        payload = serialize_ingest_progress(&event);
+    }
+
+    payload
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stage_finished(error: &str) -> IngestProgressEvent {
+        IngestProgressEvent::StageFinished {
+            kind: "player".to_string(),
+            stage: "processing".to_string(),
+            at: Utc::now(),
+            error: Some(error.to_string()),
+        }
+    }
+
+    #[test]
+    fn short_payloads_are_unchanged() {
+        let event = stage_finished("Couldn't connect to Chron");
+        assert_eq!(
+            ingest_progress_payload(&event),
+            serialize_ingest_progress(&event),
+        );
+    }
+
+    #[test]
+    fn long_errors_are_truncated_to_fit() {
+        // Quotes are escaped and the multi-byte characters can't be split,
+        // so the serialized error is longer than it looks
+        let error = "\"é\" ".repeat(5000);
+        let payload = ingest_progress_payload(&stage_finished(&error));
+        assert!(payload.len() <= MAX_PAYLOAD_BYTES);
+
+        let IngestProgressEvent::StageFinished {
+            error: Some(truncated),
+            ..
+        } = serde_json::from_str(&payload).unwrap()
+        else {
+            panic!("the payload should still be a stage_finished event with an error");
+        };
+        assert!(truncated.ends_with(TRUNCATED_MARKER));
+        assert!(error.starts_with(truncated.trim_end_matches(TRUNCATED_MARKER)));
+    }
+}
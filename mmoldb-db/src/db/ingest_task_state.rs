@@ -0,0 +1,116 @@
+use chrono::Utc;
+use diesel::{PgConnection, prelude::*};
+
+/// The states an ingest stage can be in. These are stored as text in
+/// `info.ingest_task_state`, so the names must never change.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, strum::IntoStaticStr, strum::EnumString)]
+#[strum(serialize_all = "snake_case")]
+pub enum IngestTaskState {
+    Running,
+    /// Exited because shutdown was requested
+    Stopped,
+    /// Exited with an error
+    Failed,
+    /// Was still running when its ingest process died. Only ever set by
+    /// [`abort_orphaned_ingest_stages`].
+    Aborted,
+}
+
+/// Records that `stage` was just launched
+pub fn record_ingest_stage_started(conn: &mut PgConnection, stage: &str) -> QueryResult<()> {
+    use crate::info_schema::info::ingest_task_state::dsl as its_dsl;
+
+    let now = Utc::now();
+    let state: &str = IngestTaskState::Running.into();
+    diesel::insert_into(its_dsl::ingest_task_state)
+        .values((
+            its_dsl::stage.eq(stage),
+            its_dsl::state.eq(state),
+            its_dsl::started_at.eq(now),
+            its_dsl::updated_at.eq(now),
+            its_dsl::error.eq(None::<&str>),
+        ))
+        .on_conflict(its_dsl::stage)
+        .do_update()
+        .set((
+            its_dsl::state.eq(state),
+            its_dsl::started_at.eq(now),
+            its_dsl::updated_at.eq(now),
+            its_dsl::error.eq(None::<&str>),
+        ))
+        .execute(conn)
+        .map(|_| ())
+}
+
+/// Records that `stage` exited, with `error` if it failed
+pub fn record_ingest_stage_exited(
+    conn: &mut PgConnection,
+    stage: &str,
+    error: Option<&str>,
+) -> QueryResult<()> {
+    use crate::info_schema::info::ingest_task_state::dsl as its_dsl;
+
+    let state: &str = match error {
+        None => IngestTaskState::Stopped.into(),
+        Some(_) => IngestTaskState::Failed.into(),
+    };
+    diesel::update(its_dsl::ingest_task_state.filter(its_dsl::stage.eq(stage)))
+        .set((
+            its_dsl::state.eq(state),
+            its_dsl::updated_at.eq(Utc::now()),
+            its_dsl::error.eq(error),
+        ))
+        .execute(conn)
+        .map(|_| ())
+}
+
+/// Marks every stage that's recorded as running as aborted, and returns
+/// their names. Only call this while holding the ingest lock: then no other
+/// ingest can be running, so any stage still marked as running belonged to
+/// an ingest that died without recording how its stages exited.
+pub fn abort_orphaned_ingest_stages(conn: &mut PgConnection) -> QueryResult<Vec<String>> {
+    use crate::info_schema::info::ingest_task_state::dsl as its_dsl;
+
+    let running: &str = IngestTaskState::Running.into();
+    let aborted: &str = IngestTaskState::Aborted.into();
+    diesel::update(its_dsl::ingest_task_state.filter(its_dsl::state.eq(running)))
+        .set((
+            its_dsl::state.eq(aborted),
+            its_dsl::updated_at.eq(Utc::now()),
+            its_dsl::error.eq("The ingest exited without recording that this stage stopped"),
+        ))
+        .returning(its_dsl::stage)
+        .get_results(conn)
+}
+
+/// Every stage that has an aborted run that hasn't been relaunched since
+pub fn aborted_ingest_stages(conn: &mut PgConnection) -> QueryResult<Vec<String>> {
+    use crate::info_schema::info::ingest_task_state::dsl as its_dsl;
+
+    let aborted: &str = IngestTaskState::Aborted.into();
+    its_dsl::ingest_task_state
+        .filter(its_dsl::state.eq(aborted))
+        .select(its_dsl::stage)
+        .order_by(its_dsl::stage)
+        .get_results(conn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn states_round_trip_through_their_stored_names() {
+        for state in [
+            IngestTaskState::Running,
+            IngestTaskState::Stopped,
+            IngestTaskState::Failed,
+            IngestTaskState::Aborted,
+        ] {
+            let name: &str = state.into();
+            assert_eq!(name.parse::<IngestTaskState>(), Ok(state));
+        }
+        let aborted: &str = IngestTaskState::Aborted.into();
+        assert_eq!(aborted, "aborted");
+    }
+}
@@ -0,0 +1,216 @@
+//! Keyset pagination for list queries written as raw SQL. Pages are
+//! identified by the cursor of the last row on the previous page, so a page
+//! is one indexed range scan no matter how deep into the list it is.
+
+use diesel::pg::Pg;
+use diesel::query_builder::{BoxedSqlQuery, SqlQuery};
+use diesel::query_source::QueryableByName;
+use diesel::{PgConnection, prelude::*, sql_types::*};
+use itertools::Itertools;
+
+/// A value that identifies a row's position in a list. Tuples of cursors
+/// page over several columns, compared in order.
+pub trait KeysetCursor: Clone + 'static {
+    /// How many bind placeholders [`KeysetCursor::bind`] fills
+    const NUM_COLUMNS: usize;
+
+    fn bind(
+        self,
+        query: BoxedSqlQuery<'static, Pg, SqlQuery>,
+    ) -> BoxedSqlQuery<'static, Pg, SqlQuery>;
+}
+
+impl KeysetCursor for String {
+    const NUM_COLUMNS: usize = 1;
+
+    fn bind(
+        self,
+        query: BoxedSqlQuery<'static, Pg, SqlQuery>,
+    ) -> BoxedSqlQuery<'static, Pg, SqlQuery> {
+        query.bind::<Text, _>(self)
+    }
+}
+
+impl KeysetCursor for i64 {
+    const NUM_COLUMNS: usize = 1;
+
+    fn bind(
+        self,
+        query: BoxedSqlQuery<'static, Pg, SqlQuery>,
+    ) -> BoxedSqlQuery<'static, Pg, SqlQuery> {
+        query.bind::<Int8, _>(self)
+    }
+}
+
+impl<A: KeysetCursor, B: KeysetCursor> KeysetCursor for (A, B) {
+    const NUM_COLUMNS: usize = A::NUM_COLUMNS + B::NUM_COLUMNS;
+
+    fn bind(
+        self,
+        query: BoxedSqlQuery<'static, Pg, SqlQuery>,
+    ) -> BoxedSqlQuery<'static, Pg, SqlQuery> {
+        self.1.bind(self.0.bind(query))
+    }
+}
+
+/// The columns a list is ordered and paged by, as SQL expressions (e.g.
+/// `g.mmolb_game_id`). They must be unique together, or rows that tie will
+/// be skipped.
+#[derive(Debug, Copy, Clone)]
+pub struct Keyset(pub &'static [&'static str]);
+
+impl Keyset {
+    /// `(a, b) <op> ($first, $first+1)`
+    fn compare(&self, op: &str, first_placeholder: usize) -> String {
+        let placeholders = (first_placeholder..first_placeholder + self.0.len())
+            .map(|n| format!("${n}"))
+            .join(", ");
+        format!("({}) {op} ({placeholders})", self.0.join(", "))
+    }
+
+    fn order_by(&self, direction: &str) -> String {
+        self.0
+            .iter()
+            .map(|column| format!("{column} {direction}"))
+            .join(", ")
+    }
+}
+
+pub struct KeysetPage<T, C> {
+    pub items: Vec<T>,
+    /// The cursor to pass as `after` to get the next page. `None` on the
+    /// last page.
+    pub next_page: Option<C>,
+    // Nested option: The outer layer is whether there is a previous page. The inner
+    // layer is whether that previous page is the first page, whose cursor is None
+    pub previous_page: Option<Option<C>>,
+}
+
+impl<T, C> KeysetPage<T, C> {
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> KeysetPage<U, C> {
+        KeysetPage {
+            items: self.items.into_iter().map(f).collect(),
+            next_page: self.next_page,
+            previous_page: self.previous_page,
+        }
+    }
+}
+
+/// Gets the page of up to `page_size` rows that comes after `after`, or the
+/// first page if `after` is `None`. `make_query` must return a query that
+/// left off in the middle of a `where`, along with the number of the next
+/// unused bind placeholder (e.g. 1 if nothing has been bound yet).
+/// `cursor_of` gets a row's cursor, which must match `keyset`.
+pub fn page_by_keyset<T, C>(
+    conn: &mut PgConnection,
+    keyset: Keyset,
+    page_size: usize,
+    after: Option<C>,
+    make_query: impl Fn() -> (BoxedSqlQuery<'static, Pg, SqlQuery>, usize),
+    cursor_of: impl Fn(&T) -> C,
+) -> QueryResult<KeysetPage<T, C>>
+where
+    T: QueryableByName<Pg>,
+    C: KeysetCursor,
+{
+    assert_eq!(
+        keyset.0.len(),
+        C::NUM_COLUMNS,
+        "Keyset and cursor must have the same number of columns",
+    );
+
+    // Get N + 1 rows so we know if this is the last page or not
+    let (mut items, previous_page) = if let Some(after) = after {
+        let (query, n) = make_query();
+        let items = after
+            .clone()
+            .bind(query.sql(format!(
+                "
+            and {}
+            order by {}
+            limit ${}
+        ",
+                keyset.compare(">", n),
+                keyset.order_by("asc"),
+                n + C::NUM_COLUMNS,
+            )))
+            .bind::<Integer, _>(page_size as i32 + 1)
+            .get_results::<T>(conn)?;
+
+        // Previous page is the one page_size rows before this
+        // Get N + 1 rows so we know if this is the first page or not
+        let (query, n) = make_query();
+        let preceding_pages = after
+            .bind(query.sql(format!(
+                "
+            and {}
+            order by {}
+            limit ${}
+        ",
+                keyset.compare("<=", n),
+                keyset.order_by("desc"),
+                n + C::NUM_COLUMNS,
+            )))
+            .bind::<Integer, _>(page_size as i32 + 1)
+            .get_results::<T>(conn)?;
+
+        let preceding_page = if preceding_pages.len() > page_size {
+            // Then the preceding page is not the first page
+            Some(preceding_pages.last().map(&cursor_of))
+        } else {
+            // Then the preceding page is the first page
+            Some(None)
+        };
+
+        (items, preceding_page)
+    } else {
+        let (query, n) = make_query();
+        let items = query
+            .sql(format!(
+                "
+            order by {}
+            limit ${n}
+        ",
+                keyset.order_by("asc"),
+            ))
+            .bind::<Integer, _>(page_size as i32 + 1)
+            .get_results::<T>(conn)?;
+
+        // No cursor => this is the first page => there is no previous page
+        (items, None)
+    };
+
+    let next_page = if items.len() > page_size {
+        // Then this is not the last page
+        items.truncate(page_size);
+        // The cursor is the last row that is actually shown
+        items.last().map(&cursor_of)
+    } else {
+        // Then this is the last page
+        None
+    };
+
+    Ok(KeysetPage {
+        items,
+        next_page,
+        previous_page,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_every_column_in_order() {
+        let keyset = Keyset(&["g.season", "g.mmolb_game_id"]);
+        assert_eq!(
+            keyset.compare(">", 3),
+            "(g.season, g.mmolb_game_id) > ($3, $4)"
+        );
+        assert_eq!(
+            keyset.order_by("desc"),
+            "g.season desc, g.mmolb_game_id desc"
+        );
+    }
+}
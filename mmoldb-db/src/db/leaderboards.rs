@@ -0,0 +1,211 @@
+use diesel::{PgConnection, prelude::*, sql_query, sql_types::*};
+
+/// Pitchers need at least this many outs recorded, per game their team
+/// could have played in the seasons counted, to appear on the ERA
+/// leaderboard. Without a minimum, the leaders are pitchers who got one out
+/// and never pitched again. Scaling it by games played means the minimum
+/// is reachable partway through a season.
+pub const ERA_QUALIFYING_OUTS_PER_TEAM_GAME: i64 = 3;
+
+#[derive(
+    Debug, Copy, Clone, PartialEq, Eq, Hash, strum::EnumString, strum::Display, strum::EnumIter,
+)]
+#[strum(serialize_all = "snake_case")]
+pub enum LeaderboardStat {
+    HomeRuns,
+    Hits,
+    Walks,
+    StolenBases,
+    Strikeouts,
+    EarnedRunAverage,
+    FastestPitch,
+}
+
+impl LeaderboardStat {
+    pub fn title(self) -> &'static str {
+        match self {
+            LeaderboardStat::HomeRuns => "Home runs",
+            LeaderboardStat::Hits => "Hits",
+            LeaderboardStat::Walks => "Walks",
+            LeaderboardStat::StolenBases => "Stolen bases",
+            LeaderboardStat::Strikeouts => "Strikeouts (pitching)",
+            LeaderboardStat::EarnedRunAverage => "Earned run average",
+            LeaderboardStat::FastestPitch => "Fastest pitch",
+        }
+    }
+
+    /// Whether a lower value is better
+    pub fn is_ascending(self) -> bool {
+        matches!(self, LeaderboardStat::EarnedRunAverage)
+    }
+
+    // Aggregates over the rows of data.player_season_stats being counted
+    fn value_sql(self) -> &'static str {
+        match self {
+            LeaderboardStat::HomeRuns => "sum(home_runs)",
+            LeaderboardStat::Hits => "sum(hits)",
+            LeaderboardStat::Walks => "sum(walks)",
+            LeaderboardStat::StolenBases => "sum(stolen_bases)",
+            LeaderboardStat::Strikeouts => "sum(strikeouts)",
+            LeaderboardStat::EarnedRunAverage => {
+                "27.0 * sum(earned_runs) / nullif(sum(outs_recorded), 0)"
+            }
+            LeaderboardStat::FastestPitch => "max(max_pitch_speed)",
+        }
+    }
+
+    // `num_team_games` is the most games any team has finished in each
+    // season being counted, joined onto each of the player's season rows
+    fn qualifier_sql(self) -> String {
+        match self {
+            LeaderboardStat::EarnedRunAverage => format!(
+                "sum(outs_recorded) >= \
+                {ERA_QUALIFYING_OUTS_PER_TEAM_GAME} * coalesce(sum(tg.num_team_games), 0)"
+            ),
+            LeaderboardStat::FastestPitch => "max(max_pitch_speed) is not null".to_string(),
+            other => format!("{} > 0", other.value_sql()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, QueryableByName)]
+pub struct DbLeaderboardEntry {
+    #[diesel(sql_type = Text)]
+    pub mmolb_player_id: String,
+    /// The player's name as of the latest season counted
+    #[diesel(sql_type = Text)]
+    pub player_name: String,
+    #[diesel(sql_type = Float8)]
+    pub value: f64,
+}
+
+/// The top `limit` players for `stat` in `season`, or over all seasons if
+/// it's None. Ties are broken by player id so the order is stable.
+pub fn leaderboard(
+    conn: &mut PgConnection,
+    stat: LeaderboardStat,
+    season: Option<i32>,
+    limit: i64,
+) -> QueryResult<Vec<DbLeaderboardEntry>> {
+    leaderboard_from(conn, "data.player_season_stats", stat, season, limit)
+}
+
+// `stats` is the relation to read season stats from. It's only ever
+// data.player_season_stats outside of tests.
+fn leaderboard_from(
+    conn: &mut PgConnection,
+    stats: &str,
+    stat: LeaderboardStat,
+    season: Option<i32>,
+    limit: i64,
+) -> QueryResult<Vec<DbLeaderboardEntry>> {
+    let direction = if stat.is_ascending() { "asc" } else { "desc" };
+    sql_query(format!(
+        "
+        with team_games as (
+            select g.season, g.team_mmolb_id, count(*) as num_team_games
+            from (
+                select season, home_team_mmolb_id as team_mmolb_id
+                from data.games
+                where not is_ongoing and ($1 is null or season=$1)
+                union all
+                select season, away_team_mmolb_id as team_mmolb_id
+                from data.games
+                where not is_ongoing and ($1 is null or season=$1)
+            ) g
+            group by g.season, g.team_mmolb_id
+        ),
+        season_games as (
+            select season, max(num_team_games) as num_team_games
+            from team_games
+            group by season
+        )
+        select
+            s.mmolb_player_id,
+            (array_agg(s.player_name order by s.season desc))[1] as player_name,
+            ({value})::float8 as value
+        from {stats} s
+        left join season_games tg on tg.season=s.season
+        where $1 is null or s.season=$1
+        group by s.mmolb_player_id
+        having {qualifier}
+        order by value {direction}, s.mmolb_player_id
+        limit $2
+    ",
+        value = stat.value_sql(),
+        qualifier = stat.qualifier_sql(),
+    ))
+    .bind::<Nullable<Int4>, _>(season)
+    .bind::<Int8, _>(limit)
+    .get_results(conn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{QueryError, postgres_url_from_environment};
+
+    #[test]
+    fn era_qualifier_scales_with_games_played() {
+        let url = postgres_url_from_environment();
+        let mut conn = PgConnection::establish(&url)
+            .expect("postgres_url_from_environment should return a valid connection string");
+
+        conn.test_transaction(|conn| {
+            // 10 finished games and one ongoing one, so 10 games count
+            sql_query(
+                "
+                with w as (
+                    insert into data.weather (name, emoji, tooltip)
+                    values ('Test Weather', '', '')
+                    returning id
+                )
+                insert into data.games
+                    (mmolb_game_id, weather, season, superstar_day, away_team_emoji,
+                     away_team_name, away_team_mmolb_id, home_team_emoji, home_team_name,
+                     home_team_mmolb_id, is_ongoing, from_version, started_at)
+                select 'ffffffffffffffffff' || lpad(n::text, 6, '0'), w.id, -1, null, '', 'Away',
+                    'leaderboard-test-away', '', 'Home', 'leaderboard-test-home', n > 10,
+                    '2025-02-15', '2025-02-15T00:00:00Z'
+                from w, generate_series(1, 11) n
+            ",
+            )
+            .execute(conn)?;
+
+            sql_query(
+                "
+                create temp table leaderboard_test_stats on commit drop as
+                select * from data.player_season_stats with no data
+            ",
+            )
+            .execute(conn)?;
+            sql_query(
+                "
+                insert into leaderboard_test_stats
+                    (season, mmolb_player_id, player_name, plate_appearances, hits, home_runs,
+                     walks, stolen_bases, strikeouts, outs_recorded, earned_runs)
+                values
+                    (-1, 'qualified', 'Qualified', 0, 0, 0, 0, 0, 0, 30, 5),
+                    (-1, 'one-out-short', 'One Out Short', 0, 0, 0, 0, 0, 0, 29, 0),
+                    (-1, 'worse', 'Worse', 0, 0, 0, 0, 0, 0, 60, 20)
+            ",
+            )
+            .execute(conn)?;
+
+            let leaders = leaderboard_from(
+                conn,
+                "leaderboard_test_stats",
+                LeaderboardStat::EarnedRunAverage,
+                Some(-1),
+                10,
+            )?;
+            let leaders = leaders
+                .iter()
+                .map(|entry| (entry.mmolb_player_id.as_str(), entry.value))
+                .collect::<Vec<_>>();
+            assert_eq!(leaders, vec![("qualified", 4.5), ("worse", 9.0)]);
+
+            Ok::<_, QueryError>(())
+        });
+    }
+}
@@ -0,0 +1,28 @@
+use crate::models::DbGameInning;
+use diesel::{PgConnection, prelude::*};
+
+/// The line score of one game, one row per half-inning in order. Returns
+/// None if the game doesn't exist.
+pub fn game_innings(
+    conn: &mut PgConnection,
+    mmolb_game_id: &str,
+) -> QueryResult<Option<Vec<DbGameInning>>> {
+    use crate::data_schema::data::game_innings::dsl as innings_dsl;
+    use crate::data_schema::data::games::dsl as games_dsl;
+
+    let Some(game_id) = games_dsl::games
+        .filter(games_dsl::mmolb_game_id.eq(mmolb_game_id))
+        .select(games_dsl::id)
+        .get_result::<i64>(conn)
+        .optional()?
+    else {
+        return Ok(None);
+    };
+
+    innings_dsl::game_innings
+        .filter(innings_dsl::game_id.eq(game_id))
+        .order_by((innings_dsl::inning, innings_dsl::top_of_inning.desc()))
+        .select(DbGameInning::as_select())
+        .get_results(conn)
+        .map(Some)
+}
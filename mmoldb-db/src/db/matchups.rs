@@ -0,0 +1,118 @@
+use diesel::{PgConnection, prelude::*, sql_query, sql_types::*};
+
+/// One player's totals against one opponent, summed over the requested
+/// seasons
+#[derive(Debug, Clone, QueryableByName)]
+pub struct DbMatchupOutcome {
+    #[diesel(sql_type = Text)]
+    pub opponent_mmolb_id: String,
+    /// The opponent's name as of their most recent plate appearance against
+    /// this player
+    #[diesel(sql_type = Text)]
+    pub opponent_name: String,
+    #[diesel(sql_type = Int8)]
+    pub plate_appearances: i64,
+    #[diesel(sql_type = Int8)]
+    pub hits: i64,
+    #[diesel(sql_type = Int8)]
+    pub home_runs: i64,
+    #[diesel(sql_type = Int8)]
+    pub walks: i64,
+    #[diesel(sql_type = Int8)]
+    pub strikeouts: i64,
+    /// Plate appearances where either player's identity wasn't certain
+    #[diesel(sql_type = Int8)]
+    pub uncertain_plate_appearances: i64,
+}
+
+#[derive(QueryableByName)]
+struct DbMatchupPlayerName {
+    #[diesel(sql_type = Nullable<Text>)]
+    name: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PlayerMatchups {
+    /// The player's name as of their latest matchup, or None if they don't
+    /// have any
+    pub player_name: Option<String>,
+    /// This player batting against each pitcher they've faced
+    pub as_batter: Vec<DbMatchupOutcome>,
+    /// This player pitching against each batter they've faced
+    pub as_pitcher: Vec<DbMatchupOutcome>,
+}
+
+fn matchup_outcomes(
+    conn: &mut PgConnection,
+    player_column: &str,
+    opponent_column: &str,
+    opponent_name_column: &str,
+    player_id: &str,
+    season: Option<i32>,
+) -> QueryResult<Vec<DbMatchupOutcome>> {
+    sql_query(format!(
+        "
+        select
+            {opponent_column} as opponent_mmolb_id,
+            (array_agg({opponent_name_column} order by season desc))[1] as opponent_name,
+            sum(plate_appearances)::bigint as plate_appearances,
+            sum(hits)::bigint as hits,
+            sum(home_runs)::bigint as home_runs,
+            sum(walks)::bigint as walks,
+            sum(strikeouts)::bigint as strikeouts,
+            sum(uncertain_plate_appearances)::bigint as uncertain_plate_appearances
+        from data.matchup_outcomes
+        where {player_column}=$1 and ($2 is null or season=$2)
+        group by {opponent_column}
+        order by plate_appearances desc, opponent_mmolb_id
+    "
+    ))
+    .bind::<Text, _>(player_id)
+    .bind::<Nullable<Int4>, _>(season)
+    .get_results(conn)
+}
+
+/// Every opponent `player_id` has faced as a batter and as a pitcher, most
+/// frequent first. If `season` is given, only that season is counted.
+pub fn player_matchups(
+    conn: &mut PgConnection,
+    player_id: &str,
+    season: Option<i32>,
+) -> QueryResult<PlayerMatchups> {
+    let player_name = sql_query(
+        "
+        select coalesce(
+            (select batter_name from data.matchup_outcomes where batter_mmolb_id=$1
+                order by season desc limit 1),
+            (select pitcher_name from data.matchup_outcomes where pitcher_mmolb_id=$1
+                order by season desc limit 1)
+        ) as name
+    ",
+    )
+    .bind::<Text, _>(player_id)
+    .get_result::<DbMatchupPlayerName>(conn)?
+    .name;
+
+    let as_batter = matchup_outcomes(
+        conn,
+        "batter_mmolb_id",
+        "pitcher_mmolb_id",
+        "pitcher_name",
+        player_id,
+        season,
+    )?;
+    let as_pitcher = matchup_outcomes(
+        conn,
+        "pitcher_mmolb_id",
+        "batter_mmolb_id",
+        "batter_name",
+        player_id,
+        season,
+    )?;
+
+    Ok(PlayerMatchups {
+        player_name,
+        as_batter,
+        as_pitcher,
+    })
+}
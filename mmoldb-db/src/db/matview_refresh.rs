@@ -0,0 +1,197 @@
+use chrono::Utc;
+use diesel::{PgConnection, prelude::*, sql_query, sql_types::*};
+use tracing::{debug, warn};
+
+#[derive(QueryableByName)]
+struct MatviewRefreshability {
+    #[diesel(sql_type = Bool)]
+    populated: bool,
+    #[diesel(sql_type = Bool)]
+    has_unique_index: bool,
+}
+
+/// Whether `matview` can be refreshed concurrently. Postgres only allows it
+/// for matviews that have been populated at least once and that have a
+/// unique index on plain columns with no `where` clause.
+fn can_refresh_concurrently(conn: &mut PgConnection, matview: &str) -> QueryResult<bool> {
+    let refreshability = sql_query(
+        "
+        select
+            c.relispopulated as populated,
+            exists(
+                select 1 from pg_index i
+                where i.indrelid=c.oid
+                    and i.indisunique
+                    and i.indpred is null
+                    and i.indexprs is null
+            ) as has_unique_index
+        from pg_class c
+        where c.oid=to_regclass($1)
+    ",
+    )
+    .bind::<Text, _>(matview)
+    .get_result::<MatviewRefreshability>(conn)
+    .optional()?;
+
+    // If the matview doesn't exist, let the refresh report that
+    Ok(refreshability.is_some_and(|r| r.populated && r.has_unique_index))
+}
+
+/// Refreshes `matview` (schema-qualified, e.g. `data.weather_outcomes`),
+/// concurrently if possible so reads aren't blocked while it runs. Falls
+/// back to a plain refresh if it can't be done concurrently. How long the
+/// refresh took is recorded in `info.matview_refreshes`.
+pub fn refresh_matview(conn: &mut PgConnection, matview: &str) -> QueryResult<()> {
+    refresh_and_record(conn, matview, None)
+}
+
+#[derive(QueryableByName)]
+struct MatviewInputs {
+    #[diesel(sql_type = Text)]
+    inputs: String,
+}
+
+/// Like [`refresh_matview`], but skips the refresh if `inputs_sql`, which
+/// must select a single text column named `inputs`, returns the same
+/// fingerprint it did at the last successful refresh. For matviews that are
+/// too expensive to refresh on every pass. Returns whether it refreshed.
+pub fn refresh_matview_if_changed(
+    conn: &mut PgConnection,
+    matview: &str,
+    inputs_sql: &str,
+) -> QueryResult<bool> {
+    let inputs = sql_query(inputs_sql)
+        .get_result::<MatviewInputs>(conn)?
+        .inputs;
+    let previous_inputs = sql_query(
+        "
+        select inputs from info.matview_refreshes
+        where matview=$1 and error is null and inputs is not null
+    ",
+    )
+    .bind::<Text, _>(matview)
+    .get_result::<MatviewInputs>(conn)
+    .optional()?;
+
+    if previous_inputs.is_some_and(|previous| previous.inputs == inputs) {
+        debug!("{matview}'s inputs haven't changed since its last refresh. Skipping it.");
+        return Ok(false);
+    }
+
+    refresh_and_record(conn, matview, Some(&inputs))?;
+    Ok(true)
+}
+
+// `inputs` is only recorded if the refresh succeeds, so a failed refresh is
+// retried even if the inputs don't change
+fn refresh_and_record(
+    conn: &mut PgConnection,
+    matview: &str,
+    inputs: Option<&str>,
+) -> QueryResult<()> {
+    let started_at = Utc::now();
+    let mut concurrent = can_refresh_concurrently(conn, matview)?;
+    if !concurrent {
+        warn!("{matview} can't be refreshed concurrently. Reads will block until it's refreshed.");
+    }
+
+    // matview can't be a bind parameter. It's only ever one of our own
+    // hard-coded names.
+    let plain_refresh = format!("refresh materialized view {matview}");
+    let result = if concurrent {
+        // In its own (sub)transaction, so that a failure doesn't abort a
+        // transaction the caller is in and the plain refresh can still run
+        conn.transaction(|conn| {
+            sql_query(format!("refresh materialized view concurrently {matview}")).execute(conn)
+        })
+        // The check above can't catch everything that makes a concurrent
+        // refresh fail (like duplicate rows), so fall back to a plain one
+        .or_else(|err| {
+            warn!(
+                "Couldn't refresh {matview} concurrently: {err}. Reads will block until it's \
+                refreshed."
+            );
+            concurrent = false;
+            sql_query(&plain_refresh).execute(conn)
+        })
+    } else {
+        sql_query(&plain_refresh).execute(conn)
+    };
+
+    let refreshed_at = Utc::now();
+    let duration_seconds = (refreshed_at - started_at).as_seconds_f64();
+    debug!("Refreshing {matview} took {duration_seconds:.2}s");
+
+    // A failure to record the duration shouldn't hide the refresh's result
+    if let Err(err) = sql_query(
+        "
+        insert into info.matview_refreshes
+            (matview, refreshed_at, duration_seconds, concurrent, error, inputs)
+        values ($1, $2, $3, $4, $5, $6)
+        on conflict (matview) do update set
+            refreshed_at=excluded.refreshed_at,
+            duration_seconds=excluded.duration_seconds,
+            concurrent=excluded.concurrent,
+            error=excluded.error,
+            inputs=excluded.inputs
+    ",
+    )
+    .bind::<Text, _>(matview)
+    .bind::<Timestamptz, _>(refreshed_at)
+    .bind::<Double, _>(duration_seconds)
+    .bind::<Bool, _>(concurrent)
+    .bind::<Nullable<Text>, _>(result.as_ref().err().map(|err| err.to_string()))
+    .bind::<Nullable<Text>, _>(inputs.filter(|_| result.is_ok()))
+    .execute(conn)
+    {
+        warn!("Error recording refresh of {matview}: {err}");
+    }
+
+    result.map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{QueryError, postgres_url_from_environment};
+
+    #[test]
+    fn refresh_is_skipped_while_inputs_are_unchanged() {
+        let url = postgres_url_from_environment();
+        let mut conn = PgConnection::establish(&url)
+            .expect("postgres_url_from_environment should return a valid connection string");
+
+        conn.test_transaction(|conn| {
+            sql_query("create materialized view data.matview_refresh_test as select 1 as x")
+                .execute(conn)?;
+            sql_query("create unique index on data.matview_refresh_test (x)").execute(conn)?;
+
+            let matview = "data.matview_refresh_test";
+            assert!(refresh_matview_if_changed(
+                conn,
+                matview,
+                "select 'a' as inputs"
+            )?);
+            assert!(!refresh_matview_if_changed(
+                conn,
+                matview,
+                "select 'a' as inputs"
+            )?);
+            assert!(refresh_matview_if_changed(
+                conn,
+                matview,
+                "select 'b' as inputs"
+            )?);
+
+            // A plain refresh forgets the inputs, so the next check refreshes
+            refresh_matview(conn, matview)?;
+            assert!(refresh_matview_if_changed(
+                conn,
+                matview,
+                "select 'b' as inputs"
+            )?);
+
+            Ok::<_, QueryError>(())
+        });
+    }
+}
@@ -1,22 +1,115 @@
+mod admin_audit;
+mod attribute_distributions;
+mod attribute_progression;
+mod baserunning;
+mod chron_anomalies;
+mod copy_binary;
+mod data_freshness;
+mod day_summary;
+mod db_health;
+mod ejections;
 mod entities;
+mod entity_lists;
+mod event_filter;
+mod event_loader;
+mod explain;
+mod export;
+mod feed_event_failures;
+mod feed_event_texts;
+mod head_to_head;
+mod home_away;
+mod ingest_lock;
+mod ingest_logs;
+mod ingest_progress;
+mod ingest_task_state;
+mod keyset;
+mod leaderboards;
+mod linescore;
+mod matchups;
+mod matview_refresh;
+mod offload;
+mod outcome_summaries;
+mod player_identity;
+mod player_similarity;
+mod quality;
+mod rebuild;
+mod replay;
+mod retention;
+mod rollback;
+mod rosters;
+mod season_phases;
+mod season_rollovers;
+mod slow_queries;
+mod snapshot;
+mod stalled_games;
+mod stadiums;
 mod to_db_format;
+mod version_chains;
 mod versions;
 mod weather;
+mod weekly_rollups;
+pub mod win_probability;
 pub(crate) mod cheers;
 pub(crate) mod balk_reasons;
 
 use std::collections::HashSet;
 // Reexports
 pub use crate::db::weather::NameEmojiTooltip;
+pub use admin_audit::*;
+pub use attribute_distributions::*;
+pub use attribute_progression::*;
+pub use baserunning::*;
+pub use chron_anomalies::*;
+pub use copy_binary::*;
+pub use data_freshness::*;
+pub use day_summary::*;
+pub use db_health::*;
+pub use ejections::*;
 pub use entities::*;
+pub use entity_lists::*;
+pub use event_filter::*;
+pub use event_loader::*;
+pub use explain::*;
+pub use export::*;
+pub use feed_event_failures::*;
+pub use feed_event_texts::*;
+pub use head_to_head::*;
+pub use home_away::*;
+pub use ingest_lock::*;
+pub use ingest_logs::*;
+pub use ingest_progress::*;
+pub use ingest_task_state::*;
+pub use keyset::*;
+pub use leaderboards::*;
+pub use linescore::*;
+pub use matchups::*;
+pub use matview_refresh::*;
+pub use offload::*;
+pub use player_identity::*;
+pub use player_similarity::*;
+pub use quality::*;
+pub use rebuild::*;
+pub use replay::*;
+pub use retention::*;
+pub use rollback::*;
+pub use rosters::*;
+pub use season_phases::*;
+pub use season_rollovers::*;
+pub use slow_queries::*;
+pub use snapshot::*;
+pub use stalled_games::*;
+pub use stadiums::*;
 pub use to_db_format::RowToEventError;
+pub use version_chains::*;
 pub use versions::*;
+pub use weekly_rollups::*;
 
 // Third-party imports
 use chrono::{DateTime, NaiveDateTime, Utc};
 use diesel::connection::DefaultLoadingMode;
 use diesel::dsl::{count, count_star};
-use diesel::query_builder::SqlQuery;
+use diesel::pg::Pg;
+use diesel::query_builder::{BoxedSqlQuery, SqlQuery};
 use diesel::{PgConnection, prelude::*, sql_query, sql_types::*};
 use hashbrown::HashMap;
 use itertools::{Either, Itertools};
@@ -24,13 +117,15 @@ use mmolb_parsing::ParsedEventMessage;
 use mmolb_parsing::enums::Day;
 use serde::Serialize;
 use std::iter;
+use std::num::NonZero;
+use std::ops::Range;
 use thiserror::Error;
 use tracing::{debug, info, trace, warn};
 // First-party imports
 use crate::event_detail::{EventDetail, IngestLog};
-use crate::models::{DbAuroraPhoto, DbDoorPrize, DbDoorPrizeItem, DbEfflorescence, DbEfflorescenceGrowth, DbEjection, DbEvent, DbEventIngestLog, DbFailedEjection, DbFielder, DbGame, DbModification, DbPlayerAttributeAugment, DbPlayerEquipmentEffectVersion, DbPlayerEquipmentVersion, DbPlayerModificationVersion, DbPlayerRecomposition, DbPlayerReportAttributeVersion, DbPlayerReportVersion, DbPlayerVersion, DbRunner, DbWither, NewEventIngestLog, NewFeedEventProcessed, NewGame, NewModification, NewModificationEffects, NewPlayerAttributeAugment, NewPlayerEquipmentEffectVersion, NewPlayerEquipmentVersion, NewPlayerModificationVersion, NewPlayerParadigmShift, NewPlayerPitchCategoryBonusVersion, NewPlayerPitchTypeBonusVersion, NewPlayerPitchTypeVersion, NewPlayerRecomposition, NewPlayerReportAttributeVersion, NewPlayerReportVersion, NewPlayerVersion, NewTeamGamePlayed, NewTeamPlayerVersion, NewTeamVersion, NewVersionIngestLog, NewVersionProcessed, RawDbColumn, RawDbTable};
-use crate::taxa::Taxa;
-use crate::{ConsumptionContestForDb, PartyEvent, PitcherChange, QueryError, WitherOutcome};
+use crate::models::{DbEventIngestLog, DbGame, DbModification, DbPlayerAttributeAugment, DbPlayerEquipmentEffectVersion, DbPlayerEquipmentVersion, DbPlayerModificationVersion, DbPlayerRecomposition, DbPlayerReportAttributeVersion, DbPlayerReportVersion, DbPlayerVersion, NewAdminAudit, NewEventIngestLog, NewEventPitchSequence, NewFeedEventFailure, NewFeedEventProcessed, NewGame, NewModification, NewModificationEffects, NewPlayerAttributeAugment, NewPlayerEquipmentEffectVersion, NewPlayerEquipmentVersion, NewPlayerModificationVersion, NewPlateAppearance, NewPlayerParadigmShift, NewPlayerPitchCategoryBonusVersion, NewPlayerPitchTypeBonusVersion, NewPlayerPitchTypeVersion, NewPlayerRecomposition, NewPlayerReportAttributeVersion, NewPlayerReportVersion, NewPlayerVersion, NewTeamGamePlayed, NewTeamPlayerVersion, NewTeamTransaction, NewTeamVersion, NewVersionIngestLog, NewVersionProcessed, RawDbColumn, RawDbTable};
+use crate::taxa::{Taxa, TaxaCount, TaxaIngestErrorCode};
+use crate::{ConsumptionContestForDb, EventDefense, PartyEvent, PitchSequenceEntry, PitcherChange, PlateAppearanceWorkload, QueryError, WitherOutcome};
 
 pub fn set_current_user_statement_timeout(
     conn: &mut PgConnection,
@@ -47,6 +142,16 @@ pub fn set_current_user_statement_timeout(
     .execute(conn)
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+pub enum GameStatus {
+    Ongoing,
+    Finished,
+    NotFound,
+}
+
+/// Returns the status of each requested game, ordered by game id. Ids
+/// that aren't in the database are omitted; use [`games_status_map`] if
+/// you need to know which ones those were.
 pub fn is_ongoing(conn: &mut PgConnection, ids: &[&str]) -> QueryResult<Vec<(String, bool)>> {
     use crate::data_schema::data::games::dsl;
 
@@ -57,6 +162,36 @@ pub fn is_ongoing(conn: &mut PgConnection, ids: &[&str]) -> QueryResult<Vec<(Str
         .get_results(conn)
 }
 
+/// Returns the status of every requested game, keyed by game id. Ids that
+/// aren't in the database map to [`GameStatus::NotFound`]. Any number of
+/// ids may be passed, since they're sent as a single array parameter.
+pub fn games_status_map(
+    conn: &mut PgConnection,
+    ids: &[&str],
+) -> QueryResult<HashMap<String, GameStatus>> {
+    use crate::data_schema::data::games::dsl;
+
+    let found = dsl::games
+        .filter(dsl::mmolb_game_id.eq_any(ids))
+        .select((dsl::mmolb_game_id, dsl::is_ongoing))
+        .get_results::<(String, bool)>(conn)?;
+
+    let mut statuses: HashMap<String, GameStatus> = ids
+        .iter()
+        .map(|id| (id.to_string(), GameStatus::NotFound))
+        .collect();
+    for (id, is_ongoing) in found {
+        let status = if is_ongoing {
+            GameStatus::Ongoing
+        } else {
+            GameStatus::Finished
+        };
+        statuses.insert(id, status);
+    }
+
+    Ok(statuses)
+}
+
 pub fn game_count(conn: &mut PgConnection) -> QueryResult<i64> {
     use crate::data_schema::data::games::dsl::*;
 
@@ -145,6 +280,7 @@ pub fn get_all_game_entity_ids_set(conn: &mut PgConnection) -> QueryResult<HashS
 
     entities_dsl::entities
         .filter(entities_dsl::kind.eq("game"))
+        .filter(entities_dsl::source.is_null())
         .select(entities_dsl::entity_id)
         .order_by(entities_dsl::entity_id.desc())
         .load_iter::<_, DefaultLoadingMode>(conn)?
@@ -171,6 +307,10 @@ pub struct GameWithIssueCounts {
     pub errors_count: i64,
     #[diesel(sql_type = Int8)]
     pub critical_count: i64,
+    #[diesel(sql_type = Text)]
+    pub weather_name: String,
+    #[diesel(sql_type = Text)]
+    pub weather_emoji: String,
 }
 
 pub fn games_list_base() -> SqlQuery {
@@ -189,8 +329,11 @@ pub fn games_list_base() -> SqlQuery {
             g.*,
             coalesce(counts.critical_count, 0) as critical_count,
             coalesce(counts.errors_count, 0) as errors_count,
-            coalesce(counts.warnings_count, 0) as warnings_count
+            coalesce(counts.warnings_count, 0) as warnings_count,
+            w.name as weather_name,
+            w.emoji as weather_emoji
         from data.games g
+            join data.weather w on w.id = g.weather
             left join counts on g.id = counts.game_id
     ",
     )
@@ -228,79 +371,32 @@ pub fn page_of_games_generic(
     after_game_id: Option<&str>,
     base_query: SqlQuery,
 ) -> QueryResult<PageOfGames> {
-    // Get N + 1 games so we know if this is the last page or not
-    let (mut games, previous_page) = if let Some(after_game_id) = after_game_id {
-        // base_query must have left off in the middle of a `where`
-        let games = base_query
-            .clone()
-            .sql(
-                "
-            and g.mmolb_game_id > $1
-            order by g.mmolb_game_id asc
-            limit $2
-        ",
-            )
-            .bind::<Text, _>(after_game_id)
-            .bind::<Integer, _>(page_size as i32 + 1)
-            .get_results::<GameWithIssueCounts>(conn)?;
-
-        // Previous page is the one page_size games before this
-        // Get N + 1 games so we know if this is the first page or not
-        let preceding_pages = base_query
-            .sql(
-                "
-            and g.mmolb_game_id <= $1
-            order by g.mmolb_game_id desc
-            limit $2
-        ",
-            )
-            .bind::<Text, _>(after_game_id)
-            .bind::<Integer, _>(page_size as i32 + 1)
-            .get_results::<GameWithIssueCounts>(conn)?;
-
-        let preceding_page = if preceding_pages.len() > page_size {
-            // Then the preceding page is not the first page
-            Some(
-                preceding_pages
-                    .into_iter()
-                    .last()
-                    .map(|g| g.game.mmolb_game_id),
-            )
-        } else {
-            // Then the preceding page is the first page
-            Some(None)
-        };
-
-        (games, preceding_page)
-    } else {
-        let games = base_query
-            .sql(
-                "
-            order by g.mmolb_game_id asc
-            limit $1
-        ",
-            )
-            .bind::<Integer, _>(page_size as i32 + 1)
-            .get_results::<GameWithIssueCounts>(conn)?;
-
-        // None after_game_id => this is the first page => there is no previous page
-        (games, None)
-    };
+    page_of_games_keyset(conn, page_size, after_game_id, || {
+        (base_query.clone().into_boxed(), 1)
+    })
+}
 
-    let next_page = if games.len() > page_size {
-        // Then this is not the last page
-        games.truncate(page_size);
-        // The page token is the last game that is actually shown
-        games.last().map(|g| g.game.mmolb_game_id.clone())
-    } else {
-        // Then this is the last page
-        None
-    };
+/// Shared keyset pagination for the games lists, which are paged by MMOLB
+/// game id. See [`page_by_keyset`] for what `make_query` must return.
+fn page_of_games_keyset(
+    conn: &mut PgConnection,
+    page_size: usize,
+    after_game_id: Option<&str>,
+    make_query: impl Fn() -> (BoxedSqlQuery<'static, Pg, SqlQuery>, usize),
+) -> QueryResult<PageOfGames> {
+    let page = page_by_keyset(
+        conn,
+        Keyset(&["g.mmolb_game_id"]),
+        page_size,
+        after_game_id.map(str::to_string),
+        make_query,
+        |g: &GameWithIssueCounts| g.game.mmolb_game_id.clone(),
+    )?;
 
     Ok(PageOfGames {
-        games,
-        next_page,
-        previous_page,
+        games: page.items,
+        next_page: page.next_page,
+        previous_page: page.previous_page,
     })
 }
 
@@ -321,477 +417,123 @@ pub fn page_of_games_with_issues(
     page_of_games_generic(conn, page_size, after_game_id, games_with_issues_list())
 }
 
-pub struct EventsForGameTimings {
-    pub get_game_ids_duration: f64,
-    pub get_events_duration: f64,
-    pub group_events_duration: f64,
-    pub get_runners_duration: f64,
-    pub group_runners_duration: f64,
-    pub get_fielders_duration: f64,
-    pub group_fielders_duration: f64,
-    pub post_process_duration: f64,
-}
-
-pub fn group_child_table_results<'a, ChildT>(
-    games_events: impl IntoIterator<Item = &'a Vec<DbEvent>>,
-    child_results: Vec<ChildT>,
-    event_id_for_child: impl Fn(&ChildT) -> i64,
-) -> Vec<Vec<Vec<ChildT>>> {
-    let mut child_results_iter = child_results.into_iter().peekable();
-
-    let results = games_events
-        .into_iter()
-        .map(|game_events| {
-            game_events
-                .iter()
-                .map(|game_event| {
-                    let mut children = Vec::new();
-                    while let Some(child) =
-                        child_results_iter.next_if(|f| event_id_for_child(f) == game_event.id)
-                    {
-                        children.push(child);
-                    }
-                    children
-                })
-                .collect_vec()
-        })
-        .collect_vec();
-
-    assert_eq!(child_results_iter.count(), 0);
-
-    results
-}
-
-pub fn group_wither_table_results<'a>(
-    games_events: impl IntoIterator<Item = &'a Vec<DbEvent>>,
-    withers: Vec<DbWither>,
-) -> Vec<Vec<Vec<DbWither>>> {
-    let mut wither_iter = withers.into_iter().peekable();
-
-    let results = games_events
-        .into_iter()
-        .map(|game_events| {
-            game_events
-                .iter()
-                .map(|game_event| {
-                    let mut children = Vec::new();
-                    while let Some(child) = wither_iter.next_if(|f| {
-                        f.game_id == game_event.game_id
-                            && f.attempt_game_event_index == game_event.game_event_index
-                    }) {
-                        children.push(child);
-                    }
-                    children
-                })
-                .collect_vec()
-        })
-        .collect_vec();
-
-    assert_eq!(wither_iter.count(), 0);
-
-    results
-}
-
-pub fn group_cheer_table_results<'a>(
-    games_events: impl IntoIterator<Item = &'a Vec<DbEvent>>,
-    cheers: Vec<(i64, Option<String>)>,
-) -> Vec<Vec<Vec<Option<String>>>> {
-    let mut cheer_iter = cheers.into_iter().peekable();
-
-    let results = games_events
-        .into_iter()
-        .map(|game_events| {
-            game_events
-                .iter()
-                .map(|game_event| {
-                    let mut children = Vec::new();
-                    while let Some((_, message)) = cheer_iter.next_if(|(event_id, _)| {
-                        *event_id == game_event.id
-                    }) {
-                        children.push(message);
-                    }
-                    children
-                })
-                .collect_vec()
-        })
-        .collect_vec();
-
-    assert_eq!(cheer_iter.count(), 0);
-
-    results
-}
-
-pub fn group_balk_reason_table_results<'a>(
-    games_events: impl IntoIterator<Item = &'a Vec<DbEvent>>,
-    balk_reasons: Vec<(i64, Option<String>)>,
-) -> Vec<Vec<Vec<Option<String>>>> {
-    let mut balk_reason_iter = balk_reasons.into_iter().peekable();
-
-    let results = games_events
-        .into_iter()
-        .map(|game_events| {
-            game_events
-                .iter()
-                .map(|game_event| {
-                    let mut children = Vec::new();
-                    while let Some((_, message)) = balk_reason_iter.next_if(|(event_id, _)| {
-                        *event_id == game_event.id
-                    }) {
-                        children.push(message);
-                    }
-                    children
-                })
-                .collect_vec()
-        })
-        .collect_vec();
-
-    assert_eq!(balk_reason_iter.count(), 0);
+/// Filters for [`search_games`]. Every filter that's `None` is ignored.
+#[derive(Debug, Default, Clone)]
+pub struct GameSearch {
+    pub season: Option<i32>,
+    /// Inclusive. Games with no regular day (e.g. superstar games) never
+    /// match a day filter.
+    pub day_min: Option<i32>,
+    /// Inclusive
+    pub day_max: Option<i32>,
+    /// Matches games where this team is either home or away
+    pub team_mmolb_id: Option<String>,
+    pub weather_name: Option<String>,
+    pub stadium_name: Option<String>,
+    /// Inclusive. Absolute difference between the final scores. Games with
+    /// no final score never match a margin filter.
+    pub min_score_margin: Option<i32>,
+    /// Inclusive
+    pub max_score_margin: Option<i32>,
+    /// Whether the game has any ingest warnings, errors, or critical errors
+    pub has_issues: Option<bool>,
+}
+
+impl GameSearch {
+    /// Returns the games list query with this search's filters applied,
+    /// plus the number of the next unused bind placeholder
+    fn to_query(&self) -> (BoxedSqlQuery<'static, Pg, SqlQuery>, usize) {
+        let mut query = games_list().into_boxed();
+        let mut n = 1;
+
+        if let Some(season) = self.season {
+            query = query
+                .sql(format!(" and g.season = ${n}"))
+                .bind::<Integer, _>(season);
+            n += 1;
+        }
+        if let Some(day_min) = self.day_min {
+            query = query
+                .sql(format!(" and g.day >= ${n}"))
+                .bind::<Integer, _>(day_min);
+            n += 1;
+        }
+        if let Some(day_max) = self.day_max {
+            query = query
+                .sql(format!(" and g.day <= ${n}"))
+                .bind::<Integer, _>(day_max);
+            n += 1;
+        }
+        if let Some(team_mmolb_id) = &self.team_mmolb_id {
+            query = query
+                .sql(format!(
+                    " and (g.home_team_mmolb_id = ${n} or g.away_team_mmolb_id = ${n})"
+                ))
+                .bind::<Text, _>(team_mmolb_id.clone());
+            n += 1;
+        }
+        if let Some(weather_name) = &self.weather_name {
+            query = query
+                .sql(format!(" and w.name = ${n}"))
+                .bind::<Text, _>(weather_name.clone());
+            n += 1;
+        }
+        if let Some(stadium_name) = &self.stadium_name {
+            query = query
+                .sql(format!(" and g.stadium_name = ${n}"))
+                .bind::<Text, _>(stadium_name.clone());
+            n += 1;
+        }
+        if let Some(min_score_margin) = self.min_score_margin {
+            query = query
+                .sql(format!(
+                    " and abs(g.home_team_final_score - g.away_team_final_score) >= ${n}"
+                ))
+                .bind::<Integer, _>(min_score_margin);
+            n += 1;
+        }
+        if let Some(max_score_margin) = self.max_score_margin {
+            query = query
+                .sql(format!(
+                    " and abs(g.home_team_final_score - g.away_team_final_score) <= ${n}"
+                ))
+                .bind::<Integer, _>(max_score_margin);
+            n += 1;
+        }
+        match self.has_issues {
+            None => {}
+            Some(true) => {
+                query = query.sql(
+                    " and (counts.critical_count > 0 or counts.errors_count > 0 or counts.warnings_count > 0)",
+                );
+            }
+            Some(false) => {
+                // Games with no issues have no row in counts
+                query = query.sql(" and counts.game_id is null");
+            }
+        }
 
-    results
+        (query, n)
+    }
 }
 
-pub fn events_for_games(
+pub fn search_games(
     conn: &mut PgConnection,
-    taxa: &Taxa,
-    for_game_ids: &[&str],
-) -> QueryResult<(
-    Vec<(i64, Vec<Result<EventDetail<String>, RowToEventError>>)>,
-    EventsForGameTimings,
-)> {
-    use crate::data_schema::data::aurora_photos::dsl as aurora_photo_dsl;
-    use crate::data_schema::data::door_prize_items::dsl as door_prize_item_dsl;
-    use crate::data_schema::data::door_prizes::dsl as door_prize_dsl;
-    use crate::data_schema::data::efflorescence::dsl as efflorescence_dsl;
-    use crate::data_schema::data::efflorescence_growth::dsl as efflorescence_growth_dsl;
-    use crate::data_schema::data::ejections::dsl as ejection_dsl;
-    use crate::data_schema::data::event_baserunners::dsl as runner_dsl;
-    use crate::data_schema::data::event_fielders::dsl as fielder_dsl;
-    use crate::data_schema::data::events::dsl as events_dsl;
-    use crate::data_schema::data::failed_ejections::dsl as failed_ejection_dsl;
-    use crate::data_schema::data::games::dsl as games_dsl;
-    use crate::data_schema::data::wither::dsl as wither_dsl;
-    use crate::data_schema::data::event_cheers::dsl as event_cheers_dsl;
-    use crate::data_schema::data::cheers::dsl as cheers_dsl;
-    use crate::data_schema::data::event_balk_reasons::dsl as event_balk_reasons_dsl;
-    use crate::data_schema::data::balk_reasons::dsl as balk_reasons_dsl;
-
-    let get_game_ids_start = Utc::now();
-    let game_ids = games_dsl::games
-        .filter(games_dsl::mmolb_game_id.eq_any(for_game_ids))
-        .select(games_dsl::id)
-        .order_by(games_dsl::id.asc())
-        .get_results::<i64>(conn)?;
-    let get_game_ids_duration = (Utc::now() - get_game_ids_start).as_seconds_f64();
-
-    let get_events_start = Utc::now();
-    let db_events = events_dsl::events
-        .filter(events_dsl::game_id.eq_any(&game_ids))
-        .order_by(events_dsl::game_id.asc())
-        .then_order_by(events_dsl::game_event_index.asc())
-        .select(DbEvent::as_select())
-        .load(conn)?;
-    let all_event_ids = db_events.iter().map(|event| event.id).collect_vec();
-    let get_events_duration = (Utc::now() - get_events_start).as_seconds_f64();
-
-    let group_events_start = Utc::now();
-    let mut db_events_iter = db_events.into_iter().peekable();
-    let db_games_events = game_ids
-        .iter()
-        .map(|id| {
-            let mut game_events = Vec::new();
-            while let Some(event) = db_events_iter.next_if(|e| e.game_id == *id) {
-                game_events.push(event);
-            }
-            game_events
-        })
-        .collect_vec();
-    let group_events_duration = (Utc::now() - group_events_start).as_seconds_f64();
-
-    let get_runners_start = Utc::now();
-    let db_runners = runner_dsl::event_baserunners
-        .filter(runner_dsl::event_id.eq_any(&all_event_ids))
-        .order_by((
-            runner_dsl::event_id.asc(),
-            runner_dsl::base_before.desc().nulls_last(),
-        ))
-        .select(DbRunner::as_select())
-        .load(conn)?;
-    let get_runners_duration = (Utc::now() - get_runners_start).as_seconds_f64();
-
-    let group_runners_start = Utc::now();
-    let db_runners = group_child_table_results(&db_games_events, db_runners, |r| r.event_id);
-    let group_runners_duration = (Utc::now() - group_runners_start).as_seconds_f64();
-
-    let get_fielders_start = Utc::now();
-    let db_fielders = fielder_dsl::event_fielders
-        .filter(fielder_dsl::event_id.eq_any(&all_event_ids))
-        .order_by((fielder_dsl::event_id, fielder_dsl::play_order))
-        .select(DbFielder::as_select())
-        .load(conn)?;
-    let get_fielders_duration = (Utc::now() - get_fielders_start).as_seconds_f64();
-
-    let group_fielders_start = Utc::now();
-    let db_fielders = group_child_table_results(&db_games_events, db_fielders, |r| r.event_id);
-    let group_fielders_duration = (Utc::now() - group_fielders_start).as_seconds_f64();
-
-    let get_aurora_photos_start = Utc::now();
-    let db_aurora_photos = aurora_photo_dsl::aurora_photos
-        .filter(aurora_photo_dsl::event_id.eq_any(&all_event_ids))
-        .order_by((
-            aurora_photo_dsl::event_id,
-            aurora_photo_dsl::is_listed_first.desc(),
-        ))
-        .select(DbAuroraPhoto::as_select())
-        .load(conn)?;
-    let _get_aurora_photos_duration = (Utc::now() - get_aurora_photos_start).as_seconds_f64();
-
-    let group_aurora_photos_start = Utc::now();
-    let db_aurora_photos =
-        group_child_table_results(&db_games_events, db_aurora_photos, |r| r.event_id);
-    let _group_aurora_photos_duration = (Utc::now() - group_aurora_photos_start).as_seconds_f64();
-
-    let get_ejections_start = Utc::now();
-    let db_ejections = ejection_dsl::ejections
-        .filter(ejection_dsl::event_id.eq_any(&all_event_ids))
-        .order_by(ejection_dsl::event_id)
-        .select(DbEjection::as_select())
-        .load(conn)?;
-    let _get_ejections_duration = (Utc::now() - get_ejections_start).as_seconds_f64();
-
-    let group_ejections_start = Utc::now();
-    let db_ejections = group_child_table_results(&db_games_events, db_ejections, |r| r.event_id);
-    let _group_ejections_duration = (Utc::now() - group_ejections_start).as_seconds_f64();
-
-    let get_failed_ejections_start = Utc::now();
-    let db_failed_ejections = failed_ejection_dsl::failed_ejections
-        .filter(failed_ejection_dsl::event_id.eq_any(&all_event_ids))
-        .order_by(failed_ejection_dsl::event_id)
-        .select(DbFailedEjection::as_select())
-        .load(conn)?;
-    let _get_failed_ejections_duration = (Utc::now() - get_failed_ejections_start).as_seconds_f64();
-
-    let group_failed_ejections_start = Utc::now();
-    let db_failed_ejections =
-        group_child_table_results(&db_games_events, db_failed_ejections, |r| r.event_id);
-    let _group_failed_ejections_duration =
-        (Utc::now() - group_failed_ejections_start).as_seconds_f64();
-
-    let get_door_prizes_start = Utc::now();
-    let db_door_prizes = door_prize_dsl::door_prizes
-        .filter(door_prize_dsl::event_id.eq_any(&all_event_ids))
-        .order_by((door_prize_dsl::event_id, door_prize_dsl::door_prize_index))
-        .select(DbDoorPrize::as_select())
-        .load(conn)?;
-    let _get_door_prizes_duration = (Utc::now() - get_door_prizes_start).as_seconds_f64();
-
-    let group_door_prizes_start = Utc::now();
-    let db_door_prizes =
-        group_child_table_results(&db_games_events, db_door_prizes, |r| r.event_id);
-    let _group_door_prizes_duration = (Utc::now() - group_door_prizes_start).as_seconds_f64();
-
-    let get_door_prize_items_start = Utc::now();
-    let db_door_prize_items = door_prize_item_dsl::door_prize_items
-        .filter(door_prize_item_dsl::event_id.eq_any(&all_event_ids))
-        .order_by((
-            door_prize_item_dsl::event_id,
-            door_prize_item_dsl::door_prize_index,
-            door_prize_item_dsl::item_index,
-        ))
-        .select(DbDoorPrizeItem::as_select())
-        .load(conn)?;
-    let _get_door_prize_items_duration = (Utc::now() - get_door_prize_items_start).as_seconds_f64();
-
-    let group_door_prize_items_start = Utc::now();
-    let db_door_prize_items =
-        group_child_table_results(&db_games_events, db_door_prize_items, |r| r.event_id);
-    let _group_door_prize_items_duration =
-        (Utc::now() - group_door_prize_items_start).as_seconds_f64();
-
-    let get_efflorescences_start = Utc::now();
-    let db_efflorescences = efflorescence_dsl::efflorescence
-        .filter(efflorescence_dsl::event_id.eq_any(&all_event_ids))
-        .order_by((
-            efflorescence_dsl::event_id,
-            efflorescence_dsl::efflorescence_index,
-        ))
-        .select(DbEfflorescence::as_select())
-        .load(conn)?;
-    let _get_efflorescences_duration = (Utc::now() - get_efflorescences_start).as_seconds_f64();
-
-    let group_efflorescences_start = Utc::now();
-    let db_efflorescences =
-        group_child_table_results(&db_games_events, db_efflorescences, |r| r.event_id);
-    let _group_efflorescences_duration = (Utc::now() - group_efflorescences_start).as_seconds_f64();
-
-    let get_efflorescence_growths_start = Utc::now();
-    let db_efflorescence_growths = efflorescence_growth_dsl::efflorescence_growth
-        .filter(efflorescence_growth_dsl::event_id.eq_any(&all_event_ids))
-        .order_by((
-            efflorescence_growth_dsl::event_id,
-            efflorescence_growth_dsl::efflorescence_index,
-            efflorescence_growth_dsl::growth_index,
-        ))
-        .select(DbEfflorescenceGrowth::as_select())
-        .load(conn)?;
-    let _get_efflorescence_growths_duration =
-        (Utc::now() - get_efflorescence_growths_start).as_seconds_f64();
-
-    let group_efflorescence_growths_start = Utc::now();
-    let db_efflorescence_growths =
-        group_child_table_results(&db_games_events, db_efflorescence_growths, |r| r.event_id);
-    let _group_efflorescence_growths_duration =
-        (Utc::now() - group_efflorescence_growths_start).as_seconds_f64();
-
-    let get_wither_start = Utc::now();
-    let db_wither = wither_dsl::wither
-        .filter(wither_dsl::game_id.eq_any(&game_ids))
-        .order_by((wither_dsl::game_id, wither_dsl::attempt_game_event_index))
-        .select(DbWither::as_select())
-        .load(conn)?;
-    let _get_wither_duration = (Utc::now() - get_wither_start).as_seconds_f64();
-
-    let group_wither_start = Utc::now();
-    let db_wither: Vec<Vec<Vec<DbWither>>> =
-        group_wither_table_results(&db_games_events, db_wither);
-    let _group_wither_duration = (Utc::now() - group_wither_start).as_seconds_f64();
-
-    let get_cheer_start = Utc::now();
-    let db_cheer = event_cheers_dsl::event_cheers
-        .left_join(cheers_dsl::cheers.on(event_cheers_dsl::cheer_id.eq(cheers_dsl::id)))
-        .filter(event_cheers_dsl::event_id.eq_any(&all_event_ids))
-        .order_by(event_cheers_dsl::event_id)
-        .select((event_cheers_dsl::event_id, cheers_dsl::cheer.nullable()))
-        .load::<(i64, Option<String>)>(conn)?;
-    let _get_cheer_duration = (Utc::now() - get_cheer_start).as_seconds_f64();
-
-    let group_cheer_start = Utc::now();
-    let db_cheer = group_cheer_table_results(&db_games_events, db_cheer);
-    let _group_cheer_duration = (Utc::now() - group_cheer_start).as_seconds_f64();
-
-    let get_balk_reason_start = Utc::now();
-    let db_balk_reason = event_balk_reasons_dsl::event_balk_reasons
-        .left_join(balk_reasons_dsl::balk_reasons.on(event_balk_reasons_dsl::balk_reason_id.eq(balk_reasons_dsl::id)))
-        .filter(event_balk_reasons_dsl::event_id.eq_any(&all_event_ids))
-        .order_by(event_balk_reasons_dsl::event_id)
-        .select((event_balk_reasons_dsl::event_id, balk_reasons_dsl::balk_reason.nullable()))
-        .load::<(i64, Option<String>)>(conn)?;
-    let _get_balk_reason_duration = (Utc::now() - get_balk_reason_start).as_seconds_f64();
-
-    let group_balk_reason_start = Utc::now();
-    let db_balk_reason = group_balk_reason_table_results(&db_games_events, db_balk_reason);
-    let _group_balk_reason_duration = (Utc::now() - group_balk_reason_start).as_seconds_f64();
-
-    let post_process_start = Utc::now();
-    let result = itertools::izip!(
-        game_ids,
-        db_games_events,
-        db_runners,
-        db_fielders,
-        db_aurora_photos,
-        db_ejections,
-        db_failed_ejections,
-        db_door_prizes,
-        db_door_prize_items,
-        db_efflorescences,
-        db_efflorescence_growths,
-        db_wither,
-        db_cheer,
-        db_balk_reason,
-    )
-    .map(
-        |(
-            game_id,
-            events,
-            runners,
-            fielders,
-            aurora_photos,
-            ejections,
-            failed_ejections,
-            door_prizes,
-            door_prize_items,
-            efflorescence,
-            efflorescence_growths,
-            wither,
-            cheer,
-            balk_reason,
-        )| {
-            // Note: This should stay a vec of results. The individual results for each
-            // entry are semantically meaningful.
-            let detail_events = itertools::izip!(
-                events,
-                runners,
-                fielders,
-                aurora_photos,
-                ejections,
-                failed_ejections,
-                door_prizes,
-                door_prize_items,
-                efflorescence,
-                efflorescence_growths,
-                wither,
-                cheer,
-                balk_reason,
-            )
-            .map(
-                |(
-                    event,
-                    runners,
-                    fielders,
-                    aurora_photo,
-                    ejection,
-                    failed_ejection,
-                    door_prizes,
-                    door_prize_items,
-                    efflorescence,
-                    efflorescence_growths,
-                    wither,
-                    cheer,
-                    balk_reason,
-                )| {
-                    to_db_format::row_to_event(
-                        taxa,
-                        event,
-                        runners,
-                        fielders,
-                        aurora_photo,
-                        ejection,
-                        failed_ejection,
-                        door_prizes,
-                        door_prize_items,
-                        efflorescence,
-                        efflorescence_growths,
-                        wither,
-                        cheer,
-                        balk_reason,
-                    )
-                },
-            )
-            .collect_vec();
-            (game_id, detail_events)
-        },
-    )
-    .collect_vec();
-    let post_process_duration = (Utc::now() - post_process_start).as_seconds_f64();
-
-    Ok((
-        result,
-        EventsForGameTimings {
-            get_game_ids_duration,
-            get_events_duration,
-            group_events_duration,
-            get_runners_duration,
-            group_runners_duration,
-            get_fielders_duration,
-            group_fielders_duration,
-            post_process_duration,
-        },
-    ))
+    page_size: usize,
+    after_game_id: Option<&str>,
+    search: &GameSearch,
+) -> QueryResult<PageOfGames> {
+    page_of_games_keyset(conn, page_size, after_game_id, || search.to_query())
 }
 
 pub struct CompletedGameForDb<'g> {
     pub id: &'g str,
     pub raw_game: &'g mmolb_parsing::Game,
     pub events: Vec<EventDetail<&'g str>>,
+    // Parallel to `events`
+    pub event_defenses: Vec<EventDefense<&'g str>>,
+    pub plate_appearance_workloads: Vec<PlateAppearanceWorkload>,
+    pub pitch_sequence: Vec<PitchSequenceEntry>,
     pub pitcher_changes: Vec<PitcherChange<&'g str>>,
     pub parties: Vec<PartyEvent<&'g str>>,
     pub withers: Vec<WitherOutcome<&'g str>>,
@@ -848,6 +590,14 @@ pub enum GameForDb<'g> {
     },
 }
 
+/// Start time of a game, derived from its MMOLB id. See
+/// [`GameForDb::started_at`].
+pub fn game_started_at_from_id(game_id: &str) -> Option<DateTime<Utc>> {
+    let timestamp = game_id.get(..8)?;
+    let seconds = u32::from_str_radix(timestamp, 16).ok()?;
+    DateTime::from_timestamp(seconds.into(), 0)
+}
+
 impl<'g> GameForDb<'g> {
     pub fn metadata(&self) -> (&'g str, DateTime<Utc>) {
         match self {
@@ -880,6 +630,18 @@ impl<'g> GameForDb<'g> {
         }
     }
 
+    /// When this game started. MMOLB game ids are Mongo ObjectIds, whose
+    /// first 8 hex digits are the unix timestamp of when the game was
+    /// created. If the id somehow doesn't look like that, this falls back
+    /// to the first version of the game we saw.
+    pub fn started_at(&self) -> DateTime<Utc> {
+        let (game_id, from_version) = self.metadata();
+        game_started_at_from_id(game_id).unwrap_or_else(|| {
+            warn!("Couldn't get a start time from game id {game_id:?}. Using its version instead.");
+            from_version
+        })
+    }
+
     pub fn raw_game(&self) -> Option<&'g mmolb_parsing::Game> {
         match self {
             GameForDb::Ongoing { raw_game, .. } => Some(raw_game),
@@ -899,12 +661,34 @@ impl<'g> GameForDb<'g> {
     }
 }
 
+/// How `insert_games` inserts events and finds out their ids
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum EventInsertMode {
+    /// Stream events with a binary COPY, then query their ids back by
+    /// `(game_id, game_event_index)`. This is the fastest.
+    #[default]
+    Copy,
+    /// Insert events in chunks and get each one's id from `RETURNING`, so
+    /// ids never depend on reading rows back
+    Returning,
+}
+
+/// How many events are inserted per statement with
+/// [`EventInsertMode::Returning`]. Each event has 33 binds, and Postgres
+/// allows at most 65535 per statement.
+const RETURNING_EVENTS_CHUNK_SIZE: usize = 1000;
+
 pub struct InsertGamesTimings {
     pub delete_old_games_duration: f64,
     pub update_weather_table_duration: f64,
     pub insert_games_duration: f64,
     pub insert_logs_duration: f64,
     pub insert_events_duration: f64,
+    /// The part of `insert_events_duration` spent encoding event rows
+    pub encode_events_duration: f64,
+    /// The part of `insert_events_duration` spent writing encoded event rows
+    /// to the connection. The rest is Postgres finishing the COPY.
+    pub send_events_duration: f64,
     pub get_event_ids_duration: f64,
     pub insert_baserunners_duration: f64,
     pub insert_fielders_duration: f64,
@@ -914,6 +698,7 @@ pub fn insert_games(
     conn: &mut PgConnection,
     taxa: &Taxa,
     games: &[GameForDb],
+    event_insert_mode: EventInsertMode,
 ) -> QueryResult<InsertGamesTimings> {
     let all_cheer_messages = games.iter()
         .flat_map(|game| match game {
@@ -943,7 +728,16 @@ pub fn insert_games(
 
     let balk_reason_table = balk_reasons::create_balk_reasons_table(conn, &all_balk_reason_messages)?;
 
-    conn.transaction(|conn| insert_games_internal(conn, taxa, games, cheer_table, balk_reason_table))
+    conn.transaction(|conn| {
+        insert_games_internal(
+            conn,
+            taxa,
+            games,
+            event_insert_mode,
+            cheer_table,
+            balk_reason_table,
+        )
+    })
 }
 
 fn insert_aurora_photos<'e>(
@@ -1190,6 +984,65 @@ fn insert_pitcher_changes<'e>(
     Ok(())
 }
 
+fn insert_game_innings(
+    conn: &mut PgConnection,
+    completed_games: &[(i64, &CompletedGameForDb)],
+) -> QueryResult<()> {
+    let new_game_innings: Vec<_> = completed_games
+        .iter()
+        .flat_map(|(game_id, game)| to_db_format::game_innings_to_rows(*game_id, &game.events))
+        .collect();
+
+    let n_game_innings_to_insert = new_game_innings.len();
+    let n_game_innings_inserted =
+        diesel::copy_from(crate::schema::data_schema::data::game_innings::dsl::game_innings)
+            .from_insertable(&new_game_innings)
+            .execute(conn)?;
+
+    log_only_assert!(
+        n_game_innings_to_insert == n_game_innings_inserted,
+        "game_innings insert should have inserted {} rows, but it inserted {}",
+        n_game_innings_to_insert,
+        n_game_innings_inserted,
+    );
+
+    Ok(())
+}
+
+fn insert_pitcher_appearances<'e>(
+    conn: &mut PgConnection,
+    taxa: &Taxa,
+    completed_games: &[(i64, &CompletedGameForDb)],
+) -> QueryResult<()> {
+    let new_pitcher_appearances: Vec<_> = completed_games
+        .iter()
+        .flat_map(|(game_id, game)| {
+            to_db_format::pitcher_appearances_to_rows(
+                taxa,
+                *game_id,
+                &game.events,
+                &game.pitcher_changes,
+            )
+        })
+        .collect();
+
+    let n_pitcher_appearances_to_insert = new_pitcher_appearances.len();
+    let n_pitcher_appearances_inserted = diesel::copy_from(
+        crate::schema::data_schema::data::pitcher_appearances::dsl::pitcher_appearances,
+    )
+    .from_insertable(&new_pitcher_appearances)
+    .execute(conn)?;
+
+    log_only_assert!(
+        n_pitcher_appearances_to_insert == n_pitcher_appearances_inserted,
+        "pitcher_appearances insert should have inserted {} rows, but it inserted {}",
+        n_pitcher_appearances_to_insert,
+        n_pitcher_appearances_inserted,
+    );
+
+    Ok(())
+}
+
 fn insert_parties<'e>(
     conn: &mut PgConnection,
     taxa: &Taxa,
@@ -1344,6 +1197,200 @@ fn insert_cheers<'e>(
     Ok(())
 }
 
+fn insert_win_probabilities(
+    conn: &mut PgConnection,
+    event_ids_by_game: &Vec<(i64, Vec<i64>)>,
+    completed_games: &[(i64, &CompletedGameForDb)],
+) -> QueryResult<()> {
+    let new_win_probabilities: Vec<_> = iter::zip(event_ids_by_game, completed_games)
+        .flat_map(|((game_id_from_event_ids, event_ids), (game_id_from_games, game))| {
+            assert_eq!(game_id_from_event_ids, game_id_from_games);
+            iter::zip(event_ids, &game.events).map(|(event_id, event)| {
+                win_probability::event_to_win_probability(*event_id, event)
+            })
+        })
+        .collect();
+
+    let n_win_probabilities_to_insert = new_win_probabilities.len();
+    let n_win_probabilities_inserted = diesel::copy_from(
+        crate::schema::data_schema::data::event_win_probability::dsl::event_win_probability,
+    )
+    .from_insertable(&new_win_probabilities)
+    .execute(conn)?;
+
+    log_only_assert!(
+        n_win_probabilities_to_insert == n_win_probabilities_inserted,
+        "Win probabilities insert should have inserted {} rows, but it inserted {}",
+        n_win_probabilities_to_insert,
+        n_win_probabilities_inserted,
+    );
+
+    Ok(())
+}
+
+fn insert_event_defenses(
+    conn: &mut PgConnection,
+    event_ids_by_game: &Vec<(i64, Vec<i64>)>,
+    completed_games: &[(i64, &CompletedGameForDb)],
+) -> QueryResult<()> {
+    let mut new_event_defenses = Vec::new();
+    for ((game_id_from_event_ids, event_ids), (game_id_from_games, game)) in
+        iter::zip(event_ids_by_game, completed_games)
+    {
+        assert_eq!(game_id_from_event_ids, game_id_from_games);
+        // Zipping would silently attach defenses to the wrong events
+        if event_ids.len() != game.event_defenses.len() {
+            return Err(QueryError::SerializationError(
+                format!(
+                    "Game {game_id_from_games} has {} events but {} event defenses",
+                    event_ids.len(),
+                    game.event_defenses.len(),
+                )
+                .into(),
+            ));
+        }
+        new_event_defenses.extend(iter::zip(event_ids, &game.event_defenses).map(
+            |(event_id, defense)| to_db_format::event_defense_to_row(*event_id, defense),
+        ));
+    }
+
+    let n_event_defenses_to_insert = new_event_defenses.len();
+    let n_event_defenses_inserted = diesel::copy_from(
+        crate::schema::data_schema::data::event_defense::dsl::event_defense,
+    )
+    .from_insertable(&new_event_defenses)
+    .execute(conn)?;
+
+    log_only_assert!(
+        n_event_defenses_to_insert == n_event_defenses_inserted,
+        "Event defense insert should have inserted {} rows, but it inserted {}",
+        n_event_defenses_to_insert,
+        n_event_defenses_inserted,
+    );
+
+    Ok(())
+}
+
+fn insert_plate_appearances(
+    conn: &mut PgConnection,
+    event_ids_by_game: &Vec<(i64, Vec<i64>)>,
+    completed_games: &[(i64, &CompletedGameForDb)],
+) -> QueryResult<()> {
+    let new_plate_appearances: Vec<_> = iter::zip(event_ids_by_game, completed_games)
+        .flat_map(|((game_id_from_event_ids, event_ids), (game_id_from_games, game))| {
+            assert_eq!(game_id_from_event_ids, game_id_from_games);
+            let event_id_by_index: HashMap<usize, i64> = iter::zip(&game.events, event_ids)
+                .map(|(event, event_id)| (event.game_event_index, *event_id))
+                .collect();
+            // A plate appearance without an event would be a bug in the sim.
+            // It's logged and left out rather than failing the whole batch.
+            game.plate_appearance_workloads.iter().filter_map(move |workload| {
+                let Some(&event_id) = event_id_by_index.get(&workload.game_event_index) else {
+                    tracing::error!(
+                        "Plate appearance ending at game event index {} of game \
+                        {game_id_from_games} has no inserted event",
+                        workload.game_event_index,
+                    );
+                    return None;
+                };
+
+                Some(NewPlateAppearance {
+                    event_id,
+                    pitcher_batters_faced_before: workload.pitcher_batters_faced_before,
+                    times_through_order: workload.times_through_order,
+                    pitcher_pitches_before: workload.pitcher_pitches_before,
+                    pitches: workload.pitches,
+                })
+            })
+        })
+        .collect();
+
+    let n_plate_appearances_to_insert = new_plate_appearances.len();
+    let n_plate_appearances_inserted = diesel::copy_from(
+        crate::schema::data_schema::data::plate_appearances::dsl::plate_appearances,
+    )
+    .from_insertable(&new_plate_appearances)
+    .execute(conn)?;
+
+    log_only_assert!(
+        n_plate_appearances_to_insert == n_plate_appearances_inserted,
+        "Plate appearance insert should have inserted {} rows, but it inserted {}",
+        n_plate_appearances_to_insert,
+        n_plate_appearances_inserted,
+    );
+
+    Ok(())
+}
+
+fn insert_pitch_sequences(
+    conn: &mut PgConnection,
+    taxa: &Taxa,
+    event_ids_by_game: &Vec<(i64, Vec<i64>)>,
+    completed_games: &[(i64, &CompletedGameForDb)],
+) -> QueryResult<()> {
+    let new_pitch_sequences: Vec<_> = iter::zip(event_ids_by_game, completed_games)
+        .flat_map(|((game_id_from_event_ids, event_ids), (game_id_from_games, game))| {
+            assert_eq!(game_id_from_event_ids, game_id_from_games);
+            let event_by_index: HashMap<usize, (i64, &EventDetail<&str>)> =
+                iter::zip(event_ids, &game.events)
+                    .map(|(event_id, event)| (event.game_event_index, (*event_id, event)))
+                    .collect();
+            // An entry without an event would be a bug in the sim. It's
+            // logged and left out rather than failing the whole batch.
+            game.pitch_sequence.iter().filter_map(move |entry| {
+                let Some(&(event_id, event)) = event_by_index.get(&entry.game_event_index) else {
+                    tracing::error!(
+                        "Pitch at game event index {} of game {game_id_from_games} has no \
+                        inserted event",
+                        entry.game_event_index,
+                    );
+                    return None;
+                };
+                let previous_pitch_event_id = match entry.previous_pitch_game_event_index {
+                    None => None,
+                    Some(index) => match event_by_index.get(&index) {
+                        Some(&(previous_event_id, _)) => Some(previous_event_id),
+                        None => {
+                            tracing::error!(
+                                "Previous pitch at game event index {index} of game \
+                                {game_id_from_games} has no inserted event",
+                            );
+                            return None;
+                        }
+                    },
+                };
+
+                Some(NewEventPitchSequence {
+                    event_id,
+                    pitch_of_plate_appearance: entry.pitch_of_plate_appearance,
+                    previous_pitch_event_id,
+                    count_before: TaxaCount::from_balls_strikes(
+                        event.balls_before,
+                        event.strikes_before,
+                    )
+                    .map(|count| taxa.count_id(count)),
+                })
+            })
+        })
+        .collect();
+
+    let n_pitch_sequences_to_insert = new_pitch_sequences.len();
+    let n_pitch_sequences_inserted = diesel::copy_from(
+        crate::schema::data_schema::data::event_pitch_sequence::dsl::event_pitch_sequence,
+    )
+    .from_insertable(&new_pitch_sequences)
+    .execute(conn)?;
+
+    log_only_assert!(
+        n_pitch_sequences_to_insert == n_pitch_sequences_inserted,
+        "Pitch sequence insert should have inserted {} rows, but it inserted {}",
+        n_pitch_sequences_to_insert,
+        n_pitch_sequences_inserted,
+    );
+
+    Ok(())
+}
+
 fn insert_balk_reasons<'e>(
     conn: &mut PgConnection,
     event_ids_by_game: &Vec<(i64, Vec<i64>)>,
@@ -1383,6 +1430,7 @@ fn insert_games_internal<'e>(
     conn: &mut PgConnection,
     taxa: &Taxa,
     games: &[GameForDb],
+    event_insert_mode: EventInsertMode,
     cheer_table: cheers::CheerTable,
     balk_reason_table: balk_reasons::BalkReasonTable,
 ) -> QueryResult<InsertGamesTimings> {
@@ -1402,6 +1450,10 @@ fn insert_games_internal<'e>(
         .map(|(id, _)| id)
         .collect_vec();
 
+    // The old games' events have to come out of the summaries before the
+    // events themselves are deleted
+    outcome_summaries::subtract_outcome_summaries(conn, &game_mmolb_ids)?;
+
     diesel::delete(games_dsl::games)
         .filter(games_dsl::mmolb_game_id.eq_any(game_mmolb_ids))
         .execute(conn)?;
@@ -1416,6 +1468,7 @@ fn insert_games_internal<'e>(
         .iter()
         .map(|game| {
             let (game_id, from_version) = game.metadata();
+            let started_at = game.started_at();
             let Some(raw_game) = game.raw_game() else {
                 // TODO Is there a more elegant solution than a defaulted game?
                 // Get an arbitrary weather. This is a bad solution.
@@ -1451,6 +1504,7 @@ fn insert_games_internal<'e>(
                     home_team_photo_contest_score: None,
                     away_team_photo_contest_top_scorer: None,
                     away_team_photo_contest_score: None,
+                    started_at,
                 };
             };
 
@@ -1511,6 +1565,7 @@ fn insert_games_internal<'e>(
                     away_team_photo_contest_top_scorer: completed_game
                         .away_team_photo_contest_top_scorer,
                     away_team_photo_contest_score: completed_game.away_team_photo_contest_score,
+                    started_at,
                 },
                 _ => NewGame {
                     mmolb_game_id: game_id,
@@ -1537,6 +1592,7 @@ fn insert_games_internal<'e>(
                     home_team_photo_contest_score: None,
                     away_team_photo_contest_top_scorer: None,
                     away_team_photo_contest_score: None,
+                    started_at,
                 },
             }
         })
@@ -1566,6 +1622,9 @@ fn insert_games_internal<'e>(
                     log_index: 0,           // there's only ever one
                     log_level: 3,           // info
                     log_text: "This is a bugged terminally-incomplete game. It will never be ingested.",
+                    error_code: Some(
+                        taxa.ingest_error_code_id(TaxaIngestErrorCode::GameForeverIncomplete),
+                    ),
                 }))
             },
             GameForDb::Completed { game, .. } => Some(Either::Left((*game_id, game))),
@@ -1576,6 +1635,9 @@ fn insert_games_internal<'e>(
                     log_index: 0,           // there's only ever one
                     log_level: 3,           // info
                     log_text: reason,
+                    error_code: Some(
+                        taxa.ingest_error_code_id(TaxaIngestErrorCode::GameNotSupported),
+                    ),
                 }))
             },
             GameForDb::FatalError { error_message, .. } => {
@@ -1585,6 +1647,9 @@ fn insert_games_internal<'e>(
                     log_index: 0,           // there's only ever one
                     log_level: 0,           // critical
                     log_text: error_message,
+                    error_code: Some(
+                        taxa.ingest_error_code_id(TaxaIngestErrorCode::GameFatalError),
+                    ),
                 }))
             }
             GameForDb::DeserializeError { error_message, .. } => {
@@ -1594,6 +1659,9 @@ fn insert_games_internal<'e>(
                     log_index: 0,           // there's only ever one (this overrides FatalError)
                     log_level: 0,           // critical
                     log_text: error_message,
+                    error_code: Some(
+                        taxa.ingest_error_code_id(TaxaIngestErrorCode::GameDeserializeError),
+                    ),
                 }))
             }
         })
@@ -1617,6 +1685,7 @@ fn insert_games_internal<'e>(
                             log_index: log_index as i32,
                             log_level: log.log_level,
                             log_text: &log.log_text,
+                            error_code: log.error_code.map(|code| taxa.ingest_error_code_id(code)),
                         }
                     })
                 })
@@ -1638,19 +1707,50 @@ fn insert_games_internal<'e>(
     let insert_logs_duration = (Utc::now() - insert_logs_start).as_seconds_f64();
 
     let insert_events_start = Utc::now();
-    let new_events: Vec<_> = completed_games
+    let n_events_to_insert = completed_games
         .iter()
-        .flat_map(|(game_id, game)| {
-            game.events
+        .map(|(_, game)| game.events.len())
+        .sum::<usize>();
+    let (n_events_inserted, returned_event_ids, copy_events_timings) = match event_insert_mode {
+        EventInsertMode::Copy => {
+            // Events are by far the biggest table, so rows are encoded straight
+            // into the COPY stream rather than collected into a Vec first
+            let games = completed_games
                 .iter()
-                .map(|event| to_db_format::event_to_row(taxa, *game_id, event))
-        })
-        .collect();
-
-    let n_events_to_insert = new_events.len();
-    let n_events_inserted = diesel::copy_from(events_dsl::events)
-        .from_insertable(&new_events)
-        .execute(conn)?;
+                .map(|(game_id, game)| (*game_id, game.events.as_slice()))
+                .collect_vec();
+            let (n_events_inserted, timings) = copy_binary::copy_event_rows(conn, taxa, &games)?;
+            (n_events_inserted, None, timings)
+        }
+        EventInsertMode::Returning => {
+            let new_events = completed_games
+                .iter()
+                .flat_map(|(game_id, game)| {
+                    game.events
+                        .iter()
+                        .map(|event| to_db_format::event_to_row(taxa, *game_id, event))
+                })
+                .collect_vec();
+            let mut event_ids = Vec::with_capacity(new_events.len());
+            for chunk in new_events.chunks(RETURNING_EVENTS_CHUNK_SIZE) {
+                event_ids.extend(
+                    diesel::insert_into(events_dsl::events)
+                        .values(chunk)
+                        .returning((
+                            events_dsl::game_id,
+                            events_dsl::game_event_index,
+                            events_dsl::id,
+                        ))
+                        .get_results::<(i64, i32, i64)>(conn)?,
+                );
+            }
+            (
+                event_ids.len(),
+                Some(event_ids),
+                copy_binary::EventCopyTimings::default(),
+            )
+        }
+    };
 
     log_only_assert!(
         n_events_to_insert == n_events_inserted,
@@ -1659,22 +1759,62 @@ fn insert_games_internal<'e>(
         n_events_inserted,
     );
     let insert_events_duration = (Utc::now() - insert_events_start).as_seconds_f64();
+    let encode_events_duration = copy_events_timings.encode_duration;
+    let send_events_duration = copy_events_timings.send_duration;
+
+    let update_outcome_summaries_start = Utc::now();
+    outcome_summaries::add_outcome_summaries(conn, &game_ids)?;
+    let _update_outcome_summaries_duration =
+        (Utc::now() - update_outcome_summaries_start).as_seconds_f64();
 
     let get_event_ids_start = Utc::now();
-    // Postgres' copy doesn't support returning ids, but we need them, so we query them from scratch
-    let event_ids = events_dsl::events
-        .filter(events_dsl::game_id.eq_any(&game_ids))
-        .select((events_dsl::game_id, events_dsl::id))
-        .order_by(events_dsl::game_id)
-        .then_order_by(events_dsl::game_event_index)
-        .get_results::<(i64, i64)>(conn)?;
-
-    let event_ids_by_game = event_ids
-        .into_iter()
-        .chunk_by(|(game_id, _)| *game_id)
+    let event_ids = match returned_event_ids {
+        Some(event_ids) => event_ids,
+        // Postgres' copy doesn't support returning ids, so they're queried from
+        // scratch. (game_id, game_event_index) is unique, so this can't pick up
+        // another writer's rows.
+        None => events_dsl::events
+            .filter(events_dsl::game_id.eq_any(&game_ids))
+            .select((
+                events_dsl::game_id,
+                events_dsl::game_event_index,
+                events_dsl::id,
+            ))
+            .get_results::<(i64, i32, i64)>(conn)?,
+    };
+
+    // Ids are matched to events by key rather than by position, so they line
+    // up with each game's events no matter what order they came back in
+    let event_id_by_index = event_ids
         .into_iter()
-        .map(|(game_id, group)| (game_id, group.map(|(_, event_id)| event_id).collect_vec()))
-        .collect_vec();
+        .map(|(game_id, game_event_index, event_id)| ((game_id, game_event_index), event_id))
+        .collect::<HashMap<_, _>>();
+    let event_ids_by_game = completed_games
+        .iter()
+        .map(|(game_id, game)| {
+            let event_ids = game
+                .events
+                .iter()
+                .map(|event| {
+                    // Failing here rolls back the whole batch, which is better
+                    // than attaching child rows to the wrong events
+                    event_id_by_index
+                        .get(&(*game_id, event.game_event_index as i32))
+                        .copied()
+                        .ok_or_else(|| {
+                            QueryError::DeserializationError(
+                                format!(
+                                    "Event {} of game {game_id} has no id after the events insert",
+                                    event.game_event_index,
+                                )
+                                .into(),
+                            )
+                        })
+                })
+                .collect::<QueryResult<Vec<_>>>()?;
+            Ok((*game_id, event_ids))
+        })
+        .collect::<QueryResult<Vec<_>>>()?;
     let get_event_ids_duration = (Utc::now() - get_event_ids_start).as_seconds_f64();
 
     let insert_baserunners_start = Utc::now();
@@ -1727,6 +1867,11 @@ fn insert_games_internal<'e>(
     );
     let insert_fielders_duration = (Utc::now() - insert_fielders_start).as_seconds_f64();
 
+    let resolve_player_identities_start = Utc::now();
+    resolve_player_identities(conn, &game_ids)?;
+    let _resolve_player_identities_duration =
+        (Utc::now() - resolve_player_identities_start).as_seconds_f64();
+
     let insert_aurora_photos_start = Utc::now();
     insert_aurora_photos(conn, taxa, &event_ids_by_game, &completed_games)?;
     let _insert_aurora_photos_duration = (Utc::now() - insert_aurora_photos_start).as_seconds_f64();
@@ -1751,6 +1896,16 @@ fn insert_games_internal<'e>(
     let _insert_pitcher_changes_duration =
         (Utc::now() - insert_pitcher_changes_start).as_seconds_f64();
 
+    let insert_pitcher_appearances_start = Utc::now();
+    insert_pitcher_appearances(conn, taxa, &completed_games)?;
+    let _insert_pitcher_appearances_duration =
+        (Utc::now() - insert_pitcher_appearances_start).as_seconds_f64();
+
+    let insert_game_innings_start = Utc::now();
+    insert_game_innings(conn, &completed_games)?;
+    let _insert_game_innings_duration =
+        (Utc::now() - insert_game_innings_start).as_seconds_f64();
+
     let insert_parties_start = Utc::now();
     insert_parties(conn, taxa, &completed_games)?;
     let _insert_parties_duration = (Utc::now() - insert_parties_start).as_seconds_f64();
@@ -1774,12 +1929,34 @@ fn insert_games_internal<'e>(
     let _insert_balk_reasons_duration =
         (Utc::now() - insert_balk_reasons_start).as_seconds_f64();
 
+    let insert_event_defenses_start = Utc::now();
+    insert_event_defenses(conn, &event_ids_by_game, &completed_games)?;
+    let _insert_event_defenses_duration =
+        (Utc::now() - insert_event_defenses_start).as_seconds_f64();
+
+    let insert_plate_appearances_start = Utc::now();
+    insert_plate_appearances(conn, &event_ids_by_game, &completed_games)?;
+    let _insert_plate_appearances_duration =
+        (Utc::now() - insert_plate_appearances_start).as_seconds_f64();
+
+    let insert_pitch_sequences_start = Utc::now();
+    insert_pitch_sequences(conn, taxa, &event_ids_by_game, &completed_games)?;
+    let _insert_pitch_sequences_duration =
+        (Utc::now() - insert_pitch_sequences_start).as_seconds_f64();
+
+    let insert_win_probabilities_start = Utc::now();
+    insert_win_probabilities(conn, &event_ids_by_game, &completed_games)?;
+    let _insert_win_probabilities_duration =
+        (Utc::now() - insert_win_probabilities_start).as_seconds_f64();
+
     Ok(InsertGamesTimings {
         delete_old_games_duration,
         update_weather_table_duration,
         insert_games_duration,
         insert_logs_duration,
         insert_events_duration,
+        encode_events_duration,
+        send_events_duration,
         get_event_ids_duration,
         insert_baserunners_duration,
         insert_fielders_duration,
@@ -1788,6 +1965,7 @@ fn insert_games_internal<'e>(
 
 pub fn insert_additional_ingest_logs(
     conn: &mut PgConnection,
+    taxa: &Taxa,
     extra_ingest_logs: &[(i64, Vec<IngestLog>)],
 ) -> QueryResult<()> {
     use crate::info_schema::info::event_ingest_log::dsl as event_ingest_log_dsl;
@@ -1836,6 +2014,9 @@ pub fn insert_additional_ingest_logs(
                         log_index: *log_index,
                         log_level: ingest_log.log_level,
                         log_text: &ingest_log.log_text,
+                        error_code: ingest_log
+                            .error_code
+                            .map(|code| taxa.ingest_error_code_id(code)),
                     }
                 })
                 // The intermediate vec is for lifetime reasons
@@ -1850,9 +2031,55 @@ pub fn insert_additional_ingest_logs(
     Ok(())
 }
 
+/// Stores the values that had to be set aside for each game to deserialize
+/// in `data.games.extra`, along with a game-wide warning explaining why.
+/// Each item is (mmolb game id, values keyed by JSON pointer, warning text).
+/// The games must already have been inserted.
+pub fn record_games_set_aside_values(
+    conn: &mut PgConnection,
+    taxa: &Taxa,
+    set_aside: &[(&str, serde_json::Value, String)],
+) -> QueryResult<()> {
+    use crate::data_schema::data::games::dsl as games_dsl;
+    use crate::info_schema::info::event_ingest_log::dsl as event_ingest_log_dsl;
+
+    let error_code = taxa.ingest_error_code_id(TaxaIngestErrorCode::GameValuesSetAside);
+    for (mmolb_game_id, values, log_text) in set_aside {
+        let game_id = diesel::update(games_dsl::games)
+            .filter(games_dsl::mmolb_game_id.eq(*mmolb_game_id))
+            .set(games_dsl::extra.eq(values))
+            .returning(games_dsl::id)
+            .get_result::<i64>(conn)?;
+
+        // Other game-wide logs may already exist for this game
+        let highest_log_index = event_ingest_log_dsl::event_ingest_log
+            .filter(event_ingest_log_dsl::game_id.eq(game_id))
+            .filter(event_ingest_log_dsl::game_event_index.is_null())
+            .select(diesel::dsl::max(event_ingest_log_dsl::log_index))
+            .get_result::<Option<i32>>(conn)?;
+
+        diesel::insert_into(event_ingest_log_dsl::event_ingest_log)
+            .values(NewEventIngestLog {
+                game_id,
+                game_event_index: None, // None => applies to the entire game
+                log_index: highest_log_index.map_or(0, |n| n + 1),
+                log_level: 2, // warning
+                log_text,
+                error_code: Some(error_code),
+            })
+            .execute(conn)?;
+    }
+
+    Ok(())
+}
+
 pub struct DbFullGameWithLogs {
     pub game: DbGame,
     pub game_wide_logs: Vec<DbEventIngestLog>,
+    /// How many events the game has, including any outside `events`
+    pub num_events: usize,
+    /// The game event indices of `raw_events_with_logs`
+    pub events: Range<usize>,
     pub raw_events_with_logs: Vec<(mmolb_parsing::game::Event, Vec<DbEventIngestLog>)>,
 }
 
@@ -1863,13 +2090,33 @@ pub enum QueryDeserializeError {
 
     #[error(transparent)]
     Deserialize(#[from] serde_json::error::Error),
+
+    #[error("the raw game has been offloaded to object storage at {0:?}")]
+    Offloaded(String),
+}
+
+#[derive(QueryableByName)]
+struct RawGameEventCount {
+    #[diesel(sql_type = Integer)]
+    num_events: i32,
+    #[diesel(sql_type = Nullable<Text>)]
+    offloaded_to: Option<String>,
+}
+
+#[derive(QueryableByName)]
+struct RawGameEvent {
+    #[diesel(sql_type = Jsonb)]
+    event_raw: serde_json::Value,
 }
 
+/// A game, its game-wide logs, and the events that `choose_events` picks
+/// given the number of events in the game. Only the chosen events and their
+/// logs are read, so a window into a long game doesn't load all of it.
 pub fn game_and_raw_events(
     conn: &mut PgConnection,
     mmolb_game_id: &str,
+    choose_events: impl FnOnce(usize) -> Range<usize>,
 ) -> Result<DbFullGameWithLogs, QueryDeserializeError> {
-    use crate::data_schema::data::entities::dsl as entities_dsl;
     use crate::data_schema::data::games::dsl as games_dsl;
     use crate::info_schema::info::event_ingest_log::dsl as event_ingest_log_dsl;
 
@@ -1878,15 +2125,52 @@ pub fn game_and_raw_events(
         .select(DbGame::as_select())
         .get_result::<DbGame>(conn)?;
 
-    let raw_game = entities_dsl::entities
-        .filter(entities_dsl::entity_id.eq(mmolb_game_id))
-        .select(entities_dsl::data)
-        .get_result::<serde_json::Value>(conn)?;
+    let count = sql_query(
+        "
+        select jsonb_array_length(coalesce(e.data->'EventLog', '[]'::jsonb)) as num_events,
+            e.offloaded_to
+        from data.entities e
+        where e.kind='game' and e.entity_id=$1 and e.source is null
+    ",
+    )
+    .bind::<Text, _>(mmolb_game_id)
+    .get_result::<RawGameEventCount>(conn)?;
+    if let Some(key) = count.offloaded_to {
+        return Err(QueryDeserializeError::Offloaded(key));
+    }
+
+    let num_events = count.num_events as usize;
+    let events = choose_events(num_events);
+    let events = events.start.min(num_events)..events.end.min(num_events);
 
-    let raw_game: mmolb_parsing::Game = serde_json::from_value(raw_game)?;
+    // `with ordinality` counts from 1
+    let raw_events = sql_query(
+        "
+        select ev.event_raw
+        from data.entities e
+        cross join lateral jsonb_array_elements(e.data->'EventLog')
+            with ordinality as ev(event_raw, ordinality)
+        where e.kind='game' and e.entity_id=$1 and e.source is null
+            and ev.ordinality > $2 and ev.ordinality <= $3
+        order by ev.ordinality
+    ",
+    )
+    .bind::<Text, _>(mmolb_game_id)
+    .bind::<BigInt, _>(events.start as i64)
+    .bind::<BigInt, _>(events.end as i64)
+    .get_results::<RawGameEvent>(conn)?
+    .into_iter()
+    .map(|row| serde_json::from_value::<mmolb_parsing::game::Event>(row.event_raw))
+    .collect::<Result<Vec<_>, _>>()?;
 
     let mut raw_logs = event_ingest_log_dsl::event_ingest_log
         .filter(event_ingest_log_dsl::game_id.eq(game.id))
+        .filter(
+            event_ingest_log_dsl::game_event_index
+                .is_null()
+                .or(event_ingest_log_dsl::game_event_index
+                    .between(events.start as i32, events.end as i32 - 1)),
+        )
         .order_by(event_ingest_log_dsl::game_event_index.asc().nulls_first())
         .then_order_by(event_ingest_log_dsl::log_index.asc())
         .get_results::<DbEventIngestLog>(conn)?
@@ -1898,11 +2182,9 @@ pub fn game_and_raw_events(
         game_wide_logs.push(event);
     }
 
-    let logs_by_event = raw_game
-        .event_log
-        .iter()
-        .enumerate()
-        .map(|(game_event_index, _)| {
+    let logs_by_event = events
+        .clone()
+        .map(|game_event_index| {
             let mut events = Vec::new();
             while let Some(event) =
                 raw_logs.next_if(|log| log.game_event_index.expect("All logs with a None game_event_index should have been extracted before this loop began") == game_event_index as i32)
@@ -1915,8 +2197,7 @@ pub fn game_and_raw_events(
 
     assert!(raw_logs.next().is_none(), "Failed to map all raw logs");
 
-    let raw_events_with_logs = raw_game
-        .event_log
+    let raw_events_with_logs = raw_events
         .into_iter()
         .zip(logs_by_event)
         .collect::<Vec<_>>();
@@ -1924,6 +2205,8 @@ pub fn game_and_raw_events(
     Ok(DbFullGameWithLogs {
         game,
         game_wide_logs,
+        num_events,
+        events,
         raw_events_with_logs,
     })
 }
@@ -1979,10 +2262,11 @@ pub fn games_progress(
             timespan_bucket(valid_from, $1, $2) as bucket_index,
             count(*) as count
         from data.entities
+        where source is null
         group by bucket_index
         order by bucket_index
     ")
-        .bind::<Timestamp, _>(history_start.naive_utc())
+        .bind::<Timestamptz, _>(history_start)
         .bind::<Interval, _>(time_step);
     println!("{}", diesel::debug_query::<diesel::pg::Pg, _>(&q));
     let progress_entries = q
@@ -2045,11 +2329,11 @@ pub fn versions_progress(
             timespan_bucket(valid_from, $1, $2) as bucket_index,
             count(*) as count
         from data.versions
-        where kind=$3
+        where kind=$3 and source is null
         group by bucket_index
         order by bucket_index
     ")
-        .bind::<Timestamp, _>(history_start.naive_utc())
+        .bind::<Timestamptz, _>(history_start)
         .bind::<Interval, _>(time_step)
         .bind::<Text, _>(kind)
         .get_results::<DbProgressEntry>(conn)?;
@@ -2069,7 +2353,7 @@ pub fn versions_progress(
         group by bucket_index
         order by bucket_index
     ")
-        .bind::<Timestamp, _>(history_start.naive_utc())
+        .bind::<Timestamptz, _>(history_start)
         .bind::<Interval, _>(time_step)
         .bind::<Text, _>(kind)
         .get_results::<DbProgressEntry>(conn)?;
@@ -2111,11 +2395,11 @@ pub fn feed_event_versions_progress(
             timespan_bucket(valid_from, $1, $2) as bucket_index,
             count(*) as count
         from data.feed_event_versions
-        where kind=$3
+        where kind=$3 and source is null
         group by bucket_index
         order by bucket_index
     ")
-        .bind::<Timestamp, _>(history_start.naive_utc())
+        .bind::<Timestamptz, _>(history_start)
         .bind::<Interval, _>(time_step)
         .bind::<Text, _>(kind)
         .get_results::<DbProgressEntry>(conn)?;
@@ -2135,7 +2419,7 @@ pub fn feed_event_versions_progress(
         group by bucket_index
         order by bucket_index
     ")
-        .bind::<Timestamp, _>(history_start.naive_utc())
+        .bind::<Timestamptz, _>(history_start)
         .bind::<Interval, _>(time_step)
         .bind::<Text, _>(kind)
         .get_results::<DbProgressEntry>(conn)?;
@@ -2162,6 +2446,34 @@ pub fn feed_event_versions_progress(
     })
 }
 
+/// Every row of every table in the `taxa` schema, as JSON objects keyed by
+/// column name. Taxa tables are small, so this is cheap.
+pub fn taxa_table_rows(
+    conn: &mut PgConnection,
+    catalog_name: &str,
+) -> Result<Vec<(String, Vec<serde_json::Value>)>, DbMetaQueryError> {
+    #[derive(QueryableByName)]
+    struct TaxaRow {
+        #[diesel(sql_type = Jsonb)]
+        row: serde_json::Value,
+    }
+
+    tables_for_schema(conn, catalog_name, "taxa")?
+        .into_iter()
+        .map(|table| {
+            // Table names can't be bound as parameters. This name comes from
+            // the database catalog, but quote it anyway to be safe.
+            let rows = sql_query(format!(
+                "select to_jsonb(t) as row from taxa.\"{}\" t order by t.id",
+                table.name.replace('"', "\"\""),
+            ))
+            .get_results::<TaxaRow>(conn)?;
+
+            Ok((table.name, rows.into_iter().map(|r| r.row).collect()))
+        })
+        .collect()
+}
+
 #[derive(Debug, Error)]
 pub enum DbMetaQueryError {
     #[error(transparent)]
@@ -2378,7 +2690,7 @@ pub fn insert_modifications(
     Ok(Some(results))
 }
 
-pub fn get_latest_player_valid_from(conn: &mut PgConnection) -> QueryResult<Option<NaiveDateTime>> {
+pub fn get_latest_player_valid_from(conn: &mut PgConnection) -> QueryResult<Option<DateTime<Utc>>> {
     use crate::data_schema::data::player_versions::dsl as pv_dsl;
 
     pv_dsl::player_versions
@@ -2414,6 +2726,7 @@ type NewPlayerFeedVersionExt<'a> = (
     Option<NewPlayerParadigmShift<'a>>,
     Vec<NewPlayerRecomposition<'a>>,
     Vec<NewVersionIngestLog<'a>>,
+    Option<NewFeedEventFailure<'a>>,
 );
 
 pub(crate) type NewPlayerVersionExt<'a> = (
@@ -2673,7 +2986,7 @@ pub fn insert_player_feed_versions<'container, 'game: 'container>(
     // Convert reference to tuple into tuple of references
     let new_player_feed_versions = new_player_feed_versions
         .into_iter()
-        .map(|(a, b, c, d, e)| (a, b, c, d, e));
+        .map(|(a, b, c, d, e, f)| (a, b, c, d, e, f));
 
     let (
         new_player_feed_events_processed,
@@ -2681,12 +2994,14 @@ pub fn insert_player_feed_versions<'container, 'game: 'container>(
         new_player_paradigm_shifts,
         new_player_recompositions,
         ingest_logs,
+        failures,
     ): (
         Vec<&NewFeedEventProcessed>,
         Vec<&Option<NewPlayerAttributeAugment>>,
         Vec<&Option<NewPlayerParadigmShift>>,
         Vec<&Vec<NewPlayerRecomposition>>,
         Vec<&Vec<NewVersionIngestLog>>,
+        Vec<&Option<NewFeedEventFailure>>,
     ) = itertools::multiunzip(new_player_feed_versions);
 
     let mut full_total = 0;
@@ -2712,6 +3027,7 @@ pub fn insert_player_feed_versions<'container, 'game: 'container>(
         .count();
     full_inserted += insert_player_recompositions(conn, new_player_recompositions)?;
     insert_nested_ingest_logs(conn, ingest_logs)?;
+    insert_feed_event_failures(conn, failures.into_iter().flatten().collect())?;
 
     // This is last so that we don't mark them as processed if there were db errors
     insert_feed_events_processed(conn, new_player_feed_events_processed)?;
@@ -2723,6 +3039,8 @@ type NewTeamFeedVersionExt<'a> = (
     NewFeedEventProcessed<'a>,
     Option<NewTeamGamePlayed<'a>>,
     Vec<NewVersionIngestLog<'a>>,
+    Option<NewFeedEventFailure<'a>>,
+    Option<NewTeamTransaction<'a>>,
 );
 
 fn insert_new_team_games_played(
@@ -2738,6 +3056,85 @@ fn insert_new_team_games_played(
         .execute(conn)
 }
 
+pub fn insert_team_transactions<'a>(
+    conn: &mut PgConnection,
+    new_team_transactions: impl IntoIterator<Item = &'a NewTeamTransaction<'a>>,
+) -> QueryResult<usize> {
+    use crate::data_schema::data::team_transactions::dsl as tt_dsl;
+    let new_team_transactions = new_team_transactions.into_iter().collect_vec();
+
+    // Feed events can be processed more than once, and a transaction is
+    // identified by its feed event
+    diesel::insert_into(tt_dsl::team_transactions)
+        .values(new_team_transactions)
+        .on_conflict_do_nothing()
+        .execute(conn)
+}
+
+/// [`insert_team_transactions`] for admin tools. The insert is recorded in
+/// the admin audit log in the same transaction.
+pub fn backfill_team_transactions(
+    conn: &mut PgConnection,
+    new_team_transactions: &[NewTeamTransaction],
+    actor: &str,
+) -> QueryResult<usize> {
+    conn.transaction(|conn| {
+        let num_added = insert_team_transactions(conn, new_team_transactions)?;
+
+        // The rest were already recorded
+        let num_existing = new_team_transactions.len() - num_added;
+        record_admin_action(
+            conn,
+            &NewAdminAudit {
+                actor,
+                action: "backfill_team_transactions",
+                details: serde_json::json!({
+                    "feed_events": new_team_transactions
+                        .iter()
+                        .map(|t| (t.mmolb_team_id, t.feed_event_index))
+                        .collect_vec(),
+                }),
+                before: Some(serde_json::json!({ "rows": num_existing })),
+                after: Some(serde_json::json!({ "rows": new_team_transactions.len() })),
+            },
+        )?;
+
+        Ok(num_added)
+    })
+}
+
+/// The latest main MMOLB version of up to `limit` feed events of `kind`,
+/// after `after` in (entity id, feed event index) order. Returns each
+/// version's entity id, feed event index, valid_from, and data.
+pub fn latest_feed_event_versions_page(
+    conn: &mut PgConnection,
+    kind: &str,
+    after: Option<(&str, i32)>,
+    limit: i64,
+) -> QueryResult<Vec<(String, i32, DateTime<Utc>, serde_json::Value)>> {
+    use crate::data_schema::data::feed_event_versions::dsl as fev_dsl;
+
+    let (after_entity_id, after_feed_event_index) = after.unwrap_or(("", i32::MIN));
+    fev_dsl::feed_event_versions
+        .filter(fev_dsl::kind.eq(kind))
+        .filter(fev_dsl::source.is_null())
+        .filter(fev_dsl::valid_until.is_null())
+        .filter(
+            fev_dsl::entity_id.gt(after_entity_id).or(fev_dsl::entity_id
+                .eq(after_entity_id)
+                .and(fev_dsl::feed_event_index.gt(after_feed_event_index))),
+        )
+        .order_by((fev_dsl::entity_id.asc(), fev_dsl::feed_event_index.asc()))
+        .select((
+            fev_dsl::entity_id,
+            fev_dsl::feed_event_index,
+            fev_dsl::valid_from,
+            fev_dsl::data,
+        ))
+        .limit(limit)
+        .get_results(conn)
+}
+
 fn insert_feed_events_processed(
     conn: &mut PgConnection,
     new_feed_events_processed: Vec<&NewFeedEventProcessed>,
@@ -2760,18 +3157,28 @@ pub fn insert_team_feed_versions<'container, 'game: 'container>(
 ) -> QueryResult<(usize, usize)> {
     let new_team_feed_versions = new_team_feed_versions
         .into_iter()
-        .map(|(a, b, c)| (a, b, c));
+        .map(|(a, b, c, d, e)| (a, b, c, d, e));
 
-    let (new_team_feed_events_processed, new_team_games_played, ingest_logs): (
+    let (
+        new_team_feed_events_processed,
+        new_team_games_played,
+        ingest_logs,
+        failures,
+        new_team_transactions,
+    ): (
         Vec<&NewFeedEventProcessed>,
         Vec<&Option<NewTeamGamePlayed>>,
         Vec<&Vec<NewVersionIngestLog>>,
+        Vec<&Option<NewFeedEventFailure>>,
+        Vec<&Option<NewTeamTransaction>>,
     ) = itertools::multiunzip(new_team_feed_versions);
 
     // Insert new records
     let total = new_team_games_played.len();
     let inserted = insert_new_team_games_played(conn, new_team_games_played)?;
+    insert_team_transactions(conn, new_team_transactions.into_iter().flatten())?;
     insert_nested_ingest_logs(conn, ingest_logs)?;
+    insert_feed_event_failures(conn, failures.into_iter().flatten().collect())?;
 
     // This is last so that we don't mark them as processed if there were db errors
     insert_feed_events_processed(conn, new_team_feed_events_processed)?;
@@ -3014,74 +3421,226 @@ pub fn insert_player_versions_all<'container, 'game: 'container>(
     let insert_player_pitch_category_bonuses_duration =
         (Utc::now() - insert_player_pitch_category_bonuses_start).as_seconds_f64();
 
-    let insert_ingest_logs_start = Utc::now();
-    // Don't add this to totals
-    insert_nested_ingest_logs(conn, new_ingest_logs)?;
-    let insert_ingest_logs_duration = (Utc::now() - insert_ingest_logs_start).as_seconds_f64();
+    let insert_ingest_logs_start = Utc::now();
+    // Don't add this to totals
+    insert_nested_ingest_logs(conn, new_ingest_logs)?;
+    let insert_ingest_logs_duration = (Utc::now() - insert_ingest_logs_start).as_seconds_f64();
+
+    info!(
+        "preprocess_duration: {preprocess_duration:.2}, \
+        insert_version_processed_duration: {insert_version_processed_duration:.2}, \
+        insert_player_equipment_duration: {insert_player_equipment_duration:.2}, \
+        insert_player_pitch_types_duration: {insert_player_pitch_types_duration:.2}, \
+        insert_player_pitch_type_bonuses_duration: {insert_player_pitch_type_bonuses_duration:.2}, \
+        insert_player_pitch_category_bonuses_duration: {insert_player_pitch_category_bonuses_duration:.2}, \
+        insert_player_reports_duration: {insert_player_reports_duration:.2}, \
+        insert_player_modifications_duration: {insert_player_modifications_duration:.2}, \
+        insert_player_version_duration: {insert_player_version_duration:.2}, \
+        insert_ingest_logs_duration: {insert_ingest_logs_duration:.2}"
+    );
+
+    Ok((total_versions, inserted_versions))
+}
+
+/// How far along [`insert_player_versions_chunked`] is
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ChunkedInsertProgress {
+    pub chunks_inserted: usize,
+    pub versions_inserted: usize,
+    pub total_versions: usize,
+    /// Rows sent to the database so far, across every player table
+    pub rows_total: usize,
+    /// Rows that were actually inserted so far. The rest were duplicates.
+    pub rows_inserted: usize,
+}
+
+/// Like [`insert_player_versions_all`], but takes `versions` a chunk of
+/// `chunk_size` at a time, so only one chunk's rows are ever in memory. Each
+/// chunk is a pass of its own in its own transaction: `to_rows` builds the
+/// chunk's rows, looking up anything they need on the connection it's
+/// given, and then they're inserted. A failure only rolls back the chunk it
+/// happened in, and `on_progress` is called after each chunk commits. The
+/// caller must not be in a transaction, or every chunk would commit with it.
+pub fn insert_player_versions_chunked<'v, T: 'v>(
+    conn: &mut PgConnection,
+    versions: impl ExactSizeIterator<Item = &'v T>,
+    chunk_size: NonZero<usize>,
+    mut to_rows: impl FnMut(&mut PgConnection, &[&'v T]) -> QueryResult<Vec<NewPlayerVersionExt<'v>>>,
+    mut on_progress: impl FnMut(&ChunkedInsertProgress),
+) -> QueryResult<(usize, usize)> {
+    let mut progress = ChunkedInsertProgress {
+        total_versions: versions.len(),
+        ..Default::default()
+    };
+
+    for chunk in &versions.chunks(chunk_size.get()) {
+        let chunk = chunk.collect_vec();
+        // The rows are dropped at the end of the transaction, before the
+        // next chunk's are built
+        let (total, inserted) = conn.transaction(|conn| {
+            let rows = to_rows(conn, &chunk)?;
+            insert_player_versions_all(conn, &rows)
+        })?;
+
+        progress.chunks_inserted += 1;
+        progress.versions_inserted += chunk.len();
+        progress.rows_total += total;
+        progress.rows_inserted += inserted;
+        on_progress(&progress);
+    }
+
+    Ok((progress.rows_total, progress.rows_inserted))
+}
+
+pub fn get_player_versions(
+    conn: &mut PgConnection,
+    player_id: &str,
+) -> QueryResult<Vec<DbPlayerVersion>> {
+    use crate::data_schema::data::player_versions::dsl as pv_dsl;
+
+    pv_dsl::player_versions
+        .filter(pv_dsl::mmolb_player_id.eq(player_id))
+        .order(pv_dsl::valid_from.asc())
+        .select(DbPlayerVersion::as_select())
+        .get_results(conn)
+}
+
+pub fn get_player_modification_versions(
+    conn: &mut PgConnection,
+    player_id: &str,
+) -> QueryResult<Vec<DbPlayerModificationVersion>> {
+    use crate::data_schema::data::player_modification_versions::dsl as pmv_dsl;
+
+    pmv_dsl::player_modification_versions
+        .filter(pmv_dsl::mmolb_player_id.eq(player_id))
+        .order(pmv_dsl::valid_from.asc())
+        .select(DbPlayerModificationVersion::as_select())
+        .get_results(conn)
+}
+
+pub fn get_player_equipment_versions(
+    conn: &mut PgConnection,
+    player_id: &str,
+) -> QueryResult<Vec<DbPlayerEquipmentVersion>> {
+    use crate::data_schema::data::player_equipment_versions::dsl as pev_dsl;
+
+    pev_dsl::player_equipment_versions
+        .filter(pev_dsl::mmolb_player_id.eq(player_id))
+        .order(pev_dsl::valid_from.asc())
+        .select(DbPlayerEquipmentVersion::as_select())
+        .get_results(conn)
+}
+
+pub fn get_player_equipment_effect_versions(
+    conn: &mut PgConnection,
+    player_id: &str,
+) -> QueryResult<Vec<DbPlayerEquipmentEffectVersion>> {
+    use crate::data_schema::data::player_equipment_effect_versions::dsl as peev_dsl;
+
+    peev_dsl::player_equipment_effect_versions
+        .filter(peev_dsl::mmolb_player_id.eq(player_id))
+        .order(peev_dsl::valid_from.asc())
+        .select(DbPlayerEquipmentEffectVersion::as_select())
+        .get_results(conn)
+}
 
-    info!(
-        "preprocess_duration: {preprocess_duration:.2}, \
-        insert_version_processed_duration: {insert_version_processed_duration:.2}, \
-        insert_player_equipment_duration: {insert_player_equipment_duration:.2}, \
-        insert_player_pitch_types_duration: {insert_player_pitch_types_duration:.2}, \
-        insert_player_pitch_type_bonuses_duration: {insert_player_pitch_type_bonuses_duration:.2}, \
-        insert_player_pitch_category_bonuses_duration: {insert_player_pitch_category_bonuses_duration:.2}, \
-        insert_player_reports_duration: {insert_player_reports_duration:.2}, \
-        insert_player_modifications_duration: {insert_player_modifications_duration:.2}, \
-        insert_player_version_duration: {insert_player_version_duration:.2}, \
-        insert_ingest_logs_duration: {insert_ingest_logs_duration:.2}"
-    );
+/// The start time of the first game of each season, ordered by season
+pub fn season_start_times(conn: &mut PgConnection) -> QueryResult<Vec<(i32, DateTime<Utc>)>> {
+    use crate::data_schema::data::games::dsl as games_dsl;
 
-    Ok((total_versions, inserted_versions))
+    games_dsl::games
+        .group_by(games_dsl::season)
+        .select((games_dsl::season, diesel::dsl::min(games_dsl::started_at)))
+        .order_by(games_dsl::season.asc())
+        .get_results::<(i32, Option<DateTime<Utc>>)>(conn)
+        .map(|seasons| {
+            seasons
+                .into_iter()
+                .filter_map(|(season, start)| Some((season, start?)))
+                .collect()
+        })
 }
 
-pub fn get_player_versions(
+/// Up to `limit` of a player's versions whose `valid_from` is in
+/// `[since, until)`, oldest first
+pub fn get_player_versions_page(
     conn: &mut PgConnection,
     player_id: &str,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    limit: i64,
 ) -> QueryResult<Vec<DbPlayerVersion>> {
     use crate::data_schema::data::player_versions::dsl as pv_dsl;
 
-    pv_dsl::player_versions
+    let mut query = pv_dsl::player_versions
         .filter(pv_dsl::mmolb_player_id.eq(player_id))
+        .into_boxed();
+    if let Some(since) = since {
+        query = query.filter(pv_dsl::valid_from.ge(since));
+    }
+    if let Some(until) = until {
+        query = query.filter(pv_dsl::valid_from.lt(until));
+    }
+
+    query
         .order(pv_dsl::valid_from.asc())
+        .limit(limit)
         .select(DbPlayerVersion::as_select())
         .get_results(conn)
 }
 
-pub fn get_player_modification_versions(
+/// A player's modification versions that were valid at any point between
+/// `first` and `last`, inclusive
+pub fn get_player_modification_versions_between(
     conn: &mut PgConnection,
     player_id: &str,
+    first: DateTime<Utc>,
+    last: DateTime<Utc>,
 ) -> QueryResult<Vec<DbPlayerModificationVersion>> {
     use crate::data_schema::data::player_modification_versions::dsl as pmv_dsl;
 
     pmv_dsl::player_modification_versions
         .filter(pmv_dsl::mmolb_player_id.eq(player_id))
+        .filter(pmv_dsl::valid_from.le(last))
+        .filter(pmv_dsl::valid_until.is_null().or(pmv_dsl::valid_until.gt(first)))
         .order(pmv_dsl::valid_from.asc())
         .select(DbPlayerModificationVersion::as_select())
         .get_results(conn)
 }
 
-pub fn get_player_equipment_versions(
+/// A player's equipment versions that were valid at any point between
+/// `first` and `last`, inclusive
+pub fn get_player_equipment_versions_between(
     conn: &mut PgConnection,
     player_id: &str,
+    first: DateTime<Utc>,
+    last: DateTime<Utc>,
 ) -> QueryResult<Vec<DbPlayerEquipmentVersion>> {
     use crate::data_schema::data::player_equipment_versions::dsl as pev_dsl;
 
     pev_dsl::player_equipment_versions
         .filter(pev_dsl::mmolb_player_id.eq(player_id))
+        .filter(pev_dsl::valid_from.le(last))
+        .filter(pev_dsl::valid_until.is_null().or(pev_dsl::valid_until.gt(first)))
         .order(pev_dsl::valid_from.asc())
         .select(DbPlayerEquipmentVersion::as_select())
         .get_results(conn)
 }
 
-pub fn get_player_equipment_effect_versions(
+/// A player's equipment effect versions that were valid at any point
+/// between `first` and `last`, inclusive
+pub fn get_player_equipment_effect_versions_between(
     conn: &mut PgConnection,
     player_id: &str,
+    first: DateTime<Utc>,
+    last: DateTime<Utc>,
 ) -> QueryResult<Vec<DbPlayerEquipmentEffectVersion>> {
     use crate::data_schema::data::player_equipment_effect_versions::dsl as peev_dsl;
 
     peev_dsl::player_equipment_effect_versions
         .filter(peev_dsl::mmolb_player_id.eq(player_id))
+        .filter(peev_dsl::valid_from.le(last))
+        .filter(peev_dsl::valid_until.is_null().or(peev_dsl::valid_until.gt(first)))
         .order(peev_dsl::valid_from.asc())
         .select(DbPlayerEquipmentEffectVersion::as_select())
         .get_results(conn)
@@ -3122,30 +3681,6 @@ pub fn get_modifications(conn: &mut PgConnection, ids: &[i64]) -> QueryResult<Ve
         .get_results(conn)
 }
 
-macro_rules! rollback_table {
-    ($conn:expr, $($namespace:ident)::*, $table_name:ident, $dt:ident) => {{
-        // This struct is a workaround for the apparent otherwise inability
-        // to get Diesel to set a column to null
-        #[derive(AsChangeset)]
-        #[diesel(table_name = $($namespace)::*::$table_name)]
-        #[diesel(treat_none_as_null = true)]
-        struct Update {
-            valid_until: Option<NaiveDateTime>,
-        }
-
-        // Delete all versions that began after the target date
-        diesel::delete($($namespace)::*::$table_name::dsl::$table_name)
-            .filter($($namespace)::*::$table_name::dsl::valid_from.le($dt))
-            .execute($conn)?;
-
-        // Un-close-out all versions that were closed out after the target date
-        diesel::update($($namespace)::*::$table_name::dsl::$table_name)
-            .filter($($namespace)::*::$table_name::dsl::valid_until.le($dt))
-            .set(&Update { valid_until: None })
-            .execute($conn)?;
-    }};
-}
-
 pub fn get_player_recompositions(
     conn: &mut PgConnection,
     player_id: &str,
@@ -3179,8 +3714,8 @@ pub fn get_player_attribute_augments(
 pub struct DbPlayerParty {
     #[diesel(sql_type = Text)]
     pub mmolb_game_id: String,
-    #[diesel(sql_type = Timestamp)]
-    pub game_start_time: NaiveDateTime,
+    #[diesel(sql_type = Timestamptz)]
+    pub game_start_time: DateTime<Utc>,
     #[diesel(sql_type = Timestamp)]
     pub game_end_time: NaiveDateTime,
     #[diesel(sql_type = Int8)]
@@ -3200,6 +3735,7 @@ pub fn get_player_parties(
             group by mmolb_game_id
         ), parties_extended as (select
             g.mmolb_game_id,
+            g.started_at,
             case when p.is_pitcher=p.top_of_inning then g.home_team_mmolb_id else g.away_team_mmolb_id end as mmolb_team_id,
             p.player_name,
             p.attribute,
@@ -3208,7 +3744,7 @@ pub fn get_player_parties(
         left join data.games g on g.id=p.game_id)
         select
             pe.mmolb_game_id,
-            to_timestamp(('0x'||substr(pe.mmolb_game_id, 1, 8))::numeric) as game_start_time,
+            pe.started_at as game_start_time,
             gt.time as game_end_time,
             pe.attribute,
             pe.value
@@ -3224,21 +3760,6 @@ pub fn get_player_parties(
     q.bind::<Text, _>(player_id).get_results(conn)
 }
 
-// TODO Update this
-pub fn roll_back_ingest_to_date(conn: &mut PgConnection, dt: NaiveDateTime) -> QueryResult<()> {
-    use crate::schema::data_schema::data as schema;
-
-    rollback_table!(conn, schema, player_versions, dt);
-    rollback_table!(conn, schema, player_modification_versions, dt);
-    rollback_table!(conn, schema, player_equipment_versions, dt);
-    rollback_table!(conn, schema, player_equipment_effect_versions, dt);
-    rollback_table!(conn, schema, player_report_versions, dt);
-    rollback_table!(conn, schema, player_report_attribute_versions, dt);
-    rollback_table!(conn, schema, team_versions, dt);
-
-    Ok(())
-}
-
 #[derive(Queryable)]
 pub struct PitchTypeInfo {
     pub pitch_type: Option<i64>,
@@ -3503,17 +4024,11 @@ pub fn insert_team_versions_all<'container, 'game: 'container>(
 pub fn refresh_entity_counting_matviews(conn: &mut PgConnection) -> Vec<QueryError> {
     let mut errs = Vec::new();
 
-    trace!("Refreshing materialized view info.entities_count");
-    if let Err(e) = sql_query("refresh materialized view info.entities_count").execute(conn) {
-        errs.push(e);
-    }
-
-    trace!("Refreshing materialized view info.entities_with_issues_count");
-    if let Err(e) =
-        sql_query("refresh materialized view concurrently info.entities_with_issues_count")
-            .execute(conn)
-    {
-        errs.push(e);
+    for matview in ["info.entities_count", "info.entities_with_issues_count"] {
+        trace!("Refreshing materialized view {matview}");
+        if let Err(e) = refresh_matview(conn, matview) {
+            errs.push(e);
+        }
     }
 
     errs
@@ -3522,21 +4037,354 @@ pub fn refresh_entity_counting_matviews(conn: &mut PgConnection) -> Vec<QueryErr
 pub fn refresh_player_matviews(conn: &mut PgConnection) -> Vec<QueryError> {
     let mut errs = Vec::new();
 
-    info!("Refreshing materialized view data.player_versions_extended");
-    if let Err(e) =
-        sql_query("refresh materialized view concurrently data.player_versions_extended")
-            .execute(conn)
-    {
-        errs.push(e);
+    for matview in [
+        "data.player_versions_extended",
+        "data.player_equipment_attribute_deltas",
+    ] {
+        info!("Refreshing materialized view {matview}");
+        if let Err(e) = refresh_matview(conn, matview) {
+            errs.push(e);
+        }
     }
 
     errs
 }
 
+/// Changes whenever a game is added, replaced, or finished, or a player's
+/// identity is re-resolved. Games and identity resolutions are replaced
+/// rather than updated, so their max ids change when they're replaced.
+const GAMES_FINGERPRINT_SQL: &str = "
+    select concat_ws(
+        ':',
+        count(*),
+        max(id),
+        count(*) filter (where is_ongoing),
+        (select concat_ws(':', count(*), max(id)) from data.player_identity_resolution)
+    ) as inputs
+    from data.games
+";
+
+/// Changes whenever [`GAMES_FINGERPRINT_SQL`] does or a player's report
+/// changes
+const SIMILARITY_FINGERPRINT_SQL: &str = "
+    select concat_ws(
+        ':',
+        (select concat_ws(':', count(*), max(id), count(*) filter (where is_ongoing))
+            from data.games),
+        (select concat_ws(':', count(*), max(id)) from data.player_identity_resolution),
+        (select concat_ws(':', count(*), max(id)) from data.player_report_attribute_versions)
+    ) as inputs
+";
+
 pub fn refresh_game_matviews(conn: &mut PgConnection) -> Vec<QueryError> {
-    // Nothing to refresh, for now
-    let _ = conn;
-    Vec::new()
+    let mut errs = Vec::new();
+
+    // These aggregate every event ever recorded, so they're only refreshed
+    // when games or player identities have changed
+    for matview in ["data.player_season_stats", "data.matchup_outcomes"] {
+        info!("Refreshing materialized view {matview} if its inputs changed");
+        if let Err(e) = refresh_matview_if_changed(conn, matview, GAMES_FINGERPRINT_SQL) {
+            errs.push(e);
+        }
+    }
+
+    for matview in [
+        "data.catcher_called_strikes",
+        "data.pitcher_tto_splits",
+        "data.pitcher_pitch_count_splits",
+        "data.weather_outcomes",
+    ] {
+        info!("Refreshing materialized view {matview}");
+        if let Err(e) = refresh_matview(conn, matview) {
+            errs.push(e);
+        }
+    }
+
+    // Comparing every pair of players is expensive, so these are only
+    // refreshed when their inputs change. The features depend on
+    // data.player_season_stats, so they have to be refreshed after it, and
+    // the similar players depend on the features.
+    for matview in [
+        "data.player_similarity_features",
+        "data.player_similar_players",
+    ] {
+        info!("Refreshing materialized view {matview} if its inputs changed");
+        if let Err(e) = refresh_matview_if_changed(conn, matview, SIMILARITY_FINGERPRINT_SQL) {
+            errs.push(e);
+        }
+    }
+
+    info!("Updating weekly rollups");
+    match update_weekly_rollups(conn) {
+        Ok(weeks) => info!("Updated weekly rollups for {weeks} week(s)"),
+        Err(e) => errs.push(e),
+    }
+
+    errs
+}
+
+#[derive(QueryableByName)]
+pub struct DbCatcherCalledStrikes {
+    #[diesel(sql_type = Int4)]
+    pub season: i32,
+    #[diesel(sql_type = Text)]
+    pub catcher_mmolb_id: String,
+    #[diesel(sql_type = Text)]
+    pub catcher_name: String,
+    #[diesel(sql_type = Int8)]
+    pub taken_pitches: i64,
+    #[diesel(sql_type = Int8)]
+    pub called_strikes: i64,
+    #[diesel(sql_type = Int8)]
+    pub balls: i64,
+    #[diesel(sql_type = Double)]
+    pub called_strike_rate: f64,
+    #[diesel(sql_type = Double)]
+    pub called_strikes_above_expected: f64,
+}
+
+/// Catcher called-strike stats for one season, best framers first
+pub fn catcher_called_strikes_for_season(
+    conn: &mut PgConnection,
+    season: i32,
+    min_taken_pitches: i64,
+) -> QueryResult<Vec<DbCatcherCalledStrikes>> {
+    sql_query(
+        "
+        select *
+        from data.catcher_called_strikes
+        where season=$1 and taken_pitches >= $2
+        order by called_strikes_above_expected desc, catcher_mmolb_id
+    ",
+    )
+    .bind::<Int4, _>(season)
+    .bind::<Int8, _>(min_taken_pitches)
+    .get_results(conn)
+}
+
+#[derive(QueryableByName)]
+pub struct DbPitcherTtoSplit {
+    #[diesel(sql_type = Int4)]
+    pub season: i32,
+    #[diesel(sql_type = Text)]
+    pub team_mmolb_id: String,
+    #[diesel(sql_type = Text)]
+    pub pitcher_name: String,
+    #[diesel(sql_type = Int4)]
+    pub times_through_order: i32,
+    #[diesel(sql_type = Int8)]
+    pub plate_appearances: i64,
+    #[diesel(sql_type = Int8)]
+    pub hits: i64,
+    #[diesel(sql_type = Int8)]
+    pub home_runs: i64,
+    #[diesel(sql_type = Int8)]
+    pub walks: i64,
+    #[diesel(sql_type = Int8)]
+    pub strikeouts: i64,
+    #[diesel(sql_type = Int8)]
+    pub pitches: i64,
+}
+
+pub fn pitcher_tto_splits_for_season(
+    conn: &mut PgConnection,
+    season: i32,
+    team_mmolb_id: Option<&str>,
+) -> QueryResult<Vec<DbPitcherTtoSplit>> {
+    sql_query(
+        "
+        select *
+        from data.pitcher_tto_splits
+        where season=$1 and ($2 is null or team_mmolb_id=$2)
+        order by team_mmolb_id, pitcher_name, times_through_order
+    ",
+    )
+    .bind::<Int4, _>(season)
+    .bind::<Nullable<Text>, _>(team_mmolb_id)
+    .get_results(conn)
+}
+
+#[derive(QueryableByName)]
+pub struct DbPitcherPitchCountSplit {
+    #[diesel(sql_type = Int4)]
+    pub season: i32,
+    #[diesel(sql_type = Text)]
+    pub team_mmolb_id: String,
+    #[diesel(sql_type = Text)]
+    pub pitcher_name: String,
+    #[diesel(sql_type = Int4)]
+    pub pitch_count_min: i32,
+    #[diesel(sql_type = Int8)]
+    pub plate_appearances: i64,
+    #[diesel(sql_type = Int8)]
+    pub hits: i64,
+    #[diesel(sql_type = Int8)]
+    pub home_runs: i64,
+    #[diesel(sql_type = Int8)]
+    pub walks: i64,
+    #[diesel(sql_type = Int8)]
+    pub strikeouts: i64,
+    #[diesel(sql_type = Int8)]
+    pub pitches: i64,
+}
+
+pub fn pitcher_pitch_count_splits_for_season(
+    conn: &mut PgConnection,
+    season: i32,
+    team_mmolb_id: Option<&str>,
+) -> QueryResult<Vec<DbPitcherPitchCountSplit>> {
+    sql_query(
+        "
+        select *
+        from data.pitcher_pitch_count_splits
+        where season=$1 and ($2 is null or team_mmolb_id=$2)
+        order by team_mmolb_id, pitcher_name, pitch_count_min
+    ",
+    )
+    .bind::<Int4, _>(season)
+    .bind::<Nullable<Text>, _>(team_mmolb_id)
+    .get_results(conn)
+}
+
+#[derive(QueryableByName)]
+pub struct DbWeatherOutcome {
+    #[diesel(sql_type = Int4)]
+    pub season: i32,
+    #[diesel(sql_type = Int8)]
+    pub weather: i64,
+    #[diesel(sql_type = Text)]
+    pub weather_name: String,
+    #[diesel(sql_type = Text)]
+    pub weather_emoji: String,
+    #[diesel(sql_type = Int8)]
+    pub event_type: i64,
+    #[diesel(sql_type = Text)]
+    pub event_type_name: String,
+    /// Games in this weather and season that had at least one of this event
+    #[diesel(sql_type = Int8)]
+    pub games: i64,
+    #[diesel(sql_type = Int8)]
+    pub events: i64,
+}
+
+/// Event counts by weather and event type, optionally limited to one
+/// season and/or one weather (by name, since weathers with the same name
+/// can have different emoji or tooltips)
+pub fn weather_outcomes(
+    conn: &mut PgConnection,
+    season: Option<i32>,
+    weather_name: Option<&str>,
+) -> QueryResult<Vec<DbWeatherOutcome>> {
+    sql_query(
+        "
+        select *
+        from data.weather_outcomes
+        where ($1 is null or season=$1) and ($2 is null or weather_name=$2)
+        order by season, weather_name, weather, events desc, event_type
+    ",
+    )
+    .bind::<Nullable<Int4>, _>(season)
+    .bind::<Nullable<Text>, _>(weather_name)
+    .get_results(conn)
+}
+
+/// A weather as it appears in filters. Weathers with the same name can have
+/// different emoji or tooltips, so this is one arbitrary emoji per name.
+#[derive(QueryableByName, Serialize)]
+pub struct DbWeatherName {
+    #[diesel(sql_type = Text)]
+    pub name: String,
+    #[diesel(sql_type = Text)]
+    pub emoji: String,
+}
+
+/// Every weather that at least one game was played in, by name
+pub fn weathers_with_games(conn: &mut PgConnection) -> QueryResult<Vec<DbWeatherName>> {
+    sql_query(
+        "
+        select w.name, min(w.emoji) as emoji
+        from data.weather w
+        where exists(select 1 from data.games g where g.weather = w.id)
+        group by w.name
+        order by w.name
+    ",
+    )
+    .get_results(conn)
+}
+
+#[derive(QueryableByName)]
+pub struct DbWeatherGameCount {
+    #[diesel(sql_type = Int4)]
+    pub season: i32,
+    #[diesel(sql_type = Text)]
+    pub weather_name: String,
+    #[diesel(sql_type = Text)]
+    pub weather_emoji: String,
+    #[diesel(sql_type = Int8)]
+    pub games: i64,
+}
+
+/// The number of games played under each weather (by name) in each
+/// season, optionally limited to one season. Ordered by season, then most
+/// games first.
+pub fn weather_game_counts(
+    conn: &mut PgConnection,
+    season: Option<i32>,
+) -> QueryResult<Vec<DbWeatherGameCount>> {
+    sql_query(
+        "
+        select g.season, w.name as weather_name, min(w.emoji) as weather_emoji, count(*) as games
+        from data.games g
+            join data.weather w on w.id = g.weather
+        where ($1 is null or g.season=$1)
+        group by g.season, w.name
+        order by g.season, games desc, w.name
+    ",
+    )
+    .bind::<Nullable<Int4>, _>(season)
+    .get_results(conn)
+}
+
+#[derive(QueryableByName)]
+pub struct DbPlayerEquipmentAttributeDelta {
+    #[diesel(sql_type = Text)]
+    pub mmolb_player_id: String,
+    #[diesel(sql_type = Timestamptz)]
+    pub valid_from: DateTime<Utc>,
+    #[diesel(sql_type = Nullable<Timestamptz>)]
+    pub valid_until: Option<DateTime<Utc>>,
+    #[diesel(sql_type = Int8)]
+    pub attribute: i64,
+    #[diesel(sql_type = Text)]
+    pub attribute_name: String,
+    #[diesel(sql_type = Double)]
+    pub flat_bonus: f64,
+    #[diesel(sql_type = Double)]
+    pub multiplier_bonus: f64,
+    #[diesel(sql_type = Int8)]
+    pub num_effects: i64,
+}
+
+/// Total equipment bonus to each attribute for one player. If `at` is
+/// given, only the totals that were in effect at that time are returned.
+/// Otherwise the player's full history is returned, oldest first.
+pub fn player_equipment_attribute_deltas(
+    conn: &mut PgConnection,
+    mmolb_player_id: &str,
+    at: Option<DateTime<Utc>>,
+) -> QueryResult<Vec<DbPlayerEquipmentAttributeDelta>> {
+    sql_query(
+        "
+        select *
+        from data.player_equipment_attribute_deltas
+        where mmolb_player_id=$1
+            and ($2 is null or (valid_from <= $2 and (valid_until is null or valid_until > $2)))
+        order by valid_from, attribute
+    ",
+    )
+    .bind::<Text, _>(mmolb_player_id)
+    .bind::<Nullable<Timestamptz>, _>(at)
+    .get_results(conn)
 }
 
 pub struct GamesStats {
@@ -3755,6 +4603,32 @@ pub fn latest_game(
         .optional()
 }
 
+/// The MMOLB ids of the `limit` most recently inserted finished games
+pub fn latest_finished_mmolb_game_ids(
+    conn: &mut PgConnection,
+    limit: i64,
+) -> QueryResult<Vec<String>> {
+    game_dsl::games
+        .filter(game_dsl::is_ongoing.eq(false))
+        .order_by(game_dsl::id.desc())
+        .limit(limit)
+        .select(game_dsl::mmolb_game_id)
+        .get_results(conn)
+}
+
+/// Which version of a team to show when a query joins historical events to
+/// team names. Teams can be renamed (or have their emoji changed) at any
+/// time, so these can give different results for the same record.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default, strum::EnumString, strum::Display, strum::EnumIter)]
+#[strum(serialize_all = "snake_case")]
+pub enum TeamIdentityPolicy {
+    /// The team's current name and emoji
+    #[default]
+    Latest,
+    /// The team's name and emoji at the time of the game
+    AsOfGame,
+}
+
 #[derive(QueryableByName)]
 pub struct PitchSpeedRecord {
     #[diesel(sql_type = Text)]
@@ -3777,8 +4651,7 @@ pub struct PitchSpeedRecord {
     pub pitch_speed: f64,
 }
 
-pub fn fastest_pitch(conn: &mut PgConnection) -> QueryResult<Option<PitchSpeedRecord>> {
-    sql_query("
+pub(crate) const FASTEST_PITCH_SQL: &str = "
         select
             tv.mmolb_team_id,
             tv.emoji as team_emoji,
@@ -3793,16 +4666,29 @@ pub fn fastest_pitch(conn: &mut PgConnection) -> QueryResult<Option<PitchSpeedRe
         inner join data.team_player_versions tpv on tpv.mmolb_team_id=ee.defending_team_mmolb_id
             and tpv.first_name || ' ' || tpv.last_name=ee.pitcher_name
             and tpv.valid_from <= ee.game_end_time and ee.game_end_time < coalesce(tpv.valid_until, 'infinity')
-        -- I'm intentionally selecting the latest team version, rather than the one from when the record
-        -- was set, because I want to get the latest team name and emoji
+        -- $1 is a TeamIdentityPolicy. `latest` selects the latest team version, to get the
+        -- latest team name and emoji. `as_of_game` selects the version from when the record
+        -- was set.
         inner join data.team_versions tv on tv.mmolb_team_id=ee.defending_team_mmolb_id
-            and tv.valid_until is null
+            and case when $1 = 'as_of_game'
+                then tv.valid_from <= ee.game_end_time and ee.game_end_time < coalesce(tv.valid_until, 'infinity')
+                else tv.valid_until is null
+            end
         where ee.pitch_speed is not null
         -- Select highest pitch speed, and in case of ties, select earliest game id
         -- This will get the earliest record setter unless the record was broken multiple times in the same day
         order by ee.pitch_speed desc, ee.mmolb_game_id asc, ee.game_event_index asc
         limit 1
-    ").get_result(conn).optional()
+    ";
+
+pub fn fastest_pitch(
+    conn: &mut PgConnection,
+    team_identity: TeamIdentityPolicy,
+) -> QueryResult<Option<PitchSpeedRecord>> {
+    sql_query(FASTEST_PITCH_SQL)
+        .bind::<Text, _>(team_identity.to_string())
+        .get_result(conn)
+        .optional()
 }
 
 #[derive(QueryableByName)]
@@ -3825,10 +4711,7 @@ pub struct MostPitchesInGameRecord {
     pub num_pitch_like_events: i64,
 }
 
-pub fn most_pitches_by_player_in_one_game(
-    conn: &mut PgConnection,
-) -> QueryResult<Option<MostPitchesInGameRecord>> {
-    sql_query("
+pub(crate) const MOST_PITCHES_BY_PLAYER_IN_ONE_GAME_SQL: &str = "
         with counts as (
             select
                 count(1) as num_pitch_like_events,
@@ -3857,16 +4740,25 @@ pub fn most_pitches_by_player_in_one_game(
         inner join data.team_player_versions tpv on tpv.mmolb_team_id=c.defending_team_mmolb_id
             and tpv.first_name || ' ' || tpv.last_name=c.pitcher_name
             and tpv.valid_from <= c.game_end_time and c.game_end_time < coalesce(tpv.valid_until, 'infinity')
-        -- I'm intentionally selecting the latest team version, rather than the one from when the record
-        -- was set, because I want to get the latest team name and emoji
+        -- See FASTEST_PITCH_SQL for the meaning of $1
         inner join data.team_versions tv on tv.mmolb_team_id=c.defending_team_mmolb_id
-            and tv.valid_until is null
-    ").get_result(conn).optional()
+            and case when $1 = 'as_of_game'
+                then tv.valid_from <= c.game_end_time and c.game_end_time < coalesce(tv.valid_until, 'infinity')
+                else tv.valid_until is null
+            end
+    ";
+
+pub fn most_pitches_by_player_in_one_game(
+    conn: &mut PgConnection,
+    team_identity: TeamIdentityPolicy,
+) -> QueryResult<Option<MostPitchesInGameRecord>> {
+    sql_query(MOST_PITCHES_BY_PLAYER_IN_ONE_GAME_SQL)
+        .bind::<Text, _>(team_identity.to_string())
+        .get_result(conn)
+        .optional()
 }
 
-pub fn highest_scoring_game(conn: &mut PgConnection) -> QueryResult<Option<DbGame>> {
-    sql_query(
-        "
+pub(crate) const HIGHEST_SCORING_GAME_SQL: &str = "
         select *
         from data.games g
         where g.away_team_final_score is not null
@@ -3874,15 +4766,15 @@ pub fn highest_scoring_game(conn: &mut PgConnection) -> QueryResult<Option<DbGam
         order by g.away_team_final_score + g.home_team_final_score desc,
             g.mmolb_game_id asc
         limit 1
-    ",
-    )
+    ";
+
+pub fn highest_scoring_game(conn: &mut PgConnection) -> QueryResult<Option<DbGame>> {
+    sql_query(HIGHEST_SCORING_GAME_SQL)
     .get_result(conn)
     .optional()
 }
 
-pub fn highest_score_in_a_game(conn: &mut PgConnection) -> QueryResult<Option<DbGame>> {
-    sql_query(
-        "
+pub(crate) const HIGHEST_SCORE_IN_A_GAME_SQL: &str = "
         select *
         from data.games g
         where g.away_team_final_score is not null
@@ -3890,8 +4782,10 @@ pub fn highest_score_in_a_game(conn: &mut PgConnection) -> QueryResult<Option<Db
         order by greatest(g.away_team_final_score, g.home_team_final_score) desc,
             g.mmolb_game_id asc
         limit 1
-    ",
-    )
+    ";
+
+pub fn highest_score_in_a_game(conn: &mut PgConnection) -> QueryResult<Option<DbGame>> {
+    sql_query(HIGHEST_SCORE_IN_A_GAME_SQL)
     .get_result(conn)
     .optional()
 }
@@ -3916,24 +4810,22 @@ pub struct GameWithCount {
     pub count: i64,
 }
 
-pub fn longest_game_by_events(conn: &mut PgConnection) -> QueryResult<Option<GameWithCount>> {
-    sql_query(
-        "
+pub(crate) const LONGEST_GAME_BY_EVENTS_SQL: &str = "
         select
             (select count(1) from data.events e where e.game_id=g.id) as count,
             g.*
         from data.games g
         order by count desc, g.mmolb_game_id asc
         limit 1
-    ",
-    )
+    ";
+
+pub fn longest_game_by_events(conn: &mut PgConnection) -> QueryResult<Option<GameWithCount>> {
+    sql_query(LONGEST_GAME_BY_EVENTS_SQL)
     .get_result(conn)
     .optional()
 }
 
-pub fn longest_game_by_innings(conn: &mut PgConnection) -> QueryResult<Option<GameWithCount>> {
-    sql_query(
-        "
+pub(crate) const LONGEST_GAME_BY_INNINGS_SQL: &str = "
         select
             e.inning::bigint as count,
             g.*
@@ -3941,8 +4833,10 @@ pub fn longest_game_by_innings(conn: &mut PgConnection) -> QueryResult<Option<Ga
         left join data.games g on g.id=e.game_id
         order by count desc, g.mmolb_game_id asc
         limit 1
-    ",
-    )
+    ";
+
+pub fn longest_game_by_innings(conn: &mut PgConnection) -> QueryResult<Option<GameWithCount>> {
+    sql_query(LONGEST_GAME_BY_INNINGS_SQL)
     .get_result(conn)
     .optional()
 }
@@ -3967,11 +4861,7 @@ pub struct DbPlayerIdentityWithValue {
 }
 
 // TODO Consider excluding the period during season 6 where values were fluctuating a lot
-pub fn highest_reported_attribute(
-    conn: &mut PgConnection,
-    attr_name: &str,
-) -> QueryResult<Option<DbPlayerIdentityWithValue>> {
-    sql_query("
+pub(crate) const HIGHEST_REPORTED_ATTRIBUTE_SQL: &str = "
         select
             tv.mmolb_team_id,
             tv.emoji as team_emoji,
@@ -3983,14 +4873,29 @@ pub fn highest_reported_attribute(
         from data.player_report_attribute_versions prav
         inner join data.player_versions pv on pv.mmolb_player_id=prav.mmolb_player_id
             and prav.valid_from >= pv.valid_from and prav.valid_from < coalesce(pv.valid_until, 'infinity')
-        -- intentionally getting the latest team version
+        -- See FASTEST_PITCH_SQL for the meaning of $2. Here the time of the record is the
+        -- time of the report.
         inner join data.team_versions tv on tv.mmolb_team_id=pv.mmolb_team_id
-            and tv.valid_until is null
+            and case when $2 = 'as_of_game'
+                then tv.valid_from <= prav.valid_from and prav.valid_from < coalesce(tv.valid_until, 'infinity')
+                else tv.valid_until is null
+            end
         inner join taxa.attribute a on a.id=prav.attribute
         where a.name=$1 and prav.modified_total is not null
         order by prav.modified_total desc, prav.valid_from asc
         limit 1
-    ").bind::<Text, _>(attr_name).get_result(conn).optional()
+    ";
+
+pub fn highest_reported_attribute(
+    conn: &mut PgConnection,
+    attr_name: &str,
+    team_identity: TeamIdentityPolicy,
+) -> QueryResult<Option<DbPlayerIdentityWithValue>> {
+    sql_query(HIGHEST_REPORTED_ATTRIBUTE_SQL)
+        .bind::<Text, _>(attr_name)
+        .bind::<Text, _>(team_identity.to_string())
+        .get_result(conn)
+        .optional()
 }
 
 pub fn replace_modifier_effects(conn: &mut PgConnection, effects: Vec<NewModificationEffects>) -> QueryResult<()> {
@@ -4006,4 +4911,58 @@ pub fn replace_modifier_effects(conn: &mut PgConnection, effects: Vec<NewModific
         .execute(conn)?;
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{QueryError, postgres_url_from_environment};
+
+    #[test]
+    fn every_requested_game_gets_a_status() {
+        let url = postgres_url_from_environment();
+        let mut conn = PgConnection::establish(&url)
+            .expect("postgres_url_from_environment should return a valid connection string");
+
+        conn.test_transaction(|conn| {
+            sql_query(
+                "
+                with w as (
+                    insert into data.weather (name, emoji, tooltip)
+                    values ('Test Weather', '', '')
+                    returning id
+                )
+                insert into data.games
+                    (mmolb_game_id, weather, season, superstar_day, away_team_emoji,
+                     away_team_name, away_team_mmolb_id, home_team_emoji, home_team_name,
+                     home_team_mmolb_id, is_ongoing, from_version, started_at)
+                select g.game_id, w.id, -1, null, '', 'Away', 'status-test-away', '', 'Home',
+                    'status-test-home', g.is_ongoing, '2025-02-15', '2025-02-15T00:00:00Z'
+                from w, (
+                    values ('ffffffffffffffffff000001', true),
+                        ('ffffffffffffffffff000002', false)
+                ) as g(game_id, is_ongoing)
+            ",
+            )
+            .execute(conn)?;
+
+            // The last game was never inserted
+            let statuses = games_status_map(
+                conn,
+                &[
+                    "ffffffffffffffffff000001",
+                    "ffffffffffffffffff000002",
+                    "ffffffffffffffffff000003",
+                ],
+            )?;
+            assert_eq!(statuses.len(), 3);
+            assert_eq!(statuses["ffffffffffffffffff000001"], GameStatus::Ongoing);
+            assert_eq!(statuses["ffffffffffffffffff000002"], GameStatus::Finished);
+            assert_eq!(statuses["ffffffffffffffffff000003"], GameStatus::NotFound);
+
+            assert!(games_status_map(conn, &[])?.is_empty());
+
+            Ok::<_, QueryError>(())
+        });
+    }
+}
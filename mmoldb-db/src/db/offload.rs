@@ -0,0 +1,227 @@
+use chron::ChronEntity;
+use chrono::{DateTime, Utc};
+use diesel::{PgConnection, prelude::*};
+use hashbrown::HashMap;
+use itertools::Itertools;
+
+use super::record_admin_action;
+use crate::data_schema::data::entities::dsl as entities_dsl;
+use crate::models::NewAdminAudit;
+
+/// The latest season that has any games, or None if there are no games yet
+pub fn latest_game_season(conn: &mut PgConnection) -> QueryResult<Option<i32>> {
+    use crate::data_schema::data::games::dsl as games_dsl;
+
+    games_dsl::games
+        .select(diesel::dsl::max(games_dsl::season))
+        .get_result(conn)
+}
+
+/// Main MMOLB raw games that are still stored in the database, are
+/// finished, and are from `max_season` or earlier, ordered by id
+pub fn get_game_entities_to_offload(
+    conn: &mut PgConnection,
+    max_season: i32,
+    after_game_id: Option<&str>,
+    batch_size: usize,
+) -> QueryResult<Vec<ChronEntity<serde_json::Value>>> {
+    use crate::data_schema::data::games::dsl as games_dsl;
+
+    let old_games = games_dsl::games
+        .filter(games_dsl::is_ongoing.eq(false))
+        .filter(games_dsl::season.le(max_season))
+        .select(games_dsl::mmolb_game_id);
+
+    let mut query = entities_dsl::entities
+        .filter(entities_dsl::kind.eq("game"))
+        .filter(entities_dsl::source.is_null())
+        .filter(entities_dsl::offloaded_to.is_null())
+        .filter(entities_dsl::entity_id.eq_any(old_games))
+        .into_boxed();
+    if let Some(after_game_id) = after_game_id {
+        query = query.filter(entities_dsl::entity_id.gt(after_game_id));
+    }
+
+    query
+        .order_by(entities_dsl::entity_id.asc())
+        .select((
+            entities_dsl::kind,
+            entities_dsl::entity_id,
+            entities_dsl::valid_from,
+            entities_dsl::data,
+        ))
+        .limit(batch_size as i64)
+        .get_results::<(String, String, DateTime<Utc>, serde_json::Value)>(conn)
+        .map(|entities| {
+            entities
+                .into_iter()
+                .map(|(kind, entity_id, valid_from, data)| ChronEntity {
+                    kind,
+                    entity_id,
+                    valid_from,
+                    valid_to: None,
+                    data,
+                })
+                .collect_vec()
+        })
+}
+
+/// Where an entity's data was uploaded to
+pub struct OffloadedEntity<'a> {
+    pub kind: &'a str,
+    pub entity_id: &'a str,
+    /// The version that was uploaded. If the entity has been replaced by a
+    /// newer version since then, it's left alone.
+    pub valid_from: DateTime<Utc>,
+    pub key: &'a str,
+}
+
+/// Replaces the data of entities that have been uploaded to object storage
+/// with a pointer to where they were uploaded. Returns the number of
+/// entities that were offloaded.
+pub fn mark_entities_offloaded(
+    conn: &mut PgConnection,
+    entities: &[OffloadedEntity],
+    actor: &str,
+) -> QueryResult<usize> {
+    conn.transaction(|conn| {
+        let mut offloaded = 0;
+        for entity in entities {
+            offloaded += diesel::update(entities_dsl::entities)
+                .filter(entities_dsl::kind.eq(entity.kind))
+                .filter(entities_dsl::entity_id.eq(entity.entity_id))
+                .filter(entities_dsl::source.is_null())
+                .filter(entities_dsl::valid_from.eq(entity.valid_from))
+                .set((
+                    entities_dsl::data.eq(serde_json::Value::Null),
+                    entities_dsl::offloaded_to.eq(entity.key),
+                ))
+                .execute(conn)?;
+        }
+
+        record_admin_action(
+            conn,
+            &NewAdminAudit {
+                actor,
+                action: "offload_entities",
+                details: serde_json::json!({
+                    "entities": entities
+                        .iter()
+                        .map(|entity| {
+                            serde_json::json!({
+                                "kind": entity.kind,
+                                "entity_id": entity.entity_id,
+                                "valid_from": entity.valid_from,
+                                "key": entity.key,
+                            })
+                        })
+                        .collect_vec(),
+                }),
+                before: Some(serde_json::json!({
+                    "rows": { "stored": entities.len(), "offloaded": 0 },
+                })),
+                after: Some(serde_json::json!({
+                    "rows": { "stored": entities.len() - offloaded, "offloaded": offloaded },
+                })),
+            },
+        )?;
+
+        Ok(offloaded)
+    })
+}
+
+/// The object storage keys of whichever of `entity_ids` have been
+/// offloaded, by entity id
+pub fn get_offloaded_entity_keys(
+    conn: &mut PgConnection,
+    kind: &str,
+    entity_ids: &[&str],
+) -> QueryResult<HashMap<String, String>> {
+    entities_dsl::entities
+        .filter(entities_dsl::kind.eq(kind))
+        .filter(entities_dsl::entity_id.eq_any(entity_ids))
+        .filter(entities_dsl::source.is_null())
+        .filter(entities_dsl::offloaded_to.is_not_null())
+        .select((
+            entities_dsl::entity_id,
+            entities_dsl::offloaded_to.assume_not_null(),
+        ))
+        .get_results::<(String, String)>(conn)
+        .map(|keys| keys.into_iter().collect())
+}
+
+/// Raw games that have been offloaded, ordered by id, with their keys
+pub fn get_offloaded_game_keys(
+    conn: &mut PgConnection,
+    after_game_id: Option<&str>,
+    batch_size: usize,
+) -> QueryResult<Vec<(String, String)>> {
+    let mut query = entities_dsl::entities
+        .filter(entities_dsl::kind.eq("game"))
+        .filter(entities_dsl::source.is_null())
+        .filter(entities_dsl::offloaded_to.is_not_null())
+        .into_boxed();
+    if let Some(after_game_id) = after_game_id {
+        query = query.filter(entities_dsl::entity_id.gt(after_game_id));
+    }
+
+    query
+        .order_by(entities_dsl::entity_id.asc())
+        .select((
+            entities_dsl::entity_id,
+            entities_dsl::offloaded_to.assume_not_null(),
+        ))
+        .limit(batch_size as i64)
+        .get_results(conn)
+}
+
+/// Puts offloaded entities' data back in the database. Each entity is
+/// `(entity id, key, data)`. Entities that were offloaded to a different key
+/// since `key` was read are left alone. Returns the number of entities that
+/// were restored.
+pub fn restore_offloaded_entities(
+    conn: &mut PgConnection,
+    kind: &str,
+    entities: &[(&str, &str, serde_json::Value)],
+    actor: &str,
+) -> QueryResult<usize> {
+    conn.transaction(|conn| {
+        let mut restored = 0;
+        for (entity_id, key, data) in entities {
+            restored += diesel::update(entities_dsl::entities)
+                .filter(entities_dsl::kind.eq(kind))
+                .filter(entities_dsl::entity_id.eq(entity_id))
+                .filter(entities_dsl::offloaded_to.eq(key))
+                .set((
+                    entities_dsl::data.eq(data),
+                    entities_dsl::offloaded_to.eq(None::<&str>),
+                ))
+                .execute(conn)?;
+        }
+
+        record_admin_action(
+            conn,
+            &NewAdminAudit {
+                actor,
+                action: "restore_offloaded_entities",
+                details: serde_json::json!({
+                    "kind": kind,
+                    "entities": entities
+                        .iter()
+                        .map(|(entity_id, key, _)| {
+                            serde_json::json!({ "entity_id": entity_id, "key": key })
+                        })
+                        .collect_vec(),
+                }),
+                before: Some(serde_json::json!({
+                    "rows": { "stored": 0, "offloaded": entities.len() },
+                })),
+                after: Some(serde_json::json!({
+                    "rows": { "stored": restored, "offloaded": entities.len() - restored },
+                })),
+            },
+        )?;
+
+        Ok(restored)
+    })
+}
@@ -0,0 +1,88 @@
+use diesel::sql_types::{Array, Int8, Text};
+use diesel::{PgConnection, prelude::*, sql_query};
+
+/// The outcome summary tables, and the expression for the team each one is
+/// keyed on. Offense is the batting team, defense is the fielding team.
+const OUTCOME_SUMMARIES: [(&str, &str); 2] = [
+    (
+        "data.offense_outcomes",
+        "case when e.top_of_inning then g.away_team_mmolb_id else g.home_team_mmolb_id end",
+    ),
+    (
+        "data.defense_outcomes",
+        "case when e.top_of_inning then g.home_team_mmolb_id else g.away_team_mmolb_id end",
+    ),
+];
+
+/// Removes the events of these games from the outcome summary tables. This
+/// must be called before the games are deleted.
+pub(super) fn subtract_outcome_summaries(
+    conn: &mut PgConnection,
+    mmolb_game_ids: &[&str],
+) -> QueryResult<()> {
+    for (table, team) in OUTCOME_SUMMARIES {
+        sql_query(format!(
+            "\
+            with removed as (
+                select
+                    g.season,
+                    {team} as team_mmolb_id,
+                    e.fair_ball_direction,
+                    e.event_type,
+                    e.hit_base,
+                    e.fielding_error_type,
+                    count(1) as count
+                from data.events e
+                join data.games g on g.id=e.game_id
+                where g.mmolb_game_id = any($1)
+                group by 1, 2, 3, 4, 5, 6
+            )
+            update {table} o
+            set count = o.count - removed.count
+            from removed
+            where o.season = removed.season
+                and o.team_mmolb_id = removed.team_mmolb_id
+                and o.fair_ball_direction is not distinct from removed.fair_ball_direction
+                and o.event_type = removed.event_type
+                and o.hit_base is not distinct from removed.hit_base
+                and o.fielding_error_type is not distinct from removed.fielding_error_type
+        ",
+        ))
+        .bind::<Array<Text>, _>(mmolb_game_ids)
+        .execute(conn)?;
+
+        sql_query(format!("delete from {table} where count <= 0")).execute(conn)?;
+    }
+
+    Ok(())
+}
+
+/// Adds the events of these (newly inserted) games to the outcome summary
+/// tables
+pub(super) fn add_outcome_summaries(conn: &mut PgConnection, game_ids: &[i64]) -> QueryResult<()> {
+    for (table, team) in OUTCOME_SUMMARIES {
+        sql_query(format!(
+            "\
+            insert into {table} as o
+            select
+                g.season,
+                {team},
+                e.fair_ball_direction,
+                e.event_type,
+                e.hit_base,
+                e.fielding_error_type,
+                count(1)
+            from data.events e
+            join data.games g on g.id=e.game_id
+            where g.id = any($1)
+            group by 1, 2, 3, 4, 5, 6
+            on conflict (season, team_mmolb_id, fair_ball_direction, event_type, hit_base, fielding_error_type)
+            do update set count = o.count + excluded.count
+        ",
+        ))
+        .bind::<Array<Int8>, _>(game_ids)
+        .execute(conn)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,541 @@
+use chrono::{DateTime, Utc};
+use diesel::sql_types::{Array, Int8, Text, Timestamptz};
+use diesel::{PgConnection, prelude::*, sql_query};
+
+use super::record_admin_action;
+use crate::models::NewAdminAudit;
+
+/// Every name that appears in these games, along with the team the player
+/// was on and the role they appeared in. Fielders take the role of the slot
+/// they fielded from.
+const APPEARANCES_SQL: &str = "
+    select e.game_id,
+        case when e.top_of_inning then g.away_team_mmolb_id else g.home_team_mmolb_id end as mmolb_team_id,
+        e.batter_name as player_name,
+        'Batter' as role
+    from data.events e
+    join data.games g on g.id=e.game_id
+    where e.game_id = any($1)
+    union
+    select e.game_id,
+        case when e.top_of_inning then g.home_team_mmolb_id else g.away_team_mmolb_id end,
+        e.pitcher_name,
+        'Pitcher'
+    from data.events e
+    join data.games g on g.id=e.game_id
+    where e.game_id = any($1)
+    union
+    select e.game_id,
+        case when e.top_of_inning then g.home_team_mmolb_id else g.away_team_mmolb_id end,
+        f.fielder_name,
+        s.role
+    from data.event_fielders f
+    join data.events e on e.id=f.event_id
+    join data.games g on g.id=e.game_id
+    join taxa.slot s on s.id=f.fielder_slot
+    where e.game_id = any($1)
+";
+
+/// Works out which roster player each batter, pitcher, and fielder name in
+/// these games refers to, and stores the result in
+/// `data.player_identity_resolution` and on the events and fielders. Players
+/// in superstar games are also linked back to their own teams. Any previous
+/// resolution for these games is replaced, so this can be re-run once
+/// rosters that were missing have been ingested.
+pub fn resolve_player_identities(conn: &mut PgConnection, game_ids: &[i64]) -> QueryResult<()> {
+    sql_query("delete from data.player_identity_resolution where game_id = any($1)")
+        .bind::<Array<Int8>, _>(game_ids)
+        .execute(conn)?;
+
+    // Candidates in the role the name appeared in are preferred. Among the
+    // preferred candidates, the lowest player id is chosen so the result is
+    // at least deterministic.
+    sql_query(format!(
+        "\
+        with appearances as ({APPEARANCES_SQL}),
+        candidates as (
+            select
+                a.game_id,
+                a.mmolb_team_id,
+                a.player_name,
+                a.role,
+                tpv.mmolb_player_id,
+                coalesce(s.role = a.role, false) as in_role
+            from appearances a
+            join data.games g on g.id=a.game_id
+            join data.team_player_versions tpv on tpv.mmolb_team_id=a.mmolb_team_id
+                and player_full_name(tpv.first_name, tpv.last_name, tpv.name_suffix)=a.player_name
+                and tpv.valid_from <= g.started_at
+                and g.started_at < coalesce(tpv.valid_until, 'infinity')
+            left join taxa.slot s on s.id=tpv.slot
+            where tpv.mmolb_player_id is not null
+        ),
+        counted as (
+            select
+                *,
+                count(distinct mmolb_player_id) over (
+                    partition by game_id, mmolb_team_id, player_name, role, in_role
+                ) as num_candidates
+            from candidates
+        ),
+        best as (
+            select distinct on (game_id, mmolb_team_id, player_name, role) *
+            from counted
+            order by game_id, mmolb_team_id, player_name, role, in_role desc, mmolb_player_id
+        )
+        insert into data.player_identity_resolution
+            (game_id, mmolb_team_id, player_name, role, mmolb_player_id, num_candidates)
+        select
+            a.game_id,
+            a.mmolb_team_id,
+            a.player_name,
+            a.role,
+            b.mmolb_player_id,
+            coalesce(b.num_candidates, 0)
+        from appearances a
+        left join best b on b.game_id=a.game_id
+            and b.mmolb_team_id=a.mmolb_team_id
+            and b.player_name=a.player_name
+            and b.role=a.role
+    ",
+    ))
+    .bind::<Array<Int8>, _>(game_ids)
+    .execute(conn)?;
+
+    // The batter and pitcher are set together so each event is only
+    // updated once. Every batter and pitcher name has a resolution row, but
+    // its id is null if it couldn't be resolved.
+    sql_query(
+        "\
+        update data.events e
+        set batter_mmolb_id=data.mmolb_id_from_text(rb.mmolb_player_id),
+            batter_identity_confident=case when rb.mmolb_player_id is null then null else rb.num_candidates = 1 end,
+            pitcher_mmolb_id=data.mmolb_id_from_text(rp.mmolb_player_id),
+            pitcher_identity_confident=case when rp.mmolb_player_id is null then null else rp.num_candidates = 1 end
+        from data.games g, data.player_identity_resolution rb, data.player_identity_resolution rp
+        where e.game_id = any($1)
+            and g.id=e.game_id
+            and rb.game_id=e.game_id
+            and rb.mmolb_team_id=case when e.top_of_inning then g.away_team_mmolb_id else g.home_team_mmolb_id end
+            and rb.player_name=e.batter_name
+            and rb.role='Batter'
+            and rp.game_id=e.game_id
+            and rp.mmolb_team_id=case when e.top_of_inning then g.home_team_mmolb_id else g.away_team_mmolb_id end
+            and rp.player_name=e.pitcher_name
+            and rp.role='Pitcher'
+    ",
+    )
+    .bind::<Array<Int8>, _>(game_ids)
+    .execute(conn)?;
+
+    sql_query(
+        "\
+        update data.event_fielders f
+        set fielder_mmolb_id=r.mmolb_player_id,
+            fielder_identity_confident=case when r.mmolb_player_id is null then null else r.num_candidates = 1 end
+        from data.events e, data.games g, taxa.slot s, data.player_identity_resolution r
+        where e.game_id = any($1)
+            and e.id=f.event_id
+            and g.id=e.game_id
+            and s.id=f.fielder_slot
+            and r.game_id=e.game_id
+            and r.mmolb_team_id=case when e.top_of_inning then g.home_team_mmolb_id else g.away_team_mmolb_id end
+            and r.player_name=f.fielder_name
+            and r.role=s.role
+    ",
+    )
+    .bind::<Array<Int8>, _>(game_ids)
+    .execute(conn)?;
+
+    link_superstar_game_players(conn, game_ids)?;
+
+    Ok(())
+}
+
+#[derive(QueryableByName)]
+struct ResolutionCounts {
+    #[diesel(sql_type = Int8)]
+    rows: i64,
+    #[diesel(sql_type = Int8)]
+    resolved: i64,
+}
+
+fn resolution_counts(conn: &mut PgConnection, game_ids: &[i64]) -> QueryResult<serde_json::Value> {
+    let counts = sql_query(
+        "\
+        select count(*) as rows, count(mmolb_player_id) as resolved
+        from data.player_identity_resolution
+        where game_id = any($1)
+    ",
+    )
+    .bind::<Array<Int8>, _>(game_ids)
+    .get_result::<ResolutionCounts>(conn)?;
+
+    Ok(serde_json::json!({ "rows": counts.rows, "resolved": counts.resolved }))
+}
+
+/// [`resolve_player_identities`] for admin tools. The games are resolved in
+/// one transaction, which is recorded in the admin audit log.
+pub fn reresolve_player_identities(
+    conn: &mut PgConnection,
+    game_ids: &[i64],
+    actor: &str,
+) -> QueryResult<()> {
+    conn.transaction(|conn| {
+        let before = resolution_counts(conn, game_ids)?;
+        resolve_player_identities(conn, game_ids)?;
+        let after = resolution_counts(conn, game_ids)?;
+
+        record_admin_action(
+            conn,
+            &NewAdminAudit {
+                actor,
+                action: "resolve_player_identities",
+                details: serde_json::json!({ "game_ids": game_ids }),
+                before: Some(before),
+                after: Some(after),
+            },
+        )
+    })
+}
+
+/// Links the players in any superstar games among these games to their own
+/// teams and leagues, in `data.superstar_game_players`. Names are resolved
+/// against the superstar team's roster when it's known. Otherwise they're
+/// looked up among the players on teams in the superstar team's league.
+fn link_superstar_game_players(conn: &mut PgConnection, game_ids: &[i64]) -> QueryResult<()> {
+    sql_query("delete from data.superstar_game_players where game_id = any($1)")
+        .bind::<Array<Int8>, _>(game_ids)
+        .execute(conn)?;
+
+    // A name can appear in more than one role. Resolved roles win. Version
+    // ranges can overlap, so only the latest player and team versions are
+    // used to find the source team, or the insert would hit the unique
+    // constraint.
+    sql_query(
+        "\
+        with appearances as (
+            select distinct on (r.game_id, r.mmolb_team_id, r.player_name)
+                r.game_id,
+                r.mmolb_team_id,
+                r.player_name,
+                r.mmolb_player_id,
+                r.num_candidates,
+                sl.id as superstar_league,
+                sl.mmolb_league_id as superstar_mmolb_league_id,
+                g.started_at as at_time
+            from data.player_identity_resolution r
+            join data.games g on g.id=r.game_id
+            left join taxa.leagues sl on sl.parent_team_id=r.mmolb_team_id
+            where r.game_id = any($1) and g.superstar_day is not null
+            order by r.game_id, r.mmolb_team_id, r.player_name, r.mmolb_player_id nulls last
+        )
+        insert into data.superstar_game_players
+            (game_id, superstar_team_mmolb_id, superstar_league, player_name, mmolb_player_id,
+             num_candidates, source_team_mmolb_id, source_league)
+        select
+            a.game_id,
+            a.mmolb_team_id,
+            a.superstar_league,
+            a.player_name,
+            coalesce(a.mmolb_player_id, n.mmolb_player_id),
+            case when a.mmolb_player_id is null then n.num_candidates else a.num_candidates end,
+            src.mmolb_team_id,
+            src.league
+        from appearances a
+        cross join lateral (
+            select min(pv.mmolb_player_id) as mmolb_player_id,
+                count(distinct pv.mmolb_player_id)::int as num_candidates
+            from data.player_versions pv
+            join data.team_versions tv on tv.mmolb_team_id=pv.mmolb_team_id
+                and tv.valid_from <= a.at_time
+                and a.at_time < coalesce(tv.valid_until, 'infinity')
+            where a.mmolb_player_id is null
+                and tv.mmolb_league_id=a.superstar_mmolb_league_id
+                and player_full_name(pv.first_name, pv.last_name, pv.name_suffix)=a.player_name
+                and pv.valid_from <= a.at_time
+                and a.at_time < coalesce(pv.valid_until, 'infinity')
+        ) n
+        left join lateral (
+            select pv.mmolb_team_id, l.id as league
+            from data.player_versions pv
+            left join data.team_versions tv on tv.mmolb_team_id=pv.mmolb_team_id
+                and tv.valid_from <= a.at_time
+                and a.at_time < coalesce(tv.valid_until, 'infinity')
+            left join taxa.leagues l on l.mmolb_league_id=tv.mmolb_league_id
+            where pv.mmolb_player_id=coalesce(a.mmolb_player_id, n.mmolb_player_id)
+                and pv.valid_from <= a.at_time
+                and a.at_time < coalesce(pv.valid_until, 'infinity')
+            order by pv.valid_from desc, tv.valid_from desc nulls last
+            limit 1
+        ) src on true
+    ",
+    )
+    .bind::<Array<Int8>, _>(game_ids)
+    .execute(conn)?;
+
+    Ok(())
+}
+
+/// Ids of games, in order, that have at least one name that couldn't be
+/// resolved. If `all` is true, every game is returned instead.
+pub fn game_ids_for_identity_resolution(
+    conn: &mut PgConnection,
+    all: bool,
+    after_game_id: Option<i64>,
+    batch_size: usize,
+) -> QueryResult<Vec<i64>> {
+    use crate::data_schema::data::games::dsl as games_dsl;
+    use crate::data_schema::data::player_identity_resolution::dsl as pir_dsl;
+
+    let mut query = games_dsl::games
+        .filter(games_dsl::is_ongoing.eq(false))
+        .into_boxed();
+    if !all {
+        query = query.filter(
+            games_dsl::id.eq_any(
+                pir_dsl::player_identity_resolution
+                    .filter(pir_dsl::mmolb_player_id.is_null())
+                    .select(pir_dsl::game_id),
+            ),
+        );
+    }
+    if let Some(after_game_id) = after_game_id {
+        query = query.filter(games_dsl::id.gt(after_game_id));
+    }
+
+    query
+        .order_by(games_dsl::id.asc())
+        .select(games_dsl::id)
+        .limit(batch_size as i64)
+        .get_results(conn)
+}
+
+/// Who a name referred to, according to the team's roster at the time
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlayerNameResolution {
+    /// Nobody on the roster had this name
+    Unknown,
+    Resolved(String),
+    /// More than one player on the roster had this name. Their ids are
+    /// sorted.
+    Ambiguous(Vec<String>),
+}
+
+#[derive(QueryableByName)]
+struct PlayerNameCandidate {
+    #[diesel(sql_type = Int8)]
+    query_index: i64,
+    #[diesel(sql_type = Text)]
+    mmolb_player_id: String,
+}
+
+/// Resolves each `(player name, team id, time)` to the players with that
+/// full name (suffix included) on the team's roster at that time. Results
+/// are in the same order as `names`.
+pub fn resolve_player_names(
+    conn: &mut PgConnection,
+    names: &[(&str, &str, DateTime<Utc>)],
+) -> QueryResult<Vec<PlayerNameResolution>> {
+    if names.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let player_names = names.iter().map(|(name, _, _)| *name).collect::<Vec<_>>();
+    let team_ids = names.iter().map(|(_, team, _)| *team).collect::<Vec<_>>();
+    let times = names.iter().map(|(_, _, at)| *at).collect::<Vec<_>>();
+
+    // Roster entries from before a player id was known have a null id and
+    // can't resolve to anyone
+    let candidates = sql_query(
+        "
+        select distinct q.query_index, tpv.mmolb_player_id
+        from unnest($1, $2, $3) with ordinality
+            as q(player_name, mmolb_team_id, at_time, query_index)
+        join data.team_player_versions tpv on tpv.mmolb_team_id=q.mmolb_team_id
+            and player_full_name(tpv.first_name, tpv.last_name, tpv.name_suffix)=q.player_name
+            and tpv.valid_from <= q.at_time
+            and q.at_time < coalesce(tpv.valid_until, 'infinity')
+        where tpv.mmolb_player_id is not null
+        order by q.query_index, tpv.mmolb_player_id
+    ",
+    )
+    .bind::<Array<Text>, _>(player_names)
+    .bind::<Array<Text>, _>(team_ids)
+    .bind::<Array<Timestamptz>, _>(times)
+    .get_results::<PlayerNameCandidate>(conn)?;
+
+    Ok(group_name_candidates(
+        names.len(),
+        candidates
+            .into_iter()
+            .map(|c| (c.query_index, c.mmolb_player_id)),
+    ))
+}
+
+// `candidates` must be sorted by query index, which is 1-based because it
+// comes from `with ordinality`
+fn group_name_candidates(
+    num_queries: usize,
+    candidates: impl IntoIterator<Item = (i64, String)>,
+) -> Vec<PlayerNameResolution> {
+    let mut candidates = candidates.into_iter().peekable();
+    (1..=num_queries as i64)
+        .map(|query_index| {
+            let mut ids = Vec::new();
+            while let Some((_, id)) = candidates.next_if(|(i, _)| *i == query_index) {
+                ids.push(id);
+            }
+            match ids.len() {
+                0 => PlayerNameResolution::Unknown,
+                1 => PlayerNameResolution::Resolved(ids.pop().unwrap()),
+                _ => PlayerNameResolution::Ambiguous(ids),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{QueryError, postgres_url_from_environment};
+    use diesel::sql_types::{Int4, Nullable};
+
+    #[test]
+    fn candidates_are_grouped_in_query_order() {
+        let resolutions = group_name_candidates(
+            3,
+            [
+                (1, "a".to_string()),
+                (3, "b".to_string()),
+                (3, "c".to_string()),
+            ],
+        );
+        assert_eq!(
+            resolutions,
+            vec![
+                PlayerNameResolution::Resolved("a".to_string()),
+                PlayerNameResolution::Unknown,
+                PlayerNameResolution::Ambiguous(vec!["b".to_string(), "c".to_string()]),
+            ]
+        );
+    }
+
+    #[derive(QueryableByName)]
+    struct GameId {
+        #[diesel(sql_type = Int8)]
+        id: i64,
+    }
+
+    #[derive(QueryableByName, Debug, PartialEq)]
+    struct SuperstarGamePlayer {
+        #[diesel(sql_type = Nullable<Text>)]
+        mmolb_player_id: Option<String>,
+        #[diesel(sql_type = Int4)]
+        num_candidates: i32,
+        #[diesel(sql_type = Nullable<Text>)]
+        source_team_mmolb_id: Option<String>,
+        #[diesel(sql_type = Nullable<Int8>)]
+        source_league: Option<i64>,
+    }
+
+    #[test]
+    fn superstar_players_are_linked_once_despite_overlapping_versions() {
+        let url = postgres_url_from_environment();
+        let mut conn = PgConnection::establish(&url)
+            .expect("postgres_url_from_environment should return a valid connection string");
+
+        conn.test_transaction(|conn| {
+            sql_query(
+                "
+                insert into taxa.leagues
+                    (id, name, color, emoji, league_type, parent_team_id, mmolb_league_id)
+                values (-1, 'Test League', '000000', '', 'Lesser', 'test-superstars', 'test-league')
+            ",
+            )
+            .execute(conn)?;
+
+            // Two team versions that both cover the game's start
+            sql_query(
+                "
+                insert into data.team_versions
+                    (mmolb_team_id, valid_from, valid_until, name, emoji, color, location,
+                     mmolb_league_id, num_players)
+                values
+                    ('ffffffffffffffffffff0001', '2025-01-01', '2025-03-01', 'Old Name', '',
+                     '000000', 'Here', 'test-league', 0),
+                    ('ffffffffffffffffffff0001', '2025-02-01', null, 'New Name', '',
+                     '000000', 'Here', 'test-league', 0)
+            ",
+            )
+            .execute(conn)?;
+
+            sql_query(
+                "
+                insert into data.player_versions
+                    (mmolb_player_id, valid_from, first_name, last_name, home, birthseason,
+                     likes, dislikes, number, mmolb_team_id, num_modifications,
+                     occupied_equipment_slots, included_report_categories)
+                values
+                    ('ffffffffffffffffffff0002', '2025-01-01', 'Test', 'Player', 'Here', 0, '',
+                     '', 0, 'ffffffffffffffffffff0001', 0, '{}', '{}')
+            ",
+            )
+            .execute(conn)?;
+
+            let game_id = sql_query(
+                "
+                with w as (
+                    insert into data.weather (name, emoji, tooltip)
+                    values ('Test Weather', '', '')
+                    returning id
+                )
+                insert into data.games
+                    (mmolb_game_id, weather, season, superstar_day, away_team_emoji,
+                     away_team_name, away_team_mmolb_id, home_team_emoji, home_team_name,
+                     home_team_mmolb_id, is_ongoing, from_version, started_at)
+                select 'ffffffffffffffffffff0003', w.id, 0, 1, '', 'Superstars',
+                    'test-superstars', '', 'Others', 'test-others', false, '2025-02-15',
+                    '2025-02-15T00:00:00Z'
+                from w
+                returning id
+            ",
+            )
+            .get_result::<GameId>(conn)?
+            .id;
+
+            sql_query(
+                "
+                insert into data.player_identity_resolution
+                    (game_id, mmolb_team_id, player_name, role, mmolb_player_id, num_candidates)
+                values ($1, 'test-superstars', 'Test Player', 'Batter', null, 0)
+            ",
+            )
+            .bind::<Int8, _>(game_id)
+            .execute(conn)?;
+
+            link_superstar_game_players(conn, &[game_id])?;
+
+            let linked = sql_query(
+                "
+                select mmolb_player_id, num_candidates, source_team_mmolb_id, source_league
+                from data.superstar_game_players
+                where game_id=$1
+            ",
+            )
+            .bind::<Int8, _>(game_id)
+            .get_results::<SuperstarGamePlayer>(conn)?;
+            assert_eq!(
+                linked,
+                vec![SuperstarGamePlayer {
+                    mmolb_player_id: Some("ffffffffffffffffffff0002".to_string()),
+                    num_candidates: 1,
+                    source_team_mmolb_id: Some("ffffffffffffffffffff0001".to_string()),
+                    source_league: Some(-1),
+                }],
+            );
+
+            Ok::<_, QueryError>(())
+        });
+    }
+}
@@ -0,0 +1,127 @@
+use diesel::{PgConnection, prelude::*, sql_query, sql_types::*};
+
+#[derive(QueryableByName)]
+pub struct DbSimilarPlayer {
+    #[diesel(sql_type = Text)]
+    pub mmolb_player_id: String,
+    #[diesel(sql_type = Text)]
+    pub first_name: String,
+    #[diesel(sql_type = Text)]
+    pub last_name: String,
+    /// Cosine similarity of the two players' feature vectors, from -1
+    /// (opposites) to 1 (identical)
+    #[diesel(sql_type = Double)]
+    pub similarity: f64,
+}
+
+/// How many of each player's most similar players are stored in
+/// `data.player_similar_players`. Asking [`similar_players`] for more than
+/// this returns at most this many.
+pub const SIMILAR_PLAYERS_STORED: i64 = 10;
+
+/// The `limit` players whose feature vectors in
+/// `data.player_similarity_features` are closest to `player_id`'s by cosine
+/// similarity, most similar first. These are precomputed in
+/// `data.player_similar_players`. Empty if the player has no features yet.
+pub fn similar_players(
+    conn: &mut PgConnection,
+    player_id: &str,
+    limit: i64,
+) -> QueryResult<Vec<DbSimilarPlayer>> {
+    sql_query(
+        "
+        select
+            sp.similar_mmolb_player_id as mmolb_player_id,
+            pv.first_name,
+            pv.last_name,
+            sp.similarity
+        from data.player_similar_players sp
+        join data.player_versions pv on pv.mmolb_player_id=sp.similar_mmolb_player_id
+            and pv.valid_until is null
+        where sp.mmolb_player_id=$1
+        order by sp.rank
+        limit $2
+    ",
+    )
+    .bind::<Text, _>(player_id)
+    .bind::<Int8, _>(limit)
+    .get_results(conn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::refresh_matview;
+    use crate::{QueryError, postgres_url_from_environment};
+
+    #[test]
+    fn similar_players_are_precomputed() {
+        let url = postgres_url_from_environment();
+        let mut conn = PgConnection::establish(&url)
+            .expect("postgres_url_from_environment should return a valid connection string");
+
+        conn.test_transaction(|conn| {
+            // Far enough from any real player's stars that the twins (ending
+            // in 1 and 2) are each other's closest match
+            sql_query(
+                "
+                with attributes as (
+                    select id, row_number() over (order by id) as n
+                    from taxa.attribute
+                    order by id
+                    limit 2
+                )
+                insert into data.player_report_attribute_versions
+                    (mmolb_player_id, category, attribute, valid_from, base_stars)
+                select
+                    p.mmolb_player_id,
+                    (select min(id) from taxa.attribute_category),
+                    a.id,
+                    '2025-01-01',
+                    case when (a.n = 1) = p.flipped then -1000 else 1000 end
+                from attributes a
+                cross join (
+                    values ('ffffffffffffffffffff0001', false),
+                        ('ffffffffffffffffffff0002', false),
+                        ('ffffffffffffffffffff0003', true)
+                ) as p(mmolb_player_id, flipped)
+            ",
+            )
+            .execute(conn)?;
+            sql_query(
+                "
+                insert into data.player_versions
+                    (mmolb_player_id, valid_from, first_name, last_name, home, birthseason,
+                     likes, dislikes, number, mmolb_team_id, num_modifications,
+                     occupied_equipment_slots, included_report_categories)
+                select id, '2025-01-01', 'Test', id, 'Here', 0, '', '', 0, null, 0, '{}', '{}'
+                from unnest(array['ffffffffffffffffffff0001', 'ffffffffffffffffffff0002',
+                    'ffffffffffffffffffff0003']) as id
+            ",
+            )
+            .execute(conn)?;
+
+            refresh_matview(conn, "data.player_similarity_features")?;
+            refresh_matview(conn, "data.player_similar_players")?;
+
+            let (twin_a, twin_b, opposite) = (
+                "ffffffffffffffffffff0001",
+                "ffffffffffffffffffff0002",
+                "ffffffffffffffffffff0003",
+            );
+            let similar = similar_players(conn, twin_a, SIMILAR_PLAYERS_STORED)?;
+            assert_eq!(similar[0].mmolb_player_id, twin_b);
+            assert!((similar[0].similarity - 1.0).abs() < 1e-9);
+
+            let similar = similar_players(conn, opposite, SIMILAR_PLAYERS_STORED)?;
+            assert!(
+                similar
+                    .iter()
+                    .filter(|p| [twin_a, twin_b].contains(&p.mmolb_player_id.as_str()))
+                    .all(|p| p.similarity < 0.0)
+            );
+
+            Ok::<_, QueryError>(())
+        });
+    }
+}
@@ -0,0 +1,503 @@
+use crate::taxa::{TaxaReconciliation, check_taxa_tables};
+use chrono::{DateTime, TimeDelta, Utc};
+use diesel::sql_types::{Int4, Int8, Text};
+use diesel::{PgConnection, prelude::*, sql_query};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+/// The Postgres NOTIFY channel subsystem status changes are published on.
+/// This has to be a valid unquoted identifier because LISTEN can't take a
+/// bind parameter.
+pub const QUALITY_ALERT_CHANNEL: &str = "mmoldb_quality_alerts";
+
+/// Data older than this is yellow
+const FRESHNESS_YELLOW_AFTER: TimeDelta = TimeDelta::hours(1);
+/// Data older than this is red
+const FRESHNESS_RED_AFTER: TimeDelta = TimeDelta::hours(6);
+/// Fraction of a kind's entities that can have ingest warnings or errors
+/// before it's yellow
+const ISSUE_RATE_YELLOW_AT: f64 = 0.01;
+/// Fraction of a kind's entities that can have ingest warnings or errors
+/// before it's red
+const ISSUE_RATE_RED_AT: f64 = 0.05;
+/// Growth in the fraction of games with ingest warnings or errors, from the
+/// previous season to the latest one, before it's yellow
+const ISSUE_TREND_YELLOW_AT: f64 = 0.01;
+/// Growth in the fraction of games with ingest warnings or errors, from the
+/// previous season to the latest one, before it's red
+const ISSUE_TREND_RED_AT: f64 = 0.05;
+/// How far back to look for Chron anomalies
+const ANOMALY_WINDOW: TimeDelta = TimeDelta::days(1);
+/// Unresolved anomalies in the window before it's red. Any is yellow.
+const UNRESOLVED_ANOMALIES_RED_AT: i64 = 10;
+/// Feed events in the dead-letter queue before it's red. Any is yellow.
+const FEED_EVENT_FAILURES_RED_AT: i64 = 100;
+/// Transactions open longer than this hold back vacuum enough to be yellow
+const OLDEST_TRANSACTION_YELLOW_AFTER_SECONDS: f64 = 60.0 * 60.0;
+/// Replication lag beyond this is yellow
+const REPLICATION_LAG_YELLOW_AFTER_SECONDS: f64 = 5.0 * 60.0;
+/// Fraction of the connection limit in use before it's red
+const CONNECTIONS_RED_AT: f64 = 0.9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QualityStatus {
+    Green,
+    Yellow,
+    Red,
+}
+
+impl QualityStatus {
+    fn from_thresholds<T: PartialOrd>(value: T, yellow_at: T, red_at: T) -> Self {
+        if value >= red_at {
+            QualityStatus::Red
+        } else if value >= yellow_at {
+            QualityStatus::Yellow
+        } else {
+            QualityStatus::Green
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SubsystemQuality {
+    pub name: String,
+    pub status: QualityStatus,
+    /// One line explaining the status
+    pub summary: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QualitySummary {
+    /// The worst status of any subsystem
+    pub overall: QualityStatus,
+    pub subsystems: Vec<SubsystemQuality>,
+    pub generated_at: DateTime<Utc>,
+}
+
+fn freshness_quality(
+    conn: &mut PgConnection,
+    now: DateTime<Utc>,
+) -> QueryResult<Vec<SubsystemQuality>> {
+    Ok(super::data_freshness(conn)?
+        .into_iter()
+        .map(|freshness| {
+            let age = now - freshness.last_ingest_at;
+            SubsystemQuality {
+                name: format!("{} freshness", freshness.kind),
+                status: QualityStatus::from_thresholds(
+                    age,
+                    FRESHNESS_YELLOW_AFTER,
+                    FRESHNESS_RED_AFTER,
+                ),
+                summary: format!("Last processed {} minutes ago", age.num_minutes()),
+            }
+        })
+        .collect())
+}
+
+fn ingest_issue_quality(conn: &mut PgConnection) -> QueryResult<Vec<SubsystemQuality>> {
+    Ok(super::entity_counts(conn)?
+        .into_iter()
+        .sorted_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(kind, (count, with_issues))| {
+            let rate = if count > 0 {
+                with_issues as f64 / count as f64
+            } else {
+                0.0
+            };
+            SubsystemQuality {
+                name: format!("{kind} ingest issues"),
+                status: QualityStatus::from_thresholds(
+                    rate,
+                    ISSUE_RATE_YELLOW_AT,
+                    ISSUE_RATE_RED_AT,
+                ),
+                summary: format!(
+                    "{with_issues} of {count} have warnings or errors ({:.2}%)",
+                    rate * 100.0,
+                ),
+            }
+        })
+        .collect())
+}
+
+/// How many of one season's games have ingest warnings or errors
+#[derive(Debug, Clone, QueryableByName)]
+struct SeasonIssueRate {
+    #[diesel(sql_type = Int4)]
+    season: i32,
+    #[diesel(sql_type = Int8)]
+    num_games: i64,
+    #[diesel(sql_type = Int8)]
+    num_games_with_issues: i64,
+}
+
+impl SeasonIssueRate {
+    fn rate(&self) -> f64 {
+        if self.num_games > 0 {
+            self.num_games_with_issues as f64 / self.num_games as f64
+        } else {
+            0.0
+        }
+    }
+}
+
+/// `rates` has the latest season first
+fn issue_trend(rates: &[SeasonIssueRate]) -> SubsystemQuality {
+    let name = "Game ingest issue trend".to_string();
+    let [latest, previous, ..] = rates else {
+        return SubsystemQuality {
+            name,
+            status: QualityStatus::Green,
+            summary: "Not enough seasons to compare".to_string(),
+        };
+    };
+
+    SubsystemQuality {
+        name,
+        status: QualityStatus::from_thresholds(
+            latest.rate() - previous.rate(),
+            ISSUE_TREND_YELLOW_AT,
+            ISSUE_TREND_RED_AT,
+        ),
+        summary: format!(
+            "{:.2}% of season {} games have warnings or errors, compared to {:.2}% in season {}",
+            latest.rate() * 100.0,
+            latest.season,
+            previous.rate() * 100.0,
+            previous.season,
+        ),
+    }
+}
+
+fn issue_trend_quality(conn: &mut PgConnection) -> QueryResult<SubsystemQuality> {
+    let rates = sql_query(
+        "
+        select
+            g.season,
+            count(*) as num_games,
+            count(*) filter (where exists (
+                select 1 from info.event_ingest_log l
+                where l.game_id=g.id and l.log_level < 3
+            )) as num_games_with_issues
+        from data.games g
+        where g.season >= (select max(season) - 1 from data.games)
+        group by g.season
+        order by g.season desc
+    ",
+    )
+    .get_results::<SeasonIssueRate>(conn)?;
+
+    Ok(issue_trend(&rates))
+}
+
+fn taxa_reconciliation_quality(reports: &[TaxaReconciliation]) -> SubsystemQuality {
+    let missing: usize = reports.iter().map(|report| report.inserted.len()).sum();
+    let mismatched: usize = reports.iter().map(|report| report.mismatched.len()).sum();
+    let orphaned: usize = reports.iter().map(|report| report.orphaned.len()).sum();
+
+    // Missing and mismatched rows make queries that join on taxa wrong.
+    // Orphaned rows are only clutter, unless something still refers to them.
+    let status = if missing > 0 || mismatched > 0 {
+        QualityStatus::Red
+    } else if orphaned > 0 {
+        QualityStatus::Yellow
+    } else {
+        QualityStatus::Green
+    };
+
+    SubsystemQuality {
+        name: "Taxa tables".to_string(),
+        status,
+        summary: format!(
+            "{missing} rows missing, {mismatched} columns mismatched and {orphaned} rows orphaned \
+            compared to the definitions (see `mmoldb-cli reconcile-taxa`)"
+        ),
+    }
+}
+
+fn chron_anomaly_quality(
+    conn: &mut PgConnection,
+    now: DateTime<Utc>,
+) -> QueryResult<SubsystemQuality> {
+    use crate::info_schema::info::chron_anomalies::dsl as ca_dsl;
+
+    let since = now - ANOMALY_WINDOW;
+    let total: i64 = ca_dsl::chron_anomalies
+        .filter(ca_dsl::detected_at.ge(since))
+        .count()
+        .get_result(conn)?;
+    let unresolved: i64 = ca_dsl::chron_anomalies
+        .filter(ca_dsl::detected_at.ge(since))
+        .filter(ca_dsl::resolved_by_refetch.eq(false))
+        .count()
+        .get_result(conn)?;
+
+    Ok(SubsystemQuality {
+        name: "Chron pages".to_string(),
+        status: QualityStatus::from_thresholds(unresolved, 1, UNRESOLVED_ANOMALIES_RED_AT),
+        summary: format!("{total} anomalies in the last day, {unresolved} not fixed by refetching"),
+    })
+}
+
+fn feed_event_failure_quality(conn: &mut PgConnection) -> QueryResult<SubsystemQuality> {
+    use crate::info_schema::info::feed_event_failures::dsl as fef_dsl;
+
+    let failures: i64 = fef_dsl::feed_event_failures.count().get_result(conn)?;
+
+    Ok(SubsystemQuality {
+        name: "Feed event dead-letter queue".to_string(),
+        status: QualityStatus::from_thresholds(failures, 1, FEED_EVENT_FAILURES_RED_AT),
+        summary: format!("{failures} feed events failed to ingest"),
+    })
+}
+
+/// Any overlap is red, because it makes queries double-count versions. Gaps
+/// are only yellow.
+fn version_chain_quality(conn: &mut PgConnection) -> QueryResult<SubsystemQuality> {
+    let (gaps, overlaps) = super::count_version_chain_breaks(conn)?;
+
+    let status = if overlaps > 0 {
+        QualityStatus::Red
+    } else if gaps > 0 {
+        QualityStatus::Yellow
+    } else {
+        QualityStatus::Green
+    };
+
+    Ok(SubsystemQuality {
+        name: "Version chains".to_string(),
+        status,
+        summary: format!("{gaps} gaps and {overlaps} overlaps between consecutive versions"),
+    })
+}
+
+fn database_quality(conn: &mut PgConnection) -> QueryResult<SubsystemQuality> {
+    let health = super::db_health(conn)?;
+
+    let connection_use = health.total_connections as f64 / health.max_connections as f64;
+    let oldest_transaction = health.oldest_transaction_age_seconds.unwrap_or(0.0);
+    let replication_lag = health.replication_lag_seconds.unwrap_or(0.0);
+
+    let status = if connection_use >= CONNECTIONS_RED_AT {
+        QualityStatus::Red
+    } else if oldest_transaction >= OLDEST_TRANSACTION_YELLOW_AFTER_SECONDS
+        || replication_lag >= REPLICATION_LAG_YELLOW_AFTER_SECONDS
+    {
+        QualityStatus::Yellow
+    } else {
+        QualityStatus::Green
+    };
+
+    Ok(SubsystemQuality {
+        name: "Database".to_string(),
+        status,
+        summary: format!(
+            "{} of {} connections in use, oldest transaction {:.0}s, replication lag {:.0}s",
+            health.total_connections, health.max_connections, oldest_transaction, replication_lag,
+        ),
+    })
+}
+
+/// A red/yellow/green status for each part of the ingest and database,
+/// combining the freshness, ingest issue and issue trend, taxa
+/// reconciliation, Chron anomaly, dead-letter queue, version chain, and
+/// database health checks
+pub fn quality_summary(conn: &mut PgConnection) -> QueryResult<QualitySummary> {
+    let now = Utc::now();
+
+    let mut subsystems = freshness_quality(conn, now)?;
+    subsystems.extend(ingest_issue_quality(conn)?);
+    subsystems.push(issue_trend_quality(conn)?);
+    subsystems.push(taxa_reconciliation_quality(&check_taxa_tables(conn)?));
+    subsystems.push(chron_anomaly_quality(conn, now)?);
+    subsystems.push(feed_event_failure_quality(conn)?);
+    subsystems.push(version_chain_quality(conn)?);
+    subsystems.push(database_quality(conn)?);
+
+    let overall = subsystems
+        .iter()
+        .map(|subsystem| subsystem.status)
+        .max()
+        .unwrap_or(QualityStatus::Green);
+
+    Ok(QualitySummary {
+        overall,
+        subsystems,
+        generated_at: now,
+    })
+}
+
+/// A subsystem whose status changed from one quality summary to the next
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QualityAlert {
+    pub name: String,
+    /// None if the subsystem wasn't in the previous summary
+    pub previous: Option<QualityStatus>,
+    pub status: QualityStatus,
+    pub summary: String,
+    pub at: DateTime<Utc>,
+}
+
+/// The subsystems whose status in `current` differs from `previous`.
+/// Subsystems that are new (including all of them, without a `previous`)
+/// are only alerts if they aren't green.
+pub fn quality_alerts(
+    previous: Option<&QualitySummary>,
+    current: &QualitySummary,
+) -> Vec<QualityAlert> {
+    current
+        .subsystems
+        .iter()
+        .filter_map(|subsystem| {
+            let previous_status = previous.and_then(|previous| {
+                previous
+                    .subsystems
+                    .iter()
+                    .find(|p| p.name == subsystem.name)
+                    .map(|p| p.status)
+            });
+            let changed = previous_status.unwrap_or(QualityStatus::Green) != subsystem.status;
+
+            changed.then(|| QualityAlert {
+                name: subsystem.name.clone(),
+                previous: previous_status,
+                status: subsystem.status,
+                summary: subsystem.summary.clone(),
+                at: current.generated_at,
+            })
+        })
+        .collect()
+}
+
+/// Publishes `alert` to everyone listening on [`QUALITY_ALERT_CHANNEL`].
+/// Like other notifications, it's only delivered once the current
+/// transaction commits.
+pub fn publish_quality_alert(conn: &mut PgConnection, alert: &QualityAlert) -> QueryResult<()> {
+    // Serializing this struct can't fail
+    let payload = serde_json::to_string(alert).expect("Quality alert should serialize");
+    sql_query("select pg_notify($1, $2)")
+        .bind::<Text, _>(QUALITY_ALERT_CHANNEL)
+        .bind::<Text, _>(payload)
+        .execute(conn)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thresholds_are_inclusive() {
+        assert_eq!(
+            QualityStatus::from_thresholds(0, 1, 10),
+            QualityStatus::Green
+        );
+        assert_eq!(
+            QualityStatus::from_thresholds(1, 1, 10),
+            QualityStatus::Yellow
+        );
+        assert_eq!(
+            QualityStatus::from_thresholds(10, 1, 10),
+            QualityStatus::Red
+        );
+    }
+
+    fn subsystem(name: &str, status: QualityStatus) -> SubsystemQuality {
+        SubsystemQuality {
+            name: name.to_string(),
+            status,
+            summary: String::new(),
+        }
+    }
+
+    fn summary(subsystems: Vec<SubsystemQuality>) -> QualitySummary {
+        QualitySummary {
+            overall: QualityStatus::Green,
+            subsystems,
+            generated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn only_status_changes_are_alerts() {
+        let first = summary(vec![
+            subsystem("a", QualityStatus::Green),
+            subsystem("b", QualityStatus::Yellow),
+        ]);
+        let alerts = quality_alerts(None, &first);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].name, "b");
+        assert_eq!(alerts[0].previous, None);
+
+        let second = summary(vec![
+            subsystem("a", QualityStatus::Red),
+            subsystem("b", QualityStatus::Yellow),
+            subsystem("c", QualityStatus::Green),
+        ]);
+        let alerts = quality_alerts(Some(&first), &second);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].name, "a");
+        assert_eq!(alerts[0].previous, Some(QualityStatus::Green));
+        assert_eq!(alerts[0].status, QualityStatus::Red);
+
+        // Recoveries are alerts too
+        let third = summary(vec![subsystem("a", QualityStatus::Green)]);
+        let alerts = quality_alerts(Some(&second), &third);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].previous, Some(QualityStatus::Red));
+    }
+
+    #[test]
+    fn issue_trend_compares_the_latest_two_seasons() {
+        let rate = |season, num_games_with_issues| SeasonIssueRate {
+            season,
+            num_games: 100,
+            num_games_with_issues,
+        };
+
+        assert_eq!(issue_trend(&[]).status, QualityStatus::Green);
+        assert_eq!(issue_trend(&[rate(5, 50)]).status, QualityStatus::Green);
+        // Fewer issues than last season is fine, however many there are
+        assert_eq!(
+            issue_trend(&[rate(5, 50), rate(4, 60)]).status,
+            QualityStatus::Green
+        );
+        assert_eq!(
+            issue_trend(&[rate(5, 3), rate(4, 1)]).status,
+            QualityStatus::Yellow
+        );
+        assert_eq!(
+            issue_trend(&[rate(5, 10), rate(4, 1)]).status,
+            QualityStatus::Red
+        );
+    }
+
+    #[test]
+    fn taxa_differences_set_the_reconciliation_status() {
+        use crate::taxa::{TaxaOrphan, TaxaReconcileMode};
+
+        let mut report = TaxaReconciliation::new("base", TaxaReconcileMode::ReportOnly);
+        assert_eq!(
+            taxa_reconciliation_quality(std::slice::from_ref(&report)).status,
+            QualityStatus::Green
+        );
+
+        report.orphaned.push(TaxaOrphan {
+            id: 99,
+            name: Some("Removed".to_string()),
+        });
+        assert_eq!(
+            taxa_reconciliation_quality(std::slice::from_ref(&report)).status,
+            QualityStatus::Yellow
+        );
+
+        report.inserted.push("Home");
+        assert_eq!(
+            taxa_reconciliation_quality(std::slice::from_ref(&report)).status,
+            QualityStatus::Red
+        );
+    }
+}
@@ -0,0 +1,532 @@
+use diesel::{PgConnection, prelude::*};
+use itertools::Itertools;
+use std::collections::HashSet;
+use std::iter;
+
+use super::{CompletedGameForDb, GameForDb, balk_reasons, cheers, record_admin_action};
+use crate::models::{DbGame, GameMetadataUpdate, NewAdminAudit};
+use crate::taxa::Taxa;
+
+/// Tables that are derived entirely from a game's events, and so can be
+/// rebuilt one at a time from the raw game without touching the rest of
+/// the game's data. Some entries cover a parent table and its child table,
+/// because they can't be rebuilt independently.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, strum::EnumString, strum::Display, strum::EnumIter)]
+#[strum(serialize_all = "snake_case")]
+pub enum DerivedTable {
+    EventBaserunners,
+    EventFielders,
+    AuroraPhotos,
+    Ejections,
+    FailedEjections,
+    /// Also rebuilds `door_prize_items`
+    DoorPrizes,
+    /// Also rebuilds `efflorescence_growth`
+    Efflorescence,
+    PitcherChanges,
+    PitcherAppearances,
+    GameInnings,
+    Parties,
+    Wither,
+    /// Also rebuilds `consumption_contest_events`
+    ConsumptionContests,
+    EventCheers,
+    EventBalkReasons,
+    EventDefense,
+    PlateAppearances,
+    EventWinProbability,
+    EventPitchSequence,
+}
+
+#[derive(Debug, Default)]
+pub struct RebuildDerivedTableStats {
+    pub games_rebuilt: usize,
+    /// Games whose stored events don't line up with the events from the raw
+    /// game. Their derived rows can't be matched to events, so they're left
+    /// alone. These need a full reingest.
+    pub games_skipped: usize,
+    /// Games that hit a fatal error when they were run through the sim.
+    /// They have no derived rows to rebuild.
+    pub games_failed: usize,
+}
+
+/// Deletes `table`'s rows for these games and regenerates them. Only
+/// completed games are rebuilt; games in any other state don't have derived
+/// rows.
+pub fn rebuild_derived_table(
+    conn: &mut PgConnection,
+    taxa: &Taxa,
+    table: DerivedTable,
+    games: &[GameForDb],
+    actor: &str,
+) -> QueryResult<RebuildDerivedTableStats> {
+    use crate::data_schema::data::events::dsl as events_dsl;
+    use crate::data_schema::data::games::dsl as games_dsl;
+
+    let completed_games = games
+        .iter()
+        .filter_map(|game| match game {
+            GameForDb::Completed { game, .. } => Some(game),
+            _ => None,
+        })
+        .collect_vec();
+    let games_failed = games
+        .iter()
+        .filter(|game| matches!(game, GameForDb::FatalError { .. }))
+        .count();
+
+    // These have to be created outside the transaction, same as in
+    // insert_games
+    let cheer_table = if table == DerivedTable::EventCheers {
+        let messages: HashSet<String> = completed_games
+            .iter()
+            .flat_map(|game| &game.events)
+            .flat_map(|event| &event.cheer)
+            .map(|cheer| cheer.to_string())
+            .collect();
+        Some(cheers::create_cheers_table(conn, &messages)?)
+    } else {
+        None
+    };
+    let balk_reason_table = if table == DerivedTable::EventBalkReasons {
+        let messages: HashSet<String> = completed_games
+            .iter()
+            .flat_map(|game| &game.events)
+            .flat_map(|event| &event.balk_reason)
+            .map(|balk_reason| balk_reason.to_string())
+            .collect();
+        Some(balk_reasons::create_balk_reasons_table(conn, &messages)?)
+    } else {
+        None
+    };
+
+    conn.transaction(|conn| {
+        let game_ids = games_dsl::games
+            .filter(games_dsl::mmolb_game_id.eq_any(completed_games.iter().map(|g| g.id)))
+            .select((games_dsl::mmolb_game_id, games_dsl::id))
+            .get_results::<(String, i64)>(conn)?
+            .into_iter()
+            .collect::<hashbrown::HashMap<_, _>>();
+
+        let event_ids = events_dsl::events
+            .filter(events_dsl::game_id.eq_any(game_ids.values()))
+            .select((events_dsl::game_id, events_dsl::id))
+            .order_by(events_dsl::game_id)
+            .then_order_by(events_dsl::game_event_index)
+            .get_results::<(i64, i64)>(conn)?
+            .into_iter()
+            .into_group_map();
+
+        let mut stats = RebuildDerivedTableStats {
+            games_failed,
+            ..Default::default()
+        };
+        let mut games_to_rebuild: Vec<(i64, &CompletedGameForDb)> = Vec::new();
+        let mut event_ids_by_game: Vec<(i64, Vec<i64>)> = Vec::new();
+        for game in completed_games {
+            let Some(game_id) = game_ids.get(game.id) else {
+                stats.games_skipped += 1;
+                continue;
+            };
+            let game_event_ids = event_ids.get(game_id).cloned().unwrap_or_default();
+            if game_event_ids.len() != game.events.len() {
+                stats.games_skipped += 1;
+                continue;
+            }
+            games_to_rebuild.push((*game_id, game));
+            event_ids_by_game.push((*game_id, game_event_ids));
+        }
+        stats.games_rebuilt = games_to_rebuild.len();
+
+        let rebuilt_game_ids = games_to_rebuild.iter().map(|(id, _)| *id).collect_vec();
+        let rebuilt_event_ids = event_ids_by_game
+            .iter()
+            .flat_map(|(_, ids)| ids.iter().copied())
+            .collect_vec();
+
+        let rows_before = count_derived_rows(conn, table, &rebuilt_game_ids, &rebuilt_event_ids)?;
+        delete_derived_rows(conn, table, &rebuilt_game_ids, &rebuilt_event_ids)?;
+
+        let games = games_to_rebuild.as_slice();
+        let events = &event_ids_by_game;
+        match table {
+            DerivedTable::EventBaserunners => {
+                use crate::data_schema::data::event_baserunners::dsl as baserunners_dsl;
+                let rows = iter::zip(events, games)
+                    .flat_map(|((_, event_ids), (_, game))| {
+                        iter::zip(event_ids, &game.events).flat_map(|(event_id, event)| {
+                            super::to_db_format::event_to_baserunners(taxa, *event_id, event)
+                        })
+                    })
+                    .collect_vec();
+                diesel::copy_from(baserunners_dsl::event_baserunners)
+                    .from_insertable(&rows)
+                    .execute(conn)?;
+            }
+            DerivedTable::EventFielders => {
+                use crate::data_schema::data::event_fielders::dsl as fielders_dsl;
+                let rows = iter::zip(events, games)
+                    .flat_map(|((_, event_ids), (_, game))| {
+                        iter::zip(event_ids, &game.events).flat_map(|(event_id, event)| {
+                            super::to_db_format::event_to_fielders(taxa, *event_id, event)
+                        })
+                    })
+                    .collect_vec();
+                diesel::copy_from(fielders_dsl::event_fielders)
+                    .from_insertable(&rows)
+                    .execute(conn)?;
+                // The new rows don't have their players resolved yet
+                super::resolve_player_identities(conn, &rebuilt_game_ids)?;
+            }
+            DerivedTable::AuroraPhotos => super::insert_aurora_photos(conn, taxa, events, games)?,
+            DerivedTable::Ejections => super::insert_ejections(conn, taxa, events, games)?,
+            DerivedTable::FailedEjections => super::insert_failed_ejections(conn, events, games)?,
+            DerivedTable::DoorPrizes => super::insert_door_prizes(conn, events, games)?,
+            DerivedTable::Efflorescence => super::insert_efflorescences(conn, taxa, events, games)?,
+            DerivedTable::PitcherChanges => super::insert_pitcher_changes(conn, taxa, games)?,
+            DerivedTable::PitcherAppearances => {
+                super::insert_pitcher_appearances(conn, taxa, games)?
+            }
+            DerivedTable::GameInnings => super::insert_game_innings(conn, games)?,
+            DerivedTable::Parties => super::insert_parties(conn, taxa, games)?,
+            DerivedTable::Wither => super::insert_withers(conn, taxa, games)?,
+            DerivedTable::ConsumptionContests => super::insert_consumption_contests(conn, games)?,
+            DerivedTable::EventCheers => super::insert_cheers(
+                conn,
+                events,
+                games,
+                cheer_table
+                    .as_ref()
+                    .expect("cheer table is created above for this table"),
+            )?,
+            DerivedTable::EventBalkReasons => super::insert_balk_reasons(
+                conn,
+                events,
+                games,
+                balk_reason_table
+                    .as_ref()
+                    .expect("balk reason table is created above for this table"),
+            )?,
+            DerivedTable::EventDefense => super::insert_event_defenses(conn, events, games)?,
+            DerivedTable::PlateAppearances => super::insert_plate_appearances(conn, events, games)?,
+            DerivedTable::EventWinProbability => {
+                super::insert_win_probabilities(conn, events, games)?
+            }
+            DerivedTable::EventPitchSequence => {
+                super::insert_pitch_sequences(conn, taxa, events, games)?
+            }
+        }
+
+        let rows_after = count_derived_rows(conn, table, &rebuilt_game_ids, &rebuilt_event_ids)?;
+        record_admin_action(
+            conn,
+            &NewAdminAudit {
+                actor,
+                action: "rebuild_derived_table",
+                details: serde_json::json!({
+                    "table": table.to_string(),
+                    "mmolb_game_ids": games.iter().map(|(_, game)| game.id).collect_vec(),
+                    "games_rebuilt": stats.games_rebuilt,
+                    "games_skipped": stats.games_skipped,
+                    "games_failed": stats.games_failed,
+                }),
+                before: Some(serde_json::json!({ "rows": rows_before })),
+                after: Some(serde_json::json!({ "rows": rows_after })),
+            },
+        )?;
+
+        Ok(stats)
+    })
+}
+
+#[derive(Debug, Default)]
+pub struct RecomputeGameMetadataStats {
+    /// Games whose metadata was recomputed, whether or not it changed
+    pub games_recomputed: usize,
+    /// Games where at least one recomputed column differed from the stored
+    /// value
+    pub games_changed: usize,
+    /// Completed games that aren't in `data.games`. These need a full
+    /// ingest.
+    pub games_skipped: usize,
+}
+
+fn game_metadata<'g>(game: &CompletedGameForDb<'g>) -> GameMetadataUpdate<'g> {
+    GameMetadataUpdate {
+        away_team_final_score: game.away_team_final_score,
+        home_team_final_score: game.home_team_final_score,
+        home_team_earned_coins: game.home_team_earned_coins,
+        away_team_earned_coins: game.away_team_earned_coins,
+        home_team_photo_contest_top_scorer: game.home_team_photo_contest_top_scorer,
+        home_team_photo_contest_score: game.home_team_photo_contest_score,
+        away_team_photo_contest_top_scorer: game.away_team_photo_contest_top_scorer,
+        away_team_photo_contest_score: game.away_team_photo_contest_score,
+        home_manager_name: game.home_manager_name,
+        away_manager_name: game.away_manager_name,
+        stadium_name: game.stadium_name,
+    }
+}
+
+/// Overwrites the computed columns of `data.games` (final scores, earned
+/// coins, photo contest results, manager and stadium names) with the values
+/// from these games. Only completed games are updated. Events and derived
+/// tables are left alone; use [`rebuild_derived_table`] for those.
+pub fn recompute_game_metadata(
+    conn: &mut PgConnection,
+    games: &[GameForDb],
+    actor: &str,
+) -> QueryResult<RecomputeGameMetadataStats> {
+    use crate::data_schema::data::games::dsl as games_dsl;
+
+    let completed_games = games
+        .iter()
+        .filter_map(|game| match game {
+            GameForDb::Completed { game, .. } => Some(game),
+            _ => None,
+        })
+        .collect_vec();
+
+    conn.transaction(|conn| {
+        let existing = games_dsl::games
+            .filter(games_dsl::mmolb_game_id.eq_any(completed_games.iter().map(|g| g.id)))
+            .select(DbGame::as_select())
+            .get_results(conn)?
+            .into_iter()
+            .map(|game| (game.mmolb_game_id.clone(), game))
+            .collect::<hashbrown::HashMap<_, _>>();
+
+        let mut stats = RecomputeGameMetadataStats::default();
+        let mut changed_game_ids = Vec::new();
+        let mut games_before = serde_json::Map::new();
+        let mut games_after = serde_json::Map::new();
+        for game in completed_games {
+            let Some(existing_game) = existing.get(game.id) else {
+                stats.games_skipped += 1;
+                continue;
+            };
+            stats.games_recomputed += 1;
+
+            let metadata = game_metadata(game);
+            if metadata.matches(existing_game) {
+                continue;
+            }
+            diesel::update(games_dsl::games.find(existing_game.id))
+                .set(&metadata)
+                .execute(conn)?;
+            stats.games_changed += 1;
+            changed_game_ids.push(game.id);
+            games_before.insert(
+                game.id.to_string(),
+                serde_json::json!(GameMetadataUpdate::from(existing_game)),
+            );
+            games_after.insert(game.id.to_string(), serde_json::json!(metadata));
+        }
+
+        record_admin_action(
+            conn,
+            &NewAdminAudit {
+                actor,
+                action: "recompute_game_metadata",
+                details: serde_json::json!({
+                    "changed_mmolb_game_ids": changed_game_ids,
+                    "games_recomputed": stats.games_recomputed,
+                    "games_changed": stats.games_changed,
+                    "games_skipped": stats.games_skipped,
+                }),
+                before: Some(serde_json::json!({
+                    "rows": stats.games_changed,
+                    "games": games_before,
+                })),
+                after: Some(serde_json::json!({
+                    "rows": stats.games_changed,
+                    "games": games_after,
+                })),
+            },
+        )?;
+
+        Ok(stats)
+    })
+}
+
+/// Returns the number of rows deleted from the table itself, not counting
+/// its child table if it has one
+/// How many rows `table` (and its child table, if it has one) has for these
+/// games, keyed by table name
+fn count_derived_rows(
+    conn: &mut PgConnection,
+    table: DerivedTable,
+    game_ids: &[i64],
+    event_ids: &[i64],
+) -> QueryResult<serde_json::Map<String, serde_json::Value>> {
+    use crate::data_schema::data;
+
+    let mut counts = serde_json::Map::new();
+    macro_rules! count_by {
+        ($table:ident, $column:ident, $ids:expr) => {{
+            let count = data::$table::table
+                .filter(data::$table::$column.eq_any($ids))
+                .count()
+                .get_result::<i64>(conn)?;
+            counts.insert(stringify!($table).to_string(), count.into());
+        }};
+    }
+
+    match table {
+        DerivedTable::EventBaserunners => count_by!(event_baserunners, event_id, event_ids),
+        DerivedTable::EventFielders => count_by!(event_fielders, event_id, event_ids),
+        DerivedTable::AuroraPhotos => count_by!(aurora_photos, event_id, event_ids),
+        DerivedTable::Ejections => count_by!(ejections, event_id, event_ids),
+        DerivedTable::FailedEjections => count_by!(failed_ejections, event_id, event_ids),
+        DerivedTable::DoorPrizes => {
+            count_by!(door_prize_items, event_id, event_ids);
+            count_by!(door_prizes, event_id, event_ids)
+        }
+        DerivedTable::Efflorescence => {
+            count_by!(efflorescence_growth, event_id, event_ids);
+            count_by!(efflorescence, event_id, event_ids)
+        }
+        DerivedTable::PitcherChanges => count_by!(pitcher_changes, game_id, game_ids),
+        DerivedTable::PitcherAppearances => count_by!(pitcher_appearances, game_id, game_ids),
+        DerivedTable::GameInnings => count_by!(game_innings, game_id, game_ids),
+        DerivedTable::Parties => count_by!(parties, game_id, game_ids),
+        DerivedTable::Wither => count_by!(wither, game_id, game_ids),
+        DerivedTable::ConsumptionContests => {
+            count_by!(consumption_contest_events, game_id, game_ids);
+            count_by!(consumption_contests, game_id, game_ids)
+        }
+        DerivedTable::EventCheers => count_by!(event_cheers, event_id, event_ids),
+        DerivedTable::EventBalkReasons => count_by!(event_balk_reasons, event_id, event_ids),
+        DerivedTable::EventDefense => count_by!(event_defense, event_id, event_ids),
+        DerivedTable::PlateAppearances => count_by!(plate_appearances, event_id, event_ids),
+        DerivedTable::EventWinProbability => {
+            count_by!(event_win_probability, event_id, event_ids)
+        }
+        DerivedTable::EventPitchSequence => count_by!(event_pitch_sequence, event_id, event_ids),
+    }
+
+    Ok(counts)
+}
+
+fn delete_derived_rows(
+    conn: &mut PgConnection,
+    table: DerivedTable,
+    game_ids: &[i64],
+    event_ids: &[i64],
+) -> QueryResult<()> {
+    use crate::data_schema::data;
+
+    // Each table is a different type, so this can't be factored out any
+    // further than a macro
+    macro_rules! delete_by {
+        ($table:ident, $column:ident, $ids:expr) => {{
+            diesel::delete(data::$table::table.filter(data::$table::$column.eq_any($ids)))
+                .execute(conn)?;
+        }};
+    }
+
+    match table {
+        DerivedTable::EventBaserunners => delete_by!(event_baserunners, event_id, event_ids),
+        DerivedTable::EventFielders => delete_by!(event_fielders, event_id, event_ids),
+        DerivedTable::AuroraPhotos => delete_by!(aurora_photos, event_id, event_ids),
+        DerivedTable::Ejections => delete_by!(ejections, event_id, event_ids),
+        DerivedTable::FailedEjections => delete_by!(failed_ejections, event_id, event_ids),
+        DerivedTable::DoorPrizes => {
+            delete_by!(door_prize_items, event_id, event_ids);
+            delete_by!(door_prizes, event_id, event_ids)
+        }
+        DerivedTable::Efflorescence => {
+            delete_by!(efflorescence_growth, event_id, event_ids);
+            delete_by!(efflorescence, event_id, event_ids)
+        }
+        DerivedTable::PitcherChanges => delete_by!(pitcher_changes, game_id, game_ids),
+        DerivedTable::PitcherAppearances => delete_by!(pitcher_appearances, game_id, game_ids),
+        DerivedTable::GameInnings => delete_by!(game_innings, game_id, game_ids),
+        DerivedTable::Parties => delete_by!(parties, game_id, game_ids),
+        DerivedTable::Wither => delete_by!(wither, game_id, game_ids),
+        DerivedTable::ConsumptionContests => {
+            delete_by!(consumption_contest_events, game_id, game_ids);
+            delete_by!(consumption_contests, game_id, game_ids)
+        }
+        DerivedTable::EventCheers => delete_by!(event_cheers, event_id, event_ids),
+        DerivedTable::EventBalkReasons => delete_by!(event_balk_reasons, event_id, event_ids),
+        DerivedTable::EventDefense => delete_by!(event_defense, event_id, event_ids),
+        DerivedTable::PlateAppearances => delete_by!(plate_appearances, event_id, event_ids),
+        DerivedTable::EventWinProbability => {
+            delete_by!(event_win_probability, event_id, event_ids)
+        }
+        DerivedTable::EventPitchSequence => delete_by!(event_pitch_sequence, event_id, event_ids),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{QueryError, postgres_url_from_environment};
+
+    #[test]
+    fn recomputed_metadata_only_matches_a_game_with_every_value_equal() {
+        use crate::data_schema::data::games::dsl as games_dsl;
+
+        let url = postgres_url_from_environment();
+        let mut conn = PgConnection::establish(&url)
+            .expect("postgres_url_from_environment should return a valid connection string");
+
+        conn.test_transaction(|conn| {
+            diesel::sql_query(
+                "
+                with w as (
+                    insert into data.weather (name, emoji, tooltip)
+                    values ('Test Weather', '', '')
+                    returning id
+                )
+                insert into data.games
+                    (mmolb_game_id, weather, season, superstar_day, away_team_emoji,
+                     away_team_name, away_team_mmolb_id, home_team_emoji, home_team_name,
+                     home_team_mmolb_id, home_manager_name, is_ongoing, from_version,
+                     started_at, away_team_final_score, home_team_final_score)
+                select 'dddddddddddddddddd000001', w.id, -1, null, '', 'Away',
+                    'recompute-test-away', '', 'Home', 'recompute-test-home', 'Manager',
+                    false, '2025-02-15', '2025-02-15T00:00:00Z', 3, 5
+                from w
+                ",
+            )
+            .execute(conn)?;
+            let game = games_dsl::games
+                .filter(games_dsl::mmolb_game_id.eq("dddddddddddddddddd000001"))
+                .select(DbGame::as_select())
+                .get_result(conn)?;
+
+            let metadata = GameMetadataUpdate {
+                away_team_final_score: Some(3),
+                home_team_final_score: Some(5),
+                home_team_earned_coins: None,
+                away_team_earned_coins: None,
+                home_team_photo_contest_top_scorer: None,
+                home_team_photo_contest_score: None,
+                away_team_photo_contest_top_scorer: None,
+                away_team_photo_contest_score: None,
+                home_manager_name: Some("Manager"),
+                away_manager_name: None,
+                stadium_name: None,
+            };
+            assert!(metadata.matches(&game));
+
+            let changed_score = GameMetadataUpdate {
+                home_team_final_score: Some(6),
+                ..metadata
+            };
+            assert!(!changed_score.matches(&game));
+
+            // A value that's newly known counts as a change
+            let new_stadium = GameMetadataUpdate {
+                stadium_name: Some("Stadium"),
+                ..metadata
+            };
+            assert!(!new_stadium.matches(&game));
+
+            Ok::<_, QueryError>(())
+        });
+    }
+}
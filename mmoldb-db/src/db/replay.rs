@@ -0,0 +1,188 @@
+use diesel::{PgConnection, prelude::*};
+use itertools::Itertools;
+use mmolb_parsing::parsed_event::{Ejection, EjectionReplacement};
+
+use super::events_for_games;
+use crate::event_detail::{EventDetail, EventDetailRunner};
+use crate::models::{DbGame, DbParty, DbPitcherChange};
+use crate::taxa::{Taxa, TaxaBase};
+
+pub use mmoldb_client::types::{
+    GameReplay, ReplayEjection, ReplayEvent, ReplayItem, ReplayParty, ReplayPitcherChange,
+    ReplayRunner,
+};
+
+fn bases_occupied(bases: impl IntoIterator<Item = TaxaBase>) -> [bool; 3] {
+    let mut occupied = [false; 3];
+    for base in bases {
+        match base {
+            TaxaBase::Home => {}
+            TaxaBase::First => occupied[0] = true,
+            TaxaBase::Second => occupied[1] = true,
+            TaxaBase::Third => occupied[2] = true,
+        }
+    }
+    occupied
+}
+
+fn replay_ejection(ejection: &Ejection<String>) -> ReplayEjection {
+    match ejection {
+        Ejection::Ejection {
+            team,
+            ejected_player,
+            violation_type,
+            reason,
+            replacement,
+        } => ReplayEjection::Ejection {
+            team_name: team.name.clone(),
+            ejected_player_name: ejected_player.name.clone(),
+            violation_type: violation_type.to_string(),
+            reason: reason.to_string(),
+            replacement_player_name: match replacement {
+                EjectionReplacement::BenchPlayer { player_name } => player_name.clone(),
+                EjectionReplacement::RosterPlayer { player } => player.name.clone(),
+            },
+        },
+        Ejection::FailedEjection { player_names } => ReplayEjection::FailedEjection {
+            player_names: player_names.clone(),
+        },
+    }
+}
+
+fn replay_runner(runner: &EventDetailRunner<String>) -> ReplayRunner {
+    ReplayRunner {
+        name: runner.name.clone(),
+        base_before: runner.base_before.map(|base| base.to_string()),
+        base_after: runner.base_after.to_string(),
+        is_out: runner.is_out,
+        is_steal: runner.is_steal,
+    }
+}
+
+fn replay_event(event: &EventDetail<String>) -> ReplayEvent {
+    ReplayEvent {
+        game_event_index: event.game_event_index as i32,
+        inning: event.inning as i32,
+        top_of_inning: event.top_of_inning,
+        event_type: event.detail_type.to_string(),
+        batter_name: event.batter_name.clone(),
+        pitcher_name: event.pitcher_name.clone(),
+        balls_before: event.balls_before as i32,
+        strikes_before: event.strikes_before as i32,
+        outs_before: event.outs_before,
+        outs_after: event.outs_after,
+        away_team_score_before: event.away_team_score_before as i32,
+        away_team_score_after: event.away_team_score_after as i32,
+        home_team_score_before: event.home_team_score_before as i32,
+        home_team_score_after: event.home_team_score_after as i32,
+        bases_occupied_before: bases_occupied(
+            event
+                .baserunners
+                .iter()
+                .filter_map(|runner| runner.base_before),
+        ),
+        bases_occupied_after: bases_occupied(
+            event
+                .baserunners
+                .iter()
+                .filter(|runner| !runner.is_out)
+                .map(|runner| runner.base_after),
+        ),
+        runners: event.baserunners.iter().map(replay_runner).collect_vec(),
+        ejection: event.ejection.as_ref().map(replay_ejection),
+    }
+}
+
+/// Assembles the replay of one game. Returns None if the game doesn't exist.
+pub fn game_replay(
+    conn: &mut PgConnection,
+    taxa: &Taxa,
+    mmolb_game_id: &str,
+) -> QueryResult<Option<GameReplay>> {
+    use crate::data_schema::data::games::dsl as games_dsl;
+    use crate::data_schema::data::parties::dsl as parties_dsl;
+    use crate::data_schema::data::pitcher_changes::dsl as pitcher_changes_dsl;
+
+    let Some(game) = games_dsl::games
+        .filter(games_dsl::mmolb_game_id.eq(mmolb_game_id))
+        .select(DbGame::as_select())
+        .get_result(conn)
+        .optional()?
+    else {
+        return Ok(None);
+    };
+
+    let (games_events, _) = events_for_games(conn, taxa, &[mmolb_game_id])?;
+
+    let parties = parties_dsl::parties
+        .filter(parties_dsl::game_id.eq(game.id))
+        .select(DbParty::as_select())
+        .get_results(conn)?;
+
+    let pitcher_changes = pitcher_changes_dsl::pitcher_changes
+        .filter(pitcher_changes_dsl::game_id.eq(game.id))
+        .select(DbPitcherChange::as_select())
+        .get_results(conn)?;
+
+    let mut items = Vec::new();
+    let mut event_errors = Vec::new();
+    for event in games_events.into_iter().flat_map(|(_, events)| events) {
+        match event {
+            Ok(event) => items.push(ReplayItem::Event(replay_event(&event))),
+            Err(err) => event_errors.push(err.to_string()),
+        }
+    }
+
+    // A bad taxa id only loses that one item, same as an event that can't be
+    // reconstructed
+    for party in parties {
+        match taxa.attribute_from_id(party.attribute) {
+            Ok(attribute) => items.push(ReplayItem::Party(ReplayParty {
+                game_event_index: party.game_event_index,
+                top_of_inning: party.top_of_inning,
+                is_pitcher: party.is_pitcher,
+                player_name: party.player_name,
+                attribute: attribute.to_string(),
+                value: party.value,
+                durability_loss: party.durability_loss,
+            })),
+            Err(err) => event_errors.push(err.to_string()),
+        }
+    }
+
+    for change in pitcher_changes {
+        match taxa.pitcher_change_source_from_id(change.source) {
+            Ok(source) => items.push(ReplayItem::PitcherChange(ReplayPitcherChange {
+                game_event_index: change.game_event_index,
+                inning: change.inning,
+                top_of_inning: change.top_of_inning,
+                source: source.to_string(),
+                pitcher_name: change.pitcher_name,
+                new_pitcher_name: change.new_pitcher_name,
+            })),
+            Err(err) => event_errors.push(err.to_string()),
+        }
+    }
+
+    // Stable sort, so items at the same index stay in the order above
+    items.sort_by_key(ReplayItem::game_event_index);
+
+    Ok(Some(GameReplay {
+        mmolb_game_id: game.mmolb_game_id,
+        season: game.season,
+        day: game.day,
+        superstar_day: game.superstar_day,
+        away_team_emoji: game.away_team_emoji,
+        away_team_name: game.away_team_name,
+        away_team_mmolb_id: game.away_team_mmolb_id,
+        home_team_emoji: game.home_team_emoji,
+        home_team_name: game.home_team_name,
+        home_team_mmolb_id: game.home_team_mmolb_id,
+        stadium_name: game.stadium_name,
+        is_ongoing: game.is_ongoing,
+        away_team_final_score: game.away_team_final_score,
+        home_team_final_score: game.home_team_final_score,
+        items,
+        event_errors,
+    }))
+}
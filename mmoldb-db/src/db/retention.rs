@@ -0,0 +1,314 @@
+use chrono::{DateTime, Utc};
+use diesel::{PgConnection, prelude::*, sql_query, sql_types::*};
+use itertools::Itertools;
+
+use crate::QueryError;
+
+/// What one call to [`prune_versions`] deleted
+#[derive(Debug, QueryableByName)]
+pub struct PrunedVersions {
+    #[diesel(sql_type = Int8)]
+    pub num_versions: i64,
+    #[diesel(sql_type = Int8)]
+    pub num_entities: i64,
+    #[diesel(sql_type = Nullable<Timestamptz>)]
+    pub earliest_valid_from: Option<DateTime<Utc>>,
+    #[diesel(sql_type = Nullable<Timestamptz>)]
+    pub latest_valid_from: Option<DateTime<Utc>>,
+    /// The last entity this call looked at. Pass it as `after_entity_id` to
+    /// continue. `None` once every entity has been looked at.
+    #[diesel(sql_type = Nullable<Text>)]
+    pub last_entity_id: Option<String>,
+}
+
+/// Deletes main MMOLB raw versions of `kind` from `data.versions`, looking
+/// at the versions of up to `batch_size` entities with ids after
+/// `after_entity_id`. A version is only deleted if:
+///
+/// - it stopped being valid before `older_than`,
+/// - it isn't one of the `keep_latest` latest versions of its entity, and
+/// - it has been processed (with or without errors).
+///
+/// The derived tables are left alone, but pruned versions can't be
+/// reprocessed, so a rebuild or a rollback to before `older_than` will
+/// leave gaps.
+pub fn prune_versions(
+    conn: &mut PgConnection,
+    kind: &str,
+    keep_latest: i64,
+    older_than: DateTime<Utc>,
+    after_entity_id: &str,
+    batch_size: i64,
+) -> QueryResult<PrunedVersions> {
+    sql_query(
+        "
+        with page as (
+            select distinct v.entity_id
+            from data.versions v
+            where v.kind=$1 and v.source is null and v.entity_id > $4
+            order by v.entity_id
+            limit $5
+        ),
+        ranked as (
+            select
+                v.entity_id,
+                v.valid_from,
+                v.valid_to,
+                row_number() over (partition by v.entity_id order by v.valid_from desc) as recency
+            from data.versions v
+            join page p on p.entity_id=v.entity_id
+            where v.kind=$1 and v.source is null
+        ),
+        to_prune as (
+            select r.entity_id, r.valid_from
+            from ranked r
+            where r.recency > $2
+                and r.valid_to is not null
+                and r.valid_to < $3
+                and exists (
+                    select 1 from data.versions_processed p
+                    where p.kind=$1 and p.entity_id=r.entity_id and p.valid_from=r.valid_from
+                )
+        ),
+        deleted as (
+            delete from data.versions v
+            using to_prune tp
+            where v.kind=$1
+                and v.source is null
+                and v.entity_id=tp.entity_id
+                and v.valid_from=tp.valid_from
+            returning v.entity_id, v.valid_from
+        )
+        select
+            count(*) as num_versions,
+            count(distinct entity_id) as num_entities,
+            min(valid_from) as earliest_valid_from,
+            max(valid_from) as latest_valid_from,
+            (select max(entity_id) from page) as last_entity_id
+        from deleted
+    ",
+    )
+    .bind::<Text, _>(kind)
+    .bind::<Int8, _>(keep_latest)
+    .bind::<Timestamptz, _>(older_than)
+    .bind::<Text, _>(after_entity_id)
+    .bind::<Int8, _>(batch_size)
+    .get_result(conn)
+}
+
+#[derive(QueryableByName)]
+struct UncompressedVersion {
+    #[diesel(sql_type = Text)]
+    entity_id: String,
+    #[diesel(sql_type = Timestamptz)]
+    valid_from: DateTime<Utc>,
+    #[diesel(sql_type = Jsonb)]
+    data: serde_json::Value,
+}
+
+/// What one call to [`compress_versions`] compressed
+#[derive(Debug)]
+pub struct CompressedVersions {
+    pub num_versions: usize,
+    pub uncompressed_bytes: usize,
+    pub compressed_bytes: usize,
+    /// The last version this call compressed. Pass it as `after` to
+    /// continue. `None` once there's nothing left to compress.
+    pub last_version: Option<(String, DateTime<Utc>)>,
+}
+
+fn compress_json(data: &serde_json::Value, level: i32) -> QueryResult<(usize, Vec<u8>)> {
+    let json =
+        serde_json::to_vec(data).map_err(|err| QueryError::SerializationError(Box::new(err)))?;
+    let compressed = zstd::encode_all(json.as_slice(), level)
+        .map_err(|err| QueryError::SerializationError(Box::new(err)))?;
+    Ok((json.len(), compressed))
+}
+
+/// A raw version's data, from `data` or, if the version was compressed by
+/// [`compress_versions`], from `data_zstd`
+pub fn decompress_version_data(
+    data: Option<serde_json::Value>,
+    data_zstd: Option<&[u8]>,
+) -> QueryResult<serde_json::Value> {
+    match (data, data_zstd) {
+        (Some(data), _) => Ok(data),
+        (None, Some(compressed)) => {
+            let json = zstd::decode_all(compressed)
+                .map_err(|err| QueryError::DeserializationError(Box::new(err)))?;
+            serde_json::from_slice(&json)
+                .map_err(|err| QueryError::DeserializationError(Box::new(err)))
+        }
+        // The versions_data_stored constraint prevents this
+        (None, None) => Err(QueryError::DeserializationError(
+            "version has neither data nor data_zstd".into(),
+        )),
+    }
+}
+
+/// Compresses the data of up to `batch_size` main MMOLB raw versions of
+/// `kind` that come after `after` in (entity id, valid_from) order, stopped
+/// being valid before `older_than`, and have been processed. Compressed
+/// versions can still be reprocessed.
+pub fn compress_versions(
+    conn: &mut PgConnection,
+    kind: &str,
+    older_than: DateTime<Utc>,
+    after: Option<(&str, DateTime<Utc>)>,
+    batch_size: i64,
+    level: i32,
+) -> QueryResult<CompressedVersions> {
+    let (after_entity_id, after_valid_from) = after.unwrap_or(("", DateTime::<Utc>::MIN_UTC));
+    let versions = sql_query(
+        "
+        select v.entity_id, v.valid_from, v.data
+        from data.versions v
+        where v.kind=$1
+            and v.source is null
+            and v.data is not null
+            and v.valid_to < $2
+            and (v.entity_id, v.valid_from) > ($3, $4)
+            and exists (
+                select 1 from data.versions_processed p
+                where p.kind=$1 and p.entity_id=v.entity_id and p.valid_from=v.valid_from
+            )
+        order by v.entity_id, v.valid_from
+        limit $5
+    ",
+    )
+    .bind::<Text, _>(kind)
+    .bind::<Timestamptz, _>(older_than)
+    .bind::<Text, _>(after_entity_id)
+    .bind::<Timestamptz, _>(after_valid_from)
+    .bind::<Int8, _>(batch_size)
+    .get_results::<UncompressedVersion>(conn)?;
+
+    let mut uncompressed_bytes = 0;
+    let mut compressed_bytes = 0;
+    let compressed = versions
+        .iter()
+        .map(|version| {
+            let (json_len, compressed) = compress_json(&version.data, level)?;
+            uncompressed_bytes += json_len;
+            compressed_bytes += compressed.len();
+            Ok(compressed)
+        })
+        .collect::<QueryResult<Vec<_>>>()?;
+
+    // The data check makes sure a version that was replaced in the meantime
+    // (by a rollback and reingest, say) isn't overwritten
+    let num_versions = sql_query(
+        "
+        update data.versions v
+        set data=null, data_zstd=c.data_zstd
+        from unnest($2::text[], $3::timestamptz[], $4::bytea[]) as c(entity_id, valid_from, data_zstd)
+        where v.kind=$1
+            and v.source is null
+            and v.entity_id=c.entity_id
+            and v.valid_from=c.valid_from
+            and v.data is not null
+    ",
+    )
+    .bind::<Text, _>(kind)
+    .bind::<Array<Text>, _>(versions.iter().map(|v| v.entity_id.as_str()).collect_vec())
+    .bind::<Array<Timestamptz>, _>(versions.iter().map(|v| v.valid_from).collect_vec())
+    .bind::<Array<Bytea>, _>(compressed)
+    .execute(conn)?;
+
+    Ok(CompressedVersions {
+        num_versions,
+        uncompressed_bytes,
+        compressed_bytes,
+        last_version: versions
+            .last()
+            .map(|version| (version.entity_id.clone(), version.valid_from)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::NewVersionProcessed;
+    use crate::{db, postgres_url_from_environment};
+    use chron::ChronEntity;
+
+    #[test]
+    fn old_versions_are_compressed_then_pruned() {
+        let url = postgres_url_from_environment();
+        let mut conn = PgConnection::establish(&url)
+            .expect("postgres_url_from_environment should return a valid connection string");
+
+        conn.test_transaction(|conn| {
+            use crate::data_schema::data::versions::dsl as v_dsl;
+            use crate::data_schema::data::versions_processed::dsl as vp_dsl;
+
+            let kind = "retention_test";
+            let first_valid_from = Utc::now() - chrono::Duration::days(30);
+            let valid_froms = (0..3)
+                .map(|day| first_valid_from + chrono::Duration::days(day))
+                .collect_vec();
+            for (day, valid_from) in valid_froms.iter().enumerate() {
+                let version = ChronEntity {
+                    kind: kind.to_string(),
+                    entity_id: "retention-test-entity".to_string(),
+                    valid_from: *valid_from,
+                    valid_to: None,
+                    data: serde_json::json!({ "day": day }),
+                };
+                db::insert_versions(conn, &[version], None)?;
+                diesel::insert_into(vp_dsl::versions_processed)
+                    .values(NewVersionProcessed {
+                        kind,
+                        entity_id: "retention-test-entity",
+                        valid_from: *valid_from,
+                        skipped: false,
+                        fatal_error: false,
+                    })
+                    .execute(conn)?;
+            }
+
+            // Only the two versions that were replaced can be compressed
+            let compressed = compress_versions(conn, kind, Utc::now(), None, 10, 3)?;
+            assert_eq!(compressed.num_versions, 2);
+            assert_eq!(
+                compressed.last_version,
+                Some(("retention-test-entity".to_string(), valid_froms[1])),
+            );
+            let after = compressed
+                .last_version
+                .as_ref()
+                .map(|(id, valid_from)| (id.as_str(), *valid_from));
+            let compressed = compress_versions(conn, kind, Utc::now(), after, 10, 3)?;
+            assert_eq!(compressed.num_versions, 0);
+            assert_eq!(compressed.last_version, None);
+
+            let stored = v_dsl::versions
+                .filter(v_dsl::kind.eq(kind))
+                .order_by(v_dsl::valid_from.asc())
+                .select((v_dsl::data, v_dsl::data_zstd))
+                .get_results::<(Option<serde_json::Value>, Option<Vec<u8>>)>(conn)?;
+            assert!(stored[0].0.is_none() && stored[1].0.is_none());
+            assert!(stored[2].1.is_none());
+            for (day, (data, data_zstd)) in stored.into_iter().enumerate() {
+                assert_eq!(
+                    decompress_version_data(data, data_zstd.as_deref())?,
+                    serde_json::json!({ "day": day }),
+                );
+            }
+
+            let pruned = prune_versions(conn, kind, 2, Utc::now(), "", 10)?;
+            assert_eq!(pruned.num_versions, 1);
+            assert_eq!(pruned.earliest_valid_from, Some(valid_froms[0]));
+            assert_eq!(
+                pruned.last_entity_id.as_deref(),
+                Some("retention-test-entity"),
+            );
+
+            let pruned = prune_versions(conn, kind, 2, Utc::now(), "retention-test-entity", 10)?;
+            assert_eq!(pruned.num_versions, 0);
+            assert_eq!(pruned.last_entity_id, None);
+
+            Ok::<_, QueryError>(())
+        });
+    }
+}
@@ -0,0 +1,274 @@
+use chrono::{DateTime, Utc};
+use diesel::{PgConnection, prelude::*, sql_query, sql_types::*};
+use serde::Serialize;
+
+use super::record_admin_action;
+use crate::QueryError;
+use crate::models::NewAdminAudit;
+
+/// The versioned kinds that can be rolled back
+#[derive(Debug, Copy, Clone, PartialEq, Eq, strum::EnumString, strum::Display, strum::EnumIter)]
+#[strum(serialize_all = "snake_case")]
+pub enum RollbackKind {
+    Player,
+    Team,
+    Stadium,
+    PlayerFeed,
+    TeamFeed,
+}
+
+impl RollbackKind {
+    fn is_feed(self) -> bool {
+        matches!(self, RollbackKind::PlayerFeed | RollbackKind::TeamFeed)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RollbackTableCounts {
+    pub table: &'static str,
+    /// Rows that were (or would be) deleted
+    pub deleted: usize,
+    /// Rows that were (or would be) made current again
+    pub reopened: usize,
+}
+
+macro_rules! rollback_table {
+    ($conn:expr, $($namespace:ident)::*, $table_name:ident, $dt:ident) => {{
+        // This struct is a workaround for the apparent otherwise inability
+        // to get Diesel to set a column to null
+        #[derive(AsChangeset)]
+        #[diesel(table_name = $($namespace)::*::$table_name)]
+        #[diesel(treat_none_as_null = true)]
+        struct Update {
+            valid_until: Option<DateTime<Utc>>,
+        }
+
+        // Delete all versions that began after the target date
+        let deleted = diesel::delete($($namespace)::*::$table_name::dsl::$table_name)
+            .filter($($namespace)::*::$table_name::dsl::valid_from.gt($dt))
+            .execute($conn)?;
+
+        // Un-close-out all versions that were closed out after the target date
+        let reopened = diesel::update($($namespace)::*::$table_name::dsl::$table_name)
+            .filter($($namespace)::*::$table_name::dsl::valid_until.gt($dt))
+            .set(&Update { valid_until: None })
+            .execute($conn)?;
+
+        RollbackTableCounts {
+            table: stringify!($table_name),
+            deleted,
+            reopened,
+        }
+    }};
+}
+
+/// Feed-derived tables don't have their own valid_from, so they're trimmed
+/// by feed_event_index instead. A row is deleted if its feed event was only
+/// processed from versions after `dt`. Rows whose feed event was also
+/// processed at or before `dt` are kept; when the later versions are
+/// re-ingested, the table's insert trigger suppresses exact duplicates.
+///
+/// Must run before `feed_events_processed` is trimmed.
+fn roll_back_feed_table(
+    conn: &mut PgConnection,
+    kind: RollbackKind,
+    table: &'static str,
+    entity_id_column: &str,
+    dt: DateTime<Utc>,
+) -> QueryResult<RollbackTableCounts> {
+    let deleted = sql_query(format!(
+        "
+        delete from data.{table} t
+        where exists (
+                select 1 from data.feed_events_processed fep
+                where fep.kind=$1
+                    and fep.entity_id=t.{entity_id_column}
+                    and fep.feed_event_index=t.feed_event_index
+                    and fep.valid_from > $2
+            )
+            and not exists (
+                select 1 from data.feed_events_processed fep
+                where fep.kind=$1
+                    and fep.entity_id=t.{entity_id_column}
+                    and fep.feed_event_index=t.feed_event_index
+                    and fep.valid_from <= $2
+            )
+    "
+    ))
+    .bind::<Text, _>(kind.to_string())
+    .bind::<Timestamptz, _>(dt)
+    .execute(conn)?;
+
+    Ok(RollbackTableCounts {
+        table,
+        deleted,
+        reopened: 0,
+    })
+}
+
+fn roll_back_kind(
+    conn: &mut PgConnection,
+    kind: RollbackKind,
+    dt: DateTime<Utc>,
+) -> QueryResult<Vec<RollbackTableCounts>> {
+    use crate::schema::data_schema::data as schema;
+
+    Ok(match kind {
+        RollbackKind::Player => vec![
+            rollback_table!(conn, schema, player_versions, dt),
+            rollback_table!(conn, schema, player_modification_versions, dt),
+            rollback_table!(conn, schema, player_equipment_versions, dt),
+            rollback_table!(conn, schema, player_equipment_effect_versions, dt),
+            rollback_table!(conn, schema, player_report_versions, dt),
+            rollback_table!(conn, schema, player_report_attribute_versions, dt),
+            rollback_table!(conn, schema, player_pitch_type_versions, dt),
+            rollback_table!(conn, schema, player_pitch_category_bonus_versions, dt),
+            rollback_table!(conn, schema, player_pitch_type_bonus_versions, dt),
+        ],
+        RollbackKind::Team => vec![
+            rollback_table!(conn, schema, team_versions, dt),
+            rollback_table!(conn, schema, team_player_versions, dt),
+        ],
+        RollbackKind::Stadium => vec![rollback_table!(conn, schema, stadium_versions, dt)],
+        RollbackKind::PlayerFeed => vec![
+            roll_back_feed_table(conn, kind, "player_attribute_augments", "mmolb_player_id", dt)?,
+            roll_back_feed_table(conn, kind, "player_paradigm_shifts", "mmolb_player_id", dt)?,
+            roll_back_feed_table(conn, kind, "player_recompositions", "mmolb_player_id", dt)?,
+        ],
+        RollbackKind::TeamFeed => vec![roll_back_feed_table(
+            conn,
+            kind,
+            "team_games_played",
+            "mmolb_team_id",
+            dt,
+        )?],
+    })
+}
+
+/// Undoes the ingest of every version of `kinds` that's valid from after
+/// `dt`: those versions are deleted, the versions they closed out are made
+/// current again, and their processed markers and ingest logs are deleted
+/// so the next ingest processes them again. For feed kinds, rows derived
+/// from feed events that were first processed after `dt` are deleted along
+/// with the feed events' processed markers. Everything happens in one
+/// transaction.
+///
+/// If `dry_run` is true, the transaction is always rolled back, so this
+/// only reports what would change.
+pub fn roll_back_ingest_to_date(
+    conn: &mut PgConnection,
+    dt: DateTime<Utc>,
+    kinds: &[RollbackKind],
+    dry_run: bool,
+    actor: &str,
+) -> QueryResult<Vec<RollbackTableCounts>> {
+    use crate::data_schema::data::versions_processed::dsl as vp_dsl;
+    use crate::info_schema::info::version_ingest_log::dsl as vil_dsl;
+
+    use crate::data_schema::data::feed_events_processed::dsl as fep_dsl;
+    use crate::info_schema::info::feed_event_failures::dsl as fef_dsl;
+
+    let mut counts = Vec::new();
+    let result = conn.transaction(|conn| {
+        for kind in kinds {
+            counts.extend(roll_back_kind(conn, *kind, dt)?);
+        }
+
+        let kind_names = kinds
+            .iter()
+            .map(|kind| kind.to_string())
+            .collect::<Vec<_>>();
+        let deleted = diesel::delete(
+            vp_dsl::versions_processed
+                .filter(vp_dsl::kind.eq_any(&kind_names))
+                .filter(vp_dsl::valid_from.gt(dt)),
+        )
+        .execute(conn)?;
+        counts.push(RollbackTableCounts {
+            table: "versions_processed",
+            deleted,
+            reopened: 0,
+        });
+
+        let deleted = diesel::delete(
+            vil_dsl::version_ingest_log
+                .filter(vil_dsl::kind.eq_any(&kind_names))
+                .filter(vil_dsl::valid_from.gt(dt)),
+        )
+        .execute(conn)?;
+        counts.push(RollbackTableCounts {
+            table: "version_ingest_log",
+            deleted,
+            reopened: 0,
+        });
+
+        // Feed events are marked processed individually, and the derived
+        // tables were trimmed by roll_back_kind using these markers, so
+        // they must only be deleted now
+        let feed_kind_names = kinds
+            .iter()
+            .filter(|kind| kind.is_feed())
+            .map(|kind| kind.to_string())
+            .collect::<Vec<_>>();
+        let deleted = diesel::delete(
+            fep_dsl::feed_events_processed
+                .filter(fep_dsl::kind.eq_any(&feed_kind_names))
+                .filter(fep_dsl::valid_from.gt(dt)),
+        )
+        .execute(conn)?;
+        counts.push(RollbackTableCounts {
+            table: "feed_events_processed",
+            deleted,
+            reopened: 0,
+        });
+
+        let deleted = diesel::delete(
+            fef_dsl::feed_event_failures
+                .filter(fef_dsl::kind.eq_any(&feed_kind_names))
+                .filter(fef_dsl::valid_from.gt(dt)),
+        )
+        .execute(conn)?;
+        counts.push(RollbackTableCounts {
+            table: "feed_event_failures",
+            deleted,
+            reopened: 0,
+        });
+
+        // Of the rows the rollback touched, how many there were before it and
+        // how many are left after it (only the re-opened ones)
+        let row_counts = |count: fn(&RollbackTableCounts) -> usize| {
+            counts
+                .iter()
+                .map(|c| (c.table.to_string(), serde_json::Value::from(count(c))))
+                .collect::<serde_json::Map<_, _>>()
+        };
+        record_admin_action(
+            conn,
+            &NewAdminAudit {
+                actor,
+                action: "roll_back_ingest_to_date",
+                details: serde_json::json!({
+                    "rolled_back_to": dt,
+                    "kinds": kind_names,
+                    "tables": counts,
+                }),
+                before: Some(serde_json::json!({
+                    "rows": row_counts(|c| c.deleted + c.reopened),
+                })),
+                after: Some(serde_json::json!({ "rows": row_counts(|c| c.reopened) })),
+            },
+        )?;
+
+        if dry_run {
+            Err(QueryError::RollbackTransaction)
+        } else {
+            Ok(())
+        }
+    });
+
+    match result {
+        Ok(()) => Ok(counts),
+        Err(QueryError::RollbackTransaction) if dry_run => Ok(counts),
+        Err(err) => Err(err),
+    }
+}
@@ -0,0 +1,129 @@
+use chrono::{DateTime, Utc};
+use diesel::{PgConnection, prelude::*, sql_query, sql_types::*};
+use serde::Serialize;
+
+use crate::models::{DbTeamPlayerVersion, DbTeamVersion};
+
+/// The team and its roster as they were at one point in time
+#[derive(Debug, Clone, Serialize)]
+pub struct TeamRoster {
+    /// None if the team didn't exist yet (or hasn't been ingested)
+    pub team: Option<DbTeamVersion>,
+    /// Ordered by roster position
+    pub players: Vec<DbTeamPlayerVersion>,
+}
+
+pub fn team_roster_at(
+    conn: &mut PgConnection,
+    team_id: &str,
+    at: DateTime<Utc>,
+) -> QueryResult<TeamRoster> {
+    use crate::data_schema::data::team_player_versions::dsl as tpv_dsl;
+    use crate::data_schema::data::team_versions::dsl as tv_dsl;
+
+    let team = tv_dsl::team_versions
+        .filter(tv_dsl::mmolb_team_id.eq(team_id))
+        .filter(tv_dsl::valid_from.le(at))
+        .filter(tv_dsl::valid_until.is_null().or(tv_dsl::valid_until.gt(at)))
+        .select(DbTeamVersion::as_select())
+        .get_result(conn)
+        .optional()?;
+
+    let players = tpv_dsl::team_player_versions
+        .filter(tpv_dsl::mmolb_team_id.eq(team_id))
+        .filter(tpv_dsl::valid_from.le(at))
+        .filter(
+            tpv_dsl::valid_until
+                .is_null()
+                .or(tpv_dsl::valid_until.gt(at)),
+        )
+        .order_by(tpv_dsl::team_player_index.asc())
+        .select(DbTeamPlayerVersion::as_select())
+        .get_results(conn)?;
+
+    Ok(TeamRoster { team, players })
+}
+
+#[derive(Debug, Clone, QueryableByName, Serialize)]
+pub struct DbRosterChange {
+    #[diesel(sql_type = Timestamptz)]
+    pub at: DateTime<Utc>,
+    /// One of `joined`, `left`, `recomposed`, or `changed_slot`
+    #[diesel(sql_type = Text)]
+    pub change: String,
+    #[diesel(sql_type = Text)]
+    pub mmolb_player_id: String,
+    #[diesel(sql_type = Text)]
+    pub player_name: String,
+    /// The player's name before a recomposition. Null for other changes.
+    #[diesel(sql_type = Nullable<Text>)]
+    pub previous_player_name: Option<String>,
+    #[diesel(sql_type = Nullable<Int8>)]
+    pub slot: Option<i64>,
+    /// The player's slot before a slot change. Null for other changes.
+    #[diesel(sql_type = Nullable<Int8>)]
+    pub previous_slot: Option<i64>,
+}
+
+/// Every roster change in the team's history, oldest first.
+///
+/// A team's roster is stored by position, and positions shift whenever a
+/// player leaves, so this follows each player across positions instead.
+/// A player joins when they have a version with no version immediately
+/// before it, and leaves when they have a version that ends with no version
+/// immediately after it. A recomposition is a player keeping their id but
+/// changing their name.
+pub fn team_roster_timeline(
+    conn: &mut PgConnection,
+    team_id: &str,
+) -> QueryResult<Vec<DbRosterChange>> {
+    sql_query(
+        "
+        with versions as (
+            select
+                tpv.mmolb_player_id,
+                tpv.first_name || ' ' || tpv.last_name
+                    || coalesce(' ' || tpv.name_suffix, '') as player_name,
+                tpv.slot,
+                tpv.valid_from,
+                tpv.valid_until,
+                lag(tpv.valid_until) over w as prev_valid_until,
+                lag(tpv.first_name || ' ' || tpv.last_name
+                    || coalesce(' ' || tpv.name_suffix, '')) over w as prev_player_name,
+                lag(tpv.slot) over w as prev_slot,
+                lead(tpv.valid_from) over w as next_valid_from
+            from data.team_player_versions tpv
+            where tpv.mmolb_team_id=$1 and tpv.mmolb_player_id is not null
+            window w as (partition by tpv.mmolb_player_id order by tpv.valid_from)
+        ), changes as (
+            select
+                v.valid_from as at, 'joined' as change, v.mmolb_player_id, v.player_name,
+                null::text as previous_player_name, v.slot, null::int8 as previous_slot
+            from versions v
+            where v.prev_valid_until is null or v.prev_valid_until < v.valid_from
+            union all
+            select
+                v.valid_until, 'left', v.mmolb_player_id, v.player_name, null, v.slot, null
+            from versions v
+            where v.valid_until is not null
+                and (v.next_valid_from is null or v.next_valid_from > v.valid_until)
+            union all
+            select
+                v.valid_from, 'recomposed', v.mmolb_player_id, v.player_name,
+                v.prev_player_name, v.slot, null
+            from versions v
+            where v.prev_valid_until=v.valid_from and v.prev_player_name <> v.player_name
+            union all
+            select
+                v.valid_from, 'changed_slot', v.mmolb_player_id, v.player_name, null, v.slot,
+                v.prev_slot
+            from versions v
+            where v.prev_valid_until=v.valid_from and v.prev_slot is distinct from v.slot
+        )
+        select * from changes
+        order by at, mmolb_player_id, change
+    ",
+    )
+    .bind::<Text, _>(team_id)
+    .get_results(conn)
+}
@@ -0,0 +1,113 @@
+use chrono::{DateTime, Utc};
+use diesel::{PgConnection, dsl::exists, prelude::*, sql_query};
+
+use crate::models::{NewSeasonPhase, NewVersionIngestLog, NewVersionProcessed};
+
+/// Every phase of one season, as scheduled in one version of the `time`
+/// entity
+pub struct NewSeasonPhases {
+    pub season: i32,
+    pub from_version: DateTime<Utc>,
+    pub phases: Vec<NewSeasonPhase>,
+}
+
+pub type NewTimeVersionExt<'a> = (
+    NewVersionProcessed<'a>,
+    Option<NewSeasonPhases>,
+    Vec<NewVersionIngestLog<'a>>,
+);
+
+pub fn insert_time_versions_all<'container, 'v: 'container>(
+    conn: &mut PgConnection,
+    new_time_versions: impl IntoIterator<Item = &'container NewTimeVersionExt<'v>>,
+) -> QueryResult<(usize, usize)> {
+    use crate::data_schema::data::versions_processed::dsl as vp_dsl;
+
+    let (new_processed, new_season_phases, new_ingest_logs): (
+        Vec<&NewVersionProcessed>,
+        Vec<&Option<NewSeasonPhases>>,
+        Vec<&Vec<NewVersionIngestLog>>,
+    ) = itertools::multiunzip(new_time_versions.into_iter().map(|(a, b, c)| (a, b, c)));
+
+    let mut total = new_processed.len();
+    let mut inserted = diesel::copy_from(vp_dsl::versions_processed)
+        .from_insertable(new_processed)
+        .execute(conn)?;
+
+    // Versions must be applied in order, because each one replaces the
+    // phases from the last
+    let mut seasons = Vec::new();
+    for season_phases in new_season_phases.into_iter().flatten() {
+        total += season_phases.phases.len();
+        inserted += replace_season_phases(conn, season_phases)?;
+        seasons.push(season_phases.season);
+    }
+    close_out_previous_season_phases(conn, &seasons)?;
+
+    super::insert_nested_ingest_logs(conn, new_ingest_logs)?;
+
+    Ok((total, inserted))
+}
+
+/// Replaces every phase of a season, unless the existing phases came from
+/// a newer version of `time`
+fn replace_season_phases(
+    conn: &mut PgConnection,
+    season_phases: &NewSeasonPhases,
+) -> QueryResult<usize> {
+    use crate::data_schema::data::season_phases::dsl as sp_dsl;
+
+    conn.transaction(|conn| {
+        let have_newer = diesel::select(exists(
+            sp_dsl::season_phases
+                .filter(sp_dsl::season.eq(season_phases.season))
+                .filter(sp_dsl::from_version.gt(season_phases.from_version)),
+        ))
+        .get_result::<bool>(conn)?;
+
+        if have_newer {
+            return Ok(0);
+        }
+
+        diesel::delete(sp_dsl::season_phases.filter(sp_dsl::season.eq(season_phases.season)))
+            .execute(conn)?;
+
+        diesel::insert_into(sp_dsl::season_phases)
+            .values(&season_phases.phases)
+            .execute(conn)
+    })
+}
+
+/// The last phase of a season doesn't know when it ends until the next
+/// season's phases are scheduled. This ends it when the next season's
+/// first phase starts. Only `seasons`, whose phases were just replaced, and
+/// the seasons on either side of them can be affected, so only they are
+/// looked at.
+fn close_out_previous_season_phases(
+    conn: &mut PgConnection,
+    seasons: &[i32],
+) -> QueryResult<usize> {
+    if seasons.is_empty() {
+        return Ok(0);
+    }
+
+    sql_query(
+        "
+        update data.season_phases sp
+        set ended_at=next_season.started_at
+        from (
+            select season, min(started_at) as started_at
+            from data.season_phases
+            -- the seasons after the replaced seasons, to close out the
+            -- replaced seasons' last phases, and the replaced seasons, to
+            -- close out the last phases of the seasons before them
+            where season = any($1) or season - 1 = any($1)
+            group by season
+        ) next_season
+        where sp.ended_at is null
+            and next_season.season=sp.season + 1
+    ",
+    )
+    .bind::<diesel::sql_types::Array<diesel::sql_types::Int4>, _>(seasons)
+    .execute(conn)
+}
@@ -0,0 +1,289 @@
+use diesel::{PgConnection, prelude::*, sql_query, sql_types::*};
+
+use super::record_admin_action;
+use crate::models::NewAdminAudit;
+
+/// Returns every season that has finished but hasn't had `task` completed
+/// for it yet, oldest first. A season counts as finished once none of its
+/// games are ongoing and every postseason phase in its phase calendar has
+/// ended. Seasons from before the phase calendar was ingested count as
+/// finished once games from a later season have been ingested.
+pub fn seasons_pending_rollover(conn: &mut PgConnection, task: &str) -> QueryResult<Vec<i32>> {
+    #[derive(QueryableByName)]
+    struct PendingSeason {
+        #[diesel(sql_type = Int4)]
+        season: i32,
+    }
+
+    sql_query(
+        "
+        with postseasons as (
+            select
+                sp.season,
+                -- A phase only gets an end once the phase after it is scheduled
+                bool_and(sp.ended_at is not null and sp.ended_at <= now()) as is_over
+            from data.season_phases sp
+            join taxa.day_type dt on dt.id=sp.day_type
+            where dt.name in ('PostseasonRound1', 'PostseasonRound2', 'PostseasonRound3')
+            group by sp.season
+        )
+        select distinct g.season
+        from data.games g
+        left join postseasons ps on ps.season=g.season
+        where (
+                ps.is_over
+                or (
+                    ps.season is null
+                    and not exists(select 1 from data.season_phases sp where sp.season=g.season)
+                    and g.season < (select max(season) from data.games)
+                )
+            )
+            and not exists(
+                select 1 from data.games og where og.season=g.season and og.is_ongoing
+            )
+            and not exists(
+                select 1 from info.season_rollovers sr
+                where sr.season=g.season and sr.task=$1 and sr.completed_at is not null
+            )
+        order by g.season
+    ",
+    )
+    .bind::<Text, _>(task)
+    .get_results::<PendingSeason>(conn)
+    .map(|seasons| seasons.into_iter().map(|s| s.season).collect())
+}
+
+/// Records an attempt at `task` for `season`. A successful attempt stops
+/// the job from running again. A failed one is retried next time.
+pub fn record_season_rollover_attempt(
+    conn: &mut PgConnection,
+    season: i32,
+    task: &str,
+    error: Option<&str>,
+) -> QueryResult<usize> {
+    sql_query(
+        "
+        insert into info.season_rollovers
+            (season, task, completed_at, attempts, last_attempted_at, last_error)
+        values ($1, $2, case when $3::text is null then now() end, 1, now(), $3)
+        on conflict (season, task) do update set
+            completed_at=excluded.completed_at,
+            attempts=info.season_rollovers.attempts + 1,
+            last_attempted_at=excluded.last_attempted_at,
+            last_error=excluded.last_error
+    ",
+    )
+    .bind::<Int4, _>(season)
+    .bind::<Text, _>(task)
+    .bind::<Nullable<Text>, _>(error)
+    .execute(conn)
+}
+
+/// Runs one rollover job for one season, and records that it ran. The job
+/// and its success are written in the same transaction, so a job that fails
+/// partway through leaves nothing behind and is retried from scratch next
+/// time. The failure is recorded separately.
+pub fn run_season_rollover_task(
+    conn: &mut PgConnection,
+    season: i32,
+    task: &str,
+    job: impl FnOnce(&mut PgConnection, i32) -> QueryResult<usize>,
+) -> QueryResult<usize> {
+    let result = conn.transaction(|conn| {
+        let affected = job(conn, season)?;
+        record_season_rollover_attempt(conn, season, task, None)?;
+        Ok(affected)
+    });
+
+    if let Err(err) = &result {
+        record_season_rollover_attempt(conn, season, task, Some(&err.to_string()))?;
+    }
+    result
+}
+
+/// Forgets that rollover jobs ran for `season`, so they run again on the
+/// next check. Only `task` is forgotten if it's given. Returns how many
+/// jobs were forgotten.
+pub fn clear_season_rollovers(
+    conn: &mut PgConnection,
+    season: i32,
+    task: Option<&str>,
+    actor: &str,
+) -> QueryResult<usize> {
+    use crate::info_schema::info::season_rollovers::dsl as sr_dsl;
+
+    conn.transaction(|conn| {
+        let mut query = diesel::delete(sr_dsl::season_rollovers)
+            .filter(sr_dsl::season.eq(season))
+            .into_boxed();
+        if let Some(task) = task {
+            query = query.filter(sr_dsl::task.eq(task));
+        }
+        let cleared = query.execute(conn)?;
+
+        record_admin_action(
+            conn,
+            &NewAdminAudit {
+                actor,
+                action: "clear_season_rollovers",
+                details: serde_json::json!({ "season": season, "task": task }),
+                before: Some(serde_json::json!({ "rows": cleared })),
+                after: Some(serde_json::json!({ "rows": 0 })),
+            },
+        )?;
+
+        Ok(cleared)
+    })
+}
+
+/// Records every team's final record for a season in
+/// `data.season_final_standings`, replacing any existing snapshot.
+/// Superstar games don't count.
+pub fn snapshot_season_final_standings(conn: &mut PgConnection, season: i32) -> QueryResult<usize> {
+    use crate::data_schema::data::season_final_standings::dsl as sfs_dsl;
+
+    diesel::delete(sfs_dsl::season_final_standings.filter(sfs_dsl::season.eq(season)))
+        .execute(conn)?;
+
+    sql_query(
+        "
+        insert into data.season_final_standings
+            (season, team_mmolb_id, team_name, team_emoji, games_played, wins, losses,
+             runs_scored, runs_allowed)
+        with team_games as (
+            select
+                g.started_at,
+                g.home_team_mmolb_id as team_mmolb_id,
+                g.home_team_name as team_name,
+                g.home_team_emoji as team_emoji,
+                g.home_team_final_score as runs_scored,
+                g.away_team_final_score as runs_allowed
+            from data.games g
+            where g.season=$1 and g.superstar_day is null and not g.is_ongoing
+            union all
+            select
+                g.started_at,
+                g.away_team_mmolb_id as team_mmolb_id,
+                g.away_team_name as team_name,
+                g.away_team_emoji as team_emoji,
+                g.away_team_final_score as runs_scored,
+                g.home_team_final_score as runs_allowed
+            from data.games g
+            where g.season=$1 and g.superstar_day is null and not g.is_ongoing
+        )
+        select
+            $1,
+            tg.team_mmolb_id,
+            -- Teams can be renamed mid-season, so use the name they finished with
+            (array_agg(tg.team_name order by tg.started_at desc))[1],
+            (array_agg(tg.team_emoji order by tg.started_at desc))[1],
+            count(1),
+            count(1) filter (where tg.runs_scored > tg.runs_allowed),
+            count(1) filter (where tg.runs_scored < tg.runs_allowed),
+            coalesce(sum(tg.runs_scored), 0),
+            coalesce(sum(tg.runs_allowed), 0)
+        from team_games tg
+        group by tg.team_mmolb_id
+    ",
+    )
+    .bind::<Int4, _>(season)
+    .execute(conn)
+}
+
+/// Computes the season's awards into `data.season_awards`, replacing any
+/// existing ones. Every player tied for the lead gets the award. Players are
+/// identified by name and team because that's all `data.events` records.
+pub fn compute_season_awards(conn: &mut PgConnection, season: i32) -> QueryResult<usize> {
+    use crate::data_schema::data::season_awards::dsl as sa_dsl;
+
+    diesel::delete(sa_dsl::season_awards.filter(sa_dsl::season.eq(season))).execute(conn)?;
+
+    sql_query(
+        "
+        insert into data.season_awards (season, award, player_name, team_mmolb_id, value)
+        with award_counts as (
+            select
+                'home_run_leader' as award,
+                e.batter_name as player_name,
+                case when e.top_of_inning then g.away_team_mmolb_id else g.home_team_mmolb_id end
+                    as team_mmolb_id,
+                count(1) as value
+            from data.events e
+            join data.games g on g.id=e.game_id
+            join taxa.event_type et on et.id=e.event_type
+            where g.season=$1 and g.superstar_day is null and et.name='HomeRun'
+            group by 1, 2, 3
+            union all
+            select
+                'strikeout_leader' as award,
+                e.pitcher_name as player_name,
+                case when e.top_of_inning then g.home_team_mmolb_id else g.away_team_mmolb_id end
+                    as team_mmolb_id,
+                count(1) as value
+            from data.events e
+            join data.games g on g.id=e.game_id
+            join taxa.event_type et on et.id=e.event_type
+            where g.season=$1 and g.superstar_day is null and et.is_strikeout
+            group by 1, 2, 3
+        ), ranked as (
+            select *, rank() over (partition by award order by value desc) as award_rank
+            from award_counts
+        )
+        select $1, award, player_name, team_mmolb_id, value
+        from ranked
+        where award_rank=1
+    ",
+    )
+    .bind::<Int4, _>(season)
+    .execute(conn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::postgres_url_from_environment;
+
+    #[test]
+    fn failed_jobs_are_recorded_and_can_be_rerun() {
+        use crate::info_schema::info::season_rollovers::dsl as sr_dsl;
+
+        let url = postgres_url_from_environment();
+        let mut conn = PgConnection::establish(&url)
+            .expect("postgres_url_from_environment should return a valid connection string");
+
+        conn.test_transaction(|conn| {
+            let marker = |conn: &mut PgConnection| {
+                sr_dsl::season_rollovers
+                    .filter(sr_dsl::season.eq(-1))
+                    .filter(sr_dsl::task.eq("test_task"))
+                    .select((
+                        sr_dsl::completed_at.is_not_null(),
+                        sr_dsl::attempts,
+                        sr_dsl::last_error,
+                    ))
+                    .get_result::<(bool, i32, Option<String>)>(conn)
+            };
+
+            let result = run_season_rollover_task(conn, -1, "test_task", |_, _| {
+                Err(diesel::result::Error::RollbackTransaction)
+            });
+            assert!(result.is_err());
+            let (completed, attempts, last_error) = marker(conn)?;
+            assert!(!completed);
+            assert_eq!(attempts, 1);
+            assert!(last_error.is_some());
+
+            let affected = run_season_rollover_task(conn, -1, "test_task", |_, _| Ok(3))?;
+            assert_eq!(affected, 3);
+            assert_eq!(marker(conn)?, (true, 2, None));
+
+            assert_eq!(
+                clear_season_rollovers(conn, -1, Some("test_task"), "test")?,
+                1
+            );
+            assert_eq!(marker(conn).optional()?, None);
+
+            Ok::<_, diesel::result::Error>(())
+        });
+    }
+}
@@ -0,0 +1,13 @@
+use diesel::{PgConnection, prelude::*};
+
+use crate::models::NewSlowQuery;
+
+pub fn insert_slow_query(conn: &mut PgConnection, new_query: &NewSlowQuery) -> QueryResult<()> {
+    use crate::info_schema::info::slow_queries::dsl as sq_dsl;
+
+    diesel::insert_into(sq_dsl::slow_queries)
+        .values(new_query)
+        .execute(conn)?;
+
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use diesel::{PgConnection, prelude::*, sql_query};
+
+use crate::models::{NewStadiumVersion, NewVersionIngestLog, NewVersionProcessed};
+
+pub type NewStadiumVersionExt<'a> = (
+    NewVersionProcessed<'a>,
+    Option<NewStadiumVersion<'a>>,
+    Vec<NewVersionIngestLog<'a>>,
+);
+
+pub fn insert_stadium_versions_all<'container, 'v: 'container>(
+    conn: &mut PgConnection,
+    new_stadium_versions: impl IntoIterator<Item = &'container NewStadiumVersionExt<'v>>,
+) -> QueryResult<(usize, usize)> {
+    use crate::data_schema::data::stadium_versions::dsl as sv_dsl;
+    use crate::data_schema::data::versions_processed::dsl as vp_dsl;
+
+    let (new_processed, new_stadium_versions, new_ingest_logs): (
+        Vec<&NewVersionProcessed>,
+        Vec<&Option<NewStadiumVersion>>,
+        Vec<&Vec<NewVersionIngestLog>>,
+    ) = itertools::multiunzip(new_stadium_versions.into_iter().map(|(a, b, c)| (a, b, c)));
+
+    let mut total = new_processed.len();
+    let mut inserted = diesel::copy_from(vp_dsl::versions_processed)
+        .from_insertable(new_processed)
+        .execute(conn)?;
+
+    let new_stadium_versions = new_stadium_versions
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    total += new_stadium_versions.len();
+    inserted += diesel::copy_from(sv_dsl::stadium_versions)
+        .from_insertable(new_stadium_versions)
+        .execute(conn)?;
+
+    super::insert_nested_ingest_logs(conn, new_ingest_logs)?;
+
+    Ok((total, inserted))
+}
+
+/// Fills in `data.games.mmolb_stadium_id` for games that don't have it yet.
+/// Games only record their stadium's name, so this matches that name
+/// against the stadium versions belonging to the home team. Games whose
+/// stadium hasn't been ingested yet are left alone and linked on a later
+/// call. Games are updated `batch_size` at a time, each batch in its own
+/// statement, so a large backlog doesn't hold row locks on every game at
+/// once. Returns the number of games linked.
+pub fn link_games_to_stadiums(conn: &mut PgConnection, batch_size: i64) -> QueryResult<usize> {
+    let mut num_linked = 0;
+    loop {
+        let batch_linked = sql_query(
+            "
+            with stadium_names as (
+                select distinct on (sv.mmolb_team_id, sv.name)
+                    sv.mmolb_team_id, sv.name, sv.mmolb_stadium_id
+                from data.stadium_versions sv
+                where sv.mmolb_team_id is not null
+                -- if a team has had more than one stadium with the same name,
+                -- prefer the most recent one
+                order by sv.mmolb_team_id, sv.name, sv.valid_from desc
+            ),
+            batch as (
+                select g.id, s.mmolb_stadium_id
+                from data.games g
+                join stadium_names s
+                    on g.home_team_mmolb_id=s.mmolb_team_id and g.stadium_name=s.name
+                where g.mmolb_stadium_id is null
+                limit $1
+            )
+            update data.games g
+            set mmolb_stadium_id=b.mmolb_stadium_id
+            from batch b
+            where g.id=b.id
+        ",
+        )
+        .bind::<diesel::sql_types::Int8, _>(batch_size)
+        .execute(conn)?;
+
+        num_linked += batch_linked;
+        if batch_linked < batch_size as usize {
+            return Ok(num_linked);
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use chrono::{DateTime, Utc};
+use diesel::{Connection, PgConnection, prelude::*, sql_query, sql_types::*};
+
+use crate::taxa::{Taxa, TaxaIngestErrorCode};
+
+const STALLED_GAME_LOG_TEXT: &str = "This game stopped updating before it finished, and later \
+    games have been played since. It's treated as forever incomplete, and will be ingested if a \
+    new version of it appears.";
+
+/// Flags ongoing games as forever incomplete if their latest version is
+/// from before `no_versions_since` and a game from a later day has
+/// started since. MMOLB sometimes leaves a game unfinished forever without
+/// ever giving it a terminal state, and otherwise those games would show
+/// as in progress forever.
+///
+/// Games whose entity has a version that hasn't been processed yet are
+/// left alone, since the ingest will get to them. Returns the number of
+/// games that were flagged.
+pub fn flag_stalled_games(
+    conn: &mut PgConnection,
+    taxa: &Taxa,
+    no_versions_since: DateTime<Utc>,
+) -> QueryResult<usize> {
+    conn.transaction(|conn| {
+        sql_query(
+            "
+            with stalled as (
+                update data.games g
+                set is_ongoing=false
+                where g.is_ongoing
+                    and g.from_version < ($1 at time zone 'UTC')
+                    and not exists (
+                        select 1 from data.entities e
+                        where e.kind='game'
+                            and e.entity_id=g.mmolb_game_id
+                            and e.source is null
+                            and e.valid_from > (g.from_version at time zone 'UTC')
+                    )
+                    and exists (
+                        select 1 from data.games later
+                        where later.started_at > g.started_at
+                            and (later.season, later.day, later.superstar_day)
+                                is distinct from (g.season, g.day, g.superstar_day)
+                    )
+                returning g.id
+            )
+            insert into info.event_ingest_log
+                (game_id, game_event_index, log_index, log_level, log_text, error_code)
+            select s.id, null, 0, 3, $2, $3
+            from stalled s
+        ",
+        )
+        .bind::<Timestamptz, _>(no_versions_since)
+        .bind::<Text, _>(STALLED_GAME_LOG_TEXT)
+        .bind::<Int8, _>(taxa.ingest_error_code_id(TaxaIngestErrorCode::GameStalled))
+        .execute(conn)
+    })
+}
+
+/// Puts games that were flagged by [`flag_stalled_games`] back in progress
+/// if a new version of them has been fetched since, until the ingest
+/// processes that version. Returns the number of games that were unflagged.
+pub fn unflag_stalled_games_with_new_versions(
+    conn: &mut PgConnection,
+    taxa: &Taxa,
+) -> QueryResult<usize> {
+    conn.transaction(|conn| {
+        sql_query(
+            "
+            with unflagged as (
+                delete from info.event_ingest_log l
+                using data.games g, data.entities e
+                where l.game_id=g.id
+                    and l.game_event_index is null
+                    and l.error_code=$1
+                    and e.kind='game'
+                    and e.entity_id=g.mmolb_game_id
+                    and e.source is null
+                    and e.valid_from > (g.from_version at time zone 'UTC')
+                returning l.game_id
+            )
+            update data.games g
+            set is_ongoing=true
+            from unflagged u
+            where g.id=u.game_id
+        ",
+        )
+        .bind::<Int8, _>(taxa.ingest_error_code_id(TaxaIngestErrorCode::GameStalled))
+        .execute(conn)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::insert_entities;
+    use crate::{QueryError, postgres_url_from_environment};
+    use chron::ChronEntity;
+    use chrono::TimeZone;
+
+    #[derive(QueryableByName)]
+    struct GameState {
+        #[diesel(sql_type = Text)]
+        mmolb_game_id: String,
+        #[diesel(sql_type = Bool)]
+        is_ongoing: bool,
+        #[diesel(sql_type = BigInt)]
+        stalled_logs: i64,
+    }
+
+    fn game_states(conn: &mut PgConnection, taxa: &Taxa) -> QueryResult<Vec<(String, bool, i64)>> {
+        let states = sql_query(
+            "
+            select g.mmolb_game_id, g.is_ongoing, count(l.id) as stalled_logs
+            from data.games g
+            left join info.event_ingest_log l on l.game_id=g.id and l.error_code=$1
+            where g.mmolb_game_id like 'dddddddddddddddddd%'
+            group by g.id
+            order by g.mmolb_game_id
+            ",
+        )
+        .bind::<Int8, _>(taxa.ingest_error_code_id(TaxaIngestErrorCode::GameStalled))
+        .get_results::<GameState>(conn)?;
+        Ok(states
+            .into_iter()
+            .map(|s| (s.mmolb_game_id, s.is_ongoing, s.stalled_logs))
+            .collect())
+    }
+
+    #[test]
+    fn stalled_games_are_flagged_until_a_new_version_appears() {
+        let url = postgres_url_from_environment();
+        let mut conn = PgConnection::establish(&url)
+            .expect("postgres_url_from_environment should return a valid connection string");
+
+        conn.test_transaction(|conn| {
+            let taxa = Taxa::new(conn)?;
+            // Game 1 stopped updating on day 1. Game 2 is still going on day
+            // 2, the latest day, so it isn't stalled however old its latest
+            // version is. These are far in the future so that no real game
+            // counts as later than them.
+            sql_query(
+                "
+                with w as (
+                    insert into data.weather (name, emoji, tooltip)
+                    values ('Test Weather', '', '')
+                    returning id
+                )
+                insert into data.games
+                    (mmolb_game_id, weather, season, day, superstar_day, away_team_emoji,
+                     away_team_name, away_team_mmolb_id, home_team_emoji, home_team_name,
+                     home_team_mmolb_id, is_ongoing, from_version, started_at)
+                select 'dddddddddddddddddd' || lpad(n::text, 6, '0'), w.id, -1, n, null, '',
+                    'Away', 'stalled-test-away', '', 'Home', 'stalled-test-home', true,
+                    timestamp '2099-01-01' + (n - 1) * interval '1 day',
+                    timestamptz '2099-01-01T00:00:00Z' + (n - 1) * interval '1 day'
+                from w, generate_series(1, 2) n
+                ",
+            )
+            .execute(conn)?;
+            let game_entity = |n: u32, day: u32| ChronEntity {
+                kind: "game".to_string(),
+                entity_id: format!("dddddddddddddddddd{n:06}"),
+                valid_from: Utc.with_ymd_and_hms(2099, 1, day, 0, 0, 0).unwrap(),
+                valid_to: None,
+                data: serde_json::json!({}),
+            };
+            insert_entities(conn, vec![game_entity(1, 1), game_entity(2, 2)], None)?;
+
+            let no_versions_since = Utc.with_ymd_and_hms(2099, 1, 3, 0, 0, 0).unwrap();
+            flag_stalled_games(conn, &taxa, no_versions_since)?;
+            assert_eq!(
+                game_states(conn, &taxa)?,
+                [
+                    ("dddddddddddddddddd000001".to_string(), false, 1),
+                    ("dddddddddddddddddd000002".to_string(), true, 0),
+                ],
+            );
+
+            // Nothing has changed, so nothing is unflagged
+            unflag_stalled_games_with_new_versions(conn, &taxa)?;
+            assert_eq!(
+                game_states(conn, &taxa)?[0],
+                ("dddddddddddddddddd000001".to_string(), false, 1)
+            );
+
+            insert_entities(conn, vec![game_entity(1, 4)], None)?;
+            unflag_stalled_games_with_new_versions(conn, &taxa)?;
+            assert_eq!(
+                game_states(conn, &taxa)?[0],
+                ("dddddddddddddddddd000001".to_string(), true, 0)
+            );
+
+            // It has an unprocessed version now, so it isn't flagged again
+            flag_stalled_games(conn, &taxa, no_versions_since)?;
+            assert_eq!(
+                game_states(conn, &taxa)?[0],
+                ("dddddddddddddddddd000001".to_string(), true, 0)
+            );
+
+            Ok::<_, QueryError>(())
+        });
+    }
+}
@@ -1,8 +1,9 @@
 use crate::event_detail::{EventDetail, EventDetailFielder, EventDetailRunner};
-use crate::models::{DbAuroraPhoto, DbDoorPrize, DbDoorPrizeItem, DbEfflorescence, DbEfflorescenceGrowth, DbEjection, DbEvent, DbFailedEjection, DbFielder, DbRunner, DbWither, NewAuroraPhoto, NewBaserunner, NewEventCheer, NewConsumptionContest, NewConsumptionContestEvent, NewDoorPrize, NewDoorPrizeItem, NewEfflorescence, NewEfflorescenceGrowth, NewEjection, NewEvent, NewFailedEjection, NewFielder, NewParty, NewPitcherChange, NewWither, NewEventBalkReason};
-use crate::taxa::Taxa;
+use crate::models::{DbAuroraPhoto, DbDoorPrize, DbDoorPrizeItem, DbEfflorescence, DbEfflorescenceGrowth, DbEjection, DbEvent, DbFailedEjection, DbFielder, DbRunner, DbWither, NewAuroraPhoto, NewBaserunner, NewEventCheer, NewConsumptionContest, NewConsumptionContestEvent, NewDoorPrize, NewDoorPrizeItem, NewEfflorescence, NewEfflorescenceGrowth, NewEjection, NewEvent, NewEventDefense, NewFailedEjection, NewFielder, NewGameInning, NewParty, NewPitcherAppearance, NewPitcherChange, NewWither, NewEventBalkReason};
+use crate::taxa::{AsInsertable, Taxa, UnknownTaxaId};
 use crate::{
-    ConsumptionContestEventForDb, ConsumptionContestForDb, PartyEvent, PitcherChange, WitherOutcome,
+    ConsumptionContestEventForDb, ConsumptionContestForDb, EventDefense, PartyEvent, PitcherChange,
+    WitherOutcome,
 };
 use itertools::Itertools;
 use miette::Diagnostic;
@@ -368,6 +369,113 @@ pub fn pitcher_change_to_row<'e>(
     }
 }
 
+/// One row per pitcher per team, in the order they first pitched. A
+/// pitcher's appearance ends at the first pitcher change that replaced them
+/// with someone else.
+pub fn pitcher_appearances_to_rows<'e>(
+    taxa: &Taxa,
+    game_id: i64,
+    events: &'e [EventDetail<&'e str>],
+    pitcher_changes: &'e [PitcherChange<&'e str>],
+) -> Vec<NewPitcherAppearance<'e>> {
+    let mut appearances: Vec<NewPitcherAppearance<'e>> = Vec::new();
+    for event in events {
+        // The home team pitches in the top of the inning
+        let is_home_team = event.top_of_inning;
+        let position = appearances.iter().position(|appearance| {
+            appearance.is_home_team == is_home_team && appearance.pitcher_name == event.pitcher_name
+        });
+        let position = position.unwrap_or_else(|| {
+            appearances.push(NewPitcherAppearance {
+                game_id,
+                is_home_team,
+                pitcher_name: event.pitcher_name,
+                pitcher_count: event.pitcher_count,
+                first_game_event_index: event.game_event_index as i32,
+                last_game_event_index: event.game_event_index as i32,
+                pitches: 0,
+                batters_faced: 0,
+                outs_recorded: 0,
+                replaced_by_source: None,
+                replaced_by_name: None,
+            });
+            appearances.len() - 1
+        });
+        let appearance = &mut appearances[position];
+
+        appearance.last_game_event_index = event.game_event_index as i32;
+        appearance.pitches += 1;
+        if event.detail_type.as_insertable().ends_plate_appearance {
+            appearance.batters_faced += 1;
+        }
+        appearance.outs_recorded += (event.outs_after - event.outs_before).max(0);
+    }
+
+    for appearance in &mut appearances {
+        if let Some(replacement) = appearance_replacement(appearance, pitcher_changes) {
+            appearance.replaced_by_source = Some(taxa.pitcher_change_source_id(replacement.source));
+            appearance.replaced_by_name = replacement.new_pitcher_name;
+        }
+    }
+
+    appearances
+}
+
+/// The first pitcher change after `appearance` started that replaced its
+/// pitcher with someone else. Both teams can have a pitcher with the same
+/// name, so only the pitcher's own team's changes count.
+fn appearance_replacement<'a, 'e>(
+    appearance: &NewPitcherAppearance<'e>,
+    pitcher_changes: &'a [PitcherChange<&'e str>],
+) -> Option<&'a PitcherChange<&'e str>> {
+    pitcher_changes.iter().find(|change| {
+        // The home team pitches in the top of the inning
+        change.top_of_inning == appearance.is_home_team
+            && change.pitcher_name == appearance.pitcher_name
+            && change.game_event_index as i32 > appearance.first_game_event_index
+            && change
+                .new_pitcher_name
+                .is_some_and(|new_name| new_name != appearance.pitcher_name)
+    })
+}
+
+/// One row per half-inning that has any events, in order
+pub fn game_innings_to_rows(game_id: i64, events: &[EventDetail<&str>]) -> Vec<NewGameInning> {
+    events
+        .iter()
+        .chunk_by(|event| (event.inning, event.top_of_inning))
+        .into_iter()
+        .map(|((inning, top_of_inning), half_inning)| {
+            let half_inning = half_inning.collect_vec();
+            // half_inning can't be empty because chunk_by never produces
+            // empty chunks
+            let first = half_inning[0];
+            let last = half_inning[half_inning.len() - 1];
+            let runs = if top_of_inning {
+                last.away_team_score_after as i32 - first.away_team_score_before as i32
+            } else {
+                last.home_team_score_after as i32 - first.home_team_score_before as i32
+            };
+
+            NewGameInning {
+                game_id,
+                inning: inning as i32,
+                top_of_inning,
+                runs,
+                hits: half_inning
+                    .iter()
+                    .filter(|event| event.detail_type.as_insertable().is_hit)
+                    .count() as i32,
+                errors: half_inning
+                    .iter()
+                    .map(|event| event.errors_after)
+                    .max()
+                    .unwrap_or(0),
+            }
+        })
+        .collect()
+}
+
 pub fn party_to_rows<'e>(
     taxa: &Taxa,
     game_id: i64,
@@ -515,11 +623,31 @@ pub fn balk_reason_to_rows(
     }
 }
 
+pub fn event_defense_to_row<'e>(
+    event_id: i64,
+    defense: &'e EventDefense<&'e str>,
+) -> NewEventDefense<'e> {
+    NewEventDefense {
+        event_id,
+        catcher_name: defense.catcher,
+        first_base_name: defense.first_base,
+        second_base_name: defense.second_base,
+        third_base_name: defense.third_base,
+        shortstop_name: defense.shortstop,
+        left_field_name: defense.left_field,
+        center_field_name: defense.center_field,
+        right_field_name: defense.right_field,
+    }
+}
+
 #[derive(Debug, Error, Diagnostic)]
 pub enum RowToEventError {
     #[error("invalid event type id {0}")]
     InvalidEventTypeId(i64),
 
+    #[error(transparent)]
+    UnknownTaxaId(#[from] UnknownTaxaId),
+
     #[error("invalid number of aurora photos on a single event (expected 0 or 2, not {0})")]
     InvalidNumberOfAuroraPhotos(usize),
 
@@ -731,35 +859,39 @@ pub fn row_to_event<'e>(
         .into_iter()
         .map(|r| {
             assert_eq!(r.event_id, event.id);
-            EventDetailRunner {
+            Ok(EventDetailRunner {
                 name: r.baserunner_name,
-                base_before: r.base_before.map(|id| taxa.base_from_id(id)),
-                base_after: taxa.base_from_id(r.base_after),
+                base_before: r
+                    .base_before
+                    .map(|id| taxa.base_from_id(id))
+                    .transpose()?,
+                base_after: taxa.base_from_id(r.base_after)?,
                 is_out: r.is_out,
                 base_description_format: r
                     .base_description_format
-                    .map(|id| taxa.base_description_format_from_id(id)),
+                    .map(|id| taxa.base_description_format_from_id(id))
+                    .transpose()?,
                 is_steal: r.steal,
                 source_event_index: r.source_event_index,
                 is_earned: r.is_earned,
                 assassinated_by: r.assassinated_by,
                 assassinated_on_fair_ball: r.assassinated_on_fair_ball,
-            }
+            })
         })
-        .collect();
+        .collect::<Result<_, RowToEventError>>()?;
 
     let fielders = fielders
         .into_iter()
         .map(|f| {
             assert_eq!(f.event_id, event.id);
-            EventDetailFielder {
+            Ok(EventDetailFielder {
                 name: f.fielder_name,
-                slot: taxa.slot_from_id(f.fielder_slot).into(),
+                slot: taxa.slot_from_id(f.fielder_slot)?.into(),
                 was_double_trouble: f.was_double_trouble,
                 used_jetpack: f.used_jetpack,
-            }
+            })
         })
-        .collect();
+        .collect::<Result<_, RowToEventError>>()?;
 
     let aurora_photos = match aurora_photo.len() {
         0 => None,
@@ -769,12 +901,12 @@ pub fn row_to_event<'e>(
                 first_team_emoji: first.team_emoji,
                 first_player: PlacedPlayer {
                     name: first.player_name,
-                    place: taxa.slot_from_id(first.player_slot).into(),
+                    place: taxa.slot_from_id(first.player_slot)?.into(),
                 },
                 second_team_emoji: second.team_emoji,
                 second_player: PlacedPlayer {
                     name: second.player_name,
-                    place: taxa.slot_from_id(second.player_slot).into(),
+                    place: taxa.slot_from_id(second.player_slot)?.into(),
                 },
             })
         }
@@ -794,7 +926,7 @@ pub fn row_to_event<'e>(
                 },
                 ejected_player: PlacedPlayer {
                     name: ejection.ejected_player_name,
-                    place: taxa.slot_from_id(ejection.ejected_player_slot).into(),
+                    place: taxa.slot_from_id(ejection.ejected_player_slot)?.into(),
                 },
                 violation_type: ViolationType::new(&ejection.violation_type),
                 reason: EjectionReason::new(&ejection.reason),
@@ -805,7 +937,7 @@ pub fn row_to_event<'e>(
                     Some(replacement_player_slot) => EjectionReplacement::RosterPlayer {
                         player: PlacedPlayer {
                             name: ejection.replacement_player_name,
-                            place: taxa.slot_from_id(replacement_player_slot).into(),
+                            place: taxa.slot_from_id(replacement_player_slot)?.into(),
                         },
                     },
                 },
@@ -981,7 +1113,7 @@ pub fn row_to_event<'e>(
                 .next_if(|i| i.efflorescence_index == efflorescence.efflorescence_index)
             {
                 growths.push(GrowAttributeChange {
-                    attribute: taxa.attribute_from_id(growth.attribute).into(),
+                    attribute: taxa.attribute_from_id(growth.attribute)?.into(),
                     amount: growth.value,
                 });
             }
@@ -1032,7 +1164,7 @@ pub fn row_to_event<'e>(
                 team_emoji: wither.team_emoji,
                 target: PlacedPlayer {
                     name: wither.player_name,
-                    place: taxa.slot_from_id(wither.player_slot).into(),
+                    place: taxa.slot_from_id(wither.player_slot)?.into(),
                 },
                 source_name: wither.source_player_name,
             })
@@ -1092,18 +1224,27 @@ pub fn row_to_event<'e>(
         detail_type: taxa
             .event_type_from_id(event.event_type)
             .ok_or_else(|| RowToEventError::InvalidEventTypeId(event.event_type))?,
-        hit_base: event.hit_base.map(|id| taxa.base_from_id(id)),
+        hit_base: event
+            .hit_base
+            .map(|id| taxa.base_from_id(id))
+            .transpose()?,
         fair_ball_type: event
             .fair_ball_type
-            .map(|id| taxa.fair_ball_type_from_id(id)),
+            .map(|id| taxa.fair_ball_type_from_id(id))
+            .transpose()?,
         fair_ball_direction: event
             .fair_ball_direction
-            .map(|id| taxa.fielder_location_from_id(id)),
+            .map(|id| taxa.fielder_location_from_id(id))
+            .transpose()?,
         fair_ball_fielder_name: event.fair_ball_fielder_name,
         fielding_error_type: event
             .fielding_error_type
-            .map(|id| taxa.fielding_error_type_from_id(id)),
-        pitch_type: event.pitch_type.map(|id| taxa.pitch_type_from_id(id)),
+            .map(|id| taxa.fielding_error_type_from_id(id))
+            .transpose()?,
+        pitch_type: event
+            .pitch_type
+            .map(|id| taxa.pitch_type_from_id(id))
+            .transpose()?,
         pitch_speed: event.pitch_speed,
         pitch_zone: event.pitch_zone,
         described_as_sacrifice: event.described_as_sacrifice,
@@ -1124,3 +1265,156 @@ pub fn row_to_event<'e>(
         is_surprise_strike: event.is_surprise_strike,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::taxa::{TaxaEventType, TaxaPitcherChangeSource, TaxaSlot};
+
+    fn pitcher_change<'e>(
+        game_event_index: usize,
+        top_of_inning: bool,
+        pitcher_name: &'e str,
+        new_pitcher_name: &'e str,
+    ) -> PitcherChange<&'e str> {
+        PitcherChange {
+            game_event_index,
+            previous_game_event_index: Some(game_event_index - 1),
+            source: TaxaPitcherChangeSource::PitchingChange,
+            inning: 5,
+            top_of_inning,
+            pitcher_count: 1,
+            pitcher_name,
+            pitcher_slot: TaxaSlot::StartingPitcher1,
+            new_pitcher_name: Some(new_pitcher_name),
+            new_pitcher_slot: Some(TaxaSlot::ReliefPitcher1),
+        }
+    }
+
+    #[test]
+    fn replacements_only_come_from_the_pitchers_own_team() {
+        let appearance = NewPitcherAppearance {
+            game_id: 1,
+            is_home_team: false,
+            pitcher_name: "Sam Same",
+            pitcher_count: 1,
+            first_game_event_index: 3,
+            last_game_event_index: 80,
+            pitches: 70,
+            batters_faced: 20,
+            outs_recorded: 15,
+            replaced_by_source: None,
+            replaced_by_name: None,
+        };
+
+        // The home team's pitcher has the same name and is replaced first
+        let changes = [
+            pitcher_change(60, true, "Sam Same", "Home Reliever"),
+            pitcher_change(90, false, "Sam Same", "Away Reliever"),
+        ];
+        let replacement = appearance_replacement(&appearance, &changes)
+            .expect("the away pitcher should have been replaced");
+        assert_eq!(replacement.new_pitcher_name, Some("Away Reliever"));
+
+        assert!(appearance_replacement(&appearance, &changes[..1]).is_none());
+    }
+
+    /// An event in which only the score and errors change
+    fn event(
+        game_event_index: usize,
+        inning: u8,
+        top_of_inning: bool,
+        detail_type: TaxaEventType,
+        (away_team_score_before, away_team_score_after): (u8, u8),
+        (home_team_score_before, home_team_score_after): (u8, u8),
+        errors_after: i32,
+    ) -> EventDetail<&'static str> {
+        EventDetail {
+            game_event_index,
+            fair_ball_event_index: None,
+            inning,
+            top_of_inning,
+            balls_before: 0,
+            strikes_before: 0,
+            outs_before: 0,
+            outs_after: 0,
+            errors_before: 0,
+            errors_after,
+            away_team_score_before,
+            away_team_score_after,
+            home_team_score_before,
+            home_team_score_after,
+            pitcher_name: "Pitcher",
+            batter_name: "Batter",
+            fielders: Vec::new(),
+            detail_type,
+            hit_base: None,
+            fair_ball_type: None,
+            fair_ball_direction: None,
+            fair_ball_fielder_name: None,
+            fielding_error_type: None,
+            pitch_type: None,
+            pitch_speed: None,
+            pitch_zone: None,
+            described_as_sacrifice: None,
+            is_toasty: None,
+            home_run_distance: None,
+            balk_reason: None,
+            baserunners: Vec::new(),
+            pitcher_count: 0,
+            batter_count: 0,
+            batter_subcount: 0,
+            cheer: None,
+            aurora_photos: None,
+            ejection: None,
+            door_prizes: Vec::new(),
+            wither: None,
+            efflorescences: Vec::new(),
+            is_surprise_strike: None,
+        }
+    }
+
+    #[test]
+    fn innings_total_each_half_innings_runs_hits_and_errors() {
+        let events = [
+            event(0, 1, true, TaxaEventType::Ball, (0, 0), (0, 0), 0),
+            event(1, 1, true, TaxaEventType::Hit, (0, 0), (0, 0), 0),
+            event(2, 1, true, TaxaEventType::HomeRun, (0, 2), (0, 0), 0),
+            event(3, 1, false, TaxaEventType::FieldingError, (2, 2), (0, 0), 1),
+            event(4, 1, false, TaxaEventType::Hit, (2, 2), (0, 1), 1),
+            event(5, 1, false, TaxaEventType::CaughtOut, (2, 2), (1, 1), 1),
+            event(6, 2, true, TaxaEventType::Walk, (2, 2), (1, 1), 0),
+        ];
+
+        assert_eq!(
+            game_innings_to_rows(7, &events),
+            [
+                NewGameInning {
+                    game_id: 7,
+                    inning: 1,
+                    top_of_inning: true,
+                    runs: 2,
+                    hits: 2,
+                    errors: 0,
+                },
+                NewGameInning {
+                    game_id: 7,
+                    inning: 1,
+                    top_of_inning: false,
+                    runs: 1,
+                    hits: 1,
+                    errors: 1,
+                },
+                NewGameInning {
+                    game_id: 7,
+                    inning: 2,
+                    top_of_inning: true,
+                    runs: 0,
+                    hits: 0,
+                    errors: 0,
+                },
+            ],
+        );
+        assert!(game_innings_to_rows(7, &[]).is_empty());
+    }
+}
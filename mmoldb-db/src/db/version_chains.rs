@@ -0,0 +1,400 @@
+use chrono::{DateTime, Utc};
+use diesel::{PgConnection, prelude::*, sql_query, sql_types::*};
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::models::NewVersionIngestLog;
+use crate::taxa::{Taxa, TaxaIngestErrorCode};
+
+/// Version chain breaks are logged at info level, because anything more
+/// severe also counts as an ingest issue. They have their own entry on the
+/// quality page.
+const CHAIN_BREAK_LOG_LEVEL: i32 = 3;
+
+/// A versioned table whose versions of each chain should follow on from each
+/// other: every version but the latest is valid until exactly when the next
+/// one becomes valid
+struct VersionChainTable {
+    table: &'static str,
+    /// The raw version kind that breaks are logged under
+    kind: &'static str,
+    /// Logged as the entity id
+    entity_id_column: &'static str,
+    /// Any other columns that, with the entity id, identify one chain
+    extra_key_columns: &'static [&'static str],
+    /// Whether a chain can legitimately stop and start again later. A
+    /// player's equipment slot can be emptied and refilled, for example.
+    gaps_allowed: bool,
+}
+
+static VERSION_CHAIN_TABLES: &[VersionChainTable] = &[
+    VersionChainTable {
+        table: "data.player_versions",
+        kind: "player",
+        entity_id_column: "mmolb_player_id",
+        extra_key_columns: &[],
+        gaps_allowed: false,
+    },
+    VersionChainTable {
+        table: "data.team_versions",
+        kind: "team",
+        entity_id_column: "mmolb_team_id",
+        extra_key_columns: &[],
+        gaps_allowed: false,
+    },
+    VersionChainTable {
+        table: "data.player_equipment_versions",
+        kind: "player",
+        entity_id_column: "mmolb_player_id",
+        extra_key_columns: &["equipment_slot"],
+        gaps_allowed: true,
+    },
+    VersionChainTable {
+        table: "data.player_equipment_effect_versions",
+        kind: "player",
+        entity_id_column: "mmolb_player_id",
+        extra_key_columns: &["equipment_slot", "effect_index"],
+        gaps_allowed: true,
+    },
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionChainBreak {
+    /// The version stopped being valid before the next one started
+    Gap,
+    /// The version is still valid, or stays valid, after the next one starts.
+    /// Queries for what was valid at a given time will return both.
+    Overlap,
+}
+
+impl VersionChainBreak {
+    /// How a version that's followed by a version valid from
+    /// `next_valid_from` breaks the chain, if it does
+    pub fn between(
+        valid_until: Option<DateTime<Utc>>,
+        next_valid_from: DateTime<Utc>,
+    ) -> Option<Self> {
+        match valid_until {
+            None => Some(VersionChainBreak::Overlap),
+            Some(until) if until > next_valid_from => Some(VersionChainBreak::Overlap),
+            Some(until) if until < next_valid_from => Some(VersionChainBreak::Gap),
+            Some(_) => None,
+        }
+    }
+
+    fn error_code(self) -> TaxaIngestErrorCode {
+        match self {
+            VersionChainBreak::Gap => TaxaIngestErrorCode::VersionChainGap,
+            VersionChainBreak::Overlap => TaxaIngestErrorCode::VersionChainOverlap,
+        }
+    }
+}
+
+/// A version whose `valid_until` isn't its successor's `valid_from`
+#[derive(QueryableByName)]
+struct DbChainLink {
+    #[diesel(sql_type = Text)]
+    entity_id: String,
+    /// The extra key columns, formatted for the log text
+    #[diesel(sql_type = Text)]
+    chain_key: String,
+    #[diesel(sql_type = Timestamptz)]
+    valid_from: DateTime<Utc>,
+    #[diesel(sql_type = Nullable<Timestamptz>)]
+    valid_until: Option<DateTime<Utc>>,
+    #[diesel(sql_type = Timestamptz)]
+    next_valid_from: DateTime<Utc>,
+    /// A log index that's free for this version, and not used by another
+    /// break at it
+    #[diesel(sql_type = Int4)]
+    log_index: i32,
+}
+
+/// How many breaks one table's chains have
+#[derive(Debug, Clone, Serialize)]
+pub struct VersionChainCheck {
+    pub table: &'static str,
+    pub gaps: usize,
+    pub overlaps: usize,
+}
+
+fn find_broken_links(
+    conn: &mut PgConnection,
+    chain_table: &VersionChainTable,
+) -> QueryResult<Vec<DbChainLink>> {
+    let partition = std::iter::once(chain_table.entity_id_column)
+        .chain(chain_table.extra_key_columns.iter().copied())
+        .join(", ");
+    let chain_key = if chain_table.extra_key_columns.is_empty() {
+        "''".to_string()
+    } else {
+        let fields = chain_table
+            .extra_key_columns
+            .iter()
+            .map(|column| format!("'{column} ' || {column}"))
+            .join(", ");
+        format!("concat_ws(', ', {fields})")
+    };
+
+    sql_query(format!(
+        "
+        with links as (
+            select
+                {entity_id} as entity_id,
+                {chain_key} as chain_key,
+                valid_from,
+                valid_until,
+                lead(valid_from) over (partition by {partition} order by valid_from)
+                    as next_valid_from
+            from {table}
+        )
+        select
+            l.entity_id,
+            l.chain_key,
+            l.valid_from,
+            l.valid_until,
+            l.next_valid_from,
+            -- Several chains (e.g. equipment slots) can break at the same
+            -- version, and each one needs a log index of its own
+            (coalesce((
+                select max(vil.log_index) + 1
+                from info.version_ingest_log vil
+                where vil.kind=$1
+                    and vil.entity_id=l.entity_id
+                    and vil.valid_from=l.valid_from
+            ), 0) + row_number() over (
+                partition by l.entity_id, l.valid_from order by l.chain_key
+            ) - 1)::int as log_index
+        from links l
+        where l.next_valid_from is not null
+            and l.valid_until is distinct from l.next_valid_from
+        order by l.entity_id, l.valid_from
+    ",
+        entity_id = chain_table.entity_id_column,
+        table = chain_table.table,
+    ))
+    .bind::<Text, _>(chain_table.kind)
+    .get_results(conn)
+}
+
+/// Checks that the versions in `data.player_versions`, `data.team_versions`,
+/// and the equipment version tables follow on from each other, and logs
+/// every break in `info.version_ingest_log`. Equipment is allowed to have
+/// gaps, since slots can be emptied. The previous check's logs are replaced,
+/// so fixed breaks stop being reported.
+pub fn check_version_chains(
+    conn: &mut PgConnection,
+    taxa: &Taxa,
+) -> QueryResult<Vec<VersionChainCheck>> {
+    use crate::info_schema::info::version_ingest_log::dsl as vil_dsl;
+
+    let error_codes = [
+        taxa.ingest_error_code_id(TaxaIngestErrorCode::VersionChainGap),
+        taxa.ingest_error_code_id(TaxaIngestErrorCode::VersionChainOverlap),
+    ];
+
+    conn.transaction(|conn| {
+        diesel::delete(vil_dsl::version_ingest_log.filter(vil_dsl::error_code.eq_any(error_codes)))
+            .execute(conn)?;
+
+        let mut checks = Vec::with_capacity(VERSION_CHAIN_TABLES.len());
+        for chain_table in VERSION_CHAIN_TABLES {
+            let mut check = VersionChainCheck {
+                table: chain_table.table,
+                gaps: 0,
+                overlaps: 0,
+            };
+            let links = find_broken_links(conn, chain_table)?;
+            let logs = links
+                .iter()
+                .filter_map(|link| {
+                    let chain_break =
+                        VersionChainBreak::between(link.valid_until, link.next_valid_from)?;
+                    match chain_break {
+                        VersionChainBreak::Gap if chain_table.gaps_allowed => return None,
+                        VersionChainBreak::Gap => check.gaps += 1,
+                        VersionChainBreak::Overlap => check.overlaps += 1,
+                    }
+
+                    let key = if link.chain_key.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" ({})", link.chain_key)
+                    };
+                    let valid_until = link
+                        .valid_until
+                        .map_or_else(|| "still valid".to_string(), |until| until.to_string());
+                    Some(NewVersionIngestLog {
+                        kind: chain_table.kind,
+                        entity_id: &link.entity_id,
+                        valid_from: link.valid_from,
+                        log_index: link.log_index,
+                        log_level: CHAIN_BREAK_LOG_LEVEL,
+                        log_text: format!(
+                            "{:?} in {}{key}: this version is valid until {valid_until}, but \
+                            the next one is valid from {}",
+                            chain_break, chain_table.table, link.next_valid_from,
+                        ),
+                        error_code: Some(taxa.ingest_error_code_id(chain_break.error_code())),
+                        feed_event_index: None,
+                    })
+                })
+                .collect_vec();
+            super::insert_ingest_logs(conn, logs)?;
+            checks.push(check);
+        }
+
+        Ok(checks)
+    })
+}
+
+/// One logged version chain break
+#[derive(Debug, Clone, QueryableByName, Serialize)]
+pub struct DbVersionChainBreakLog {
+    #[diesel(sql_type = Text)]
+    pub kind: String,
+    #[diesel(sql_type = Text)]
+    pub entity_id: String,
+    #[diesel(sql_type = Timestamptz)]
+    pub valid_from: DateTime<Utc>,
+    /// `VersionChainGap` or `VersionChainOverlap`
+    #[diesel(sql_type = Text)]
+    pub error_code: String,
+    #[diesel(sql_type = Text)]
+    pub log_text: String,
+}
+
+/// The version chain breaks logged by the latest check, by entity, up to
+/// `limit` of them
+pub fn version_chain_break_logs(
+    conn: &mut PgConnection,
+    limit: i64,
+) -> QueryResult<Vec<DbVersionChainBreakLog>> {
+    sql_query(
+        "
+        select vil.kind, vil.entity_id, vil.valid_from, ec.name as error_code, vil.log_text
+        from info.version_ingest_log vil
+        join taxa.ingest_error_code ec on ec.id=vil.error_code
+        where ec.name in ('VersionChainGap', 'VersionChainOverlap')
+        order by vil.kind, vil.entity_id, vil.valid_from, vil.log_index
+        limit $1
+    ",
+    )
+    .bind::<BigInt, _>(limit)
+    .get_results(conn)
+}
+
+/// How many gaps and overlaps the latest check logged, in that order
+pub fn count_version_chain_breaks(conn: &mut PgConnection) -> QueryResult<(i64, i64)> {
+    #[derive(QueryableByName)]
+    struct Counts {
+        #[diesel(sql_type = BigInt)]
+        gaps: i64,
+        #[diesel(sql_type = BigInt)]
+        overlaps: i64,
+    }
+
+    let counts: Counts = sql_query(
+        "
+        select
+            count(1) filter (where ec.name='VersionChainGap') as gaps,
+            count(1) filter (where ec.name='VersionChainOverlap') as overlaps
+        from info.version_ingest_log vil
+        join taxa.ingest_error_code ec on ec.id=vil.error_code
+        where ec.name in ('VersionChainGap', 'VersionChainOverlap')
+    ",
+    )
+    .get_result(conn)?;
+
+    Ok((counts.gaps, counts.overlaps))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{QueryError, postgres_url_from_environment};
+    use chrono::NaiveDate;
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        NaiveDate::from_ymd_opt(2026, 1, 1)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+            .and_utc()
+    }
+
+    #[test]
+    fn chain_breaks_are_classified_by_where_valid_until_falls() {
+        assert_eq!(VersionChainBreak::between(Some(at(2)), at(2)), None);
+        assert_eq!(
+            VersionChainBreak::between(Some(at(1)), at(2)),
+            Some(VersionChainBreak::Gap)
+        );
+        assert_eq!(
+            VersionChainBreak::between(Some(at(3)), at(2)),
+            Some(VersionChainBreak::Overlap)
+        );
+        assert_eq!(
+            VersionChainBreak::between(None, at(2)),
+            Some(VersionChainBreak::Overlap)
+        );
+    }
+
+    #[test]
+    fn breaks_at_the_same_version_get_their_own_log_indices() {
+        #[derive(QueryableByName)]
+        struct Log {
+            #[diesel(sql_type = Int4)]
+            log_index: i32,
+            #[diesel(sql_type = Int4)]
+            log_level: i32,
+        }
+
+        let url = postgres_url_from_environment();
+        let mut conn = PgConnection::establish(&url)
+            .expect("postgres_url_from_environment should return a valid connection string");
+
+        conn.test_transaction(|conn| {
+            let taxa = Taxa::new(conn)?;
+            // Both slots' first versions stay valid after their second
+            // versions start. The first versions have a valid_until so the
+            // insert trigger doesn't close them.
+            sql_query(
+                "
+                insert into data.player_equipment_versions
+                    (mmolb_player_id, equipment_slot, valid_from, valid_until, duplicates,
+                     emoji, name, prefixes, suffixes, num_effects, corrupted)
+                select 'version-chain-test-player', slot, v.valid_from, v.valid_until, 0,
+                    '', 'Test Equipment', '{}', '{}', 0, false
+                from unnest(array['Head', 'Body']) slot,
+                    (values
+                        (timestamptz '2026-01-01 01:00Z', timestamptz '2026-01-01 03:00Z'),
+                        (timestamptz '2026-01-01 02:00Z', null)
+                    ) v (valid_from, valid_until)
+                order by v.valid_from
+                ",
+            )
+            .execute(conn)?;
+
+            check_version_chains(conn, &taxa)?;
+
+            let logs = sql_query(
+                "
+                select log_index, log_level
+                from info.version_ingest_log
+                where entity_id='version-chain-test-player'
+                order by log_index
+                ",
+            )
+            .get_results::<Log>(conn)?;
+            let indices = logs.iter().map(|log| log.log_index).collect_vec();
+            assert_eq!(indices, [0, 1]);
+            assert!(
+                logs.iter()
+                    .all(|log| log.log_level == CHAIN_BREAK_LOG_LEVEL)
+            );
+
+            Ok::<_, QueryError>(())
+        });
+    }
+}
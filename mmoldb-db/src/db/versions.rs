@@ -6,7 +6,7 @@ use crate::QueryError;
 use crate::data_schema::data::feed_event_versions::dsl as feed_event_versions_dsl;
 use crate::data_schema::data::versions::dsl as versions_dsl;
 use chron::ChronEntity;
-use chrono::NaiveDateTime;
+use chrono::{DateTime, Utc};
 use diesel::{PgConnection, prelude::*};
 use itertools::Itertools;
 use tracing::{error, info};
@@ -15,9 +15,11 @@ use crate::models::{NewFeedEventVersion, NewVersion};
 pub fn get_latest_raw_version_cursor(
     conn: &mut PgConnection,
     kind: &str,
-) -> QueryResult<Option<(NaiveDateTime, String)>> {
+    source: Option<&str>,
+) -> QueryResult<Option<(DateTime<Utc>, String)>> {
     versions_dsl::versions
         .filter(versions_dsl::kind.eq(kind))
+        .filter(versions_dsl::source.is_not_distinct_from(source))
         .select((versions_dsl::valid_from, versions_dsl::entity_id))
         .order_by((
             versions_dsl::valid_from.desc(),
@@ -31,9 +33,11 @@ pub fn get_latest_raw_version_cursor(
 pub fn get_latest_raw_feed_event_version_cursor(
     conn: &mut PgConnection,
     kind: &str,
-) -> QueryResult<Option<(NaiveDateTime, String, i32)>> {
+    source: Option<&str>,
+) -> QueryResult<Option<(DateTime<Utc>, String, i32)>> {
     feed_event_versions_dsl::feed_event_versions
         .filter(feed_event_versions_dsl::kind.eq(kind))
+        .filter(feed_event_versions_dsl::source.is_not_distinct_from(source))
         .select((
             feed_event_versions_dsl::valid_from,
             feed_event_versions_dsl::entity_id,
@@ -52,8 +56,9 @@ pub fn get_latest_raw_feed_event_version_cursor(
 pub fn insert_versions_one_error<'v>(
     conn: &mut PgConnection,
     versions: &'v [ChronEntity<serde_json::Value>],
+    source: Option<&str>,
 ) -> Result<usize, (&'v ChronEntity<serde_json::Value>, QueryError)> {
-    match insert_versions(conn, versions) {
+    match insert_versions(conn, versions, source) {
         Ok(result) => Ok(result),
         Err(e) => {
             error!("Narrowing down version insert error: {:?}", e);
@@ -68,19 +73,19 @@ pub fn insert_versions_one_error<'v>(
                 );
                 // Then second_half must contain the one and only version
                 assert_eq!(second_half.len(), 1);
-                insert_versions(conn, second_half).map_err(|e| (&second_half[0], e))
+                insert_versions(conn, second_half, source).map_err(|e| (&second_half[0], e))
             } else {
                 info!(
                     "Error inserting batch of {} versions. Trying to insert the first {}...",
                     versions.len(),
                     first_half.len()
                 );
-                let size1 = insert_versions_one_error(conn, first_half)?;
+                let size1 = insert_versions_one_error(conn, first_half, source)?;
                 info!(
                     "First half succeeded. Trying to insert the last {}...",
                     second_half.len()
                 );
-                let size2 = insert_versions_one_error(conn, second_half)?;
+                let size2 = insert_versions_one_error(conn, second_half, source)?;
                 error!(
                     "Both halves succeeded, but we're already in the error branch. One of the halves should have failed!"
                 );
@@ -90,17 +95,21 @@ pub fn insert_versions_one_error<'v>(
     }
 }
 
+/// Inserts `versions` fetched from `source`, or from main MMOLB if it's
+/// `None`
 pub fn insert_versions(
     conn: &mut PgConnection,
     versions: &[ChronEntity<serde_json::Value>],
+    source: Option<&str>,
 ) -> QueryResult<usize> {
     let new_versions = versions
         .iter()
         .map(|v| NewVersion {
             kind: &v.kind,
             entity_id: &v.entity_id,
-            valid_from: v.valid_from.naive_utc(),
+            valid_from: v.valid_from,
             data: &v.data,
+            source,
         })
         .collect_vec();
 
@@ -112,7 +121,8 @@ pub fn insert_versions(
 pub fn insert_feed_event_versions(
     conn: &mut PgConnection,
     kind: &str,
-    versions: &[(String, i32, NaiveDateTime, serde_json::Value)],
+    versions: &[(String, i32, DateTime<Utc>, serde_json::Value)],
+    source: Option<&str>,
 ) -> QueryResult<usize> {
     let new_versions = versions
         .iter()
@@ -122,6 +132,7 @@ pub fn insert_feed_event_versions(
             feed_event_index: *idx,
             valid_from: *dt,
             data: v,
+            source,
         })
         .collect_vec();
 
@@ -129,3 +140,114 @@ pub fn insert_feed_event_versions(
         .from_insertable(&new_versions)
         .execute(conn)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::postgres_url_from_environment;
+    use chrono::TimeZone;
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 7, 1, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn sources_have_separate_version_chains_and_cursors() {
+        let url = postgres_url_from_environment();
+        let mut conn = PgConnection::establish(&url)
+            .expect("postgres_url_from_environment should return a valid connection string");
+
+        conn.test_transaction(|conn| {
+            let kind = "source_test";
+            let version = |hour| ChronEntity {
+                kind: kind.to_string(),
+                entity_id: "source-test-entity".to_string(),
+                valid_from: at(hour),
+                valid_to: None,
+                data: serde_json::json!({ "hour": hour }),
+            };
+
+            insert_versions(conn, &[version(1)], None)?;
+            insert_versions(conn, &[version(2)], Some("test-universe"))?;
+            insert_versions(conn, &[version(3)], None)?;
+
+            // Each source's versions only close out that source's versions
+            let valid_tos = versions_dsl::versions
+                .filter(versions_dsl::kind.eq(kind))
+                .order_by(versions_dsl::valid_from.asc())
+                .select(versions_dsl::valid_to)
+                .get_results::<Option<DateTime<Utc>>>(conn)?;
+            assert_eq!(valid_tos, vec![Some(at(3)), None, None]);
+
+            let entity_id = "source-test-entity".to_string();
+            assert_eq!(
+                get_latest_raw_version_cursor(conn, kind, None)?,
+                Some((at(3), entity_id.clone())),
+            );
+            assert_eq!(
+                get_latest_raw_version_cursor(conn, kind, Some("test-universe"))?,
+                Some((at(2), entity_id)),
+            );
+            assert_eq!(
+                get_latest_raw_version_cursor(conn, kind, Some("other-universe"))?,
+                None,
+            );
+
+            Ok::<_, QueryError>(())
+        });
+    }
+
+    #[test]
+    fn sources_have_separate_feed_event_chains_and_cursors() {
+        let url = postgres_url_from_environment();
+        let mut conn = PgConnection::establish(&url)
+            .expect("postgres_url_from_environment should return a valid connection string");
+
+        conn.test_transaction(|conn| {
+            let kind = "source_test_feed";
+            let event = |hour| {
+                (
+                    "source-test-entity".to_string(),
+                    0,
+                    at(hour),
+                    serde_json::json!({ "text": "unchanged" }),
+                )
+            };
+
+            insert_feed_event_versions(conn, kind, &[event(1)], None)?;
+            // Identical to the main source's event, but a different source
+            insert_feed_event_versions(conn, kind, &[event(2)], Some("test-universe"))?;
+            // Identical to the main source's current event, so suppressed
+            insert_feed_event_versions(conn, kind, &[event(3)], None)?;
+
+            let stored = feed_event_versions_dsl::feed_event_versions
+                .filter(feed_event_versions_dsl::kind.eq(kind))
+                .order_by(feed_event_versions_dsl::valid_from.asc())
+                .select((
+                    feed_event_versions_dsl::valid_from,
+                    feed_event_versions_dsl::source,
+                    feed_event_versions_dsl::valid_until,
+                ))
+                .get_results::<(DateTime<Utc>, Option<String>, Option<DateTime<Utc>>)>(conn)?;
+            assert_eq!(
+                stored,
+                vec![
+                    (at(1), None, None),
+                    (at(2), Some("test-universe".to_string()), None),
+                ],
+            );
+
+            let entity_id = "source-test-entity".to_string();
+            assert_eq!(
+                get_latest_raw_feed_event_version_cursor(conn, kind, None)?,
+                Some((at(1), entity_id.clone(), 0)),
+            );
+            assert_eq!(
+                get_latest_raw_feed_event_version_cursor(conn, kind, Some("test-universe"))?,
+                Some((at(2), entity_id, 0)),
+            );
+
+            Ok::<_, QueryError>(())
+        });
+    }
+}
@@ -0,0 +1,125 @@
+use chrono::NaiveDate;
+use diesel::{PgConnection, prelude::*, sql_query, sql_types::*};
+
+use crate::models::{DbPlayerWeeklyRollup, DbTeamWeeklyRollup};
+
+/// Recomputes the weekly rollups for every week that has had a game
+/// inserted or deleted since the last call, and returns how many weeks
+/// that was. Weeks are marked dirty by a trigger on `data.games`.
+pub fn update_weekly_rollups(conn: &mut PgConnection) -> QueryResult<usize> {
+    use crate::data_schema::data::player_weekly_rollups::dsl as pwr_dsl;
+    use crate::data_schema::data::team_weekly_rollups::dsl as twr_dsl;
+    use crate::data_schema::data::weekly_rollups_dirty::dsl as wrd_dsl;
+
+    conn.transaction(|conn| {
+        // Games that are inserted while this runs mark their week dirty
+        // again, so they're picked up next time
+        let weeks: Vec<NaiveDate> = diesel::delete(wrd_dsl::weekly_rollups_dirty)
+            .returning(wrd_dsl::week_start)
+            .get_results(conn)?;
+
+        if weeks.is_empty() {
+            return Ok(0);
+        }
+
+        diesel::delete(twr_dsl::team_weekly_rollups.filter(twr_dsl::week_start.eq_any(&weeks)))
+            .execute(conn)?;
+        diesel::delete(pwr_dsl::player_weekly_rollups.filter(pwr_dsl::week_start.eq_any(&weeks)))
+            .execute(conn)?;
+
+        sql_query(
+            "
+            insert into data.team_weekly_rollups
+                (week_start, team_mmolb_id, games, wins, runs_scored, runs_allowed)
+            with team_games as (
+                select
+                    data.game_week_start(g.started_at) as week_start,
+                    g.home_team_mmolb_id as team_mmolb_id,
+                    g.home_team_final_score as runs_scored,
+                    g.away_team_final_score as runs_allowed
+                from data.games g
+                where data.game_week_start(g.started_at) = any($1) and not g.is_ongoing
+                union all
+                select
+                    data.game_week_start(g.started_at) as week_start,
+                    g.away_team_mmolb_id as team_mmolb_id,
+                    g.away_team_final_score as runs_scored,
+                    g.home_team_final_score as runs_allowed
+                from data.games g
+                where data.game_week_start(g.started_at) = any($1) and not g.is_ongoing
+            )
+            select
+                tg.week_start,
+                tg.team_mmolb_id,
+                count(1),
+                count(1) filter (where tg.runs_scored > tg.runs_allowed),
+                coalesce(sum(tg.runs_scored), 0),
+                coalesce(sum(tg.runs_allowed), 0)
+            from team_games tg
+            group by tg.week_start, tg.team_mmolb_id
+        ",
+        )
+        .bind::<Array<Date>, _>(&weeks)
+        .execute(conn)?;
+
+        sql_query(
+            "
+            insert into data.player_weekly_rollups
+                (week_start, team_mmolb_id, batter_name, plate_appearances, hits, home_runs,
+                 walks, strikeouts)
+            select
+                data.game_week_start(g.started_at),
+                case when e.top_of_inning then g.away_team_mmolb_id else g.home_team_mmolb_id end,
+                e.batter_name,
+                count(1),
+                count(1) filter (where et.is_hit),
+                count(1) filter (where et.name='HomeRun'),
+                count(1) filter (where et.name='Walk'),
+                count(1) filter (where et.is_strikeout)
+            from data.events e
+            join data.games g on g.id=e.game_id
+            join taxa.event_type et on et.id=e.event_type
+            where data.game_week_start(g.started_at) = any($1)
+                and not g.is_ongoing
+                and et.ends_plate_appearance
+            group by 1, 2, 3
+        ",
+        )
+        .bind::<Array<Date>, _>(&weeks)
+        .execute(conn)?;
+
+        Ok(weeks.len())
+    })
+}
+
+/// Every week a team played a completed game in, oldest first
+pub fn team_weekly_rollups(
+    conn: &mut PgConnection,
+    team_mmolb_id: &str,
+) -> QueryResult<Vec<DbTeamWeeklyRollup>> {
+    use crate::data_schema::data::team_weekly_rollups::dsl as twr_dsl;
+
+    twr_dsl::team_weekly_rollups
+        .filter(twr_dsl::team_mmolb_id.eq(team_mmolb_id))
+        .order_by(twr_dsl::week_start.asc())
+        .select(DbTeamWeeklyRollup::as_select())
+        .get_results(conn)
+}
+
+/// Every week a batter had a plate appearance for a team, oldest first.
+/// Batters are identified by name and team because that's all
+/// `data.events` records.
+pub fn player_weekly_rollups(
+    conn: &mut PgConnection,
+    team_mmolb_id: &str,
+    batter_name: &str,
+) -> QueryResult<Vec<DbPlayerWeeklyRollup>> {
+    use crate::data_schema::data::player_weekly_rollups::dsl as pwr_dsl;
+
+    pwr_dsl::player_weekly_rollups
+        .filter(pwr_dsl::team_mmolb_id.eq(team_mmolb_id))
+        .filter(pwr_dsl::batter_name.eq(batter_name))
+        .order_by(pwr_dsl::week_start.asc())
+        .select(DbPlayerWeeklyRollup::as_select())
+        .get_results(conn)
+}
@@ -0,0 +1,580 @@
+//! A simple Markov-chain win probability model, used to compute win
+//! probability added and leverage index for every event.
+//!
+//! The model only knows about the inning, outs, runners on base, and score.
+//! Every plate appearance is assumed to have the same (roughly league
+//! average) distribution of outcomes, regardless of who is batting or
+//! pitching, and the count is ignored. This is the same simplification
+//! that most public win expectancy tables make.
+
+use crate::event_detail::EventDetail;
+use crate::models::NewEventWinProbability;
+use crate::taxa::TaxaBase;
+use hashbrown::HashMap;
+use std::sync::LazyLock;
+
+/// Number of innings in a game that doesn't go to extras
+const REGULATION_INNINGS: u8 = 9;
+
+/// Runs scored in a half-inning are tracked up to this number. Any more runs
+/// than that are counted as this many.
+const MAX_RUNS: usize = 20;
+const RUN_BUCKETS: usize = MAX_RUNS + 1;
+
+/// Run differentials are clamped to +/- this number. Past this point the
+/// game is effectively decided.
+const MAX_LEAD: i32 = 20;
+const LEAD_BUCKETS: usize = (2 * MAX_LEAD + 1) as usize;
+
+/// Number of passes when solving for the half-inning run distribution. Each
+/// pass extends the longest considered half-inning by one plate appearance.
+const RUN_DISTRIBUTION_ITERATIONS: usize = 200;
+
+/// When computing the average swing for leverage index, stop following
+/// games once this little probability mass remains
+const OCCUPANCY_EPSILON: f64 = 1e-9;
+
+const FIRST: u8 = 0b001;
+const SECOND: u8 = 0b010;
+const THIRD: u8 = 0b100;
+
+#[derive(Debug, Copy, Clone)]
+enum PlateAppearanceOutcome {
+    Out,
+    Walk,
+    Single,
+    Double,
+    Triple,
+    HomeRun,
+}
+
+/// Rough league-average plate appearance outcomes. These sum to 1.
+const PLATE_APPEARANCE_OUTCOMES: [(PlateAppearanceOutcome, f64); 6] = [
+    (PlateAppearanceOutcome::Out, 0.68),
+    (PlateAppearanceOutcome::Walk, 0.09),
+    (PlateAppearanceOutcome::Single, 0.15),
+    (PlateAppearanceOutcome::Double, 0.045),
+    (PlateAppearanceOutcome::Triple, 0.005),
+    (PlateAppearanceOutcome::HomeRun, 0.03),
+];
+
+fn runners_on(bases: u8) -> usize {
+    bases.count_ones() as usize
+}
+
+impl PlateAppearanceOutcome {
+    /// Returns the new base state and the number of runs scored. Outs don't
+    /// move runners.
+    fn advance(self, bases: u8) -> (u8, usize) {
+        match self {
+            PlateAppearanceOutcome::Out => (bases, 0),
+            PlateAppearanceOutcome::Walk => {
+                // Runners only advance when forced
+                let mut new_bases = bases | FIRST;
+                let mut runs = 0;
+                if bases & FIRST != 0 {
+                    new_bases |= SECOND;
+                    if bases & SECOND != 0 {
+                        new_bases |= THIRD;
+                        if bases & THIRD != 0 {
+                            runs = 1;
+                        }
+                    }
+                }
+                (new_bases, runs)
+            }
+            PlateAppearanceOutcome::Single => {
+                let runs = runners_on(bases & (SECOND | THIRD));
+                let new_bases = FIRST | if bases & FIRST != 0 { SECOND } else { 0 };
+                (new_bases, runs)
+            }
+            PlateAppearanceOutcome::Double => {
+                let runs = runners_on(bases & (SECOND | THIRD));
+                let new_bases = SECOND | if bases & FIRST != 0 { THIRD } else { 0 };
+                (new_bases, runs)
+            }
+            PlateAppearanceOutcome::Triple => (THIRD, runners_on(bases)),
+            PlateAppearanceOutcome::HomeRun => (0, runners_on(bases) + 1),
+        }
+    }
+}
+
+/// The situation at some point in a game, as far as the model cares
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct GameSituation {
+    pub inning: u8,
+    pub top_of_inning: bool,
+    pub outs: i32,
+    /// Bitmask of occupied bases. Bit 0 is first, bit 1 is second, and bit
+    /// 2 is third.
+    pub bases: u8,
+    /// Home team score minus away team score
+    pub home_lead: i32,
+}
+
+impl GameSituation {
+    fn lead_after_runs(&self, runs: usize) -> i32 {
+        if self.top_of_inning {
+            self.home_lead - runs as i32
+        } else {
+            self.home_lead + runs as i32
+        }
+    }
+
+    /// The situation at the start of the next half-inning, including the
+    /// automatic runner in extra innings
+    fn next_half_inning(&self) -> GameSituation {
+        let (inning, top_of_inning) = if self.top_of_inning {
+            (self.inning, false)
+        } else {
+            (self.inning + 1, true)
+        };
+
+        GameSituation {
+            inning,
+            top_of_inning,
+            outs: 0,
+            bases: if inning > REGULATION_INNINGS { SECOND } else { 0 },
+            home_lead: self.home_lead,
+        }
+    }
+
+    /// If the game is over in this situation, returns the home team's win
+    /// probability (either 0 or 1)
+    fn final_result(&self) -> Option<f64> {
+        let is_end_of_half = self.outs >= 3;
+        let in_final_inning = self.inning >= REGULATION_INNINGS;
+
+        let game_over = in_final_inning
+            && if self.top_of_inning {
+                // The bottom of the final inning isn't played if the home team leads
+                is_end_of_half && self.home_lead > 0
+            } else {
+                // Walk-off, or the bottom of the final inning completed without a tie
+                self.home_lead > 0 || (is_end_of_half && self.home_lead != 0)
+            };
+
+        game_over.then(|| if self.home_lead > 0 { 1.0 } else { 0.0 })
+    }
+}
+
+fn lead_index(home_lead: i32) -> usize {
+    (home_lead.clamp(-MAX_LEAD, MAX_LEAD) + MAX_LEAD) as usize
+}
+
+pub struct WinProbabilityModel {
+    /// Distribution of runs scored in the rest of a half-inning, indexed
+    /// by `[outs][bases][runs]`
+    runs_to_end_of_half: [[[f64; RUN_BUCKETS]; 8]; 3],
+    /// Home team's win probability at the start of the top of each inning,
+    /// indexed by `[inning - 1][lead_index(home_lead)]`. The last entry is
+    /// shared by all extra innings.
+    top_start: Vec<[f64; LEAD_BUCKETS]>,
+    /// Like `top_start`, for the start of the bottom of each inning
+    bottom_start: Vec<[f64; LEAD_BUCKETS]>,
+    /// Average expected absolute win probability swing per plate
+    /// appearance, for normalizing leverage index
+    average_swing: f64,
+}
+
+impl WinProbabilityModel {
+    fn new() -> Self {
+        let mut model = Self {
+            runs_to_end_of_half: Self::compute_runs_to_end_of_half(),
+            top_start: vec![[0.0; LEAD_BUCKETS]; REGULATION_INNINGS as usize + 1],
+            bottom_start: vec![[0.0; LEAD_BUCKETS]; REGULATION_INNINGS as usize + 1],
+            average_swing: 1.0,
+        };
+        model.compute_half_inning_starts();
+        model.average_swing = model.compute_average_swing();
+        model
+    }
+
+    fn compute_runs_to_end_of_half() -> [[[f64; RUN_BUCKETS]; 8]; 3] {
+        let mut dist = [[[0.0; RUN_BUCKETS]; 8]; 3];
+        for _ in 0..RUN_DISTRIBUTION_ITERATIONS {
+            let mut next = [[[0.0; RUN_BUCKETS]; 8]; 3];
+            for outs in 0..3 {
+                for bases in 0..8u8 {
+                    let target = &mut next[outs][bases as usize];
+                    for (outcome, probability) in PLATE_APPEARANCE_OUTCOMES {
+                        let (new_bases, runs) = outcome.advance(bases);
+                        let new_outs = match outcome {
+                            PlateAppearanceOutcome::Out => outs + 1,
+                            _ => outs,
+                        };
+
+                        if new_outs >= 3 {
+                            target[0] += probability;
+                        } else {
+                            for (prev_runs, prev_probability) in
+                                dist[new_outs][new_bases as usize].iter().enumerate()
+                            {
+                                target[(prev_runs + runs).min(MAX_RUNS)] +=
+                                    probability * prev_probability;
+                            }
+                        }
+                    }
+                }
+            }
+            dist = next;
+        }
+        dist
+    }
+
+    fn half_inning_runs(&self, inning: u8) -> &[f64; RUN_BUCKETS] {
+        let bases = if inning > REGULATION_INNINGS { SECOND } else { 0 };
+        &self.runs_to_end_of_half[0][bases as usize]
+    }
+
+    fn inning_index(inning: u8) -> usize {
+        (inning.clamp(1, REGULATION_INNINGS + 1) - 1) as usize
+    }
+
+    /// Home win probability at the start of a half-inning
+    fn half_inning_start(&self, inning: u8, top_of_inning: bool, home_lead: i32) -> f64 {
+        let table = if top_of_inning {
+            &self.top_start
+        } else {
+            &self.bottom_start
+        };
+        table[Self::inning_index(inning)][lead_index(home_lead)]
+    }
+
+    /// Home win probability once the half-inning in `situation` has ended
+    /// with `situation.home_lead`
+    fn end_of_half(&self, situation: &GameSituation) -> f64 {
+        let ended = GameSituation {
+            outs: 3,
+            ..*situation
+        };
+        if let Some(result) = ended.final_result() {
+            return result;
+        }
+
+        let next = ended.next_half_inning();
+        self.half_inning_start(next.inning, next.top_of_inning, next.home_lead)
+    }
+
+    fn compute_half_inning_starts(&mut self) {
+        let extras = REGULATION_INNINGS + 1;
+        let extra_runs = *self.half_inning_runs(extras);
+
+        // Extra innings are all identical, so the start of each one has the
+        // same win probability. At the start of the top of an extra inning
+        // the game is tied, and the only way to get back to that situation
+        // is by the bottom half ending tied. Solve x = a + b * x, where x is
+        // the home win probability at the start of a tied extra inning.
+        let mut a = 0.0;
+        let mut b = 0.0;
+        for (away_runs, away_probability) in extra_runs.iter().enumerate() {
+            for (home_runs, home_probability) in extra_runs.iter().enumerate() {
+                let probability = away_probability * home_probability;
+                if home_runs > away_runs {
+                    a += probability;
+                } else if home_runs == away_runs {
+                    b += probability;
+                }
+            }
+        }
+        let tied_extra_inning = if b < 1.0 { a / (1.0 - b) } else { 0.5 };
+
+        let extra_index = Self::inning_index(extras);
+        for lead_idx in 0..LEAD_BUCKETS {
+            let home_lead = lead_idx as i32 - MAX_LEAD;
+            self.bottom_start[extra_index][lead_idx] = if home_lead > 0 {
+                1.0
+            } else {
+                extra_runs
+                    .iter()
+                    .enumerate()
+                    .map(|(runs, probability)| {
+                        let lead = home_lead + runs as i32;
+                        probability
+                            * if lead > 0 {
+                                1.0
+                            } else if lead < 0 {
+                                0.0
+                            } else {
+                                tied_extra_inning
+                            }
+                    })
+                    .sum()
+            };
+        }
+        for lead_idx in 0..LEAD_BUCKETS {
+            let home_lead = lead_idx as i32 - MAX_LEAD;
+            self.top_start[extra_index][lead_idx] = extra_runs
+                .iter()
+                .enumerate()
+                .map(|(runs, probability)| {
+                    probability * self.bottom_start[extra_index][lead_index(home_lead - runs as i32)]
+                })
+                .sum();
+        }
+
+        // Regulation innings, working backwards from the last one
+        for inning in (1..=REGULATION_INNINGS).rev() {
+            let runs = *self.half_inning_runs(inning);
+            let index = Self::inning_index(inning);
+
+            for lead_idx in 0..LEAD_BUCKETS {
+                let home_lead = lead_idx as i32 - MAX_LEAD;
+                let start = GameSituation {
+                    inning,
+                    top_of_inning: false,
+                    outs: 0,
+                    bases: 0,
+                    home_lead,
+                };
+                self.bottom_start[index][lead_idx] = match start.final_result() {
+                    Some(result) => result,
+                    None => runs
+                        .iter()
+                        .enumerate()
+                        .map(|(r, probability)| {
+                            probability
+                                * self.end_of_half(&GameSituation {
+                                    home_lead: start.lead_after_runs(r),
+                                    ..start
+                                })
+                        })
+                        .sum(),
+                };
+            }
+
+            for lead_idx in 0..LEAD_BUCKETS {
+                let home_lead = lead_idx as i32 - MAX_LEAD;
+                self.top_start[index][lead_idx] = runs
+                    .iter()
+                    .enumerate()
+                    .map(|(r, probability)| {
+                        probability * self.bottom_start[index][lead_index(home_lead - r as i32)]
+                    })
+                    .sum();
+            }
+        }
+    }
+
+    /// Probability that the home team wins from this situation
+    pub fn home_win_probability(&self, situation: &GameSituation) -> f64 {
+        if let Some(result) = situation.final_result() {
+            return result;
+        }
+        if situation.outs >= 3 {
+            return self.end_of_half(situation);
+        }
+
+        self.runs_to_end_of_half[situation.outs as usize][(situation.bases & 0b111) as usize]
+            .iter()
+            .enumerate()
+            .map(|(runs, probability)| {
+                probability
+                    * self.end_of_half(&GameSituation {
+                        home_lead: situation.lead_after_runs(runs),
+                        ..*situation
+                    })
+            })
+            .sum()
+    }
+
+    fn after_plate_appearance(
+        situation: &GameSituation,
+        outcome: PlateAppearanceOutcome,
+    ) -> GameSituation {
+        let (bases, runs) = outcome.advance(situation.bases);
+        match outcome {
+            PlateAppearanceOutcome::Out => GameSituation {
+                outs: situation.outs + 1,
+                ..*situation
+            },
+            _ => GameSituation {
+                bases,
+                home_lead: situation.lead_after_runs(runs),
+                ..*situation
+            },
+        }
+    }
+
+    /// Expected absolute change in win probability over the next plate
+    /// appearance
+    fn expected_swing(&self, situation: &GameSituation) -> f64 {
+        let current = self.home_win_probability(situation);
+        PLATE_APPEARANCE_OUTCOMES
+            .iter()
+            .map(|(outcome, probability)| {
+                let next = Self::after_plate_appearance(situation, *outcome);
+                probability * (self.home_win_probability(&next) - current).abs()
+            })
+            .sum()
+    }
+
+    /// Follows every possible game from the first pitch, weighting each
+    /// plate appearance by how likely it is to happen, and returns the
+    /// average expected swing per plate appearance
+    fn compute_average_swing(&self) -> f64 {
+        let mut occupancy = HashMap::new();
+        occupancy.insert(
+            GameSituation {
+                inning: 1,
+                top_of_inning: true,
+                outs: 0,
+                bases: 0,
+                home_lead: 0,
+            },
+            1.0,
+        );
+
+        // The same situations come up over and over, so cache their swings
+        let mut swings = HashMap::new();
+        let mut total_swing = 0.0;
+        let mut total_weight = 0.0;
+        while occupancy.values().sum::<f64>() > OCCUPANCY_EPSILON {
+            let mut next_occupancy = HashMap::new();
+            for (situation, weight) in occupancy {
+                let swing = *swings
+                    .entry(situation)
+                    .or_insert_with(|| self.expected_swing(&situation));
+                total_swing += weight * swing;
+                total_weight += weight;
+
+                for (outcome, probability) in PLATE_APPEARANCE_OUTCOMES {
+                    let mut next = Self::after_plate_appearance(&situation, outcome);
+                    if next.final_result().is_some() {
+                        continue;
+                    }
+                    if next.outs >= 3 {
+                        next = next.next_half_inning();
+                    }
+                    // All extra innings are identical, so cap the inning to
+                    // keep the number of situations finite
+                    next.inning = next.inning.min(REGULATION_INNINGS + 1);
+                    next.home_lead = next.home_lead.clamp(-MAX_LEAD, MAX_LEAD);
+                    *next_occupancy.entry(next).or_insert(0.0) += weight * probability;
+                }
+            }
+            occupancy = next_occupancy;
+        }
+
+        if total_weight > 0.0 && total_swing > 0.0 {
+            total_swing / total_weight
+        } else {
+            1.0
+        }
+    }
+
+    /// How much the next plate appearance matters compared to an average
+    /// plate appearance. 1 is average, 2 is twice as important, etc.
+    pub fn leverage_index(&self, situation: &GameSituation) -> f64 {
+        self.expected_swing(situation) / self.average_swing
+    }
+}
+
+/// Building the model takes a noticeable fraction of a second, so it's
+/// only done once
+pub static WIN_PROBABILITY_MODEL: LazyLock<WinProbabilityModel> =
+    LazyLock::new(WinProbabilityModel::new);
+
+fn base_bit(base: TaxaBase) -> u8 {
+    match base {
+        TaxaBase::Home => 0,
+        TaxaBase::First => FIRST,
+        TaxaBase::Second => SECOND,
+        TaxaBase::Third => THIRD,
+    }
+}
+
+fn situations_for_event(event: &EventDetail<&str>) -> (GameSituation, GameSituation) {
+    let bases_before = event
+        .baserunners
+        .iter()
+        .filter_map(|runner| runner.base_before)
+        .fold(0, |bases, base| bases | base_bit(base));
+
+    let bases_after = event
+        .baserunners
+        .iter()
+        .filter(|runner| !runner.is_out && runner.assassinated_by.is_none())
+        .fold(0, |bases, runner| bases | base_bit(runner.base_after));
+
+    let before = GameSituation {
+        inning: event.inning,
+        top_of_inning: event.top_of_inning,
+        outs: event.outs_before,
+        bases: bases_before,
+        home_lead: event.home_team_score_before as i32 - event.away_team_score_before as i32,
+    };
+    let after = GameSituation {
+        outs: event.outs_after,
+        bases: bases_after,
+        home_lead: event.home_team_score_after as i32 - event.away_team_score_after as i32,
+        ..before
+    };
+
+    (before, after)
+}
+
+pub fn event_to_win_probability(
+    event_id: i64,
+    event: &EventDetail<&str>,
+) -> NewEventWinProbability {
+    let model = &*WIN_PROBABILITY_MODEL;
+    let (before, after) = situations_for_event(event);
+
+    let home_win_probability_before = model.home_win_probability(&before);
+    let home_win_probability_after = model.home_win_probability(&after);
+    let home_win_probability_added = home_win_probability_after - home_win_probability_before;
+
+    NewEventWinProbability {
+        event_id,
+        home_win_probability_before,
+        home_win_probability_after,
+        batting_team_win_probability_added: if event.top_of_inning {
+            -home_win_probability_added
+        } else {
+            home_win_probability_added
+        },
+        leverage_index: model.leverage_index(&before),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn situation(inning: u8, top_of_inning: bool, outs: i32, bases: u8, home_lead: i32) -> GameSituation {
+        GameSituation {
+            inning,
+            top_of_inning,
+            outs,
+            bases,
+            home_lead,
+        }
+    }
+
+    #[test]
+    fn win_probability_sanity() {
+        let model = &*WIN_PROBABILITY_MODEL;
+
+        // Home field advantage is just from batting last, so it's small
+        let first_pitch = model.home_win_probability(&situation(1, true, 0, 0, 0));
+        assert!(0.5 < first_pitch && first_pitch < 0.6, "{first_pitch}");
+
+        // Walk-off
+        assert_eq!(model.home_win_probability(&situation(9, false, 1, 0, 1)), 1.0);
+        // Home team leads after the top of the 9th
+        assert_eq!(model.home_win_probability(&situation(9, true, 3, 0, 2)), 1.0);
+        // Home team trails after the bottom of the 9th
+        assert_eq!(model.home_win_probability(&situation(9, false, 3, 0, -1)), 0.0);
+
+        // More runners is better for the batting team
+        let bases_empty = model.home_win_probability(&situation(5, false, 1, 0, 0));
+        let bases_loaded = model.home_win_probability(&situation(5, false, 1, 0b111, 0));
+        assert!(bases_loaded > bases_empty);
+
+        // Late and close is high leverage, blowouts are low leverage
+        let late_and_close = model.leverage_index(&situation(9, false, 2, 0b111, -1));
+        let blowout = model.leverage_index(&situation(9, false, 0, 0, -10));
+        assert!(late_and_close > 1.0 && blowout < 1.0);
+    }
+}
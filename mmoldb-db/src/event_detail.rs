@@ -1,7 +1,7 @@
 use crate::taxa::{
     AsInsertable, TaxaAttribute, TaxaBase, TaxaBaseDescriptionFormat,
     TaxaBaseWithDescriptionFormat, TaxaEventType, TaxaFairBallType, TaxaFielderLocation,
-    TaxaFieldingErrorType, TaxaPitchType, TaxaPitcherChangeSource, TaxaSlot,
+    TaxaFieldingErrorType, TaxaIngestErrorCode, TaxaPitchType, TaxaPitcherChangeSource, TaxaSlot,
 };
 use itertools::Itertools;
 use miette::Diagnostic;
@@ -88,6 +88,8 @@ pub struct IngestLog {
     pub game_event_index: i32,
     pub log_level: i32,
     pub log_text: String,
+    /// None means the log isn't categorized
+    pub error_code: Option<TaxaIngestErrorCode>,
 }
 
 fn placed_player_as_ref<StrT: AsRef<str> + Clone>(
@@ -858,6 +860,46 @@ impl<StrT: AsRef<str> + Clone> EventDetail<StrT> {
     }
 }
 
+/// Who was playing each fielding position when an event happened. The
+/// pitcher is left out because every event already records it.
+#[derive(Debug, Clone, Default)]
+pub struct EventDefense<StrT: Clone> {
+    pub catcher: Option<StrT>,
+    pub first_base: Option<StrT>,
+    pub second_base: Option<StrT>,
+    pub third_base: Option<StrT>,
+    pub shortstop: Option<StrT>,
+    pub left_field: Option<StrT>,
+    pub center_field: Option<StrT>,
+    pub right_field: Option<StrT>,
+}
+
+/// How much work the pitcher had done when a plate appearance ended.
+/// Counts are per pitcher per game. Every event in `data.events` except a
+/// balk counts as a pitch.
+#[derive(Debug, Clone)]
+pub struct PlateAppearanceWorkload {
+    /// Index of the event that ended the plate appearance
+    pub game_event_index: usize,
+    pub pitcher_batters_faced_before: i32,
+    /// 1 the first time the pitcher faces the batting order, 2 the second
+    /// time, and so on
+    pub times_through_order: i32,
+    pub pitcher_pitches_before: i32,
+    pub pitches: i32,
+}
+
+/// Where one pitch falls in its plate appearance. Balks aren't pitches, so
+/// they don't get one.
+#[derive(Debug, Clone)]
+pub struct PitchSequenceEntry {
+    pub game_event_index: usize,
+    /// 1 for the first pitch of the plate appearance
+    pub pitch_of_plate_appearance: i32,
+    /// None for the first pitch of the plate appearance
+    pub previous_pitch_game_event_index: Option<usize>,
+}
+
 #[derive(Debug, Clone)]
 pub struct PitcherChange<StrT: Clone> {
     pub game_event_index: usize,
@@ -1,6 +1,8 @@
 pub mod db;
 mod migrations;
+mod mmolb_id;
 pub mod models;
+mod object_store;
 mod parsing_extensions;
 mod pool;
 mod schema;
@@ -15,12 +17,15 @@ pub(crate) use schema::*;
 pub use db::DbMetaQueryError;
 pub use event_detail::*;
 pub use migrations::*;
+pub use mmolb_id::*;
+pub use object_store::*;
 pub use parsing_extensions::*;
 pub use pool::*;
 pub use url::*;
 
 pub use diesel::{
-    Connection, PgConnection, QueryResult, result::ConnectionError, result::Error as QueryError,
+    Connection, PgConnection, QueryResult, result::ConnectionError, result::DatabaseErrorKind,
+    result::Error as QueryError,
 };
 pub use diesel_async::{AsyncConnection, AsyncPgConnection};
 
@@ -56,15 +61,15 @@ mod tests {
             let processed = NewVersionProcessed {
                 kind: "team",
                 entity_id: "",
-                valid_from: valid_from.naive_utc(),
+                valid_from,
                 skipped: false,
                 fatal_error: false,
             };
             let mut team_version = NewTeamVersion::default();
-            team_version.valid_from = valid_from.naive_utc();
+            team_version.valid_from = valid_from;
             team_version.num_players = 1; // Don't close out the team player version we're inserting
             let mut team_player_version = NewTeamPlayerVersion::default();
-            team_player_version.valid_from = valid_from.naive_utc();
+            team_player_version.valid_from = valid_from;
 
             let mut team = (
                 processed,
@@ -204,12 +209,12 @@ mod tests {
             let processed = NewVersionProcessed {
                 kind: "player",
                 entity_id: "",
-                valid_from: valid_from.naive_utc(),
+                valid_from,
                 skipped: false,
                 fatal_error: false,
             };
             let mut player_version = NewPlayerVersion::default();
-            player_version.valid_from = valid_from.naive_utc();
+            player_version.valid_from = valid_from;
             player_version.num_modifications = 1; // Don't close out the player modification version we're inserting
             player_version.included_report_categories = vec![0]; // Don't close out the player report version we're inserting
             player_version.occupied_equipment_slots = vec![""]; // Don't close out the player equipment version we're inserting
@@ -217,28 +222,28 @@ mod tests {
             player_version.included_pitch_type_bonuses = vec![1]; // Don't close out the player pitch type bonus version we're inserting
             player_version.included_pitch_category_bonuses = vec![1]; // Don't close out the player pitch category bonus version we're inserting
             let mut player_modification_version = NewPlayerModificationVersion::default();
-            player_modification_version.valid_from = valid_from.naive_utc();
+            player_modification_version.valid_from = valid_from;
             player_modification_version.modification_id = modification_id; // Need a valid modification ID to satisfy the foreign key constraint
             let mut player_report_version = NewPlayerReportVersion::default();
-            player_report_version.valid_from = valid_from.naive_utc();
+            player_report_version.valid_from = valid_from;
             player_report_version.included_attributes = vec![2]; // Don't close out the player report attribute version we're inserting
             let mut player_report_attribute_version = NewPlayerReportAttributeVersion::default();
-            player_report_attribute_version.valid_from = valid_from.naive_utc();
+            player_report_attribute_version.valid_from = valid_from;
             player_report_attribute_version.attribute = 2;  // Need a valid attribute id (this comes from hard-coded taxa)
             let mut player_equipment_version = NewPlayerEquipmentVersion::default();
-            player_equipment_version.valid_from = valid_from.naive_utc();
+            player_equipment_version.valid_from = valid_from;
             player_equipment_version.num_effects = 1; // Don't close out the player equipment effect version we're inserting
             let mut player_equipment_effect_version = NewPlayerEquipmentEffectVersion::default();
-            player_equipment_effect_version.valid_from = valid_from.naive_utc();
+            player_equipment_effect_version.valid_from = valid_from;
             player_equipment_effect_version.attribute = 2;  // Need a valid attribute id (this comes from hard-coded taxa)
             player_equipment_effect_version.effect_type = 1;  // Need a valid effect type id (this comes from hard-coded taxa)
             let mut player_pitch_type_version = NewPlayerPitchTypeVersion::default();
-            player_pitch_type_version.valid_from = valid_from.naive_utc();
+            player_pitch_type_version.valid_from = valid_from;
             let mut player_pitch_type_bonus_version = NewPlayerPitchTypeBonusVersion::default();
-            player_pitch_type_bonus_version.valid_from = valid_from.naive_utc();
+            player_pitch_type_bonus_version.valid_from = valid_from;
             player_pitch_type_bonus_version.pitch_type = 1;  // Need a valid pitch type id (this comes from hard-coded taxa)
             let mut player_pitch_category_bonus_version = NewPlayerPitchCategoryBonusVersion::default();
-            player_pitch_category_bonus_version.valid_from = valid_from.naive_utc();
+            player_pitch_category_bonus_version.valid_from = valid_from;
             player_pitch_category_bonus_version.pitch_category = 1;  // Need a valid pitch category id (this comes from hard-coded taxa)
 
             let mut player = (
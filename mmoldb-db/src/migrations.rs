@@ -24,6 +24,12 @@ pub enum MigrationError {
 
     #[error("error creating taxa")]
     FailedToCreateTaxa(#[source] QueryError),
+
+    #[error("error checking for pending migrations")]
+    FailedToCheckMigrations(#[source] Box<dyn Error + Send + Sync>),
+
+    #[error("the database has pending migrations. Run the main ingest to apply them.")]
+    PendingMigrations,
 }
 
 pub fn run_migrations() -> Result<Taxa, MigrationError> {
@@ -60,3 +66,22 @@ pub fn run_migrations() -> Result<Taxa, MigrationError> {
     info!("Migrations finished");
     Ok(taxa)
 }
+
+/// Returns an error if any migrations haven't been run. For processes that
+/// can run alongside the main ingest, which is the only one that migrates,
+/// so they never change the schema under it.
+pub fn require_current_schema() -> Result<(), MigrationError> {
+    let url = crate::postgres_url_from_environment();
+
+    let mut conn =
+        PgConnection::establish(&url).map_err(MigrationError::FailedToConnectToDatabase)?;
+
+    if conn
+        .has_pending_migration(MIGRATIONS)
+        .map_err(MigrationError::FailedToCheckMigrations)?
+    {
+        return Err(MigrationError::PendingMigrations);
+    }
+
+    Ok(())
+}
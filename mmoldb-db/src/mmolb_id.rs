@@ -0,0 +1,109 @@
+use diesel::deserialize::{self, FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::pg::{Pg, PgValue};
+use diesel::serialize::{self, IsNull, Output, ToSql};
+use diesel::sql_types::Bytea;
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// An MMOLB entity id. These are Mongo object ids: 12 bytes, written as 24
+/// lowercase hex characters. In the database it's a `data.mmolb_id`, which
+/// stores the bytes and is half the size of the text. Parse one from text
+/// with [`FromStr`] and turn it back into text with [`Display`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, AsExpression, FromSqlRow)]
+#[diesel(sql_type = Bytea)]
+pub struct MmolbId([u8; 12]);
+
+#[derive(Debug, Error)]
+#[error("{0:?} isn't an MMOLB id. MMOLB ids are 24 hex characters.")]
+pub struct InvalidMmolbId(pub String);
+
+impl MmolbId {
+    pub fn from_bytes(bytes: [u8; 12]) -> Self {
+        Self(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 12] {
+        &self.0
+    }
+}
+
+impl FromStr for MmolbId {
+    type Err = InvalidMmolbId;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // from_str_radix would also accept a sign
+        if s.len() != 24 || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(InvalidMmolbId(s.to_string()));
+        }
+
+        let mut bytes = [0; 12];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&s[2 * i..2 * i + 2], 16)
+                .expect("every character was checked to be a hex digit");
+        }
+        Ok(Self(bytes))
+    }
+}
+
+impl Display for MmolbId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for byte in &self.0 {
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+impl ToSql<Bytea, Pg> for MmolbId {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        out.write_all(&self.0)?;
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<Bytea, Pg> for MmolbId {
+    fn from_sql(value: PgValue<'_>) -> deserialize::Result<Self> {
+        let bytes = value.as_bytes().try_into().map_err(|_| {
+            format!(
+                "an MMOLB id should be 12 bytes, but got {}",
+                value.as_bytes().len()
+            )
+        })?;
+        Ok(Self(bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mmolb_ids_round_trip_through_text() {
+        let text = "6805db0cac48194de3cd3fe7";
+        let id = text.parse::<MmolbId>().unwrap();
+        assert_eq!(id.as_bytes()[0], 0x68);
+        assert_eq!(id.to_string(), text);
+
+        // Uppercase is accepted, but always written back as lowercase
+        let upper = text.to_uppercase().parse::<MmolbId>().unwrap();
+        assert_eq!(upper, id);
+    }
+
+    #[test]
+    fn malformed_mmolb_ids_are_rejected() {
+        for text in [
+            "",
+            "6805db0cac48194de3cd3fe",
+            "6805db0cac48194de3cd3fez",
+            "+805db0cac48194de3cd3fe7",
+        ] {
+            assert!(
+                text.parse::<MmolbId>().is_err(),
+                "{text:?} should be rejected"
+            );
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use chrono::NaiveDateTime;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
 use diesel::prelude::*;
 use serde::Serialize;
 use one_au::OneAu;
@@ -9,9 +9,11 @@ use one_au::OneAu;
 pub struct NewVersion<'a> {
     pub kind: &'a str,
     pub entity_id: &'a str,
-    pub valid_from: NaiveDateTime,
+    pub valid_from: DateTime<Utc>,
     // New versions always have null valid_until, and it's set later by a database function
     pub data: &'a serde_json::Value,
+    // None for main MMOLB
+    pub source: Option<&'a str>,
 }
 
 #[derive(Debug, Identifiable, Queryable, Selectable, QueryableByName)]
@@ -20,10 +22,12 @@ pub struct NewVersion<'a> {
 pub struct DbVersion {
     pub kind: String,
     pub entity_id: String,
-    pub valid_from: NaiveDateTime,
+    pub valid_from: DateTime<Utc>,
     // TODO Rename this column valid_until in the database
-    pub valid_to: Option<NaiveDateTime>,
-    pub data: serde_json::Value,
+    pub valid_to: Option<DateTime<Utc>>,
+    // Exactly one of these is set. See `db::decompress_version_data`.
+    pub data: Option<serde_json::Value>,
+    pub data_zstd: Option<Vec<u8>>,
 }
 
 #[derive(Insertable)]
@@ -33,8 +37,10 @@ pub struct NewFeedEventVersion<'a> {
     pub kind: &'a str,
     pub entity_id: &'a str,
     pub feed_event_index: i32,
-    pub valid_from: NaiveDateTime,
+    pub valid_from: DateTime<Utc>,
     pub data: &'a serde_json::Value,
+    // None for main MMOLB
+    pub source: Option<&'a str>,
 }
 
 #[derive(Debug, Identifiable, Queryable, Selectable, QueryableByName)]
@@ -44,7 +50,7 @@ pub struct DbFeedEventVersion {
     pub kind: String,
     pub entity_id: String,
     pub feed_event_index: i32,
-    pub valid_from: NaiveDateTime,
+    pub valid_from: DateTime<Utc>,
     pub data: serde_json::Value,
 }
 
@@ -93,6 +99,65 @@ pub struct NewGame<'a> {
     pub home_team_photo_contest_score: Option<i32>,
     pub away_team_photo_contest_top_scorer: Option<&'a str>,
     pub away_team_photo_contest_score: Option<i32>,
+    pub started_at: DateTime<Utc>,
+    pub mmolb_stadium_id: Option<String>,
+}
+
+/// The columns of `data.games` that are computed from a completed game's
+/// events, as opposed to copied from the game's header
+#[derive(Debug, PartialEq, AsChangeset, Serialize)]
+#[diesel(table_name = crate::data_schema::data::games)]
+#[diesel(treat_none_as_null = true)]
+pub struct GameMetadataUpdate<'a> {
+    pub away_team_final_score: Option<i32>,
+    pub home_team_final_score: Option<i32>,
+    pub home_team_earned_coins: Option<i32>,
+    pub away_team_earned_coins: Option<i32>,
+    pub home_team_photo_contest_top_scorer: Option<&'a str>,
+    pub home_team_photo_contest_score: Option<i32>,
+    pub away_team_photo_contest_top_scorer: Option<&'a str>,
+    pub away_team_photo_contest_score: Option<i32>,
+    pub home_manager_name: Option<&'a str>,
+    pub away_manager_name: Option<&'a str>,
+    pub stadium_name: Option<&'a str>,
+}
+
+impl GameMetadataUpdate<'_> {
+    /// True if `game` already has these values
+    pub fn matches(&self, game: &DbGame) -> bool {
+        self.away_team_final_score == game.away_team_final_score
+            && self.home_team_final_score == game.home_team_final_score
+            && self.home_team_earned_coins == game.home_team_earned_coins
+            && self.away_team_earned_coins == game.away_team_earned_coins
+            && self.home_team_photo_contest_top_scorer
+                == game.home_team_photo_contest_top_scorer.as_deref()
+            && self.home_team_photo_contest_score == game.home_team_photo_contest_score
+            && self.away_team_photo_contest_top_scorer
+                == game.away_team_photo_contest_top_scorer.as_deref()
+            && self.away_team_photo_contest_score == game.away_team_photo_contest_score
+            && self.home_manager_name == game.home_manager_name.as_deref()
+            && self.away_manager_name == game.away_manager_name.as_deref()
+            && self.stadium_name == game.stadium_name.as_deref()
+    }
+}
+
+impl<'a> From<&'a DbGame> for GameMetadataUpdate<'a> {
+    /// The values `game` has now
+    fn from(game: &'a DbGame) -> Self {
+        Self {
+            away_team_final_score: game.away_team_final_score,
+            home_team_final_score: game.home_team_final_score,
+            home_team_earned_coins: game.home_team_earned_coins,
+            away_team_earned_coins: game.away_team_earned_coins,
+            home_team_photo_contest_top_scorer: game.home_team_photo_contest_top_scorer.as_deref(),
+            home_team_photo_contest_score: game.home_team_photo_contest_score,
+            away_team_photo_contest_top_scorer: game.away_team_photo_contest_top_scorer.as_deref(),
+            away_team_photo_contest_score: game.away_team_photo_contest_score,
+            home_manager_name: game.home_manager_name.as_deref(),
+            away_manager_name: game.away_manager_name.as_deref(),
+            stadium_name: game.stadium_name.as_deref(),
+        }
+    }
 }
 
 #[derive(Identifiable, Queryable, Selectable, QueryableByName)]
@@ -123,6 +188,7 @@ pub struct DbGame {
     pub home_team_photo_contest_score: Option<i32>,
     pub away_team_photo_contest_top_scorer: Option<String>,
     pub away_team_photo_contest_score: Option<i32>,
+    pub started_at: DateTime<Utc>,
 }
 
 #[derive(Insertable)]
@@ -215,9 +281,10 @@ pub struct NewEventIngestLog<'a> {
     // Data
     pub log_level: i32,
     pub log_text: &'a str,
+    pub error_code: Option<i64>,
 }
 
-#[derive(Identifiable, Queryable, Selectable)]
+#[derive(Identifiable, Queryable, Selectable, QueryableByName)]
 #[diesel(table_name = crate::info_schema::info::event_ingest_log)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct DbEventIngestLog {
@@ -227,6 +294,7 @@ pub struct DbEventIngestLog {
     pub log_index: i32,
     pub log_level: i32,
     pub log_text: String,
+    pub error_code: Option<i64>,
 }
 
 #[derive(Insertable)]
@@ -355,8 +423,8 @@ pub struct DbModification {
 #[diesel(treat_none_as_default_value = false)]
 pub struct NewPlayerModificationVersion<'a> {
     pub mmolb_player_id: &'a str,
-    pub valid_from: NaiveDateTime,
-    pub valid_until: Option<NaiveDateTime>,
+    pub valid_from: DateTime<Utc>,
+    pub valid_until: Option<DateTime<Utc>>,
     pub modification_type: i64,
     pub modification_index: i32,
     pub modification_id: i64,
@@ -368,8 +436,8 @@ pub struct NewPlayerModificationVersion<'a> {
 pub struct DbPlayerModificationVersion {
     pub id: i64,
     pub mmolb_player_id: String,
-    pub valid_from: NaiveDateTime,
-    pub valid_until: Option<NaiveDateTime>,
+    pub valid_from: DateTime<Utc>,
+    pub valid_until: Option<DateTime<Utc>>,
     pub modification_type: i64,
     pub modification_index: i32,
     pub modification_id: i64,
@@ -380,8 +448,8 @@ pub struct DbPlayerModificationVersion {
 #[diesel(treat_none_as_default_value = false)]
 pub struct NewPlayerVersion<'a> {
     pub mmolb_player_id: &'a str,
-    pub valid_from: NaiveDateTime,
-    pub valid_until: Option<NaiveDateTime>,
+    pub valid_from: DateTime<Utc>,
+    pub valid_until: Option<DateTime<Utc>>,
     pub first_name: &'a str,
     pub last_name: &'a str,
     pub batting_handedness: Option<i64>,
@@ -419,8 +487,8 @@ pub struct NewPlayerVersion<'a> {
 pub struct DbPlayerVersion {
     pub id: i64,
     pub mmolb_player_id: String,
-    pub valid_from: NaiveDateTime,
-    pub valid_until: Option<NaiveDateTime>,
+    pub valid_from: DateTime<Utc>,
+    pub valid_until: Option<DateTime<Utc>>,
     pub first_name: String,
     pub last_name: String,
     pub batting_handedness: Option<i64>,
@@ -466,6 +534,9 @@ pub struct DbPlayerAttributeAugment {
     pub superstar_day: Option<i32>,
     pub attribute: i64,
     pub value: i32,
+    /// Always `player_feed` when set. Null for augments whose feed event
+    /// text wasn't stored.
+    pub feed_event_kind: Option<String>,
 }
 
 #[derive(Clone, Debug, Insertable, PartialEq)]
@@ -481,6 +552,9 @@ pub struct NewPlayerAttributeAugment<'a> {
     pub superstar_day: Option<i32>,
     pub attribute: i64,
     pub value: i32,
+    /// Links the augment to its row in `data.feed_event_texts`, which has to
+    /// be inserted first
+    pub feed_event_kind: Option<&'a str>,
 }
 
 #[derive(Debug, Identifiable, Queryable, Selectable, QueryableByName)]
@@ -554,8 +628,8 @@ pub struct DbPlayerReportVersion {
     pub id: i64,
     pub mmolb_player_id: String,
     pub category: i64,
-    pub valid_from: NaiveDateTime,
-    pub valid_until: Option<NaiveDateTime>,
+    pub valid_from: DateTime<Utc>,
+    pub valid_until: Option<DateTime<Utc>>,
     pub season: Option<i32>,
     pub day_type: Option<i64>,
     pub day: Option<i32>,
@@ -570,8 +644,8 @@ pub struct DbPlayerReportVersion {
 pub struct NewPlayerReportVersion<'a> {
     pub mmolb_player_id: &'a str,
     pub category: i64,
-    pub valid_from: NaiveDateTime,
-    pub valid_until: Option<NaiveDateTime>,
+    pub valid_from: DateTime<Utc>,
+    pub valid_until: Option<DateTime<Utc>>,
     pub season: Option<i32>,
     pub day_type: Option<i64>,
     pub day: Option<i32>,
@@ -588,8 +662,8 @@ pub struct DbPlayerReportAttributeVersion {
     pub mmolb_player_id: String,
     pub category: i64,
     pub attribute: i64,
-    pub valid_from: NaiveDateTime,
-    pub valid_until: Option<NaiveDateTime>,
+    pub valid_from: DateTime<Utc>,
+    pub valid_until: Option<DateTime<Utc>>,
     pub base_stars: Option<i32>,
     pub base_total: Option<f64>,
     pub base_subtotal: Option<f64>,
@@ -604,8 +678,8 @@ pub struct NewPlayerReportAttributeVersion<'a> {
     pub mmolb_player_id: &'a str,
     pub category: i64,
     pub attribute: i64,
-    pub valid_from: NaiveDateTime,
-    pub valid_until: Option<NaiveDateTime>,
+    pub valid_from: DateTime<Utc>,
+    pub valid_until: Option<DateTime<Utc>>,
     pub base_stars: Option<i32>,
     pub base_total: Option<f64>,
     pub base_subtotal: Option<f64>,
@@ -620,8 +694,8 @@ pub struct DbPlayerEquipmentVersion {
     pub id: i64,
     pub mmolb_player_id: String,
     pub equipment_slot: String,
-    pub valid_from: NaiveDateTime,
-    pub valid_until: Option<NaiveDateTime>,
+    pub valid_from: DateTime<Utc>,
+    pub valid_until: Option<DateTime<Utc>>,
     pub emoji: String,
     pub name: String,
     pub special_type: Option<String>,
@@ -644,8 +718,8 @@ pub struct DbPlayerEquipmentVersion {
 pub struct NewPlayerEquipmentVersion<'a> {
     pub mmolb_player_id: &'a str,
     pub equipment_slot: String,
-    pub valid_from: NaiveDateTime,
-    pub valid_until: Option<NaiveDateTime>,
+    pub valid_from: DateTime<Utc>,
+    pub valid_until: Option<DateTime<Utc>>,
     pub emoji: String,
     pub name: String,
     pub special_type: Option<String>,
@@ -670,8 +744,8 @@ pub struct DbPlayerEquipmentEffectVersion {
     pub mmolb_player_id: String,
     pub equipment_slot: String,
     pub effect_index: i32,
-    pub valid_from: NaiveDateTime,
-    pub valid_until: Option<NaiveDateTime>,
+    pub valid_from: DateTime<Utc>,
+    pub valid_until: Option<DateTime<Utc>>,
     pub attribute: i64,
     pub effect_type: i64,
     pub value: f64,
@@ -688,8 +762,8 @@ pub struct NewPlayerEquipmentEffectVersion<'a> {
     pub mmolb_player_id: &'a str,
     pub equipment_slot: String,
     pub effect_index: i32,
-    pub valid_from: NaiveDateTime,
-    pub valid_until: Option<NaiveDateTime>,
+    pub valid_from: DateTime<Utc>,
+    pub valid_until: Option<DateTime<Utc>>,
     pub attribute: i64,
     pub effect_type: i64,
     pub value: f64,
@@ -779,10 +853,11 @@ pub struct DbVersionIngestLog {
     pub id: i64,
     pub kind: String,
     pub entity_id: String,
-    pub valid_from: NaiveDateTime,
+    pub valid_from: DateTime<Utc>,
     pub log_index: i32,
     pub log_level: i32,
     pub log_text: String,
+    pub error_code: Option<i64>,
 }
 
 #[derive(Clone, Debug, Insertable, PartialEq)]
@@ -791,10 +866,41 @@ pub struct DbVersionIngestLog {
 pub struct NewVersionIngestLog<'a> {
     pub kind: &'a str,
     pub entity_id: &'a str,
-    pub valid_from: NaiveDateTime,
+    pub valid_from: DateTime<Utc>,
     pub log_index: i32,
     pub log_level: i32,
     pub log_text: String,
+    pub error_code: Option<i64>,
+    /// Only set for feed events
+    pub feed_event_index: Option<i32>,
+}
+
+#[derive(Clone, Debug, Insertable, PartialEq)]
+#[diesel(table_name = crate::data_schema::data::stadium_versions)]
+#[diesel(treat_none_as_default_value = false)]
+pub struct NewStadiumVersion<'a> {
+    pub mmolb_stadium_id: &'a str,
+    pub valid_from: DateTime<Utc>,
+    pub valid_until: Option<DateTime<Utc>>,
+    pub name: &'a str,
+    pub mmolb_team_id: Option<&'a str>,
+    pub modifications: Vec<&'a str>,
+    pub capacity: Option<i32>,
+}
+
+#[derive(Debug, Clone, Identifiable, Queryable, Selectable, QueryableByName, Serialize)]
+#[diesel(table_name = crate::data_schema::data::stadium_versions)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct DbStadiumVersion {
+    pub id: i64,
+    pub mmolb_stadium_id: String,
+    pub valid_from: DateTime<Utc>,
+    pub valid_until: Option<DateTime<Utc>>,
+    pub duplicates: i32,
+    pub name: String,
+    pub mmolb_team_id: Option<String>,
+    pub modifications: Vec<String>,
+    pub capacity: Option<i32>,
 }
 
 #[derive(Clone, Debug, Insertable, PartialEq, Default, OneAu)]
@@ -802,8 +908,8 @@ pub struct NewVersionIngestLog<'a> {
 #[diesel(treat_none_as_default_value = false)]
 pub struct NewTeamVersion<'a> {
     pub mmolb_team_id: &'a str,
-    pub valid_from: NaiveDateTime,
-    pub valid_until: Option<NaiveDateTime>,
+    pub valid_from: DateTime<Utc>,
+    pub valid_until: Option<DateTime<Utc>>,
     pub name: &'a str,
     pub emoji: &'a str,
     pub color: &'a str,
@@ -823,8 +929,8 @@ pub struct NewTeamVersion<'a> {
 pub struct DbTeamVersion {
     pub id: i64,
     pub mmolb_team_id: String,
-    pub valid_from: NaiveDateTime,
-    pub valid_until: Option<NaiveDateTime>,
+    pub valid_from: DateTime<Utc>,
+    pub valid_until: Option<DateTime<Utc>>,
     pub name: String,
     pub emoji: String,
     pub color: String,
@@ -844,8 +950,8 @@ pub struct DbTeamVersion {
 pub struct NewTeamPlayerVersion<'a> {
     pub mmolb_team_id: &'a str,
     pub team_player_index: i32,
-    pub valid_from: NaiveDateTime,
-    pub valid_until: Option<NaiveDateTime>,
+    pub valid_from: DateTime<Utc>,
+    pub valid_until: Option<DateTime<Utc>>,
     pub first_name: &'a str,
     pub last_name: &'a str,
     pub name_suffix: Option<&'a str>,
@@ -860,8 +966,9 @@ pub struct NewTeamPlayerVersion<'a> {
 pub struct DbTeamPlayerVersion {
     pub id: i64,
     pub mmolb_team_id: String,
-    pub valid_from: NaiveDateTime,
-    pub valid_until: Option<NaiveDateTime>,
+    pub team_player_index: i32,
+    pub valid_from: DateTime<Utc>,
+    pub valid_until: Option<DateTime<Utc>>,
     pub first_name: String,
     pub last_name: String,
     pub name_suffix: Option<String>,
@@ -971,6 +1078,66 @@ pub struct DbPitcherChange {
     pub new_pitcher_slot: Option<i64>,
 }
 
+#[derive(Clone, Debug, Insertable, PartialEq)]
+#[diesel(table_name = crate::data_schema::data::pitcher_appearances)]
+#[diesel(treat_none_as_default_value = false)]
+pub struct NewPitcherAppearance<'a> {
+    pub game_id: i64,
+    pub is_home_team: bool,
+    pub pitcher_name: &'a str,
+    pub pitcher_count: i32,
+    pub first_game_event_index: i32,
+    pub last_game_event_index: i32,
+    pub pitches: i32,
+    pub batters_faced: i32,
+    pub outs_recorded: i32,
+    pub replaced_by_source: Option<i64>,
+    pub replaced_by_name: Option<&'a str>,
+}
+
+#[derive(Debug, Clone, Identifiable, Queryable, Selectable, QueryableByName, Serialize)]
+#[diesel(table_name = crate::data_schema::data::pitcher_appearances)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct DbPitcherAppearance {
+    pub id: i64,
+    pub game_id: i64,
+    pub is_home_team: bool,
+    pub pitcher_name: String,
+    pub pitcher_count: i32,
+    pub first_game_event_index: i32,
+    pub last_game_event_index: i32,
+    pub pitches: i32,
+    pub batters_faced: i32,
+    pub outs_recorded: i32,
+    pub replaced_by_source: Option<i64>,
+    pub replaced_by_name: Option<String>,
+}
+
+#[derive(Clone, Debug, Insertable, PartialEq)]
+#[diesel(table_name = crate::data_schema::data::game_innings)]
+#[diesel(treat_none_as_default_value = false)]
+pub struct NewGameInning {
+    pub game_id: i64,
+    pub inning: i32,
+    pub top_of_inning: bool,
+    pub runs: i32,
+    pub hits: i32,
+    pub errors: i32,
+}
+
+#[derive(Debug, Clone, Identifiable, Queryable, Selectable, QueryableByName, Serialize)]
+#[diesel(table_name = crate::data_schema::data::game_innings)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct DbGameInning {
+    pub id: i64,
+    pub game_id: i64,
+    pub inning: i32,
+    pub top_of_inning: bool,
+    pub runs: i32,
+    pub hits: i32,
+    pub errors: i32,
+}
+
 #[derive(Clone, Debug, Insertable, PartialEq)]
 #[diesel(table_name = crate::data_schema::data::parties)]
 #[diesel(treat_none_as_default_value = false)]
@@ -1010,6 +1177,19 @@ pub struct NewTeamGamePlayed<'a> {
     pub mmolb_game_id: &'a str,
 }
 
+#[derive(Clone, Debug, Insertable, PartialEq)]
+#[diesel(table_name = crate::data_schema::data::team_transactions)]
+#[diesel(treat_none_as_default_value = false)]
+pub struct NewTeamTransaction<'a> {
+    pub mmolb_team_id: &'a str,
+    pub feed_event_index: i32,
+    pub time: DateTime<Utc>,
+    /// Positive if the team earned coins, negative if it spent them
+    pub amount: i32,
+    pub source: &'static str,
+    pub mmolb_game_id: Option<&'a str>,
+}
+
 #[derive(Clone, Debug, Insertable, PartialEq)]
 #[diesel(table_name = crate::data_schema::data::wither)]
 #[diesel(treat_none_as_default_value = false)]
@@ -1132,7 +1312,7 @@ pub struct NewFeedEventProcessed<'a> {
     pub kind: &'a str,
     pub entity_id: &'a str,
     pub feed_event_index: i32,
-    pub valid_from: NaiveDateTime,
+    pub valid_from: DateTime<Utc>,
     pub skipped: bool,
     pub fatal_error: bool,
 }
@@ -1144,11 +1324,39 @@ pub struct DbFeedEventProcessed {
     pub kind: String,
     pub entity_id: String,
     pub feed_event_index: i32,
-    pub valid_from: NaiveDateTime,
+    pub valid_from: DateTime<Utc>,
     pub skipped: bool,
     pub fatal_error: bool,
 }
 
+/// A feed event version to put in the dead-letter queue. This isn't
+/// Insertable because the raw data is copied over from
+/// `data.feed_event_versions` by the database.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NewFeedEventFailure<'a> {
+    pub kind: &'a str,
+    pub entity_id: &'a str,
+    pub feed_event_index: i32,
+    pub valid_from: DateTime<Utc>,
+    pub error_message: String,
+}
+
+#[derive(Debug, Clone, Identifiable, Queryable, Selectable, QueryableByName, Serialize)]
+#[diesel(table_name = crate::info_schema::info::feed_event_failures)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct DbFeedEventFailure {
+    pub id: i64,
+    pub kind: String,
+    pub entity_id: String,
+    pub feed_event_index: i32,
+    pub valid_from: DateTime<Utc>,
+    pub raw_data: serde_json::Value,
+    pub error_message: String,
+    pub retry_count: i32,
+    pub first_failed_at: DateTime<Utc>,
+    pub last_attempt_at: DateTime<Utc>,
+}
+
 #[derive(Clone, Debug, Insertable, PartialEq)]
 #[diesel(table_name = crate::data_schema::data::efflorescence)]
 #[diesel(treat_none_as_default_value = false)]
@@ -1199,8 +1407,8 @@ pub struct DbEfflorescenceGrowth {
 pub struct NewPlayerPitchTypeVersion<'a> {
     pub mmolb_player_id: &'a str,
     pub pitch_type_index: i32,
-    pub valid_from: NaiveDateTime,
-    pub valid_until: Option<NaiveDateTime>,
+    pub valid_from: DateTime<Utc>,
+    pub valid_until: Option<DateTime<Utc>>,
     pub pitch_type: Option<i64>,
     pub frequency: f64,
     pub expect_full_precision: bool,
@@ -1213,8 +1421,8 @@ pub struct DbPlayerPitchTypeVersion {
     pub id: i64,
     pub mmolb_player_id: String,
     pub pitch_type_index: i32,
-    pub valid_from: NaiveDateTime,
-    pub valid_until: Option<NaiveDateTime>,
+    pub valid_from: DateTime<Utc>,
+    pub valid_until: Option<DateTime<Utc>>,
     pub pitch_type: Option<i64>,
     pub frequency: f64,
     pub expect_full_precision: bool,
@@ -1226,8 +1434,8 @@ pub struct DbPlayerPitchTypeVersion {
 pub struct NewPlayerPitchTypeBonusVersion<'a> {
     pub mmolb_player_id: &'a str,
     pub pitch_type: i64,
-    pub valid_from: NaiveDateTime,
-    pub valid_until: Option<NaiveDateTime>,
+    pub valid_from: DateTime<Utc>,
+    pub valid_until: Option<DateTime<Utc>>,
     pub bonus: f64,
 }
 
@@ -1238,8 +1446,8 @@ pub struct DbPlayerPitchTypeBonusVersion {
     pub id: i64,
     pub mmolb_player_id: String,
     pub pitch_type: i64,
-    pub valid_from: NaiveDateTime,
-    pub valid_until: Option<NaiveDateTime>,
+    pub valid_from: DateTime<Utc>,
+    pub valid_until: Option<DateTime<Utc>>,
     pub bonus: f64,
 }
 
@@ -1249,8 +1457,8 @@ pub struct DbPlayerPitchTypeBonusVersion {
 pub struct NewPlayerPitchCategoryBonusVersion<'a> {
     pub mmolb_player_id: &'a str,
     pub pitch_category: i64,
-    pub valid_from: NaiveDateTime,
-    pub valid_until: Option<NaiveDateTime>,
+    pub valid_from: DateTime<Utc>,
+    pub valid_until: Option<DateTime<Utc>>,
     pub bonus: f64,
 }
 
@@ -1261,8 +1469,8 @@ pub struct DbPlayerPitchCategoryBonusVersion {
     pub id: i64,
     pub mmolb_player_id: String,
     pub pitch_category: i64,
-    pub valid_from: NaiveDateTime,
-    pub valid_until: Option<NaiveDateTime>,
+    pub valid_from: DateTime<Utc>,
+    pub valid_until: Option<DateTime<Utc>>,
     pub bonus: f64,
 }
 
@@ -1272,7 +1480,7 @@ pub struct DbPlayerPitchCategoryBonusVersion {
 pub struct NewVersionProcessed<'a> {
     pub kind: &'a str,
     pub entity_id: &'a str,
-    pub valid_from: NaiveDateTime,
+    pub valid_from: DateTime<Utc>,
     pub skipped: bool,
     pub fatal_error: bool,
 }
@@ -1283,7 +1491,7 @@ pub struct NewVersionProcessed<'a> {
 pub struct DbVersionProcessed {
     pub kind: String,
     pub entity_id: String,
-    pub valid_from: NaiveDateTime,
+    pub valid_from: DateTime<Utc>,
     pub skipped: bool,
     pub fatal_error: bool,
 }
@@ -1352,13 +1560,111 @@ pub struct DbEventBalkReasons {
     pub balk_reason_id: i64,
 }
 
+#[derive(Clone, Debug, Insertable, PartialEq, Default, OneAu)]
+#[diesel(table_name = crate::data_schema::data::event_win_probability)]
+#[diesel(treat_none_as_default_value = false)]
+pub struct NewEventWinProbability {
+    pub event_id: i64,
+    pub home_win_probability_before: f64,
+    pub home_win_probability_after: f64,
+    pub batting_team_win_probability_added: f64,
+    pub leverage_index: f64,
+}
+
+#[derive(Debug, Clone, Identifiable, Queryable, Selectable, QueryableByName, Serialize)]
+#[diesel(table_name = crate::data_schema::data::event_win_probability)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct DbEventWinProbability {
+    pub id: i64,
+    pub event_id: i64,
+    pub home_win_probability_before: f64,
+    pub home_win_probability_after: f64,
+    pub batting_team_win_probability_added: f64,
+    pub leverage_index: f64,
+}
+
+#[derive(Clone, Debug, Insertable, PartialEq, Default, OneAu)]
+#[diesel(table_name = crate::data_schema::data::event_defense)]
+#[diesel(treat_none_as_default_value = false)]
+pub struct NewEventDefense<'a> {
+    pub event_id: i64,
+    pub catcher_name: Option<&'a str>,
+    pub first_base_name: Option<&'a str>,
+    pub second_base_name: Option<&'a str>,
+    pub third_base_name: Option<&'a str>,
+    pub shortstop_name: Option<&'a str>,
+    pub left_field_name: Option<&'a str>,
+    pub center_field_name: Option<&'a str>,
+    pub right_field_name: Option<&'a str>,
+}
+
+#[derive(Debug, Clone, Identifiable, Queryable, Selectable, QueryableByName, Serialize)]
+#[diesel(table_name = crate::data_schema::data::event_defense)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct DbEventDefense {
+    pub id: i64,
+    pub event_id: i64,
+    pub catcher_name: Option<String>,
+    pub first_base_name: Option<String>,
+    pub second_base_name: Option<String>,
+    pub third_base_name: Option<String>,
+    pub shortstop_name: Option<String>,
+    pub left_field_name: Option<String>,
+    pub center_field_name: Option<String>,
+    pub right_field_name: Option<String>,
+}
+
+#[derive(Clone, Debug, Insertable, PartialEq, Default, OneAu)]
+#[diesel(table_name = crate::data_schema::data::plate_appearances)]
+#[diesel(treat_none_as_default_value = false)]
+pub struct NewPlateAppearance {
+    pub event_id: i64,
+    pub pitcher_batters_faced_before: i32,
+    pub times_through_order: i32,
+    pub pitcher_pitches_before: i32,
+    pub pitches: i32,
+}
+
+#[derive(Debug, Clone, Identifiable, Queryable, Selectable, QueryableByName, Serialize)]
+#[diesel(table_name = crate::data_schema::data::plate_appearances)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct DbPlateAppearance {
+    pub id: i64,
+    pub event_id: i64,
+    pub pitcher_batters_faced_before: i32,
+    pub times_through_order: i32,
+    pub pitcher_pitches_before: i32,
+    pub pitches: i32,
+}
+
+#[derive(Clone, Debug, Insertable, PartialEq, Default, OneAu)]
+#[diesel(table_name = crate::data_schema::data::event_pitch_sequence)]
+#[diesel(treat_none_as_default_value = false)]
+pub struct NewEventPitchSequence {
+    pub event_id: i64,
+    pub pitch_of_plate_appearance: i32,
+    pub previous_pitch_event_id: Option<i64>,
+    pub count_before: Option<i64>,
+}
+
+#[derive(Debug, Clone, Identifiable, Queryable, Selectable, QueryableByName, Serialize)]
+#[diesel(table_name = crate::data_schema::data::event_pitch_sequence)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct DbEventPitchSequence {
+    pub id: i64,
+    pub event_id: i64,
+    pub pitch_of_plate_appearance: i32,
+    pub previous_pitch_event_id: Option<i64>,
+    pub count_before: Option<i64>,
+}
+
 #[derive(Clone, Debug, Insertable, PartialEq, Default, OneAu)]
 #[diesel(table_name = crate::data_schema::data::modification_effects)]
 #[diesel(treat_none_as_default_value = false, primary_key(modification_name, valid_from, attribute, effect_type))]
 pub struct NewModificationEffects<'a> {
     pub modification_name: &'a str,
-    pub valid_from: NaiveDateTime,
-    pub valid_until: Option<NaiveDateTime>,
+    pub valid_from: DateTime<Utc>,
+    pub valid_until: Option<DateTime<Utc>>,
     pub attribute: i64,
     pub effect_type: i64,
     pub value: f64,
@@ -1369,9 +1675,113 @@ pub struct NewModificationEffects<'a> {
 #[diesel(check_for_backend(diesel::pg::Pg), primary_key(modification_name, valid_from, attribute, effect_type))]
 pub struct DbModificationEffects {
     pub modification_name: String,
-    pub valid_from: NaiveDateTime,
-    pub valid_until: Option<NaiveDateTime>,
+    pub valid_from: DateTime<Utc>,
+    pub valid_until: Option<DateTime<Utc>>,
     pub attribute: i64,
     pub effect_type: i64,
     pub value: f64,
 }
+
+#[derive(Debug, Clone, Identifiable, Queryable, Selectable, Serialize)]
+#[diesel(table_name = crate::info_schema::info::data_freshness)]
+#[diesel(check_for_backend(diesel::pg::Pg), primary_key(kind))]
+pub struct DbDataFreshness {
+    pub kind: String,
+    pub last_valid_from: Option<DateTime<Utc>>,
+    pub last_ingest_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Identifiable, Queryable, Selectable, Serialize)]
+#[diesel(table_name = crate::data_schema::data::team_weekly_rollups)]
+#[diesel(check_for_backend(diesel::pg::Pg), primary_key(week_start, team_mmolb_id))]
+pub struct DbTeamWeeklyRollup {
+    pub week_start: NaiveDate,
+    pub team_mmolb_id: String,
+    pub games: i64,
+    pub wins: i64,
+    pub runs_scored: i64,
+    pub runs_allowed: i64,
+}
+
+#[derive(Debug, Clone, Identifiable, Queryable, Selectable, Serialize)]
+#[diesel(table_name = crate::data_schema::data::player_weekly_rollups)]
+#[diesel(check_for_backend(diesel::pg::Pg), primary_key(week_start, team_mmolb_id, batter_name))]
+pub struct DbPlayerWeeklyRollup {
+    pub week_start: NaiveDate,
+    pub team_mmolb_id: String,
+    pub batter_name: String,
+    pub plate_appearances: i64,
+    pub hits: i64,
+    pub home_runs: i64,
+    pub walks: i64,
+    pub strikeouts: i64,
+}
+
+#[derive(Debug, Clone, Identifiable, Queryable, Selectable)]
+#[diesel(table_name = crate::data_schema::data::attribute_distributions)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct DbAttributeDistribution {
+    pub id: i64,
+    pub season: i32,
+    pub week_start: NaiveDate,
+    pub attribute: i64,
+    pub num_players: i32,
+    pub mean: f64,
+    pub percentiles: Vec<Option<f64>>,
+    pub taken_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, Insertable, PartialEq)]
+#[diesel(table_name = crate::data_schema::data::season_phases)]
+#[diesel(treat_none_as_default_value = false)]
+pub struct NewSeasonPhase {
+    pub season: i32,
+    pub day_type: i64,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+    pub from_version: DateTime<Utc>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::info_schema::info::admin_audit)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct NewAdminAudit<'a> {
+    pub actor: &'a str,
+    pub action: &'a str,
+    pub details: serde_json::Value,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Identifiable, Queryable, Selectable, Serialize)]
+#[diesel(table_name = crate::info_schema::info::admin_audit)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct DbAdminAudit {
+    pub id: i64,
+    pub at: DateTime<Utc>,
+    pub actor: String,
+    pub action: String,
+    pub details: serde_json::Value,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::info_schema::info::chron_anomalies)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct NewChronAnomaly<'a> {
+    pub kind: &'a str,
+    pub url: &'a str,
+    pub page: Option<&'a str>,
+    pub description: &'a str,
+    pub resolved_by_refetch: bool,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::info_schema::info::slow_queries)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct NewSlowQuery<'a> {
+    pub duration_seconds: f64,
+    pub query: &'a str,
+    pub error: Option<&'a str>,
+}
@@ -0,0 +1,143 @@
+//! A client for the S3-compatible object storage that old games' raw data is
+//! offloaded to. mmoldb-ingest moves games there, and both it and
+//! mmoldb-app fetch them back.
+
+use s3::creds::Credentials;
+use s3::error::S3Error;
+use s3::{Bucket, Region};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Debug, Formatter};
+use std::time::Duration;
+use thiserror::Error;
+
+const OBJECT_STORE_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Error)]
+pub enum ObjectStoreError {
+    #[error("invalid object storage config: {message}")]
+    InvalidConfig { message: String },
+
+    #[error("object storage request for {key:?} failed")]
+    Request {
+        key: String,
+        #[source]
+        source: S3Error,
+    },
+
+    #[error("couldn't (de)serialize stored object {key:?}")]
+    Json {
+        key: String,
+        #[source]
+        source: serde_json::Error,
+    },
+}
+
+/// Where to find an S3-compatible bucket, and how to sign in to it
+#[derive(PartialEq, Deserialize, Serialize)]
+pub struct ObjectStoreConfig {
+    /// e.g. `https://s3.us-east-1.amazonaws.com` or the URL of a MinIO
+    /// server. Buckets are addressed path-style under this URL.
+    pub endpoint: String,
+    pub region: String,
+    pub bucket: String,
+    /// Prepended to every object key, e.g. `"mmoldb/"`
+    pub key_prefix: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+}
+
+// Written by hand to keep the secret out of logs
+impl Debug for ObjectStoreConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ObjectStoreConfig")
+            .field("endpoint", &self.endpoint)
+            .field("region", &self.region)
+            .field("bucket", &self.bucket)
+            .field("key_prefix", &self.key_prefix)
+            .field("access_key_id", &self.access_key_id)
+            .field("secret_access_key", &"<redacted>")
+            .finish()
+    }
+}
+
+/// A bucket in S3 or anything that speaks its API (MinIO, R2, B2, ...).
+/// Buckets are addressed path-style, which every S3-compatible service
+/// accepts. Requests block, so async code must make them from a blocking
+/// thread.
+pub struct ObjectStore {
+    bucket: Box<Bucket>,
+    key_prefix: String,
+}
+
+// Written by hand to keep the credentials out of logs
+impl Debug for ObjectStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ObjectStore")
+            .field("bucket", &self.bucket.name())
+            .field("key_prefix", &self.key_prefix)
+            .finish()
+    }
+}
+
+impl ObjectStore {
+    pub fn new(config: &ObjectStoreConfig) -> Result<Self, ObjectStoreError> {
+        let region = Region::Custom {
+            region: config.region.clone(),
+            endpoint: config.endpoint.clone(),
+        };
+        let credentials = Credentials::new(
+            Some(&config.access_key_id),
+            Some(&config.secret_access_key),
+            None,
+            None,
+            None,
+        )
+        .map_err(|err| ObjectStoreError::InvalidConfig {
+            message: err.to_string(),
+        })?;
+        let mut bucket = Bucket::new(&config.bucket, region, credentials)
+            .map_err(|err| ObjectStoreError::InvalidConfig {
+                message: err.to_string(),
+            })?
+            .with_path_style();
+        bucket.set_request_timeout(Some(OBJECT_STORE_TIMEOUT));
+
+        Ok(Self {
+            bucket,
+            key_prefix: config.key_prefix.clone(),
+        })
+    }
+
+    pub fn key_prefix(&self) -> &str {
+        &self.key_prefix
+    }
+
+    pub fn upload_json(&self, key: &str, data: &serde_json::Value) -> Result<(), ObjectStoreError> {
+        let body = serde_json::to_vec(data).map_err(|source| ObjectStoreError::Json {
+            key: key.to_string(),
+            source,
+        })?;
+        self.bucket
+            .put_object_with_content_type(key, &body, "application/json")
+            .map_err(|source| ObjectStoreError::Request {
+                key: key.to_string(),
+                source,
+            })?;
+
+        Ok(())
+    }
+
+    pub fn download_json(&self, key: &str) -> Result<serde_json::Value, ObjectStoreError> {
+        let response = self
+            .bucket
+            .get_object(key)
+            .map_err(|source| ObjectStoreError::Request {
+                key: key.to_string(),
+                source,
+            })?;
+        serde_json::from_slice(response.bytes()).map_err(|source| ObjectStoreError::Json {
+            key: key.to_string(),
+            source,
+        })
+    }
+}
@@ -1,10 +1,551 @@
-use diesel::PgConnection;
-use diesel::r2d2::{ConnectionManager, Pool};
+use crate::models::NewSlowQuery;
+use diesel::connection::{Instrumentation, InstrumentationEvent};
+use diesel::r2d2::event::{
+    AcquireEvent, CheckinEvent, CheckoutEvent, HandleEvent, ReleaseEvent, TimeoutEvent,
+};
+use diesel::r2d2::{ConnectionManager, CustomizeConnection, Pool};
+use diesel::{Connection, PgConnection};
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
 
 pub type ConnectionPool = Pool<ConnectionManager<PgConnection>>;
 pub type PoolError = diesel::r2d2::PoolError;
 pub fn get_pool(max_size: u32) -> Result<ConnectionPool, PoolError> {
+    let (pool, _) = build_pool(max_size, &PoolHealthConfig::default(), None)?;
+    Ok(pool)
+}
+
+fn build_pool(
+    max_size: u32,
+    config: &PoolHealthConfig,
+    query_log: Option<QueryLogCustomizer>,
+) -> Result<(ConnectionPool, PoolHealthReporter), PoolError> {
     let manager = ConnectionManager::new(crate::postgres_url_from_environment());
+    let counters = Arc::new(PoolEventCounters::default());
+    let pool = Pool::builder()
+        .max_size(max_size)
+        .max_lifetime(config.max_connection_lifetime_secs.map(Duration::from_secs))
+        .event_handler(Box::new(PoolHealthMonitor {
+            slow_checkout_threshold: config.slow_checkout_threshold_ms.map(Duration::from_millis),
+            counters: counters.clone(),
+        }))
+        .connection_customizer(Box::new(PoolCustomizer { query_log }))
+        .build(manager)?;
+
+    if let Some(stalled_after) = config.stalled_checkout_secs {
+        spawn_stalled_connection_reaper(
+            Arc::downgrade(&counters),
+            Duration::from_secs(stalled_after),
+        );
+    }
+
+    let reporter = PoolHealthReporter {
+        pool: pool.clone(),
+        counters,
+    };
+    Ok((pool, reporter))
+}
+
+/// Connection recycling and health reporting for a pool
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct PoolHealthConfig {
+    /// Idle connections are closed and replaced once they're this old. A
+    /// connection that's in use is closed when it's returned to the pool.
+    pub max_connection_lifetime_secs: Option<u64>,
+    /// A connection that's checked out of the pool but hasn't started or
+    /// finished a query in this long is assumed to be wedged. Its session
+    /// is terminated, so whatever holds it gets an error the next time it
+    /// uses it, and the pool replaces it when it's returned. Connections
+    /// that are running a query are never terminated.
+    pub stalled_checkout_secs: Option<u64>,
+    /// Waiting at least this long for a connection is logged at warn level
+    pub slow_checkout_threshold_ms: Option<u64>,
+    /// How often to log the pool's health. Not used by [`get_pool`], only by
+    /// callers that report health themselves.
+    pub report_interval_secs: Option<u64>,
+}
+
+impl Default for PoolHealthConfig {
+    fn default() -> Self {
+        Self {
+            max_connection_lifetime_secs: Some(30 * 60),
+            stalled_checkout_secs: Some(60 * 60),
+            slow_checkout_threshold_ms: Some(5_000),
+            report_interval_secs: Some(5 * 60),
+        }
+    }
+}
+
+/// What's known about one of a pool's open connections
+#[derive(Debug)]
+struct PooledConnectionInfo {
+    opened_at: Instant,
+    /// None if the backend's pid couldn't be looked up when it was opened
+    backend_pid: Option<i32>,
+    checked_out_at: Option<Instant>,
+}
+
+/// Counters for one pool
+#[derive(Debug, Default)]
+struct PoolEventCounters {
+    checkouts: AtomicU64,
+    checkout_wait_micros: AtomicU64,
+    timeouts: AtomicU64,
+    connections_opened: AtomicU64,
+    connections_closed: AtomicU64,
+    // Keyed by r2d2's connection id
+    connections: Mutex<HashMap<u64, PooledConnectionInfo>>,
+}
+
+impl PoolEventCounters {
+    /// The backend pids of connections that have been checked out for at
+    /// least `duration`
+    fn backends_checked_out_for(&self, duration: Duration) -> Vec<i32> {
+        self.connections
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|info| {
+                info.checked_out_at
+                    .is_some_and(|at| at.elapsed() >= duration)
+            })
+            .filter_map(|info| info.backend_pid)
+            .collect()
+    }
+}
+
+thread_local! {
+    // The customizer sees a new connection but not its id, and the event
+    // handler sees its id but not the connection. r2d2 calls one right
+    // after the other on the same thread, so this carries the connection's
+    // backend pid from one to the other.
+    static ACQUIRED_BACKEND_PID: Cell<Option<i32>> = const { Cell::new(None) };
+}
+
+#[derive(Debug)]
+struct PoolHealthMonitor {
+    slow_checkout_threshold: Option<Duration>,
+    counters: Arc<PoolEventCounters>,
+}
+
+impl HandleEvent for PoolHealthMonitor {
+    fn handle_acquire(&self, event: AcquireEvent) {
+        self.counters
+            .connections_opened
+            .fetch_add(1, Ordering::Relaxed);
+        let info = PooledConnectionInfo {
+            opened_at: Instant::now(),
+            backend_pid: ACQUIRED_BACKEND_PID.take(),
+            checked_out_at: None,
+        };
+        let mut connections = self.counters.connections.lock().unwrap();
+        connections.insert(event.connection_id(), info);
+    }
+
+    fn handle_release(&self, event: ReleaseEvent) {
+        self.counters
+            .connections_closed
+            .fetch_add(1, Ordering::Relaxed);
+        let mut connections = self.counters.connections.lock().unwrap();
+        connections.remove(&event.connection_id());
+        debug!(
+            "Closed database connection {} after {:.0}s",
+            event.connection_id(),
+            event.age().as_secs_f64(),
+        );
+    }
+
+    fn handle_checkout(&self, event: CheckoutEvent) {
+        let wait = event.duration();
+        self.counters.checkouts.fetch_add(1, Ordering::Relaxed);
+        self.counters
+            .checkout_wait_micros
+            .fetch_add(wait.as_micros() as u64, Ordering::Relaxed);
+        if let Some(info) = self
+            .counters
+            .connections
+            .lock()
+            .unwrap()
+            .get_mut(&event.connection_id())
+        {
+            info.checked_out_at = Some(Instant::now());
+        }
+        if self
+            .slow_checkout_threshold
+            .is_some_and(|threshold| wait >= threshold)
+        {
+            warn!(
+                "Waited {:.3}s for a database connection. The pool may be saturated.",
+                wait.as_secs_f64(),
+            );
+        }
+    }
+
+    fn handle_timeout(&self, event: TimeoutEvent) {
+        self.counters.timeouts.fetch_add(1, Ordering::Relaxed);
+        warn!(
+            "Timed out after {:.3}s waiting for a database connection",
+            event.timeout().as_secs_f64(),
+        );
+    }
+
+    fn handle_checkin(&self, event: CheckinEvent) {
+        if let Some(info) = self
+            .counters
+            .connections
+            .lock()
+            .unwrap()
+            .get_mut(&event.connection_id())
+        {
+            info.checked_out_at = None;
+        }
+    }
+}
+
+/// A snapshot of a pool's health. The counts are totals since the pool was
+/// created, so the difference between two snapshots covers the time between
+/// them.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PoolHealth {
+    pub max_size: u32,
+    /// Open connections, both idle and in use
+    pub connections: u32,
+    pub idle_connections: u32,
+    /// How long the oldest open connection has been open
+    pub oldest_connection_age: Option<Duration>,
+    /// How long the connection that's been checked out the longest has been
+    /// checked out
+    pub longest_checkout: Option<Duration>,
+    pub checkouts: u64,
+    /// Total time spent waiting for connections
+    pub checkout_wait: Duration,
+    /// Times a caller gave up waiting for a connection
+    pub timeouts: u64,
+    pub connections_opened: u64,
+    pub connections_closed: u64,
+}
+
+impl PoolHealth {
+    pub fn in_use_connections(&self) -> u32 {
+        self.connections - self.idle_connections
+    }
+
+    /// The fraction of the pool's capacity that's in use, from 0 to 1
+    pub fn saturation(&self) -> f64 {
+        if self.max_size == 0 {
+            return 0.0;
+        }
+        self.in_use_connections() as f64 / self.max_size as f64
+    }
+
+    /// The average time each checkout waited for a connection since
+    /// `earlier` was taken
+    pub fn mean_checkout_wait_since(&self, earlier: &PoolHealth) -> Option<Duration> {
+        let checkouts = self.checkouts.checked_sub(earlier.checkouts)?;
+        let wait = self.checkout_wait.checked_sub(earlier.checkout_wait)?;
+        (checkouts > 0).then(|| Duration::from_secs_f64(wait.as_secs_f64() / checkouts as f64))
+    }
+}
+
+/// Reports the health of the pool it was built with
+#[derive(Debug, Clone)]
+pub struct PoolHealthReporter {
+    pool: ConnectionPool,
+    counters: Arc<PoolEventCounters>,
+}
+
+impl PoolHealthReporter {
+    pub fn health(&self) -> PoolHealth {
+        let state = self.pool.state();
+        let (oldest_connection_age, longest_checkout) = {
+            let connections = self.counters.connections.lock().unwrap();
+            let oldest = connections.values().map(|info| info.opened_at).min();
+            let longest = connections
+                .values()
+                .filter_map(|info| info.checked_out_at)
+                .min();
+            (
+                oldest.map(|at| at.elapsed()),
+                longest.map(|at| at.elapsed()),
+            )
+        };
+
+        PoolHealth {
+            max_size: self.pool.max_size(),
+            connections: state.connections,
+            idle_connections: state.idle_connections,
+            oldest_connection_age,
+            longest_checkout,
+            checkouts: self.counters.checkouts.load(Ordering::Relaxed),
+            checkout_wait: Duration::from_micros(
+                self.counters.checkout_wait_micros.load(Ordering::Relaxed),
+            ),
+            timeouts: self.counters.timeouts.load(Ordering::Relaxed),
+            connections_opened: self.counters.connections_opened.load(Ordering::Relaxed),
+            connections_closed: self.counters.connections_closed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// r2d2 only ever retires idle connections, so one that's wedged while it's
+/// checked out would be held forever. This watches for connections that
+/// have been checked out for `stalled_after` and terminates the sessions of
+/// the ones that haven't run a query in that long. Terminating is done on a
+/// separate connection, because the pool may be exhausted by then.
+fn spawn_stalled_connection_reaper(counters: Weak<PoolEventCounters>, stalled_after: Duration) {
+    std::thread::Builder::new()
+        .name("stalled-connection-reaper".to_string())
+        .spawn(move || reap_stalled_connections(counters, stalled_after))
+        .expect("failed to spawn stalled connection reaper thread");
+}
+
+fn reap_stalled_connections(counters: Weak<PoolEventCounters>, stalled_after: Duration) {
+    let url = crate::postgres_url_from_environment();
+    let mut conn: Option<PgConnection> = None;
+    let check_interval = (stalled_after / 4).clamp(Duration::from_secs(1), Duration::from_secs(60));
+
+    loop {
+        std::thread::sleep(check_interval);
+        // Exits once the pool (and with it every reporter) is dropped
+        let Some(counters) = counters.upgrade() else {
+            return;
+        };
+        let suspects = counters.backends_checked_out_for(stalled_after);
+        drop(counters);
+        if suspects.is_empty() {
+            continue;
+        }
+
+        let reaper_conn = match &mut conn {
+            Some(conn) => conn,
+            None => match PgConnection::establish(&url) {
+                Ok(new_conn) => conn.insert(new_conn),
+                Err(err) => {
+                    warn!("Couldn't connect to check for stalled database connections: {err}");
+                    continue;
+                }
+            },
+        };
+
+        match crate::db::terminate_stalled_backends(
+            reaper_conn,
+            &suspects,
+            stalled_after.as_secs_f64(),
+        ) {
+            Ok(terminated) => {
+                for pid in terminated {
+                    warn!(
+                        "Terminated database session {pid}, which was checked out of the pool \
+                        and hadn't run a query in {:.0}s",
+                        stalled_after.as_secs_f64(),
+                    );
+                }
+            }
+            Err(err) => {
+                warn!("Couldn't terminate stalled database connections: {err}");
+                // Reconnect next time, in case it was this connection that broke
+                conn = None;
+            }
+        }
+    }
+}
+
+/// How many slow queries can be waiting to be written before new ones are
+/// dropped
+const SLOW_QUERY_BUFFER_SIZE: usize = 1000;
+/// Longer statements (usually big inserts with all their binds) are cut off
+/// at this many characters before they're logged or stored
+const MAX_CAPTURED_QUERY_CHARS: usize = 10_000;
+
+/// Opt-in instrumentation for every connection in a pool
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct QueryLogConfig {
+    /// Log every statement and how long it took, at debug level
+    pub log_all_queries: bool,
+    /// Statements that take at least this long are logged at warn level and
+    /// recorded in `info.slow_queries`
+    pub slow_query_threshold_ms: Option<u64>,
+}
+
+impl QueryLogConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.log_all_queries || self.slow_query_threshold_ms.is_some()
+    }
+}
+
+/// Like [`get_pool`], but every connection logs its queries according to
+/// `config`, and connections are recycled according to `health_config`.
+/// Also returns a reporter for the pool's health.
+pub fn get_pool_with_query_log(
+    max_size: u32,
+    config: &QueryLogConfig,
+    health_config: &PoolHealthConfig,
+) -> Result<(ConnectionPool, PoolHealthReporter), PoolError> {
+    let query_log = config.is_enabled().then(|| QueryLogCustomizer {
+        config: config.clone(),
+        slow_queries: config
+            .slow_query_threshold_ms
+            .map(|_| spawn_slow_query_writer()),
+    });
+    build_pool(max_size, health_config, query_log)
+}
+
+#[derive(Debug)]
+struct CapturedQuery {
+    duration: Duration,
+    query: String,
+    error: Option<String>,
+}
+
+#[derive(Debug)]
+struct QueryLogCustomizer {
+    config: QueryLogConfig,
+    slow_queries: Option<SyncSender<CapturedQuery>>,
+}
+
+impl QueryLogCustomizer {
+    fn instrument(&self, conn: &mut PgConnection) {
+        conn.set_instrumentation(QueryLogger {
+            log_all_queries: self.config.log_all_queries,
+            slow_query_threshold: self
+                .config
+                .slow_query_threshold_ms
+                .map(Duration::from_millis),
+            slow_queries: self.slow_queries.clone(),
+            started_at: None,
+        });
+    }
+}
+
+#[derive(Debug)]
+struct PoolCustomizer {
+    query_log: Option<QueryLogCustomizer>,
+}
+
+impl CustomizeConnection<PgConnection, diesel::r2d2::Error> for PoolCustomizer {
+    fn on_acquire(&self, conn: &mut PgConnection) -> Result<(), diesel::r2d2::Error> {
+        // Looked up before the query log is attached, so it isn't logged.
+        // Without it the connection can't be terminated if it stalls, which
+        // isn't worth refusing the connection over.
+        let backend_pid = crate::db::backend_pid(conn)
+            .inspect_err(|err| warn!("Couldn't get a new connection's backend pid: {err}"))
+            .ok();
+        ACQUIRED_BACKEND_PID.set(backend_pid);
+
+        if let Some(query_log) = &self.query_log {
+            query_log.instrument(conn);
+        }
+        Ok(())
+    }
+}
+
+struct QueryLogger {
+    log_all_queries: bool,
+    slow_query_threshold: Option<Duration>,
+    slow_queries: Option<SyncSender<CapturedQuery>>,
+    // A connection only runs one statement at a time, so there's only ever
+    // one start time to keep track of
+    started_at: Option<Instant>,
+}
+
+fn truncate_query(mut query: String) -> String {
+    if let Some((cutoff, _)) = query.char_indices().nth(MAX_CAPTURED_QUERY_CHARS) {
+        query.truncate(cutoff);
+        query.push_str("...");
+    }
+    query
+}
+
+impl Instrumentation for QueryLogger {
+    fn on_connection_event(&mut self, event: InstrumentationEvent<'_>) {
+        match event {
+            InstrumentationEvent::StartQuery { .. } => {
+                self.started_at = Some(Instant::now());
+            }
+            InstrumentationEvent::FinishQuery { query, error, .. } => {
+                let Some(started_at) = self.started_at.take() else {
+                    return;
+                };
+                let duration = started_at.elapsed();
+                let is_slow = self
+                    .slow_query_threshold
+                    .is_some_and(|threshold| duration >= threshold);
+                if !is_slow && !self.log_all_queries {
+                    return;
+                }
+
+                let query = truncate_query(query.to_string());
+                if !is_slow {
+                    debug!("Query took {:.3}s: {query}", duration.as_secs_f64());
+                    return;
+                }
+
+                warn!("Slow query took {:.3}s: {query}", duration.as_secs_f64());
+                if let Some(slow_queries) = &self.slow_queries {
+                    let captured = CapturedQuery {
+                        duration,
+                        query,
+                        error: error.map(|e| e.to_string()),
+                    };
+                    match slow_queries.try_send(captured) {
+                        Ok(()) => {}
+                        Err(TrySendError::Full(_)) => {
+                            debug!("Slow query buffer is full. Dropping a slow query.");
+                        }
+                        Err(TrySendError::Disconnected(_)) => {
+                            debug!("Slow query writer has exited. Dropping a slow query.");
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Slow queries are written from a separate thread on a separate,
+/// uninstrumented connection. Recording them on the connection that ran them
+/// isn't possible from inside the instrumentation, and would be captured
+/// again if the insert were itself slow.
+fn spawn_slow_query_writer() -> SyncSender<CapturedQuery> {
+    let (sender, receiver) = std::sync::mpsc::sync_channel(SLOW_QUERY_BUFFER_SIZE);
+    std::thread::Builder::new()
+        .name("slow-query-writer".to_string())
+        .spawn(move || write_slow_queries(receiver))
+        .expect("failed to spawn slow query writer thread");
+    sender
+}
+
+fn write_slow_queries(receiver: Receiver<CapturedQuery>) {
+    let url = crate::postgres_url_from_environment();
+    let mut conn: Option<PgConnection> = None;
+
+    // Exits once the pool (and with it every sender) is dropped
+    for captured in receiver {
+        let conn = match &mut conn {
+            Some(conn) => conn,
+            None => match PgConnection::establish(&url) {
+                Ok(new_conn) => conn.insert(new_conn),
+                Err(err) => {
+                    warn!("Couldn't connect to record a slow query: {err}");
+                    continue;
+                }
+            },
+        };
 
-    Pool::builder().max_size(max_size).build(manager)
+        let new_query = NewSlowQuery {
+            duration_seconds: captured.duration.as_secs_f64(),
+            query: &captured.query,
+            error: captured.error.as_deref(),
+        };
+        if let Err(err) = crate::db::insert_slow_query(conn, &new_query) {
+            warn!("Couldn't record a slow query: {err}");
+        }
+    }
 }
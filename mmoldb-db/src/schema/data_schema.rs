@@ -1,6 +1,19 @@
 // @generated automatically by Diesel CLI.
 
 pub mod data {
+    diesel::table! {
+        data.attribute_distributions (id) {
+            id -> Int8,
+            season -> Int4,
+            week_start -> Date,
+            attribute -> Int8,
+            num_players -> Int4,
+            mean -> Float8,
+            percentiles -> Array<Nullable<Float8>>,
+            taken_at -> Timestamptz,
+        }
+    }
+
     diesel::table! {
         data.aurora_photos (id) {
             id -> Int8,
@@ -64,6 +77,19 @@ pub mod data {
         }
     }
 
+    diesel::table! {
+        // These have no primary key. Their unique key stands in for it.
+        data.defense_outcomes (season, team_mmolb_id, fair_ball_direction, event_type, hit_base, fielding_error_type) {
+            season -> Int4,
+            team_mmolb_id -> Text,
+            fair_ball_direction -> Nullable<Int8>,
+            event_type -> Int8,
+            hit_base -> Nullable<Int8>,
+            fielding_error_type -> Nullable<Int8>,
+            count -> Int8,
+        }
+    }
+
     diesel::table! {
         data.door_prize_items (id) {
             id -> Int8,
@@ -135,8 +161,10 @@ pub mod data {
         data.entities (kind, entity_id) {
             kind -> Text,
             entity_id -> Text,
-            valid_from -> Timestamp,
+            valid_from -> Timestamptz,
             data -> Jsonb,
+            offloaded_to -> Nullable<Text>,
+            source -> Nullable<Text>,
         }
     }
 
@@ -173,6 +201,21 @@ pub mod data {
         }
     }
 
+    diesel::table! {
+        data.event_defense (id) {
+            id -> Int8,
+            event_id -> Int8,
+            catcher_name -> Nullable<Text>,
+            first_base_name -> Nullable<Text>,
+            second_base_name -> Nullable<Text>,
+            third_base_name -> Nullable<Text>,
+            shortstop_name -> Nullable<Text>,
+            left_field_name -> Nullable<Text>,
+            center_field_name -> Nullable<Text>,
+            right_field_name -> Nullable<Text>,
+        }
+    }
+
     diesel::table! {
         data.event_fielders (id) {
             id -> Int8,
@@ -182,6 +225,29 @@ pub mod data {
             play_order -> Int4,
             was_double_trouble -> Nullable<Bool>,
             used_jetpack -> Nullable<Bool>,
+            fielder_mmolb_id -> Nullable<Text>,
+            fielder_identity_confident -> Nullable<Bool>,
+        }
+    }
+
+    diesel::table! {
+        data.event_pitch_sequence (id) {
+            id -> Int8,
+            event_id -> Int8,
+            pitch_of_plate_appearance -> Int4,
+            previous_pitch_event_id -> Nullable<Int8>,
+            count_before -> Nullable<Int8>,
+        }
+    }
+
+    diesel::table! {
+        data.event_win_probability (id) {
+            id -> Int8,
+            event_id -> Int8,
+            home_win_probability_before -> Float8,
+            home_win_probability_after -> Float8,
+            batting_team_win_probability_added -> Float8,
+            leverage_index -> Float8,
         }
     }
 
@@ -221,6 +287,10 @@ pub mod data {
             fair_ball_fielder_name -> Nullable<Text>,
             home_run_distance -> Nullable<Int4>,
             is_surprise_strike -> Nullable<Bool>,
+            batter_mmolb_id -> Nullable<Bytea>,
+            batter_identity_confident -> Nullable<Bool>,
+            pitcher_mmolb_id -> Nullable<Bytea>,
+            pitcher_identity_confident -> Nullable<Bool>,
         }
     }
 
@@ -233,14 +303,29 @@ pub mod data {
         }
     }
 
+    // text_search is left out. It's generated from text, so it can't be
+    // inserted or copied into, and diesel has no tsvector type.
+    diesel::table! {
+        data.feed_event_texts (kind, entity_id, feed_event_index) {
+            kind -> Text,
+            entity_id -> Text,
+            feed_event_index -> Int4,
+            valid_from -> Timestamptz,
+            season -> Int4,
+            time -> Timestamptz,
+            text -> Text,
+        }
+    }
+
     diesel::table! {
         data.feed_event_versions (kind, entity_id, feed_event_index, valid_from) {
             kind -> Text,
             entity_id -> Text,
             feed_event_index -> Int4,
-            valid_from -> Timestamp,
-            valid_until -> Nullable<Timestamp>,
+            valid_from -> Timestamptz,
+            valid_until -> Nullable<Timestamptz>,
             data -> Jsonb,
+            source -> Nullable<Text>,
         }
     }
 
@@ -249,12 +334,24 @@ pub mod data {
             kind -> Text,
             entity_id -> Text,
             feed_event_index -> Int4,
-            valid_from -> Timestamp,
+            valid_from -> Timestamptz,
             skipped -> Bool,
             fatal_error -> Bool,
         }
     }
 
+    diesel::table! {
+        data.game_innings (id) {
+            id -> Int8,
+            game_id -> Int8,
+            inning -> Int4,
+            top_of_inning -> Bool,
+            runs -> Int4,
+            hits -> Int4,
+            errors -> Int4,
+        }
+    }
+
     diesel::table! {
         data.games (id) {
             id -> Int8,
@@ -282,14 +379,17 @@ pub mod data {
             away_team_photo_contest_score -> Nullable<Int4>,
             away_manager_name -> Nullable<Text>,
             home_manager_name -> Nullable<Text>,
+            started_at -> Timestamptz,
+            mmolb_stadium_id -> Nullable<Text>,
+            extra -> Nullable<Jsonb>,
         }
     }
 
     diesel::table! {
         data.modification_effects (modification_name, valid_from, attribute, effect_type) {
             modification_name -> Text,
-            valid_from -> Timestamp,
-            valid_until -> Nullable<Timestamp>,
+            valid_from -> Timestamptz,
+            valid_until -> Nullable<Timestamptz>,
             attribute -> Int8,
             effect_type -> Int8,
             value -> Float8,
@@ -305,6 +405,19 @@ pub mod data {
         }
     }
 
+    diesel::table! {
+        // These have no primary key. Their unique key stands in for it.
+        data.offense_outcomes (season, team_mmolb_id, fair_ball_direction, event_type, hit_base, fielding_error_type) {
+            season -> Int4,
+            team_mmolb_id -> Text,
+            fair_ball_direction -> Nullable<Int8>,
+            event_type -> Int8,
+            hit_base -> Nullable<Int8>,
+            fielding_error_type -> Nullable<Int8>,
+            count -> Int8,
+        }
+    }
+
     diesel::table! {
         data.parties (id) {
             id -> Int8,
@@ -319,6 +432,23 @@ pub mod data {
         }
     }
 
+    diesel::table! {
+        data.pitcher_appearances (id) {
+            id -> Int8,
+            game_id -> Int8,
+            is_home_team -> Bool,
+            pitcher_name -> Text,
+            pitcher_count -> Int4,
+            first_game_event_index -> Int4,
+            last_game_event_index -> Int4,
+            pitches -> Int4,
+            batters_faced -> Int4,
+            outs_recorded -> Int4,
+            replaced_by_source -> Nullable<Int8>,
+            replaced_by_name -> Nullable<Text>,
+        }
+    }
+
     diesel::table! {
         data.pitcher_changes (id) {
             id -> Int8,
@@ -336,6 +466,17 @@ pub mod data {
         }
     }
 
+    diesel::table! {
+        data.plate_appearances (id) {
+            id -> Int8,
+            event_id -> Int8,
+            pitcher_batters_faced_before -> Int4,
+            times_through_order -> Int4,
+            pitcher_pitches_before -> Int4,
+            pitches -> Int4,
+        }
+    }
+
     diesel::table! {
         data.player_attribute_augments (id) {
             id -> Int8,
@@ -348,6 +489,7 @@ pub mod data {
             day_type -> Nullable<Int8>,
             day -> Nullable<Int4>,
             superstar_day -> Nullable<Int4>,
+            feed_event_kind -> Nullable<Text>,
         }
     }
 
@@ -357,8 +499,8 @@ pub mod data {
             mmolb_player_id -> Text,
             equipment_slot -> Text,
             effect_index -> Int4,
-            valid_from -> Timestamp,
-            valid_until -> Nullable<Timestamp>,
+            valid_from -> Timestamptz,
+            valid_until -> Nullable<Timestamptz>,
             duplicates -> Int4,
             attribute -> Int8,
             effect_type -> Int8,
@@ -375,8 +517,8 @@ pub mod data {
             id -> Int8,
             mmolb_player_id -> Text,
             equipment_slot -> Text,
-            valid_from -> Timestamp,
-            valid_until -> Nullable<Timestamp>,
+            valid_from -> Timestamptz,
+            valid_until -> Nullable<Timestamptz>,
             duplicates -> Int4,
             emoji -> Text,
             name -> Text,
@@ -395,14 +537,26 @@ pub mod data {
         }
     }
 
+    diesel::table! {
+        data.player_identity_resolution (id) {
+            id -> Int8,
+            game_id -> Int8,
+            mmolb_team_id -> Text,
+            player_name -> Text,
+            role -> Text,
+            mmolb_player_id -> Nullable<Text>,
+            num_candidates -> Int4,
+        }
+    }
+
     diesel::table! {
         data.player_modification_versions (id) {
             id -> Int8,
             mmolb_player_id -> Text,
             modification_type -> Int8,
             modification_index -> Int4,
-            valid_from -> Timestamp,
-            valid_until -> Nullable<Timestamp>,
+            valid_from -> Timestamptz,
+            valid_until -> Nullable<Timestamptz>,
             duplicates -> Int4,
             modification_id -> Int8,
         }
@@ -427,8 +581,8 @@ pub mod data {
             id -> Int8,
             mmolb_player_id -> Text,
             pitch_category -> Int8,
-            valid_from -> Timestamp,
-            valid_until -> Nullable<Timestamp>,
+            valid_from -> Timestamptz,
+            valid_until -> Nullable<Timestamptz>,
             duplicates -> Int4,
             bonus -> Float8,
         }
@@ -439,8 +593,8 @@ pub mod data {
             id -> Int8,
             mmolb_player_id -> Text,
             pitch_type -> Int8,
-            valid_from -> Timestamp,
-            valid_until -> Nullable<Timestamp>,
+            valid_from -> Timestamptz,
+            valid_until -> Nullable<Timestamptz>,
             duplicates -> Int4,
             bonus -> Float8,
         }
@@ -451,8 +605,8 @@ pub mod data {
             id -> Int8,
             mmolb_player_id -> Text,
             pitch_type_index -> Int4,
-            valid_from -> Timestamp,
-            valid_until -> Nullable<Timestamp>,
+            valid_from -> Timestamptz,
+            valid_until -> Nullable<Timestamptz>,
             duplicates -> Int4,
             pitch_type -> Nullable<Int8>,
             frequency -> Float8,
@@ -483,8 +637,8 @@ pub mod data {
             mmolb_player_id -> Text,
             category -> Int8,
             attribute -> Int8,
-            valid_from -> Timestamp,
-            valid_until -> Nullable<Timestamp>,
+            valid_from -> Timestamptz,
+            valid_until -> Nullable<Timestamptz>,
             base_stars -> Nullable<Int4>,
             base_total -> Nullable<Float8>,
             base_subtotal -> Nullable<Float8>,
@@ -498,8 +652,8 @@ pub mod data {
             id -> Int8,
             mmolb_player_id -> Text,
             category -> Int8,
-            valid_from -> Timestamp,
-            valid_until -> Nullable<Timestamp>,
+            valid_from -> Timestamptz,
+            valid_until -> Nullable<Timestamptz>,
             season -> Nullable<Int4>,
             day_type -> Nullable<Int8>,
             day -> Nullable<Int4>,
@@ -513,8 +667,8 @@ pub mod data {
         data.player_versions (id) {
             id -> Int8,
             mmolb_player_id -> Text,
-            valid_from -> Timestamp,
-            valid_until -> Nullable<Timestamp>,
+            valid_from -> Timestamptz,
+            valid_until -> Nullable<Timestamptz>,
             duplicates -> Int4,
             first_name -> Text,
             last_name -> Text,
@@ -548,6 +702,90 @@ pub mod data {
         }
     }
 
+    diesel::table! {
+        data.player_weekly_rollups (week_start, team_mmolb_id, batter_name) {
+            week_start -> Date,
+            team_mmolb_id -> Text,
+            batter_name -> Text,
+            plate_appearances -> Int8,
+            hits -> Int8,
+            home_runs -> Int8,
+            walks -> Int8,
+            strikeouts -> Int8,
+        }
+    }
+
+    diesel::table! {
+        data.season_awards (id) {
+            id -> Int8,
+            season -> Int4,
+            award -> Text,
+            player_name -> Text,
+            team_mmolb_id -> Text,
+            value -> Int4,
+        }
+    }
+
+    diesel::table! {
+        data.season_final_standings (id) {
+            id -> Int8,
+            season -> Int4,
+            team_mmolb_id -> Text,
+            team_name -> Text,
+            team_emoji -> Text,
+            games_played -> Int4,
+            wins -> Int4,
+            losses -> Int4,
+            runs_scored -> Int4,
+            runs_allowed -> Int4,
+        }
+    }
+
+    diesel::table! {
+        data.season_phases (id) {
+            id -> Int8,
+            season -> Int4,
+            day_type -> Int8,
+            started_at -> Timestamptz,
+            ended_at -> Nullable<Timestamptz>,
+            from_version -> Timestamptz,
+        }
+    }
+
+    diesel::table! {
+        data.stadium_versions (id) {
+            id -> Int8,
+            mmolb_stadium_id -> Text,
+            valid_from -> Timestamptz,
+            valid_until -> Nullable<Timestamptz>,
+            duplicates -> Int4,
+            name -> Text,
+            mmolb_team_id -> Nullable<Text>,
+            modifications -> Array<Text>,
+            capacity -> Nullable<Int4>,
+        }
+    }
+
+    diesel::table! {
+        data.stadiums (mmolb_stadium_id) {
+            mmolb_stadium_id -> Text,
+        }
+    }
+
+    diesel::table! {
+        data.superstar_game_players (id) {
+            id -> Int8,
+            game_id -> Int8,
+            superstar_team_mmolb_id -> Text,
+            superstar_league -> Nullable<Int8>,
+            player_name -> Text,
+            mmolb_player_id -> Nullable<Text>,
+            num_candidates -> Int4,
+            source_team_mmolb_id -> Nullable<Text>,
+            source_league -> Nullable<Int8>,
+        }
+    }
+
     diesel::table! {
         data.team_games_played (id) {
             id -> Int8,
@@ -563,8 +801,8 @@ pub mod data {
             id -> Int8,
             mmolb_team_id -> Text,
             team_player_index -> Int4,
-            valid_from -> Timestamp,
-            valid_until -> Nullable<Timestamp>,
+            valid_from -> Timestamptz,
+            valid_until -> Nullable<Timestamptz>,
             duplicates -> Int4,
             first_name -> Text,
             last_name -> Text,
@@ -575,12 +813,24 @@ pub mod data {
         }
     }
 
+    diesel::table! {
+        data.team_transactions (id) {
+            id -> Int8,
+            mmolb_team_id -> Text,
+            feed_event_index -> Int4,
+            time -> Timestamptz,
+            amount -> Int4,
+            source -> Text,
+            mmolb_game_id -> Nullable<Text>,
+        }
+    }
+
     diesel::table! {
         data.team_versions (id) {
             id -> Int8,
             mmolb_team_id -> Text,
-            valid_from -> Timestamp,
-            valid_until -> Nullable<Timestamp>,
+            valid_from -> Timestamptz,
+            valid_until -> Nullable<Timestamptz>,
             duplicates -> Int4,
             name -> Text,
             emoji -> Text,
@@ -596,13 +846,26 @@ pub mod data {
         }
     }
 
+    diesel::table! {
+        data.team_weekly_rollups (week_start, team_mmolb_id) {
+            week_start -> Date,
+            team_mmolb_id -> Text,
+            games -> Int8,
+            wins -> Int8,
+            runs_scored -> Int8,
+            runs_allowed -> Int8,
+        }
+    }
+
     diesel::table! {
         data.versions (kind, entity_id, valid_from) {
             kind -> Text,
             entity_id -> Text,
-            valid_from -> Timestamp,
-            valid_to -> Nullable<Timestamp>,
-            data -> Jsonb,
+            valid_from -> Timestamptz,
+            valid_to -> Nullable<Timestamptz>,
+            data -> Nullable<Jsonb>,
+            source -> Nullable<Text>,
+            data_zstd -> Nullable<Bytea>,
         }
     }
 
@@ -610,7 +873,7 @@ pub mod data {
         data.versions_processed (kind, entity_id, valid_from) {
             kind -> Text,
             entity_id -> Text,
-            valid_from -> Timestamp,
+            valid_from -> Timestamptz,
             skipped -> Bool,
             fatal_error -> Bool,
         }
@@ -625,6 +888,12 @@ pub mod data {
         }
     }
 
+    diesel::table! {
+        data.weekly_rollups_dirty (week_start) {
+            week_start -> Date,
+        }
+    }
+
     diesel::table! {
         data.wither (id) {
             id -> Int8,
@@ -654,21 +923,32 @@ pub mod data {
     diesel::joinable!(event_baserunners -> events (event_id));
     diesel::joinable!(event_cheers -> cheers (cheer_id));
     diesel::joinable!(event_cheers -> events (event_id));
+    diesel::joinable!(event_defense -> events (event_id));
     diesel::joinable!(event_fielders -> events (event_id));
+    diesel::joinable!(event_win_probability -> events (event_id));
     diesel::joinable!(events -> games (game_id));
     diesel::joinable!(failed_ejections -> events (event_id));
+    diesel::joinable!(game_innings -> games (game_id));
+    diesel::joinable!(games -> stadiums (mmolb_stadium_id));
     diesel::joinable!(games -> weather (weather));
     diesel::joinable!(parties -> games (game_id));
+    diesel::joinable!(pitcher_appearances -> games (game_id));
     diesel::joinable!(pitcher_changes -> games (game_id));
+    diesel::joinable!(plate_appearances -> events (event_id));
+    diesel::joinable!(player_identity_resolution -> games (game_id));
     diesel::joinable!(player_modification_versions -> modifications (modification_id));
+    diesel::joinable!(stadium_versions -> stadiums (mmolb_stadium_id));
+    diesel::joinable!(superstar_game_players -> games (game_id));
     diesel::joinable!(wither -> games (game_id));
 
     diesel::allow_tables_to_appear_in_same_query!(
+        attribute_distributions,
         aurora_photos,
         balk_reasons,
         cheers,
         consumption_contest_events,
         consumption_contests,
+        defense_outcomes,
         door_prize_items,
         door_prizes,
         efflorescence,
@@ -678,19 +958,28 @@ pub mod data {
         event_balk_reasons,
         event_baserunners,
         event_cheers,
+        event_defense,
         event_fielders,
+        event_pitch_sequence,
+        event_win_probability,
         events,
         failed_ejections,
+        feed_event_texts,
         feed_event_versions,
         feed_events_processed,
+        game_innings,
         games,
         modification_effects,
         modifications,
+        offense_outcomes,
         parties,
+        pitcher_appearances,
         pitcher_changes,
+        plate_appearances,
         player_attribute_augments,
         player_equipment_effect_versions,
         player_equipment_versions,
+        player_identity_resolution,
         player_modification_versions,
         player_paradigm_shifts,
         player_pitch_category_bonus_versions,
@@ -700,12 +989,22 @@ pub mod data {
         player_report_attribute_versions,
         player_report_versions,
         player_versions,
+        player_weekly_rollups,
+        season_awards,
+        season_final_standings,
+        season_phases,
+        stadium_versions,
+        stadiums,
+        superstar_game_players,
         team_games_played,
         team_player_versions,
+        team_transactions,
         team_versions,
+        team_weekly_rollups,
         versions,
         versions_processed,
         weather,
+        weekly_rollups_dirty,
         wither,
     );
 }
@@ -1,6 +1,38 @@
 // @generated automatically by Diesel CLI.
 
 pub mod info {
+    diesel::table! {
+        info.admin_audit (id) {
+            id -> Int8,
+            at -> Timestamptz,
+            actor -> Text,
+            action -> Text,
+            details -> Jsonb,
+            before -> Nullable<Jsonb>,
+            after -> Nullable<Jsonb>,
+        }
+    }
+
+    diesel::table! {
+        info.chron_anomalies (id) {
+            id -> Int8,
+            detected_at -> Timestamptz,
+            kind -> Text,
+            url -> Text,
+            page -> Nullable<Text>,
+            description -> Text,
+            resolved_by_refetch -> Bool,
+        }
+    }
+
+    diesel::table! {
+        info.data_freshness (kind) {
+            kind -> Text,
+            last_valid_from -> Nullable<Timestamptz>,
+            last_ingest_at -> Timestamptz,
+        }
+    }
+
     diesel::table! {
         info.event_ingest_log (id) {
             id -> Int8,
@@ -9,6 +41,65 @@ pub mod info {
             log_index -> Int4,
             log_level -> Int4,
             log_text -> Text,
+            error_code -> Nullable<Int8>,
+            feed_event_index -> Nullable<Int4>,
+        }
+    }
+
+    diesel::table! {
+        info.feed_event_failures (id) {
+            id -> Int8,
+            kind -> Text,
+            entity_id -> Text,
+            feed_event_index -> Int4,
+            valid_from -> Timestamptz,
+            raw_data -> Jsonb,
+            error_message -> Text,
+            retry_count -> Int4,
+            first_failed_at -> Timestamptz,
+            last_attempt_at -> Timestamptz,
+        }
+    }
+
+    diesel::table! {
+        info.ingest_task_state (stage) {
+            stage -> Text,
+            state -> Text,
+            started_at -> Timestamptz,
+            updated_at -> Timestamptz,
+            error -> Nullable<Text>,
+        }
+    }
+
+    diesel::table! {
+        info.matview_refreshes (matview) {
+            matview -> Text,
+            refreshed_at -> Timestamptz,
+            duration_seconds -> Float8,
+            concurrent -> Bool,
+            error -> Nullable<Text>,
+            inputs -> Nullable<Text>,
+        }
+    }
+
+    diesel::table! {
+        info.season_rollovers (season, task) {
+            season -> Int4,
+            task -> Text,
+            completed_at -> Nullable<Timestamptz>,
+            attempts -> Int4,
+            last_attempted_at -> Timestamptz,
+            last_error -> Nullable<Text>,
+        }
+    }
+
+    diesel::table! {
+        info.slow_queries (id) {
+            id -> Int8,
+            captured_at -> Timestamptz,
+            duration_seconds -> Float8,
+            query -> Text,
+            error -> Nullable<Text>,
         }
     }
 
@@ -17,12 +108,24 @@ pub mod info {
             id -> Int8,
             kind -> Text,
             entity_id -> Text,
-            valid_from -> Timestamp,
+            valid_from -> Timestamptz,
             log_index -> Int4,
             log_level -> Int4,
             log_text -> Text,
+            error_code -> Nullable<Int8>,
         }
     }
 
-    diesel::allow_tables_to_appear_in_same_query!(event_ingest_log, version_ingest_log,);
+    diesel::allow_tables_to_appear_in_same_query!(
+        admin_audit,
+        chron_anomalies,
+        data_freshness,
+        event_ingest_log,
+        feed_event_failures,
+        ingest_task_state,
+        matview_refreshes,
+        season_rollovers,
+        slow_queries,
+        version_ingest_log,
+    );
 }
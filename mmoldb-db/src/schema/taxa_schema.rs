@@ -6,6 +6,7 @@ pub mod taxa {
             id -> Int8,
             name -> Text,
             category -> Int8,
+            description -> Nullable<Text>,
         }
     }
 
@@ -13,6 +14,7 @@ pub mod taxa {
         taxa.attribute_category (id) {
             id -> Int8,
             name -> Text,
+            description -> Nullable<Text>,
         }
     }
 
@@ -20,6 +22,7 @@ pub mod taxa {
         taxa.attribute_effect_phase (id) {
             id -> Int8,
             name -> Text,
+            description -> Nullable<Text>,
         }
     }
 
@@ -27,6 +30,7 @@ pub mod taxa {
         taxa.attribute_effect_type (id) {
             id -> Int8,
             name -> Text,
+            description -> Nullable<Text>,
         }
     }
 
@@ -35,6 +39,7 @@ pub mod taxa {
             id -> Int8,
             name -> Text,
             bases_achieved -> Int4,
+            description -> Nullable<Text>,
         }
     }
 
@@ -42,6 +47,30 @@ pub mod taxa {
         taxa.base_description_format (id) {
             id -> Int8,
             name -> Text,
+            description -> Nullable<Text>,
+        }
+    }
+
+    diesel::table! {
+        taxa.base_movement (id) {
+            id -> Int8,
+            name -> Text,
+            display_name -> Text,
+            is_steal_attempt -> Bool,
+            is_out -> Bool,
+            is_advance -> Bool,
+            description -> Nullable<Text>,
+        }
+    }
+
+    diesel::table! {
+        taxa.count (id) {
+            id -> Int8,
+            name -> Text,
+            display_name -> Text,
+            balls -> Int4,
+            strikes -> Int4,
+            description -> Nullable<Text>,
         }
     }
 
@@ -50,6 +79,7 @@ pub mod taxa {
             id -> Int8,
             name -> Text,
             display_name -> Text,
+            description -> Nullable<Text>,
         }
     }
 
@@ -69,6 +99,7 @@ pub mod taxa {
             is_foul_tip -> Bool,
             batter_swung -> Bool,
             is_error -> Bool,
+            description -> Nullable<Text>,
         }
     }
 
@@ -77,6 +108,7 @@ pub mod taxa {
             id -> Int8,
             name -> Text,
             display_name -> Text,
+            description -> Nullable<Text>,
         }
     }
 
@@ -87,6 +119,7 @@ pub mod taxa {
             display_name -> Text,
             abbreviation -> Text,
             area -> Text,
+            description -> Nullable<Text>,
         }
     }
 
@@ -94,6 +127,7 @@ pub mod taxa {
         taxa.fielding_error_type (id) {
             id -> Int8,
             name -> Text,
+            description -> Nullable<Text>,
         }
     }
 
@@ -101,6 +135,16 @@ pub mod taxa {
         taxa.handedness (id) {
             id -> Int8,
             name -> Text,
+            description -> Nullable<Text>,
+        }
+    }
+
+    diesel::table! {
+        taxa.ingest_error_code (id) {
+            id -> Int8,
+            name -> Text,
+            display_name -> Text,
+            description -> Nullable<Text>,
         }
     }
 
@@ -113,6 +157,7 @@ pub mod taxa {
             league_type -> Text,
             parent_team_id -> Text,
             mmolb_league_id -> Text,
+            description -> Nullable<Text>,
         }
     }
 
@@ -121,6 +166,7 @@ pub mod taxa {
             id -> Int8,
             name -> Text,
             display_name -> Text,
+            description -> Nullable<Text>,
         }
     }
 
@@ -128,6 +174,7 @@ pub mod taxa {
         taxa.pitch_category (id) {
             id -> Int8,
             name -> Text,
+            description -> Nullable<Text>,
         }
     }
 
@@ -138,6 +185,7 @@ pub mod taxa {
             display_name -> Text,
             abbreviation -> Text,
             category -> Int8,
+            description -> Nullable<Text>,
         }
     }
 
@@ -146,6 +194,7 @@ pub mod taxa {
             id -> Int8,
             name -> Text,
             display_name -> Text,
+            description -> Nullable<Text>,
         }
     }
 
@@ -159,6 +208,7 @@ pub mod taxa {
             pitcher_type -> Nullable<Text>,
             slot_number -> Nullable<Int4>,
             location -> Nullable<Int8>,
+            description -> Nullable<Text>,
         }
     }
 
@@ -166,6 +216,7 @@ pub mod taxa {
         taxa.slot_type (id) {
             id -> Int8,
             name -> Text,
+            description -> Nullable<Text>,
         }
     }
 
@@ -180,12 +231,15 @@ pub mod taxa {
         attribute_effect_type,
         base,
         base_description_format,
+        base_movement,
+        count,
         day_type,
         event_type,
         fair_ball_type,
         fielder_location,
         fielding_error_type,
         handedness,
+        ingest_error_code,
         leagues,
         modification_type,
         pitch_category,
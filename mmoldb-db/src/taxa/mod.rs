@@ -1,16 +1,25 @@
+mod reconcile;
+mod renumber;
+mod stars;
 mod taxa_macro;
 
-pub use taxa_macro::AsInsertable;
+pub use reconcile::{TaxaMismatch, TaxaOrphan, TaxaReconcileMode, TaxaReconciliation};
+pub use renumber::*;
+pub use stars::*;
+pub use taxa_macro::{AsInsertable, TaxaColumnDefinition, TaxaColumnType, TaxaDefinitionRow};
 
 use paste::paste;
 
+use crate::db::record_admin_action;
+use crate::models::NewAdminAudit;
 use crate::parsing_extensions;
 use diesel::QueryResult;
 use diesel::prelude::*;
 use diesel::{PgConnection, RunQueryDsl};
 use enum_map::EnumMap;
-use serde::{Serialize, Deserialize};
-use std::collections::HashSet;
+use reconcile::{count_taxa_rows, diff_taxa_row, load_taxa_rows, orphaned_rows};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use taxa_macro::*;
 use tracing::{error, warn};
 
@@ -19,6 +28,28 @@ taxa! {
         schema = crate::taxa_schema::taxa::event_type,
         table = crate::taxa_schema::taxa::event_type::dsl::event_type,
         id_column = crate::taxa_schema::taxa::event_type::dsl::id,
+        columns = {
+            display_name: "The human-friendly name for this event type.",
+            ends_plate_appearance: "Does an event of this type end the current plate appearance?",
+            is_in_play: "Does an event of this type result in a ball in play?",
+            is_hit: "Does an event of this type count as a hit, for the purposes of batter \
+                statistics? Home runs are hits. Reaching on an error or a fielder's choice is not.",
+            is_error: "Does an event of this type count as an error, for the purposes of both \
+                batter and pitcher statistics?",
+            is_ball: "Does an event of this type represent a pitch outside the strike zone on \
+                which the batter did not swing? Walks and hit-by-pitches count too.",
+            is_strike: "Does an event of this type represent either a pitch inside the strike \
+                zone or a pitch on which the batter swung? Balls in play count too.",
+            is_strikeout: "Does an event of this type count as a strikeout, for the purposes of \
+                pitcher statistics?",
+            is_basic_strike: "Does an event of this type count as either a strikeout or a \
+                non-PA-ending strike? Unlike `is_strike`, this is `false` for balls in play. \
+                Foul balls count even when they don't add a strike.",
+            is_foul: "Does an event of this type count as a foul ball or foul tip?",
+            is_foul_tip: "Does an event of this type count as a foul tip?",
+            batter_swung: "Does an event of this type represent a pitch on which the batter \
+                swung?",
+        },
     ]
     pub enum TaxaEventType {
         #[
@@ -35,7 +66,7 @@ taxa! {
             is_foul_tip: bool = false,
             batter_swung: bool = false,
         ]
-        Ball = 0,
+        Ball = 0 => "A pitch outside the strike zone that the batter didn't swing at, which didn't end the plate appearance",
         #[
             display_name: &'a str = "called strike",
             ends_plate_appearance: bool = false,
@@ -50,7 +81,7 @@ taxa! {
             is_foul_tip: bool = false,
             batter_swung: bool = false,
         ]
-        CalledStrike = 1,
+        CalledStrike = 1 => "A pitch in the strike zone that the batter didn't swing at, which didn't end the plate appearance",
         #[
             display_name: &'a str = "swinging strike",
             ends_plate_appearance: bool = false,
@@ -65,7 +96,7 @@ taxa! {
             is_foul_tip: bool = false,
             batter_swung: bool = true,
         ]
-        SwingingStrike = 2,
+        SwingingStrike = 2 => "A pitch the batter swung at and missed, which didn't end the plate appearance",
         #[
             display_name: &'a str = "foul tip",
             ends_plate_appearance: bool = false,
@@ -80,7 +111,7 @@ taxa! {
             is_foul_tip: bool = true,
             batter_swung: bool = true,
         ]
-        FoulTip = 3,
+        FoulTip = 3 => "A pitch the batter tipped into the catcher's glove, which didn't end the plate appearance",
         #[
             display_name: &'a str = "foul ball",
             ends_plate_appearance: bool = false,
@@ -95,7 +126,7 @@ taxa! {
             is_foul_tip: bool = false,
             batter_swung: bool = true,
         ]
-        FoulBall = 4,
+        FoulBall = 4 => "A pitch the batter hit into foul territory",
         #[
             display_name: &'a str = "hit",
             ends_plate_appearance: bool = true,
@@ -110,7 +141,7 @@ taxa! {
             is_foul_tip: bool = false,
             batter_swung: bool = true,
         ]
-        Hit = 5,
+        Hit = 5 => "A ball in play that let the batter reach base safely without an error, other than a home run",
         #[
             display_name: &'a str = "force out",
             ends_plate_appearance: bool = true,
@@ -125,7 +156,7 @@ taxa! {
             is_foul_tip: bool = false,
             batter_swung: bool = true,
         ]
-        ForceOut = 6,
+        ForceOut = 6 => "A ball in play where a runner was forced out",
         #[
             display_name: &'a str = "caught out",
             ends_plate_appearance: bool = true,
@@ -140,7 +171,7 @@ taxa! {
             is_foul_tip: bool = false,
             batter_swung: bool = true,
         ]
-        CaughtOut = 7,
+        CaughtOut = 7 => "A ball in play that was caught in the air for an out",
         #[
             display_name: &'a str = "grounded out",
             ends_plate_appearance: bool = true,
@@ -155,7 +186,7 @@ taxa! {
             is_foul_tip: bool = false,
             batter_swung: bool = true,
         ]
-        GroundedOut = 8,
+        GroundedOut = 8 => "A ground ball where the batter was thrown out at first",
         #[
             display_name: &'a str = "walk",
             ends_plate_appearance: bool = true,
@@ -170,7 +201,7 @@ taxa! {
             is_foul_tip: bool = false,
             batter_swung: bool = false,
         ]
-        Walk = 9,
+        Walk = 9 => "A fourth ball, which sends the batter to first",
         #[
             display_name: &'a str = "home run",
             ends_plate_appearance: bool = true,
@@ -185,7 +216,7 @@ taxa! {
             is_foul_tip: bool = false,
             batter_swung: bool = true,
         ]
-        HomeRun = 10,
+        HomeRun = 10 => "A ball hit out of the park",
         #[
             display_name: &'a str = "fielding error",
             ends_plate_appearance: bool = true,
@@ -200,7 +231,7 @@ taxa! {
             is_foul_tip: bool = false,
             batter_swung: bool = true,
         ]
-        FieldingError = 11,
+        FieldingError = 11 => "A ball in play where the batter reached base because of a fielding error",
         #[
             display_name: &'a str = "hit by pitch",
             ends_plate_appearance: bool = true,
@@ -215,7 +246,7 @@ taxa! {
             is_foul_tip: bool = false,
             batter_swung: bool = false,
         ]
-        HitByPitch = 12,
+        HitByPitch = 12 => "A pitch that hit the batter, which sends the batter to first",
         #[
             display_name: &'a str = "double play",
             ends_plate_appearance: bool = true,
@@ -230,7 +261,7 @@ taxa! {
             is_foul_tip: bool = false,
             batter_swung: bool = true,
         ]
-        DoublePlay = 13,
+        DoublePlay = 13 => "A ball in play that resulted in two outs",
         #[
             display_name: &'a str = "fielder's choice",
             ends_plate_appearance: bool = true,
@@ -245,7 +276,7 @@ taxa! {
             is_foul_tip: bool = false,
             batter_swung: bool = true,
         ]
-        FieldersChoice = 14,
+        FieldersChoice = 14 => "A ball in play where the fielder chose to put out a runner other than the batter",
         #[
             display_name: &'a str = "error on fielder's choice",
             ends_plate_appearance: bool = true,
@@ -260,7 +291,7 @@ taxa! {
             is_foul_tip: bool = false,
             batter_swung: bool = true,
         ]
-        ErrorOnFieldersChoice = 15,
+        ErrorOnFieldersChoice = 15 => "A fielder's choice where the attempted out failed because of an error",
         #[
             display_name: &'a str = "balk",
             ends_plate_appearance: bool = false,
@@ -275,7 +306,7 @@ taxa! {
             is_foul_tip: bool = false,
             batter_swung: bool = false,
         ]
-        Balk = 16,
+        Balk = 16 => "A balk by the pitcher, which advances every runner and isn't a pitch",
         // These used to be folded into strikes but they're now separate
         #[
             display_name: &'a str = "called strikeout",
@@ -291,7 +322,7 @@ taxa! {
             is_foul_tip: bool = false,
             batter_swung: bool = false,
         ]
-        CalledStrikeout = 17,
+        CalledStrikeout = 17 => "A third strike that the batter didn't swing at",
         #[
             display_name: &'a str = "swinging strikeout",
             ends_plate_appearance: bool = true,
@@ -306,7 +337,7 @@ taxa! {
             is_foul_tip: bool = false,
             batter_swung: bool = true,
         ]
-        SwingingStrikeout = 18,
+        SwingingStrikeout = 18 => "A third strike that the batter swung at and missed",
         #[
             display_name: &'a str = "foul tip strikeout",
             ends_plate_appearance: bool = true,
@@ -321,7 +352,7 @@ taxa! {
             is_foul_tip: bool = true,
             batter_swung: bool = true,
         ]
-        FoulTipStrikeout = 19,
+        FoulTipStrikeout = 19 => "A third strike that the batter tipped into the catcher's glove",
     }
 }
 
@@ -431,8 +462,8 @@ taxa! {
         id_column = crate::taxa_schema::taxa::slot_type::dsl::id,
     ]
     pub enum TaxaSlotType {
-        Batter = 1,
-        Pitcher = 2,
+        Batter = 1 => "A slot in the lineup or on the bench",
+        Pitcher = 2 => "A slot in the rotation or the bullpen",
     }
 }
 
@@ -823,13 +854,13 @@ taxa! {
     ]
     pub enum TaxaFairBallType {
         #[display_name: &'a str = "Ground ball"]
-        GroundBall = 1,
+        GroundBall = 1 => "A ball hit on the ground",
         #[display_name: &'a str = "Fly ball"]
-        FlyBall = 2,
+        FlyBall = 2 => "A ball hit high in the air, usually to the outfield",
         #[display_name: &'a str = "Line drive"]
-        LineDrive = 3,
+        LineDrive = 3 => "A ball hit hard on a low, flat trajectory",
         #[display_name: &'a str = "Popup"]
-        Popup = 4,
+        Popup = 4 => "A ball hit high in the air that stays in or near the infield",
     }
 }
 
@@ -855,6 +886,63 @@ impl From<mmolb_parsing::enums::FairBallType> for TaxaFairBallType {
     }
 }
 
+taxa! {
+    #[
+        schema = crate::taxa_schema::taxa::count,
+        table = crate::taxa_schema::taxa::count::dsl::count,
+        id_column = crate::taxa_schema::taxa::count::dsl::id,
+        derive = (Serialize,)
+    ]
+    pub enum TaxaCount {
+        #[display_name: &'a str = "0-0", balls: i32 = 0, strikes: i32 = 0]
+        Balls0Strikes0 = 1,
+        #[display_name: &'a str = "0-1", balls: i32 = 0, strikes: i32 = 1]
+        Balls0Strikes1 = 2,
+        #[display_name: &'a str = "0-2", balls: i32 = 0, strikes: i32 = 2]
+        Balls0Strikes2 = 3,
+        #[display_name: &'a str = "1-0", balls: i32 = 1, strikes: i32 = 0]
+        Balls1Strikes0 = 4,
+        #[display_name: &'a str = "1-1", balls: i32 = 1, strikes: i32 = 1]
+        Balls1Strikes1 = 5,
+        #[display_name: &'a str = "1-2", balls: i32 = 1, strikes: i32 = 2]
+        Balls1Strikes2 = 6,
+        #[display_name: &'a str = "2-0", balls: i32 = 2, strikes: i32 = 0]
+        Balls2Strikes0 = 7,
+        #[display_name: &'a str = "2-1", balls: i32 = 2, strikes: i32 = 1]
+        Balls2Strikes1 = 8,
+        #[display_name: &'a str = "2-2", balls: i32 = 2, strikes: i32 = 2]
+        Balls2Strikes2 = 9,
+        #[display_name: &'a str = "3-0", balls: i32 = 3, strikes: i32 = 0]
+        Balls3Strikes0 = 10,
+        #[display_name: &'a str = "3-1", balls: i32 = 3, strikes: i32 = 1]
+        Balls3Strikes1 = 11,
+        #[display_name: &'a str = "3-2", balls: i32 = 3, strikes: i32 = 2]
+        Balls3Strikes2 = 12,
+    }
+}
+
+impl TaxaCount {
+    /// None for counts that can't come up in a normal plate appearance,
+    /// which mods can cause
+    pub fn from_balls_strikes(balls: u8, strikes: u8) -> Option<Self> {
+        match (balls, strikes) {
+            (0, 0) => Some(Self::Balls0Strikes0),
+            (0, 1) => Some(Self::Balls0Strikes1),
+            (0, 2) => Some(Self::Balls0Strikes2),
+            (1, 0) => Some(Self::Balls1Strikes0),
+            (1, 1) => Some(Self::Balls1Strikes1),
+            (1, 2) => Some(Self::Balls1Strikes2),
+            (2, 0) => Some(Self::Balls2Strikes0),
+            (2, 1) => Some(Self::Balls2Strikes1),
+            (2, 2) => Some(Self::Balls2Strikes2),
+            (3, 0) => Some(Self::Balls3Strikes0),
+            (3, 1) => Some(Self::Balls3Strikes1),
+            (3, 2) => Some(Self::Balls3Strikes2),
+            _ => None,
+        }
+    }
+}
+
 taxa! {
     #[
         schema = crate::taxa_schema::taxa::base,
@@ -941,9 +1029,33 @@ taxa! {
         id_column = crate::taxa_schema::taxa::base_description_format::dsl::id,
     ]
     pub enum TaxaBaseDescriptionFormat {
-        NumberB = 1,  // e.g. "1B"
-        Name = 2,     // e.g. "first"
-        NameBase = 3, // e.g. "first base"
+        NumberB = 1 => "The base's number and a B, e.g. \"1B\"",
+        Name = 2 => "The base's ordinal name, e.g. \"first\"",
+        NameBase = 3 => "The base's ordinal name and \"base\", e.g. \"first base\"",
+    }
+}
+
+taxa! {
+    #[
+        schema = crate::taxa_schema::taxa::base_movement,
+        table = crate::taxa_schema::taxa::base_movement::dsl::base_movement,
+        id_column = crate::taxa_schema::taxa::base_movement::dsl::id,
+        derive = (Serialize)
+    ]
+    // These aren't stored on event_baserunners. They're derived from its
+    // steal, is_out, base_before, and base_after columns when queried (see
+    // db::baserunning_outcomes), so if you change them, change that too.
+    pub enum TaxaBaseMovement {
+        #[display_name: &'a str = "Stolen base", is_steal_attempt: bool = true, is_out: bool = false, is_advance: bool = true]
+        StolenBase = 1,
+        #[display_name: &'a str = "Caught stealing", is_steal_attempt: bool = true, is_out: bool = true, is_advance: bool = false]
+        CaughtStealing = 2,
+        #[display_name: &'a str = "Advanced", is_steal_attempt: bool = false, is_out: bool = false, is_advance: bool = true]
+        Advanced = 3,
+        #[display_name: &'a str = "Held", is_steal_attempt: bool = false, is_out: bool = false, is_advance: bool = false]
+        Held = 4,
+        #[display_name: &'a str = "Out on the bases", is_steal_attempt: bool = false, is_out: bool = true, is_advance: bool = false]
+        OutOnBases = 5,
     }
 }
 
@@ -1011,8 +1123,8 @@ taxa! {
         id_column = crate::taxa_schema::taxa::fielding_error_type::dsl::id,
     ]
     pub enum TaxaFieldingErrorType {
-        Fielding = 1,
-        Throwing = 2,
+        Fielding = 1 => "The fielder misplayed the ball",
+        Throwing = 2 => "The fielder made a bad throw",
     }
 }
 
@@ -1042,9 +1154,9 @@ taxa! {
         derive = (Serialize,)
     ]
     pub enum TaxaPitchCategory {
-        Fast = 1,
-        Breaking = 2,
-        Offspeed = 3,
+        Fast = 1 => "Fastballs, like four-seamers, sinkers, and cutters",
+        Breaking = 2 => "Pitches that curve, like curveballs and sliders",
+        Offspeed = 3 => "Slower pitches, like changeups and splitters",
     }
 }
 
@@ -1137,9 +1249,9 @@ taxa! {
         derive = (Serialize)
     ]
     pub enum TaxaHandedness {
-        Right = 1,
-        Left = 2,
-        Switch = 3,
+        Right = 1 => "Right-handed",
+        Left = 2 => "Left-handed",
+        Switch = 3 => "Switch-handed",
     }
 }
 
@@ -1455,8 +1567,9 @@ taxa! {
         derive = (Serialize, Deserialize)
     ]
     pub enum TaxaEffectType {
-        Flat = 1,
-        Multiplier = 2,
+        Flat = 1 => "Adds a fixed amount to the attribute",
+        Multiplier = 2 => "Multiplies the attribute. Zone-conditional multipliers are also stored \
+            as multipliers.",
         // MultiplicativeMultiplier = 3,
     }
 }
@@ -1467,7 +1580,9 @@ impl From<mmolb_parsing::enums::EquipmentEffectType> for TaxaEffectType {
             mmolb_parsing::enums::EquipmentEffectType::FlatBonus => TaxaEffectType::Flat,
             mmolb_parsing::enums::EquipmentEffectType::Multiplier => TaxaEffectType::Multiplier,
             // Other fields in the db should let the user tell when it's ZoneConditionalMultiplier
-            mmolb_parsing::enums::EquipmentEffectType::ZoneConditionalMultiplier => TaxaEffectType::Multiplier,
+            mmolb_parsing::enums::EquipmentEffectType::ZoneConditionalMultiplier => {
+                TaxaEffectType::Multiplier
+            }
         }
     }
 }
@@ -1480,8 +1595,8 @@ taxa! {
         derive = (Serialize, Deserialize)
     ]
     pub enum TaxaEffectPhase {
-        Batting = 1,
-        Pitching = 2,
+        Batting = 1 => "Applies while the player is batting",
+        Pitching = 2 => "Applies while the player is pitching",
     }
 }
 
@@ -1503,11 +1618,11 @@ taxa! {
     ]
     pub enum TaxaPitcherChangeSource {
         #[display_name: &'a str = "Inning change"]
-        InningChange = 1,
+        InningChange = 1 => "The pitcher was replaced between innings",
         #[display_name: &'a str = "Mound visit"]
-        MoundVisit = 2,
+        MoundVisit = 2 => "The pitcher was replaced during a mound visit",
         #[display_name: &'a str = "Pitching change"]
-        PitchingChange = 3,
+        PitchingChange = 3 => "The pitcher was replaced by a pitching change without a mound visit",
     }
 }
 
@@ -1541,56 +1656,292 @@ taxa! {
     }
 }
 
-// This _entire_ thing and its impl could be generated by macro
+taxa! {
+    #[
+        schema = crate::taxa_schema::taxa::ingest_error_code,
+        table = crate::taxa_schema::taxa::ingest_error_code::dsl::ingest_error_code,
+        id_column = crate::taxa_schema::taxa::ingest_error_code::dsl::id,
+        derive = (Serialize)
+    ]
+    pub enum TaxaIngestErrorCode {
+        #[display_name: &'a str = "Game not supported"]
+        GameNotSupported = 1 => "The game is a kind of game MMOLDB doesn't ingest, like a Home Run Challenge.",
+        #[display_name: &'a str = "Game forever incomplete"]
+        GameForeverIncomplete = 2 => "The game is bugged and will never finish, so it will never be ingested.",
+        #[display_name: &'a str = "Game fatal error"]
+        GameFatalError = 3 => "An error prevented the entire game from being ingested.",
+        #[display_name: &'a str = "Game deserialize error"]
+        GameDeserializeError = 4 => "The game's JSON couldn't be deserialized.",
+        #[display_name: &'a str = "Event processing error"]
+        EventProcessingError = 5 => "An error prevented an event from being ingested. The event was skipped.",
+        #[display_name: &'a str = "Unparse mismatch"]
+        UnparseMismatch = 6 => "Converting the parsed event back to text didn't reproduce the original event text.",
+        #[display_name: &'a str = "Round-trip mismatch"]
+        RoundTripMismatch = 7 => "The event read back from the database didn't match the event that was written.",
+        #[display_name: &'a str = "Baserunner mismatch"]
+        BaserunnerMismatch = 8 => "The baserunners described by an event didn't match the simulated baserunners.",
+        #[display_name: &'a str = "Count mismatch"]
+        CountMismatch = 9 => "The balls or strikes in an event didn't match the simulated count.",
+        #[display_name: &'a str = "Team mismatch"]
+        TeamMismatch = 10 => "A team name or emoji in an event didn't match the game's stored team.",
+        #[display_name: &'a str = "Player mismatch"]
+        PlayerMismatch = 11 => "A batter, pitcher, or manager in an event didn't match the simulated one.",
+        #[display_name: &'a str = "Version deserialize error"]
+        VersionDeserializeError = 12 => "A non-game entity version's JSON couldn't be deserialized.",
+        #[display_name: &'a str = "Game values set aside"]
+        GameValuesSetAside = 13 => "Some values in a game couldn't be deserialized, so they were set aside and the rest of the game was ingested.",
+        #[display_name: &'a str = "Game stalled"]
+        GameStalled = 14 => "The game stopped updating before it finished and later games have been played since, so it's treated as forever incomplete. It will be ingested if a new version appears.",
+        #[display_name: &'a str = "Version chain gap"]
+        VersionChainGap = 15 => "A version stopped being valid before the next version of the same thing started, so nothing was valid in between.",
+        #[display_name: &'a str = "Version chain overlap"]
+        VersionChainOverlap = 16 => "A version was still valid after the next version of the same thing started, so queries for that time return both.",
+        #[display_name: &'a str = "Inning mismatch"]
+        InningMismatch = 17 => "The inning number or side in an event didn't match the simulated inning.",
+        #[display_name: &'a str = "Fielder mismatch"]
+        FielderMismatch = 18 => "A fielder named in an event didn't match the fielders listed for the play.",
+        #[display_name: &'a str = "Contest mismatch"]
+        ContestMismatch = 19 => "A consumption contest event didn't match the contest's earlier events or the game's teams.",
+        #[display_name: &'a str = "Fair ball mismatch"]
+        FairBallMismatch = 20 => "A fair ball's type or destination didn't match the event that announced it.",
+        #[display_name: &'a str = "Ambiguous event"]
+        AmbiguousEvent = 21 => "An event could refer to more than one player or team, so MMOLDB had to guess or leave it out.",
+        #[display_name: &'a str = "Unexpected event"]
+        UnexpectedEvent = 22 => "An event appeared when MMOLDB didn't expect it, or had a form MMOLDB didn't expect. It was ingested as well as possible.",
+        #[display_name: &'a str = "Unrecognized value"]
+        UnrecognizedValue = 23 => "A value in a game or entity version wasn't one MMOLDB recognizes, so it was left out.",
+        #[display_name: &'a str = "Unexpected version data"]
+        UnexpectedVersionData = 24 => "An entity version had a shape MMOLDB didn't expect, like both the old and new forms of a field.",
+        #[display_name: &'a str = "Feed event changed"]
+        FeedEventChanged = 25 => "A feed event changed between versions in a way MMOLDB doesn't expect.",
+        #[display_name: &'a str = "Feed event parse error"]
+        FeedEventParseError = 26 => "A feed event's text couldn't be parsed.",
+        #[display_name: &'a str = "Score mismatch"]
+        ScoreMismatch = 27 => "A score in an event didn't match the score MMOLDB counted.",
+        #[display_name: &'a str = "Stats mismatch"]
+        StatsMismatch = 28 => "A player's stats in an event didn't match the stats MMOLDB counted.",
+    }
+}
+
+/// A taxa id that doesn't match any variant this build knows about, which
+/// means a row and the taxa tables disagree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("unknown {taxa} id {id}")]
+pub struct UnknownTaxaId {
+    pub taxa: &'static str,
+    pub id: i64,
+}
+
+fn from_id<T: enum_map::EnumArray<i64>>(
+    mapping: &EnumMap<T, i64>,
+    taxa: &'static str,
+    id: i64,
+) -> Result<T, UnknownTaxaId> {
+    mapping
+        .iter()
+        .find(|(_, ty_id)| id == **ty_id)
+        .map(|(val, _)| val)
+        .ok_or(UnknownTaxaId { taxa, id })
+}
+
+/// The rows of one taxa table, as its Rust definition describes them
 #[derive(Debug, Clone)]
-pub struct Taxa {
-    event_type_mapping: EnumMap<TaxaEventType, i64>,
-    fielder_location_mapping: EnumMap<TaxaFielderLocation, i64>,
-    slot_type_mapping: EnumMap<TaxaSlotType, i64>,
-    slot_mapping: EnumMap<TaxaSlot, i64>,
-    fair_ball_type_mapping: EnumMap<TaxaFairBallType, i64>,
-    base_mapping: EnumMap<TaxaBase, i64>,
-    base_description_format_mapping: EnumMap<TaxaBaseDescriptionFormat, i64>,
-    fielding_error_type_mapping: EnumMap<TaxaFieldingErrorType, i64>,
-    pitch_category_mapping: EnumMap<TaxaPitchCategory, i64>,
-    pitch_type_mapping: EnumMap<TaxaPitchType, i64>,
-    handedness_mapping: EnumMap<TaxaHandedness, i64>,
-    day_type_mapping: EnumMap<TaxaDayType, i64>,
-    #[allow(dead_code)]
-    league_mapping: EnumMap<TaxaLeagues, i64>,
-    #[allow(dead_code)]
-    attribute_category_mapping: EnumMap<TaxaAttributeCategory, i64>,
-    attribute_mapping: EnumMap<TaxaAttribute, i64>,
-    effect_type_mapping: EnumMap<TaxaEffectType, i64>,
-    effect_phase_mapping: EnumMap<TaxaEffectPhase, i64>,
-    pitcher_change_source_mapping: EnumMap<TaxaPitcherChangeSource, i64>,
-    modification_type_mapping: EnumMap<TaxaModificationType, i64>,
+pub struct TaxaTableDefinition {
+    pub table: &'static str,
+    pub columns: Vec<TaxaColumnDefinition>,
+    pub rows: Vec<TaxaDefinitionRow>,
+}
+
+/// Compares every taxa table with its Rust definition without changing
+/// anything, for reporting on tables that [`Taxa::new`] hasn't reconciled
+/// yet or can't (like orphaned rows)
+pub fn check_taxa_tables(conn: &mut PgConnection) -> QueryResult<Vec<TaxaReconciliation>> {
+    taxa_table_definitions()
+        .into_iter()
+        .map(|definition| {
+            let mut report =
+                TaxaReconciliation::new(definition.table, TaxaReconcileMode::ReportOnly);
+            let mut existing = load_taxa_rows(conn, definition.table)?
+                .into_iter()
+                .map(|row| (row.id, row.row))
+                .collect::<HashMap<_, _>>();
+
+            for row in definition.rows {
+                let Some(in_db) = existing.remove(&row.id) else {
+                    report.inserted.push(row.name);
+                    continue;
+                };
+
+                // The definition row splits these out of the insertable
+                let mut expected = row.columns;
+                expected.insert("id".to_string(), row.id.into());
+                expected.insert("name".to_string(), row.name.into());
+                expected.insert("description".to_string(), row.description.into());
+                report.mismatched.extend(diff_taxa_row(
+                    row.id,
+                    row.name,
+                    &in_db,
+                    &serde_json::Value::Object(expected),
+                ));
+            }
+
+            report.orphaned = orphaned_rows(existing);
+            Ok(report)
+        })
+        .collect()
+}
+
+fn with_report<T>(
+    reports: &mut Vec<TaxaReconciliation>,
+    (mapping, report): (T, TaxaReconciliation),
+) -> T {
+    reports.push(report);
+    mapping
+}
+
+/// Generates [`Taxa`], [`Taxa::new_reconciled`], and
+/// [`taxa_table_definitions`] from one list of taxa tables, so a new table
+/// only has to be added in one place. Tables are reconciled in list order.
+macro_rules! taxa_tables {
+    ($($mapping:ident: $enum_name:ident),+ $(,)?) => {
+        #[derive(Debug, Clone)]
+        pub struct Taxa {
+            $($mapping: EnumMap<$enum_name, i64>,)+
+        }
+
+        impl Taxa {
+            /// Like [`Taxa::new`], but returns how each taxa table differed
+            /// from its definition instead of logging it. In
+            /// [`TaxaReconcileMode::ReportOnly`], existing rows are left alone.
+            pub fn new_reconciled(
+                conn: &mut PgConnection,
+                mode: TaxaReconcileMode,
+            ) -> QueryResult<(Self, Vec<TaxaReconciliation>)> {
+                let mut reports = Vec::new();
+                // Struct fields are initialized in the order they're written
+                let taxa = Self {
+                    $($mapping: with_report(
+                        &mut reports,
+                        $enum_name::reconcile_id_mapping(conn, mode)?,
+                    ),)+
+                };
+
+                Ok((taxa, reports))
+            }
+        }
+
+        /// Every taxa table, in the order [`Taxa::new`] reconciles them.
+        /// Building a [`Taxa`] makes the database match these, so they can
+        /// be served without querying it.
+        pub fn taxa_table_definitions() -> Vec<TaxaTableDefinition> {
+            vec![$(TaxaTableDefinition {
+                table: $enum_name::table_name(),
+                columns: $enum_name::column_definitions(),
+                rows: $enum_name::definition_rows(),
+            }),+]
+        }
+    };
+}
+
+taxa_tables! {
+    event_type_mapping: TaxaEventType,
+    // fielder_location_mapping must come before slot_mapping
+    fielder_location_mapping: TaxaFielderLocation,
+    slot_type_mapping: TaxaSlotType,
+    slot_mapping: TaxaSlot,
+    fair_ball_type_mapping: TaxaFairBallType,
+    base_mapping: TaxaBase,
+    base_description_format_mapping: TaxaBaseDescriptionFormat,
+    base_movement_mapping: TaxaBaseMovement,
+    count_mapping: TaxaCount,
+    fielding_error_type_mapping: TaxaFieldingErrorType,
+    pitch_category_mapping: TaxaPitchCategory,
+    pitch_type_mapping: TaxaPitchType,
+    handedness_mapping: TaxaHandedness,
+    day_type_mapping: TaxaDayType,
+    league_mapping: TaxaLeagues,
+    attribute_category_mapping: TaxaAttributeCategory,
+    attribute_mapping: TaxaAttribute,
+    effect_type_mapping: TaxaEffectType,
+    effect_phase_mapping: TaxaEffectPhase,
+    pitcher_change_source_mapping: TaxaPitcherChangeSource,
+    modification_type_mapping: TaxaModificationType,
+    ingest_error_code_mapping: TaxaIngestErrorCode,
 }
 
 impl Taxa {
+    /// Builds the id mappings, inserting missing taxa rows and updating any
+    /// that don't match their definitions. Differences are logged.
     pub fn new(conn: &mut PgConnection) -> QueryResult<Self> {
-        Ok(Self {
-            event_type_mapping: TaxaEventType::make_id_mapping(conn)?,
-            // fielder_location_mapping must appear before slot_mapping in the initializer
-            // (it doesn't matter what order it is in the struct declaration)
-            fielder_location_mapping: TaxaFielderLocation::make_id_mapping(conn)?,
-            slot_type_mapping: TaxaSlotType::make_id_mapping(conn)?,
-            slot_mapping: TaxaSlot::make_id_mapping(conn)?,
-            fair_ball_type_mapping: TaxaFairBallType::make_id_mapping(conn)?,
-            base_mapping: TaxaBase::make_id_mapping(conn)?,
-            base_description_format_mapping: TaxaBaseDescriptionFormat::make_id_mapping(conn)?,
-            fielding_error_type_mapping: TaxaFieldingErrorType::make_id_mapping(conn)?,
-            pitch_category_mapping: TaxaPitchCategory::make_id_mapping(conn)?,
-            pitch_type_mapping: TaxaPitchType::make_id_mapping(conn)?,
-            handedness_mapping: TaxaHandedness::make_id_mapping(conn)?,
-            day_type_mapping: TaxaDayType::make_id_mapping(conn)?,
-            league_mapping: TaxaLeagues::make_id_mapping(conn)?,
-            attribute_category_mapping: TaxaAttributeCategory::make_id_mapping(conn)?,
-            attribute_mapping: TaxaAttribute::make_id_mapping(conn)?,
-            effect_type_mapping: TaxaEffectType::make_id_mapping(conn)?,
-            effect_phase_mapping: TaxaEffectPhase::make_id_mapping(conn)?,
-            pitcher_change_source_mapping: TaxaPitcherChangeSource::make_id_mapping(conn)?,
-            modification_type_mapping: TaxaModificationType::make_id_mapping(conn)?,
+        let (taxa, reports) = Self::new_reconciled(conn, TaxaReconcileMode::Update)?;
+        for report in &reports {
+            report.log();
+        }
+        Ok(taxa)
+    }
+
+    /// [`Taxa::new_reconciled`] for admin tools. In
+    /// [`TaxaReconcileMode::Update`] mode, the changes are recorded in the
+    /// admin audit log in the same transaction.
+    pub fn new_reconciled_by(
+        conn: &mut PgConnection,
+        mode: TaxaReconcileMode,
+        actor: &str,
+    ) -> QueryResult<(Self, Vec<TaxaReconciliation>)> {
+        conn.transaction(|conn| {
+            let (taxa, reports) = Self::new_reconciled(conn, mode)?;
+            if mode != TaxaReconcileMode::Update {
+                return Ok((taxa, reports));
+            }
+
+            let mut rows_before = serde_json::Map::new();
+            let mut rows_after = serde_json::Map::new();
+            let mut values_before = Vec::new();
+            let mut values_after = Vec::new();
+            for report in reports.iter().filter(|r| !r.is_clean()) {
+                let table = format!("taxa.{}", report.table);
+                let count = count_taxa_rows(conn, report.table)?;
+                rows_before.insert(table.clone(), (count - report.inserted.len()).into());
+                rows_after.insert(table.clone(), count.into());
+                for mismatch in &report.mismatched {
+                    let value = |value: &serde_json::Value| {
+                        serde_json::json!({
+                            "table": table,
+                            "id": mismatch.id,
+                            "column": mismatch.column,
+                            "value": value,
+                        })
+                    };
+                    values_before.push(value(&mismatch.in_db));
+                    values_after.push(value(&mismatch.expected));
+                }
+            }
+
+            record_admin_action(
+                conn,
+                &NewAdminAudit {
+                    actor,
+                    action: "reconcile_taxa",
+                    details: serde_json::json!({
+                        "inserted": reports
+                            .iter()
+                            .filter(|r| !r.inserted.is_empty())
+                            .map(|r| (format!("taxa.{}", r.table), r.inserted.clone()))
+                            .collect::<HashMap<_, _>>(),
+                        "orphaned_rows": reports.iter().map(|r| r.orphaned.len()).sum::<usize>(),
+                    }),
+                    before: Some(serde_json::json!({
+                        "rows": rows_before,
+                        "values": values_before,
+                    })),
+                    after: Some(serde_json::json!({
+                        "rows": rows_after,
+                        "values": values_after,
+                    })),
+                },
+            )?;
+
+            Ok((taxa, reports))
         })
     }
 
@@ -1626,6 +1977,14 @@ impl Taxa {
         self.base_description_format_mapping[ty]
     }
 
+    pub fn base_movement_id(&self, ty: TaxaBaseMovement) -> i64 {
+        self.base_movement_mapping[ty]
+    }
+
+    pub fn count_id(&self, ty: TaxaCount) -> i64 {
+        self.count_mapping[ty]
+    }
+
     pub fn fielding_error_type_id(&self, ty: TaxaFieldingErrorType) -> i64 {
         self.fielding_error_type_mapping[ty]
     }
@@ -1657,7 +2016,7 @@ impl Taxa {
     pub fn effect_type_id(&self, ty: TaxaEffectType) -> i64 {
         self.effect_type_mapping[ty]
     }
-    
+
     pub fn effect_phase_id(&self, ty: TaxaEffectPhase) -> i64 {
         self.effect_phase_mapping[ty]
     }
@@ -1670,6 +2029,10 @@ impl Taxa {
         self.modification_type_mapping[ty]
     }
 
+    pub fn ingest_error_code_id(&self, ty: TaxaIngestErrorCode) -> i64 {
+        self.ingest_error_code_mapping[ty]
+    }
+
     pub fn event_type_from_id(&self, id: i64) -> Option<TaxaEventType> {
         self.event_type_mapping
             .iter()
@@ -1677,123 +2040,159 @@ impl Taxa {
             .map(|(val, _)| val)
     }
 
-    pub fn fielder_location_from_id(&self, id: i64) -> TaxaFielderLocation {
-        self.fielder_location_mapping
-            .iter()
-            .find(|(_, ty_id)| id == **ty_id)
-            .expect("TODO Handle unknown fielder location")
-            .0
+    pub fn fielder_location_from_id(
+        &self,
+        id: i64,
+    ) -> Result<TaxaFielderLocation, UnknownTaxaId> {
+        from_id(&self.fielder_location_mapping, "fielder_location", id)
     }
 
-    pub fn slot_type_from_id(&self, id: i64) -> TaxaSlotType {
-        self.slot_type_mapping
-            .iter()
-            .find(|(_, ty_id)| id == **ty_id)
-            .expect("TODO Handle unknown slot type")
-            .0
+    pub fn slot_type_from_id(&self, id: i64) -> Result<TaxaSlotType, UnknownTaxaId> {
+        from_id(&self.slot_type_mapping, "slot_type", id)
     }
 
-    pub fn slot_from_id(&self, id: i64) -> TaxaSlot {
-        self.slot_mapping
-            .iter()
-            .find(|(_, ty_id)| id == **ty_id)
-            .expect("TODO Handle unknown slot")
-            .0
+    pub fn slot_from_id(&self, id: i64) -> Result<TaxaSlot, UnknownTaxaId> {
+        from_id(&self.slot_mapping, "slot", id)
     }
 
-    pub fn fair_ball_type_from_id(&self, id: i64) -> TaxaFairBallType {
-        self.fair_ball_type_mapping
-            .iter()
-            .find(|(_, ty_id)| id == **ty_id)
-            .expect("TODO Handle unknown fair ball type")
-            .0
+    pub fn fair_ball_type_from_id(&self, id: i64) -> Result<TaxaFairBallType, UnknownTaxaId> {
+        from_id(&self.fair_ball_type_mapping, "fair_ball_type", id)
     }
 
-    pub fn base_from_id(&self, id: i64) -> TaxaBase {
-        self.base_mapping
-            .iter()
-            .find(|(_, ty_id)| id == **ty_id)
-            .expect("TODO Handle unknown base type")
-            .0
+    pub fn base_from_id(&self, id: i64) -> Result<TaxaBase, UnknownTaxaId> {
+        from_id(&self.base_mapping, "base", id)
     }
 
-    pub fn base_description_format_from_id(&self, id: i64) -> TaxaBaseDescriptionFormat {
-        self.base_description_format_mapping
-            .iter()
-            .find(|(_, ty_id)| id == **ty_id)
-            .expect("TODO Handle unknown base description format")
-            .0
+    pub fn base_description_format_from_id(
+        &self,
+        id: i64,
+    ) -> Result<TaxaBaseDescriptionFormat, UnknownTaxaId> {
+        from_id(
+            &self.base_description_format_mapping,
+            "base_description_format",
+            id,
+        )
     }
 
-    pub fn fielding_error_type_from_id(&self, id: i64) -> TaxaFieldingErrorType {
-        self.fielding_error_type_mapping
-            .iter()
-            .find(|(_, ty_id)| id == **ty_id)
-            .expect("TODO Handle unknown base description format")
-            .0
+    pub fn base_movement_from_id(&self, id: i64) -> Result<TaxaBaseMovement, UnknownTaxaId> {
+        from_id(&self.base_movement_mapping, "base_movement", id)
     }
 
-    pub fn pitch_type_from_id(&self, id: i64) -> TaxaPitchType {
-        self.pitch_type_mapping
-            .iter()
-            .find(|(_, ty_id)| id == **ty_id)
-            .expect("TODO Handle unknown pitch type")
-            .0
+    pub fn fielding_error_type_from_id(
+        &self,
+        id: i64,
+    ) -> Result<TaxaFieldingErrorType, UnknownTaxaId> {
+        from_id(&self.fielding_error_type_mapping, "fielding_error_type", id)
     }
 
-    pub fn handedness_from_id(&self, id: i64) -> TaxaHandedness {
-        self.handedness_mapping
-            .iter()
-            .find(|(_, ty_id)| id == **ty_id)
-            .expect("TODO Handle unknown handedness")
-            .0
+    pub fn pitch_type_from_id(&self, id: i64) -> Result<TaxaPitchType, UnknownTaxaId> {
+        from_id(&self.pitch_type_mapping, "pitch_type", id)
     }
 
-    pub fn attribute_category_from_id(&self, id: i64) -> TaxaAttributeCategory {
-        self.attribute_category_mapping
-            .iter()
-            .find(|(_, ty_id)| id == **ty_id)
-            .expect("TODO Handle unknown attribute category")
-            .0
+    pub fn handedness_from_id(&self, id: i64) -> Result<TaxaHandedness, UnknownTaxaId> {
+        from_id(&self.handedness_mapping, "handedness", id)
     }
 
-    pub fn attribute_from_id(&self, id: i64) -> TaxaAttribute {
-        self.attribute_mapping
-            .iter()
-            .find(|(_, ty_id)| id == **ty_id)
-            .expect("TODO Handle unknown attribute")
-            .0
+    pub fn attribute_category_from_id(
+        &self,
+        id: i64,
+    ) -> Result<TaxaAttributeCategory, UnknownTaxaId> {
+        from_id(&self.attribute_category_mapping, "attribute_category", id)
     }
 
-    pub fn effect_type_from_id(&self, id: i64) -> TaxaEffectType {
-        self.effect_type_mapping
-            .iter()
-            .find(|(_, ty_id)| id == **ty_id)
-            .expect("TODO Handle unknown effect type")
-            .0
+    pub fn attribute_from_id(&self, id: i64) -> Result<TaxaAttribute, UnknownTaxaId> {
+        from_id(&self.attribute_mapping, "attribute", id)
     }
 
-    pub fn effect_phase_from_id(&self, id: i64) -> TaxaEffectPhase {
-        self.effect_phase_mapping
-            .iter()
-            .find(|(_, ty_id)| id == **ty_id)
-            .expect("TODO Handle unknown effect phase")
-            .0
+    pub fn effect_type_from_id(&self, id: i64) -> Result<TaxaEffectType, UnknownTaxaId> {
+        from_id(&self.effect_type_mapping, "effect_type", id)
     }
 
-    pub fn day_type_from_id(&self, id: i64) -> TaxaDayType {
-        self.day_type_mapping
-            .iter()
-            .find(|(_, ty_id)| id == **ty_id)
-            .expect("TODO Handle unknown day type")
-            .0
+    pub fn effect_phase_from_id(&self, id: i64) -> Result<TaxaEffectPhase, UnknownTaxaId> {
+        from_id(&self.effect_phase_mapping, "effect_phase", id)
     }
 
-    pub fn modification_type_from_id(&self, id: i64) -> TaxaModificationType {
-        self.modification_type_mapping
-            .iter()
-            .find(|(_, ty_id)| id == **ty_id)
-            .expect("TODO Handle unknown modification type")
-            .0
+    pub fn day_type_from_id(&self, id: i64) -> Result<TaxaDayType, UnknownTaxaId> {
+        from_id(&self.day_type_mapping, "day_type", id)
+    }
+
+    pub fn modification_type_from_id(
+        &self,
+        id: i64,
+    ) -> Result<TaxaModificationType, UnknownTaxaId> {
+        from_id(&self.modification_type_mapping, "modification_type", id)
+    }
+
+    pub fn pitcher_change_source_from_id(
+        &self,
+        id: i64,
+    ) -> Result<TaxaPitcherChangeSource, UnknownTaxaId> {
+        from_id(
+            &self.pitcher_change_source_mapping,
+            "pitcher_change_source",
+            id,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::postgres_url_from_environment;
+
+    #[test]
+    fn ids_map_back_to_their_taxa_and_unknown_ids_are_errors() {
+        let url = postgres_url_from_environment();
+        let mut conn = PgConnection::establish(&url)
+            .expect("postgres_url_from_environment should return a valid connection string");
+
+        conn.test_transaction(|conn| {
+            let taxa = Taxa::new(conn)?;
+
+            for (attribute, &id) in taxa.attribute_mapping.iter() {
+                assert_eq!(taxa.attribute_from_id(id), Ok(attribute));
+            }
+            for (base, &id) in taxa.base_mapping.iter() {
+                assert_eq!(taxa.base_from_id(id), Ok(base));
+            }
+
+            assert_eq!(
+                taxa.attribute_from_id(-1),
+                Err(UnknownTaxaId {
+                    taxa: "attribute",
+                    id: -1,
+                }),
+            );
+            assert_eq!(
+                taxa.pitch_type_from_id(-1),
+                Err(UnknownTaxaId {
+                    taxa: "pitch_type",
+                    id: -1,
+                }),
+            );
+
+            Ok::<_, diesel::result::Error>(())
+        });
+    }
+
+    #[test]
+    fn checking_reconciled_tables_finds_nothing_missing_or_mismatched() {
+        let url = postgres_url_from_environment();
+        let mut conn = PgConnection::establish(&url)
+            .expect("postgres_url_from_environment should return a valid connection string");
+
+        conn.test_transaction(|conn| {
+            Taxa::new(conn)?;
+
+            for report in check_taxa_tables(conn)? {
+                assert!(
+                    report.inserted.is_empty() && report.mismatched.is_empty(),
+                    "taxa.{} still differs from its definition: {report:?}",
+                    report.table,
+                );
+            }
+
+            Ok::<_, diesel::result::Error>(())
+        });
     }
 }
@@ -0,0 +1,197 @@
+use diesel::prelude::*;
+use diesel::sql_types::{Int8, Jsonb};
+use std::collections::HashMap;
+use tracing::{info, warn};
+
+/// What to do with existing taxa rows that don't match the Rust definitions
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum TaxaReconcileMode {
+    /// Report mismatched rows without changing them. Missing rows are
+    /// still inserted, since the id mappings can't be built without them.
+    ReportOnly,
+    /// Report mismatched rows and overwrite their non-key columns with the
+    /// values from the Rust definitions
+    #[default]
+    Update,
+}
+
+/// A row in a taxa table whose id isn't used by any variant of the enum.
+/// These are usually left behind when a variant is removed or renumbered.
+#[derive(Debug, Clone)]
+pub struct TaxaOrphan {
+    pub id: i64,
+    /// Null if the row doesn't have a name, which should never happen
+    pub name: Option<String>,
+}
+
+/// One column of one taxa row that doesn't match the Rust definition
+#[derive(Debug, Clone)]
+pub struct TaxaMismatch {
+    pub id: i64,
+    pub name: &'static str,
+    pub column: String,
+    pub in_db: serde_json::Value,
+    pub expected: serde_json::Value,
+}
+
+/// How one taxa table differed from its Rust definition
+#[derive(Debug, Clone)]
+pub struct TaxaReconciliation {
+    pub table: &'static str,
+    /// Names of the variants whose rows were missing. They've been inserted,
+    /// unless this came from [`super::check_taxa_tables`], which never
+    /// writes.
+    pub inserted: Vec<&'static str>,
+    pub orphaned: Vec<TaxaOrphan>,
+    pub mismatched: Vec<TaxaMismatch>,
+    /// Whether the mismatched rows were updated to match
+    pub updated: bool,
+}
+
+impl TaxaReconciliation {
+    pub fn new(table: &'static str, mode: TaxaReconcileMode) -> Self {
+        Self {
+            table,
+            inserted: Vec::new(),
+            orphaned: Vec::new(),
+            mismatched: Vec::new(),
+            updated: mode == TaxaReconcileMode::Update,
+        }
+    }
+
+    /// True if the table already matched the Rust definition exactly
+    pub fn is_clean(&self) -> bool {
+        self.inserted.is_empty() && self.orphaned.is_empty() && self.mismatched.is_empty()
+    }
+
+    pub fn log(&self) {
+        let table = self.table;
+        for name in &self.inserted {
+            info!("Inserted new taxa.{table} row for {name}");
+        }
+        for orphan in &self.orphaned {
+            warn!(
+                "taxa.{table} row {} ({}) doesn't match any variant. It may be left over from \
+                a removed or renumbered variant.",
+                orphan.id,
+                orphan.name.as_deref().unwrap_or("<no name>"),
+            );
+        }
+        for mismatch in &self.mismatched {
+            let action = if self.updated { "Updated" } else { "Found mismatched" };
+            warn!(
+                "{action} taxa.{table}.{} for row {} ({}): database has {}, definition has {}",
+                mismatch.column, mismatch.id, mismatch.name, mismatch.in_db, mismatch.expected,
+            );
+        }
+    }
+}
+
+#[derive(QueryableByName)]
+pub(super) struct TaxaDbRow {
+    #[diesel(sql_type = Int8)]
+    pub id: i64,
+    #[diesel(sql_type = Jsonb)]
+    pub row: serde_json::Value,
+}
+
+/// Every row of `taxa.<table>`, as JSON objects keyed by column name
+pub(super) fn load_taxa_rows(conn: &mut PgConnection, table: &str) -> QueryResult<Vec<TaxaDbRow>> {
+    // Table names come from the taxa! invocations, never from users
+    diesel::sql_query(format!(
+        "select t.id, to_jsonb(t) as row from taxa.\"{table}\" t order by t.id"
+    ))
+    .load(conn)
+}
+
+#[derive(QueryableByName)]
+struct TaxaRowCount {
+    #[diesel(sql_type = Int8)]
+    count: i64,
+}
+
+/// How many rows `taxa.<table>` has
+pub(super) fn count_taxa_rows(conn: &mut PgConnection, table: &str) -> QueryResult<usize> {
+    diesel::sql_query(format!("select count(*) as count from taxa.\"{table}\""))
+        .get_result::<TaxaRowCount>(conn)
+        .map(|row| row.count as usize)
+}
+
+/// The rows left over after every variant claimed its row, in id order
+pub(super) fn orphaned_rows(rows: HashMap<i64, serde_json::Value>) -> Vec<TaxaOrphan> {
+    let mut orphaned = rows
+        .into_iter()
+        .map(|(id, row)| TaxaOrphan {
+            id,
+            name: row.get("name").and_then(|n| n.as_str()).map(str::to_string),
+        })
+        .collect::<Vec<_>>();
+    orphaned.sort_by_key(|orphan| orphan.id);
+    orphaned
+}
+
+/// Compares a row from the database with the row the Rust definition would
+/// insert. Columns that the definition doesn't set are ignored.
+pub(super) fn diff_taxa_row(
+    id: i64,
+    name: &'static str,
+    in_db: &serde_json::Value,
+    expected: &serde_json::Value,
+) -> Vec<TaxaMismatch> {
+    let Some(expected) = expected.as_object() else {
+        return Vec::new();
+    };
+
+    expected
+        .iter()
+        .filter_map(|(column, expected_value)| {
+            let db_value = in_db.get(column).unwrap_or(&serde_json::Value::Null);
+            // Compare numbers numerically so an i32 field matches an int8 column
+            let matches = match (db_value.as_f64(), expected_value.as_f64()) {
+                (Some(a), Some(b)) => a == b,
+                _ => db_value == expected_value,
+            };
+
+            (!matches).then(|| TaxaMismatch {
+                id,
+                name,
+                column: column.clone(),
+                in_db: db_value.clone(),
+                expected: expected_value.clone(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn diff_finds_only_changed_columns() {
+        let mismatches = diff_taxa_row(
+            3,
+            "Walk",
+            &json!({"id": 3, "name": "BB", "description": null, "is_hit": false}),
+            &json!({"id": 3, "name": "Walk", "description": null, "is_hit": false}),
+        );
+
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].column, "name");
+        assert_eq!(mismatches[0].in_db, json!("BB"));
+        assert_eq!(mismatches[0].expected, json!("Walk"));
+    }
+
+    #[test]
+    fn diff_ignores_columns_the_definition_doesnt_set() {
+        let mismatches = diff_taxa_row(
+            1,
+            "First",
+            &json!({"id": 1, "name": "First", "bases_achieved": 1, "extra": "x"}),
+            &json!({"id": 1, "name": "First", "bases_achieved": 1}),
+        );
+
+        assert!(mismatches.is_empty());
+    }
+}
@@ -0,0 +1,913 @@
+use crate::QueryError;
+use crate::db::record_admin_action;
+use crate::models::NewAdminAudit;
+use diesel::prelude::*;
+use diesel::sql_types::{Array, Int8, Text};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use thiserror::Error;
+use tracing::info;
+
+use super::reconcile::{count_taxa_rows, load_taxa_rows};
+
+/// One entry in a renumbering file: the row of `taxa.<table>` named `name`
+/// should have id `to` instead of `from`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TaxaRenumbering {
+    pub table: String,
+    /// The row's `name`. This guards against moving the wrong row, and it's
+    /// how an entry that was already applied is recognized.
+    pub name: String,
+    pub from: i64,
+    pub to: i64,
+}
+
+#[derive(Debug, Error)]
+pub enum TaxaRenumberError {
+    #[error(transparent)]
+    Db(#[from] QueryError),
+
+    #[error("taxa.{0} doesn't exist")]
+    UnknownTable(String),
+
+    #[error("taxa.{table} id {id} appears in more than one entry")]
+    DuplicateId { table: String, id: i64 },
+
+    #[error("taxa.{table} row {id} is named {actual:?}, not {expected:?}")]
+    WrongName {
+        table: String,
+        id: i64,
+        expected: String,
+        actual: Option<String>,
+    },
+
+    #[error("taxa.{table} has no row {from} to renumber, and no row {to} named {name:?}")]
+    Missing {
+        table: String,
+        name: String,
+        from: i64,
+        to: i64,
+    },
+
+    #[error(
+        "taxa.{table} id {to} is already used by {existing:?}, which isn't being renumbered \
+        out of the way"
+    )]
+    Conflict {
+        table: String,
+        to: i64,
+        existing: Option<String>,
+    },
+
+    #[error(
+        "{column} references taxa.{table} with a multi-column foreign key ({constraint}), \
+        which can't be renumbered automatically"
+    )]
+    CompositeForeignKey {
+        table: String,
+        column: String,
+        constraint: String,
+    },
+
+    #[error(
+        "{column} references taxa.{table} ({constraint}) but isn't in TAXA_REFERENCES, so it \
+        wouldn't be renumbered"
+    )]
+    UnmappedForeignKey {
+        table: String,
+        column: String,
+        constraint: String,
+    },
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum TaxaReferenceKind {
+    /// A bigint column with a foreign key to the taxa table
+    ForeignKey,
+    /// A sorted bigint[] column. Postgres can't put foreign keys on array
+    /// elements, so nothing in the catalog says these refer to taxa.
+    SortedArray,
+}
+
+use TaxaReferenceKind::{ForeignKey, SortedArray};
+
+/// Every column that holds taxa ids, as (taxa table, referencing table,
+/// column, kind). Renumbering only updates the columns listed here, and it
+/// refuses to run if a foreign key to the taxa table isn't listed, so a
+/// migration that adds a column referring to taxa has to add it here too.
+/// Materialized views aren't listed because they're refreshed afterwards.
+#[rustfmt::skip]
+const TAXA_REFERENCES: &[(&str, &str, &str, TaxaReferenceKind)] = &[
+    ("attribute", "data.attribute_distributions", "attribute", ForeignKey),
+    ("attribute", "data.efflorescence_growth", "attribute", ForeignKey),
+    ("attribute", "data.modification_effects", "attribute", ForeignKey),
+    ("attribute", "data.parties", "attribute", ForeignKey),
+    ("attribute", "data.player_attribute_augments", "attribute", ForeignKey),
+    ("attribute", "data.player_equipment_effect_versions", "attribute", ForeignKey),
+    ("attribute", "data.player_paradigm_shifts", "attribute", ForeignKey),
+    ("attribute", "data.player_report_attribute_versions", "attribute", ForeignKey),
+    ("attribute", "data.player_report_versions", "included_attributes", SortedArray),
+    ("attribute_category", "data.player_report_attribute_versions", "category", ForeignKey),
+    ("attribute_category", "data.player_report_versions", "category", ForeignKey),
+    ("attribute_category", "data.player_versions", "included_report_categories", SortedArray),
+    ("attribute_category", "taxa.attribute", "category", ForeignKey),
+    ("attribute_effect_phase", "data.player_equipment_effect_versions", "phase", ForeignKey),
+    ("attribute_effect_type", "data.modification_effects", "effect_type", ForeignKey),
+    ("attribute_effect_type", "data.player_equipment_effect_versions", "effect_type", ForeignKey),
+    ("base", "data.defense_outcomes", "hit_base", ForeignKey),
+    ("base", "data.event_baserunners", "base_after", ForeignKey),
+    ("base", "data.event_baserunners", "base_before", ForeignKey),
+    ("base", "data.events", "hit_base", ForeignKey),
+    ("base", "data.offense_outcomes", "hit_base", ForeignKey),
+    ("base_description_format", "data.event_baserunners", "base_description_format", ForeignKey),
+    ("count", "data.event_pitch_sequence", "count_before", ForeignKey),
+    ("day_type", "data.player_attribute_augments", "day_type", ForeignKey),
+    ("day_type", "data.player_paradigm_shifts", "day_type", ForeignKey),
+    ("day_type", "data.player_recompositions", "day_type", ForeignKey),
+    ("day_type", "data.player_report_versions", "day_type", ForeignKey),
+    ("day_type", "data.player_versions", "birthday_type", ForeignKey),
+    ("day_type", "data.season_phases", "day_type", ForeignKey),
+    ("event_type", "data.defense_outcomes", "event_type", ForeignKey),
+    ("event_type", "data.events", "event_type", ForeignKey),
+    ("event_type", "data.offense_outcomes", "event_type", ForeignKey),
+    ("fair_ball_type", "data.events", "fair_ball_type", ForeignKey),
+    ("fielder_location", "data.defense_outcomes", "fair_ball_direction", ForeignKey),
+    ("fielder_location", "data.events", "fair_ball_direction", ForeignKey),
+    ("fielder_location", "data.offense_outcomes", "fair_ball_direction", ForeignKey),
+    ("fielder_location", "taxa.slot", "location", ForeignKey),
+    ("fielding_error_type", "data.defense_outcomes", "fielding_error_type", ForeignKey),
+    ("fielding_error_type", "data.events", "fielding_error_type", ForeignKey),
+    ("fielding_error_type", "data.offense_outcomes", "fielding_error_type", ForeignKey),
+    ("handedness", "data.player_versions", "batting_handedness", ForeignKey),
+    ("handedness", "data.player_versions", "pitching_handedness", ForeignKey),
+    ("ingest_error_code", "info.event_ingest_log", "error_code", ForeignKey),
+    ("ingest_error_code", "info.version_ingest_log", "error_code", ForeignKey),
+    ("leagues", "data.superstar_game_players", "source_league", ForeignKey),
+    ("leagues", "data.superstar_game_players", "superstar_league", ForeignKey),
+    ("modification_type", "data.player_modification_versions", "modification_type", ForeignKey),
+    ("pitch_category", "data.player_pitch_category_bonus_versions", "pitch_category", ForeignKey),
+    ("pitch_category", "data.player_versions", "included_pitch_category_bonuses", SortedArray),
+    ("pitch_category", "taxa.pitch_type", "category", ForeignKey),
+    ("pitch_type", "data.events", "pitch_type", ForeignKey),
+    ("pitch_type", "data.player_pitch_type_bonus_versions", "pitch_type", ForeignKey),
+    ("pitch_type", "data.player_pitch_type_versions", "pitch_type", ForeignKey),
+    ("pitch_type", "data.player_versions", "included_pitch_type_bonuses", SortedArray),
+    ("pitcher_change_source", "data.pitcher_appearances", "replaced_by_source", ForeignKey),
+    ("pitcher_change_source", "data.pitcher_changes", "source", ForeignKey),
+    ("slot", "data.aurora_photos", "player_slot", ForeignKey),
+    ("slot", "data.ejections", "ejected_player_slot", ForeignKey),
+    ("slot", "data.ejections", "replacement_player_slot", ForeignKey),
+    ("slot", "data.event_fielders", "fielder_slot", ForeignKey),
+    ("slot", "data.pitcher_changes", "new_pitcher_slot", ForeignKey),
+    ("slot", "data.pitcher_changes", "pitcher_slot", ForeignKey),
+    ("slot", "data.player_versions", "slot", ForeignKey),
+    ("slot", "data.team_player_versions", "slot", ForeignKey),
+    ("slot", "data.wither", "player_slot", ForeignKey),
+    ("slot_type", "data.player_equipment_versions", "prefix_position_type", ForeignKey),
+];
+
+/// One column that refers to a taxa table
+#[derive(Debug, Copy, Clone)]
+struct TaxaReference {
+    /// `schema.table`
+    table: &'static str,
+    column: &'static str,
+    kind: TaxaReferenceKind,
+}
+
+/// Quotes a `schema.table` name
+fn quote_table(table: &str) -> String {
+    format!("\"{}\"", table.replace('.', "\".\""))
+}
+
+impl TaxaReference {
+    fn quoted_table(&self) -> String {
+        quote_table(self.table)
+    }
+
+    fn name(&self) -> String {
+        format!("{}.{}", self.table, self.column)
+    }
+
+    fn has_foreign_key(&self, foreign_keys: &[ForeignKeyRow]) -> bool {
+        foreign_keys
+            .iter()
+            .any(|fk| fk.table_name == self.table && fk.column_names == [self.column])
+    }
+}
+
+fn references_to(taxa_table: &str) -> Vec<TaxaReference> {
+    TAXA_REFERENCES
+        .iter()
+        .filter(|(t, _, _, _)| *t == taxa_table)
+        .map(|(_, table, column, kind)| TaxaReference {
+            table,
+            column,
+            kind: *kind,
+        })
+        .collect()
+}
+
+/// What will be done for one renumbering entry
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaxaRenumberAction {
+    /// The row and everything referencing it move to the new id
+    Move,
+    /// A row with the new id and the same name already exists, which
+    /// happens when the new build started before the renumbering was
+    /// applied. References move to the existing row and the old row is
+    /// deleted.
+    Merge,
+    /// This entry was already applied
+    AlreadyDone,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TaxaRenumberPlanEntry {
+    pub renumbering: TaxaRenumbering,
+    pub action: TaxaRenumberAction,
+}
+
+/// How many rows one referencing column had renumbered
+#[derive(Debug, Clone, Serialize)]
+pub struct TaxaReferenceUpdate {
+    /// `schema.table.column`
+    pub column: String,
+    pub rows_updated: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TaxaRenumberReport {
+    pub table: String,
+    pub plan: Vec<TaxaRenumberPlanEntry>,
+    pub references: Vec<TaxaReferenceUpdate>,
+}
+
+/// Decides what to do with each entry for one table, given the table's
+/// current rows (id to name). Fails without changing anything if any entry
+/// conflicts with the table or with another entry.
+fn plan_table_renumbering(
+    table: &str,
+    rows: &HashMap<i64, Option<String>>,
+    entries: &[&TaxaRenumbering],
+) -> Result<Vec<TaxaRenumberPlanEntry>, TaxaRenumberError> {
+    let entries = entries
+        .iter()
+        .filter(|entry| entry.from != entry.to)
+        .collect_vec();
+
+    let mut seen_from = HashSet::new();
+    let mut seen_to = HashSet::new();
+    for entry in &entries {
+        if !seen_from.insert(entry.from) {
+            return Err(TaxaRenumberError::DuplicateId {
+                table: table.to_string(),
+                id: entry.from,
+            });
+        }
+        if !seen_to.insert(entry.to) {
+            return Err(TaxaRenumberError::DuplicateId {
+                table: table.to_string(),
+                id: entry.to,
+            });
+        }
+    }
+
+    let has_name = |id: i64, name: &str| {
+        rows.get(&id)
+            .is_some_and(|row_name| row_name.as_deref() == Some(name))
+    };
+
+    entries
+        .into_iter()
+        .map(|entry| {
+            let action = match rows.get(&entry.from) {
+                Some(from_name) if from_name.as_deref() != Some(entry.name.as_str()) => {
+                    return Err(TaxaRenumberError::WrongName {
+                        table: table.to_string(),
+                        id: entry.from,
+                        expected: entry.name.clone(),
+                        actual: from_name.clone(),
+                    });
+                }
+                Some(_) => match rows.get(&entry.to) {
+                    // The row at `to` is moving somewhere else
+                    _ if seen_from.contains(&entry.to) => TaxaRenumberAction::Move,
+                    None => TaxaRenumberAction::Move,
+                    Some(_) if has_name(entry.to, &entry.name) => TaxaRenumberAction::Merge,
+                    Some(existing) => {
+                        return Err(TaxaRenumberError::Conflict {
+                            table: table.to_string(),
+                            to: entry.to,
+                            existing: existing.clone(),
+                        });
+                    }
+                },
+                None if has_name(entry.to, &entry.name) => TaxaRenumberAction::AlreadyDone,
+                None => {
+                    return Err(TaxaRenumberError::Missing {
+                        table: table.to_string(),
+                        name: entry.name.clone(),
+                        from: entry.from,
+                        to: entry.to,
+                    });
+                }
+            };
+
+            Ok(TaxaRenumberPlanEntry {
+                renumbering: (*entry).clone(),
+                action,
+            })
+        })
+        .collect()
+}
+
+#[derive(QueryableByName)]
+struct ForeignKeyRow {
+    #[diesel(sql_type = Text)]
+    constraint_name: String,
+    #[diesel(sql_type = Text)]
+    table_name: String,
+    #[diesel(sql_type = Array<Text>)]
+    column_names: Vec<String>,
+}
+
+/// Every foreign key that references `taxa.<table>`. `table_name` is
+/// `schema.table`.
+fn foreign_keys_referencing(
+    conn: &mut PgConnection,
+    table: &str,
+) -> QueryResult<Vec<ForeignKeyRow>> {
+    diesel::sql_query(
+        "
+        select
+            c.conname::text as constraint_name,
+            n.nspname || '.' || r.relname as table_name,
+            array(
+                select a.attname::text
+                from unnest(c.conkey) k(attnum)
+                join pg_attribute a on a.attrelid=c.conrelid and a.attnum=k.attnum
+            ) as column_names
+        from pg_constraint c
+        join pg_class r on r.oid=c.conrelid
+        join pg_namespace n on n.oid=r.relnamespace
+        where c.contype='f' and c.confrelid=('taxa.' || quote_ident($1))::regclass
+        order by table_name, constraint_name
+    ",
+    )
+    .bind::<Text, _>(table)
+    .load(conn)
+}
+
+/// Makes sure every foreign key to `taxa.<table>` is one that
+/// [`TAXA_REFERENCES`] knows about, so no references get left behind
+fn check_foreign_keys(
+    foreign_keys: &[ForeignKeyRow],
+    table: &str,
+    references: &[TaxaReference],
+) -> Result<(), TaxaRenumberError> {
+    for fk in foreign_keys {
+        let [column] = fk.column_names.as_slice() else {
+            return Err(TaxaRenumberError::CompositeForeignKey {
+                table: table.to_string(),
+                column: format!("{}({})", fk.table_name, fk.column_names.join(", ")),
+                constraint: fk.constraint_name.clone(),
+            });
+        };
+
+        let mapped = references.iter().any(|r| {
+            r.kind == TaxaReferenceKind::ForeignKey
+                && r.table == fk.table_name
+                && r.column == column
+        });
+        if !mapped {
+            return Err(TaxaRenumberError::UnmappedForeignKey {
+                table: table.to_string(),
+                column: format!("{}.{column}", fk.table_name),
+                constraint: fk.constraint_name.clone(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(QueryableByName)]
+struct TableExists {
+    #[diesel(sql_type = diesel::sql_types::Bool)]
+    exists: bool,
+}
+
+fn taxa_table_exists(conn: &mut PgConnection, table: &str) -> QueryResult<bool> {
+    diesel::sql_query(
+        "select exists(select 1 from pg_tables where schemaname='taxa' and tablename=$1) as exists",
+    )
+    .bind::<Text, _>(table)
+    .get_result::<TableExists>(conn)
+    .map(|row| row.exists)
+}
+
+#[derive(QueryableByName)]
+struct RowCount {
+    #[diesel(sql_type = Int8)]
+    count: i64,
+}
+
+/// How many rows of `reference` have one of the `from` ids
+fn count_references(
+    conn: &mut PgConnection,
+    reference: &TaxaReference,
+    from: &[i64],
+) -> QueryResult<usize> {
+    let matches = match reference.kind {
+        TaxaReferenceKind::ForeignKey => "= any($1)",
+        TaxaReferenceKind::SortedArray => "&& $1",
+    };
+    diesel::sql_query(format!(
+        "select count(*) as count from {} where \"{}\" {matches}",
+        reference.quoted_table(),
+        reference.column,
+    ))
+    .bind::<Array<Int8>, _>(from)
+    .get_result::<RowCount>(conn)
+    .map(|row| row.count as usize)
+}
+
+/// Sets the new id in up to `batch_size` rows of `reference` that have
+/// one of the old ids. Returns the number of rows updated.
+fn remap_batch(
+    conn: &mut PgConnection,
+    reference: &TaxaReference,
+    from: &[i64],
+    to: &[i64],
+    batch_size: i64,
+) -> QueryResult<usize> {
+    let table = reference.quoted_table();
+    let column = reference.column;
+    let query = match reference.kind {
+        TaxaReferenceKind::ForeignKey => format!(
+            "with batch as (select ctid from {table} where \"{column}\" = any($1) limit $3) \
+            update {table} t set \"{column}\"=m.new_id \
+            from batch b, unnest($1, $2) as m(old_id, new_id) \
+            where t.ctid=b.ctid and t.\"{column}\"=m.old_id"
+        ),
+        TaxaReferenceKind::SortedArray => format!(
+            "with batch as (select ctid from {table} where \"{column}\" && $1 limit $3) \
+            update {table} t set \"{column}\"=array( \
+                select coalesce(m.new_id, x.id) \
+                from unnest(t.\"{column}\") as x(id) \
+                left join unnest($1, $2) as m(old_id, new_id) on m.old_id=x.id \
+                order by 1 \
+            ) \
+            from batch b \
+            where t.ctid=b.ctid"
+        ),
+    };
+
+    diesel::sql_query(query)
+        .bind::<Array<Int8>, _>(from)
+        .bind::<Array<Int8>, _>(to)
+        .bind::<Int8, _>(batch_size)
+        .execute(conn)
+}
+
+/// Moves every reference from the old ids to the new ones, one batch per
+/// transaction, so no table stays locked for long
+fn remap_references(
+    conn: &mut PgConnection,
+    references: &[TaxaReference],
+    from: &[i64],
+    to: &[i64],
+    batch_size: i64,
+) -> QueryResult<Vec<TaxaReferenceUpdate>> {
+    references
+        .iter()
+        .map(|reference| {
+            let mut rows_updated = 0;
+            loop {
+                let updated = remap_batch(conn, reference, from, to, batch_size)?;
+                if updated == 0 {
+                    break;
+                }
+                rows_updated += updated;
+            }
+
+            Ok(TaxaReferenceUpdate {
+                column: reference.name(),
+                rows_updated,
+            })
+        })
+        .collect()
+}
+
+fn foreign_key_name(reference: &TaxaReference) -> String {
+    let (_, table) = reference
+        .table
+        .split_once('.')
+        .unwrap_or(("", reference.table));
+    format!("{table}_{}_fkey", reference.column)
+}
+
+/// Adds back the foreign key from `reference` to `taxa.<table>` if it isn't
+/// there. It's added as `not valid` and then validated, which checks the
+/// existing rows without blocking reads or writes.
+fn restore_foreign_key(
+    conn: &mut PgConnection,
+    foreign_keys: &[ForeignKeyRow],
+    table: &str,
+    reference: &TaxaReference,
+) -> QueryResult<()> {
+    if reference.has_foreign_key(foreign_keys) {
+        return Ok(());
+    }
+
+    let name = foreign_key_name(reference);
+    diesel::sql_query(format!(
+        "alter table {} add constraint \"{name}\" \
+        foreign key (\"{}\") references taxa.\"{table}\" (id) not valid",
+        reference.quoted_table(),
+        reference.column,
+    ))
+    .execute(conn)?;
+    diesel::sql_query(format!(
+        "alter table {} validate constraint \"{name}\"",
+        reference.quoted_table(),
+    ))
+    .execute(conn)?;
+
+    Ok(())
+}
+
+/// Applies one table's plan.
+///
+/// Foreign keys can't be deferred unless they were declared deferrable,
+/// which ours aren't, so they're dropped for the duration and added back at
+/// the end. References go through temporary negative ids, which taxa never
+/// uses, so that swaps don't collide with themselves partway through. Each
+/// entry's temporary id comes from its position in the plan, so a run that
+/// was interrupted can be finished by running the same file again: a
+/// dropped foreign key means the last run didn't finish.
+///
+/// Each step commits on its own, and the references are updated in batches,
+/// so nothing is locked for more than a moment. Until it finishes, the rows
+/// being moved show up without their taxa.
+fn renumber_table(
+    conn: &mut PgConnection,
+    table: &str,
+    plan: &[TaxaRenumberPlanEntry],
+    batch_size: i64,
+) -> Result<Vec<TaxaReferenceUpdate>, TaxaRenumberError> {
+    let references = references_to(table);
+    let foreign_keys = foreign_keys_referencing(conn, table)?;
+    check_foreign_keys(&foreign_keys, table, &references)?;
+
+    let temp = (1..=plan.len() as i64).map(|i| -i).collect_vec();
+    let to = plan.iter().map(|e| e.renumbering.to).collect_vec();
+    let to_apply = plan
+        .iter()
+        .zip(&temp)
+        .filter(|(entry, _)| entry.action != TaxaRenumberAction::AlreadyDone)
+        .collect_vec();
+    let interrupted = references.iter().any(|reference| {
+        reference.kind == TaxaReferenceKind::ForeignKey && !reference.has_foreign_key(&foreign_keys)
+    });
+    if to_apply.is_empty() && !interrupted {
+        return Ok(Vec::new());
+    }
+
+    for fk in &foreign_keys {
+        diesel::sql_query(format!(
+            "alter table {} drop constraint \"{}\"",
+            quote_table(&fk.table_name),
+            fk.constraint_name,
+        ))
+        .execute(conn)?;
+    }
+
+    let applied_from = to_apply
+        .iter()
+        .map(|(e, _)| e.renumbering.from)
+        .collect_vec();
+    let applied_temp = to_apply.iter().map(|(_, temp)| **temp).collect_vec();
+    let updates = remap_references(conn, &references, &applied_from, &applied_temp, batch_size)?;
+
+    let (moved, merged): (Vec<_>, Vec<_>) = to_apply
+        .iter()
+        .partition(|(entry, _)| entry.action == TaxaRenumberAction::Move);
+    let moved_from = moved.iter().map(|(e, _)| e.renumbering.from).collect_vec();
+    let moved_temp = moved.iter().map(|(_, temp)| **temp).collect_vec();
+    let moved_to = moved.iter().map(|(e, _)| e.renumbering.to).collect_vec();
+    let merged_from = merged.iter().map(|(e, _)| e.renumbering.from).collect_vec();
+    conn.transaction(|conn| {
+        let taxa_table = format!("taxa.\"{table}\"");
+        for (from, to) in [(&moved_from, &moved_temp), (&moved_temp, &moved_to)] {
+            diesel::sql_query(format!(
+                "update {taxa_table} t set id=m.new_id \
+                from unnest($1, $2) as m(old_id, new_id) \
+                where t.id=m.old_id"
+            ))
+            .bind::<Array<Int8>, _>(from)
+            .bind::<Array<Int8>, _>(to)
+            .execute(conn)?;
+        }
+        diesel::sql_query(format!("delete from {taxa_table} where id = any($1)"))
+            .bind::<Array<Int8>, _>(&merged_from)
+            .execute(conn)?;
+        Ok::<_, QueryError>(())
+    })?;
+
+    // This includes every entry, to finish a run that was interrupted after
+    // the taxa table was updated
+    remap_references(conn, &references, &temp, &to, batch_size)?;
+
+    let remaining_foreign_keys = foreign_keys_referencing(conn, table)?;
+    for reference in &references {
+        if reference.kind == TaxaReferenceKind::ForeignKey {
+            restore_foreign_key(conn, &remaining_foreign_keys, table, reference)?;
+        }
+    }
+
+    Ok(updates)
+}
+
+/// Changes the ids of taxa rows and every column in [`TAXA_REFERENCES`]
+/// that refers to them. Every entry is checked against the database before
+/// anything changes, and entries that were already applied are skipped, so
+/// running the same file twice is safe. Running it again also finishes a run
+/// that was interrupted.
+///
+/// The caller must hold the ingest lock, since an ingest writing taxa ids
+/// while they're being renumbered would leave references to the old ids.
+/// Materialized views need to be refreshed afterwards.
+///
+/// If `dry_run` is true, nothing is changed, and the report says how many
+/// rows of each column would be updated.
+pub fn renumber_taxa(
+    conn: &mut PgConnection,
+    renumberings: &[TaxaRenumbering],
+    dry_run: bool,
+    batch_size: i64,
+    actor: &str,
+) -> Result<Vec<TaxaRenumberReport>, TaxaRenumberError> {
+    let by_table = renumberings.iter().into_group_map_by(|r| r.table.as_str());
+
+    // Check every table before changing any of them
+    let mut plans = Vec::new();
+    let mut rows_before = serde_json::Map::new();
+    for (table, entries) in by_table.iter().sorted_by_key(|(table, _)| **table) {
+        if !taxa_table_exists(conn, table)? {
+            return Err(TaxaRenumberError::UnknownTable(table.to_string()));
+        }
+
+        let rows = load_taxa_rows(conn, table)?
+            .into_iter()
+            .map(|row| {
+                let name = row
+                    .row
+                    .get("name")
+                    .and_then(|n| n.as_str())
+                    .map(str::to_string);
+                (row.id, name)
+            })
+            .collect::<HashMap<_, _>>();
+        rows_before.insert(format!("taxa.{table}"), rows.len().into());
+        let plan = plan_table_renumbering(table, &rows, entries)?;
+        let foreign_keys = foreign_keys_referencing(conn, table)?;
+        check_foreign_keys(&foreign_keys, table, &references_to(table))?;
+        for entry in &plan {
+            info!(
+                "taxa.{table} {} ({} -> {}): {:?}",
+                entry.renumbering.name, entry.renumbering.from, entry.renumbering.to, entry.action,
+            );
+        }
+        plans.push((table.to_string(), plan));
+    }
+
+    let mut reports = Vec::new();
+    for (table, plan) in plans {
+        let references = if dry_run {
+            let from = plan
+                .iter()
+                .filter(|entry| entry.action != TaxaRenumberAction::AlreadyDone)
+                .map(|entry| entry.renumbering.from)
+                .collect_vec();
+            references_to(&table)
+                .iter()
+                .map(|reference| {
+                    Ok(TaxaReferenceUpdate {
+                        column: reference.name(),
+                        rows_updated: count_references(conn, reference, &from)?,
+                    })
+                })
+                .collect::<QueryResult<Vec<_>>>()?
+        } else {
+            renumber_table(conn, &table, &plan, batch_size)?
+        };
+        reports.push(TaxaRenumberReport {
+            table,
+            plan,
+            references,
+        });
+    }
+
+    if !dry_run {
+        // Merged rows are deleted, so this can go down
+        let mut rows_after = serde_json::Map::new();
+        for report in &reports {
+            let count = count_taxa_rows(conn, &report.table)?;
+            rows_after.insert(format!("taxa.{}", report.table), count.into());
+        }
+        record_admin_action(
+            conn,
+            &NewAdminAudit {
+                actor,
+                action: "renumber_taxa",
+                details: serde_json::json!({ "tables": reports }),
+                before: Some(serde_json::json!({ "rows": rows_before })),
+                after: Some(serde_json::json!({ "rows": rows_after })),
+            },
+        )?;
+    }
+
+    Ok(reports)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::postgres_url_from_environment;
+
+    fn entry(name: &str, from: i64, to: i64) -> TaxaRenumbering {
+        TaxaRenumbering {
+            table: "slot".to_string(),
+            name: name.to_string(),
+            from,
+            to,
+        }
+    }
+
+    fn rows(rows: &[(i64, &str)]) -> HashMap<i64, Option<String>> {
+        rows.iter()
+            .map(|(id, name)| (*id, Some(name.to_string())))
+            .collect()
+    }
+
+    fn actions(
+        rows: &HashMap<i64, Option<String>>,
+        entries: &[TaxaRenumbering],
+    ) -> Result<Vec<TaxaRenumberAction>, TaxaRenumberError> {
+        let entries = entries.iter().collect_vec();
+        plan_table_renumbering("slot", rows, &entries)
+            .map(|plan| plan.into_iter().map(|e| e.action).collect())
+    }
+
+    #[test]
+    fn plans_moves_and_swaps() {
+        let rows = rows(&[(1, "A"), (2, "B")]);
+        assert_eq!(
+            actions(&rows, &[entry("A", 1, 3)]).unwrap(),
+            vec![TaxaRenumberAction::Move],
+        );
+        assert_eq!(
+            actions(&rows, &[entry("A", 1, 2), entry("B", 2, 1)]).unwrap(),
+            vec![TaxaRenumberAction::Move, TaxaRenumberAction::Move],
+        );
+    }
+
+    #[test]
+    fn recognizes_applied_and_merged_entries() {
+        // Already applied
+        let applied = rows(&[(3, "A")]);
+        assert_eq!(
+            actions(&applied, &[entry("A", 1, 3)]).unwrap(),
+            vec![TaxaRenumberAction::AlreadyDone],
+        );
+
+        // The new build already inserted a row at the new id
+        let duplicated = rows(&[(1, "A"), (3, "A")]);
+        assert_eq!(
+            actions(&duplicated, &[entry("A", 1, 3)]).unwrap(),
+            vec![TaxaRenumberAction::Merge],
+        );
+    }
+
+    #[test]
+    fn rejects_conflicts() {
+        let rows = rows(&[(1, "A"), (2, "B")]);
+        assert!(matches!(
+            actions(&rows, &[entry("A", 1, 2)]),
+            Err(TaxaRenumberError::Conflict { to: 2, .. }),
+        ));
+        assert!(matches!(
+            actions(&rows, &[entry("B", 1, 3)]),
+            Err(TaxaRenumberError::WrongName { id: 1, .. }),
+        ));
+        assert!(matches!(
+            actions(&rows, &[entry("C", 5, 6)]),
+            Err(TaxaRenumberError::Missing { from: 5, .. }),
+        ));
+        assert!(matches!(
+            actions(&rows, &[entry("A", 1, 3), entry("B", 2, 3)]),
+            Err(TaxaRenumberError::DuplicateId { id: 3, .. }),
+        ));
+    }
+
+    #[test]
+    fn every_foreign_key_to_taxa_is_mapped() {
+        #[derive(QueryableByName)]
+        struct TaxaTable {
+            #[diesel(sql_type = Text)]
+            tablename: String,
+        }
+
+        let url = postgres_url_from_environment();
+        let mut conn = PgConnection::establish(&url)
+            .expect("postgres_url_from_environment should return a valid connection string");
+
+        let tables =
+            diesel::sql_query("select tablename::text from pg_tables where schemaname='taxa'")
+                .load::<TaxaTable>(&mut conn)
+                .unwrap();
+        for table in tables {
+            let foreign_keys = foreign_keys_referencing(&mut conn, &table.tablename).unwrap();
+            check_foreign_keys(
+                &foreign_keys,
+                &table.tablename,
+                &references_to(&table.tablename),
+            )
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn swaps_references_and_restores_foreign_keys() {
+        #[derive(QueryableByName, Debug, PartialEq)]
+        struct SlotLocation {
+            #[diesel(sql_type = Int8)]
+            id: i64,
+            #[diesel(sql_type = diesel::sql_types::Nullable<Int8>)]
+            location: Option<i64>,
+        }
+
+        let url = postgres_url_from_environment();
+        let mut conn = PgConnection::establish(&url)
+            .expect("postgres_url_from_environment should return a valid connection string");
+
+        conn.test_transaction(|conn| {
+            let slot_locations = |conn: &mut PgConnection| {
+                diesel::sql_query("select id, location from taxa.slot order by id")
+                    .load::<SlotLocation>(conn)
+            };
+
+            let locations = load_taxa_rows(conn, "fielder_location")?;
+            let [a, b] = [&locations[0], &locations[1]].map(|row| TaxaRenumbering {
+                table: "fielder_location".to_string(),
+                name: row.row["name"].as_str().unwrap_or_default().to_string(),
+                from: row.id,
+                to: row.id,
+            });
+            let swap = [
+                TaxaRenumbering {
+                    to: b.from,
+                    ..a.clone()
+                },
+                TaxaRenumbering {
+                    to: a.from,
+                    ..b.clone()
+                },
+            ];
+
+            let before = slot_locations(conn)?;
+            // A batch size of 1 makes sure the batching loops
+            let reports = renumber_taxa(conn, &swap, false, 1, "test")?;
+            assert_eq!(reports[0].plan.len(), 2);
+
+            let swapped = |location: Option<i64>| match location {
+                Some(id) if id == a.from => Some(b.from),
+                Some(id) if id == b.from => Some(a.from),
+                other => other,
+            };
+            let expected = before
+                .into_iter()
+                .map(|slot| SlotLocation {
+                    id: slot.id,
+                    location: swapped(slot.location),
+                })
+                .collect_vec();
+            assert_eq!(slot_locations(conn)?, expected);
+
+            let foreign_keys = foreign_keys_referencing(conn, "fielder_location")?;
+            for reference in references_to("fielder_location") {
+                assert!(
+                    reference.has_foreign_key(&foreign_keys),
+                    "{}",
+                    reference.name()
+                );
+            }
+
+            Ok::<_, TaxaRenumberError>(())
+        });
+    }
+}
@@ -0,0 +1,169 @@
+use super::TaxaAttributeCategory;
+
+/// How attribute totals in one attribute category were converted to stars,
+/// starting in some season
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct StarScale {
+    /// The first season this scale applies to, or None if it applies from
+    /// the beginning
+    pub since_season: Option<i32>,
+    /// The amount of total that earns one star
+    pub total_per_star: f64,
+}
+
+// Every category has used the same scale so far. When MMOLB changes one,
+// add the new scale to the end of that category's list.
+const BATTING_SCALES: &[StarScale] = &[StarScale {
+    since_season: None,
+    total_per_star: 0.25,
+}];
+const PITCHING_SCALES: &[StarScale] = &[StarScale {
+    since_season: None,
+    total_per_star: 0.25,
+}];
+const DEFENSE_SCALES: &[StarScale] = &[StarScale {
+    since_season: None,
+    total_per_star: 0.25,
+}];
+const BASERUNNING_SCALES: &[StarScale] = &[StarScale {
+    since_season: None,
+    total_per_star: 0.25,
+}];
+
+/// Whether every scale earns a star for some positive, finite total, and the
+/// scales are sorted by the season they start in with only the first one
+/// starting from the beginning
+const fn scales_are_valid(scales: &[StarScale]) -> bool {
+    if scales.is_empty() {
+        return false;
+    }
+
+    let mut i = 0;
+    while i < scales.len() {
+        let scale = scales[i];
+        if !(scale.total_per_star > 0.0 && scale.total_per_star.is_finite()) {
+            return false;
+        }
+        match (i, scale.since_season) {
+            (0, _) => {}
+            (_, None) => return false,
+            (_, Some(since)) => {
+                if let Some(previous) = scales[i - 1].since_season {
+                    if previous >= since {
+                        return false;
+                    }
+                }
+            }
+        }
+        i += 1;
+    }
+    true
+}
+
+// A bad scale would divide by zero or pick the wrong era, so it shouldn't
+// compile
+const _: () = assert!(scales_are_valid(BATTING_SCALES));
+const _: () = assert!(scales_are_valid(PITCHING_SCALES));
+const _: () = assert!(scales_are_valid(DEFENSE_SCALES));
+const _: () = assert!(scales_are_valid(BASERUNNING_SCALES));
+
+impl TaxaAttributeCategory {
+    /// Every scale this category has used, oldest first
+    pub fn star_scales(self) -> &'static [StarScale] {
+        match self {
+            TaxaAttributeCategory::Batting => BATTING_SCALES,
+            TaxaAttributeCategory::Pitching => PITCHING_SCALES,
+            TaxaAttributeCategory::Defense => DEFENSE_SCALES,
+            TaxaAttributeCategory::Baserunning => BASERUNNING_SCALES,
+        }
+    }
+
+    /// The scale that applied to this category in `season`. Reports with
+    /// no season use the latest scale.
+    pub fn star_scale(self, season: Option<i32>) -> StarScale {
+        let scales = self.star_scales();
+        let latest = *scales
+            .last()
+            .expect("Every category should have a star scale");
+        let Some(season) = season else {
+            return latest;
+        };
+
+        scales
+            .iter()
+            .rev()
+            .find(|scale| scale.since_season.is_none_or(|since| since <= season))
+            .copied()
+            .unwrap_or(latest)
+    }
+
+    /// The number of stars a report would show for an attribute in this
+    /// category with the given total. Partial stars are dropped, and
+    /// negative totals show no stars.
+    pub fn stars_for_total(self, total: f64, season: Option<i32>) -> i32 {
+        let scale = self.star_scale(season);
+        // The small epsilon keeps totals that are a whole number of stars
+        // from losing a star to float error
+        ((total / scale.total_per_star) + 1e-9).floor().max(0.0) as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn stars_for_total() {
+        for category in TaxaAttributeCategory::iter() {
+            assert_eq!(category.stars_for_total(0.0, Some(1)), 0);
+            assert_eq!(category.stars_for_total(0.24, Some(1)), 0);
+            assert_eq!(category.stars_for_total(0.25, Some(1)), 1);
+            assert_eq!(category.stars_for_total(0.1 + 0.2 + 0.45, Some(1)), 3);
+            assert_eq!(category.stars_for_total(1.3, None), 5);
+            assert_eq!(category.stars_for_total(-0.3, None), 0);
+        }
+    }
+
+    #[test]
+    fn invalid_star_scales_are_rejected() {
+        let scale = |since_season, total_per_star| StarScale {
+            since_season,
+            total_per_star,
+        };
+        assert!(scales_are_valid(&[scale(None, 0.25), scale(Some(3), 0.5)]));
+
+        assert!(!scales_are_valid(&[]));
+        assert!(!scales_are_valid(&[scale(None, 0.0)]));
+        assert!(!scales_are_valid(&[scale(None, -0.25)]));
+        assert!(!scales_are_valid(&[scale(None, f64::NAN)]));
+        assert!(!scales_are_valid(&[scale(None, f64::INFINITY)]));
+        // Out of order, or two scales starting in the same season
+        assert!(!scales_are_valid(&[
+            scale(Some(5), 0.25),
+            scale(Some(3), 0.5)
+        ]));
+        assert!(!scales_are_valid(&[
+            scale(Some(3), 0.25),
+            scale(Some(3), 0.5)
+        ]));
+        // Only the first scale can apply from the beginning
+        assert!(!scales_are_valid(&[scale(Some(3), 0.25), scale(None, 0.5)]));
+    }
+
+    #[test]
+    fn star_scale_eras() {
+        for category in TaxaAttributeCategory::iter() {
+            let scales = category.star_scales();
+            assert!(!scales.is_empty());
+            // Scales must be sorted so the latest one that has started wins
+            assert!(scales.is_sorted_by_key(|scale| scale.since_season));
+
+            for scale in scales {
+                if let Some(since) = scale.since_season {
+                    assert_eq!(category.star_scale(Some(since)), *scale);
+                }
+            }
+        }
+    }
+}
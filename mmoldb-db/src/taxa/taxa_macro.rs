@@ -4,11 +4,135 @@ pub trait AsInsertable<'a> {
     fn as_insertable(&self) -> Self::Insertable;
 }
 
+/// One row of a taxa table, as its Rust definition describes it
+#[derive(Debug, Clone)]
+pub struct TaxaDefinitionRow {
+    pub id: i64,
+    /// The variant name, e.g. `HomeRun`
+    pub name: &'static str,
+    pub description: Option<String>,
+    /// Every other column the definition sets, e.g. `display_name` or
+    /// `is_hit`
+    pub columns: serde_json::Map<String, serde_json::Value>,
+}
+
+impl TaxaDefinitionRow {
+    /// `row` is the serialized insertable for the variant
+    pub fn new(id: i64, name: &'static str, row: serde_json::Value) -> Self {
+        let mut columns = match row {
+            serde_json::Value::Object(columns) => columns,
+            _ => serde_json::Map::new(),
+        };
+        columns.remove("id");
+        columns.remove("name");
+        let description = columns
+            .remove("description")
+            .and_then(|description| description.as_str().map(str::to_string));
+
+        Self {
+            id,
+            name,
+            description,
+            columns,
+        }
+    }
+}
+
+/// Maps a taxa column's Rust type to the type `information_schema` reports
+/// for it, so the schema docs can describe the column without repeating it
+pub trait TaxaColumnType {
+    const SQL_TYPE: &'static str;
+    const IS_NULLABLE: bool = false;
+}
+
+impl TaxaColumnType for bool {
+    const SQL_TYPE: &'static str = "boolean";
+}
+
+impl TaxaColumnType for i32 {
+    const SQL_TYPE: &'static str = "integer";
+}
+
+impl TaxaColumnType for i64 {
+    const SQL_TYPE: &'static str = "bigint";
+}
+
+impl TaxaColumnType for &str {
+    const SQL_TYPE: &'static str = "text";
+}
+
+impl<T: TaxaColumnType> TaxaColumnType for Option<T> {
+    const SQL_TYPE: &'static str = T::SQL_TYPE;
+    const IS_NULLABLE: bool = true;
+}
+
+/// One column of a taxa table, as its Rust definition describes it
+#[derive(Debug, Clone)]
+pub struct TaxaColumnDefinition {
+    pub name: &'static str,
+    /// The type as `information_schema` names it, e.g. `boolean`
+    pub sql_type: &'static str,
+    pub is_nullable: bool,
+    /// Markdown. Set with `columns = { ... }` in the `taxa!` header.
+    pub description: Option<&'static str>,
+    /// Markdown. Only set for columns every taxa table has.
+    pub nullable_explanation: Option<&'static str>,
+}
+
+impl TaxaColumnDefinition {
+    pub fn new<T: TaxaColumnType>(name: &'static str) -> Self {
+        Self {
+            name,
+            sql_type: T::SQL_TYPE,
+            is_nullable: T::IS_NULLABLE,
+            description: None,
+            nullable_explanation: None,
+        }
+    }
+
+    /// The `description` column every taxa table has
+    pub fn row_description() -> Self {
+        Self {
+            description: Some("A human-readable description of this row."),
+            nullable_explanation: Some("`null` for rows that haven't been given a description."),
+            ..Self::new::<Option<&str>>("description")
+        }
+    }
+
+    /// Attaches the descriptions from a `taxa!` header to `columns`.
+    /// Panics if a description names a column the table doesn't have,
+    /// since that's a typo in the definition.
+    pub fn describe(
+        table: &str,
+        mut columns: Vec<Self>,
+        descriptions: &[(&'static str, &'static str)],
+    ) -> Vec<Self> {
+        for (name, description) in descriptions {
+            let column = columns
+                .iter_mut()
+                .find(|column| column.name == *name)
+                .unwrap_or_else(|| panic!("taxa.{table} has no column {name} to describe"));
+            column.description = Some(description);
+        }
+        columns
+    }
+}
+
+/// The table name at the end of a diesel schema path, e.g. `event_type` for
+/// `crate::taxa_schema::taxa::event_type`
+pub fn taxa_table_name(schema_path: &'static str) -> &'static str {
+    schema_path
+        .rsplit("::")
+        .next()
+        .map(str::trim)
+        .unwrap_or(schema_path)
+}
+
 #[macro_export]
 macro_rules! taxa_main_enum {
-    ($table:path, $id_column:path, $(($($derive:ident),*))?, $vis:vis, $enum_name:ident, $(
+    ($table_name:expr, $table:path, $id_column:path, $(($($derive:ident),*))?, $vis:vis, $enum_name:ident, $(
         $(#[$($attr_name:ident: $attr_type:ty = $attr_value:expr),* $(,)?])?
-        $variant_name:ident = $variant_id:expr
+        $variant_name:ident = $variant_id:expr $(=> $description:literal)?
     ),+$(,)?) => {
         #[derive(Debug, enum_map::Enum, Eq, PartialEq, Hash, Copy, Clone, strum::Display, strum::IntoStaticStr, strum::EnumIter)]
         $(#[derive($($derive),*)])?
@@ -17,21 +141,80 @@ macro_rules! taxa_main_enum {
         }
 
         impl $enum_name {
+            /// Name of this enum's table in the `taxa` schema
+            pub fn table_name() -> &'static str {
+                $table_name
+            }
+
+            /// Every row this enum defines, in variant order
+            pub fn definition_rows() -> Vec<TaxaDefinitionRow> {
+                <Self as strum::IntoEnumIterator>::iter()
+                    .map(|taxa| {
+                        let new_taxa = taxa.as_insertable();
+                        let row = serde_json::to_value(&new_taxa)
+                            .expect("Taxa insertables should always serialize");
+                        TaxaDefinitionRow::new(new_taxa.id, taxa.into(), row)
+                    })
+                    .collect()
+            }
+
             pub fn make_id_mapping(conn: &mut PgConnection) -> QueryResult<EnumMap<Self, i64>> {
-                let mut mapping: EnumMap<Self, i64> = EnumMap::default();
+                let (mapping, report) =
+                    Self::reconcile_id_mapping(conn, TaxaReconcileMode::Update)?;
+                report.log();
+                Ok(mapping)
+            }
+
+            /// Builds the id mapping, inserting any missing rows, and reports
+            /// how the table differs from this enum. Existing rows are only
+            /// changed in [`TaxaReconcileMode::Update`].
+            pub fn reconcile_id_mapping(
+                conn: &mut PgConnection,
+                mode: TaxaReconcileMode,
+            ) -> QueryResult<(EnumMap<Self, i64>, TaxaReconciliation)> {
+                let table_name = $table_name;
+                let mut report = TaxaReconciliation::new(table_name, mode);
+                let mut existing = load_taxa_rows(conn, table_name)?
+                    .into_iter()
+                    .map(|row| (row.id, row.row))
+                    .collect::<HashMap<_, _>>();
 
+                let mut mapping: EnumMap<Self, i64> = EnumMap::default();
                 for (taxa, key) in mapping.iter_mut() {
                     let new_taxa = taxa.as_insertable();
+                    let expected = serde_json::to_value(&new_taxa)
+                        .expect("Taxa insertables should always serialize");
 
-                    *key = diesel::insert_into($table)
-                        .values(&new_taxa)
-                        .on_conflict($id_column)
-                        .do_update()
-                        .set(&new_taxa)
-                        .returning($id_column)
-                        .get_result(conn)?;
+                    let needs_upsert = match existing.remove(&new_taxa.id) {
+                        None => {
+                            report.inserted.push(taxa.into());
+                            true
+                        }
+                        Some(in_db) => {
+                            let mismatches =
+                                diff_taxa_row(new_taxa.id, taxa.into(), &in_db, &expected);
+                            let changed = !mismatches.is_empty();
+                            report.mismatched.extend(mismatches);
+                            changed && mode == TaxaReconcileMode::Update
+                        }
+                    };
+
+                    *key = if needs_upsert {
+                        diesel::insert_into($table)
+                            .values(&new_taxa)
+                            .on_conflict($id_column)
+                            .do_update()
+                            .set(&new_taxa)
+                            .returning($id_column)
+                            .get_result(conn)?
+                    } else {
+                        new_taxa.id
+                    };
                 }
 
+                // Whatever wasn't claimed by a variant is orphaned
+                report.orphaned = orphaned_rows(existing);
+
                 // Final safety check: Mapping should hold all distinct values
                 // Implemented as # of unique keys == # of unique values
                 assert_eq!(
@@ -47,7 +230,7 @@ macro_rules! taxa_main_enum {
                         .len(),
                 );
 
-                Ok(mapping)
+                Ok((mapping, report))
             }
         }
     };
@@ -57,21 +240,50 @@ macro_rules! taxa_main_enum {
 macro_rules! taxa_insertable_enum {
     // This extracts just the data of the first variant and discards the rest (by ignoring tail)
     ($schema:path, $insertable_name:ident, #[$($attr_name:ident: $attr_type:ty = $attr_value:expr),* $(,)?] $($tail:tt)*) => {
-        #[derive(Insertable, AsChangeset)]
+        #[derive(Insertable, AsChangeset, Serialize)]
         #[diesel(table_name = $schema)]
+        // Otherwise removing a description wouldn't remove it from the db
+        #[diesel(treat_none_as_null = true)]
         pub struct $insertable_name<'a> {
             pub id: i64,
             pub name: &'a str,
+            pub description: Option<&'a str>,
             $(pub $attr_name: $attr_type,)*
         }
+
+        impl<'a> $insertable_name<'a> {
+            /// Every column of the table, in order, without descriptions
+            pub fn columns() -> Vec<TaxaColumnDefinition> {
+                vec![
+                    TaxaColumnDefinition::new::<i64>("id"),
+                    TaxaColumnDefinition::new::<&str>("name"),
+                    TaxaColumnDefinition::row_description(),
+                    $(TaxaColumnDefinition::new::<$attr_type>(stringify!($attr_name)),)*
+                ]
+            }
+        }
     };
     // This is intended to match when there is no attribute, hopefully it works
     ($schema:path, $insertable_name:ident, $($tail:tt)*) => {
-        #[derive(Insertable, AsChangeset)]
+        #[derive(Insertable, AsChangeset, Serialize)]
         #[diesel(table_name = $schema)]
+        // Otherwise removing a description wouldn't remove it from the db
+        #[diesel(treat_none_as_null = true)]
         pub struct $insertable_name<'a> {
             pub id: i64,
             pub name: &'a str,
+            pub description: Option<&'a str>,
+        }
+
+        impl $insertable_name<'_> {
+            /// Every column of the table, in order, without descriptions
+            pub fn columns() -> Vec<TaxaColumnDefinition> {
+                vec![
+                    TaxaColumnDefinition::new::<i64>("id"),
+                    TaxaColumnDefinition::new::<&str>("name"),
+                    TaxaColumnDefinition::row_description(),
+                ]
+            }
         }
     };
 }
@@ -80,7 +292,7 @@ macro_rules! taxa_insertable_enum {
 macro_rules! taxa_as_insertable_impl {
     ($enum_name:ty, $insertable_name:ident, $(
         #[$($attr_name:ident: $attr_type:ty = $attr_value:expr),* $(,)?]
-        $variant_name:ident = $variant_id:expr
+        $variant_name:ident = $variant_id:expr $(=> $description:literal)?
     ),+$(,)?) => {
         impl<'a> AsInsertable<'a> for $enum_name {
             type Insertable = $insertable_name<'a>;
@@ -91,6 +303,7 @@ macro_rules! taxa_as_insertable_impl {
                         Self::Insertable {
                             id: $variant_id,
                             name: self.into(),
+                            description: taxa_description!($($description)?),
                             $($attr_name: $attr_value,)*
                         }
                     }),*
@@ -99,7 +312,7 @@ macro_rules! taxa_as_insertable_impl {
         }
     };
     ($enum_name:ty, $insertable_name:ident, $(
-        $variant_name:ident = $variant_id:expr
+        $variant_name:ident = $variant_id:expr $(=> $description:literal)?
     ),+$(,)?) => {
         impl<'a> AsInsertable<'a> for $enum_name {
             type Insertable = $insertable_name<'a>;
@@ -110,6 +323,7 @@ macro_rules! taxa_as_insertable_impl {
                         Self::Insertable {
                             id: $variant_id,
                             name: self.into(),
+                            description: taxa_description!($($description)?),
                         }
                     }),*
                 }
@@ -118,6 +332,16 @@ macro_rules! taxa_as_insertable_impl {
     };
 }
 
+#[macro_export]
+macro_rules! taxa_description {
+    () => {
+        None
+    };
+    ($description:literal) => {
+        Some($description)
+    };
+}
+
 #[macro_export]
 macro_rules! taxa {
     (
@@ -126,6 +350,7 @@ macro_rules! taxa {
             table = $table:path,
             id_column = $id_column:path
             $(, derive = ($($derive:ident),* $(,)?))?
+            $(, columns = { $($column_name:ident: $column_description:literal),* $(,)? })?
             $(,)?
         ]
         $vis:vis enum $enum_name:ident {
@@ -133,11 +358,105 @@ macro_rules! taxa {
         }
     ) => {
         paste! {
-            taxa_main_enum!($table, $id_column, $(($($derive),*))?, $vis, $enum_name, $($variants)*);
+            taxa_main_enum!(
+                taxa_table_name(stringify!($schema)),
+                $table,
+                $id_column,
+                $(($($derive),*))?,
+                $vis,
+                $enum_name,
+                $($variants)*
+            );
             taxa_insertable_enum!($schema, [<New $enum_name>], $($variants)*);
             taxa_as_insertable_impl!($enum_name, [<New $enum_name>], $($variants)*);
+
+            impl $enum_name {
+                /// Every column of this enum's table, with the descriptions
+                /// its definition gives them
+                pub fn column_definitions() -> Vec<TaxaColumnDefinition> {
+                    TaxaColumnDefinition::describe(
+                        Self::table_name(),
+                        [<New $enum_name>]::columns(),
+                        &[$($((stringify!($column_name), $column_description)),*)?],
+                    )
+                }
+            }
         }
     };
 }
 
-pub use {taxa, taxa_as_insertable_impl, taxa_insertable_enum, taxa_main_enum};
+pub use {
+    taxa, taxa_as_insertable_impl, taxa_description, taxa_insertable_enum, taxa_main_enum,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::taxa::{TaxaEventType, taxa_table_definitions};
+    use std::collections::HashSet;
+
+    #[test]
+    fn definition_rows_split_out_the_key_columns() {
+        let row = TaxaDefinitionRow::new(
+            3,
+            "Walk",
+            serde_json::json!({"id": 3, "name": "Walk", "description": null, "is_hit": false}),
+        );
+
+        assert_eq!(row.id, 3);
+        assert_eq!(row.name, "Walk");
+        assert_eq!(row.description, None);
+        assert_eq!(row.columns.keys().collect::<Vec<_>>(), ["is_hit"]);
+    }
+
+    #[test]
+    fn column_definitions_have_types_and_descriptions() {
+        let columns = TaxaEventType::column_definitions();
+        let column = |name: &str| {
+            columns
+                .iter()
+                .find(|column| column.name == name)
+                .unwrap_or_else(|| panic!("taxa.event_type should have a {name} column"))
+        };
+
+        assert_eq!(column("id").sql_type, "bigint");
+        assert_eq!(column("display_name").sql_type, "text");
+        assert!(column("description").is_nullable);
+        assert!(column("description").nullable_explanation.is_some());
+        assert_eq!(column("is_basic_strike").sql_type, "boolean");
+        assert!(!column("is_basic_strike").is_nullable);
+        assert!(column("is_basic_strike").description.is_some());
+    }
+
+    #[test]
+    #[should_panic(expected = "taxa.event_type has no column is_strke to describe")]
+    fn describing_a_missing_column_panics() {
+        TaxaColumnDefinition::describe(
+            "event_type",
+            TaxaEventType::column_definitions(),
+            &[("is_strke", "A typo")],
+        );
+    }
+
+    #[test]
+    fn every_taxa_table_is_listed_once_with_unique_ids() {
+        let definitions = taxa_table_definitions();
+
+        let tables = definitions.iter().map(|d| d.table).collect::<HashSet<_>>();
+        assert_eq!(tables.len(), definitions.len());
+        assert!(tables.contains(TaxaEventType::table_name()));
+        for definition in &definitions {
+            let ids = definition
+                .rows
+                .iter()
+                .map(|row| row.id)
+                .collect::<HashSet<_>>();
+            assert_eq!(
+                ids.len(),
+                definition.rows.len(),
+                "in taxa.{}",
+                definition.table
+            );
+        }
+    }
+}
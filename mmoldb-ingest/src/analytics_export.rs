@@ -0,0 +1,296 @@
+//! Mirrors selected tables into a SQLite file, so people can query the data
+//! locally without running Postgres. The file is rebuilt from scratch on
+//! every pass and swapped into place once it's complete, so readers never
+//! see a partial export. Every table is read from the same snapshot, so the
+//! tables are consistent with each other even while ingest is running.
+
+use crate::config::AnalyticsExportConfig;
+use itertools::Itertools;
+use mmoldb_db::db::{self, ExportError};
+use mmoldb_db::{Connection, ConnectionPool, PgConnection};
+use rusqlite::types::Value as SqliteValue;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+#[derive(Debug, Error)]
+pub enum AnalyticsExportError {
+    #[error("table names must look like schema.table, but got {0:?}")]
+    InvalidTableName(String),
+
+    #[error(transparent)]
+    Export(#[from] ExportError),
+
+    #[error(transparent)]
+    Db(#[from] mmoldb_db::QueryError),
+
+    #[error(transparent)]
+    Sqlite(#[from] rusqlite::Error),
+
+    #[error("couldn't move the finished export into place")]
+    Io(#[from] std::io::Error),
+
+    #[error("shutdown was requested before the export finished")]
+    Cancelled,
+}
+
+fn quote_ident(ident: &str) -> String {
+    format!("\"{}\"", ident.replace('"', "\"\""))
+}
+
+/// SQLite only has a few storage classes, so every postgres type is
+/// squashed into one of them
+fn sqlite_type(postgres_type: &str) -> &'static str {
+    match postgres_type {
+        "smallint" | "integer" | "bigint" | "boolean" => "integer",
+        "real" | "double precision" | "numeric" => "real",
+        _ => "text",
+    }
+}
+
+fn sqlite_value(value: serde_json::Value) -> SqliteValue {
+    match value {
+        serde_json::Value::Null => SqliteValue::Null,
+        serde_json::Value::Bool(b) => SqliteValue::Integer(b.into()),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => SqliteValue::Integer(i),
+            None => SqliteValue::Real(n.as_f64().unwrap_or(f64::NAN)),
+        },
+        serde_json::Value::String(s) => SqliteValue::Text(s),
+        // Arrays and JSON columns are stored as JSON text, which SQLite's
+        // json functions can read
+        other => SqliteValue::Text(other.to_string()),
+    }
+}
+
+/// Copies one table into `sqlite`, as `schema_table`. Returns the number of
+/// rows copied.
+fn export_table(
+    conn: &mut PgConnection,
+    sqlite: &mut rusqlite::Connection,
+    qualified_table: &str,
+    page_size: i64,
+    shutdown_requested: &CancellationToken,
+) -> Result<usize, AnalyticsExportError> {
+    let (schema, table) = qualified_table
+        .split_once('.')
+        .ok_or_else(|| AnalyticsExportError::InvalidTableName(qualified_table.to_string()))?;
+    let plan = db::plan_export(conn, schema, table, None, Vec::new())?;
+
+    let sqlite_table = quote_ident(&format!("{schema}_{table}"));
+    let column_defs = plan
+        .columns()
+        .map(|(name, sql_type)| format!("{} {}", quote_ident(name), sqlite_type(sql_type)))
+        .join(", ");
+    sqlite.execute_batch(&format!("create table {sqlite_table} ({column_defs});"))?;
+
+    let placeholders = plan.columns().map(|_| "?").join(", ");
+    let insert_sql = format!("insert into {sqlite_table} values ({placeholders})");
+
+    let mut num_rows = 0;
+    let mut after: Option<String> = None;
+    loop {
+        if shutdown_requested.is_cancelled() {
+            return Err(AnalyticsExportError::Cancelled);
+        }
+        let page = db::export_json_page(conn, &plan, after.as_deref(), page_size)?;
+
+        let tx = sqlite.transaction()?;
+        {
+            let mut insert = tx.prepare_cached(&insert_sql)?;
+            for row in page.rows {
+                insert.execute(rusqlite::params_from_iter(row.into_iter().map(sqlite_value)))?;
+                num_rows += 1;
+            }
+        }
+        tx.commit()?;
+
+        match page.next_page {
+            Some(next_page) => after = Some(next_page),
+            None => break,
+        }
+    }
+
+    Ok(num_rows)
+}
+
+fn temp_path(output_path: &Path) -> PathBuf {
+    let mut path = output_path.as_os_str().to_owned();
+    path.push(".partial");
+    PathBuf::from(path)
+}
+
+fn write_sqlite_file(
+    conn: &mut PgConnection,
+    path: &Path,
+    config: &AnalyticsExportConfig,
+    shutdown_requested: &CancellationToken,
+) -> Result<(), AnalyticsExportError> {
+    let mut sqlite = rusqlite::Connection::open(path)?;
+    // This file is thrown away if anything goes wrong, so there's no need
+    // to pay for durability
+    sqlite.execute_batch("pragma journal_mode = off; pragma synchronous = off;")?;
+
+    conn.build_transaction()
+        .repeatable_read()
+        .read_only()
+        .run(|conn| {
+            for table in &config.tables {
+                let num_rows = export_table(
+                    conn,
+                    &mut sqlite,
+                    table,
+                    config.page_size.get() as i64,
+                    shutdown_requested,
+                )?;
+                info!("Exported {num_rows} rows of {table} for analytics");
+            }
+            Ok::<_, AnalyticsExportError>(())
+        })?;
+
+    sqlite.close().map_err(|(_, err)| err)?;
+    Ok(())
+}
+
+/// Writes every configured table to a new SQLite file, then replaces
+/// `output_path` with it. If the export fails or shutdown is requested
+/// partway through, `output_path` is left alone.
+pub fn export_analytics_bundle(
+    conn: &mut PgConnection,
+    config: &AnalyticsExportConfig,
+    shutdown_requested: &CancellationToken,
+) -> Result<(), AnalyticsExportError> {
+    let partial_path = temp_path(&config.output_path);
+    // Leftovers from an interrupted export
+    if partial_path.exists() {
+        std::fs::remove_file(&partial_path)?;
+    }
+
+    if let Err(err) = write_sqlite_file(conn, &partial_path, config, shutdown_requested) {
+        if let Err(remove_err) = std::fs::remove_file(&partial_path) {
+            warn!("Couldn't remove partial analytics export: {remove_err}");
+        }
+        return Err(err);
+    }
+
+    std::fs::rename(&partial_path, &config.output_path)?;
+    info!("Wrote analytics export to {}", config.output_path.display());
+
+    Ok(())
+}
+
+pub async fn analytics_export_task(
+    shutdown_requested: CancellationToken,
+    pool: ConnectionPool,
+    config: &'static AnalyticsExportConfig,
+) {
+    loop {
+        let export_pool = pool.clone();
+        let export_shutdown_requested = shutdown_requested.clone();
+        let result = tokio::task::spawn_blocking(move || match export_pool.get() {
+            Ok(mut conn) => {
+                match export_analytics_bundle(&mut conn, config, &export_shutdown_requested) {
+                    Ok(()) | Err(AnalyticsExportError::Cancelled) => {}
+                    Err(err) => warn!("Error writing analytics export: {err}"),
+                }
+            }
+            Err(e) => {
+                warn!("Couldn't get connection to write analytics export: {e}");
+            }
+        })
+        .await;
+        if let Err(err) = result {
+            warn!("Analytics export panicked: {err}");
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs(config.interval_minutes * 60)) => {}
+            _ = shutdown_requested.cancelled() => { break; }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mmoldb_db::taxa::{Taxa, TaxaEventType};
+    use std::num::NonZero;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn values_use_sqlite_storage_classes() {
+        assert_eq!(sqlite_value(serde_json::json!(null)), SqliteValue::Null);
+        assert_eq!(sqlite_value(serde_json::json!(true)), SqliteValue::Integer(1));
+        assert_eq!(sqlite_value(serde_json::json!(42)), SqliteValue::Integer(42));
+        assert_eq!(sqlite_value(serde_json::json!(1.5)), SqliteValue::Real(1.5));
+        assert_eq!(
+            sqlite_value(serde_json::json!("text")),
+            SqliteValue::Text("text".to_string()),
+        );
+        assert_eq!(
+            sqlite_value(serde_json::json!([1, 2])),
+            SqliteValue::Text("[1,2]".to_string()),
+        );
+        assert_eq!(sqlite_type("bigint"), "integer");
+        assert_eq!(sqlite_type("double precision"), "real");
+        assert_eq!(sqlite_type("timestamp with time zone"), "text");
+    }
+
+    fn test_config(name: &str) -> AnalyticsExportConfig {
+        let output_dir = std::env::temp_dir().join(format!(
+            "mmoldb-analytics-export-{name}-{}",
+            std::process::id(),
+        ));
+        std::fs::create_dir_all(&output_dir).expect("should be able to create a temp dir");
+        AnalyticsExportConfig {
+            tables: vec!["taxa.event_type".to_string()],
+            output_path: output_dir.join("analytics.sqlite"),
+            // Small enough that the table takes several pages
+            page_size: NonZero::new(5).unwrap(),
+            interval_minutes: 60,
+        }
+    }
+
+    #[test]
+    fn tables_are_copied_into_sqlite() {
+        let url = mmoldb_db::postgres_url_from_environment();
+        let mut conn = PgConnection::establish(&url)
+            .expect("postgres_url_from_environment should return a valid connection string");
+        let config = test_config("copy");
+        // Makes sure the taxa tables are populated
+        Taxa::new(&mut conn).expect("taxa should load");
+
+        let result = export_analytics_bundle(&mut conn, &config, &CancellationToken::new());
+        let exported = result.and_then(|()| {
+            let sqlite = rusqlite::Connection::open(&config.output_path)?;
+            let num_rows: i64 =
+                sqlite.query_row("select count(*) from taxa_event_type", [], |row| row.get(0))?;
+            Ok(num_rows)
+        });
+        let output_dir = config.output_path.parent().unwrap();
+        std::fs::remove_dir_all(output_dir).expect("should be able to remove the temp dir");
+
+        // Taxa tables have one row per variant
+        assert_eq!(exported.unwrap() as usize, TaxaEventType::iter().count());
+    }
+
+    #[test]
+    fn cancelled_exports_leave_nothing_behind() {
+        let url = mmoldb_db::postgres_url_from_environment();
+        let mut conn = PgConnection::establish(&url)
+            .expect("postgres_url_from_environment should return a valid connection string");
+        let config = test_config("cancel");
+
+        let shutdown_requested = CancellationToken::new();
+        shutdown_requested.cancel();
+        let result = export_analytics_bundle(&mut conn, &config, &shutdown_requested);
+        let output_dir = config.output_path.parent().unwrap();
+        let leftovers = std::fs::read_dir(output_dir).unwrap().count();
+        std::fs::remove_dir_all(output_dir).expect("should be able to remove the temp dir");
+
+        assert!(matches!(result, Err(AnalyticsExportError::Cancelled)));
+        assert_eq!(leftovers, 0);
+    }
+}
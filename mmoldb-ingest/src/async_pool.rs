@@ -0,0 +1,149 @@
+use mmoldb_db::{AsyncConnection, AsyncPgConnection, ConnectionError};
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// A pool of async connections, for the processing stages' version streams
+/// and the game round-trip check. Connections are opened as they're needed,
+/// up to `max_size`, and kept open for reuse until they're `max_lifetime`
+/// old. Checking one out waits while `max_size` are in use.
+#[derive(Debug, Clone)]
+pub struct AsyncConnectionPool {
+    url: Arc<str>,
+    max_size: usize,
+    max_lifetime: Option<Duration>,
+    permits: Arc<Semaphore>,
+    idle: Arc<Mutex<Vec<IdleConnection>>>,
+}
+
+struct IdleConnection {
+    conn: AsyncPgConnection,
+    opened_at: Instant,
+}
+
+impl std::fmt::Debug for IdleConnection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IdleConnection")
+            .field("opened_at", &self.opened_at)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AsyncConnectionPool {
+    pub fn new(max_size: usize, max_lifetime: Option<Duration>) -> Self {
+        Self {
+            url: mmoldb_db::postgres_url_from_environment().into(),
+            max_size,
+            max_lifetime,
+            permits: Arc::new(Semaphore::new(max_size)),
+            idle: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Waits for a connection to be free, reusing an idle one if there is
+    /// one and opening a new one otherwise
+    pub async fn get(&self) -> Result<PooledAsyncConnection, ConnectionError> {
+        let permit = self
+            .permits
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("AsyncConnectionPool never closes its semaphore");
+        self.check_out(permit).await
+    }
+
+    /// Checks out `n` connections at once, for queries that are spread over
+    /// several connections. They're waited for all together, so callers
+    /// that each hold some can't starve each other. If `n` is more than the
+    /// pool's size, only the pool's size are checked out.
+    pub async fn get_many(&self, n: usize) -> Result<Vec<PooledAsyncConnection>, ConnectionError> {
+        let n = n.min(self.max_size);
+        let mut permits = self
+            .permits
+            .clone()
+            .acquire_many_owned(n.try_into().expect("get_many's n should fit in a u32"))
+            .await
+            .expect("AsyncConnectionPool never closes its semaphore");
+        let mut conns = Vec::with_capacity(n);
+        for _ in 1..n {
+            let permit = permits
+                .split(1)
+                .expect("the permits for get_many should split into one per connection");
+            conns.push(self.check_out(permit).await?);
+        }
+        if n > 0 {
+            conns.push(self.check_out(permits).await?);
+        }
+        Ok(conns)
+    }
+
+    async fn check_out(
+        &self,
+        permit: OwnedSemaphorePermit,
+    ) -> Result<PooledAsyncConnection, ConnectionError> {
+        let idle = loop {
+            let Some(idle) = self.idle.lock().unwrap().pop() else {
+                break None;
+            };
+            // An expired connection is dropped, which closes it
+            if !self.is_expired(&idle) {
+                break Some(idle);
+            }
+        };
+        let idle = match idle {
+            Some(idle) => idle,
+            None => IdleConnection {
+                conn: AsyncPgConnection::establish(&self.url).await?,
+                opened_at: Instant::now(),
+            },
+        };
+
+        Ok(PooledAsyncConnection {
+            conn: Some(idle),
+            pool: self.clone(),
+            _permit: permit,
+        })
+    }
+
+    fn is_expired(&self, idle: &IdleConnection) -> bool {
+        self.max_lifetime
+            .is_some_and(|max_lifetime| idle.opened_at.elapsed() >= max_lifetime)
+    }
+}
+
+/// A connection checked out of an [`AsyncConnectionPool`]. It goes back to
+/// the pool when it's dropped.
+pub struct PooledAsyncConnection {
+    // Only None while it's being returned to the pool
+    conn: Option<IdleConnection>,
+    pool: AsyncConnectionPool,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl Deref for PooledAsyncConnection {
+    type Target = AsyncPgConnection;
+
+    fn deref(&self) -> &Self::Target {
+        &self.conn.as_ref().unwrap().conn
+    }
+}
+
+impl DerefMut for PooledAsyncConnection {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.conn.as_mut().unwrap().conn
+    }
+}
+
+impl Drop for PooledAsyncConnection {
+    fn drop(&mut self) {
+        // The permit is released after this, so a waiting checkout finds
+        // the connection
+        let Some(idle) = self.conn.take() else {
+            return;
+        };
+        if !self.pool.is_expired(&idle) {
+            self.pool.idle.lock().unwrap().push(idle);
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use mmoldb_db::{ConnectionPool, db};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+static ATTRIBUTE_DISTRIBUTION_SNAPSHOT_INTERVAL_MS: u64 = 60 * 60 * 1000;
+
+/// Periodically retakes this week's snapshot of the league-wide attribute
+/// distributions, so each week ends up with the last snapshot taken in it
+pub async fn attribute_distribution_task(
+    shutdown_requested: CancellationToken,
+    pool: ConnectionPool,
+) {
+    loop {
+        // Getting a connection and the snapshot query both block
+        let snapshot_pool = pool.clone();
+        let result = tokio::task::spawn_blocking(move || match snapshot_pool.get() {
+            Ok(mut conn) => match db::snapshot_attribute_distributions(&mut conn) {
+                Ok(num_attributes) => {
+                    info!("Snapshotted distributions of {num_attributes} attributes");
+                }
+                Err(err) => {
+                    warn!("Error snapshotting attribute distributions: {err}");
+                }
+            },
+            Err(e) => {
+                warn!("Couldn't get connection to snapshot attribute distributions: {e}");
+            }
+        })
+        .await;
+        if let Err(err) = result {
+            warn!("Attribute distribution snapshot panicked: {err}");
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_millis(ATTRIBUTE_DISTRIBUTION_SNAPSHOT_INTERVAL_MS)) => {}
+            _ = shutdown_requested.cancelled() => { break; }
+        }
+    }
+}
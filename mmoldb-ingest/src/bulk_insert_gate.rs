@@ -0,0 +1,61 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Tracks how many bulk inserts are in flight, so background maintenance
+/// (like refreshing the entity counting matviews) can stay out of their way.
+/// Bulk inserts and matview refreshes both lean heavily on disk I/O, and
+/// running them at the same time slows both down.
+#[derive(Debug, Clone)]
+pub struct BulkInsertGate {
+    in_flight: Arc<watch::Sender<usize>>,
+}
+
+/// Marks a bulk insert as in flight until it's dropped
+#[must_use = "the insert is only marked as in flight until the guard is dropped"]
+pub struct BulkInsertGuard {
+    in_flight: Arc<watch::Sender<usize>>,
+}
+
+impl BulkInsertGate {
+    pub fn new() -> Self {
+        Self {
+            in_flight: Arc::new(watch::Sender::new(0)),
+        }
+    }
+
+    /// Call before starting a bulk insert and hold the guard until the insert
+    /// has committed (or failed)
+    pub fn begin(&self) -> BulkInsertGuard {
+        self.in_flight.send_modify(|n| *n += 1);
+        BulkInsertGuard {
+            in_flight: self.in_flight.clone(),
+        }
+    }
+
+    pub fn is_idle(&self) -> bool {
+        *self.in_flight.borrow() == 0
+    }
+
+    /// Waits until no bulk inserts are in flight, or until `max_wait` has
+    /// passed. Returns whether the gate is idle. Inserts may start again
+    /// as soon as this returns, so this only smooths out contention.
+    pub async fn wait_until_idle(&self, max_wait: Duration) -> bool {
+        let mut recv = self.in_flight.subscribe();
+        tokio::time::timeout(max_wait, recv.wait_for(|n| *n == 0))
+            .await
+            .is_ok()
+    }
+}
+
+impl Default for BulkInsertGate {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for BulkInsertGuard {
+    fn drop(&mut self) {
+        self.in_flight.send_modify(|n| *n -= 1);
+    }
+}
@@ -0,0 +1,681 @@
+use chrono::{DateTime, Utc};
+use miette::{IntoDiagnostic, miette};
+use mmoldb_db::db::{DerivedTable, RollbackKind};
+use mmoldb_db::{Connection, PgConnection};
+use figment::Figment;
+use figment::providers::{Format, Toml};
+use mmoldb_db::taxa::{Taxa, TaxaReconcileMode, TaxaRenumberAction, TaxaRenumbering};
+use mmoldb_ingest::async_pool::AsyncConnectionPool;
+use mmoldb_ingest::config::{GameOffloadConfig, IngestConfig};
+use mmoldb_ingest::ingest_lock::{IngestLock, IngestLockAttempt, describe_ingest_lock_holder};
+use mmoldb_ingest::offload::ObjectStore;
+use mmoldb_ingest::rebuild::StoredGameSelection;
+use serde::Deserialize;
+use std::fs::File;
+use std::io::BufWriter;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use strum::IntoEnumIterator;
+use tracing::info;
+
+static REBUILD_BATCH_SIZE: usize = 100;
+static OFFLOAD_BATCH_SIZE: usize = 100;
+static RESOLVE_BATCH_SIZE: usize = 100;
+static RENUMBER_BATCH_SIZE: i64 = 10_000;
+static TEAM_TRANSACTIONS_BATCH_SIZE: i64 = 10_000;
+static SNAPSHOT_COMPRESSION_LEVEL: i32 = 3;
+static BENCH_EVENT_READS_GAMES: usize = 100;
+static BENCH_EVENT_READS_REPEATS: usize = 5;
+static BENCH_EVENT_READS_MAX_CONNECTIONS: usize = 4;
+
+const USAGE: &str = "Usage:
+    mmoldb-cli rebuild-table <name> [--season <season>]
+    mmoldb-cli recompute-games [--season <season>] [--games <game id>,...]
+    mmoldb-cli rebuild-team-transactions
+    mmoldb-cli rollback --to <timestamp> [--kinds <kind>,...] [--execute]
+    mmoldb-cli offload-games
+    mmoldb-cli restore-games
+    mmoldb-cli resolve-player-identities [--all]
+    mmoldb-cli reconcile-taxa [--update]
+    mmoldb-cli renumber-taxa <mapping file> [--execute]
+    mmoldb-cli snapshot <output file>
+    mmoldb-cli restore-snapshot <snapshot file>
+    mmoldb-cli rerun-season-rollover <season> [--task <task>]
+    mmoldb-cli bench-event-reads [--games <count>] [--repeats <count>] [--connections <count>]";
+
+/// Who to record in the admin audit log
+fn actor() -> String {
+    let user = std::env::var("USER").unwrap_or_else(|_| "unknown".to_string());
+    format!("{user} (mmoldb-cli)")
+}
+
+/// Takes the main ingest lock, so that mmoldb-ingest can't start writing
+/// while a maintenance command runs, and two maintenance commands can't run
+/// at once. The lock is held until the returned guard is dropped.
+fn hold_ingest_lock(command: &str) -> miette::Result<IngestLock> {
+    let holder_name = format!("mmoldb-cli {command}");
+    match IngestLock::try_acquire(None, &holder_name).into_diagnostic()? {
+        IngestLockAttempt::Acquired(lock) => Ok(lock),
+        IngestLockAttempt::Held(holder) => Err(miette!(
+            "{} mmoldb-ingest and other maintenance commands have to be stopped before running \
+            this command.",
+            describe_ingest_lock_holder(holder.as_ref()),
+        )),
+    }
+}
+
+/// The object storage that old games are offloaded to, if one is configured
+fn offload_config() -> miette::Result<Option<GameOffloadConfig>> {
+    Ok(IngestConfig::config().into_diagnostic()?.game_offload)
+}
+
+fn offload_store() -> miette::Result<Option<ObjectStore>> {
+    offload_config()?
+        .map(|config| ObjectStore::new(&config.store))
+        .transpose()
+        .into_diagnostic()
+}
+
+fn derived_table_names() -> String {
+    DerivedTable::iter()
+        .map(|t| t.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn rebuild_table(mut args: impl Iterator<Item = String>) -> miette::Result<()> {
+    let name = args.next().ok_or_else(|| miette!("{USAGE}"))?;
+    let table = DerivedTable::from_str(&name).map_err(|_| {
+        miette!(
+            "Unknown derived table {name:?}. Valid tables are: {}",
+            derived_table_names(),
+        )
+    })?;
+
+    let mut season = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--season" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| miette!("--season needs a value"))?;
+                season = Some(value.parse::<i32>().into_diagnostic()?);
+            }
+            other => return Err(miette!("Unexpected argument {other:?}\n{USAGE}")),
+        }
+    }
+
+    let store = offload_store()?;
+    let _ingest_lock = hold_ingest_lock("rebuild-table")?;
+    let pool = mmoldb_db::get_pool(1).into_diagnostic()?;
+    let mut conn = pool.get().into_diagnostic()?;
+
+    match season {
+        Some(season) => info!("Rebuilding {table} for season {season}"),
+        None => info!("Rebuilding {table} for all seasons"),
+    }
+    let stats = mmoldb_ingest::rebuild::rebuild_table(
+        &mut conn,
+        table,
+        season,
+        REBUILD_BATCH_SIZE,
+        &actor(),
+        store.as_ref(),
+    )
+    .into_diagnostic()?;
+    info!(
+        "Rebuilt {table} for {} games. Skipped {} games that need a full reingest and {} games \
+        that failed in the sim.",
+        stats.games_rebuilt, stats.games_skipped, stats.games_failed,
+    );
+
+    Ok(())
+}
+
+fn recompute_games(mut args: impl Iterator<Item = String>) -> miette::Result<()> {
+    let mut season = None;
+    let mut game_ids: Option<Vec<String>> = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--season" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| miette!("--season needs a value"))?;
+                season = Some(value.parse::<i32>().into_diagnostic()?);
+            }
+            "--games" => {
+                let value = args.next().ok_or_else(|| miette!("--games needs a value"))?;
+                let ids = value
+                    .split(',')
+                    .map(|id| id.trim())
+                    .filter(|id| !id.is_empty())
+                    .map(|id| id.to_string())
+                    .collect::<Vec<_>>();
+                if ids.is_empty() {
+                    return Err(miette!("--games needs at least one game id"));
+                }
+                game_ids = Some(ids);
+            }
+            other => return Err(miette!("Unexpected argument {other:?}\n{USAGE}")),
+        }
+    }
+
+    let store = offload_store()?;
+    let _ingest_lock = hold_ingest_lock("recompute-games")?;
+    let pool = mmoldb_db::get_pool(1).into_diagnostic()?;
+    let mut conn = pool.get().into_diagnostic()?;
+
+    match (season, &game_ids) {
+        (_, Some(ids)) => info!("Recomputing {} selected games", ids.len()),
+        (Some(season), None) => info!("Recomputing games for season {season}"),
+        (None, None) => info!("Recomputing games for all seasons"),
+    }
+    let selection = StoredGameSelection {
+        season,
+        mmolb_game_ids: game_ids.as_deref(),
+    };
+    let stats = mmoldb_ingest::rebuild::recompute_games(
+        &mut conn,
+        selection,
+        REBUILD_BATCH_SIZE,
+        &actor(),
+        store.as_ref(),
+    )
+    .into_diagnostic()?;
+    info!(
+        "Recomputed {} games, {} of which changed. Skipped {} games that need a full reingest \
+        and {} games that failed in the sim.",
+        stats.games_recomputed, stats.games_changed, stats.games_skipped, stats.games_failed,
+    );
+
+    Ok(())
+}
+
+fn parse_rollback_kinds(value: &str) -> miette::Result<Vec<RollbackKind>> {
+    value
+        .split(',')
+        .map(|name| {
+            RollbackKind::from_str(name.trim()).map_err(|_| {
+                let valid = RollbackKind::iter()
+                    .map(|k| k.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                miette!("Unknown kind {name:?}. Valid kinds are: {valid}")
+            })
+        })
+        .collect()
+}
+
+/// Without `--execute` this is a dry run: it reports what would change and
+/// then rolls everything back.
+fn rollback(mut args: impl Iterator<Item = String>) -> miette::Result<()> {
+    let mut to = None;
+    let mut kinds = RollbackKind::iter().collect::<Vec<_>>();
+    let mut execute = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--to" => {
+                let value = args.next().ok_or_else(|| miette!("--to needs a value"))?;
+                to = Some(value.parse::<DateTime<Utc>>().into_diagnostic()?);
+            }
+            "--kinds" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| miette!("--kinds needs a value"))?;
+                kinds = parse_rollback_kinds(&value)?;
+            }
+            "--execute" => execute = true,
+            other => return Err(miette!("Unexpected argument {other:?}\n{USAGE}")),
+        }
+    }
+    let to = to.ok_or_else(|| miette!("--to is required\n{USAGE}"))?;
+
+    // Even a dry run does the deletes before rolling them back, so it can't
+    // run alongside the ingest either
+    let _ingest_lock = hold_ingest_lock("rollback")?;
+    let pool = mmoldb_db::get_pool(1).into_diagnostic()?;
+    let mut conn = pool.get().into_diagnostic()?;
+
+    let counts = mmoldb_db::db::roll_back_ingest_to_date(&mut conn, to, &kinds, !execute, &actor())
+        .into_diagnostic()?;
+
+    for count in &counts {
+        println!(
+            "{}: {} deleted, {} re-opened",
+            count.table, count.deleted, count.reopened,
+        );
+    }
+    if execute {
+        println!("Rolled back to {to}.");
+    } else {
+        println!("Dry run. Nothing was changed. Pass --execute to roll back to {to}.");
+    }
+
+    Ok(())
+}
+
+fn offload_games() -> miette::Result<()> {
+    let config = offload_config()?
+        .ok_or_else(|| miette!("game_offload must be configured to offload games"))?;
+    let store = ObjectStore::new(&config.store).into_diagnostic()?;
+
+    let _ingest_lock = hold_ingest_lock("offload-games")?;
+    let pool = mmoldb_db::get_pool(1).into_diagnostic()?;
+    let mut conn = pool.get().into_diagnostic()?;
+
+    let stats = mmoldb_ingest::offload::offload_old_games(
+        &mut conn,
+        &store,
+        config.keep_recent_seasons,
+        OFFLOAD_BATCH_SIZE,
+        &actor(),
+    )
+    .into_diagnostic()?;
+    info!(
+        "Offloaded {} games. {} games changed while they were being offloaded and were left in \
+        the database.",
+        stats.games_offloaded, stats.games_changed,
+    );
+
+    Ok(())
+}
+
+fn restore_games() -> miette::Result<()> {
+    let store = offload_store()?
+        .ok_or_else(|| miette!("game_offload must be configured to restore games"))?;
+
+    let _ingest_lock = hold_ingest_lock("restore-games")?;
+    let pool = mmoldb_db::get_pool(1).into_diagnostic()?;
+    let mut conn = pool.get().into_diagnostic()?;
+
+    let restored = mmoldb_ingest::offload::restore_offloaded_games(
+        &mut conn,
+        &store,
+        OFFLOAD_BATCH_SIZE,
+        &actor(),
+    )
+    .into_diagnostic()?;
+    info!("Restored {restored} games");
+
+    Ok(())
+}
+
+/// Re-resolves player names in games that have unresolved names, or in
+/// every game with `--all`
+fn resolve_player_identities(args: impl Iterator<Item = String>) -> miette::Result<()> {
+    let mut all = false;
+    for arg in args {
+        match arg.as_str() {
+            "--all" => all = true,
+            other => return Err(miette!("Unexpected argument {other:?}\n{USAGE}")),
+        }
+    }
+
+    let _ingest_lock = hold_ingest_lock("resolve-player-identities")?;
+    let pool = mmoldb_db::get_pool(1).into_diagnostic()?;
+    let mut conn = pool.get().into_diagnostic()?;
+
+    let mut after_game_id = None;
+    let mut games_resolved = 0;
+    loop {
+        let game_ids = mmoldb_db::db::game_ids_for_identity_resolution(
+            &mut conn,
+            all,
+            after_game_id,
+            RESOLVE_BATCH_SIZE,
+        )
+        .into_diagnostic()?;
+        let Some(last_game_id) = game_ids.last() else {
+            break;
+        };
+        after_game_id = Some(*last_game_id);
+
+        // Each batch is committed on its own, so a batch that fails partway
+        // through never leaves its games half resolved
+        mmoldb_db::db::reresolve_player_identities(&mut conn, &game_ids, &actor())
+            .into_diagnostic()?;
+        games_resolved += game_ids.len();
+        info!("Resolved player identities in {games_resolved} games");
+    }
+
+    Ok(())
+}
+
+/// Fills in `data.team_transactions` from team feed events that were
+/// processed before it existed
+fn rebuild_team_transactions() -> miette::Result<()> {
+    let _ingest_lock = hold_ingest_lock("rebuild-team-transactions")?;
+    let pool = mmoldb_db::get_pool(1).into_diagnostic()?;
+    let mut conn = pool.get().into_diagnostic()?;
+
+    info!("Rebuilding team transactions");
+    let num_added = mmoldb_ingest::rebuild::rebuild_team_transactions(
+        &mut conn,
+        TEAM_TRANSACTIONS_BATCH_SIZE,
+        &actor(),
+    )
+    .into_diagnostic()?;
+    info!("Added {num_added} team transactions");
+
+    Ok(())
+}
+
+/// Reports taxa rows that don't match the Rust definitions. Mismatched
+/// rows are only changed with `--update`.
+fn reconcile_taxa(args: impl Iterator<Item = String>) -> miette::Result<()> {
+    let mut mode = TaxaReconcileMode::ReportOnly;
+    for arg in args {
+        match arg.as_str() {
+            "--update" => mode = TaxaReconcileMode::Update,
+            other => return Err(miette!("Unexpected argument {other:?}\n{USAGE}")),
+        }
+    }
+
+    // Reporting only inserts missing rows, which the ingest also does when
+    // it starts, so it's safe to run alongside the ingest
+    let _ingest_lock = match mode {
+        TaxaReconcileMode::ReportOnly => None,
+        TaxaReconcileMode::Update => Some(hold_ingest_lock("reconcile-taxa")?),
+    };
+    let pool = mmoldb_db::get_pool(1).into_diagnostic()?;
+    let mut conn = pool.get().into_diagnostic()?;
+
+    let (_, reports) = Taxa::new_reconciled_by(&mut conn, mode, &actor()).into_diagnostic()?;
+    for report in &reports {
+        report.log();
+    }
+
+    let num_orphaned: usize = reports.iter().map(|r| r.orphaned.len()).sum();
+    let num_mismatched: usize = reports.iter().map(|r| r.mismatched.len()).sum();
+    let num_inserted: usize = reports.iter().map(|r| r.inserted.len()).sum();
+    println!(
+        "{num_inserted} rows inserted, {num_orphaned} orphaned rows, {num_mismatched} mismatched \
+        columns",
+    );
+    if num_mismatched > 0 && mode == TaxaReconcileMode::ReportOnly {
+        println!("Nothing was updated. Pass --update to make the mismatched rows match.");
+    }
+
+    Ok(())
+}
+
+/// The file read by `renumber-taxa`. Each entry looks like:
+///
+/// ```toml
+/// [[renumber]]
+/// table = "event_type"
+/// name = "FoulTip"
+/// from = 12
+/// to = 40
+/// ```
+#[derive(Deserialize)]
+struct TaxaRenumberFile {
+    renumber: Vec<TaxaRenumbering>,
+}
+
+/// Moves taxa rows to new ids, along with every column that references
+/// them. This has to be run before starting a build that changed a taxa
+/// variant's hard-coded id. Without `--execute` this is a dry run.
+fn renumber_taxa(mut args: impl Iterator<Item = String>) -> miette::Result<()> {
+    let path = args.next().ok_or_else(|| miette!("{USAGE}"))?;
+    let mut execute = false;
+    for arg in args {
+        match arg.as_str() {
+            "--execute" => execute = true,
+            other => return Err(miette!("Unexpected argument {other:?}\n{USAGE}")),
+        }
+    }
+
+    let file: TaxaRenumberFile = Figment::from(Toml::file_exact(&path))
+        .extract()
+        .into_diagnostic()?;
+
+    let _ingest_lock = hold_ingest_lock("renumber-taxa")?;
+    let pool = mmoldb_db::get_pool(1).into_diagnostic()?;
+    let mut conn = pool.get().into_diagnostic()?;
+
+    let reports = mmoldb_db::taxa::renumber_taxa(
+        &mut conn,
+        &file.renumber,
+        !execute,
+        RENUMBER_BATCH_SIZE,
+        &actor(),
+    )
+    .into_diagnostic()?;
+
+    for report in &reports {
+        for entry in &report.plan {
+            let r = &entry.renumbering;
+            let action = match entry.action {
+                TaxaRenumberAction::Move => "moved",
+                TaxaRenumberAction::Merge => "merged into the existing row",
+                TaxaRenumberAction::AlreadyDone => "already renumbered",
+            };
+            println!("taxa.{} {} {} -> {}: {action}", report.table, r.name, r.from, r.to);
+        }
+        for reference in &report.references {
+            let updated = if execute { "updated" } else { "to update" };
+            println!("    {}: {} rows {updated}", reference.column, reference.rows_updated);
+        }
+    }
+    if execute {
+        // Materialized views have copies of the old ids
+        let mut errs = mmoldb_db::db::refresh_game_matviews(&mut conn);
+        errs.extend(mmoldb_db::db::refresh_player_matviews(&mut conn));
+        for err in errs {
+            println!("Couldn't refresh a materialized view: {err}");
+        }
+        println!("Renumbered taxa.");
+    } else {
+        println!("Dry run. Nothing was changed. Pass --execute to renumber.");
+    }
+
+    Ok(())
+}
+
+/// Writes every derived table, but none of the raw entities, to a
+/// zstd-compressed snapshot file
+fn snapshot(mut args: impl Iterator<Item = String>) -> miette::Result<()> {
+    let path = args.next().ok_or_else(|| miette!("{USAGE}"))?;
+    if let Some(other) = args.next() {
+        return Err(miette!("Unexpected argument {other:?}\n{USAGE}"));
+    }
+
+    let pool = mmoldb_db::get_pool(1).into_diagnostic()?;
+    let mut conn = pool.get().into_diagnostic()?;
+
+    let file = File::create_new(&path).into_diagnostic()?;
+    let mut out =
+        zstd::Encoder::new(BufWriter::new(file), SNAPSHOT_COMPRESSION_LEVEL).into_diagnostic()?;
+    let reports = mmoldb_db::db::write_snapshot(&mut conn, &mut out).into_diagnostic()?;
+    let file = out
+        .finish()
+        .into_diagnostic()?
+        .into_inner()
+        .into_diagnostic()?;
+    file.sync_all().into_diagnostic()?;
+
+    for report in &reports {
+        let size = humansize::format_size(report.count, humansize::DECIMAL);
+        println!("{}: {size}", report.table);
+    }
+    println!("Wrote snapshot to {path}.");
+
+    Ok(())
+}
+
+/// Restores a snapshot into a database that has no derived data yet,
+/// running migrations first so a brand new database works
+fn restore_snapshot(mut args: impl Iterator<Item = String>) -> miette::Result<()> {
+    let path = args.next().ok_or_else(|| miette!("{USAGE}"))?;
+    if let Some(other) = args.next() {
+        return Err(miette!("Unexpected argument {other:?}\n{USAGE}"));
+    }
+
+    let file = File::open(&path).into_diagnostic()?;
+    let mut input = zstd::Decoder::new(file).into_diagnostic()?;
+
+    let _ingest_lock = hold_ingest_lock("restore-snapshot")?;
+    mmoldb_db::run_migrations().into_diagnostic()?;
+    let pool = mmoldb_db::get_pool(1).into_diagnostic()?;
+    let mut conn = pool.get().into_diagnostic()?;
+
+    let reports = mmoldb_db::db::restore_snapshot(&mut conn, &mut input, &path, &actor())
+        .into_diagnostic()?;
+    for report in &reports {
+        println!("{}: {} rows", report.table, report.count);
+    }
+
+    // Materialized views aren't in snapshots
+    let mut errs = mmoldb_db::db::refresh_game_matviews(&mut conn);
+    errs.extend(mmoldb_db::db::refresh_player_matviews(&mut conn));
+    for err in errs {
+        println!("Couldn't refresh a materialized view: {err}");
+    }
+    println!("Restored snapshot from {path}.");
+
+    Ok(())
+}
+
+/// Makes the ingest run a finished season's rollover jobs again on its next
+/// check. The jobs replace what they wrote the last time they ran.
+fn rerun_season_rollover(mut args: impl Iterator<Item = String>) -> miette::Result<()> {
+    let season = args
+        .next()
+        .ok_or_else(|| miette!("{USAGE}"))?
+        .parse::<i32>()
+        .into_diagnostic()?;
+    let mut task = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--task" => {
+                task = Some(args.next().ok_or_else(|| miette!("--task needs a value"))?);
+            }
+            other => return Err(miette!("Unexpected argument {other:?}\n{USAGE}")),
+        }
+    }
+
+    // This doesn't take the ingest lock, because it only marks the jobs to
+    // be run again, and the running ingest is what runs them
+    let pool = mmoldb_db::get_pool(1).into_diagnostic()?;
+    let mut conn = pool.get().into_diagnostic()?;
+
+    let cleared =
+        mmoldb_db::db::clear_season_rollovers(&mut conn, season, task.as_deref(), &actor())
+            .into_diagnostic()?;
+    println!(
+        "Cleared {cleared} season rollover jobs for season {season}. The ingest will run them \
+        again the next time it checks."
+    );
+
+    Ok(())
+}
+
+fn print_timings(label: &str, timings: &mut [Duration]) {
+    timings.sort();
+    println!(
+        "{label}: median {:.1} ms, fastest {:.1} ms",
+        timings[timings.len() / 2].as_secs_f64() * 1000.0,
+        timings[0].as_secs_f64() * 1000.0,
+    );
+}
+
+/// Times reading back the events of the latest finished games the way the
+/// round-trip check does, first one query at a time and then with the
+/// queries spread over 1 to `--connections` connections. Only reads.
+fn bench_event_reads(mut args: impl Iterator<Item = String>) -> miette::Result<()> {
+    let mut num_games = BENCH_EVENT_READS_GAMES;
+    let mut repeats = BENCH_EVENT_READS_REPEATS;
+    let mut max_connections = BENCH_EVENT_READS_MAX_CONNECTIONS;
+    while let Some(arg) = args.next() {
+        let target = match arg.as_str() {
+            "--games" => &mut num_games,
+            "--repeats" => &mut repeats,
+            "--connections" => &mut max_connections,
+            other => return Err(miette!("Unexpected argument {other:?}\n{USAGE}")),
+        };
+        let value = args
+            .next()
+            .ok_or_else(|| miette!("{arg} needs a value"))?;
+        *target = value.parse::<usize>().into_diagnostic()?.max(1);
+    }
+
+    let url = mmoldb_db::postgres_url_from_environment();
+    let mut conn = PgConnection::establish(&url).into_diagnostic()?;
+    let taxa = Taxa::new(&mut conn).into_diagnostic()?;
+    let game_ids = mmoldb_db::db::latest_finished_mmolb_game_ids(&mut conn, num_games as i64)
+        .into_diagnostic()?;
+    if game_ids.is_empty() {
+        return Err(miette!("There are no finished games to read back"));
+    }
+    let game_ids = game_ids.iter().map(String::as_str).collect::<Vec<_>>();
+    println!(
+        "Reading back the events of {} games, {repeats} times each",
+        game_ids.len(),
+    );
+
+    let mut timings = Vec::with_capacity(repeats);
+    for _ in 0..repeats {
+        let start = Instant::now();
+        mmoldb_db::db::events_for_games(&mut conn, &taxa, &game_ids).into_diagnostic()?;
+        timings.push(start.elapsed());
+    }
+    print_timings("One query at a time", &mut timings);
+
+    let runtime = tokio::runtime::Runtime::new().into_diagnostic()?;
+    let pool = AsyncConnectionPool::new(max_connections, None);
+    for num_connections in 1..=max_connections {
+        let mut conns = runtime
+            .block_on(pool.get_many(num_connections))
+            .into_diagnostic()?;
+        let mut timings = Vec::with_capacity(repeats);
+        for _ in 0..repeats {
+            let start = Instant::now();
+            runtime
+                .block_on(mmoldb_db::db::events_for_games_concurrently(
+                    &mut conns[..],
+                    &taxa,
+                    &game_ids,
+                ))
+                .into_diagnostic()?;
+            timings.push(start.elapsed());
+        }
+        print_timings(
+            &format!("Concurrently over {num_connections} connections"),
+            &mut timings,
+        );
+    }
+
+    Ok(())
+}
+
+fn main() -> miette::Result<()> {
+    let filter = tracing_subscriber::EnvFilter::builder()
+        .with_default_directive("mmoldb_ingest=info".parse().into_diagnostic()?)
+        .from_env()
+        .into_diagnostic()?
+        .add_directive("mmolb_parsing=off".parse().into_diagnostic()?);
+
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .compact()
+        .init();
+
+    let mut args = std::env::args().skip(1);
+    match args.next().as_deref() {
+        Some("rebuild-table") => rebuild_table(args),
+        Some("recompute-games") => recompute_games(args),
+        Some("rebuild-team-transactions") => rebuild_team_transactions(),
+        Some("rollback") => rollback(args),
+        Some("offload-games") => offload_games(),
+        Some("restore-games") => restore_games(),
+        Some("resolve-player-identities") => resolve_player_identities(args),
+        Some("reconcile-taxa") => reconcile_taxa(args),
+        Some("renumber-taxa") => renumber_taxa(args),
+        Some("snapshot") => snapshot(args),
+        Some("restore-snapshot") => restore_snapshot(args),
+        Some("rerun-season-rollover") => rerun_season_rollover(args),
+        Some("bench-event-reads") => bench_event_reads(args),
+        _ => Err(miette!("{USAGE}")),
+    }
+}
@@ -1,20 +1,44 @@
+use chron::SharedMirrorRange;
 use figment::Figment;
 use figment::providers::{Env, Format, Serialized, Toml};
 use mmolb_parsing::player::Deserialize;
+use mmoldb_db::{ObjectStoreConfig, PoolHealthConfig, QueryLogConfig};
 use serde::Serialize;
 use std::num::NonZero;
+use std::path::PathBuf;
 
 #[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub struct IngestibleConfig {
     pub enable_fetch: bool,
     pub enable_processing: bool,
+    /// Page size for requests to Chron
     pub chron_fetch_batch_size: NonZero<usize>,
     pub chron_fetch_interval_seconds: u64,
+    /// How many fetched entities or versions are inserted at once
     pub insert_raw_entity_batch_size: NonZero<usize>,
     pub processing_interval_seconds: u64,
+    /// How many entities or versions each processing worker handles at once
     pub process_batch_size: NonZero<usize>,
+    /// How many versions' rows are built and inserted in each transaction
+    /// within a processing batch, so a big backfill batch doesn't have to
+    /// be held in memory and inserted all at once. Games don't use this.
+    pub insert_chunk_size: NonZero<usize>,
+    /// How many processing workers to run. Defaults to 1.
     pub ingest_parallelism: Option<NonZero<usize>>,
     pub debug_db_insert_delay: f64,
+    /// How many times in a row a fetch or processing pass is retried after a
+    /// transient error (network or database connection trouble) before the
+    /// ingest gives up
+    pub max_transient_retries: u32,
+    /// Delay before the first retry after a transient error. Doubles with
+    /// each consecutive retry.
+    pub transient_retry_backoff_seconds: u64,
+    /// Process entities that changed recently before backfilling the rest,
+    /// so the freshest data shows up in the database first during long runs
+    pub prioritize_recent: bool,
+    /// Entities with a version from within this many hours, or since the
+    /// last processing pass if that was longer ago, count as recent
+    pub recent_window_hours: u64,
 }
 
 impl Default for IngestibleConfig {
@@ -27,37 +51,222 @@ impl Default for IngestibleConfig {
             insert_raw_entity_batch_size: 1000.try_into().unwrap(),
             processing_interval_seconds: 10 * 60,
             process_batch_size: 1000.try_into().unwrap(),
+            insert_chunk_size: 200.try_into().unwrap(),
             ingest_parallelism: None,
             debug_db_insert_delay: 0.0,
+            max_transient_retries: 5,
+            transient_retry_backoff_seconds: 30,
+            prioritize_recent: true,
+            recent_window_hours: 24,
         }
     }
 }
 
+/// An S3-compatible bucket to move the raw data of old games into
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+pub struct GameOffloadConfig {
+    /// Games from this many of the most recent seasons stay in the database
+    pub keep_recent_seasons: i32,
+    #[serde(flatten)]
+    pub store: ObjectStoreConfig,
+}
+
+/// Which raw versions in `data.versions` to delete once they've been
+/// processed. Pruned versions can't be reprocessed, so rebuilds and
+/// rollbacks that reach back past them will leave gaps.
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+pub struct VersionRetentionConfig {
+    /// Kinds to prune, e.g. `["player", "team"]`
+    pub kinds: Vec<String>,
+    /// How many of each entity's latest versions to always keep
+    pub keep_latest_versions: NonZero<u32>,
+    /// Versions are only pruned once they've been out of date for this
+    /// many days
+    pub min_age_days: u32,
+    /// If set, versions that have been out of date for this many days are
+    /// compressed in place. Compressed versions can still be reprocessed.
+    #[serde(default)]
+    pub compress_after_days: Option<u32>,
+    /// How many entities' versions are pruned in each transaction. Also
+    /// how many versions are compressed in each transaction.
+    pub batch_size: NonZero<usize>,
+    pub interval_minutes: u64,
+}
+
+/// Tables to periodically copy into a SQLite file for local analysis
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+pub struct AnalyticsExportConfig {
+    /// Tables to export, e.g. `["data.games", "data.events"]`. Each one is
+    /// written to a SQLite table named `schema_table`. Every table needs a
+    /// primary key, which the export pages by.
+    pub tables: Vec<String>,
+    /// Where to write the SQLite file. It's replaced on every export.
+    pub output_path: PathBuf,
+    /// How many rows are read from postgres at once
+    pub page_size: NonZero<usize>,
+    pub interval_minutes: u64,
+}
+
+/// A Chron-compatible API other than main MMOLB's, like one serving a test
+/// universe
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+pub struct ChronSourceConfig {
+    /// Stored in the `source` column of the raw tables, so this source's ids
+    /// can't collide with main MMOLB's. Must never change once data has
+    /// been ingested with it.
+    pub name: String,
+    /// Where the API is, e.g. `https://example.com/api/chron/v0`
+    pub chron_base_url: String,
+}
+
+/// Where fetched data is written
+#[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SinkConfig {
+    /// Into the database, where the processing stages turn it into the
+    /// tables mmoldb serves
+    #[default]
+    Postgres,
+    /// Into newline-delimited JSON files in `dir`, one per kind. Nothing is
+    /// processed and the database isn't used at all. See
+    /// `mmoldb_ingest::NdjsonSink` for the format.
+    Ndjson { dir: PathBuf },
+}
+
 #[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub struct IngestConfig {
     pub db_pool_size: u32,
+    /// Size of the separate pool of async connections, which processing
+    /// uses to stream unprocessed versions and to check games' round trips.
+    /// Each processing stage holds one while it runs, and each game
+    /// processing worker briefly holds a few per batch.
+    pub async_db_pool_size: NonZero<usize>,
+    /// What to do if another ingest is already running against the same
+    /// database. If true, wait for it to exit. If false, exit immediately.
+    pub wait_for_ingest_lock: bool,
     pub set_postgres_statement_timeout: Option<i64>,
+    /// Query timing for every connection the ingest uses. Slow queries are
+    /// recorded in `info.slow_queries`.
+    pub query_log: QueryLogConfig,
+    /// Connection recycling and periodic pool health logging
+    pub db_pool_health: PoolHealthConfig,
     pub use_local_cheap_cashews: bool,
+    /// If set, fetch from fixture files in this directory instead of Chron.
+    /// See `chron::LocalChron` for the layout.
+    pub chron_fixture_dir: Option<PathBuf>,
+    /// If set, save every response from Chron to this directory, so it can
+    /// be replayed in tests by `chron::FixtureServer`
+    pub record_chron_responses_dir: Option<PathBuf>,
+    /// Whether to check every page fetched from Chron for signs of
+    /// corruption. Bad pages are fetched again and recorded in
+    /// `info.chron_anomalies`.
+    pub verify_chron_pages: bool,
+    /// Spans of time that both Chron mirrors have complete data for. These
+    /// are fetched from whichever mirror responds fastest, falling back to
+    /// the other on errors. Everything else is fetched from the mirror
+    /// that's known to have it.
+    pub shared_chron_mirror_ranges: Vec<SharedMirrorRange>,
+    /// Where fetched data is written. Anything but Postgres only runs the
+    /// fetch stages.
+    pub sink: SinkConfig,
+    /// Fetch from this source instead of main MMOLB. Its data is only
+    /// stored raw: only the fetch stages run, since nothing downstream of
+    /// the raw tables knows about sources yet. Runs alongside the main
+    /// MMOLB ingest, not instead of it.
+    pub source: Option<ChronSourceConfig>,
     pub fetch_known_missing_games: bool,
+    /// Insert game events in chunks that return each event's id, rather
+    /// than with a COPY followed by a query for the ids. This is slower, and
+    /// is mainly here to compare the two.
+    pub strict_event_ordering: bool,
+    /// If set, games that are still in progress are treated as forever
+    /// incomplete once they haven't had a new version in this many hours
+    /// and a game from a later day has started
+    pub forever_incomplete_after_hours: Option<u64>,
+    /// Whether to run the one-time jobs (final standings, awards, and
+    /// archiving old games if `game_offload` is configured) for each season
+    /// after it ends
+    pub enable_season_rollover: bool,
+    /// Whether to periodically snapshot the distribution of each attribute
+    /// across players who are on a team, for `data.attribute_distributions`
+    pub enable_attribute_distributions: bool,
+    /// Whether to periodically check the player, team, and equipment
+    /// version tables for gaps and overlaps between consecutive versions,
+    /// and log them in `info.version_ingest_log`
+    pub enable_version_chain_checks: bool,
+    /// Whether to periodically check data quality (as shown on mmoldb-app's
+    /// `/quality` page) and publish an alert on the
+    /// `mmoldb_quality_alerts` Postgres channel when a status changes
+    pub enable_quality_alerts: bool,
     pub team_ingest: IngestibleConfig,
     pub team_feed_ingest: IngestibleConfig,
     pub player_ingest: IngestibleConfig,
     pub player_feed_ingest: IngestibleConfig,
     pub game_ingest: IngestibleConfig,
+    pub stadium_ingest: IngestibleConfig,
+    pub time_ingest: IngestibleConfig,
+    /// Names of ingest stages (e.g. "player_fetch", "game_processing") in
+    /// the order they should be launched. Stages not listed here are
+    /// launched afterwards in their default order.
+    pub stage_order: Option<Vec<String>>,
+    /// Names of ingest stages that should not be launched at all. This
+    /// applies on top of each kind's `enable_fetch` and `enable_processing`.
+    pub disabled_stages: Vec<String>,
+    /// Whether to launch stages that were aborted, i.e. that were still
+    /// running when the previous ingest died. If false, they're held back
+    /// (and stay marked as aborted in `info.ingest_task_state`) until this
+    /// is set, so whatever killed them can be looked into first.
+    pub resume_aborted_stages: bool,
+    /// If set, `mmoldb-cli offload-games` moves the raw data of old games to
+    /// this object storage, and anything that reprocesses them fetches them
+    /// back from it.
+    pub game_offload: Option<GameOffloadConfig>,
+    /// If set, old raw versions are periodically deleted from the database
+    pub version_retention: Option<VersionRetentionConfig>,
+    /// If set, the listed tables are periodically exported to a SQLite file
+    pub analytics_export: Option<AnalyticsExportConfig>,
 }
 
 impl Default for IngestConfig {
     fn default() -> Self {
         Self {
             db_pool_size: 50,
+            async_db_pool_size: 20.try_into().unwrap(),
+            wait_for_ingest_lock: false,
             set_postgres_statement_timeout: Some(0), // 0 means no timeout
+            query_log: Default::default(),
+            db_pool_health: Default::default(),
             use_local_cheap_cashews: false,
+            chron_fixture_dir: None,
+            record_chron_responses_dir: None,
+            verify_chron_pages: true,
+            shared_chron_mirror_ranges: Vec::new(),
+            sink: SinkConfig::Postgres,
+            source: None,
             fetch_known_missing_games: false,
+            strict_event_ordering: false,
+            forever_incomplete_after_hours: None,
+            enable_season_rollover: true,
+            enable_attribute_distributions: true,
+            enable_version_chain_checks: true,
+            enable_quality_alerts: true,
             team_ingest: Default::default(),
             team_feed_ingest: Default::default(),
             player_ingest: Default::default(),
             player_feed_ingest: Default::default(),
-            game_ingest: Default::default(),
+            game_ingest: IngestibleConfig {
+                // Games are much bigger than versions
+                process_batch_size: 100.try_into().unwrap(),
+                ..Default::default()
+            },
+            stadium_ingest: Default::default(),
+            time_ingest: Default::default(),
+            stage_order: None,
+            disabled_stages: Vec::new(),
+            resume_aborted_stages: true,
+            game_offload: None,
+            version_retention: None,
+            analytics_export: None,
         }
     }
 }
@@ -1,27 +1,105 @@
+use super::sink::{FeedEventVersion, Sink};
 use crate::IngestFatalError;
-use chron::{Chron, ChronEntity};
-use chrono::NaiveDateTime;
+use crate::bulk_insert_gate::BulkInsertGate;
+use chron::{
+    AnomalyHandler, Chron, ChronAnomaly, ChronEntity, ChronProvider, FixtureRecorder, LocalChron,
+    SharedMirrorRange,
+};
 use futures::{FutureExt, StreamExt};
 use futures::{TryStreamExt, pin_mut};
 use hashbrown::HashMap;
 use hashbrown::hash_map::Entry;
 use itertools::Either;
 use mmolb_parsing::player::Deserialize;
-use mmoldb_db::{ConnectionPool, db};
 use std::iter;
 use std::num::NonZero;
+use std::path::PathBuf;
+use std::sync::Arc;
 use tokio_util::sync::CancellationToken;
-use tracing::{error, info};
+use tracing::{info, warn};
 
 #[derive(Debug, Clone)]
 pub struct ChronFetchArgs {
     pub shutdown_requested: CancellationToken,
-    pub pool: ConnectionPool,
+    /// Where fetched data is written, and where each fetch resumes from
+    pub sink: Arc<dyn Sink>,
+    /// Fetch from this Chron-compatible API instead of the Chron mirrors
+    pub chron_base_url: Option<String>,
     pub use_local_cheap_cashews: bool,
+    /// Read from fixture files in this directory instead of Chron. See
+    /// [`LocalChron`] for the layout.
+    pub chron_fixture_dir: Option<PathBuf>,
+    /// Save every response from Chron in this directory. See
+    /// [`FixtureRecorder`].
+    pub record_chron_responses_dir: Option<PathBuf>,
+    /// Check pages from Chron for corruption. Has no effect on fixture data.
+    pub verify_chron_pages: bool,
+    /// Spans that either Chron mirror can serve
+    pub shared_chron_mirror_ranges: Vec<SharedMirrorRange>,
     pub enabled: bool,
     pub chron_fetch_interval_seconds: u64,
     pub chron_fetch_batch_size: NonZero<usize>,
     pub insert_raw_entity_batch_size: NonZero<usize>,
+    pub bulk_inserts: BulkInsertGate,
+}
+
+/// Hands each anomaly to the sink to record. Sinks do blocking I/O, so
+/// that happens on the blocking pool rather than in the fetch.
+fn anomaly_recorder(sink: Arc<dyn Sink>) -> AnomalyHandler {
+    Arc::new(move |anomaly: ChronAnomaly| {
+        let sink = sink.clone();
+        tokio::task::spawn_blocking(move || sink.record_chron_anomaly(&anomaly));
+    })
+}
+
+/// Runs `f` on the blocking pool, since every sink does blocking I/O
+async fn with_sink<T: Send + 'static>(
+    sink: &Arc<dyn Sink>,
+    f: impl FnOnce(&dyn Sink) -> Result<T, IngestFatalError> + Send + 'static,
+) -> Result<T, IngestFatalError> {
+    let sink = sink.clone();
+    tokio::task::spawn_blocking(move || f(sink.as_ref()))
+        .await
+        .map_err(IngestFatalError::JoinError)?
+}
+
+async fn chron_provider(args: &ChronFetchArgs) -> Box<dyn ChronProvider> {
+    match &args.chron_fixture_dir {
+        Some(dir) => Box::new(LocalChron::new(dir)),
+        None => {
+            let mut chron = Chron::new(args.chron_fetch_batch_size)
+                .with_local_cheap_cashews(args.use_local_cheap_cashews)
+                .with_shared_mirror_ranges(args.shared_chron_mirror_ranges.clone());
+            if let Some(base_url) = &args.chron_base_url {
+                chron = chron.with_base_url(base_url.clone());
+            }
+            if let Some(dir) = &args.record_chron_responses_dir {
+                // Recording is only ever for tests, so it's not worth
+                // stopping the fetch over
+                match FixtureRecorder::new(dir) {
+                    Ok(recorder) => chron = chron.with_fixture_recording(recorder),
+                    Err(err) => warn!("Not recording Chron responses: {err}"),
+                }
+            }
+            if !args.shared_chron_mirror_ranges.is_empty() {
+                chron.probe_mirrors().await;
+            }
+            if args.verify_chron_pages {
+                Box::new(chron.with_page_verification(anomaly_recorder(args.sink.clone())))
+            } else {
+                Box::new(chron)
+            }
+        }
+    }
+}
+
+/// Chron's mirrors overlap around the cutover dates, so the same version can
+/// come back twice. Those are dropped before they get here.
+fn log_duplicates(chron: &dyn ChronProvider, kind: &str) {
+    let duplicates = chron.duplicates_suppressed();
+    if duplicates > 0 {
+        info!("Dropped {duplicates} duplicate {kind} version(s) from Chron");
+    }
 }
 
 // It may be possible to remove 'static
@@ -29,17 +107,14 @@ pub async fn fetch_entity_kind(
     kind: &'static str,
     args: ChronFetchArgs,
 ) -> Result<(), IngestFatalError> {
-    let mut conn = args.pool.get()?;
-    let chron = Chron::new(args.chron_fetch_batch_size);
+    let chron = chron_provider(&args).await;
 
-    let start_date = db::get_latest_entity_valid_from(&mut conn, kind)?
-        .as_ref()
-        .map(NaiveDateTime::and_utc);
+    let start_date = with_sink(&args.sink, move |sink| sink.latest_entity_valid_from(kind)).await?;
 
     info!("{} fetch will start from date {:?}", kind, start_date);
 
     let stream = chron
-        .entities(kind, start_date, 3, args.use_local_cheap_cashews)
+        .entities(kind, start_date)
         // End the stream early when cancellation is requested. By ending the stream at this
         // point, we stop waiting for any more network requests but we still process any that
         // are still waiting to be collected in the next try_chunks item.
@@ -68,7 +143,10 @@ pub async fn fetch_entity_kind(
             Err(err) => (err.0, Some(err.1)),
         };
         info!("Saving {} {}(s)", chunk.len(), kind);
-        let inserted = db::insert_entities(&mut conn, chunk)?;
+        let inserted = {
+            let _bulk_insert = args.bulk_inserts.begin();
+            with_sink(&args.sink, move |sink| sink.write_entities(kind, chunk)).await?
+        };
         info!("Saved {} {}(s)", inserted, kind);
 
         if let Some(err) = maybe_err {
@@ -76,6 +154,7 @@ pub async fn fetch_entity_kind(
         }
     }
 
+    log_duplicates(chron.as_ref(), kind);
     Ok(())
 }
 
@@ -84,17 +163,15 @@ pub async fn fetch_version_kind(
     kind: &'static str,
     args: ChronFetchArgs,
 ) -> Result<(), IngestFatalError> {
-    let mut conn = args.pool.get()?;
-    let chron = Chron::new(args.chron_fetch_batch_size);
+    let chron = chron_provider(&args).await;
 
-    let start_cursor =
-        db::get_latest_raw_version_cursor(&mut conn, kind)?.map(|(dt, id)| (dt.and_utc(), id));
+    let start_cursor = with_sink(&args.sink, move |sink| sink.latest_version_cursor(kind)).await?;
     let start_date = start_cursor.as_ref().map(|(dt, _)| *dt);
 
     info!("{} fetch will start from date {:?}", kind, start_date);
 
     let stream = chron
-        .versions(kind, start_date, 3, args.use_local_cheap_cashews)
+        .versions(kind, start_date)
         // End the stream early when cancellation is requested. By ending the stream at this
         // point, we stop waiting for any more network requests but we still process any that
         // are still waiting to be collected in the next try_chunks item.
@@ -144,13 +221,9 @@ pub async fn fetch_version_kind(
             Err(err) => (err.0, Some(err.1)),
         };
         info!("{kind} stage 1 ingest saving {} {kind}(s)", chunk.len());
-        let inserted = match db::insert_versions_one_error(&mut conn, &chunk) {
-            Ok(x) => Ok(x),
-            Err((entity, err)) => {
-                error!("Error in stage 1 ingest write: {err} while inserting: {entity:#?}");
-                Err(err)
-            }
-        }?;
+        let bulk_insert = args.bulk_inserts.begin();
+        let inserted = with_sink(&args.sink, move |sink| sink.write_versions(kind, &chunk)).await?;
+        drop(bulk_insert);
         info!("{kind} stage 1 ingest saved {inserted} {kind}(s)");
 
         if let Some(err) = maybe_err {
@@ -159,6 +232,7 @@ pub async fn fetch_version_kind(
     }
 
     info!("{} stage 1 ingest finished", kind);
+    log_duplicates(chron.as_ref(), kind);
     Ok(())
 }
 
@@ -167,20 +241,20 @@ pub async fn fetch_feed_event_version_kind(
     kind: &'static str,
     args: ChronFetchArgs,
 ) -> Result<(), IngestFatalError> {
-    let mut conn = args.pool.get()?;
-    let chron = Chron::new(args.chron_fetch_batch_size);
+    let chron = chron_provider(&args).await;
 
-    let start_cursor =
-        db::get_latest_raw_feed_event_version_cursor(&mut conn, kind)?.map(|(dt, id, _)| (dt, id));
-    let start_cursor_utc = start_cursor.as_ref().map(|(dt, id)| (dt.and_utc(), id));
+    let start_cursor = with_sink(&args.sink, move |sink| {
+        sink.latest_feed_event_version_cursor(kind)
+    })
+    .await?;
 
-    let start_date = start_cursor.as_ref().map(|(dt, _)| dt.and_utc());
+    let start_date = start_cursor.as_ref().map(|(dt, _)| *dt);
     info!("{} fetch will start from date {:?}", kind, start_date,);
 
     // TODO Add a Metric for the size of this
     let mut event_cache = HashMap::new();
     let stream = chron
-        .versions(kind, start_date, 3, args.use_local_cheap_cashews)
+        .versions(kind, start_date)
         // End the stream early when cancellation is requested. By ending the stream at this
         // point, we stop waiting for any more network requests but we still process any that
         // are still waiting to be collected in the next try_chunks item.
@@ -209,7 +283,7 @@ pub async fn fetch_feed_event_version_kind(
         // the stream api.
         .skip_while(|result| {
             let skip_this =
-                start_cursor_utc
+                start_cursor
                     .as_ref()
                     .is_some_and(|(start_valid_from, start_entity_id)| {
                         result.as_ref().is_ok_and(|entity| {
@@ -234,7 +308,7 @@ pub async fn fetch_feed_event_version_kind(
                     match serde_json::from_value::<SomethingWithFeed>(version.data) {
                         Ok(des) => {
                             if let Some(feed) = des.feed {
-                                let dt = version.valid_from.naive_utc();
+                                let dt = version.valid_from;
                                 Either::Left(Either::Right(feed.into_iter().enumerate().map(
                                     move |(idx, item)| {
                                         Ok((version.entity_id.clone(), idx as i32, dt, item))
@@ -269,13 +343,12 @@ pub async fn fetch_feed_event_version_kind(
         };
 
         info!("{kind} stage 1 ingest saving {} {kind}(s)", chunk.len());
-        let inserted = match db::insert_feed_event_versions(&mut conn, kind, &chunk) {
-            Ok(x) => Ok(x),
-            Err(err) => {
-                error!("Error in stage 1 ingest write: {err}");
-                Err(err)
-            }
-        }?;
+        let bulk_insert = args.bulk_inserts.begin();
+        let inserted = with_sink(&args.sink, move |sink| {
+            sink.write_feed_event_versions(kind, &chunk)
+        })
+        .await?;
+        drop(bulk_insert);
         info!("{kind} stage 1 ingest saved {inserted} {kind}(s)");
 
         if let Some(err) = maybe_err {
@@ -284,12 +357,13 @@ pub async fn fetch_feed_event_version_kind(
     }
 
     info!("{} stage 1 ingest finished", kind);
+    log_duplicates(chron.as_ref(), kind);
     Ok(())
 }
 
 fn filter_cached(
     event_cache: &mut HashMap<(String, i32), serde_json::Value>,
-    result: &Result<(String, i32, NaiveDateTime, serde_json::Value), IngestFatalError>,
+    result: &Result<FeedEventVersion, IngestFatalError>,
 ) -> bool {
     match result {
         Ok((id, idx, _, item)) => {
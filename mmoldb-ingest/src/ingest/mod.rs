@@ -1,23 +1,36 @@
 use futures::FutureExt;
 mod fetch;
 mod processing;
+mod progress;
+mod sink;
+mod stage;
 
+use crate::async_pool::AsyncConnectionPool;
+use crate::bulk_insert_gate::BulkInsertGate;
 use crate::config::{IngestConfig, IngestibleConfig};
+use crate::offload::ObjectStore;
 use crate::partitioner::Partitioner;
+use crate::processing_locks::ProcessingLocks;
 use chron::{ChronEntity, ChronStreamError};
-use chrono::{DateTime, NaiveDateTime, Utc};
+use chrono::{DateTime, Utc};
 pub use fetch::ChronFetchArgs;
 use futures::{Stream, StreamExt, pin_mut};
 use hashbrown::HashMap;
 use hashbrown::hash_map::Entry;
 use itertools::Itertools;
 use miette::Diagnostic;
-use mmoldb_db::models::NewVersionIngestLog;
-use mmoldb_db::taxa::Taxa;
+use mmoldb_db::async_db::ProcessingPass;
+use mmoldb_db::db::{EventInsertMode, IngestProgressEvent};
+use mmoldb_db::models::{NewFeedEventFailure, NewVersionIngestLog};
+use mmoldb_db::taxa::{Taxa, TaxaIngestErrorCode};
 use mmoldb_db::{
-    AsyncConnection, AsyncPgConnection, ConnectionPool, PgConnection, QueryError, QueryResult, db,
+    AsyncPgConnection, ConnectionPool, DatabaseErrorKind, PgConnection, QueryError, QueryResult,
+    db,
 };
 pub use processing::ProcessingArgs;
+pub use progress::ProgressPublisher;
+pub use sink::*;
+pub use stage::*;
 use rayon::iter::IntoParallelIterator;
 use rayon::iter::ParallelIterator;
 use std::fmt::{Debug, Display, Formatter};
@@ -69,26 +82,167 @@ pub enum IngestFatalError {
 
     #[error(transparent)]
     IoError(#[from] std::io::Error),
+
+    #[error("more than one ingest stage is named {0:?}")]
+    DuplicateIngestStage(String),
+
+    #[error("config refers to ingest stage {0:?}, but no stage with that name is registered")]
+    UnknownIngestStage(String),
+
+    #[error(transparent)]
+    OffloadError(#[from] crate::offload::OffloadError),
+
+    #[error("{kind} version of {entity_id} from {valid_from} has no integer feed_event_index")]
+    MissingFeedEventIndex {
+        kind: String,
+        entity_id: String,
+        valid_from: DateTime<Utc>,
+    },
+
+    #[error("{0} versions don't have a feed")]
+    NoFeed(&'static str),
+}
+
+/// Broad categories of ingest error, used to decide whether a failed fetch
+/// or processing pass is worth retrying
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum IngestErrorClass {
+    /// Network trouble talking to Chron, or Chron reporting a server error
+    ChronNetwork,
+    /// Couldn't reach the database, or the database asked for a retry
+    DbUnavailable,
+    /// The database rejected the data, e.g. a constraint violation
+    DbConstraint,
+    /// Data from Chron couldn't be parsed. This is a bug.
+    Parse,
+    Other,
+}
+
+impl IngestErrorClass {
+    /// Transient errors are expected to go away on their own, so the ingest
+    /// pauses and retries instead of exiting
+    pub fn is_transient(self) -> bool {
+        matches!(
+            self,
+            IngestErrorClass::ChronNetwork | IngestErrorClass::DbUnavailable
+        )
+    }
+}
+
+impl IngestFatalError {
+    pub fn class(&self) -> IngestErrorClass {
+        match self {
+            IngestFatalError::ChronStreamError(e) => chron_error_class(e),
+            IngestFatalError::DeserializeError(_)
+            | IngestFatalError::NonAsciiEntityId(_)
+            | IngestFatalError::NonHexEntityId(_)
+            | IngestFatalError::MissingFeedEventIndex { .. } => IngestErrorClass::Parse,
+            IngestFatalError::DbError(e)
+            | IngestFatalError::OffloadError(crate::offload::OffloadError::Db(e)) => {
+                query_error_class(e)
+            }
+            IngestFatalError::DbPoolError(_) => IngestErrorClass::DbUnavailable,
+            // The other connection errors mean the connection settings are
+            // wrong, which retrying won't fix
+            IngestFatalError::AsyncDbPoolError(mmoldb_db::ConnectionError::BadConnection(_)) => {
+                IngestErrorClass::DbUnavailable
+            }
+            _ => IngestErrorClass::Other,
+        }
+    }
 }
 
-pub struct VersionIngestLogs<'a> {
+fn chron_error_class(err: &ChronStreamError) -> IngestErrorClass {
+    if err.is_transient() {
+        return IngestErrorClass::ChronNetwork;
+    }
+
+    match err {
+        ChronStreamError::RequestDeserializeError(_) | ChronStreamError::DeserializeError(_) => {
+            IngestErrorClass::Parse
+        }
+        _ => IngestErrorClass::Other,
+    }
+}
+
+fn query_error_class(err: &QueryError) -> IngestErrorClass {
+    match err {
+        QueryError::DatabaseError(kind, _) => match kind {
+            DatabaseErrorKind::UniqueViolation
+            | DatabaseErrorKind::ForeignKeyViolation
+            | DatabaseErrorKind::NotNullViolation
+            | DatabaseErrorKind::CheckViolation
+            | DatabaseErrorKind::RestrictViolation
+            | DatabaseErrorKind::ExclusionViolation => IngestErrorClass::DbConstraint,
+            DatabaseErrorKind::SerializationFailure
+            | DatabaseErrorKind::ClosedConnection
+            | DatabaseErrorKind::UnableToSendCommand => IngestErrorClass::DbUnavailable,
+            _ => IngestErrorClass::Other,
+        },
+        _ => IngestErrorClass::Other,
+    }
+}
+
+/// How a fetch or processing pass is retried after a transient error
+#[derive(Debug, Clone)]
+pub struct TransientRetryPolicy {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+}
+
+impl TransientRetryPolicy {
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+    }
+}
+
+pub struct VersionIngestLogs<'a, 't> {
     pub kind: &'a str,
     pub entity_id: &'a str,
-    pub valid_from: NaiveDateTime,
+    pub valid_from: DateTime<Utc>,
+    pub feed_event_index: Option<i32>,
+    taxa: &'t Taxa,
     logs: Vec<NewVersionIngestLog<'a>>,
 }
 
-impl<'a> VersionIngestLogs<'a> {
-    pub fn new(kind: &'a str, entity_id: &'a str, valid_from: DateTime<Utc>) -> Self {
+impl<'a, 't> VersionIngestLogs<'a, 't> {
+    pub fn new(
+        taxa: &'t Taxa,
+        kind: &'a str,
+        entity_id: &'a str,
+        valid_from: DateTime<Utc>,
+    ) -> Self {
         Self {
             kind,
             entity_id,
-            valid_from: valid_from.naive_utc(),
+            valid_from,
+            feed_event_index: None,
+            taxa,
             logs: Vec::new(),
         }
     }
 
-    pub fn add_log(&mut self, log_level: i32, s: impl Into<String>) {
+    /// Logs for one event from a version of a feed
+    pub fn for_feed_event(
+        taxa: &'t Taxa,
+        kind: &'a str,
+        entity_id: &'a str,
+        feed_event_index: i32,
+        valid_from: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            feed_event_index: Some(feed_event_index),
+            ..Self::new(taxa, kind, entity_id, valid_from)
+        }
+    }
+
+    fn push(
+        &mut self,
+        log_level: i32,
+        error_code: Option<TaxaIngestErrorCode>,
+        s: impl Into<String>,
+    ) {
         let log_index = self.logs.len() as i32;
         self.logs.push(NewVersionIngestLog {
             kind: self.kind,
@@ -97,37 +251,42 @@ impl<'a> VersionIngestLogs<'a> {
             log_index,
             log_level,
             log_text: s.into(),
+            error_code: error_code.map(|code| self.taxa.ingest_error_code_id(code)),
+            feed_event_index: self.feed_event_index,
         });
     }
 
+    // Anything at warning level or above needs an error code, so it can be
+    // counted on the issues page
+
     #[allow(dead_code)]
-    pub fn critical(&mut self, s: impl Into<String>) {
-        self.add_log(0, s);
+    pub fn critical(&mut self, error_code: TaxaIngestErrorCode, s: impl Into<String>) {
+        self.push(0, Some(error_code), s);
     }
 
     #[allow(dead_code)]
-    pub fn error(&mut self, s: impl Into<String>) {
-        self.add_log(1, s);
+    pub fn error(&mut self, error_code: TaxaIngestErrorCode, s: impl Into<String>) {
+        self.push(1, Some(error_code), s);
     }
 
     #[allow(dead_code)]
-    pub fn warn(&mut self, s: impl Into<String>) {
-        self.add_log(2, s);
+    pub fn warn(&mut self, error_code: TaxaIngestErrorCode, s: impl Into<String>) {
+        self.push(2, Some(error_code), s);
     }
 
     #[allow(dead_code)]
     pub fn info(&mut self, s: impl Into<String>) {
-        self.add_log(3, s);
+        self.push(3, None, s);
     }
 
     #[allow(dead_code)]
     pub fn debug(&mut self, s: impl Into<String>) {
-        self.add_log(4, s);
+        self.push(4, None, s);
     }
 
     #[allow(dead_code)]
     pub fn trace(&mut self, s: impl Into<String>) {
-        self.add_log(5, s);
+        self.push(5, None, s);
     }
 
     pub fn into_vec(self) -> Vec<NewVersionIngestLog<'a>> {
@@ -191,10 +350,6 @@ impl IngestErrorContainer {
     }
 }
 
-pub trait IngestStage {
-    fn name(&self) -> &'static str;
-}
-
 pub trait Ingestable {
     const KIND: &'static str;
 
@@ -215,16 +370,30 @@ pub trait IngestibleFromVersions {
     type Ident: Clone + Debug + Eq + Hash + Send;
 
     fn trim_unused(version: &serde_json::Value) -> serde_json::Value;
-    fn ident_raw(entity: &ChronEntity<serde_json::Value>) -> Self::Ident;
+    fn ident_raw(
+        entity: &ChronEntity<serde_json::Value>,
+    ) -> Result<Self::Ident, IngestFatalError>;
     fn ident(entity: &ChronEntity<Self::Entity>) -> Self::Ident;
+    /// Feed event kinds return the version's feed event index, which
+    /// puts versions that fail to deserialize in the dead-letter queue
+    fn feed_event_index(entity: &ChronEntity<serde_json::Value>) -> Option<i32> {
+        let _ = entity;
+        None
+    }
+    /// Whether `insert_batch` splits its versions into chunks of
+    /// `insert_chunk_size` itself. Otherwise they're split up before it's
+    /// called, and each chunk is inserted with its own call.
+    const INSERTS_IN_CHUNKS: bool = false;
     fn insert_batch(
         conn: &mut PgConnection,
         taxa: &Taxa,
         versions: &Vec<PreparedIngestItem<Self::Ident, Self::Entity>>,
+        insert_chunk_size: NonZero<usize>,
     ) -> QueryResult<(usize, usize)>;
     fn stream_unprocessed_versions(
         conn: &mut AsyncPgConnection,
         kind: &str,
+        pass: ProcessingPass,
     ) -> impl Future<
         Output = QueryResult<
             impl Stream<Item = QueryResult<ChronEntity<serde_json::Value>>> + Send,
@@ -295,49 +464,54 @@ impl<VersionIngest: IngestibleFromVersions + Send + Sync + 'static> Stage2Ingest
             .collect::<Result<Vec<_>, _>>()
             .map_err(IngestFatalError::TaskSpawnError)?;
 
-        // TODO Pool this too?
-        let url = mmoldb_db::postgres_url_from_environment();
-        let mut async_conn = AsyncPgConnection::establish(&url).await?;
+        let mut async_conn = args.async_pool.get().await?;
+
+        let passes = args.processing_passes(&mut async_conn, self.kind).await;
 
         // Probably not all of this needs to be in the loop but I'm tired, boss
         loop {
-            let versions_stream = VersionIngest::stream_unprocessed_versions(
-                &mut async_conn,
-                self.kind,
-            )
-            .await?
-                .take_until(args.shutdown_requested.cancelled().then(|()| {
-                    // Some detail of the Rust compiler makes it forget that this is 'static
-                    // during some important checking phase. The only way I've found to make
-                    // that not cause issues is to make it an owned value.
-                    let kind = self.kind.to_string();
-                    async move {
-                        info!(
-                            "Closing {} processing stream because shutdown was requested",
-                            kind
+            for &pass in &passes {
+                if args.shutdown_requested.is_cancelled() {
+                    break;
+                }
+                info!("Starting {} stage 2 {:?} pass", self.kind, pass);
+
+                let versions_stream =
+                    VersionIngest::stream_unprocessed_versions(&mut async_conn, self.kind, pass)
+                        .await?
+                        .take_until(args.shutdown_requested.cancelled().then(|()| {
+                            // Some detail of the Rust compiler makes it forget that this is 'static
+                            // during some important checking phase. The only way I've found to make
+                            // that not cause issues is to make it an owned value.
+                            let kind = self.kind.to_string();
+                            async move {
+                                info!(
+                                    "Closing {} processing stream because shutdown was requested",
+                                    kind
+                                );
+                            }
+                        }));
+                pin_mut!(versions_stream);
+
+                while let Some(version_result) = versions_stream.next().await {
+                    let version = version_result?;
+                    let assigned_worker = partitioner.partition_for(&version.entity_id)?;
+                    // This panics on OOB, which is correct
+                    let (pipe, _) = &tasks[assigned_worker];
+
+                    // If the send fails it's probably because a child errored. Propagate child
+                    // errors first
+                    if let Err(pipe_err) = pipe.send(version).await {
+                        warn!(
+                            "Got a pipe error, which probably means there's an error in a child task. Joining child tasks..."
                         );
+                        for (pipe, task) in tasks.into_iter() {
+                            drop(pipe); // Signals child to exit
+                            task.await.map_err(IngestFatalError::JoinError)??;
+                        }
+                        warn!("No child tasks exited with errors. Propagating the pipe error instead.");
+                        return Err(IngestFatalError::SendFailed(pipe_err));
                     }
-                }));
-            pin_mut!(versions_stream);
-
-            while let Some(version_result) = versions_stream.next().await {
-                let version = version_result?;
-                let assigned_worker = partitioner.partition_for(&version.entity_id)?;
-                // This panics on OOB, which is correct
-                let (pipe, _) = &tasks[assigned_worker];
-
-                // If the send fails it's probably because a child errored. Propagate child
-                // errors first
-                if let Err(pipe_err) = pipe.send(version).await {
-                    warn!(
-                        "Got a pipe error, which probably means there's an error in a child task. Joining child tasks..."
-                    );
-                    for (pipe, task) in tasks.into_iter() {
-                        drop(pipe); // Signals child to exit
-                        task.await.map_err(IngestFatalError::JoinError)??;
-                    }
-                    warn!("No child tasks exited with errors. Propagating the pipe error instead.");
-                    return Err(IngestFatalError::SendFailed(pipe_err));
                 }
             }
 
@@ -436,15 +610,18 @@ impl<VersionIngest: IngestibleFromVersions + Send + Sync + 'static> Stage2Ingest
                         // We store the trimmed version for future comparisons
                         vacant.insert(trimmed_version);
                         // We need to return the untrimmed version, or else deserialize will fail
-                        FilteredIngestItem::DoIngest(version)
+                        Ok(FilteredIngestItem::DoIngest(version))
                     }
                     Entry::Occupied(mut occupied) => {
                         if occupied.get() == &trimmed_version {
                             // This is a duplicate -- no need to return it
-                            FilteredIngestItem::MarkAsSkipped(VersionIngest::ident_raw(&version), version.valid_from)
+                            Ok(FilteredIngestItem::MarkAsSkipped(
+                                VersionIngest::ident_raw(&version)?,
+                                version.valid_from,
+                            ))
                         } else {
                             occupied.insert(trimmed_version);
-                            FilteredIngestItem::DoIngest(version)
+                            Ok(FilteredIngestItem::DoIngest(version))
                         }
                     }
                 }
@@ -454,6 +631,9 @@ impl<VersionIngest: IngestibleFromVersions + Send + Sync + 'static> Stage2Ingest
 
         let mut wait_for_chunk_start = Utc::now();
         while let Some(raw_versions) = chunk_stream.next().await {
+            let raw_versions = raw_versions
+                .into_iter()
+                .collect::<Result<Vec<_>, IngestFatalError>>()?;
             let wait_for_chunk_duration = Utc::now() - wait_for_chunk_start;
             info!("{} ingest worker {} waited {:.2} seconds for a chunk of {} {}s", self.kind, worker_idx, wait_for_chunk_duration.as_seconds_f64(), raw_versions.len(), self.kind);
             self.ingest_page(
@@ -462,6 +642,9 @@ impl<VersionIngest: IngestibleFromVersions + Send + Sync + 'static> Stage2Ingest
                 &mut conn,
                 worker_idx,
                 args.debug_db_insert_delay,
+                args.insert_chunk_size,
+                &args.bulk_inserts,
+                &args.progress,
             )?;
             wait_for_chunk_start = Utc::now();
         }
@@ -481,6 +664,9 @@ impl<VersionIngest: IngestibleFromVersions + Send + Sync + 'static> Stage2Ingest
         conn: &mut PgConnection,
         worker_id: usize,
         debug_db_insert_delay: f64,
+        insert_chunk_size: NonZero<usize>,
+        bulk_inserts: &BulkInsertGate,
+        progress: &ProgressPublisher,
     ) -> Result<i32, IngestFatalError> {
         debug!(
             "Starting ingest of {} {}(s) on worker {worker_id}",
@@ -489,9 +675,13 @@ impl<VersionIngest: IngestibleFromVersions + Send + Sync + 'static> Stage2Ingest
         );
         let save_start = Utc::now();
 
-        let (deserialize_errors, items): (Vec<_>, Vec<_>) = raw_versions.into_par_iter()
+        let (deserialize_errors, items): (Vec<_>, Vec<_>) = raw_versions
+            .into_par_iter()
             .map(|item| match item {
-                FilteredIngestItem::MarkAsSkipped(entity_id, valid_from ) => (None, PreparedIngestItem::MarkAsSkipped(entity_id, valid_from)),
+                FilteredIngestItem::MarkAsSkipped(entity_id, valid_from) => Ok((
+                    None,
+                    PreparedIngestItem::MarkAsSkipped(entity_id, valid_from),
+                )),
                 FilteredIngestItem::DoIngest(entity) => {
                     if entity.kind != self.kind {
                         warn!("{} ingest task got a {} entity!", self.kind, entity.kind);
@@ -507,34 +697,67 @@ impl<VersionIngest: IngestibleFromVersions + Send + Sync + 'static> Stage2Ingest
                                 valid_to: entity.valid_to,
                                 data,
                             });
-                            (None, item)
-                        },
-                        Err(err) => (
+                            Ok((None, item))
+                        }
+                        Err(err) => Ok((
                             // Kinda inefficient to return the entity id and valid from twice, but it makes
                             // downstream code a little nicer
-                            Some((err, entity.entity_id.clone(), entity.valid_from)),
-                            PreparedIngestItem::MarkAsSkipped(VersionIngest::ident_raw(&entity), entity.valid_from),
-                        ),
+                            Some((
+                                err,
+                                entity.entity_id.clone(),
+                                entity.valid_from,
+                                VersionIngest::feed_event_index(&entity),
+                            )),
+                            PreparedIngestItem::MarkAsSkipped(
+                                VersionIngest::ident_raw(&entity)?,
+                                entity.valid_from,
+                            ),
+                        )),
                     }
                 }
             })
+            .collect::<Result<Vec<_>, IngestFatalError>>()?
+            .into_iter()
             .unzip();
 
         let new_ingest_logs = deserialize_errors
             .iter()
-            .filter_map(|e| e.as_ref().map(|(err, entity_id, valid_from)| NewVersionIngestLog {
-                kind: self.kind,
-                entity_id,
-                valid_from: valid_from.naive_utc(),
-                log_index: 0, // Deserialize error is always the 0th log item for that version
-                log_level: 0, // Critical error
-                log_text: format!("Error deserializing: {:?}", err), // Not sure whether this should be debug
-            }))
+            .filter_map(|e| {
+                e.as_ref()
+                    .map(|(err, entity_id, valid_from, feed_event_index)| NewVersionIngestLog {
+                        kind: self.kind,
+                        entity_id,
+                        valid_from: *valid_from,
+                        log_index: 0, // Deserialize error is always the 0th log item for that version
+                        log_level: 0, // Critical error
+                        log_text: format!("Error deserializing: {:?}", err), // Not sure whether this should be debug
+                        error_code: Some(
+                            taxa.ingest_error_code_id(TaxaIngestErrorCode::VersionDeserializeError),
+                        ),
+                        feed_event_index: *feed_event_index,
+                    })
+            })
             .collect();
 
         let inserted = db::insert_ingest_logs(conn, new_ingest_logs)?;
         debug!("Saved {inserted} deserialize errors");
 
+        let new_failures = deserialize_errors
+            .iter()
+            .flatten()
+            .filter_map(|(err, entity_id, valid_from, feed_event_index)| {
+                Some(NewFeedEventFailure {
+                    kind: self.kind,
+                    entity_id,
+                    feed_event_index: (*feed_event_index)?,
+                    valid_from: *valid_from,
+                    error_message: format!("Error deserializing: {:?}", err),
+                })
+            })
+            .collect_vec();
+        let inserted = db::insert_feed_event_failures(conn, new_failures.iter().collect())?;
+        debug!("Added {inserted} versions to the dead-letter queue");
+
         let earliest_time = items
             .first()
             .map(|version| version.valid_from())
@@ -592,9 +815,36 @@ impl<VersionIngest: IngestibleFromVersions + Send + Sync + 'static> Stage2Ingest
                 })
                 .collect_vec();
 
-            let (total, inserted) = VersionIngest::insert_batch(conn, taxa, &batch)?;
+            let latest_valid_from = batch.iter().map(|version| version.valid_from()).max();
+            let chunks = if VersionIngest::INSERTS_IN_CHUNKS {
+                vec![batch]
+            } else {
+                batch
+                    .into_iter()
+                    .chunks(insert_chunk_size.get())
+                    .into_iter()
+                    .map(Iterator::collect)
+                    .collect_vec()
+            };
+            let (mut total, mut inserted) = (0, 0);
+            for chunk in &chunks {
+                let _bulk_insert = bulk_inserts.begin();
+                let (chunk_total, chunk_inserted) =
+                    VersionIngest::insert_batch(conn, taxa, chunk, insert_chunk_size)?;
+                total += chunk_total;
+                inserted += chunk_inserted;
+            }
             total_inserted += inserted as i32;
 
+            progress.publish(IngestProgressEvent::BatchProcessed {
+                kind: self.kind.to_string(),
+                num_items: to_insert,
+                num_rows: total,
+                num_rows_inserted: inserted,
+                latest_valid_from,
+                at: Utc::now(),
+            });
+
             let human_time_ago = if latest_human_time_ago == earliest_human_time_ago {
                 format!("{}", latest_human_time_ago)
             } else {
@@ -620,14 +870,6 @@ impl<VersionIngest: IngestibleFromVersions + Send + Sync + 'static> Stage2Ingest
     }
 }
 
-impl<VersionIngest: IngestibleFromVersions + Send + Sync + 'static> IngestStage
-    for Stage2Ingest<VersionIngest>
-{
-    fn name(&self) -> &'static str {
-        "Stage 2"
-    }
-}
-
 pub fn batch_by_entity<KeyT: Clone + Eq + Hash, DataT>(
     versions: Vec<(KeyT, DataT)>,
 ) -> impl Iterator<Item = Vec<DataT>> {
@@ -672,6 +914,8 @@ pub fn batch_by_entity<KeyT: Clone + Eq + Hash, DataT>(
 pub enum VersionedIngestKind {
     Team,
     Player,
+    Stadium,
+    Time,
 }
 
 impl VersionedIngestKind {
@@ -679,13 +923,18 @@ impl VersionedIngestKind {
         match self {
             VersionedIngestKind::Team => "team",
             VersionedIngestKind::Player => "player",
+            VersionedIngestKind::Stadium => "stadium",
+            VersionedIngestKind::Time => "time",
         }
     }
 
-    fn as_feed_event_kind(self) -> &'static str {
+    fn as_feed_event_kind(self) -> Result<&'static str, IngestFatalError> {
         match self {
-            VersionedIngestKind::Team => "team_feed",
-            VersionedIngestKind::Player => "player_feed",
+            VersionedIngestKind::Team => Ok("team_feed"),
+            VersionedIngestKind::Player => Ok("player_feed"),
+            VersionedIngestKind::Stadium | VersionedIngestKind::Time => {
+                Err(IngestFatalError::NoFeed(self.as_kind()))
+            }
         }
     }
 }
@@ -714,7 +963,10 @@ impl Display for IngestKind {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             IngestKind::Versioned(k) => write!(f, "{}", k.as_kind()),
-            IngestKind::Feed(k) => write!(f, "{}", k.as_feed_event_kind()),
+            IngestKind::Feed(k) => match k.as_feed_event_kind() {
+                Ok(kind) => write!(f, "{kind}"),
+                Err(_) => write!(f, "{} feed", k.as_kind()),
+            },
             IngestKind::Entity(k) => write!(f, "{}", k.as_kind()),
         }
     }
@@ -724,19 +976,24 @@ impl Display for IngestKind {
 pub struct IngestForKind {
     kind: IngestKind,
     fetch_args: ChronFetchArgs,
-    processing_args: ProcessingArgs,
+    /// None when fetched data isn't going to Postgres, so there's nothing
+    /// to process
+    processing_args: Option<ProcessingArgs>,
+    retry_policy: TransientRetryPolicy,
 }
 
 impl IngestForKind {
     pub fn new(
         kind: IngestKind,
         fetch_args: ChronFetchArgs,
-        processing_args: ProcessingArgs,
+        processing_args: Option<ProcessingArgs>,
+        retry_policy: TransientRetryPolicy,
     ) -> Self {
         Self {
             kind,
             fetch_args,
             processing_args,
+            retry_policy,
         }
     }
 
@@ -749,7 +1006,7 @@ impl IngestForKind {
     }
 
     pub fn processing_is_enabled(&self) -> bool {
-        self.processing_args.enabled
+        self.processing_args.as_ref().is_some_and(|args| args.enabled)
     }
 
     /// The indefinite fetch task. Repeats until canceled.
@@ -770,12 +1027,72 @@ impl IngestForKind {
             }
 
             info!("Beginning next {} fetch", self.kind);
-            self.fetch_all_available().await?;
+            self.fetch_args.sink.publish_progress(&self.stage_started_event("fetch"));
+            let result = self
+                .retrying_transient_errors("fetch", &self.fetch_args.shutdown_requested, || {
+                    self.fetch_all_available()
+                })
+                .await;
+            self.fetch_args.sink.publish_progress(&self.stage_finished_event("fetch", &result));
+            result?;
         }
 
         Ok(())
     }
 
+    /// Runs `pass` until it succeeds, fails with an error that isn't
+    /// transient, or has failed with a transient error more than the retry
+    /// policy allows. Passes resume from whatever is already in the database,
+    /// so a retry picks up where the failed pass left off.
+    async fn retrying_transient_errors<Fut>(
+        &self,
+        what: &str,
+        shutdown_requested: &CancellationToken,
+        mut pass: impl FnMut() -> Fut,
+    ) -> Result<(), IngestFatalError>
+    where
+        Fut: Future<Output = Result<(), IngestFatalError>>,
+    {
+        let mut attempt = 0;
+        loop {
+            let err = match pass().await {
+                Ok(()) => return Ok(()),
+                Err(err) => err,
+            };
+
+            let class = err.class();
+            if !class.is_transient() {
+                error!("{} {what} failed with a {class:?} error: {err}", self.kind);
+                return Err(err);
+            }
+
+            attempt += 1;
+            if attempt > self.retry_policy.max_retries {
+                error!(
+                    "{} {what} failed with a {class:?} error after {} retries: {err}",
+                    self.kind, self.retry_policy.max_retries,
+                );
+                return Err(err);
+            }
+
+            let backoff = self.retry_policy.backoff(attempt);
+            warn!(
+                "{} {what} failed with a {class:?} error: {err}. Retrying in {}s (attempt {attempt} \
+                of {}).",
+                self.kind,
+                backoff.as_secs(),
+                self.retry_policy.max_retries,
+            );
+            tokio::select! {
+                biased;
+                _ = shutdown_requested.cancelled() => {
+                    return Ok(()); // Shutdown requested, no need to retry
+                }
+                _ = tokio::time::sleep(backoff) => {},
+            }
+        }
+    }
+
     /// One single instance of fetch. Exits once Chron says we're caught up,
     /// or when canceled.
     async fn fetch_all_available(&self) -> Result<(), IngestFatalError> {
@@ -786,12 +1103,10 @@ impl IngestForKind {
                     .await
             }
             IngestKind::Feed(kind) => {
-                fetch::fetch_feed_event_version_kind(
-                    kind.as_feed_event_kind(),
-                    self.fetch_args.clone(),
-                )
-                .instrument(info_span!("fetch_task", kind = kind.as_feed_event_kind()))
-                .await
+                let kind = kind.as_feed_event_kind()?;
+                fetch::fetch_feed_event_version_kind(kind, self.fetch_args.clone())
+                    .instrument(info_span!("fetch_task", kind = kind))
+                    .await
             }
             IngestKind::Entity(kind) => {
                 fetch::fetch_entity_kind(kind.as_kind(), self.fetch_args.clone())
@@ -803,53 +1118,112 @@ impl IngestForKind {
 
     /// The indefinite processing task. Repeats until canceled.
     pub async fn processing_task(&self) -> Result<(), IngestFatalError> {
+        let Some(processing_args) = &self.processing_args else {
+            // processing_is_enabled is false, so this is never launched
+            return Ok(());
+        };
         let mut interval = tokio::time::interval(Duration::from_secs(
-            self.processing_args.processing_interval_seconds,
+            processing_args.processing_interval_seconds,
         ));
         interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
 
-        while !self.processing_args.shutdown_requested.is_cancelled() {
+        while !processing_args.shutdown_requested.is_cancelled() {
             debug!(
                 "Sleeping until it's time for the next {:?} processing",
                 self.kind
             );
             tokio::select! {
                 biased;
-                _ = self.processing_args.shutdown_requested.cancelled() => {
+                _ = processing_args.shutdown_requested.cancelled() => {
                     break; // Shutdown requested, break and return immediately
                 }
                 _ = interval.tick() => {}, // Tick finishes, just proceed with the loop
             }
 
             info!("Beginning next {:?} processing", self.kind);
-            self.processing_all_available().await?;
+            processing_args
+                .progress
+                .publish(self.stage_started_event("processing"));
+            let result = self
+                .retrying_transient_errors(
+                    "processing",
+                    &processing_args.shutdown_requested,
+                    || self.processing_all_available(processing_args),
+                )
+                .await;
+            processing_args
+                .progress
+                .publish(self.stage_finished_event("processing", &result));
+            result?;
+            self.record_data_freshness(&processing_args.pool);
         }
 
         Ok(())
     }
 
+    fn stage_started_event(&self, stage: &str) -> IngestProgressEvent {
+        IngestProgressEvent::StageStarted {
+            kind: self.kind.to_string(),
+            stage: stage.to_string(),
+            at: Utc::now(),
+        }
+    }
+
+    fn stage_finished_event(
+        &self,
+        stage: &str,
+        result: &Result<(), IngestFatalError>,
+    ) -> IngestProgressEvent {
+        IngestProgressEvent::StageFinished {
+            kind: self.kind.to_string(),
+            stage: stage.to_string(),
+            at: Utc::now(),
+            error: result.as_ref().err().map(|err| err.to_string()),
+        }
+    }
+
+    /// Records how current this kind's data is. Failing to record it isn't
+    /// worth stopping the ingest over, so errors are only logged.
+    fn record_data_freshness(&self, pool: &ConnectionPool) {
+        let kind = self.kind.to_string();
+        match pool.get() {
+            Ok(mut conn) => {
+                if let Err(err) = db::record_data_freshness(&mut conn, &kind) {
+                    warn!("Error recording {kind} data freshness: {err}");
+                }
+            }
+            Err(err) => {
+                warn!("Couldn't get database connection to record {kind} data freshness: {err}");
+            }
+        }
+    }
+
     /// One single instance of processing. Exits once the db says we're caught up,
     /// or when canceled.
-    async fn processing_all_available(&self) -> Result<(), IngestFatalError> {
+    async fn processing_all_available(
+        &self,
+        processing_args: &ProcessingArgs,
+    ) -> Result<(), IngestFatalError> {
+        let lock_kind = match self.kind {
+            IngestKind::Versioned(kind) => kind.as_kind(),
+            IngestKind::Feed(kind) => kind.as_feed_event_kind()?,
+            IngestKind::Entity(kind) => kind.as_kind(),
+        };
+        let _processing_lock = processing_args.processing_locks.lock(lock_kind).await;
+
         match self.kind {
             IngestKind::Versioned(kind) => {
-                processing::process_version_kind(kind.as_kind(), self.processing_args.clone())
+                processing::process_version_kind(kind.as_kind(), processing_args.clone())
                     .instrument(info_span!("processing_task", kind = kind.as_kind()))
                     .await
             }
-            IngestKind::Feed(kind) => {
-                processing::process_feed_event_version_kind(
-                    kind.as_feed_event_kind(),
-                    self.processing_args.clone(),
-                )
-                .instrument(info_span!(
-                    "processing_task",
-                    kind = kind.as_feed_event_kind()
-                ))
-                .await
+            IngestKind::Feed(_) => {
+                processing::process_feed_event_version_kind(lock_kind, processing_args.clone())
+                    .instrument(info_span!("processing_task", kind = lock_kind))
+                    .await
             }
             IngestKind::Entity(kind) => {
-                processing::process_entity_kind(kind.as_kind(), self.processing_args.clone())
+                processing::process_entity_kind(kind.as_kind(), processing_args.clone())
                     .instrument(info_span!("processing_task", kind = kind.as_kind()))
                     .await
             }
@@ -857,9 +1231,16 @@ impl IngestForKind {
     }
 }
 
+/// Sets up fetching for every kind, writing to `sink`. Processing is only set
+/// up if there are `pools` to process from, and publishes its progress to the
+/// publisher that comes with them.
 pub fn ingest_kinds(
     shutdown_requested: &CancellationToken,
-    pool: &ConnectionPool,
+    sink: Arc<dyn Sink>,
+    pools: Option<(&ConnectionPool, &AsyncConnectionPool, &ProgressPublisher)>,
+    offload_store: Option<Arc<ObjectStore>>,
+    bulk_inserts: &BulkInsertGate,
+    processing_locks: &ProcessingLocks,
     config: &'static IngestConfig,
 ) -> Vec<Arc<IngestForKind>> {
     let kinds_configs = [
@@ -879,6 +1260,14 @@ pub fn ingest_kinds(
             IngestKind::Feed(VersionedIngestKind::Player),
             &config.player_feed_ingest,
         ),
+        (
+            IngestKind::Versioned(VersionedIngestKind::Stadium),
+            &config.stadium_ingest,
+        ),
+        (
+            IngestKind::Versioned(VersionedIngestKind::Time),
+            &config.time_ingest,
+        ),
         (
             IngestKind::Entity(EntityIngestKind::Game),
             &config.game_ingest,
@@ -890,12 +1279,18 @@ pub fn ingest_kinds(
         .map(|(kind, kind_config)| {
             let fetch_args = ChronFetchArgs {
                 shutdown_requested: shutdown_requested.clone(),
-                pool: pool.clone(),
+                sink: sink.clone(),
+                chron_base_url: config.source.as_ref().map(|s| s.chron_base_url.clone()),
                 use_local_cheap_cashews: config.use_local_cheap_cashews,
+                chron_fixture_dir: config.chron_fixture_dir.clone(),
+                record_chron_responses_dir: config.record_chron_responses_dir.clone(),
+                verify_chron_pages: config.verify_chron_pages,
+                shared_chron_mirror_ranges: config.shared_chron_mirror_ranges.clone(),
                 enabled: kind_config.enable_fetch,
                 chron_fetch_interval_seconds: kind_config.chron_fetch_interval_seconds,
                 chron_fetch_batch_size: kind_config.chron_fetch_batch_size,
                 insert_raw_entity_batch_size: kind_config.insert_raw_entity_batch_size,
+                bulk_inserts: bulk_inserts.clone(),
             };
 
             let parallelism = kind_config.ingest_parallelism.unwrap_or_else(|| {
@@ -907,16 +1302,166 @@ pub fn ingest_kinds(
                 // })
             });
 
-            let processing_args = ProcessingArgs {
+            let processing_args = pools.map(|(pool, async_pool, progress)| ProcessingArgs {
                 shutdown_requested: shutdown_requested.clone(),
                 pool: pool.clone(),
+                async_pool: async_pool.clone(),
+                progress: progress.clone(),
                 enabled: kind_config.enable_processing,
                 processing_interval_seconds: kind_config.processing_interval_seconds,
                 parallelism,
                 process_batch_size: kind_config.process_batch_size,
+                insert_chunk_size: kind_config.insert_chunk_size,
                 debug_db_insert_delay: kind_config.debug_db_insert_delay,
+                bulk_inserts: bulk_inserts.clone(),
+                prioritize_recent: kind_config.prioritize_recent,
+                recent_window: chrono::Duration::hours(
+                    kind_config.recent_window_hours.try_into().unwrap_or(i64::MAX),
+                ),
+                event_insert_mode: if config.strict_event_ordering {
+                    EventInsertMode::Returning
+                } else {
+                    EventInsertMode::Copy
+                },
+                forever_incomplete_after: config
+                    .forever_incomplete_after_hours
+                    .map(|hours| chrono::Duration::hours(hours.try_into().unwrap_or(i64::MAX))),
+                offload_store: offload_store.clone(),
+                processing_locks: processing_locks.clone(),
+            });
+            let retry_policy = TransientRetryPolicy {
+                max_retries: kind_config.max_transient_retries,
+                initial_backoff: Duration::from_secs(kind_config.transient_retry_backoff_seconds),
             };
-            Arc::new(IngestForKind::new(kind, fetch_args, processing_args))
+            Arc::new(IngestForKind::new(
+                kind,
+                fetch_args,
+                processing_args,
+                retry_policy,
+            ))
         })
         .collect()
 }
+
+/// Builds the registry of all ingest stages. To add a custom stage, register
+/// it here and (optionally) give it a position in `stage_order` in the config.
+pub fn stage_registry(
+    shutdown_requested: &CancellationToken,
+    pool: &ConnectionPool,
+    bulk_inserts: &BulkInsertGate,
+    processing_locks: &ProcessingLocks,
+    config: &'static IngestConfig,
+) -> Result<IngestStageRegistry, IngestFatalError> {
+    let progress = ProgressPublisher::start(pool.clone())?;
+    let sink = Arc::new(PostgresSink::new(pool.clone(), None).with_progress(progress.clone()));
+    let offload_store = config
+        .game_offload
+        .as_ref()
+        .map(|config| ObjectStore::new(&config.store))
+        .transpose()
+        .map_err(crate::offload::OffloadError::from)?
+        .map(Arc::new);
+    let async_pool = AsyncConnectionPool::new(
+        config.async_db_pool_size.get(),
+        config
+            .db_pool_health
+            .max_connection_lifetime_secs
+            .map(Duration::from_secs),
+    );
+    let mut registry = IngestStageRegistry::new();
+    registry.register_kinds(ingest_kinds(
+        shutdown_requested,
+        sink,
+        Some((pool, &async_pool, &progress)),
+        offload_store,
+        bulk_inserts,
+        processing_locks,
+        config,
+    ))?;
+    Ok(registry)
+}
+
+/// Builds the registry of fetch stages for an ingest that only fetches,
+/// because its sink isn't Postgres or its data isn't from main MMOLB.
+/// There's nothing to process, so every processing stage is disabled.
+pub fn fetch_only_stage_registry(
+    shutdown_requested: &CancellationToken,
+    sink: Arc<dyn Sink>,
+    config: &'static IngestConfig,
+) -> Result<IngestStageRegistry, IngestFatalError> {
+    let mut registry = IngestStageRegistry::new();
+    registry.register_kinds(ingest_kinds(
+        shutdown_requested,
+        sink,
+        None,
+        None,
+        // Nothing else in this process is competing for the sink's I/O
+        &BulkInsertGate::new(),
+        &ProcessingLocks::new(),
+        config,
+    ))?;
+    Ok(registry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::offload::OffloadError;
+    use mmoldb_db::ConnectionError;
+
+    fn db_error(kind: DatabaseErrorKind) -> QueryError {
+        QueryError::DatabaseError(kind, Box::new("test error".to_string()))
+    }
+
+    #[test]
+    fn chron_errors_are_only_parse_errors_when_a_response_didnt_parse() {
+        let bad_json = serde_json::from_str::<i32>("not json").unwrap_err();
+        let err = IngestFatalError::ChronStreamError(ChronStreamError::DeserializeError(bad_json));
+        assert_eq!(err.class(), IngestErrorClass::Parse);
+
+        let err = IngestFatalError::ChronStreamError(ChronStreamError::LocalReadError {
+            path: "fixtures/player.json".to_string(),
+            source: std::io::Error::from(std::io::ErrorKind::NotFound),
+        });
+        assert_eq!(err.class(), IngestErrorClass::Other);
+
+        let err = IngestFatalError::ChronStreamError(ChronStreamError::PageAnomaly(
+            "page went backwards".to_string(),
+        ));
+        assert_eq!(err.class(), IngestErrorClass::ChronNetwork);
+        assert!(err.class().is_transient());
+    }
+
+    #[test]
+    fn database_errors_are_classified_by_kind() {
+        let err = IngestFatalError::DbError(db_error(DatabaseErrorKind::UniqueViolation));
+        assert_eq!(err.class(), IngestErrorClass::DbConstraint);
+        assert!(!err.class().is_transient());
+
+        let err = IngestFatalError::DbError(db_error(DatabaseErrorKind::ClosedConnection));
+        assert_eq!(err.class(), IngestErrorClass::DbUnavailable);
+        assert!(err.class().is_transient());
+
+        let err = IngestFatalError::DbError(QueryError::NotFound);
+        assert_eq!(err.class(), IngestErrorClass::Other);
+
+        // Database errors are classified the same wherever they come from
+        let err = IngestFatalError::OffloadError(OffloadError::Db(db_error(
+            DatabaseErrorKind::SerializationFailure,
+        )));
+        assert_eq!(err.class(), IngestErrorClass::DbUnavailable);
+    }
+
+    #[test]
+    fn only_lost_async_connections_are_transient() {
+        let err = IngestFatalError::AsyncDbPoolError(ConnectionError::BadConnection(
+            "connection refused".to_string(),
+        ));
+        assert!(err.class().is_transient());
+
+        let err = IngestFatalError::AsyncDbPoolError(ConnectionError::InvalidConnectionUrl(
+            "not a url".to_string(),
+        ));
+        assert_eq!(err.class(), IngestErrorClass::Other);
+    }
+}
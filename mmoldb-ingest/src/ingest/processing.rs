@@ -1,24 +1,94 @@
-use crate::ingest_player_feed::PlayerFeedIngestFromVersions;
+use crate::async_pool::AsyncConnectionPool;
+use crate::bulk_insert_gate::BulkInsertGate;
+use crate::ingest_feed_shared::redrive_feed_event_failures;
+use crate::ingest_player_feed::{PlayerFeedIngestFromVersions, reparse_player_feed_event};
 use crate::ingest_players::PlayerIngestFromVersions;
-use crate::ingest_team_feed::TeamFeedIngestFromVersions;
+use crate::ingest_stadiums::StadiumIngestFromVersions;
+use crate::ingest_team_feed::{TeamFeedIngestFromVersions, reparse_team_feed_event};
 use crate::ingest_teams::TeamIngestFromVersions;
-use crate::{IngestFatalError, Stage2Ingest};
-use mmoldb_db::ConnectionPool;
-use mmoldb_db::db::{refresh_game_matviews, refresh_player_matviews};
+use crate::ingest_time::TimeIngestFromVersions;
+use crate::offload::ObjectStore;
+use crate::processing_locks::ProcessingLocks;
+use crate::{IngestFatalError, ProgressPublisher, Stage2Ingest};
+use chrono::{DateTime, Utc};
+use mmoldb_db::async_db::{self, ProcessingPass};
+use mmoldb_db::db::{EventInsertMode, refresh_game_matviews, refresh_player_matviews};
+use mmoldb_db::taxa::Taxa;
+use mmoldb_db::{AsyncPgConnection, ConnectionPool, db};
 use std::num::NonZero;
 use std::sync::Arc;
 use tokio_util::sync::CancellationToken;
 use tracing::{info, warn};
 
+/// How many games are linked to their stadium per statement
+const LINK_STADIUMS_BATCH_SIZE: i64 = 1000;
+
 #[derive(Debug, Clone)]
 pub struct ProcessingArgs {
     pub shutdown_requested: CancellationToken,
     pub pool: ConnectionPool,
+    /// For the version streams and the game round-trip check
+    pub async_pool: AsyncConnectionPool,
+    /// Where processing progress is published for mmoldb-app
+    pub progress: ProgressPublisher,
     pub enabled: bool,
     pub processing_interval_seconds: u64,
     pub parallelism: NonZero<usize>,
     pub process_batch_size: NonZero<usize>,
+    pub insert_chunk_size: NonZero<usize>,
     pub debug_db_insert_delay: f64,
+    pub bulk_inserts: BulkInsertGate,
+    pub prioritize_recent: bool,
+    pub recent_window: chrono::Duration,
+    pub event_insert_mode: EventInsertMode,
+    /// See `IngestConfig::forever_incomplete_after_hours`
+    pub forever_incomplete_after: Option<chrono::Duration>,
+    /// Where offloaded games are fetched back from when they're reprocessed
+    pub offload_store: Option<Arc<ObjectStore>>,
+    /// Held for each whole processing pass
+    pub processing_locks: ProcessingLocks,
+}
+
+impl ProcessingArgs {
+    /// The passes one processing iteration of `kind` runs, in order. With
+    /// prioritization on, entities that changed since the last processing
+    /// pass (or within `recent_window`, whichever reaches back further) are
+    /// processed before everything else.
+    pub async fn processing_passes(
+        &self,
+        conn: &mut AsyncPgConnection,
+        kind: &str,
+    ) -> Vec<ProcessingPass> {
+        if !self.prioritize_recent {
+            return vec![ProcessingPass::All];
+        }
+
+        let last_ingest_at = match async_db::last_ingest_at(conn, kind).await {
+            Ok(last_ingest_at) => last_ingest_at,
+            Err(err) => {
+                warn!("Error getting last {kind} ingest time: {err}");
+                None
+            }
+        };
+
+        prioritized_passes(self.recent_window, last_ingest_at, Utc::now()).to_vec()
+    }
+}
+
+fn prioritized_passes(
+    recent_window: chrono::Duration,
+    last_ingest_at: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> [ProcessingPass; 2] {
+    let window_start = now
+        .checked_sub_signed(recent_window)
+        .unwrap_or(DateTime::<Utc>::MIN_UTC);
+    let since = last_ingest_at.map_or(window_start, |last| last.min(window_start));
+
+    [
+        ProcessingPass::RecentlyActive(since),
+        ProcessingPass::Inactive(since),
+    ]
 }
 
 // It may be possible to remove 'static
@@ -28,8 +98,38 @@ pub async fn process_entity_kind(
 ) -> Result<(), IngestFatalError> {
     assert_eq!(kind, "game", "`game` is the only supported entity kind");
 
+    let pool = args.pool.clone();
+    if let Err(err) = tokio::task::spawn_blocking(move || unflag_stalled_games(&pool)).await {
+        warn!("Unflagging stalled games panicked: {err}");
+    }
+
     // TODO Refactor this code to get rid of remnants of the old staged system
-    crate::ingest_games::ingest_stage_2(args.pool.clone(), args.shutdown_requested).await?;
+    let passes = {
+        let mut async_conn = args.async_pool.get().await?;
+        args.processing_passes(&mut async_conn, kind).await
+    };
+    crate::ingest_games::ingest_stage_2(
+        args.pool.clone(),
+        args.async_pool.clone(),
+        args.offload_store.clone(),
+        args.progress.clone(),
+        passes,
+        args.shutdown_requested,
+        args.parallelism,
+        args.process_batch_size,
+        args.bulk_inserts,
+        args.event_insert_mode,
+    )
+    .await?;
+    if let Some(forever_incomplete_after) = args.forever_incomplete_after {
+        let pool = args.pool.clone();
+        let flag = move || flag_stalled_games(&pool, forever_incomplete_after);
+        if let Err(err) = tokio::task::spawn_blocking(flag).await {
+            warn!("Flagging stalled games panicked: {err}");
+        }
+    }
+    // New games may have been played in stadiums that were already ingested
+    link_games_to_stadiums(&args.pool);
     info!("game process iteration finished. Refreshing game matviews.");
     // TODO Don't hard-code this
     match args.pool.get() {
@@ -48,6 +148,72 @@ pub async fn process_entity_kind(
     Ok(())
 }
 
+/// Puts games that were flagged as stalled back in progress if a new version
+/// of them has been fetched, so they don't look bugged while they wait to be
+/// processed. Errors are logged and otherwise ignored, since the next
+/// iteration will try again.
+fn unflag_stalled_games(pool: &ConnectionPool) {
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(err) => {
+            warn!("Couldn't get database connection to unflag stalled games: {err}");
+            return;
+        }
+    };
+
+    let result = Taxa::new(&mut conn)
+        .and_then(|taxa| db::unflag_stalled_games_with_new_versions(&mut conn, &taxa));
+    match result {
+        Ok(0) => {}
+        Ok(num_unflagged) => {
+            info!("{num_unflagged} stalled games have new versions and are back in progress")
+        }
+        Err(err) => warn!("Error unflagging stalled games: {err}"),
+    }
+}
+
+/// Links games to the stadiums they were played in, a batch at a time.
+/// Errors are logged and otherwise ignored, since the next iteration will
+/// try again.
+fn link_games_to_stadiums(pool: &ConnectionPool) {
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(err) => {
+            warn!("Couldn't get database connection to link games to stadiums: {err}");
+            return;
+        }
+    };
+
+    match db::link_games_to_stadiums(&mut conn, LINK_STADIUMS_BATCH_SIZE) {
+        Ok(0) => {}
+        Ok(num_linked) => info!("Linked {num_linked} games to their stadiums"),
+        Err(err) => warn!("Error linking games to stadiums: {err}"),
+    }
+}
+
+/// Flags games that haven't had a new version in `forever_incomplete_after`
+/// as forever incomplete. See [`db::flag_stalled_games`].
+fn flag_stalled_games(pool: &ConnectionPool, forever_incomplete_after: chrono::Duration) {
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(err) => {
+            warn!("Couldn't get database connection to flag stalled games: {err}");
+            return;
+        }
+    };
+
+    let no_versions_since = Utc::now()
+        .checked_sub_signed(forever_incomplete_after)
+        .unwrap_or(DateTime::<Utc>::MIN_UTC);
+    let result = Taxa::new(&mut conn)
+        .and_then(|taxa| db::flag_stalled_games(&mut conn, &taxa, no_versions_since));
+    match result {
+        Ok(0) => {}
+        Ok(num_flagged) => info!("Flagged {num_flagged} stalled games as forever incomplete"),
+        Err(err) => warn!("Error flagging stalled games: {err}"),
+    }
+}
+
 // It may be possible to remove 'static
 pub async fn process_version_kind(
     kind: &'static str,
@@ -82,8 +248,22 @@ pub async fn process_version_kind(
             let stage = Arc::new(Stage2Ingest::new(kind, TeamIngestFromVersions));
             stage.run(args).await
         }
+        "stadium" => {
+            let pool_for_linking = args.pool.clone();
+            // TODO Refactor this code to get rid of remnants of the old staged system
+            let stage = Arc::new(Stage2Ingest::new(kind, StadiumIngestFromVersions));
+            stage.run(args).await?;
+            // New stadium versions may match games that were ingested earlier
+            link_games_to_stadiums(&pool_for_linking);
+            Ok(())
+        }
+        "time" => {
+            // TODO Refactor this code to get rid of remnants of the old staged system
+            let stage = Arc::new(Stage2Ingest::new(kind, TimeIngestFromVersions));
+            stage.run(args).await
+        }
         _ => {
-            panic!("`player` and `team` are the only supported version kinds")
+            panic!("`player`, `team`, `stadium`, and `time` are the only supported version kinds")
         }
     }
 }
@@ -96,11 +276,13 @@ pub async fn process_feed_event_version_kind(
     // TODO Refactor this to not match on kind
     match kind {
         "player_feed" => {
+            redrive_failures(&args.pool, kind, reparse_player_feed_event);
             // TODO Refactor this code to get rid of remnants of the old staged system
             let stage = Arc::new(Stage2Ingest::new(kind, PlayerFeedIngestFromVersions));
             stage.run(args).await
         }
         "team_feed" => {
+            redrive_failures(&args.pool, kind, reparse_team_feed_event);
             // TODO Refactor this code to get rid of remnants of the old staged system
             let stage = Arc::new(Stage2Ingest::new(kind, TeamFeedIngestFromVersions));
             stage.run(args).await
@@ -110,3 +292,70 @@ pub async fn process_feed_event_version_kind(
         }
     }
 }
+
+fn redrive_failures(
+    pool: &ConnectionPool,
+    kind: &str,
+    reparse: impl Fn(&serde_json::Value) -> Result<(), String>,
+) {
+    // A failure here shouldn't stop new versions from being processed
+    match redrive_feed_event_failures(pool, kind, reparse) {
+        Ok((requeued, still_failing)) => {
+            info!(
+                "Re-drove {kind} dead-letter queue: {requeued} events requeued, \
+                {still_failing} still failing",
+            );
+        }
+        Err(err) => {
+            warn!("Error re-driving {kind} dead-letter queue: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn since(passes: [ProcessingPass; 2]) -> DateTime<Utc> {
+        let [
+            ProcessingPass::RecentlyActive(since),
+            ProcessingPass::Inactive(inactive_since),
+        ] = passes
+        else {
+            panic!("recently active entities should be processed first, then the rest");
+        };
+        // The passes must split at the same time so they cover everything
+        // without overlapping
+        assert_eq!(since, inactive_since);
+        since
+    }
+
+    #[test]
+    fn recent_entities_go_back_to_the_last_ingest_or_the_window_whichever_is_earlier() {
+        let now = Utc.with_ymd_and_hms(2025, 7, 2, 12, 0, 0).unwrap();
+        let window = chrono::Duration::hours(24);
+        let window_start = Utc.with_ymd_and_hms(2025, 7, 1, 12, 0, 0).unwrap();
+
+        let recent_ingest = now - chrono::Duration::hours(1);
+        assert_eq!(
+            since(prioritized_passes(window, Some(recent_ingest), now)),
+            window_start
+        );
+
+        let old_ingest = Utc.with_ymd_and_hms(2025, 6, 1, 0, 0, 0).unwrap();
+        assert_eq!(
+            since(prioritized_passes(window, Some(old_ingest), now)),
+            old_ingest
+        );
+
+        assert_eq!(since(prioritized_passes(window, None, now)), window_start);
+    }
+
+    #[test]
+    fn a_window_reaching_past_the_earliest_time_covers_everything() {
+        let now = Utc.with_ymd_and_hms(2025, 7, 2, 12, 0, 0).unwrap();
+        let passes = prioritized_passes(chrono::Duration::MAX, None, now);
+        assert_eq!(since(passes), DateTime::<Utc>::MIN_UTC);
+    }
+}
@@ -0,0 +1,61 @@
+use crate::IngestFatalError;
+use mmoldb_db::db::IngestProgressEvent;
+use mmoldb_db::{ConnectionPool, db};
+use std::sync::mpsc;
+use tracing::{debug, warn};
+
+/// How many events can wait to be published before new ones are dropped.
+/// Events only pile up if the database is unreachable, and a live feed has
+/// no use for a backlog of stale ones.
+const MAX_QUEUED_EVENTS: usize = 1000;
+
+/// Publishes ingest progress for mmoldb-app's live progress feed. Events are
+/// queued for a background thread that publishes them on a connection of
+/// its own, so publishing never blocks the caller and a notification is
+/// never held back (or discarded) by a transaction the caller is in. Nobody
+/// may be listening, so events that can't be published are only logged.
+#[derive(Debug, Clone)]
+pub struct ProgressPublisher {
+    events: mpsc::SyncSender<IngestProgressEvent>,
+}
+
+impl ProgressPublisher {
+    /// Starts the publishing thread, which runs until every clone of the
+    /// returned publisher has been dropped
+    pub fn start(pool: ConnectionPool) -> Result<Self, IngestFatalError> {
+        let (events, events_recv) = mpsc::sync_channel(MAX_QUEUED_EVENTS);
+        std::thread::Builder::new()
+            .name("ingest-progress-publisher".to_string())
+            .spawn(move || run_publisher(pool, events_recv))
+            .map_err(IngestFatalError::TaskSpawnError)?;
+
+        Ok(Self { events })
+    }
+
+    pub fn publish(&self, event: IngestProgressEvent) {
+        match self.events.try_send(event) {
+            Ok(()) => {}
+            Err(mpsc::TrySendError::Full(event)) => {
+                debug!("Dropped ingest progress event {event:?} because the queue is full");
+            }
+            Err(mpsc::TrySendError::Disconnected(_)) => {
+                warn!("Couldn't publish ingest progress because the publishing thread exited");
+            }
+        }
+    }
+}
+
+fn run_publisher(pool: ConnectionPool, events: mpsc::Receiver<IngestProgressEvent>) {
+    for event in events {
+        match pool.get() {
+            Ok(mut conn) => {
+                if let Err(err) = db::publish_ingest_progress(&mut conn, &event) {
+                    warn!("Error publishing ingest progress: {err}");
+                }
+            }
+            Err(err) => {
+                warn!("Couldn't get database connection to publish ingest progress: {err}");
+            }
+        }
+    }
+}
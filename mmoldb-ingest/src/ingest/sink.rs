@@ -0,0 +1,585 @@
+use crate::IngestFatalError;
+use crate::ingest::ProgressPublisher;
+use chron::{ChronAnomaly, ChronEntity};
+use chrono::{DateTime, Utc};
+use hashbrown::HashMap;
+use itertools::Itertools;
+use mmoldb_db::db::IngestProgressEvent;
+use mmoldb_db::models::NewChronAnomaly;
+use mmoldb_db::{ConnectionPool, db};
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use std::fs::{File, OpenOptions};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::{error, warn};
+
+/// One raw feed event, as it's pulled out of a version of its entity's feed
+pub type FeedEventVersion = (String, i32, DateTime<Utc>, serde_json::Value);
+
+/// Where fetched data from Chron ends up. Each fetch asks its sink where to
+/// resume from, so a sink must be able to report the latest thing it has
+/// written for each kind.
+pub trait Sink: Debug + Send + Sync {
+    /// `valid_from` of the latest entity of this kind, if there are any
+    fn latest_entity_valid_from(
+        &self,
+        kind: &str,
+    ) -> Result<Option<DateTime<Utc>>, IngestFatalError>;
+
+    /// `valid_from` and entity id of the latest version of this kind, if
+    /// there are any
+    fn latest_version_cursor(
+        &self,
+        kind: &str,
+    ) -> Result<Option<(DateTime<Utc>, String)>, IngestFatalError>;
+
+    /// `valid_from` and entity id of the latest feed event version of this
+    /// kind, if there are any
+    fn latest_feed_event_version_cursor(
+        &self,
+        kind: &str,
+    ) -> Result<Option<(DateTime<Utc>, String)>, IngestFatalError>;
+
+    /// Returns how many entities were written
+    fn write_entities(
+        &self,
+        kind: &str,
+        entities: Vec<ChronEntity<serde_json::Value>>,
+    ) -> Result<usize, IngestFatalError>;
+
+    /// Returns how many versions were written
+    fn write_versions(
+        &self,
+        kind: &str,
+        versions: &[ChronEntity<serde_json::Value>],
+    ) -> Result<usize, IngestFatalError>;
+
+    /// Returns how many feed event versions were written
+    fn write_feed_event_versions(
+        &self,
+        kind: &str,
+        versions: &[FeedEventVersion],
+    ) -> Result<usize, IngestFatalError>;
+
+    /// Called with every anomaly Chron's page verification finds. Failing to
+    /// record one isn't worth failing the fetch over, so this can't fail.
+    fn record_chron_anomaly(&self, anomaly: &ChronAnomaly) {
+        warn!("Chron anomaly: {anomaly:?}");
+    }
+
+    /// Called when a fetch starts or finishes. Nobody may be listening, so
+    /// this can't fail.
+    fn publish_progress(&self, _event: &IngestProgressEvent) {}
+}
+
+/// Writes to the `data` schema in Postgres, for the processing stages to
+/// pick up. This is the default.
+#[derive(Debug, Clone)]
+pub struct PostgresSink {
+    pool: ConnectionPool,
+    /// Which Chron-compatible source the data comes from, or None for main
+    /// MMOLB. Every row is tagged with it, and only main MMOLB's rows are
+    /// processed.
+    source: Option<String>,
+    progress: Option<ProgressPublisher>,
+}
+
+impl PostgresSink {
+    pub fn new(pool: ConnectionPool, source: Option<String>) -> Self {
+        Self {
+            pool,
+            source,
+            progress: None,
+        }
+    }
+
+    /// Publishes fetch progress to `progress`. Only main MMOLB's progress is
+    /// published, since that's what the status page shows.
+    pub fn with_progress(self, progress: ProgressPublisher) -> Self {
+        Self {
+            progress: Some(progress),
+            ..self
+        }
+    }
+}
+
+impl Sink for PostgresSink {
+    fn latest_entity_valid_from(
+        &self,
+        kind: &str,
+    ) -> Result<Option<DateTime<Utc>>, IngestFatalError> {
+        let mut conn = self.pool.get()?;
+        Ok(db::get_latest_entity_valid_from(
+            &mut conn,
+            kind,
+            self.source.as_deref(),
+        )?)
+    }
+
+    fn latest_version_cursor(
+        &self,
+        kind: &str,
+    ) -> Result<Option<(DateTime<Utc>, String)>, IngestFatalError> {
+        let mut conn = self.pool.get()?;
+        Ok(db::get_latest_raw_version_cursor(
+            &mut conn,
+            kind,
+            self.source.as_deref(),
+        )?)
+    }
+
+    fn latest_feed_event_version_cursor(
+        &self,
+        kind: &str,
+    ) -> Result<Option<(DateTime<Utc>, String)>, IngestFatalError> {
+        let mut conn = self.pool.get()?;
+        Ok(
+            db::get_latest_raw_feed_event_version_cursor(&mut conn, kind, self.source.as_deref())?
+                .map(|(dt, id, _)| (dt, id)),
+        )
+    }
+
+    fn write_entities(
+        &self,
+        _kind: &str,
+        entities: Vec<ChronEntity<serde_json::Value>>,
+    ) -> Result<usize, IngestFatalError> {
+        let mut conn = self.pool.get()?;
+        Ok(db::insert_entities(
+            &mut conn,
+            entities,
+            self.source.as_deref(),
+        )?)
+    }
+
+    fn write_versions(
+        &self,
+        _kind: &str,
+        versions: &[ChronEntity<serde_json::Value>],
+    ) -> Result<usize, IngestFatalError> {
+        let mut conn = self.pool.get()?;
+        match db::insert_versions_one_error(&mut conn, versions, self.source.as_deref()) {
+            Ok(inserted) => Ok(inserted),
+            Err((entity, err)) => {
+                error!("Error in stage 1 ingest write: {err} while inserting: {entity:#?}");
+                Err(err.into())
+            }
+        }
+    }
+
+    fn write_feed_event_versions(
+        &self,
+        kind: &str,
+        versions: &[FeedEventVersion],
+    ) -> Result<usize, IngestFatalError> {
+        let mut conn = self.pool.get()?;
+        match db::insert_feed_event_versions(&mut conn, kind, versions, self.source.as_deref()) {
+            Ok(inserted) => Ok(inserted),
+            Err(err) => {
+                error!("Error in stage 1 ingest write: {err}");
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Records the anomaly in `info.chron_anomalies`
+    fn record_chron_anomaly(&self, anomaly: &ChronAnomaly) {
+        let new_anomaly = NewChronAnomaly {
+            kind: &anomaly.kind,
+            url: &anomaly.url,
+            page: anomaly.page.as_deref(),
+            description: &anomaly.description,
+            resolved_by_refetch: anomaly.resolved_by_refetch,
+        };
+        let result = self
+            .pool
+            .get()
+            .map_err(|err| err.to_string())
+            .and_then(|mut conn| {
+                db::insert_chron_anomaly(&mut conn, &new_anomaly).map_err(|err| err.to_string())
+            });
+        if let Err(err) = result {
+            warn!("Couldn't record Chron anomaly {anomaly:?}: {err}");
+        }
+    }
+
+    fn publish_progress(&self, event: &IngestProgressEvent) {
+        if let (Some(progress), None) = (&self.progress, &self.source) {
+            progress.publish(event.clone());
+        }
+    }
+}
+
+/// One line of a feed event version file written by [`NdjsonSink`]
+#[derive(Debug, Serialize)]
+struct NdjsonFeedEventVersion<'a> {
+    kind: &'a str,
+    entity_id: &'a str,
+    feed_event_index: i32,
+    valid_from: DateTime<Utc>,
+    data: &'a serde_json::Value,
+}
+
+/// The fields of any line written by [`NdjsonSink`] that resuming needs.
+/// Only feed event version lines have `feed_event_index`.
+#[derive(Deserialize)]
+struct NdjsonLine {
+    entity_id: String,
+    valid_from: DateTime<Utc>,
+    feed_event_index: Option<i32>,
+    data: serde_json::Value,
+}
+
+/// What [`NdjsonSink`] knows about one kind's file
+#[derive(Debug, Default)]
+struct NdjsonFile {
+    /// The latest `(valid_from, entity_id)` in the file
+    cursor: Option<(DateTime<Utc>, String)>,
+    /// A hash of the latest data written for each `(entity_id,
+    /// feed_event_index)`, so feed events that didn't change in a new
+    /// version of their feed aren't written again. Empty for kinds that
+    /// aren't feeds.
+    feed_events: HashMap<(String, i32), u64>,
+}
+
+/// Appends everything to newline-delimited JSON files in a directory, one
+/// per kind (e.g. `player.ndjson`, `player_feed.ndjson`, `game.ndjson`).
+/// Entities and versions are written exactly as Chron returns them. Feed
+/// event versions are written with their `entity_id`, `feed_event_index`
+/// and `valid_from`, and only when the event is new or has changed since
+/// it was last written.
+///
+/// Unlike Postgres, an entity that's fetched again (e.g. a game that was
+/// in progress last time) is appended again rather than replaced, so
+/// readers should keep the last line for each `entity_id` (or each
+/// `entity_id` and `feed_event_index`, for feeds). Nothing stops two
+/// ingests from writing to the same directory at once, so don't do that.
+///
+/// Every method does blocking file I/O, so call them from the blocking
+/// pool.
+#[derive(Debug)]
+pub struct NdjsonSink {
+    dir: PathBuf,
+    /// Each kind's file is read on first use, then kept up to date as it's
+    /// written. Each kind has its own lock, so writing one kind's file never
+    /// waits for another's.
+    files: Mutex<HashMap<String, Arc<Mutex<Option<NdjsonFile>>>>>,
+}
+
+impl NdjsonSink {
+    /// Creates `dir` if it doesn't exist. Files that are already in it are
+    /// appended to.
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self, IngestFatalError> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            files: Mutex::new(HashMap::new()),
+        })
+    }
+
+    fn path(&self, kind: &str) -> PathBuf {
+        self.dir.join(format!("{kind}.ndjson"))
+    }
+
+    /// Calls `f` with what's known about `kind`'s file, reading the file
+    /// first if this is the first time `kind` has been used. `kind`'s lock
+    /// is held until `f` returns.
+    fn with_file<T>(
+        &self,
+        kind: &str,
+        f: impl FnOnce(&mut NdjsonFile) -> Result<T, IngestFatalError>,
+    ) -> Result<T, IngestFatalError> {
+        // Only held long enough to find this kind's lock
+        let file_lock = self
+            .files
+            .lock()
+            .unwrap()
+            .entry(kind.to_string())
+            .or_default()
+            .clone();
+
+        let mut file = file_lock.lock().unwrap();
+        match &mut *file {
+            Some(file) => f(file),
+            unread => f(unread.insert(read_file(&self.path(kind))?)),
+        }
+    }
+
+    fn cursor(&self, kind: &str) -> Result<Option<(DateTime<Utc>, String)>, IngestFatalError> {
+        self.with_file(kind, |file| Ok(file.cursor.clone()))
+    }
+
+    /// Appends one line per item and moves the cursor forward. Returns how
+    /// many lines were written.
+    fn append<T: Serialize>(
+        &self,
+        kind: &str,
+        file: &mut NdjsonFile,
+        items: impl IntoIterator<Item = (DateTime<Utc>, String, T)>,
+    ) -> Result<usize, IngestFatalError> {
+        let out = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path(kind))?;
+        let mut writer = BufWriter::new(out);
+
+        let mut latest = None;
+        let mut written = 0;
+        for (valid_from, entity_id, item) in items {
+            serde_json::to_writer(&mut writer, &item)?;
+            writer.write_all(b"\n")?;
+            written += 1;
+            latest = max_cursor(latest, Some((valid_from, entity_id)));
+        }
+        writer.flush()?;
+
+        file.cursor = max_cursor(file.cursor.take(), latest);
+        Ok(written)
+    }
+}
+
+fn max_cursor(
+    a: Option<(DateTime<Utc>, String)>,
+    b: Option<(DateTime<Utc>, String)>,
+) -> Option<(DateTime<Utc>, String)> {
+    // None sorts before Some
+    std::cmp::max(a, b)
+}
+
+/// Feed events are compared by hash so the whole event doesn't have to be
+/// kept in memory
+fn feed_event_hash(data: &serde_json::Value) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    data.to_string().hash(&mut hasher);
+    hasher.finish()
+}
+
+/// The latest `(valid_from, entity_id)` in an NDJSON file and the latest
+/// version of every feed event in it. Empty if the file doesn't exist.
+fn read_file(path: &Path) -> Result<NdjsonFile, IngestFatalError> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(NdjsonFile::default());
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut result = NdjsonFile::default();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let NdjsonLine {
+            entity_id,
+            valid_from,
+            feed_event_index,
+            data,
+        } = serde_json::from_str(&line)?;
+        if let Some(index) = feed_event_index {
+            result
+                .feed_events
+                .insert((entity_id.clone(), index), feed_event_hash(&data));
+        }
+        result.cursor = max_cursor(result.cursor, Some((valid_from, entity_id)));
+    }
+    Ok(result)
+}
+
+impl Sink for NdjsonSink {
+    fn latest_entity_valid_from(
+        &self,
+        kind: &str,
+    ) -> Result<Option<DateTime<Utc>>, IngestFatalError> {
+        Ok(self.cursor(kind)?.map(|(valid_from, _)| valid_from))
+    }
+
+    fn latest_version_cursor(
+        &self,
+        kind: &str,
+    ) -> Result<Option<(DateTime<Utc>, String)>, IngestFatalError> {
+        self.cursor(kind)
+    }
+
+    fn latest_feed_event_version_cursor(
+        &self,
+        kind: &str,
+    ) -> Result<Option<(DateTime<Utc>, String)>, IngestFatalError> {
+        self.cursor(kind)
+    }
+
+    fn write_entities(
+        &self,
+        kind: &str,
+        entities: Vec<ChronEntity<serde_json::Value>>,
+    ) -> Result<usize, IngestFatalError> {
+        self.write_versions(kind, &entities)
+    }
+
+    fn write_versions(
+        &self,
+        kind: &str,
+        versions: &[ChronEntity<serde_json::Value>],
+    ) -> Result<usize, IngestFatalError> {
+        self.with_file(kind, |file| {
+            self.append(
+                kind,
+                file,
+                versions
+                    .iter()
+                    .map(|version| (version.valid_from, version.entity_id.clone(), version)),
+            )
+        })
+    }
+
+    fn write_feed_event_versions(
+        &self,
+        kind: &str,
+        versions: &[FeedEventVersion],
+    ) -> Result<usize, IngestFatalError> {
+        self.with_file(kind, |file| {
+            // Each version of a feed has every event that's still in it, so
+            // most of them haven't changed since they were last written
+            let changed = versions
+                .iter()
+                .map(|version| (feed_event_hash(&version.3), version))
+                .filter(|(hash, (entity_id, index, _, _))| {
+                    file.feed_events.get(&(entity_id.clone(), *index)) != Some(hash)
+                })
+                .collect_vec();
+
+            let written = self.append(
+                kind,
+                file,
+                changed
+                    .iter()
+                    .map(|(_, (entity_id, index, valid_from, data))| {
+                        (
+                            *valid_from,
+                            entity_id.clone(),
+                            NdjsonFeedEventVersion {
+                                kind,
+                                entity_id,
+                                feed_event_index: *index,
+                                valid_from: *valid_from,
+                                data,
+                            },
+                        )
+                    }),
+            )?;
+
+            // Only once they're safely written
+            for (hash, (entity_id, index, _, _)) in changed {
+                file.feed_events.insert((entity_id.clone(), *index), hash);
+            }
+            Ok(written)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    /// Deleted when dropped, even if the test panics
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir()
+                .join(format!("mmoldb-ndjson-sink-{name}-{}", std::process::id()));
+            // Left over from a previous run that was killed
+            let _ = std::fs::remove_dir_all(&dir);
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn valid_from(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 7, 1, hour, 0, 0).unwrap()
+    }
+
+    fn num_lines(dir: &TempDir, kind: &str) -> usize {
+        std::fs::read_to_string(dir.0.join(format!("{kind}.ndjson")))
+            .unwrap()
+            .lines()
+            .count()
+    }
+
+    #[test]
+    fn ndjson_sink_resumes_from_the_latest_line() {
+        let dir = TempDir::new("resume");
+        let version = |entity_id: &str, hour| ChronEntity {
+            kind: "player".to_string(),
+            entity_id: entity_id.to_string(),
+            valid_from: valid_from(hour),
+            valid_to: None,
+            data: serde_json::json!({ "FirstName": "Test" }),
+        };
+
+        let sink = NdjsonSink::new(&dir.0).unwrap();
+        assert_eq!(sink.latest_version_cursor("player").unwrap(), None);
+        sink.write_versions("player", &[version("b", 2), version("a", 1)])
+            .unwrap();
+
+        let expected = Some((valid_from(2), "b".to_string()));
+        assert_eq!(sink.latest_version_cursor("player").unwrap(), expected);
+        // A new sink has to read the cursor back from the file
+        let reopened = NdjsonSink::new(&dir.0).unwrap();
+        assert_eq!(reopened.latest_version_cursor("player").unwrap(), expected);
+    }
+
+    #[test]
+    fn ndjson_sink_only_writes_changed_feed_events() {
+        let dir = TempDir::new("feed");
+        let event = |index, hour, text: &str| {
+            (
+                "team".to_string(),
+                index,
+                valid_from(hour),
+                serde_json::json!({ "text": text }),
+            )
+        };
+
+        let sink = NdjsonSink::new(&dir.0).unwrap();
+        let written = sink
+            .write_feed_event_versions("team_feed", &[event(0, 1, "a"), event(1, 1, "b")])
+            .unwrap();
+        assert_eq!(written, 2);
+
+        // The next version of the feed repeats both events and changes one
+        let written = sink
+            .write_feed_event_versions("team_feed", &[event(0, 2, "a"), event(1, 2, "c")])
+            .unwrap();
+        assert_eq!(written, 1);
+        assert_eq!(num_lines(&dir, "team_feed"), 3);
+
+        // A new sink has to remember which events it wrote from the file
+        let reopened = NdjsonSink::new(&dir.0).unwrap();
+        assert_eq!(
+            reopened
+                .latest_feed_event_version_cursor("team_feed")
+                .unwrap(),
+            Some((valid_from(2), "team".to_string())),
+        );
+        let written = reopened
+            .write_feed_event_versions(
+                "team_feed",
+                &[event(0, 3, "a"), event(1, 3, "c"), event(2, 3, "d")],
+            )
+            .unwrap();
+        assert_eq!(written, 1);
+        assert_eq!(num_lines(&dir, "team_feed"), 4);
+    }
+}
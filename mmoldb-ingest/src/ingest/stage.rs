@@ -0,0 +1,135 @@
+use crate::config::IngestConfig;
+use crate::{IngestFatalError, IngestForKind};
+use futures::FutureExt;
+use futures::future::BoxFuture;
+use hashbrown::HashSet;
+use std::sync::Arc;
+
+/// One independently-scheduled piece of the ingest pipeline, like "fetch
+/// player versions" or "process games". Every registered stage that's
+/// enabled gets its own task, which should run until shutdown is
+/// requested.
+pub trait IngestStage: Send + Sync {
+    /// Unique name of this stage. This is how the stage is referred to
+    /// in `stage_order` and `disabled_stages` in the config.
+    fn name(&self) -> String;
+
+    fn is_enabled(&self) -> bool;
+
+    /// The indefinite task for this stage. Should only exit early on
+    /// a fatal error.
+    fn run(self: Arc<Self>) -> BoxFuture<'static, Result<(), IngestFatalError>>;
+}
+
+pub struct FetchStage(pub Arc<IngestForKind>);
+
+impl IngestStage for FetchStage {
+    fn name(&self) -> String {
+        format!("{}_fetch", self.0.kind())
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.0.fetch_is_enabled()
+    }
+
+    fn run(self: Arc<Self>) -> BoxFuture<'static, Result<(), IngestFatalError>> {
+        async move { self.0.fetch_task().await }.boxed()
+    }
+}
+
+pub struct ProcessingStage(pub Arc<IngestForKind>);
+
+impl IngestStage for ProcessingStage {
+    fn name(&self) -> String {
+        format!("{}_processing", self.0.kind())
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.0.processing_is_enabled()
+    }
+
+    fn run(self: Arc<Self>) -> BoxFuture<'static, Result<(), IngestFatalError>> {
+        async move { self.0.processing_task().await }.boxed()
+    }
+}
+
+/// Ordered collection of every ingest stage the ingest binary knows
+/// about. Stages are launched in registration order unless the config
+/// provides a `stage_order`.
+#[derive(Default)]
+pub struct IngestStageRegistry {
+    stages: Vec<Arc<dyn IngestStage>>,
+}
+
+impl IngestStageRegistry {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn register(&mut self, stage: Arc<dyn IngestStage>) -> Result<(), IngestFatalError> {
+        let name = stage.name();
+        if self.stages.iter().any(|existing| existing.name() == name) {
+            return Err(IngestFatalError::DuplicateIngestStage(name));
+        }
+
+        self.stages.push(stage);
+        Ok(())
+    }
+
+    /// Registers the fetch and processing stages for every kind in
+    /// `ingest_kinds`
+    pub fn register_kinds(
+        &mut self,
+        ingest_kinds: impl IntoIterator<Item = Arc<IngestForKind>>,
+    ) -> Result<(), IngestFatalError> {
+        let ingest_kinds = ingest_kinds.into_iter().collect::<Vec<_>>();
+        // All fetch stages go first so that processing stages always
+        // have something to work on
+        for ingest_kind in &ingest_kinds {
+            self.register(Arc::new(FetchStage(ingest_kind.clone())))?;
+        }
+        for ingest_kind in ingest_kinds {
+            self.register(Arc::new(ProcessingStage(ingest_kind)))?;
+        }
+        Ok(())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = String> + '_ {
+        self.stages.iter().map(|stage| stage.name())
+    }
+
+    /// Returns the stages in the order they should be launched, with
+    /// stages listed in `disabled_stages` removed. If `stage_order` is
+    /// set, any stages it doesn't mention are launched after the ones it
+    /// does, in registration order.
+    pub fn into_ordered_stages(
+        self,
+        config: &IngestConfig,
+    ) -> Result<Vec<Arc<dyn IngestStage>>, IngestFatalError> {
+        let known_names = self.names().collect::<HashSet<_>>();
+        for name in config.stage_order.iter().flatten().chain(&config.disabled_stages) {
+            if !known_names.contains(name) {
+                return Err(IngestFatalError::UnknownIngestStage(name.clone()));
+            }
+        }
+
+        let mut remaining = self.stages.into_iter().map(Some).collect::<Vec<_>>();
+        let mut ordered = Vec::with_capacity(remaining.len());
+        for name in config.stage_order.iter().flatten() {
+            let found = remaining
+                .iter_mut()
+                .find(|stage| stage.as_ref().is_some_and(|stage| &stage.name() == name));
+            // A name listed twice in stage_order is found the first time and
+            // ignored the second time
+            if let Some(stage) = found.and_then(Option::take) {
+                ordered.push(stage);
+            }
+        }
+        ordered.extend(remaining.into_iter().flatten());
+
+        Ok(ordered
+            .into_iter()
+            .filter(|stage| !config.disabled_stages.contains(&stage.name()))
+            .collect())
+    }
+}
@@ -1,6 +1,11 @@
+use crate::{IngestFatalError, PreparedIngestItem};
+use chron::ChronEntity;
 use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use mmolb_parsing::feed_event::FeedEvent;
 use mmolb_parsing::player::Deserialize;
+use mmoldb_db::db::NewFeedEventText;
+use mmoldb_db::{ConnectionPool, db};
+use serde::de::IntoDeserializer;
 
 pub(crate) const fn datetime_from_parts(
     year: i32,
@@ -30,3 +35,111 @@ pub struct FeedItemContainer {
     pub prev_valid_from: Option<DateTime<Utc>>,
     pub prev_data: Option<FeedEvent>,
 }
+
+/// Gets the feed event index out of a version that hasn't been
+/// deserialized yet. The stream of unprocessed feed event versions always
+/// adds one, so this only fails if that stream is broken.
+pub(crate) fn raw_feed_event_index(
+    entity: &ChronEntity<serde_json::Value>,
+) -> Result<i32, IngestFatalError> {
+    entity
+        .data
+        .get("feed_event_index")
+        .and_then(serde_json::Value::as_i64)
+        .and_then(|index| i32::try_from(index).ok())
+        .ok_or_else(|| IngestFatalError::MissingFeedEventIndex {
+            kind: entity.kind.clone(),
+            entity_id: entity.entity_id.clone(),
+            valid_from: entity.valid_from,
+        })
+}
+
+/// The text of each feed event being ingested in `versions`, for search.
+/// Events with empty text are left out.
+pub(crate) fn feed_event_texts<'a, Ident>(
+    kind: &'a str,
+    versions: &'a [PreparedIngestItem<Ident, FeedItemContainer>],
+) -> Vec<NewFeedEventText<'a>> {
+    versions
+        .iter()
+        .filter_map(|item| match item {
+            PreparedIngestItem::DoIngest(entity) => Some(entity),
+            _ => None,
+        })
+        .filter(|entity| !entity.data.data.text.is_empty())
+        .map(|entity| NewFeedEventText {
+            kind,
+            entity_id: &entity.entity_id,
+            feed_event_index: entity.data.feed_event_index,
+            valid_from: entity.valid_from,
+            season: entity.data.data.season as i32,
+            time: entity.data.data.timestamp,
+            text: &entity.data.data.text,
+        })
+        .collect()
+}
+
+pub(crate) fn deserialize_feed_event(raw_event: &serde_json::Value) -> Result<FeedEvent, String> {
+    serde_path_to_error::deserialize(raw_event.into_deserializer())
+        .map_err(|err| format!("Error deserializing: {:?}", err))
+}
+
+/// Re-attempts every feed event of this kind in the dead-letter queue.
+/// Events that `reparse` now accepts are marked as unprocessed so the
+/// normal processing pass will ingest them. The rest have their retry
+/// count incremented. Returns the number requeued and the number that
+/// failed again. Requeues are recorded in the admin audit log as done by
+/// the ingest itself.
+pub fn redrive_feed_event_failures(
+    pool: &ConnectionPool,
+    kind: &str,
+    reparse: impl Fn(&serde_json::Value) -> Result<(), String>,
+) -> Result<(usize, usize), IngestFatalError> {
+    let mut conn = pool.get()?;
+    let failures = db::feed_event_failures_for_kind(&mut conn, kind)?;
+
+    let mut to_requeue = Vec::new();
+    let mut still_failing = 0;
+    for failure in &failures {
+        match reparse(&failure.raw_data) {
+            Ok(()) => to_requeue.push(failure),
+            Err(message) => {
+                db::record_feed_event_failure_retry(&mut conn, failure.id, &message)?;
+                still_failing += 1;
+            }
+        }
+    }
+
+    db::requeue_feed_event_failures(&mut conn, &to_requeue, "mmoldb-ingest")?;
+    Ok((to_requeue.len(), still_failing))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed_event_version(data: serde_json::Value) -> ChronEntity<serde_json::Value> {
+        ChronEntity {
+            kind: "team_feed".to_string(),
+            entity_id: "team".to_string(),
+            valid_from: Utc::now(),
+            valid_to: None,
+            data,
+        }
+    }
+
+    #[test]
+    fn missing_feed_event_index_is_an_error() {
+        let version = feed_event_version(serde_json::json!({ "feed_event_index": 3 }));
+        assert_eq!(raw_feed_event_index(&version).ok(), Some(3));
+
+        let version = feed_event_version(serde_json::json!({ "data": {} }));
+        assert!(matches!(
+            raw_feed_event_index(&version),
+            Err(IngestFatalError::MissingFeedEventIndex { .. })
+        ));
+
+        let version = feed_event_version(serde_json::json!({ "feed_event_index": i64::MAX }));
+        assert!(raw_feed_event_index(&version).is_err());
+    }
+}
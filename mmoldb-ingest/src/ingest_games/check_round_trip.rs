@@ -5,6 +5,7 @@ use mmolb_parsing::ParsedEventMessage;
 use mmolb_parsing::enums::Place;
 use mmolb_parsing::parsed_event::PlacedPlayer;
 use mmoldb_db::EventDetail;
+use mmoldb_db::taxa::TaxaIngestErrorCode;
 use strum::IntoDiscriminant;
 
 fn log_if_error<'g, E: std::fmt::Display>(
@@ -16,7 +17,11 @@ fn log_if_error<'g, E: std::fmt::Display>(
     match to_parsed_result {
         Ok(to_contact_result) => Some(to_contact_result),
         Err(err) => {
-            ingest_logs.error(index, format!("{log_prefix}: {err}"));
+            ingest_logs.error(
+                index,
+                TaxaIngestErrorCode::RoundTripMismatch,
+                format!("{log_prefix}: {err}"),
+            );
             None
         }
     }
@@ -68,6 +73,7 @@ fn downgrade_to_match(
             } else {
                 ingest_logs.warn(
                     game_event_index,
+                    TaxaIngestErrorCode::RoundTripMismatch,
                     format!(
                         "Not downgrading parsed Places because the event types don't match \
                         (reconstructed is {:?} and original is {:?})",
@@ -93,6 +99,7 @@ fn downgrade_to_match(
             } else {
                 ingest_logs.warn(
                     game_event_index,
+                    TaxaIngestErrorCode::RoundTripMismatch,
                     format!(
                         "Not downgrading parsed Places because the event types don't match \
                         (reconstructed is {:?} and original is {:?})",
@@ -118,6 +125,7 @@ fn downgrade_to_match(
             } else {
                 ingest_logs.warn(
                     game_event_index,
+                    TaxaIngestErrorCode::RoundTripMismatch,
                     format!(
                         "Not downgrading parsed Places because the event types don't match \
                         (reconstructed is {:?} and original is {:?})",
@@ -143,6 +151,7 @@ fn downgrade_to_match(
             } else {
                 ingest_logs.warn(
                     game_event_index,
+                    TaxaIngestErrorCode::RoundTripMismatch,
                     format!(
                         "Not downgrading parsed Places because the event types don't match \
                         (reconstructed is {:?} and original is {:?})",
@@ -168,6 +177,7 @@ fn downgrade_to_match(
             } else {
                 ingest_logs.warn(
                     game_event_index,
+                    TaxaIngestErrorCode::RoundTripMismatch,
                     format!(
                         "Not downgrading parsed Places because the event types don't match \
                         (reconstructed is {:?} and original is {:?})",
@@ -199,6 +209,7 @@ fn downgrade_places_to_match(
             EitherOrBoth::Left(ours) => {
                 ingest_logs.warn(
                     game_event_index,
+                    TaxaIngestErrorCode::RoundTripMismatch,
                     format!(
                         "Not downgrading parsed Place for {} because the reconstructed event's \
                         {log_loc} item at index {i} had no corresponding item in the original",
@@ -207,7 +218,7 @@ fn downgrade_places_to_match(
                 );
             }
             EitherOrBoth::Right(original) => {
-                ingest_logs.warn(game_event_index, format!(
+                ingest_logs.warn(game_event_index, TaxaIngestErrorCode::RoundTripMismatch, format!(
                     "Not downgrading parsed Place for {} because the original event's {log_loc} \
                     item at index {i} had no corresponding item in the reconstruction",
                     original.name,
@@ -226,6 +237,7 @@ fn downgrade_place_to_match(
     if ours.name != original.name {
         ingest_logs.warn(
             game_event_index,
+            TaxaIngestErrorCode::RoundTripMismatch,
             format!(
                 "Not downgrading parsed Place at {log_loc} because the reconstructed player's name \
                 ({}) didn't match the original's ({})",
@@ -272,6 +284,7 @@ fn downgrade_place_to_starting_pitcher(
         _ => {
             ingest_logs.error(
                 game_event_index,
+                TaxaIngestErrorCode::RoundTripMismatch,
                 format!(
                     "Can't \"downgrade\" {ours:?} into Place::StartingPitcher(None) at {log_loc}",
                 ),
@@ -291,6 +304,7 @@ fn downgrade_place_to_relief_pitcher(
         _ => {
             ingest_logs.error(
                 game_event_index,
+                TaxaIngestErrorCode::RoundTripMismatch,
                 format!(
                     "Can't \"downgrade\" {ours:?} into Place::ReliefPitcher(None) at {log_loc}",
                 ),
@@ -334,6 +348,7 @@ pub fn check_round_trip(
     if parsed != &parsed_through_detail {
         ingest_logs.error(
             index,
+            TaxaIngestErrorCode::RoundTripMismatch,
             format!(
                 "Round-trip of {} through EventDetail produced a mismatch:\n\
                  Original: <pre>{:?}</pre>\
@@ -354,6 +369,7 @@ pub fn check_round_trip(
         Err(err) => {
             ingest_logs.error(
                 index,
+                TaxaIngestErrorCode::RoundTripMismatch,
                 format!(
                     "Attempt to round-trip {} ParsedEventMessage -> EventDetail -> database \
                     -> EventDetail -> ParsedEventMessage failed at the database -> EventDetail \
@@ -396,6 +412,7 @@ pub fn check_round_trip(
     if parsed != &parsed_through_db {
         ingest_logs.error(
             index,
+            TaxaIngestErrorCode::RoundTripMismatch,
             format!(
                 "Round-trip of {} through database produced a mismatch:\n\
                  Original: <pre>{:?}</pre>\n\
@@ -0,0 +1,150 @@
+use serde::de::DeserializeOwned;
+use serde_path_to_error::Segment;
+
+/// How many values may be set aside before an entity is given up on
+const MAX_SET_ASIDE_VALUES: usize = 16;
+
+/// Values that were taken out of an entity's JSON so the rest of it would
+/// deserialize
+#[derive(Debug, Default)]
+pub struct SetAsideValues {
+    /// Keyed by the JSON pointer each value was taken from
+    pub values: serde_json::Map<String, serde_json::Value>,
+    /// The error each value caused, in the order they were set aside
+    pub errors: Vec<String>,
+}
+
+fn json_pointer(path: &serde_path_to_error::Path) -> Option<String> {
+    path.iter()
+        .map(|segment| match segment {
+            Segment::Seq { index } => Some(format!("/{index}")),
+            Segment::Map { key } => Some(format!("/{}", key.replace('~', "~0").replace('/', "~1"))),
+            Segment::Enum { variant } => Some(format!("/{variant}")),
+            Segment::Unknown => None,
+        })
+        .collect()
+}
+
+// Only object members are taken out. Taking an element out of an array
+// would shift everything after it.
+fn take_object_member(data: &mut serde_json::Value, pointer: &str) -> Option<serde_json::Value> {
+    let (parent, key) = pointer.rsplit_once('/')?;
+    let key = key.replace("~1", "/").replace("~0", "~");
+    data.pointer_mut(parent)?.as_object_mut()?.remove(&key)
+}
+
+/// Deserializes `data`, and if that fails, repeatedly sets aside whichever
+/// value failed and tries again. This keeps an entity ingestible when
+/// upstream adds a variant or changes the shape of a field this version of
+/// the parser doesn't depend on. Gives up, returning the original error, if
+/// a required value would have to be set aside.
+pub fn deserialize_leniently<T: DeserializeOwned>(
+    mut data: serde_json::Value,
+) -> Result<(T, Option<SetAsideValues>), serde_path_to_error::Error<serde_json::Error>> {
+    let original_err = match serde_path_to_error::deserialize(&data) {
+        Ok(value) => return Ok((value, None)),
+        Err(err) => err,
+    };
+
+    let mut set_aside = SetAsideValues::default();
+    let mut err_string = original_err.to_string();
+    let mut pointer = json_pointer(original_err.path());
+    while set_aside.values.len() < MAX_SET_ASIDE_VALUES {
+        let Some(this_pointer) = pointer else {
+            break;
+        };
+        // The error is about something that contains a value that was
+        // already set aside, which means that value was required
+        if set_aside
+            .values
+            .keys()
+            .any(|prev| prev.starts_with(&format!("{this_pointer}/")))
+        {
+            break;
+        }
+        let Some(value) = take_object_member(&mut data, &this_pointer) else {
+            break;
+        };
+        set_aside.values.insert(this_pointer, value);
+        set_aside.errors.push(err_string);
+
+        match serde_path_to_error::deserialize(&data) {
+            Ok(value) => return Ok((value, Some(set_aside))),
+            Err(err) => {
+                err_string = err.to_string();
+                pointer = json_pointer(err.path());
+            }
+        }
+    }
+
+    Err(original_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    enum Weather {
+        Sunny,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Inner {
+        weather: Option<Weather>,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Outer {
+        name: String,
+        #[serde(default)]
+        inner: Option<Inner>,
+        count: u8,
+    }
+
+    #[test]
+    fn valid_data_sets_nothing_aside() {
+        let (value, set_aside) = deserialize_leniently::<Outer>(json!({
+            "name": "a",
+            "inner": { "weather": "Sunny" },
+            "count": 1,
+        }))
+        .unwrap();
+        assert_eq!(
+            value.inner,
+            Some(Inner {
+                weather: Some(Weather::Sunny)
+            })
+        );
+        assert!(set_aside.is_none());
+    }
+
+    #[test]
+    fn optional_value_is_set_aside() {
+        let (value, set_aside) = deserialize_leniently::<Outer>(json!({
+            "name": "a",
+            "inner": { "weather": "Foggy" },
+            "count": 1,
+        }))
+        .unwrap();
+        assert_eq!(value.inner, Some(Inner { weather: None }));
+        let set_aside = set_aside.unwrap();
+        assert_eq!(
+            set_aside.values.get("/inner/weather"),
+            Some(&json!("Foggy"))
+        );
+        assert_eq!(set_aside.errors.len(), 1);
+    }
+
+    #[test]
+    fn required_value_is_not_set_aside() {
+        let err = deserialize_leniently::<Outer>(json!({
+            "name": "a",
+            "count": "many",
+        }))
+        .unwrap_err();
+        assert_eq!(err.path().to_string(), "count");
+    }
+}
@@ -1,21 +1,29 @@
 mod check_round_trip;
 mod config;
+mod lenient;
 mod sim;
 mod worker;
 
+pub(crate) use worker::{check_round_trips, prepare_game_for_db};
 use worker::*;
 
-use crate::IngestFatalError;
+use crate::{IngestFatalError, ProgressPublisher};
+use crate::async_pool::AsyncConnectionPool;
+use crate::bulk_insert_gate::BulkInsertGate;
+use crate::offload::{self, ObjectStore};
 use crate::partitioner::Partitioner;
-use chron::{Chron, ChronEntity};
+use chron::{Chron, ChronEntity, ChronProvider};
 use futures::FutureExt;
 use futures::{Stream, StreamExt, TryStreamExt, pin_mut};
 use itertools::Itertools;
+use mmoldb_db::async_db::ProcessingPass;
+use mmoldb_db::db::EventInsertMode;
 use mmoldb_db::taxa::Taxa;
-use mmoldb_db::{AsyncConnection, AsyncPgConnection, ConnectionPool, QueryResult, async_db, db};
+use mmoldb_db::{ConnectionPool, QueryResult, async_db, db};
 use std::collections::HashSet;
 use std::hash::RandomState;
 use std::num::NonZero;
+use std::sync::{Arc, Mutex};
 use tokio::fs;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::sync::mpsc::{Receiver, Sender};
@@ -25,7 +33,9 @@ use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, warn};
 
 const CHRON_MAX_IDS_PER_CALL: usize = 50;
-const PROCESS_GAME_BATCH_SIZE: usize = 100;
+// Per worker. The round-trip check's child table queries are pipelined on
+// each connection, so a few is enough to overlap most of their latency.
+const NUM_ROUND_TRIP_CHECK_CONNECTIONS: usize = 3;
 
 // TODO Use this again
 #[allow(unused)]
@@ -72,51 +82,65 @@ pub async fn fetch_missed_games(pool: ConnectionPool) -> Result<(), IngestFatalE
         );
 
         info!("Saving {} games", chunk.len());
-        let inserted = db::insert_entities(&mut conn, entities.items)?;
+        let inserted = db::insert_entities(&mut conn, entities.items, None)?;
         info!("Saved {} games", inserted);
     }
 
     Ok(())
 }
 
+/// Parses and sims games on `num_workers` workers in parallel, `batch_size`
+/// games at a time. Parsing and simming are CPU-bound and independent per
+/// game, but concurrent inserts into the game tables contend with each
+/// other, so only one worker at a time is allowed to write to the database.
+/// Each of `passes` is streamed to the workers in turn. Games that were
+/// offloaded are fetched back from `offload_store` before they're processed.
 pub async fn ingest_stage_2(
     pool: ConnectionPool,
+    async_pool: AsyncConnectionPool,
+    offload_store: Option<Arc<ObjectStore>>,
+    progress: ProgressPublisher,
+    passes: Vec<ProcessingPass>,
     finish: CancellationToken,
+    num_workers: NonZero<usize>,
+    batch_size: NonZero<usize>,
+    bulk_inserts: BulkInsertGate,
+    event_insert_mode: EventInsertMode,
 ) -> Result<(), IngestFatalError> {
-    // TODO Use parallelism parameter from config, or remove parallelism
-    // Setting workers to 1 after implementing concurrent ingest
-    // let num_workers = std::thread::available_parallelism().unwrap_or_else(|err| {
-    //     warn!("Couldn't get available cores: {}. Falling back to 1.", err);
-    //     NonZero::new(1).expect("Literal 1 should be nonzero")
-    // });
-    let num_workers = NonZero::new(1).unwrap();
     debug!("Ingesting with {} workers", num_workers);
 
     let partitioner = Partitioner::new(num_workers);
+    let db_insert_lock = Arc::new(Mutex::new(()));
 
-    let url = mmoldb_db::postgres_url_from_environment();
-    let mut async_conn = AsyncPgConnection::establish(&url).await?;
+    let mut async_conn = async_pool.get().await?;
 
     // Task names have to outlive their tasks, so we build then in advance
-    let task_names_and_nums = (0..=num_workers.get())
+    let task_names_and_nums = (0..num_workers.get())
         .map(|worker_idx| (format!("game Stage 2 worker {}", worker_idx), worker_idx))
         .collect_vec();
 
     // Launching and awaiting subtasks outside the loop leads to multiple copies of a
     // game being stuck in the queue. There's probably a more elegant solution than
     // spinning up and shutting down tasks so often, but this will do for now.
-    let tasks = task_names_and_nums
+    let mut tasks = task_names_and_nums
         .iter()
         .map(|(name, worker_idx)| {
             // The channel buffer is the mechanism by which we can collect one batch
             // of versions while still processing the previous batch (I think). To
             // get perfect concurrency, we need to have the channel buffer size (at
             // least) match the size of the chunk() call on the other end, which is
-            // PROCESS_GAME_BATCH_SIZE
-            let (send, recv) = tokio::sync::mpsc::channel(PROCESS_GAME_BATCH_SIZE);
+            // batch_size
+            let (send, recv) = tokio::sync::mpsc::channel(batch_size.get());
             let handle = tokio::task::Builder::new().name(name).spawn(process_games(
                 pool.clone(),
+                async_pool.clone(),
+                offload_store.clone(),
+                progress.clone(),
                 recv,
+                db_insert_lock.clone(),
+                bulk_inserts.clone(),
+                batch_size,
+                event_insert_mode,
                 *worker_idx,
             ))?;
 
@@ -124,13 +148,20 @@ pub async fn ingest_stage_2(
         })
         .collect::<Result<Vec<_>, _>>()?;
 
-    let stream = async_db::stream_unprocessed_game_versions(&mut async_conn)
-        .await?
-        .take_until(finish.cancelled().then(|()| async move {
-            info!("Closing game processing stream because shutdown was requested");
-        }));
+    for pass in passes {
+        if finish.is_cancelled() {
+            break;
+        }
+        info!("Starting game stage 2 {:?} pass", pass);
+
+        let stream = async_db::stream_unprocessed_game_versions(&mut async_conn, pass)
+            .await?
+            .take_until(finish.cancelled().then(|()| async move {
+                info!("Closing game processing stream because shutdown was requested");
+            }));
 
-    let tasks = dispatch_to_stage_2_workers(&partitioner, tasks, stream).await?;
+        tasks = dispatch_to_stage_2_workers(&partitioner, tasks, stream).await?;
+    }
 
     // Drop all the senders. This causes the receivers to output None, which is the
     // signal the workers use to know when to exit.
@@ -267,10 +298,29 @@ async fn dispatch_to_stage_2_workers<'name>(
 
 async fn process_games(
     pool: ConnectionPool,
+    async_pool: AsyncConnectionPool,
+    offload_store: Option<Arc<ObjectStore>>,
+    progress: ProgressPublisher,
     game_recv: Receiver<ChronEntity<serde_json::Value>>,
+    db_insert_lock: Arc<Mutex<()>>,
+    bulk_inserts: BulkInsertGate,
+    batch_size: NonZero<usize>,
+    event_insert_mode: EventInsertMode,
     worker_id: usize,
 ) -> Result<(), IngestFatalError> {
-    let result = process_games_internal(pool, game_recv, worker_id).await;
+    let result = process_games_internal(
+        pool,
+        async_pool,
+        offload_store,
+        progress,
+        game_recv,
+        db_insert_lock,
+        bulk_inserts,
+        batch_size,
+        event_insert_mode,
+        worker_id,
+    )
+    .await;
     if let Err(err) = &result {
         error!("Error in process games: {}. ", err);
     }
@@ -279,33 +329,69 @@ async fn process_games(
 
 async fn process_games_internal(
     pool: ConnectionPool,
+    async_pool: AsyncConnectionPool,
+    offload_store: Option<Arc<ObjectStore>>,
+    progress: ProgressPublisher,
     game_recv: Receiver<ChronEntity<serde_json::Value>>,
+    db_insert_lock: Arc<Mutex<()>>,
+    bulk_inserts: BulkInsertGate,
+    batch_size: NonZero<usize>,
+    event_insert_mode: EventInsertMode,
     worker_idx: usize,
 ) -> Result<(), IngestFatalError> {
     let mut conn = pool.get()?;
     let taxa = Taxa::new(&mut conn)?;
 
     let chunk_stream =
-        tokio_stream::wrappers::ReceiverStream::new(game_recv).chunks(PROCESS_GAME_BATCH_SIZE);
+        tokio_stream::wrappers::ReceiverStream::new(game_recv).chunks(batch_size.get());
     pin_mut!(chunk_stream);
 
     // TODO This is going to be duplicated across workers now. It's only used for
     //   timings, so it's not terrible, but it should be fixed.
-    while let Some(raw_games) = chunk_stream.next().await {
+    while let Some(mut raw_games) = chunk_stream.next().await {
         info!(
             "Processing batch of {} raw games on worker {worker_idx}",
             raw_games.len()
         );
-        let stats = ingest_page_of_games(&taxa, raw_games, &mut conn, worker_idx)?;
+        // The round-trip check reads back the batch's games with its child
+        // table queries spread over these. They're only held for the batch,
+        // so idle workers don't tie up connections.
+        let mut check_conns = async_pool
+            .get_many(NUM_ROUND_TRIP_CHECK_CONNECTIONS)
+            .await?;
+        // Parsing and simming a batch takes long enough that it would starve
+        // other tasks if it ran on the async executor as normal
+        let stats = tokio::task::block_in_place(|| {
+            // Offloaded games come out of the database without their data
+            offload::fetch_offloaded_entities(
+                &mut conn,
+                offload_store.as_deref(),
+                "game",
+                &mut raw_games,
+            )?;
+            ingest_page_of_games(
+                &taxa,
+                raw_games,
+                &mut conn,
+                &mut check_conns,
+                &db_insert_lock,
+                &bulk_inserts,
+                &progress,
+                event_insert_mode,
+                worker_idx,
+            )
+        })?;
         info!(
             "Ingested {} games, skipped {} games due to fatal errors, ignored {} games in \
-            progress, skipped {} unsupported games, and skipped {} bugged games on worker {}.",
+            progress, skipped {} unsupported games, and skipped {} bugged games on worker {}. \
+            Waited {:.3}s for other workers' inserts.",
             stats.num_games_imported,
             stats.num_games_with_fatal_errors,
             stats.num_ongoing_games_skipped,
             stats.num_unsupported_games_skipped,
             stats.num_bugged_games_skipped,
             worker_idx,
+            stats.db_insert_lock_wait_seconds,
         );
     }
 
@@ -8,14 +8,15 @@ use mmolb_parsing::enums::{
 use mmolb_parsing::game::{EventBatterVersions, EventPitcherVersions, MaybePlayer};
 use mmolb_parsing::parsed_event::{Assassination, BaseSteal, BasicPitcherSwap, Cheer, ContainResult, DoorPrize, Efflorescence, Ejection, EjectionReplacement, EmojiFood, EmojiPlayer, EmojiTeam, FallingStarOutcome, FieldingAttempt, KnownBug, ParsedEventMessageDiscriminants, PartyDurabilityLoss, PlacedPlayer, RunnerAdvance, RunnerOut, SnappedPhotos, StartOfInningPitcher, WeatherConsumptionEvents, WitherResult, WitherStruggle};
 use mmolb_parsing::{MaybeRecognizedResult, ParsedEventMessage};
-use mmoldb_db::taxa::{AsInsertable, TaxaPitcherChangeSource};
+use mmoldb_db::taxa::{AsInsertable, TaxaIngestErrorCode, TaxaPitcherChangeSource};
 use mmoldb_db::taxa::{
     TaxaBase, TaxaEventType, TaxaFairBallType, TaxaFielderLocation, TaxaFieldingErrorType, TaxaSlot,
 };
 use mmoldb_db::{
     BestEffortSlot, BestEffortSlottedPlayer, ConsumptionContestEventForDb, ConsumptionContestForDb,
-    EventDetail, EventDetailFielder, EventDetailRunner, IngestLog, PartyEvent,
-    PerTeamConsumptionContestForDb, PitcherChange, WitherOutcome,
+    EventDefense, EventDetail, EventDetailFielder, EventDetailRunner, IngestLog, PartyEvent,
+    PerTeamConsumptionContestForDb, PitchSequenceEntry, PitcherChange, PlateAppearanceWorkload,
+    WitherOutcome,
 };
 use std::cmp::Ordering;
 use std::collections::{HashMap, VecDeque};
@@ -134,52 +135,45 @@ impl IngestLogs {
         }
     }
 
-    pub fn critical(&mut self, s: impl Into<String>) {
-        self.logs.push(IngestLog {
-            game_event_index: self.game_event_index,
-            log_level: 0,
-            log_text: s.into(),
-        });
+    // Anything at warning level or above is a problem with the game or with
+    // MMOLDB, so it needs an error code to be counted on the issues page
+
+    pub fn critical(&mut self, error_code: TaxaIngestErrorCode, s: impl Into<String>) {
+        self.push(0, Some(error_code), s);
     }
 
-    pub fn error(&mut self, s: impl Into<String>) {
-        self.logs.push(IngestLog {
-            game_event_index: self.game_event_index,
-            log_level: 1,
-            log_text: s.into(),
-        });
+    pub fn error(&mut self, error_code: TaxaIngestErrorCode, s: impl Into<String>) {
+        self.push(1, Some(error_code), s);
     }
 
-    pub fn warn(&mut self, s: impl Into<String>) {
-        self.logs.push(IngestLog {
-            game_event_index: self.game_event_index,
-            log_level: 2,
-            log_text: s.into(),
-        });
+    pub fn warn(&mut self, error_code: TaxaIngestErrorCode, s: impl Into<String>) {
+        self.push(2, Some(error_code), s);
     }
 
     pub fn info(&mut self, s: impl Into<String>) {
-        self.logs.push(IngestLog {
-            game_event_index: self.game_event_index,
-            log_level: 3,
-            log_text: s.into(),
-        });
+        self.push(3, None, s);
     }
 
     pub fn debug(&mut self, s: impl Into<String>) {
-        self.logs.push(IngestLog {
-            game_event_index: self.game_event_index,
-            log_level: 4,
-            log_text: s.into(),
-        });
+        self.push(4, None, s);
     }
 
     #[allow(dead_code)]
     pub fn trace(&mut self, s: impl Into<String>) {
+        self.push(5, None, s);
+    }
+
+    fn push(
+        &mut self,
+        log_level: i32,
+        error_code: Option<TaxaIngestErrorCode>,
+        s: impl Into<String>,
+    ) {
         self.logs.push(IngestLog {
             game_event_index: self.game_event_index,
-            log_level: 5,
+            log_level,
             log_text: s.into(),
+            error_code,
         });
     }
 
@@ -397,6 +391,81 @@ pub struct TeamInGame<'g> {
     batter_subcount: i32,
     advance_to_next_batter: bool,
     has_seen_first_batter: bool,
+    pitcher_workloads: HashMap<&'g str, PitcherWorkload>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct PitcherWorkload {
+    batters_faced: i32,
+    pitches: i32,
+}
+
+/// Pitch counts for the plate appearance in progress
+#[derive(Debug, Clone, Default)]
+struct CurrentPlateAppearance<'g> {
+    pitches: i32,
+    /// The pitcher who threw this plate appearance's latest pitch. The
+    /// plate appearance is credited to whoever is pitching when it ends.
+    pitcher_name: Option<&'g str>,
+    /// How many pitches that pitcher had thrown before their first pitch
+    /// of this plate appearance
+    pitcher_pitches_before: i32,
+    /// How many of this plate appearance's pitches that pitcher threw
+    pitcher_pitches: i32,
+    last_pitch: Option<usize>,
+}
+
+impl<'g> CurrentPlateAppearance<'g> {
+    /// Counts one event against the plate appearance and the pitcher's
+    /// workload. Events that aren't pitches (balks) aren't counted. Returns
+    /// the event's place in the pitch sequence if it was a pitch, and the
+    /// plate appearance's workload if the event ended it.
+    fn record_event(
+        &mut self,
+        workload: &mut PitcherWorkload,
+        pitcher_name: &'g str,
+        game_event_index: usize,
+        is_pitch: bool,
+        ends_plate_appearance: bool,
+    ) -> (Option<PitchSequenceEntry>, Option<PlateAppearanceWorkload>) {
+        // A pitcher who comes in partway through a plate appearance starts
+        // their share of it from their own workload
+        if self.pitcher_name != Some(pitcher_name) {
+            self.pitcher_name = Some(pitcher_name);
+            self.pitcher_pitches_before = workload.pitches;
+            self.pitcher_pitches = 0;
+        }
+
+        let sequence_entry = is_pitch.then(|| {
+            workload.pitches += 1;
+            self.pitches += 1;
+            self.pitcher_pitches += 1;
+
+            let entry = PitchSequenceEntry {
+                game_event_index,
+                pitch_of_plate_appearance: self.pitches,
+                previous_pitch_game_event_index: self.last_pitch,
+            };
+            self.last_pitch = Some(game_event_index);
+            entry
+        });
+
+        let plate_appearance = ends_plate_appearance.then(|| {
+            let batters_faced_before = workload.batters_faced;
+            workload.batters_faced += 1;
+            let plate_appearance = PlateAppearanceWorkload {
+                game_event_index,
+                pitcher_batters_faced_before: batters_faced_before,
+                times_through_order: batters_faced_before / 9 + 1,
+                pitcher_pitches_before: self.pitcher_pitches_before,
+                pitches: self.pitcher_pitches,
+            };
+            *self = Self::default();
+            plate_appearance
+        });
+
+        (sequence_entry, plate_appearance)
+    }
 }
 
 impl<'g> TeamInGame<'g> {
@@ -410,6 +479,19 @@ impl<'g> TeamInGame<'g> {
     pub fn manager_name(&self) -> Option<&'g str> {
         self.manager_name
     }
+
+    pub fn defense(&self) -> EventDefense<&'g str> {
+        EventDefense {
+            catcher: self.fielder_at(TaxaFielderLocation::Catcher),
+            first_base: self.fielder_at(TaxaFielderLocation::FirstBase),
+            second_base: self.fielder_at(TaxaFielderLocation::SecondBase),
+            third_base: self.fielder_at(TaxaFielderLocation::ThirdBase),
+            shortstop: self.fielder_at(TaxaFielderLocation::Shortstop),
+            left_field: self.fielder_at(TaxaFielderLocation::LeftField),
+            center_field: self.fielder_at(TaxaFielderLocation::CenterField),
+            right_field: self.fielder_at(TaxaFielderLocation::RightField),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -463,6 +545,11 @@ pub struct Game<'g> {
     pub away_team_photo_contest_top_scorer: Option<&'g str>,
     pub away_team_photo_contest_score: Option<i32>,
     pub last_game_event_index_with_event_detail: Option<usize>,
+    // Defensive alignment for each event detail, in the same order
+    pub event_defenses: Vec<EventDefense<&'g str>>,
+    pub plate_appearance_workloads: Vec<PlateAppearanceWorkload>,
+    pub pitch_sequence: Vec<PitchSequenceEntry>,
+    current_plate_appearance: CurrentPlateAppearance<'g>,
 
     // Aggregates
     away: TeamInGame<'g>,
@@ -734,10 +821,13 @@ impl<'g> EventDetailBuilder<'g> {
             Place::StartingPitcher(Some(4)) => TaxaSlot::StartingPitcher4,
             Place::StartingPitcher(Some(5)) => TaxaSlot::StartingPitcher5,
             Place::StartingPitcher(Some(other)) => {
-                ingest_logs.warn(format!(
-                    "Unexpected starting pitcher number: {other} (expected 1-5). Falling back to \
-                    un-numbered starting pitcher type.",
-                ));
+                ingest_logs.warn(
+                    TaxaIngestErrorCode::UnexpectedEvent,
+                    format!(
+                        "Unexpected starting pitcher number: {other} (expected 1-5). Falling back to \
+                        un-numbered starting pitcher type.",
+                    ),
+                );
                 TaxaSlot::StartingPitcher
             }
             Place::ReliefPitcher(None) => TaxaSlot::ReliefPitcher,
@@ -745,10 +835,13 @@ impl<'g> EventDetailBuilder<'g> {
             Place::ReliefPitcher(Some(2)) => TaxaSlot::ReliefPitcher2,
             Place::ReliefPitcher(Some(3)) => TaxaSlot::ReliefPitcher3,
             Place::ReliefPitcher(Some(other)) => {
-                ingest_logs.warn(format!(
-                    "Unexpected relief pitcher number: {other} (expected 1-3). Falling back to \
+                ingest_logs.warn(
+                    TaxaIngestErrorCode::UnexpectedEvent,
+                    format!(
+                        "Unexpected relief pitcher number: {other} (expected 1-3). Falling back to \
                     un-numbered relief pitcher type.",
-                ));
+                    ),
+                );
                 TaxaSlot::ReliefPitcher
             }
             Place::Closer => TaxaSlot::Closer,
@@ -1015,11 +1108,14 @@ impl<'g> EventDetailBuilder<'g> {
                     if let Some(scorer_name) = scores.next() {
                         // If there are any scores left, they MUST be in runner order.
                         if scorer_name != prev_runner.runner_name {
-                            ingest_logs.error(format!(
-                                "Runner {scorer_name} scored, but the farthest runner was {}. \
+                            ingest_logs.error(
+                                TaxaIngestErrorCode::BaserunnerMismatch,
+                                format!(
+                                    "Runner {scorer_name} scored, but the farthest runner was {}. \
                                 Ignoring the score.",
-                                prev_runner.runner_name,
-                            ));
+                                    prev_runner.runner_name,
+                                ),
+                            );
                             None
                         } else {
                             Some(scorer_name)
@@ -1123,11 +1219,14 @@ impl<'g> EventDetailBuilder<'g> {
             // order doesn't matter.
             runners_out_ref.map(|out| {
                 if out.runner != batter_name {
-                    ingest_logs.warn(format!(
-                        "Got a batter-runner entry in `baserunners` that has the wrong name \
+                    ingest_logs.warn(
+                        TaxaIngestErrorCode::BaserunnerMismatch,
+                        format!(
+                            "Got a batter-runner entry in `baserunners` that has the wrong name \
                         ({}, expected {})",
-                        out.runner, batter_name,
-                    ));
+                            out.runner, batter_name,
+                        ),
+                    );
                 }
 
                 EventDetailRunner {
@@ -1150,10 +1249,13 @@ impl<'g> EventDetailBuilder<'g> {
             // There can be "advances" that put a runner on base
             advances_ref.map(|advance| {
                 if advance.runner != batter_name {
-                    ingest_logs.warn(format!(
-                        "Got a stray advance ({}) that doesn't match the batter name ({})",
-                        advance.runner, batter_name,
-                    ));
+                    ingest_logs.warn(
+                        TaxaIngestErrorCode::BaserunnerMismatch,
+                        format!(
+                            "Got a stray advance ({}) that doesn't match the batter name ({})",
+                            advance.runner, batter_name,
+                        ),
+                    );
                 }
 
                 EventDetailRunner {
@@ -1194,26 +1296,38 @@ impl<'g> EventDetailBuilder<'g> {
         // Check that we processed every change to existing runners
         let extra_steals = steals.collect::<Vec<_>>();
         if !extra_steals.is_empty() {
-            ingest_logs.error(format!("Stealing runner(s) not found: {:?}", extra_steals));
+            ingest_logs.error(
+                TaxaIngestErrorCode::BaserunnerMismatch,
+                format!("Stealing runner(s) not found: {:?}", extra_steals),
+            );
         }
         let extra_scores = scores.collect::<Vec<_>>();
         if !extra_scores.is_empty() {
-            ingest_logs.error(format!("Scoring runner(s) not found: {:?}", extra_scores));
+            ingest_logs.error(
+                TaxaIngestErrorCode::BaserunnerMismatch,
+                format!("Scoring runner(s) not found: {:?}", extra_scores),
+            );
         }
         let extra_advances = advances.collect::<Vec<_>>();
         if !extra_advances.is_empty() {
-            ingest_logs.error(format!(
-                "Advancing runner(s) not found: {:?}",
-                extra_advances
-            ));
+            ingest_logs.error(
+                TaxaIngestErrorCode::BaserunnerMismatch,
+                format!("Advancing runner(s) not found: {:?}", extra_advances),
+            );
         }
         let extra_runners_out = runners_out.collect::<Vec<_>>();
         if !extra_runners_out.is_empty() {
-            ingest_logs.error(format!("Runner(s) out not found: {:?}", extra_runners_out));
+            ingest_logs.error(
+                TaxaIngestErrorCode::BaserunnerMismatch,
+                format!("Runner(s) out not found: {:?}", extra_runners_out),
+            );
         }
         let extra_assassinations = assassinations.collect::<Vec<_>>();
         if !extra_assassinations.is_empty() {
-            ingest_logs.error(format!("Assassination(s) not found: {:?}", extra_assassinations));
+            ingest_logs.error(
+                TaxaIngestErrorCode::BaserunnerMismatch,
+                format!("Assassination(s) not found: {:?}", extra_assassinations),
+            );
         }
 
         let pitcher = match &self.raw_event.pitcher {
@@ -1435,9 +1549,10 @@ impl<'g> Game<'g> {
 
             if let Some(stadium_name) = stadium_name {
                 if game_data.season < 3 {
-                    logs.warn(format!(
-                        "Pre-s3 game was played in a stadium: {stadium_name}"
-                    ));
+                    logs.warn(
+                        TaxaIngestErrorCode::UnexpectedEvent,
+                        format!("Pre-s3 game was played in a stadium: {stadium_name}"),
+                    );
                 } else {
                     logs.debug(format!("Set stadium name to {stadium_name}"));
                 }
@@ -1445,7 +1560,10 @@ impl<'g> Game<'g> {
                 if game_data.season < 3 {
                     logs.debug("Pre-s3 game was not played in a stadium");
                 } else {
-                    logs.warn("Post-s3 game was not played in a stadium");
+                    logs.warn(
+                        TaxaIngestErrorCode::UnexpectedEvent,
+                        "Post-s3 game was not played in a stadium",
+                    );
                 }
             }
 
@@ -1479,28 +1597,40 @@ impl<'g> Game<'g> {
         )?;
         let mut event_ingest_logs = IngestLogs::new(game_event_index);
         if away_team_name_2 != away_team_name {
-            event_ingest_logs.warn(format!(
-                "Away team name from PitchingMatchup ({away_team_name_2}) did \
-                not match the one from LiveNow ({away_team_name})"
-            ));
+            event_ingest_logs.warn(
+                TaxaIngestErrorCode::TeamMismatch,
+                format!(
+                    "Away team name from PitchingMatchup ({away_team_name_2}) did \
+                    not match the one from LiveNow ({away_team_name})"
+                ),
+            );
         }
         if away_team_emoji_2 != away_team_emoji {
-            event_ingest_logs.warn(format!(
-                "Away team emoji from PitchingMatchup ({away_team_emoji_2}) did \
-                not match the one from LiveNow ({away_team_emoji})"
-            ));
+            event_ingest_logs.warn(
+                TaxaIngestErrorCode::TeamMismatch,
+                format!(
+                    "Away team emoji from PitchingMatchup ({away_team_emoji_2}) did \
+                    not match the one from LiveNow ({away_team_emoji})"
+                ),
+            );
         }
         if home_team_name_2 != home_team_name {
-            event_ingest_logs.warn(format!(
-                "Home team name from PitchingMatchup ({home_team_name_2}) did \
-                not match the one from LiveNow ({home_team_name})"
-            ));
+            event_ingest_logs.warn(
+                TaxaIngestErrorCode::TeamMismatch,
+                format!(
+                    "Home team name from PitchingMatchup ({home_team_name_2}) did \
+                    not match the one from LiveNow ({home_team_name})"
+                ),
+            );
         }
         if home_team_emoji_2 != home_team_emoji {
-            event_ingest_logs.warn(format!(
-                "Home team emoji from PitchingMatchup ({home_team_emoji_2}) did \
-                not match the one from LiveNow ({home_team_emoji})"
-            ));
+            event_ingest_logs.warn(
+                TaxaIngestErrorCode::TeamMismatch,
+                format!(
+                    "Home team emoji from PitchingMatchup ({home_team_emoji_2}) did \
+                    not match the one from LiveNow ({home_team_emoji})"
+                ),
+            );
         }
         event_ingest_logs.debug(format!(
             "Set home team pitcher to name: \"{home_pitcher_name}\""
@@ -1599,6 +1729,10 @@ impl<'g> Game<'g> {
             away_team_photo_contest_top_scorer: None,
             away_team_photo_contest_score: None,
             last_game_event_index_with_event_detail: None,
+            event_defenses: Vec::new(),
+            plate_appearance_workloads: Vec::new(),
+            pitch_sequence: Vec::new(),
+            current_plate_appearance: CurrentPlateAppearance::default(),
             away: TeamInGame {
                 team_name: away_team_name,
                 team_emoji: away_team_emoji,
@@ -1618,6 +1752,7 @@ impl<'g> Game<'g> {
                 batter_subcount: 0,
                 advance_to_next_batter: false,
                 has_seen_first_batter: false,
+                pitcher_workloads: HashMap::new(),
             },
             home: TeamInGame {
                 team_name: home_team_name,
@@ -1638,6 +1773,7 @@ impl<'g> Game<'g> {
                 batter_subcount: 0,
                 advance_to_next_batter: false,
                 has_seen_first_batter: false,
+                pitcher_workloads: HashMap::new(),
             },
             state: GameState {
                 prev_event_type: ParsedEventMessageDiscriminants::PlayBall,
@@ -1714,16 +1850,22 @@ impl<'g> Game<'g> {
 
     fn check_count(&self, (balls, strikes): (u8, u8), ingest_logs: &mut IngestLogs) {
         if self.state.count_balls != balls {
-            ingest_logs.warn(format!(
-                "Unexpected number of balls: expected {}, but saw {balls}",
-                self.state.count_balls
-            ));
+            ingest_logs.warn(
+                TaxaIngestErrorCode::CountMismatch,
+                format!(
+                    "Unexpected number of balls: expected {}, but saw {balls}",
+                    self.state.count_balls
+                ),
+            );
         }
         if self.state.count_strikes != strikes {
-            ingest_logs.warn(format!(
-                "Unexpected number of strikes: expected {}, but saw {strikes}",
-                self.state.count_strikes
-            ));
+            ingest_logs.warn(
+                TaxaIngestErrorCode::CountMismatch,
+                format!(
+                    "Unexpected number of strikes: expected {}, but saw {strikes}",
+                    self.state.count_strikes
+                ),
+            );
         }
     }
 
@@ -1765,16 +1907,22 @@ impl<'g> Game<'g> {
         let team = self.defending_team();
         if let Some(stored_manager_name) = team.manager_name {
             if stored_manager_name != incoming_manager_name {
-                ingest_logs.error(format!(
-                    "Expected the defending team's manager's stored name to be \
-                    {incoming_manager_name}, but it was {stored_manager_name}."
-                ));
+                ingest_logs.error(
+                    TaxaIngestErrorCode::PlayerMismatch,
+                    format!(
+                        "Expected the defending team's manager's stored name to be \
+                        {incoming_manager_name}, but it was {stored_manager_name}."
+                    ),
+                );
             }
         } else {
-            ingest_logs.error(format!(
-                "Expected the defending team's manager's stored name to be \
-                {incoming_manager_name}, but there was no stored manager name."
-            ));
+            ingest_logs.error(
+                TaxaIngestErrorCode::PlayerMismatch,
+                format!(
+                    "Expected the defending team's manager's stored name to be \
+                    {incoming_manager_name}, but there was no stored manager name."
+                ),
+            );
         }
     }
 
@@ -1785,20 +1933,26 @@ impl<'g> Game<'g> {
         ingest_logs: &mut IngestLogs,
     ) {
         if expected_batter_name != observed_batter_name {
-            ingest_logs.warn(format!(
-                "Unexpected batter name: Expected {}, but saw {}",
-                expected_batter_name, observed_batter_name,
-            ));
+            ingest_logs.warn(
+                TaxaIngestErrorCode::PlayerMismatch,
+                format!(
+                    "Unexpected batter name: Expected {}, but saw {}",
+                    expected_batter_name, observed_batter_name,
+                ),
+            );
         }
     }
 
     fn check_pitcher(&self, observed_pitcher_name: &str, ingest_logs: &mut IngestLogs) {
         if self.defending_team().active_pitcher.name != observed_pitcher_name {
-            ingest_logs.warn(format!(
-                "Unexpected pitcher name: Expected {}, but saw {}",
-                self.defending_team().active_pitcher.name,
-                observed_pitcher_name,
-            ));
+            ingest_logs.warn(
+                TaxaIngestErrorCode::PlayerMismatch,
+                format!(
+                    "Unexpected pitcher name: Expected {}, but saw {}",
+                    self.defending_team().active_pitcher.name,
+                    observed_pitcher_name,
+                ),
+            );
         }
     }
 
@@ -1809,10 +1963,13 @@ impl<'g> Game<'g> {
         ingest_logs: &mut IngestLogs,
     ) {
         if fair_ball_from_previous_event.fair_ball_type != fair_ball_type_from_this_event {
-            ingest_logs.warn(format!(
-                "Mismatched fair ball type: expected {} but saw {}",
-                fair_ball_from_previous_event.fair_ball_type, fair_ball_type_from_this_event,
-            ));
+            ingest_logs.warn(
+                TaxaIngestErrorCode::FairBallMismatch,
+                format!(
+                    "Mismatched fair ball type: expected {} but saw {}",
+                    fair_ball_from_previous_event.fair_ball_type, fair_ball_type_from_this_event,
+                ),
+            );
         }
     }
 
@@ -1825,11 +1982,14 @@ impl<'g> Game<'g> {
         if fair_ball_from_previous_event.fair_ball_destination
             != fair_ball_destination_from_this_event
         {
-            ingest_logs.warn(format!(
-                "Mismatched fair ball destination: expected {} but saw {}",
-                fair_ball_from_previous_event.fair_ball_destination,
-                fair_ball_destination_from_this_event,
-            ));
+            ingest_logs.warn(
+                TaxaIngestErrorCode::FairBallMismatch,
+                format!(
+                    "Mismatched fair ball destination: expected {} but saw {}",
+                    fair_ball_from_previous_event.fair_ball_destination,
+                    fair_ball_destination_from_this_event,
+                ),
+            );
         }
     }
 
@@ -1843,9 +2003,10 @@ impl<'g> Game<'g> {
         if let Some(fielder) = fielders.first() {
             self.check_placed_fielder(fair_ball_from_previous_event, fielder, ingest_logs);
         } else {
-            ingest_logs.warn(format!(
-                "Expected at least one fielder in {event_type} event",
-            ))
+            ingest_logs.warn(
+                TaxaIngestErrorCode::FielderMismatch,
+                format!("Expected at least one fielder in {event_type} event"),
+            )
         }
     }
 
@@ -2100,13 +2261,15 @@ impl<'g> Game<'g> {
             value_from_mmolb: bool,
         ) {
             if value_from_mmolb && !expected_value {
-                log.error(format!(
-                    "Observed a runner on {which_base} but we expected it to be empty"
-                ));
+                log.error(
+                    TaxaIngestErrorCode::BaserunnerMismatch,
+                    format!("Observed a runner on {which_base} but we expected it to be empty"),
+                );
             } else if !value_from_mmolb && expected_value {
-                log.error(format!(
-                    "Expected a runner on {which_base} but observed it to be empty"
-                ));
+                log.error(
+                    TaxaIngestErrorCode::BaserunnerMismatch,
+                    format!("Expected a runner on {which_base} but observed it to be empty"),
+                );
             }
         }
         test_on_base(ingest_logs, "first", on_1b, raw_event.on_1b);
@@ -2121,18 +2284,24 @@ impl<'g> Game<'g> {
             .iter()
             .is_sorted_by(|a, b| a.base > b.base)
         {
-            ingest_logs.error(format!(
-                "Runners on base list was not sorted descending by base: {:?}",
-                self.state.runners_on
-            ));
+            ingest_logs.error(
+                TaxaIngestErrorCode::BaserunnerMismatch,
+                format!(
+                    "Runners on base list was not sorted descending by base: {:?}",
+                    self.state.runners_on
+                ),
+            );
         }
 
         if self.state.runners_on.iter().unique_by(|r| r.base).count() != self.state.runners_on.len()
         {
-            ingest_logs.error(format!(
-                "Runners on base list has multiple runners on the same base: {:?}",
-                self.state.runners_on
-            ));
+            ingest_logs.error(
+                TaxaIngestErrorCode::BaserunnerMismatch,
+                format!(
+                    "Runners on base list has multiple runners on the same base: {:?}",
+                    self.state.runners_on
+                ),
+            );
         }
 
         if self
@@ -2141,10 +2310,13 @@ impl<'g> Game<'g> {
             .iter()
             .any(|r| r.base == TaxaBase::Home)
         {
-            ingest_logs.error(format!(
-                "Runners on base list has a runner on Home: {:?}",
-                self.state.runners_on
-            ));
+            ingest_logs.error(
+                TaxaIngestErrorCode::BaserunnerMismatch,
+                format!(
+                    "Runners on base list has a runner on Home: {:?}",
+                    self.state.runners_on
+                ),
+            );
         }
     }
 
@@ -2171,7 +2343,7 @@ impl<'g> Game<'g> {
             for s in updates.steals {
                 msg = format!("{msg}, {} attempted steal of {}", s.runner, s.base);
             }
-            ingest_logs.error(msg);
+            ingest_logs.error(TaxaIngestErrorCode::BaserunnerMismatch, msg);
         }
         if !updates
             .advances
@@ -2183,7 +2355,7 @@ impl<'g> Game<'g> {
             for a in updates.advances {
                 msg = format!("{msg}, {} advanced to {}", a.runner, a.base);
             }
-            ingest_logs.error(msg);
+            ingest_logs.error(TaxaIngestErrorCode::BaserunnerMismatch, msg);
         }
         ingest_logs.debug(format!("Runners out: {:?}", updates.runners_out));
         if !updates
@@ -2196,7 +2368,7 @@ impl<'g> Game<'g> {
             for o in updates.runners_out {
                 msg = format!("{msg}, {} out at {}", o.runner, o.base);
             }
-            ingest_logs.error(msg);
+            ingest_logs.error(TaxaIngestErrorCode::BaserunnerMismatch, msg);
         }
 
         let n_runners_on_before = self.state.runners_on.len();
@@ -2221,10 +2393,13 @@ impl<'g> Game<'g> {
         self.state.runners_on.retain_mut(|runner| {
             // Consistency check
             if last_occupied_base == Some(TaxaBase::Home) {
-                ingest_logs.error(format!(
-                    "When processing {} (on {:#?}), the previous occupied base was Home",
-                    runner.runner_name, runner.base
-                ));
+                ingest_logs.error(
+                    TaxaIngestErrorCode::BaserunnerMismatch,
+                    format!(
+                        "When processing {} (on {:#?}), the previous occupied base was Home",
+                        runner.runner_name, runner.base
+                    ),
+                );
             }
 
             // Runners can only score if there is no one ahead of them
@@ -2410,22 +2585,31 @@ impl<'g> Game<'g> {
         // Check that we processed every change to existing runners
         let extra_steals = steals_iter.collect::<Vec<_>>();
         if !extra_steals.is_empty() {
-            ingest_logs.error(format!("Failed to apply steal(s): {:?}", extra_steals));
+            ingest_logs.error(
+                TaxaIngestErrorCode::BaserunnerMismatch,
+                format!("Failed to apply steal(s): {:?}", extra_steals),
+            );
         }
         let extra_scores = scores_iter.collect::<Vec<_>>();
         if !extra_scores.is_empty() {
-            ingest_logs.error(format!("Failed to apply score(s): {:?}", extra_scores));
+            ingest_logs.error(
+                TaxaIngestErrorCode::BaserunnerMismatch,
+                format!("Failed to apply score(s): {:?}", extra_scores),
+            );
         }
         let extra_advances = advances_iter.collect::<Vec<_>>();
         if !extra_advances.is_empty() {
-            ingest_logs.error(format!("Failed to apply advance(s): {:?}", extra_advances));
+            ingest_logs.error(
+                TaxaIngestErrorCode::BaserunnerMismatch,
+                format!("Failed to apply advance(s): {:?}", extra_advances),
+            );
         }
         let extra_runners_out = runners_out_iter.collect::<Vec<_>>();
         if !extra_runners_out.is_empty() {
-            ingest_logs.error(format!(
-                "Failed to apply runner(s) out: {:?}",
-                extra_runners_out
-            ));
+            ingest_logs.error(
+                TaxaIngestErrorCode::BaserunnerMismatch,
+                format!("Failed to apply runner(s) out: {:?}", extra_runners_out),
+            );
         }
 
         // Consistency check
@@ -2439,13 +2623,16 @@ impl<'g> Game<'g> {
             + batter_out as isize
             + new_runners as isize;
         if self.state.runners_on.len() as isize != expected_n_runners_after {
-            ingest_logs.error(format!(
-                "Inconsistent runner counting: With {n_runners_on_before} on to start, \
-                {n_caught_stealing} caught stealing, {n_stole_home} stealing home, {n_scored} scoring\
-                , and {n_runners_out} out, including {batter_out} batter outs, plus {new_runners} new \
-                runners, expected {expected_n_runners_after} runners on but our records show {}",
-                self.state.runners_on.len(),
-            ));
+            ingest_logs.error(
+                TaxaIngestErrorCode::BaserunnerMismatch,
+                format!(
+                    "Inconsistent runner counting: With {n_runners_on_before} on to start, \
+                    {n_caught_stealing} caught stealing, {n_stole_home} stealing home, {n_scored} scoring\
+                    , and {n_runners_out} out, including {batter_out} batter outs, plus {new_runners} new \
+                    runners, expected {expected_n_runners_after} runners on but our records show {}",
+                    self.state.runners_on.len(),
+                ),
+            );
         }
 
         if let Some((runner_name, base)) = updates.runner_added {
@@ -2463,15 +2650,21 @@ impl<'g> Game<'g> {
                     }
                 } else if let Some(last_runner) = self.state.runners_on.back() {
                     if last_runner.base == base {
-                        ingest_logs.warn(format!(
-                            "Putting batter-runner {} on {:#?} when {} is already on it",
-                            runner_name, base, last_runner.runner_name,
-                        ));
+                        ingest_logs.warn(
+                            TaxaIngestErrorCode::BaserunnerMismatch,
+                            format!(
+                                "Putting batter-runner {} on {:#?} when {} is already on it",
+                                runner_name, base, last_runner.runner_name,
+                            ),
+                        );
                     } else if last_runner.base < base {
-                        ingest_logs.warn(format!(
-                            "Putting batter-runner {} on {:#?} when {} is on {:#?}",
-                            runner_name, base, last_runner.runner_name, last_runner.base,
-                        ));
+                        ingest_logs.warn(
+                            TaxaIngestErrorCode::BaserunnerMismatch,
+                            format!(
+                                "Putting batter-runner {} on {:#?} when {} is on {:#?}",
+                                runner_name, base, last_runner.runner_name, last_runner.base,
+                            ),
+                        );
                     }
                 }
 
@@ -2655,32 +2848,44 @@ impl<'g> Game<'g> {
         if self.state.home_score > self.state.away_score {
             ingest_logs.info("Winning team should be the home team");
             if self.home_team().team_emoji != winning_team.emoji {
-                ingest_logs.warn(format!(
-                    "Winning team emoji from event text ({}) did not match stored home team emoji ({})",
-                    winning_team.emoji,
-                    self.home_team().team_emoji,
-                ));
+                ingest_logs.warn(
+                    TaxaIngestErrorCode::TeamMismatch,
+                    format!(
+                        "Winning team emoji from event text ({}) did not match stored home team emoji ({})",
+                        winning_team.emoji,
+                        self.home_team().team_emoji,
+                    ),
+                );
             }
             if self.home_team().team_name != winning_team.name {
-                ingest_logs.warn(format!(
-                    "Winning team name from event text ({}) did not match stored home team name ({})",
-                    winning_team.name,
-                    self.home_team().team_name,
-                ));
+                ingest_logs.warn(
+                    TaxaIngestErrorCode::TeamMismatch,
+                    format!(
+                        "Winning team name from event text ({}) did not match stored home team name ({})",
+                        winning_team.name,
+                        self.home_team().team_name,
+                    ),
+                );
             }
             if self.away_team().team_emoji != losing_team.emoji {
-                ingest_logs.warn(format!(
-                    "Losing team emoji from event text ({}) did not match stored away team emoji ({})",
-                    losing_team.emoji,
-                    self.away_team().team_emoji,
-                ));
+                ingest_logs.warn(
+                    TaxaIngestErrorCode::TeamMismatch,
+                    format!(
+                        "Losing team emoji from event text ({}) did not match stored away team emoji ({})",
+                        losing_team.emoji,
+                        self.away_team().team_emoji,
+                    ),
+                );
             }
             if self.away_team().team_name != losing_team.name {
-                ingest_logs.warn(format!(
-                    "Losing team name from event text ({}) did not match stored away team name ({})",
-                    losing_team.name,
-                    self.away_team().team_name,
-                ));
+                ingest_logs.warn(
+                    TaxaIngestErrorCode::TeamMismatch,
+                    format!(
+                        "Losing team name from event text ({}) did not match stored away team name ({})",
+                        losing_team.name,
+                        self.away_team().team_name,
+                    ),
+                );
             }
 
             *self.home_team_earned_coins.get_or_insert(0) += winning_team_income as i32;
@@ -2688,32 +2893,44 @@ impl<'g> Game<'g> {
         } else {
             ingest_logs.info("Winning team should be the away team");
             if self.away_team().team_emoji != winning_team.emoji {
-                ingest_logs.warn(format!(
-                    "Winning team emoji from event text ({}) did not match stored away team emoji ({})",
-                    winning_team.emoji,
-                    self.away_team().team_emoji,
-                ));
+                ingest_logs.warn(
+                    TaxaIngestErrorCode::TeamMismatch,
+                    format!(
+                        "Winning team emoji from event text ({}) did not match stored away team emoji ({})",
+                        winning_team.emoji,
+                        self.away_team().team_emoji,
+                    ),
+                );
             }
             if self.away_team().team_name != winning_team.name {
-                ingest_logs.warn(format!(
-                    "Winning team name from event text ({}) did not match stored away team name ({})",
-                    winning_team.name,
-                    self.away_team().team_name,
-                ));
+                ingest_logs.warn(
+                    TaxaIngestErrorCode::TeamMismatch,
+                    format!(
+                        "Winning team name from event text ({}) did not match stored away team name ({})",
+                        winning_team.name,
+                        self.away_team().team_name,
+                    ),
+                );
             }
             if self.home_team().team_emoji != losing_team.emoji {
-                ingest_logs.warn(format!(
-                    "Losing team emoji from event text ({}) did not match stored home team emoji ({})",
-                    losing_team.emoji,
-                    self.home_team().team_emoji,
-                ));
+                ingest_logs.warn(
+                    TaxaIngestErrorCode::TeamMismatch,
+                    format!(
+                        "Losing team emoji from event text ({}) did not match stored home team emoji ({})",
+                        losing_team.emoji,
+                        self.home_team().team_emoji,
+                    ),
+                );
             }
             if self.home_team().team_name != losing_team.name {
-                ingest_logs.warn(format!(
-                    "Losing team name from event text ({}) did not match stored home team name ({})",
-                    losing_team.name,
-                    self.home_team().team_name,
-                ));
+                ingest_logs.warn(
+                    TaxaIngestErrorCode::TeamMismatch,
+                    format!(
+                        "Losing team name from event text ({}) did not match stored home team name ({})",
+                        losing_team.name,
+                        self.home_team().team_name,
+                    ),
+                );
             }
 
             *self.away_team_earned_coins.get_or_insert(0) += winning_team_income as i32;
@@ -2738,9 +2955,10 @@ impl<'g> Game<'g> {
             .collect();
 
         if let Some(player) = double_trouble {
-            ingest_logs.error(format!(
-                "Double trouble player {player} didn't match any fielder"
-            ));
+            ingest_logs.error(
+                TaxaIngestErrorCode::FielderMismatch,
+                format!("Double trouble player {player} didn't match any fielder"),
+            );
         }
 
         fielders_with_double_trouble
@@ -2768,19 +2986,25 @@ impl<'g> Game<'g> {
                 Err(err) => {
                     let candidates = err.collect_vec();
                     if let Some((i, runner)) = candidates.first() {
-                        ingest_logs.warn(format!(
-                            "There are {} runners named {}, and we can't tell which was assassinated. \
-                            Arbitrarily assuming it was the one on {}.",
-                            candidates.len(),
-                            assassination.victim_name,
-                            runner.base,
-                        ));
+                        ingest_logs.warn(
+                            TaxaIngestErrorCode::AmbiguousEvent,
+                            format!(
+                                "There are {} runners named {}, and we can't tell which was assassinated. \
+                                Arbitrarily assuming it was the one on {}.",
+                                candidates.len(),
+                                assassination.victim_name,
+                                runner.base,
+                            ),
+                        );
                         self.state.runners_on.remove(*i);
                     } else {
-                        ingest_logs.warn(format!(
-                            "A runner named {} was assassinated, but there are no runners with that name on base.",
-                            assassination.victim_name,
-                        ));
+                        ingest_logs.warn(
+                            TaxaIngestErrorCode::BaserunnerMismatch,
+                            format!(
+                                "A runner named {} was assassinated, but there are no runners with that name on base.",
+                                assassination.victim_name,
+                            ),
+                        );
                     }
                 }
             }
@@ -2801,19 +3025,25 @@ impl<'g> Game<'g> {
                 Err(err) => {
                     let candidates = err.collect_vec();
                     if let Some((i, runner)) = candidates.first() {
-                        ingest_logs.warn(format!(
-                            "There are {} runners named {}, and we can't tell which was silently assassinated. \
-                            Arbitrarily assuming it was the one on {}.",
-                            candidates.len(),
-                            victim_name,
-                            runner.base,
-                        ));
+                        ingest_logs.warn(
+                            TaxaIngestErrorCode::AmbiguousEvent,
+                            format!(
+                                "There are {} runners named {}, and we can't tell which was silently assassinated. \
+                                Arbitrarily assuming it was the one on {}.",
+                                candidates.len(),
+                                victim_name,
+                                runner.base,
+                            ),
+                        );
                         self.state.runners_on.remove(*i);
                     } else {
-                        ingest_logs.warn(format!(
-                            "A runner named {} was silently assassinated, but there are no runners with that name on base.",
-                            victim_name,
-                        ));
+                        ingest_logs.warn(
+                            TaxaIngestErrorCode::BaserunnerMismatch,
+                            format!(
+                                "A runner named {} was silently assassinated, but there are no runners with that name on base.",
+                                victim_name,
+                            ),
+                        );
                     }
                 }
             }
@@ -2834,6 +3064,10 @@ impl<'g> Game<'g> {
         self.handle_season_3_missing_now_batting_after_mound_visit(raw_event, ingest_logs)?;
         self.handle_season_3_duplicate_now_batting(event, ingest_logs)?;
 
+        // The fielders the event happened against, before the event itself
+        // (a defensive substitution, say) changes them
+        let defense_before_event = self.defending_team().defense();
+
         let detail_builder = self
             .detail_builder(self.state.clone(), game_event_index, raw_event)
             .home_run_distance(raw_event.home_run_distance);
@@ -2850,10 +3084,13 @@ impl<'g> Game<'g> {
                     automatic_runner,
                 } => {
                     if *side != self.state.inning_half.flip() {
-                        ingest_logs.warn(format!(
-                            "Unexpected inning side: expected {:?}, but saw {side:?}",
-                            self.state.inning_half.flip(),
-                        ));
+                        ingest_logs.warn(
+                            TaxaIngestErrorCode::InningMismatch,
+                            format!(
+                                "Unexpected inning side: expected {:?}, but saw {side:?}",
+                                self.state.inning_half.flip(),
+                            ),
+                        );
                     }
                     self.state.inning_half = *side;
 
@@ -2864,11 +3101,13 @@ impl<'g> Game<'g> {
                     };
 
                     if *number != expected_number {
-                        ingest_logs.warn(format!(
-                            "Unexpected inning number: expected {}, but saw {}",
-                            expected_number,
-                            number,
-                        ));
+                        ingest_logs.warn(
+                            TaxaIngestErrorCode::InningMismatch,
+                            format!(
+                                "Unexpected inning number: expected {}, but saw {}",
+                                expected_number, number,
+                            ),
+                        );
                     }
                     self.state.inning_number = *number;
 
@@ -2938,11 +3177,14 @@ impl<'g> Game<'g> {
                                     // Recompose can't have changed the player's slot
                                     defending_team.active_pitcher.name = name;
                                 } else {
-                                    ingest_logs.warn(format!(
-                                        "Returning pitcher name {} does not match stored pitcher \
-                                        name {}.",
-                                        name, defending_team.active_pitcher.name,
-                                    ));
+                                    ingest_logs.warn(
+                                        TaxaIngestErrorCode::PlayerMismatch,
+                                        format!(
+                                            "Returning pitcher name {} does not match stored pitcher \
+                                            name {}.",
+                                            name, defending_team.active_pitcher.name,
+                                        ),
+                                    );
                                 }
                             }
                             None
@@ -2961,11 +3203,15 @@ impl<'g> Game<'g> {
                                         leaving_pitcher.name, defending_team.active_pitcher.name,
                                     ));
                                 }  else {
-                                    ingest_logs.warn(format!(
-                                        "Leaving pitcher name {} does not match stored pitcher \
+                                    ingest_logs.warn(
+                                        TaxaIngestErrorCode::PlayerMismatch,
+                                        format!(
+                                            "Leaving pitcher name {} does not match stored pitcher \
                                         name {}",
-                                        leaving_pitcher.name, defending_team.active_pitcher.name,
-                                    ));
+                                            leaving_pitcher.name,
+                                            defending_team.active_pitcher.name,
+                                        ),
+                                    );
                                 }
                             }
                             self.defending_team_mut().active_pitcher = (*arriving_pitcher).into();
@@ -2986,11 +3232,14 @@ impl<'g> Game<'g> {
                         }
                         Some(StartOfInningPitcher::Flooded { swept_pitcher_name, incoming_pitcher_name, preemption }) => {
                             if *swept_pitcher_name != self.defending_team().active_pitcher.name {
-                                ingest_logs.warn(format!(
-                                    "Pitcher who got swept away, {}, was not the active pitcher, {}",
-                                    swept_pitcher_name,
-                                    self.defending_team().active_pitcher,
-                                ));
+                                ingest_logs.warn(
+                                    TaxaIngestErrorCode::PlayerMismatch,
+                                    format!(
+                                        "Pitcher who got swept away, {}, was not the active pitcher, {}",
+                                        swept_pitcher_name,
+                                        self.defending_team().active_pitcher,
+                                    ),
+                                );
                             }
 
                             if let Some(BasicPitcherSwap { leaving_pitcher, arriving_pitcher}) = preemption {
@@ -3002,13 +3251,16 @@ impl<'g> Game<'g> {
                                 ));
 
                                 if &leaving_pitcher.name != swept_pitcher_name {
-                                    ingest_logs.warn(format!(
-                                        "Leaving pitcher {} did not match the swept pitcher, {}. This may mean a MMOLB \
-                                        bug was fixed, and MMOLDB needs to be updated to handle the fix. In the \
-                                        meantime, the wrong pitcher may return next inning.",
-                                        leaving_pitcher,
-                                        swept_pitcher_name,
-                                    ));
+                                    ingest_logs.warn(
+                                        TaxaIngestErrorCode::PlayerMismatch,
+                                        format!(
+                                            "Leaving pitcher {} did not match the swept pitcher, {}. This may mean a MMOLB \
+                                            bug was fixed, and MMOLDB needs to be updated to handle the fix. In the \
+                                            meantime, the wrong pitcher may return next inning.",
+                                            leaving_pitcher,
+                                            swept_pitcher_name,
+                                        ),
+                                    );
                                 }
 
                                 // As of mid-s14, when a pitcher is removed from the mound by the manager and swept
@@ -3043,12 +3295,15 @@ impl<'g> Game<'g> {
                                 );
 
                                 if let Some(currently_swept_pitcher_name) = self.defending_team().currently_swept_pitcher_name {
-                                    ingest_logs.error(format!(
-                                        "There was already an active pitcher swept away ({}) when \
-                                        this pitcher ({}) was swept. They will be overwritten.",
-                                        currently_swept_pitcher_name,
-                                        self.defending_team().active_pitcher,
-                                    ));
+                                    ingest_logs.error(
+                                        TaxaIngestErrorCode::PlayerMismatch,
+                                        format!(
+                                            "There was already an active pitcher swept away ({}) when \
+                                            this pitcher ({}) was swept. They will be overwritten.",
+                                            currently_swept_pitcher_name,
+                                            self.defending_team().active_pitcher,
+                                        ),
+                                    );
                                 }
 
                                 self.defending_team_mut().currently_swept_pitcher_name = Some(swept_pitcher_name);
@@ -3094,8 +3349,9 @@ impl<'g> Game<'g> {
                             // 0 or 1, I can't remember.
                             if self.season > 1 {
                                 ingest_logs.warn(
+                                    TaxaIngestErrorCode::UnexpectedEvent,
                                     "Adding an inferred automatic runner after season 1. This is \
-                                    probably incorrect."
+                                    probably incorrect.",
                                 );
                             }
                             stored_automatic_runner
@@ -3144,9 +3400,15 @@ impl<'g> Game<'g> {
 
                 if !is_pitchless_pitch(event.discriminant()) {
                     if raw_event.pitch.is_none() {
-                        ingest_logs.error("Event is missing a pitch");
+                        ingest_logs.error(
+                            TaxaIngestErrorCode::UnexpectedEvent,
+                            "Event is missing a pitch",
+                        );
                     } else if pitch.is_none() {
-                        ingest_logs.error("Pitch type wasn't recognized");
+                        ingest_logs.error(
+                            TaxaIngestErrorCode::UnrecognizedValue,
+                            "Pitch type wasn't recognized",
+                        );
                     }
                 }
 
@@ -3230,11 +3492,14 @@ impl<'g> Game<'g> {
 
                         self.check_batter(batter_name, batter, ingest_logs);
                         if self.state.count_strikes < 2 {
-                            ingest_logs.warn(format!(
-                                "Unexpected strikeout: expected 2 strikes in the count, but \
+                            ingest_logs.warn(
+                                TaxaIngestErrorCode::CountMismatch,
+                                format!(
+                                    "Unexpected strikeout: expected 2 strikes in the count, but \
                                 there were {}",
-                                self.state.count_strikes,
-                            ));
+                                    self.state.count_strikes,
+                                ),
+                            );
                         }
 
                         self.update_runners_steals_only(game_event_index, false, steals, ingest_logs);
@@ -3246,13 +3511,17 @@ impl<'g> Game<'g> {
                             (None, StrikeType::Swinging) => { TaxaEventType::SwingingStrikeout }
                             (Some(FoulType::Ball), _) => {
                                 ingest_logs.error(
+                                    TaxaIngestErrorCode::UnexpectedEvent,
                                     "Can't strike out on a foul ball. \
                                     Recording this as a foul tip instead.",
                                 );
                                 TaxaEventType::FoulTipStrikeout
                             }
                             (Some(FoulType::Tip), StrikeType::Looking) => {
-                                ingest_logs.warn("Can't have a foul tip on a called strike.");
+                                ingest_logs.warn(
+                                    TaxaIngestErrorCode::UnexpectedEvent,
+                                    "Can't have a foul tip on a called strike.",
+                                );
                                 TaxaEventType::FoulTipStrikeout
                             }
                             (Some(FoulType::Tip), StrikeType::Swinging) => { TaxaEventType::FoulTipStrikeout }
@@ -3338,7 +3607,13 @@ impl<'g> Game<'g> {
 
                         let extra_assassinations = assassinations.collect::<Vec<_>>();
                         if !extra_assassinations.is_empty() {
-                            ingest_logs.error(format!("Fair ball assassination(s) not found: {:?}", extra_assassinations));
+                            ingest_logs.error(
+                                TaxaIngestErrorCode::PlayerMismatch,
+                                format!(
+                                    "Fair ball assassination(s) not found: {:?}",
+                                    extra_assassinations
+                                ),
+                            );
                         }
 
                         self.state.context = EventContext::ExpectFairBallOutcome(batter, FairBall {
@@ -3490,13 +3765,16 @@ impl<'g> Game<'g> {
                                     // Then the protected player is the pitcher and the unprotected player is the batter
                                     (None, Some(*durability_loss as i32))
                                 } else {
-                                    ingest_logs.error(format!(
-                                        "Party durability loss could not match players. Expected \
-                                        {protected_player_name} and {unprotected_player_name} to \
-                                        match {} and {} or vice versa.",
-                                        batter_name,
-                                        self.defending_team().active_pitcher.name,
-                                    ));
+                                    ingest_logs.error(
+                                        TaxaIngestErrorCode::PlayerMismatch,
+                                        format!(
+                                            "Party durability loss could not match players. Expected \
+                                            {protected_player_name} and {unprotected_player_name} to \
+                                            match {} and {} or vice versa.",
+                                            batter_name,
+                                            self.defending_team().active_pitcher.name,
+                                        ),
+                                    );
                                     (None, None)
                                 }
                             }
@@ -3521,10 +3799,14 @@ impl<'g> Game<'g> {
                         self.check_batter(batter_name, event_batter_name, ingest_logs);
 
                         if pitcher_name != &self.defending_team().active_pitcher.name {
-                            ingest_logs.warn(format!(
-                                "Unexpected pitcher name: Expected {}, but saw {}",
-                                self.defending_team().active_pitcher.name, pitcher_name,
-                            ));
+                            ingest_logs.warn(
+                                TaxaIngestErrorCode::PlayerMismatch,
+                                format!(
+                                    "Unexpected pitcher name: Expected {}, but saw {}",
+                                    self.defending_team().active_pitcher.name,
+                                    pitcher_name,
+                                ),
+                            );
                         }
 
                         ingest_logs.info(format!(
@@ -3572,6 +3854,7 @@ impl<'g> Game<'g> {
                     }
 
                     check_now_batting_stats(&stats, self.batter_stats_mut(batter), ingest_logs);
+                    self.current_plate_appearance = CurrentPlateAppearance::default();
 
                     self.state.context = EventContext::ExpectPitch {
                         batter_name: batter,
@@ -3725,12 +4008,18 @@ impl<'g> Game<'g> {
                     self.check_fair_ball_destination(&fair_ball, *destination, ingest_logs);
 
                     if *grand_slam && scores.len() != 3 {
-                        ingest_logs.warn(format!(
-                            "Parsed a grand slam, but {} non-batter runners scored (expected 3)",
-                            scores.len(),
-                        ));
+                        ingest_logs.warn(
+                            TaxaIngestErrorCode::ScoreMismatch,
+                            format!(
+                                "Parsed a grand slam, but {} non-batter runners scored (expected 3)",
+                                scores.len(),
+                            ),
+                        );
                     } else if !*grand_slam && scores.len() == 3 {
-                        ingest_logs.warn("3 non-batter scored but we didn't parse a grand slam");
+                        ingest_logs.warn(
+                            TaxaIngestErrorCode::ScoreMismatch,
+                            "3 non-batter scored but we didn't parse a grand slam",
+                        );
                     }
 
                     // This is the one situation where you can have
@@ -3920,10 +4209,10 @@ impl<'g> Game<'g> {
 
                             if let Some((listed_fielder,)) = fielders.iter().collect_tuple() {
                                 if listed_fielder.name != *fielder {
-                                    ingest_logs.warn(format!("Fielder who made the error ({}) is not the one listed as fielding the ball ({})", fielder, listed_fielder.name));
+                                    ingest_logs.warn(TaxaIngestErrorCode::FielderMismatch, format!("Fielder who made the error ({}) is not the one listed as fielding the ball ({})", fielder, listed_fielder.name));
                                 }
                             } else {
-                                ingest_logs.warn("Expected exactly one listed fielder in a fielder's choice with an error");
+                                ingest_logs.warn(TaxaIngestErrorCode::FielderMismatch, "Expected exactly one listed fielder in a fielder's choice with an error");
                             }
                             self.handle_ejection(ejection, ingest_logs);
 
@@ -3950,11 +4239,14 @@ impl<'g> Game<'g> {
                     // adds an out, even though no runner actually got out. It's only
                     // been observed with no runners on base.
                     if !self.state.runners_on.is_empty() {
-                        ingest_logs.warn(format!(
-                            "Observed FirstBasemanChoosesAGhost bug with runners {:?}. \
+                        ingest_logs.warn(
+                            TaxaIngestErrorCode::UnexpectedEvent,
+                            format!(
+                                "Observed FirstBasemanChoosesAGhost bug with runners {:?}. \
                             This bug is only expected when there are no runners.",
-                            self.state.runners_on,
-                        ));
+                                self.state.runners_on,
+                            ),
+                        );
                     }
 
                     self.add_outs(1);
@@ -4002,20 +4294,24 @@ impl<'g> Game<'g> {
 
                     if let Some(player_name) = deflection {
                         if *player_name != falling_star_hit_player {
-                            ingest_logs.warn(format!(
-                                "Expected a falling star to be deflected off {}, but it was \
+                            ingest_logs.warn(
+                                TaxaIngestErrorCode::PlayerMismatch,
+                                format!(
+                                    "Expected a falling star to be deflected off {}, but it was \
                                 deflected off {}",
-                                falling_star_hit_player,
-                                player_name,
-                            ));
+                                    falling_star_hit_player, player_name,
+                                ),
+                            );
                         }
                     } else {
                         if player_name != falling_star_hit_player {
-                            ingest_logs.warn(format!(
-                                "Expected a falling star to hit {}, but it hit {}",
-                                falling_star_hit_player,
-                                player_name,
-                            ));
+                            ingest_logs.warn(
+                                TaxaIngestErrorCode::PlayerMismatch,
+                                format!(
+                                    "Expected a falling star to hit {}, but it hit {}",
+                                    falling_star_hit_player, player_name,
+                                ),
+                            );
                         }
                     }
 
@@ -4036,21 +4332,25 @@ impl<'g> Game<'g> {
                 [ParsedEventMessageDiscriminants::WeatherWither]
                 ParsedEventMessage::WeatherWither { team_emoji, player, corrupted, contained } => {
                     if *team_emoji != struggle.team_emoji {
-                        ingest_logs.warn(format!(
-                            "Wither outcome had a different team emoji to the wither struggle. \
+                        ingest_logs.warn(
+                            TaxaIngestErrorCode::TeamMismatch,
+                            format!(
+                                "Wither outcome had a different team emoji to the wither struggle. \
                             Outcome had {}, struggle had {}",
-                            team_emoji,
-                            struggle.team_emoji,
-                        ));
+                                team_emoji, struggle.team_emoji,
+                            ),
+                        );
                     }
 
                     if *player != struggle.target {
-                        ingest_logs.warn(format!(
-                            "Wither outcome had a different target to the wither struggle. \
+                        ingest_logs.warn(
+                            TaxaIngestErrorCode::PlayerMismatch,
+                            format!(
+                                "Wither outcome had a different target to the wither struggle. \
                             Outcome had {}, struggle had {}",
-                            player,
-                            struggle.target,
-                        ));
+                                player, struggle.target,
+                            ),
+                        );
                     }
 
                     let (contain_attempted, contain_replacement_player_name) = match contained {
@@ -4058,12 +4358,15 @@ impl<'g> Game<'g> {
                         ContainResult::SuccessfulContain { replacement_player_name, contained_player_name } => {
                             if let Some(source_name) = struggle.source_name {
                                 if source_name != *contained_player_name {
-                                    ingest_logs.warn(format!(
-                                        "Contained player did not match the corruption spreader. \
-                                        Contained {}, spreader was {}",
-                                        contained_player_name,
-                                        source_name,
-                                    ));
+                                    ingest_logs.warn(
+                                        TaxaIngestErrorCode::PlayerMismatch,
+                                        format!(
+                                            "Contained player did not match the corruption spreader. \
+                                            Contained {}, spreader was {}",
+                                            contained_player_name,
+                                            source_name,
+                                        ),
+                                    );
                                 }
                             }
 
@@ -4134,12 +4437,14 @@ impl<'g> Game<'g> {
                                 // Names are kinda confusing because the *corruption* source is
                                 // also the *contain* target
                                 if source_name != *target_player_name {
-                                    ingest_logs.warn(format!(
-                                        "Contain target did not match the corruption spreader. \
+                                    ingest_logs.warn(
+                                        TaxaIngestErrorCode::PlayerMismatch,
+                                        format!(
+                                            "Contain target did not match the corruption spreader. \
                                         Contain was attempted on {}, spreader was {}",
-                                        target_player_name,
-                                        source_name,
-                                    ));
+                                            target_player_name, source_name,
+                                        ),
+                                    );
                                 }
                             }
 
@@ -4172,17 +4477,23 @@ impl<'g> Game<'g> {
                 [ParsedEventMessageDiscriminants::InningEnd]
                 ParsedEventMessage::InningEnd { number, side } => {
                     if *number != self.state.inning_number {
-                        ingest_logs.warn(format!(
-                            "Unexpected inning number: expected {}, but saw {number}",
-                            self.state.inning_number,
-                        ));
+                        ingest_logs.warn(
+                            TaxaIngestErrorCode::InningMismatch,
+                            format!(
+                                "Unexpected inning number: expected {}, but saw {number}",
+                                self.state.inning_number,
+                            ),
+                        );
                     }
 
                     if *side != self.state.inning_half {
-                        ingest_logs.warn(format!(
-                            "Unexpected inning side: expected {:?}, but saw {side:?}",
-                            self.state.inning_half,
-                        ));
+                        ingest_logs.warn(
+                            TaxaIngestErrorCode::InningMismatch,
+                            format!(
+                                "Unexpected inning side: expected {:?}, but saw {side:?}",
+                                self.state.inning_half,
+                            ),
+                        );
                     }
 
                     // These get cleared at the end of a PA, but the PA doesn't end for an inning-
@@ -4293,11 +4604,13 @@ impl<'g> Game<'g> {
                             if let Day::Day(day) = self.day {  // day
                                 if (self.season, day) > (0, 2) {
                                     ingest_logs.warn(
+                                        TaxaIngestErrorCode::UnexpectedEvent,
                                         "Old-style <em>Game Over.</em> message appeared after s0d2",
                                     );
                                 }
                             } else {
                                 ingest_logs.warn(
+                                    TaxaIngestErrorCode::UnexpectedEvent,
                                     "Old-style <em>Game Over.</em> message appeared after s0d2",
                                 );
                             }
@@ -4307,6 +4620,7 @@ impl<'g> Game<'g> {
                             if let Day::Day(day) = self.day {  // day
                                 if (self.season, day) <= (0, 2) {
                                     ingest_logs.warn(
+                                        TaxaIngestErrorCode::UnexpectedEvent,
                                         "New-style <em>\"GAME OVER.\"</em> message appeared on or \
                                         before s0d2",
                                     );
@@ -4341,9 +4655,9 @@ impl<'g> Game<'g> {
                 [ParsedEventMessageDiscriminants::Recordkeeping]
                 ParsedEventMessage::Recordkeeping { winning_score, winning_team, losing_score, losing_team } => {
                     macro_rules! warn_if_mismatch {
-                        ($ingest_logs: expr, $winning_or_losing:expr, $comparison_description:expr, $home_or_away:expr, $actual:expr, $expected:expr $(,)?) => {
+                        ($ingest_logs: expr, $error_code:expr, $winning_or_losing:expr, $comparison_description:expr, $home_or_away:expr, $actual:expr, $expected:expr $(,)?) => {
                             if $actual != $expected {
-                                $ingest_logs.warn(format!(
+                                $ingest_logs.warn($error_code, format!(
                                     "Expected the {} {} to be {} ({} team) but it was {}",
                                     $winning_or_losing,
                                     $comparison_description,
@@ -4356,23 +4670,23 @@ impl<'g> Game<'g> {
                     }
 
                     if self.state.away_score < self.state.home_score {
-                        warn_if_mismatch!(ingest_logs, "winning", "score", "home", *winning_score, self.state.home_score);
-                        warn_if_mismatch!(ingest_logs, "winning", "team emoji", "home", winning_team.emoji, self.home.team_emoji);
-                        warn_if_mismatch!(ingest_logs, "winning", "team name", "home", winning_team.name, self.home.team_name);
+                        warn_if_mismatch!(ingest_logs, TaxaIngestErrorCode::ScoreMismatch, "winning", "score", "home", *winning_score, self.state.home_score);
+                        warn_if_mismatch!(ingest_logs, TaxaIngestErrorCode::TeamMismatch, "winning", "team emoji", "home", winning_team.emoji, self.home.team_emoji);
+                        warn_if_mismatch!(ingest_logs, TaxaIngestErrorCode::TeamMismatch, "winning", "team name", "home", winning_team.name, self.home.team_name);
 
-                        warn_if_mismatch!(ingest_logs, "losing", "score", "away", *losing_score, self.state.away_score);
-                        warn_if_mismatch!(ingest_logs, "losing", "team emoji", "away", losing_team.emoji, self.away.team_emoji);
-                        warn_if_mismatch!(ingest_logs, "losing", "team name", "away", losing_team.name, self.away.team_name);
+                        warn_if_mismatch!(ingest_logs, TaxaIngestErrorCode::ScoreMismatch, "losing", "score", "away", *losing_score, self.state.away_score);
+                        warn_if_mismatch!(ingest_logs, TaxaIngestErrorCode::TeamMismatch, "losing", "team emoji", "away", losing_team.emoji, self.away.team_emoji);
+                        warn_if_mismatch!(ingest_logs, TaxaIngestErrorCode::TeamMismatch, "losing", "team name", "away", losing_team.name, self.away.team_name);
                         self.home_team_final_score = Some(*winning_score as i32);
                         self.away_team_final_score = Some(*losing_score as i32);
                     } else {
-                        warn_if_mismatch!(ingest_logs, "winning", "score", "away", *winning_score, self.state.away_score);
-                        warn_if_mismatch!(ingest_logs, "winning", "team emoji", "away", winning_team.emoji, self.away.team_emoji);
-                        warn_if_mismatch!(ingest_logs, "winning", "team name", "away", winning_team.name, self.away.team_name);
+                        warn_if_mismatch!(ingest_logs, TaxaIngestErrorCode::ScoreMismatch, "winning", "score", "away", *winning_score, self.state.away_score);
+                        warn_if_mismatch!(ingest_logs, TaxaIngestErrorCode::TeamMismatch, "winning", "team emoji", "away", winning_team.emoji, self.away.team_emoji);
+                        warn_if_mismatch!(ingest_logs, TaxaIngestErrorCode::TeamMismatch, "winning", "team name", "away", winning_team.name, self.away.team_name);
 
-                        warn_if_mismatch!(ingest_logs, "losing", "score", "home", *losing_score, self.state.home_score);
-                        warn_if_mismatch!(ingest_logs, "losing", "team emoji", "home", losing_team.emoji, self.home.team_emoji);
-                        warn_if_mismatch!(ingest_logs, "losing", "team name", "home", losing_team.name, self.home.team_name);
+                        warn_if_mismatch!(ingest_logs, TaxaIngestErrorCode::ScoreMismatch, "losing", "score", "home", *losing_score, self.state.home_score);
+                        warn_if_mismatch!(ingest_logs, TaxaIngestErrorCode::TeamMismatch, "losing", "team emoji", "home", losing_team.emoji, self.home.team_emoji);
+                        warn_if_mismatch!(ingest_logs, TaxaIngestErrorCode::TeamMismatch, "losing", "team name", "home", losing_team.name, self.home.team_name);
                         self.home_team_final_score = Some(*losing_score as i32);
                         self.away_team_final_score = Some(*winning_score as i32);
                     }
@@ -4452,15 +4766,18 @@ impl<'g> Game<'g> {
                             self.away_team_photo_contest_score = Some(*winning_score as i32);
                             self.home_team_photo_contest_score = Some(*losing_score as i32);
                         } else {
-                            ingest_logs.warn(format!(
-                                "Couldn't match winning team {} {} and losing team {} {} with home \
-                                team {} {} and away team {} {}, so we can't record the photo \
-                                contest results for this game.",
-                                winning_team.emoji, winning_team.name,
-                                losing_team.emoji, losing_team.name,
-                                self.home.team_emoji, self.home.team_name,
-                                self.away.team_emoji, self.away.team_name,
-                            ));
+                            ingest_logs.warn(
+                                TaxaIngestErrorCode::TeamMismatch,
+                                format!(
+                                    "Couldn't match winning team {} {} and losing team {} {} with home \
+                                    team {} {} and away team {} {}, so we can't record the photo \
+                                    contest results for this game.",
+                                    winning_team.emoji, winning_team.name,
+                                    losing_team.emoji, losing_team.name,
+                                    self.home.team_emoji, self.home.team_name,
+                                    self.away.team_emoji, self.away.team_name,
+                                ),
+                            );
                         }
                     } else {
                         ingest_logs.info(format!(
@@ -4486,174 +4803,204 @@ impl<'g> Game<'g> {
                 ParsedEventMessage::WeatherSimulacrum { simulacrum_team, real_team, tokens_earnt } => {
                     if simulacrum_team.emoji == real_team.emoji &&
                         simulacrum_team.name == real_team.name {
-                        ingest_logs.warn(format!(
-                            "Can't tell who got the Simulacrum weather payout because \
+                        ingest_logs.warn(
+                            TaxaIngestErrorCode::AmbiguousEvent,
+                            format!(
+                                "Can't tell who got the Simulacrum weather payout because \
                             {simulacrum_team} and {real_team} have the same name and emoji. \
                             Recording it as nobody."
-                        ));
+                            ),
+                        );
                     } else if real_team.emoji == self.home.team_emoji &&
                         real_team.name == self.home.team_name &&
                         simulacrum_team.emoji == self.away.team_emoji &&
                         simulacrum_team.name == self.away.team_name  {
                         if self.home_team_earned_coins.is_some() {
-                            ingest_logs.warn(format!(
-                                "Somehow the {real_team} earned coins before the end-of-game event \
-                                during Simulacrum weather. The coins total will be incorrect.,\
-                            "));
+                            ingest_logs.warn(
+                                TaxaIngestErrorCode::UnexpectedEvent,
+                                format!(
+                                    "Somehow the {real_team} earned coins before the end-of-game event \
+                                    during Simulacrum weather. The coins total will be incorrect.,\
+                                "));
+                            }
+                            self.home_team_earned_coins = Some(*tokens_earnt as i32);
+                        } else if real_team.emoji == self.away.team_emoji &&
+                            real_team.name == self.away.team_name &&
+                            simulacrum_team.emoji == self.home.team_emoji &&
+                            simulacrum_team.name == self.home.team_name  {
+                            if self.home_team_earned_coins.is_some() {
+                                ingest_logs.warn(TaxaIngestErrorCode::UnexpectedEvent, format!(
+                                    "Somehow the {real_team} earned coins before the end-of-game event \
+                                    during Simulacrum weather. The coins total will be incorrect.,\
+                                "));
+                            }
+                            self.home_team_earned_coins = Some(*tokens_earnt as i32);
+                        } else {
+                            ingest_logs.warn(
+                                TaxaIngestErrorCode::AmbiguousEvent,
+                                format!(
+                                    "Can't tell who got the Simulacrum weather payout because \
+                                {simulacrum_team} and {real_team} do not match {} {} and {} {}. \
+                                Recording it as nobody.",
+                                    self.home.team_emoji,
+                                    self.home.team_name,
+                                    self.away.team_emoji,
+                                    self.away.team_name,
+                                ),
+                            );
                         }
-                        self.home_team_earned_coins = Some(*tokens_earnt as i32);
-                    } else if real_team.emoji == self.away.team_emoji &&
-                        real_team.name == self.away.team_name &&
-                        simulacrum_team.emoji == self.home.team_emoji &&
-                        simulacrum_team.name == self.home.team_name  {
-                        if self.home_team_earned_coins.is_some() {
-                            ingest_logs.warn(format!(
-                                "Somehow the {real_team} earned coins before the end-of-game event \
-                                during Simulacrum weather. The coins total will be incorrect.,\
-                            "));
+                        None
+                    },
+                    [ParsedEventMessageDiscriminants::WeatherSimulacrumOffseason]
+                    ParsedEventMessage::WeatherSimulacrumOffseason => {
+                        // Nothing to do for this event type
+                        None
+                    },
+                    [ParsedEventMessageDiscriminants::WeatherNoisy]
+                    ParsedEventMessage::WeatherNoisy { ump_team, player_team, tokens_earnt } => {
+                        if ump_team.emoji == player_team.emoji &&
+                            ump_team.name == player_team.name {
+                            ingest_logs.warn(
+                                TaxaIngestErrorCode::AmbiguousEvent,
+                                format!(
+                                    "Can't tell who got the Simulacrum weather payout because \
+                                {ump_team} and {player_team} have the same name and emoji. \
+                                Recording it as nobody."
+                                ),
+                            );
+                        } else if player_team.emoji == self.home.team_emoji &&
+                            player_team.name == self.home.team_name &&
+                            ump_team.emoji == self.away.team_emoji &&
+                            ump_team.name == self.away.team_name  {
+                            if self.home_team_earned_coins.is_some() {
+                                ingest_logs.warn(TaxaIngestErrorCode::UnexpectedEvent, format!(
+                                    "Somehow the {player_team} earned coins before the end-of-game event \
+                                    during Simulacrum weather. The coins total will be incorrect.,\
+                                "));
+                            }
+                            self.home_team_earned_coins = Some(*tokens_earnt as i32);
+                        } else if player_team.emoji == self.away.team_emoji &&
+                            player_team.name == self.away.team_name &&
+                            ump_team.emoji == self.home.team_emoji &&
+                            ump_team.name == self.home.team_name  {
+                            if self.home_team_earned_coins.is_some() {
+                                ingest_logs.warn(TaxaIngestErrorCode::UnexpectedEvent, format!(
+                                    "Somehow the {player_team} earned coins before the end-of-game event \
+                                    during Simulacrum weather. The coins total will be incorrect.,\
+                                "));
+                            }
+                            self.home_team_earned_coins = Some(*tokens_earnt as i32);
+                        } else {
+                            ingest_logs.warn(
+                                TaxaIngestErrorCode::AmbiguousEvent,
+                                format!(
+                                    "Can't tell who got the Simulacrum weather payout because \
+                                {ump_team} and {player_team} do not match {} {} and {} {}. \
+                                Recording it as nobody.",
+                                    self.home.team_emoji,
+                                    self.home.team_name,
+                                    self.away.team_emoji,
+                                    self.away.team_name,
+                                ),
+                            );
                         }
-                        self.home_team_earned_coins = Some(*tokens_earnt as i32);
-                    } else {
-                        ingest_logs.warn(format!(
-                            "Can't tell who got the Simulacrum weather payout because \
-                            {simulacrum_team} and {real_team} do not match {} {} and {} {}. \
-                            Recording it as nobody.",
-                            self.home.team_emoji, self.home.team_name,
-                            self.away.team_emoji, self.away.team_name,
-                        ));
+                        None
+                    },
+                    [ParsedEventMessageDiscriminants::EndGameIncome]
+                    ParsedEventMessage::EndGameIncome { winning_team, winning_team_income, losing_team, losing_team_income } => {
+                        self.handle_end_game_income(winning_team, *winning_team_income, losing_team, *losing_team_income, ingest_logs);
+                        None
+                    },
+                    [ParsedEventMessageDiscriminants::WeatherProsperityS13]
+                    ParsedEventMessage::WeatherProsperityS13 { winning_team, winning_team_income, losing_team, losing_team_income } => {
+                        self.handle_end_game_income(winning_team, *winning_team_income, losing_team, *losing_team_income, ingest_logs);
+                        None
+                    },
+                    // TODO see if there's a way to make the error message say which bug(s) we
+                    //   were looking for
+                    [ParsedEventMessageDiscriminants::KnownBug]
+                    ParsedEventMessage::KnownBug { bug: KnownBug::NoOneProspers } => {
+                        self.away_team_earned_coins.get_or_insert(0);
+                        self.home_team_earned_coins.get_or_insert(0);
+                        None
                     }
-                    None
-                },
-                [ParsedEventMessageDiscriminants::WeatherSimulacrumOffseason]
-                ParsedEventMessage::WeatherSimulacrumOffseason => {
-                    // Nothing to do for this event type
-                    None
-                },
-                [ParsedEventMessageDiscriminants::WeatherNoisy]
-                ParsedEventMessage::WeatherNoisy { ump_team, player_team, tokens_earnt } => {
-                    if ump_team.emoji == player_team.emoji &&
-                        ump_team.name == player_team.name {
-                        ingest_logs.warn(format!(
-                            "Can't tell who got the Simulacrum weather payout because \
-                            {ump_team} and {player_team} have the same name and emoji. \
-                            Recording it as nobody."
-                        ));
-                    } else if player_team.emoji == self.home.team_emoji &&
-                        player_team.name == self.home.team_name &&
-                        ump_team.emoji == self.away.team_emoji &&
-                        ump_team.name == self.away.team_name  {
-                        if self.home_team_earned_coins.is_some() {
-                            ingest_logs.warn(format!(
-                                "Somehow the {player_team} earned coins before the end-of-game event \
-                                during Simulacrum weather. The coins total will be incorrect.,\
-                            "));
-                        }
-                        self.home_team_earned_coins = Some(*tokens_earnt as i32);
-                    } else if player_team.emoji == self.away.team_emoji &&
-                        player_team.name == self.away.team_name &&
-                        ump_team.emoji == self.home.team_emoji &&
-                        ump_team.name == self.home.team_name  {
-                        if self.home_team_earned_coins.is_some() {
-                            ingest_logs.warn(format!(
-                                "Somehow the {player_team} earned coins before the end-of-game event \
-                                during Simulacrum weather. The coins total will be incorrect.,\
-                            "));
+                ),
+                EventContext::Finished => game_event!((previous_event, event)),
+                EventContext::ExpectConsumptionContest {
+                    first_game_event_index,
+                    batting_team_player: contest_batting_team_player,
+                    pitching_team_player: contest_pitching_team_player,
+                    emoji_food: contest_emoji_food,
+                    mut updates,
+                    context_after,
+                } => game_event!(
+                    (previous_event, event),
+                    [ParsedEventMessageDiscriminants::WeatherConsumption]
+                    ParsedEventMessage::WeatherConsumption(WeatherConsumptionEvents::Consumes {
+                        batting_team_player,
+                        batting_team_progress,
+                        pitching_team_player,
+                        pitching_team_progress,
+                        food_emoji,
+                        food,
+                        batting_team_score,
+                        pitching_team_score,
+                    }) => {
+                        if contest_batting_team_player.emoji != batting_team_player.emoji {
+                            ingest_logs.warn(
+                                TaxaIngestErrorCode::ContestMismatch,
+                                format!(
+                                    "Mismatch in batting team emoji: Contest had {}, event had {}",
+                                    contest_batting_team_player.emoji, batting_team_player.emoji,
+                                ),
+                            )
                         }
-                        self.home_team_earned_coins = Some(*tokens_earnt as i32);
-                    } else {
-                        ingest_logs.warn(format!(
-                            "Can't tell who got the Simulacrum weather payout because \
-                            {ump_team} and {player_team} do not match {} {} and {} {}. \
-                            Recording it as nobody.",
-                            self.home.team_emoji, self.home.team_name,
-                            self.away.team_emoji, self.away.team_name,
-                        ));
-                    }
-                    None
-                },
-                [ParsedEventMessageDiscriminants::EndGameIncome]
-                ParsedEventMessage::EndGameIncome { winning_team, winning_team_income, losing_team, losing_team_income } => {
-                    self.handle_end_game_income(winning_team, *winning_team_income, losing_team, *losing_team_income, ingest_logs);
-                    None
-                },
-                [ParsedEventMessageDiscriminants::WeatherProsperityS13]
-                ParsedEventMessage::WeatherProsperityS13 { winning_team, winning_team_income, losing_team, losing_team_income } => {
-                    self.handle_end_game_income(winning_team, *winning_team_income, losing_team, *losing_team_income, ingest_logs);
-                    None
-                },
-                // TODO see if there's a way to make the error message say which bug(s) we
-                //   were looking for
-                [ParsedEventMessageDiscriminants::KnownBug]
-                ParsedEventMessage::KnownBug { bug: KnownBug::NoOneProspers } => {
-                    self.away_team_earned_coins.get_or_insert(0);
-                    self.home_team_earned_coins.get_or_insert(0);
-                    None
-                }
-            ),
-            EventContext::Finished => game_event!((previous_event, event)),
-            EventContext::ExpectConsumptionContest {
-                first_game_event_index,
-                batting_team_player: contest_batting_team_player,
-                pitching_team_player: contest_pitching_team_player,
-                emoji_food: contest_emoji_food,
-                mut updates,
-                context_after,
-            } => game_event!(
-                (previous_event, event),
-                [ParsedEventMessageDiscriminants::WeatherConsumption]
-                ParsedEventMessage::WeatherConsumption(WeatherConsumptionEvents::Consumes {
-                    batting_team_player,
-                    batting_team_progress,
-                    pitching_team_player,
-                    pitching_team_progress,
-                    food_emoji,
-                    food,
-                    batting_team_score,
-                    pitching_team_score,
-                }) => {
-                    if contest_batting_team_player.emoji != batting_team_player.emoji {
-                        ingest_logs.warn(format!(
-                            "Mismatch in batting team emoji: Contest had {}, event had {}",
-                            contest_batting_team_player.emoji,
-                            batting_team_player.emoji,
-                        ))
-                    }
-                    if contest_batting_team_player.name != batting_team_player.name {
-                        ingest_logs.warn(format!(
-                            "Mismatch in batting team player name: Contest had {}, event had {}",
-                            contest_batting_team_player.name,
-                            batting_team_player.name,
-                        ))
+                        if contest_batting_team_player.name != batting_team_player.name {
+                            ingest_logs.warn(TaxaIngestErrorCode::ContestMismatch, format!(
+                                "Mismatch in batting team player name: Contest had {}, event had {}",
+                                contest_batting_team_player.name,
+                                batting_team_player.name,
+                                ),
+                            )
                     }
                     if contest_pitching_team_player.emoji != pitching_team_player.emoji {
-                        ingest_logs.warn(format!(
-                            "Mismatch in defending team emoji: Contest had {}, event had {}",
-                            contest_pitching_team_player.emoji,
-                            pitching_team_player.emoji,
-                        ))
+                        ingest_logs.warn(
+                            TaxaIngestErrorCode::ContestMismatch,
+                            format!(
+                                "Mismatch in defending team emoji: Contest had {}, event had {}",
+                                contest_pitching_team_player.emoji, pitching_team_player.emoji,
+                            ),
+                        )
                     }
                     if contest_pitching_team_player.name != pitching_team_player.name {
-                        ingest_logs.warn(format!(
-                            "Mismatch in defending team player name: Contest had {}, event had {}",
-                            contest_pitching_team_player.name,
-                            pitching_team_player.name,
-                        ))
+                        ingest_logs.warn(
+                            TaxaIngestErrorCode::ContestMismatch,
+                            format!(
+                                "Mismatch in defending team player name: Contest had {}, event had {}",
+                                contest_pitching_team_player.name,
+                                pitching_team_player.name,
+                            ),
+                        )
                     }
                     if let Some(food_emoji) = food_emoji {
                         if contest_emoji_food.food_emoji != *food_emoji {
-                            ingest_logs.warn(format!(
-                                "Mismatch in food emoji: Contest had {}, event had {}",
-                                contest_emoji_food.food_emoji,
-                                food_emoji,
-                            ))
+                            ingest_logs.warn(
+                                TaxaIngestErrorCode::ContestMismatch,
+                                format!(
+                                    "Mismatch in food emoji: Contest had {}, event had {}",
+                                    contest_emoji_food.food_emoji, food_emoji,
+                                ),
+                            )
                         }
                     }
                     if contest_emoji_food.food != *food {
-                        ingest_logs.warn(format!(
-                            "Mismatch in food: Contest had {}, event had {}",
-                            contest_emoji_food.food,
-                            food,
-                        ))
+                        ingest_logs.warn(
+                            TaxaIngestErrorCode::ContestMismatch,
+                            format!(
+                                "Mismatch in food: Contest had {}, event had {}",
+                                contest_emoji_food.food, food,
+                            ),
+                        )
                     }
 
                     let batting_team_score_before: u32 = updates.iter()
@@ -4664,18 +5011,24 @@ impl<'g> Game<'g> {
                         .sum();
 
                     if batting_team_score_before + batting_team_progress != *batting_team_score {
-                        ingest_logs.warn(format!(
-                            "Batting team score before ({batting_team_score_before}) plus batting \
-                            team progress ({batting_team_progress}) did not equal batting team \
-                            score after ({batting_team_score})",
-                        ));
+                        ingest_logs.warn(
+                            TaxaIngestErrorCode::ContestMismatch,
+                            format!(
+                                "Batting team score before ({batting_team_score_before}) plus batting \
+                                team progress ({batting_team_progress}) did not equal batting team \
+                                score after ({batting_team_score})",
+                            ),
+                        );
                     }
                     if pitching_team_score_before + pitching_team_progress != *pitching_team_score {
-                        ingest_logs.warn(format!(
-                            "Pitching team score before ({pitching_team_score_before}) plus \
+                        ingest_logs.warn(
+                            TaxaIngestErrorCode::ContestMismatch,
+                            format!(
+                                "Pitching team score before ({pitching_team_score_before}) plus \
                             pitching team progress ({pitching_team_progress}) did not equal \
                             pitching team score after ({pitching_team_score})",
-                        ));
+                            ),
+                        );
                     }
 
                     updates.push(ConsumptionContestEventForDb {
@@ -4711,19 +5064,23 @@ impl<'g> Game<'g> {
                 }) => {
                     if let Some(food_emoji) = food_emoji {
                         if contest_emoji_food.food_emoji != *food_emoji {
-                            ingest_logs.warn(format!(
-                                "Mismatch in food emoji: Contest had {}, event had {}",
-                                contest_emoji_food.food_emoji,
-                                food_emoji,
-                            ))
+                            ingest_logs.warn(
+                                TaxaIngestErrorCode::ContestMismatch,
+                                format!(
+                                    "Mismatch in food emoji: Contest had {}, event had {}",
+                                    contest_emoji_food.food_emoji, food_emoji,
+                                ),
+                            )
                         }
                     }
                     if contest_emoji_food.food != *food {
-                        ingest_logs.warn(format!(
-                            "Mismatch in food: Contest had {}, event had {}",
-                            contest_emoji_food.food,
-                            food,
-                        ))
+                        ingest_logs.warn(
+                            TaxaIngestErrorCode::ContestMismatch,
+                            format!(
+                                "Mismatch in food: Contest had {}, event had {}",
+                                contest_emoji_food.food, food,
+                            ),
+                        )
                     }
 
                     let batting_team_score: u32 = updates.iter()
@@ -4742,127 +5099,163 @@ impl<'g> Game<'g> {
                         winning_player.name == contest_pitching_team_player.name;
 
                     let outcome = if batting_team_matches_winner && pitching_team_matches_winner {
-                        ingest_logs.error(format!(
-                            "Can't tell which team won the contest. Both the batting team \
+                        ingest_logs.error(
+                            TaxaIngestErrorCode::ContestMismatch,
+                            format!(
+                                "Can't tell which team won the contest. Both the batting team \
                             ({contest_batting_team_player} with {batting_team_score}) and the \
                             pitching team ({contest_pitching_team_player} with \
                             {pitching_team_score}) match the winning player ({winning_player} with \
                             {winning_score})",
-                        ));
+                            ),
+                        );
                         None
                     } else if batting_team_matches_winner {
                         ingest_logs.info("Batting team won the contest");
 
                         if self.batting_team().team_emoji != winning_team.emoji {
-                            ingest_logs.error(format!(
-                                "We deduced that the batting team won, but the winning team's \
+                            ingest_logs.error(
+                                TaxaIngestErrorCode::ContestMismatch,
+                                format!(
+                                    "We deduced that the batting team won, but the winning team's \
                                 emoji ({}) did not match the current batting team's emoji \
                                 emoji ({})",
-                                winning_team.emoji,
-                                self.batting_team().team_emoji,
-                            ));
+                                    winning_team.emoji,
+                                    self.batting_team().team_emoji,
+                                ),
+                            );
                         }
 
                         if self.batting_team().team_name != winning_team.name {
-                            ingest_logs.error(format!(
-                                "We deduced that the batting team won, but the winning team's \
+                            ingest_logs.error(
+                                TaxaIngestErrorCode::ContestMismatch,
+                                format!(
+                                    "We deduced that the batting team won, but the winning team's \
                                 name ({}) did not match the current batting team's name \
                                 name ({})",
-                                winning_team.name,
-                                self.batting_team().team_name,
-                            ));
+                                    winning_team.name,
+                                    self.batting_team().team_name,
+                                ),
+                            );
                         }
 
                         if self.defending_team().team_emoji != losing_team.emoji {
-                            ingest_logs.error(format!(
-                                "We deduced that the defending team lost, but the losing team's \
-                                emoji ({}) did not match the current defending team's emoji \
-                                emoji ({})",
-                                losing_team.emoji,
-                                self.defending_team().team_emoji,
-                            ));
+                            ingest_logs.error(
+                                TaxaIngestErrorCode::ContestMismatch,
+                                format!(
+                                    "We deduced that the defending team lost, but the losing team's \
+                                    emoji ({}) did not match the current defending team's emoji \
+                                    emoji ({})",
+                                    losing_team.emoji,
+                                    self.defending_team().team_emoji,
+                                ),
+                            );
                         }
 
                         if self.defending_team().team_name != losing_team.name {
-                            ingest_logs.error(format!(
-                                "We deduced that the defending team lost, but the losing team's \
-                                name ({}) did not match the current defending team's name \
-                                name ({})",
-                                losing_team.name,
-                                self.defending_team().team_name,
-                            ));
+                            ingest_logs.error(
+                                TaxaIngestErrorCode::ContestMismatch,
+                                format!(
+                                    "We deduced that the defending team lost, but the losing team's \
+                                    name ({}) did not match the current defending team's name \
+                                    name ({})",
+                                    losing_team.name,
+                                    self.defending_team().team_name,
+                                ),
+                            );
                         }
                         Some((*winning_score, winning_tokens, Some(winning_prize), pitching_team_score, losing_tokens, None))
                     } else if pitching_team_matches_winner {
                         ingest_logs.info("Pitching team won the contest");
 
                         if self.defending_team().team_emoji != winning_team.emoji {
-                            ingest_logs.error(format!(
-                                "We deduced that the defending team won, but the winning team's \
-                                emoji ({}) did not match the current defending team's emoji \
-                                emoji ({})",
-                                winning_team.emoji,
-                                self.defending_team().team_emoji,
-                            ));
+                            ingest_logs.error(
+                                TaxaIngestErrorCode::ContestMismatch,
+                                format!(
+                                    "We deduced that the defending team won, but the winning team's \
+                                    emoji ({}) did not match the current defending team's emoji \
+                                    emoji ({})",
+                                    winning_team.emoji,
+                                    self.defending_team().team_emoji,
+                                ),
+                            );
                         }
 
                         if self.defending_team().team_name != winning_team.name {
-                            ingest_logs.error(format!(
-                                "We deduced that the defending team won, but the winning team's \
-                                name ({}) did not match the current defending team's name \
-                                name ({})",
-                                winning_team.name,
-                                self.defending_team().team_name,
-                            ));
+                            ingest_logs.error(
+                                TaxaIngestErrorCode::ContestMismatch,
+                                format!(
+                                    "We deduced that the defending team won, but the winning team's \
+                                    name ({}) did not match the current defending team's name \
+                                    name ({})",
+                                    winning_team.name,
+                                    self.defending_team().team_name,
+                                ),
+                            );
                         }
 
                         if self.batting_team().team_emoji != losing_team.emoji {
-                            ingest_logs.error(format!(
-                                "We deduced that the batting team lost, but the losing team's \
+                            ingest_logs.error(
+                                TaxaIngestErrorCode::ContestMismatch,
+                                format!(
+                                    "We deduced that the batting team lost, but the losing team's \
                                 emoji ({}) did not match the current batting team's emoji \
                                 emoji ({})",
-                                losing_team.emoji,
-                                self.batting_team().team_emoji,
-                            ));
+                                    losing_team.emoji,
+                                    self.batting_team().team_emoji,
+                                ),
+                            );
                         }
 
                         if self.batting_team().team_name != losing_team.name {
-                            ingest_logs.error(format!(
-                                "We deduced that the batting team lost, but the losing team's \
+                            ingest_logs.error(
+                                TaxaIngestErrorCode::ContestMismatch,
+                                format!(
+                                    "We deduced that the batting team lost, but the losing team's \
                                 name ({}) did not match the current batting team's name \
                                 name ({})",
-                                losing_team.name,
-                                self.batting_team().team_name,
-                            ));
+                                    losing_team.name,
+                                    self.batting_team().team_name,
+                                ),
+                            );
                         }
                         Some((batting_team_score, losing_tokens, None, *winning_score, winning_tokens, Some(winning_prize)))
                     } else {
-                        ingest_logs.error(format!(
-                            "Can't tell which team won the contest. Neither the batting team \
+                        ingest_logs.error(
+                            TaxaIngestErrorCode::ContestMismatch,
+                            format!(
+                                "Can't tell which team won the contest. Neither the batting team \
                             ({contest_batting_team_player} with {batting_team_score}) nor the \
                             pitching team ({contest_pitching_team_player} with \
                             {pitching_team_score}) match the winning player ({winning_player} with \
                             {winning_score})",
-                        ));
+                            ),
+                        );
                         None
                     };
 
                     if self.batting_team().team_emoji != contest_batting_team_player.emoji {
-                        ingest_logs.error(format!(
-                            "Batting team player's emoji ({}) did not match expected batting team \
-                            emoji ({})",
-                            contest_batting_team_player.emoji,
-                            self.batting_team().team_emoji,
-                        ));
+                        ingest_logs.error(
+                            TaxaIngestErrorCode::ContestMismatch,
+                            format!(
+                                "Batting team player's emoji ({}) did not match expected batting team \
+                                emoji ({})",
+                                contest_batting_team_player.emoji,
+                                self.batting_team().team_emoji,
+                            ),
+                        );
                     }
 
                     if self.defending_team().team_emoji != contest_pitching_team_player.emoji {
-                        ingest_logs.error(format!(
-                            "Defending team player's emoji ({}) did not match expected defending \
-                            team emoji ({})",
-                            contest_pitching_team_player.emoji,
-                            self.defending_team().team_emoji,
-                        ));
+                        ingest_logs.error(
+                            TaxaIngestErrorCode::ContestMismatch,
+                            format!(
+                                "Defending team player's emoji ({}) did not match expected defending \
+                                team emoji ({})",
+                                contest_pitching_team_player.emoji,
+                                self.defending_team().team_emoji,
+                            ),
+                        );
                     }
 
                     self.state.context = context_after.to_event_context();
@@ -4904,19 +5297,23 @@ impl<'g> Game<'g> {
                 }) => {
                     if let Some(food_emoji) = food_emoji {
                         if contest_emoji_food.food_emoji != *food_emoji {
-                            ingest_logs.warn(format!(
-                                "Mismatch in food emoji: Contest had {}, event had {}",
-                                contest_emoji_food.food_emoji,
-                                food_emoji,
-                            ))
+                            ingest_logs.warn(
+                                TaxaIngestErrorCode::ContestMismatch,
+                                format!(
+                                    "Mismatch in food emoji: Contest had {}, event had {}",
+                                    contest_emoji_food.food_emoji, food_emoji,
+                                ),
+                            )
                         }
                     }
                     if contest_emoji_food.food != *food {
-                        ingest_logs.warn(format!(
-                            "Mismatch in food: Contest had {}, event had {}",
-                            contest_emoji_food.food,
-                            food,
-                        ))
+                        ingest_logs.warn(
+                            TaxaIngestErrorCode::ContestMismatch,
+                            format!(
+                                "Mismatch in food: Contest had {}, event had {}",
+                                contest_emoji_food.food, food,
+                            ),
+                        )
                     }
 
                     let batting_team_score: u32 = updates.iter()
@@ -4927,73 +5324,97 @@ impl<'g> Game<'g> {
                         .sum();
 
                     if *final_score != batting_team_score {
-                        ingest_logs.error(format!(
-                            "The end contest message says the final score was a tie at \
+                        ingest_logs.error(
+                            TaxaIngestErrorCode::ContestMismatch,
+                            format!(
+                                "The end contest message says the final score was a tie at \
                             {final_score}, but we counted {batting_team_score} for the batting \
                             team",
-                        ));
+                            ),
+                        );
                     }
 
                     if *final_score != pitching_team_score {
-                        ingest_logs.error(format!(
-                            "The end contest message says the final score was a tie at \
+                        ingest_logs.error(
+                            TaxaIngestErrorCode::ContestMismatch,
+                            format!(
+                                "The end contest message says the final score was a tie at \
                             {final_score}, but we counted {pitching_team_score} for the defending \
                             team",
-                        ));
+                            ),
+                        );
                     }
 
                     if self.batting_team().team_emoji != batting_team.emoji {
-                        ingest_logs.error(format!(
-                            "Named batting team's emoji ({}) did not match the current batting \
+                        ingest_logs.error(
+                            TaxaIngestErrorCode::ContestMismatch,
+                            format!(
+                                "Named batting team's emoji ({}) did not match the current batting \
                             team's emoji emoji ({})",
-                            batting_team.emoji,
-                            self.batting_team().team_emoji,
-                        ));
+                                batting_team.emoji,
+                                self.batting_team().team_emoji,
+                            ),
+                        );
                     }
 
                     if self.batting_team().team_name != batting_team.name {
-                        ingest_logs.error(format!(
-                            "Named batting team's name ({}) did not match the current batting \
+                        ingest_logs.error(
+                            TaxaIngestErrorCode::ContestMismatch,
+                            format!(
+                                "Named batting team's name ({}) did not match the current batting \
                             team's name name ({})",
-                            batting_team.name,
-                            self.batting_team().team_name,
-                        ));
+                                batting_team.name,
+                                self.batting_team().team_name,
+                            ),
+                        );
                     }
 
                     if self.defending_team().team_emoji != pitching_team.emoji {
-                        ingest_logs.error(format!(
-                            "Named defending team's emoji ({}) did not match the current defending \
-                            team's emoji emoji ({})",
-                            pitching_team.emoji,
-                            self.defending_team().team_emoji,
-                        ));
+                        ingest_logs.error(
+                            TaxaIngestErrorCode::ContestMismatch,
+                            format!(
+                                "Named defending team's emoji ({}) did not match the current defending \
+                                team's emoji emoji ({})",
+                                pitching_team.emoji,
+                                self.defending_team().team_emoji,
+                            ),
+                        );
                     }
 
                     if self.defending_team().team_name != pitching_team.name {
-                        ingest_logs.error(format!(
-                            "Named defending team's name ({}) did not match the current defending \
-                            team's name name ({})",
-                            pitching_team.name,
-                            self.defending_team().team_name,
-                        ));
+                        ingest_logs.error(
+                            TaxaIngestErrorCode::ContestMismatch,
+                            format!(
+                                "Named defending team's name ({}) did not match the current defending \
+                                team's name name ({})",
+                                pitching_team.name,
+                                self.defending_team().team_name,
+                            ),
+                        );
                     }
 
                     if self.batting_team().team_emoji != contest_batting_team_player.emoji {
-                        ingest_logs.error(format!(
-                            "Batting team player's emoji ({}) did not match expected batting team \
-                            emoji ({})",
-                            contest_batting_team_player.emoji,
-                            self.batting_team().team_emoji,
-                        ));
+                        ingest_logs.error(
+                            TaxaIngestErrorCode::ContestMismatch,
+                            format!(
+                                "Batting team player's emoji ({}) did not match expected batting team \
+                                emoji ({})",
+                                contest_batting_team_player.emoji,
+                                self.batting_team().team_emoji,
+                            ),
+                        );
                     }
 
                     if self.defending_team().team_emoji != contest_pitching_team_player.emoji {
-                        ingest_logs.error(format!(
-                            "Defending team player's emoji ({}) did not match expected defending \
-                            team emoji ({})",
-                            contest_pitching_team_player.emoji,
-                            self.defending_team().team_emoji,
-                        ));
+                        ingest_logs.error(
+                            TaxaIngestErrorCode::ContestMismatch,
+                            format!(
+                                "Defending team player's emoji ({}) did not match expected defending \
+                                team emoji ({})",
+                                contest_pitching_team_player.emoji,
+                                self.defending_team().team_emoji,
+                            ),
+                        );
                     }
 
                     self.state.context = context_after.to_event_context();
@@ -5028,11 +5449,17 @@ impl<'g> Game<'g> {
         // may be wrong after the 3rd out.
         if self.state.outs >= 3 {
             if !self.state.runners_on.is_empty() {
-                ingest_logs.error("runners_on must be empty when there are 3 (or more) outs");
+                ingest_logs.error(
+                    TaxaIngestErrorCode::BaserunnerMismatch,
+                    "runners_on must be empty when there are 3 (or more) outs",
+                );
             }
         } else if self.state.game_finished {
             if !self.state.runners_on.is_empty() {
-                ingest_logs.error("runners_on must be empty when the game is over");
+                ingest_logs.error(
+                    TaxaIngestErrorCode::BaserunnerMismatch,
+                    "runners_on must be empty when the game is over",
+                );
             }
         } else {
             self.check_baserunner_consistency(raw_event, ingest_logs);
@@ -5040,11 +5467,42 @@ impl<'g> Game<'g> {
 
         if let Some(EventForTable::EventDetail(e)) = &result {
             self.last_game_event_index_with_event_detail = Some(e.game_event_index);
+            self.event_defenses.push(defense_before_event);
+            self.track_pitcher_workload(e.game_event_index, e.pitcher_name, e.detail_type);
         }
 
         Ok(result)
     }
 
+    fn track_pitcher_workload(
+        &mut self,
+        game_event_index: usize,
+        pitcher_name: &'g str,
+        detail_type: TaxaEventType,
+    ) {
+        // Balks are the only event details that aren't pitches
+        let is_pitch = detail_type != TaxaEventType::Balk;
+        let ends_plate_appearance = detail_type.as_insertable().ends_plate_appearance;
+
+        let mut current_plate_appearance = std::mem::take(&mut self.current_plate_appearance);
+        let workload = self
+            .defending_team_mut()
+            .pitcher_workloads
+            .entry(pitcher_name)
+            .or_default();
+        let (sequence_entry, plate_appearance) = current_plate_appearance.record_event(
+            workload,
+            pitcher_name,
+            game_event_index,
+            is_pitch,
+            ends_plate_appearance,
+        );
+        self.current_plate_appearance = current_plate_appearance;
+
+        self.pitch_sequence.extend(sequence_entry);
+        self.plate_appearance_workloads.extend(plate_appearance);
+    }
+
     fn handle_season_3_missing_now_batting_after_mound_visit(
         &mut self,
         raw_event: &'g mmolb_parsing::game::Event,
@@ -5056,9 +5514,12 @@ impl<'g> Game<'g> {
             match &raw_event.event {
                 Ok(mmolb_parsing::enums::EventType::Pitch) => {}
                 other => {
-                    ingest_logs.error(format!(
-                        "Expected a Pitch after bugged season 3 mound visit, but saw {other:?}",
-                    ));
+                    ingest_logs.error(
+                        TaxaIngestErrorCode::UnexpectedEvent,
+                        format!(
+                            "Expected a Pitch after bugged season 3 mound visit, but saw {other:?}",
+                        ),
+                    );
                 }
             }
 
@@ -5113,7 +5574,10 @@ impl<'g> Game<'g> {
 
         // Warn if this happens on a different day
         if !(self.season == 3 && self.day == Day::Day(5)) {
-            ingest_logs.warn("Saw a duplicate NowBatting event outside the expected day (s3d5)");
+            ingest_logs.warn(
+                TaxaIngestErrorCode::UnexpectedEvent,
+                "Saw a duplicate NowBatting event outside the expected day (s3d5)",
+            );
         }
 
         if *batter == batter_name {
@@ -5122,9 +5586,12 @@ impl<'g> Game<'g> {
             // Reset the context to expecting the NowBatting event again.
             self.state.context = EventContext::ExpectNowBatting;
         } else {
-            ingest_logs.error(format!(
-                "Duplicate NowBatting did not match: Expected {batter_name} but saw {batter}",
-            ));
+            ingest_logs.error(
+                TaxaIngestErrorCode::PlayerMismatch,
+                format!(
+                    "Duplicate NowBatting did not match: Expected {batter_name} but saw {batter}",
+                ),
+            );
         }
 
         Ok(())
@@ -5159,10 +5626,13 @@ impl<'g> Game<'g> {
                 ingest_logs.info("Hard-coded player replacement: Ido Barrington for Lena Vitale");
                 return "Ido Barrington";
             } else {
-                ingest_logs.error(format!(
-                    "MMOLB did not name {batter_name}'s replacement. This event requires manual \
-                    correction. In the meantime, we'll act as if {batter_name} was not replaced."
-                ));
+                ingest_logs.error(
+                    TaxaIngestErrorCode::UnexpectedEvent,
+                    format!(
+                        "MMOLB did not name {batter_name}'s replacement. This event requires manual \
+                        correction. In the meantime, we'll act as if {batter_name} was not replaced."
+                    ),
+                );
                 return batter_name;
             };
         };
@@ -5208,6 +5678,7 @@ fn check_now_batting_stats(
         NowBattingStats::FirstPA => {
             if !batter_stats.is_empty() {
                 ingest_logs.warn(
+                    TaxaIngestErrorCode::StatsMismatch,
                     "In NowBatting, expected this batter to have no stats in the current game",
                 );
             }
@@ -5217,27 +5688,36 @@ fn check_now_batting_stats(
 
             match their_stats.next() {
                 None => {
-                    ingest_logs.warn("This NowBatting event had stats, but the vec was empty");
+                    ingest_logs.warn(
+                        TaxaIngestErrorCode::StatsMismatch,
+                        "This NowBatting event had stats, but the vec was empty",
+                    );
                 }
                 Some(BatterStat::HitsForAtBats { hits, at_bats }) => {
                     if *hits != batter_stats.hits {
-                        ingest_logs.warn(format!(
-                            "NowBatting said player has {hits} hits, but our records say {}",
-                            batter_stats.hits
-                        ));
+                        ingest_logs.warn(
+                            TaxaIngestErrorCode::StatsMismatch,
+                            format!(
+                                "NowBatting said player has {hits} hits, but our records say {}",
+                                batter_stats.hits
+                            ),
+                        );
                     }
                     if *at_bats != batter_stats.at_bats {
-                        ingest_logs.warn(format!(
-                            "NowBatting said player has {at_bats} at bats, but our records say {}",
-                            batter_stats.at_bats
-                        ));
+                        ingest_logs.warn(
+                            TaxaIngestErrorCode::StatsMismatch,
+                            format!(
+                                "NowBatting said player has {at_bats} at bats, but our records say {}",
+                                batter_stats.at_bats
+                            ),
+                        );
                     }
                 }
                 Some(other) => {
-                    ingest_logs.warn(format!(
-                        "First item in stats was not HitsForAtBats {:?}",
-                        other
-                    ));
+                    ingest_logs.warn(
+                        TaxaIngestErrorCode::StatsMismatch,
+                        format!("First item in stats was not HitsForAtBats {:?}", other),
+                    );
                 }
             }
 
@@ -5246,10 +5726,10 @@ fn check_now_batting_stats(
             for zipped in their_stats.zip_longest(our_stats) {
                 match zipped {
                     EitherOrBoth::Left(theirs) => {
-                        ingest_logs.warn(format!(
-                            "NowBatting event had unexpected stat entry {:?}",
-                            theirs
-                        ));
+                        ingest_logs.warn(
+                            TaxaIngestErrorCode::StatsMismatch,
+                            format!("NowBatting event had unexpected stat entry {:?}", theirs),
+                        );
                     }
                     EitherOrBoth::Right(ours) => {
                         ingest_logs
@@ -5266,3 +5746,87 @@ fn check_now_batting_stats(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn balks_are_not_counted_as_pitches() {
+        let mut workload = PitcherWorkload::default();
+        let mut plate_appearance = CurrentPlateAppearance::default();
+
+        let (pitch, ended) = plate_appearance.record_event(&mut workload, "P", 0, true, false);
+        assert_eq!(pitch.map(|p| p.pitch_of_plate_appearance), Some(1));
+        assert!(ended.is_none());
+
+        let (pitch, ended) = plate_appearance.record_event(&mut workload, "P", 1, false, false);
+        assert!(pitch.is_none());
+        assert!(ended.is_none());
+
+        let (pitch, ended) = plate_appearance.record_event(&mut workload, "P", 2, true, true);
+        let pitch = pitch.unwrap();
+        assert_eq!(pitch.pitch_of_plate_appearance, 2);
+        assert_eq!(pitch.previous_pitch_game_event_index, Some(0));
+        assert_eq!(ended.unwrap().pitches, 2);
+        assert_eq!(workload.pitches, 2);
+        assert_eq!(workload.batters_faced, 1);
+    }
+
+    #[test]
+    fn workload_carries_across_plate_appearances() {
+        let mut workload = PitcherWorkload::default();
+        let mut plate_appearance = CurrentPlateAppearance::default();
+
+        let mut ended = Vec::new();
+        for batter in 0..10 {
+            let first = batter * 2;
+            plate_appearance.record_event(&mut workload, "P", first, true, false);
+            let (pitch, pa) =
+                plate_appearance.record_event(&mut workload, "P", first + 1, true, true);
+            assert_eq!(pitch.unwrap().previous_pitch_game_event_index, Some(first));
+            ended.extend(pa);
+        }
+
+        let last = ended.last().unwrap();
+        assert_eq!(ended.len(), 10);
+        assert_eq!(last.pitcher_batters_faced_before, 9);
+        assert_eq!(last.times_through_order, 2);
+        assert_eq!(last.pitcher_pitches_before, 18);
+        assert_eq!(last.pitches, 2);
+        assert_eq!(ended[8].times_through_order, 1);
+    }
+
+    #[test]
+    fn mid_plate_appearance_pitcher_change_is_credited_to_the_new_pitcher() {
+        let mut starter = PitcherWorkload {
+            batters_faced: 3,
+            pitches: 40,
+        };
+        let mut reliever = PitcherWorkload {
+            batters_faced: 12,
+            pitches: 7,
+        };
+        let mut plate_appearance = CurrentPlateAppearance::default();
+
+        plate_appearance.record_event(&mut starter, "Starter", 0, true, false);
+        plate_appearance.record_event(&mut starter, "Starter", 1, true, false);
+        let (pitch, ended) =
+            plate_appearance.record_event(&mut reliever, "Reliever", 3, true, true);
+        let ended = ended.unwrap();
+
+        // The sequence still spans the whole plate appearance
+        let pitch = pitch.unwrap();
+        assert_eq!(pitch.pitch_of_plate_appearance, 3);
+        assert_eq!(pitch.previous_pitch_game_event_index, Some(1));
+
+        assert_eq!(ended.pitcher_batters_faced_before, 12);
+        assert_eq!(ended.times_through_order, 2);
+        assert_eq!(ended.pitcher_pitches_before, 7);
+        assert_eq!(ended.pitches, 1);
+        assert_eq!(starter.pitches, 42);
+        assert_eq!(starter.batters_faced, 3);
+        assert_eq!(reliever.pitches, 8);
+        assert_eq!(reliever.batters_faced, 13);
+    }
+}
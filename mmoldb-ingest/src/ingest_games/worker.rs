@@ -1,16 +1,19 @@
-use crate::IngestFatalError;
+use crate::{IngestFatalError, ProgressPublisher};
+use crate::async_pool::PooledAsyncConnection;
+use crate::bulk_insert_gate::BulkInsertGate;
 use crate::ingest_games::sim::{EventForTable, Game, SimStartupError};
-use crate::ingest_games::{check_round_trip, sim};
+use crate::ingest_games::{check_round_trip, lenient, sim};
 use chron::ChronEntity;
 use chrono::Utc;
 use itertools::{Either, Itertools, izip};
 use miette::Context;
 use mmolb_parsing::enums::EventType;
-use mmoldb_db::db::{CompletedGameForDb, GameForDb};
-use mmoldb_db::taxa::Taxa;
-use mmoldb_db::{IngestLog, PgConnection, db};
-use serde::de::IntoDeserializer;
-use tracing::{debug, error, info};
+use mmoldb_db::db::{CompletedGameForDb, EventInsertMode, GameForDb, IngestProgressEvent};
+use mmoldb_db::taxa::{Taxa, TaxaIngestErrorCode};
+use mmoldb_db::db::RowToEventError;
+use mmoldb_db::{Connection, EventDetail, IngestLog, PgConnection, QueryError, db};
+use std::sync::Mutex;
+use tracing::{debug, error, info, warn};
 
 pub trait GameExt {
     /// Returns true for any game which will never be updated. This includes all
@@ -40,12 +43,19 @@ pub struct IngestStats {
     pub num_games_with_fatal_errors: usize,
     pub num_unsupported_games_skipped: usize,
     pub num_games_imported: usize,
+    /// How long the batch waited for other workers to finish inserting
+    pub db_insert_lock_wait_seconds: f64,
 }
 
 pub fn ingest_page_of_games(
     taxa: &Taxa,
     all_games_json: Vec<ChronEntity<serde_json::Value>>,
     conn: &mut PgConnection,
+    check_conns: &mut [PooledAsyncConnection],
+    db_insert_lock: &Mutex<()>,
+    bulk_inserts: &BulkInsertGate,
+    progress: &ProgressPublisher,
+    event_insert_mode: EventInsertMode,
     worker_id: usize,
 ) -> Result<IngestStats, IngestFatalError> {
     debug!(
@@ -57,15 +67,17 @@ pub fn ingest_page_of_games(
     let all_games = all_games_json
         .into_iter()
         .map(|game_json| {
-            let des = game_json.data.into_deserializer();
-            match serde_path_to_error::deserialize(des) {
-                Ok(data) => Either::Left(ChronEntity {
-                    kind: game_json.kind,
-                    entity_id: game_json.entity_id,
-                    valid_from: game_json.valid_from,
-                    valid_to: game_json.valid_to,
-                    data,
-                }),
+            match lenient::deserialize_leniently(game_json.data) {
+                Ok((data, set_aside)) => Either::Left((
+                    ChronEntity {
+                        kind: game_json.kind,
+                        entity_id: game_json.entity_id,
+                        valid_from: game_json.valid_from,
+                        valid_to: game_json.valid_to,
+                        data,
+                    },
+                    set_aside,
+                )),
                 Err(err) => Either::Right((err, game_json.entity_id, game_json.valid_from)),
             }
         })
@@ -84,7 +96,7 @@ pub fn ingest_page_of_games(
     let games_for_db = all_games
         .iter()
         .map(|result| match result {
-            Either::Left(game) => prepare_game_for_db(game),
+            Either::Left((game, _)) => prepare_game_for_db(game),
             Either::Right((err, entity_id, valid_from)) => Ok(GameForDb::DeserializeError {
                 game_id: entity_id,
                 from_version: *valid_from,
@@ -149,13 +161,64 @@ pub fn ingest_page_of_games(
     );
     let _parse_and_sim_duration = (Utc::now() - parse_and_sim_start).as_seconds_f64();
 
-    let db_insert_start = Utc::now();
-    let _db_insert_timings = db::insert_games(conn, taxa, &games_for_db)?;
+    let games_set_aside_values = all_games
+        .iter()
+        .filter_map(|result| match result {
+            Either::Left((game, Some(set_aside))) => Some((game, set_aside)),
+            _ => None,
+        })
+        .map(|(game, set_aside)| {
+            warn!(
+                "Set aside {} value(s) from game {} so the rest of it could be deserialized",
+                set_aside.values.len(),
+                game.entity_id,
+            );
+            let log_text = format!(
+                "Some values in this game couldn't be deserialized, so they were set aside in \
+                `data.games.extra` and the rest of the game was ingested:\n{}",
+                set_aside.errors.iter().map(|err| format!("- {err}")).join("\n"),
+            );
+            (
+                game.entity_id.as_str(),
+                serde_json::Value::Object(set_aside.values.clone()),
+                log_text,
+            )
+        })
+        .collect_vec();
+
+    // Only the insert holds the lock, so other workers can parse, sim, and
+    // check round trips while this one writes
+    let db_insert_lock_start = Utc::now();
+    let (db_insert_lock_wait_seconds, db_insert_timings) = {
+        // A poisoned lock just means another worker panicked mid-insert. That
+        // worker's transaction was rolled back, so it's safe to continue.
+        let _db_insert_guard = db_insert_lock
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let db_insert_lock_wait_seconds = (Utc::now() - db_insert_lock_start).as_seconds_f64();
+
+        let db_insert_start = Utc::now();
+        let _bulk_insert = bulk_inserts.begin();
+        // In one transaction, so a game is never saved without the values
+        // that were set aside from it
+        let timings = conn.transaction(|conn| {
+            let timings = db::insert_games(conn, taxa, &games_for_db, event_insert_mode)?;
+            if !games_set_aside_values.is_empty() {
+                db::record_games_set_aside_values(conn, taxa, &games_set_aside_values)?;
+            }
+            Ok::<_, QueryError>(timings)
+        })?;
+        let _db_insert_duration = (Utc::now() - db_insert_start).as_seconds_f64();
+        (db_insert_lock_wait_seconds, timings)
+    };
     debug!(
-        "Inserted {} games on worker {worker_id}",
-        games_for_db.len()
+        "Inserted {} games on worker {worker_id}. Inserting events took {:.3}s ({:.3}s encoding, \
+        {:.3}s sending).",
+        games_for_db.len(),
+        db_insert_timings.insert_events_duration,
+        db_insert_timings.encode_events_duration,
+        db_insert_timings.send_events_duration,
     );
-    let _db_insert_duration = (Utc::now() - db_insert_start).as_seconds_f64();
 
     // Immediately turn around and fetch all the games we just inserted,
     // so we can verify that they round-trip correctly.
@@ -175,8 +238,13 @@ pub fn ingest_page_of_games(
         mmolb_game_ids.len()
     );
 
-    let (ingested_games, _events_for_game_timings) =
-        db::events_for_games(conn, taxa, &mmolb_game_ids)?;
+    // This runs inside block_in_place, so it's fine to block on the runtime
+    let (ingested_games, _events_for_game_timings) = tokio::runtime::Handle::current()
+        .block_on(db::events_for_games_concurrently(
+            check_conns,
+            taxa,
+            &mmolb_game_ids,
+        ))?;
     assert_eq!(mmolb_game_ids.len(), ingested_games.len());
     debug!(
         "Fetched {} games on worker {worker_id}",
@@ -185,12 +253,60 @@ pub fn ingest_page_of_games(
     let _db_fetch_for_check_duration = (Utc::now() - db_fetch_for_check_start).as_seconds_f64();
 
     let check_round_trip_start = Utc::now();
-    let additional_logs = games_for_db.iter()
+    let additional_logs = check_round_trips(&games_for_db, &ingested_games);
+    debug!(
+        "Collected logs for {} games on worker {worker_id}",
+        additional_logs.len()
+    );
+    let _check_round_trip_duration = (Utc::now() - check_round_trip_start).as_seconds_f64();
+
+    let insert_extra_logs_start = Utc::now();
+    if !additional_logs.is_empty() {
+        db::insert_additional_ingest_logs(conn, taxa, &additional_logs)?;
+        debug!(
+            "Inserted logs for {} games on worker {worker_id}",
+            additional_logs.len()
+        );
+    } else {
+        debug!("No need to insert additional logs on worker {worker_id}");
+    }
+    let _insert_extra_logs_duration = (Utc::now() - insert_extra_logs_start).as_seconds_f64();
+    let _save_duration = (Utc::now() - save_start).as_seconds_f64();
+
+    progress.publish(IngestProgressEvent::BatchProcessed {
+        kind: "game".to_string(),
+        num_items: games_for_db.len(),
+        num_rows: games_for_db.len(),
+        num_rows_inserted: num_games_imported,
+        latest_valid_from: games_for_db.iter().map(|game| game.metadata().1).max(),
+        at: Utc::now(),
+    });
+
+    Ok::<_, IngestFatalError>(IngestStats {
+        num_ongoing_games_skipped,
+        num_bugged_games_skipped,
+        num_games_with_fatal_errors,
+        num_unsupported_games_skipped,
+        num_games_imported,
+        db_insert_lock_wait_seconds,
+    })
+}
+
+/// Compares each completed game's events against the same events read back
+/// from the database, returning extra ingest logs for every game where the
+/// two don't match. `ingested_games` must be in the same order as the
+/// completed games in `games_for_db`.
+pub(crate) fn check_round_trips(
+    games_for_db: &[GameForDb],
+    ingested_games: &[(i64, Vec<Result<EventDetail<String>, RowToEventError>>)],
+) -> Vec<(i64, Vec<IngestLog>)> {
+    games_for_db
+        .iter()
         .filter_map(|game| match game {
             GameForDb::Completed { game, .. } => Some(game),
             _ => None,
         })
-        .zip(&ingested_games)
+        .zip(ingested_games)
         .filter_map(|(game, (game_id, inserted_events))| {
             let detail_events = &game.events;
             let mut extra_ingest_logs = IngestLogs::new();
@@ -233,33 +349,7 @@ pub fn ingest_page_of_games(
                 Some((*game_id, extra_ingest_logs))
             }
         })
-        .collect_vec();
-    debug!(
-        "Collected logs for {} games on worker {worker_id}",
-        additional_logs.len()
-    );
-    let _check_round_trip_duration = (Utc::now() - check_round_trip_start).as_seconds_f64();
-
-    let insert_extra_logs_start = Utc::now();
-    if !additional_logs.is_empty() {
-        db::insert_additional_ingest_logs(conn, &additional_logs)?;
-        debug!(
-            "Inserted logs for {} games on worker {worker_id}",
-            additional_logs.len()
-        );
-    } else {
-        debug!("No need to insert additional logs on worker {worker_id}");
-    }
-    let _insert_extra_logs_duration = (Utc::now() - insert_extra_logs_start).as_seconds_f64();
-    let _save_duration = (Utc::now() - save_start).as_seconds_f64();
-
-    Ok::<_, IngestFatalError>(IngestStats {
-        num_ongoing_games_skipped,
-        num_bugged_games_skipped,
-        num_games_with_fatal_errors,
-        num_unsupported_games_skipped,
-        num_games_imported,
-    })
+        .collect_vec()
 }
 
 fn diagnostic_to_string(err: miette::Report) -> String {
@@ -274,7 +364,7 @@ fn diagnostic_to_string(err: miette::Report) -> String {
     error_message
 }
 
-fn prepare_game_for_db(
+pub(crate) fn prepare_game_for_db(
     entity: &ChronEntity<mmolb_parsing::Game>,
 ) -> Result<GameForDb<'_>, IngestFatalError> {
     Ok(if !entity.data.is_terminal() {
@@ -352,12 +442,15 @@ fn prepare_completed_game_for_db(
                 .clone()
                 .unparse(&entity.data, Some(game_event_index as _));
             if unparsed != raw.message {
-                ingest_logs.error(format!(
-                    "Round-trip of raw event through ParsedEvent produced a mismatch:\n\
-                     Original: <pre>{:?}</pre>\n\
-                     Through EventDetail: <pre>{:?}</pre>",
-                    raw.message, unparsed,
-                ));
+                ingest_logs.error(
+                    TaxaIngestErrorCode::UnparseMismatch,
+                    format!(
+                        "Round-trip of raw event through ParsedEvent produced a mismatch:\n\
+                         Original: <pre>{:?}</pre>\n\
+                         Through EventDetail: <pre>{:?}</pre>",
+                        raw.message, unparsed,
+                    ),
+                );
             }
 
             let event_result = game
@@ -369,7 +462,10 @@ fn prepare_completed_game_for_db(
             let event = match event_result {
                 Ok(result) => result,
                 Err(err) => {
-                    ingest_logs.critical(diagnostic_to_string(err));
+                    ingest_logs.critical(
+                        TaxaIngestErrorCode::EventProcessingError,
+                        diagnostic_to_string(err),
+                    );
                     None
                 }
             };
@@ -406,6 +502,9 @@ fn prepare_completed_game_for_db(
         id: &entity.entity_id,
         raw_game: &entity.data,
         events,
+        event_defenses: std::mem::take(&mut game.event_defenses),
+        plate_appearance_workloads: std::mem::take(&mut game.plate_appearance_workloads),
+        pitch_sequence: std::mem::take(&mut game.pitch_sequence),
         pitcher_changes,
         parties,
         withers,
@@ -436,56 +535,63 @@ impl IngestLogs {
         Self { logs: Vec::new() }
     }
 
-    #[allow(dead_code)]
-    pub fn critical(&mut self, game_event_index: usize, s: impl Into<String>) {
-        self.logs.push(IngestLog {
-            game_event_index: game_event_index as i32,
-            log_level: 0,
-            log_text: s.into(),
-        });
+    // Like the sim's IngestLogs, anything at warning level or above needs an
+    // error code
+
+    pub fn critical(
+        &mut self,
+        game_event_index: usize,
+        error_code: TaxaIngestErrorCode,
+        s: impl Into<String>,
+    ) {
+        self.push(game_event_index, 0, Some(error_code), s);
     }
 
-    pub fn error(&mut self, game_event_index: usize, s: impl Into<String>) {
-        self.logs.push(IngestLog {
-            game_event_index: game_event_index as i32,
-            log_level: 1,
-            log_text: s.into(),
-        });
+    pub fn error(
+        &mut self,
+        game_event_index: usize,
+        error_code: TaxaIngestErrorCode,
+        s: impl Into<String>,
+    ) {
+        self.push(game_event_index, 1, Some(error_code), s);
     }
 
-    #[allow(dead_code)]
-    pub fn warn(&mut self, game_event_index: usize, s: impl Into<String>) {
-        self.logs.push(IngestLog {
-            game_event_index: game_event_index as i32,
-            log_level: 2,
-            log_text: s.into(),
-        });
+    pub fn warn(
+        &mut self,
+        game_event_index: usize,
+        error_code: TaxaIngestErrorCode,
+        s: impl Into<String>,
+    ) {
+        self.push(game_event_index, 2, Some(error_code), s);
     }
 
     #[allow(dead_code)]
     pub fn info(&mut self, game_event_index: usize, s: impl Into<String>) {
-        self.logs.push(IngestLog {
-            game_event_index: game_event_index as i32,
-            log_level: 3,
-            log_text: s.into(),
-        });
+        self.push(game_event_index, 3, None, s);
     }
 
     #[allow(dead_code)]
     pub fn debug(&mut self, game_event_index: usize, s: impl Into<String>) {
-        self.logs.push(IngestLog {
-            game_event_index: game_event_index as i32,
-            log_level: 4,
-            log_text: s.into(),
-        });
+        self.push(game_event_index, 4, None, s);
     }
 
     #[allow(dead_code)]
     pub fn trace(&mut self, game_event_index: usize, s: impl Into<String>) {
+        self.push(game_event_index, 5, None, s);
+    }
+
+    fn push(
+        &mut self,
+        game_event_index: usize,
+        log_level: i32,
+        error_code: Option<TaxaIngestErrorCode>,
+        s: impl Into<String>,
+    ) {
         self.logs.push(IngestLog {
             game_event_index: game_event_index as i32,
-            log_level: 5,
+            log_level,
             log_text: s.into(),
+            error_code,
         });
     }
 
@@ -0,0 +1,185 @@
+use mmoldb_db::db::IngestLockHolder;
+use mmoldb_db::{Connection, ConnectionError, PgConnection, QueryError, db};
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use thiserror::Error;
+use tracing::warn;
+
+/// How often the lock's holder runs a query to show that it's still alive
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A holder that hasn't run a query in this long has probably died without
+/// its session being closed, e.g. because its host went away. It's many
+/// heartbeats long so a slow heartbeat isn't mistaken for a dead holder.
+const STALE_AFTER_SECONDS: i64 = 10 * 60;
+
+#[derive(Debug, Error)]
+pub enum IngestLockError {
+    #[error("couldn't connect to the database to take the ingest lock")]
+    ConnectionError(#[from] ConnectionError),
+
+    #[error(transparent)]
+    DbError(#[from] QueryError),
+
+    #[error("couldn't start the ingest lock heartbeat")]
+    HeartbeatSpawnError(#[source] std::io::Error),
+}
+
+pub enum IngestLockAttempt {
+    Acquired(IngestLock),
+    /// Someone else holds the lock. The holder is None if they released it
+    /// before they could be looked up.
+    Held(Option<IngestLockHolder>),
+}
+
+/// The ingest lock for one source, held until this is dropped. The lock
+/// belongs to a connection of its own, which a background thread uses to
+/// run a heartbeat query so that anyone who finds the lock taken can tell
+/// whether its holder is still alive.
+pub struct IngestLock {
+    // Dropping this tells the heartbeat thread to close the connection
+    stop: Option<mpsc::Sender<()>>,
+    heartbeat: Option<JoinHandle<()>>,
+}
+
+impl IngestLock {
+    /// Tries to take the ingest lock for `source` (None for main MMOLB)
+    /// without waiting. `holder_name` is shown to anyone who finds the lock
+    /// taken.
+    pub fn try_acquire(
+        source: Option<&str>,
+        holder_name: &str,
+    ) -> Result<IngestLockAttempt, IngestLockError> {
+        let url = mmoldb_db::postgres_url_from_environment();
+        let mut conn = PgConnection::establish(&url)?;
+        db::set_application_name(&mut conn, holder_name)?;
+
+        if !db::try_acquire_ingest_lock(&mut conn, source)? {
+            let holder = db::ingest_lock_holder(&mut conn, source)?;
+            return Ok(IngestLockAttempt::Held(holder));
+        }
+
+        let (stop, stop_recv) = mpsc::channel();
+        let heartbeat = std::thread::Builder::new()
+            .name("ingest-lock-heartbeat".to_string())
+            .spawn(move || run_heartbeat(conn, stop_recv))
+            .map_err(IngestLockError::HeartbeatSpawnError)?;
+
+        Ok(IngestLockAttempt::Acquired(Self {
+            stop: Some(stop),
+            heartbeat: Some(heartbeat),
+        }))
+    }
+}
+
+impl Drop for IngestLock {
+    fn drop(&mut self) {
+        drop(self.stop.take());
+        // Wait for the connection to close, so the lock is free as soon as
+        // this returns
+        if let Some(heartbeat) = self.heartbeat.take() {
+            if heartbeat.join().is_err() {
+                warn!("The ingest lock heartbeat panicked");
+            }
+        }
+    }
+}
+
+fn run_heartbeat(mut conn: PgConnection, stop: mpsc::Receiver<()>) {
+    while let Err(mpsc::RecvTimeoutError::Timeout) = stop.recv_timeout(HEARTBEAT_INTERVAL) {
+        if let Err(err) = db::ingest_lock_heartbeat(&mut conn) {
+            warn!("Ingest lock heartbeat failed: {err}");
+        }
+    }
+    // Dropping conn closes the session, which releases the lock
+}
+
+/// Whether the lock's holder has gone long enough without a heartbeat that
+/// it's probably dead
+pub fn ingest_lock_holder_is_stale(holder: &IngestLockHolder) -> bool {
+    holder
+        .idle_seconds
+        .is_some_and(|idle| idle >= STALE_AFTER_SECONDS)
+}
+
+/// Describes who holds the ingest lock, for telling whoever couldn't take it
+pub fn describe_ingest_lock_holder(holder: Option<&IngestLockHolder>) -> String {
+    let Some(holder) = holder else {
+        return "The ingest lock is held by another process.".to_string();
+    };
+
+    let name = if holder.application_name.is_empty() {
+        "an unnamed process"
+    } else {
+        holder.application_name.as_str()
+    };
+    let addr = holder.client_addr.as_deref().unwrap_or("a local socket");
+    let description = format!(
+        "The ingest lock is held by {name} (database session {} from {addr})",
+        holder.pid,
+    );
+
+    match holder.idle_seconds {
+        Some(idle) if ingest_lock_holder_is_stale(holder) => format!(
+            "{description}, which hasn't run a query in {} minutes. Its process has probably \
+            died without its session being closed. If so, release the lock with \
+            `select pg_terminate_backend({})`.",
+            idle / 60,
+            holder.pid,
+        ),
+        _ => format!("{description}."),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A source of its own, so this doesn't contend with a real ingest
+    const TEST_SOURCE: Option<&str> = Some("ingest_lock_test");
+
+    #[test]
+    fn a_held_lock_reports_its_live_holder() {
+        let IngestLockAttempt::Acquired(lock) =
+            IngestLock::try_acquire(TEST_SOURCE, "ingest lock test").unwrap()
+        else {
+            panic!("the test lock should be free");
+        };
+
+        let IngestLockAttempt::Held(Some(holder)) =
+            IngestLock::try_acquire(TEST_SOURCE, "second ingest lock test").unwrap()
+        else {
+            panic!("the test lock should be held, by a session that can be looked up");
+        };
+        assert_eq!(holder.application_name, "ingest lock test");
+        assert!(!ingest_lock_holder_is_stale(&holder));
+
+        // Dropping the lock releases it right away
+        drop(lock);
+        assert!(matches!(
+            IngestLock::try_acquire(TEST_SOURCE, "ingest lock test").unwrap(),
+            IngestLockAttempt::Acquired(_),
+        ));
+    }
+
+    #[test]
+    fn a_holder_without_recent_heartbeats_is_reported_as_dead() {
+        let mut holder = IngestLockHolder {
+            pid: 1234,
+            application_name: "mmoldb-ingest".to_string(),
+            client_addr: None,
+            backend_start: None,
+            idle_seconds: Some(HEARTBEAT_INTERVAL.as_secs() as i64),
+        };
+        assert!(!ingest_lock_holder_is_stale(&holder));
+        assert!(!describe_ingest_lock_holder(Some(&holder)).contains("pg_terminate_backend"));
+
+        holder.idle_seconds = Some(STALE_AFTER_SECONDS);
+        assert!(ingest_lock_holder_is_stale(&holder));
+        assert!(
+            describe_ingest_lock_holder(Some(&holder))
+                .contains("select pg_terminate_backend(1234)")
+        );
+    }
+}
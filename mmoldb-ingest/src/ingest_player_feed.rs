@@ -1,7 +1,8 @@
-use crate::{IngestibleFromVersions, PreparedIngestItem};
+use crate::{IngestFatalError, IngestibleFromVersions, PreparedIngestItem};
 use crate::ingest::VersionIngestLogs;
 use crate::ingest_feed_shared::{
     FEED_INVERSION_EVENT_END, FEED_INVERSION_EVENT_START, FeedItemContainer,
+    deserialize_feed_event, feed_event_texts, raw_feed_event_index,
 };
 use crate::ingest_players::day_to_db;
 use chron::ChronEntity;
@@ -13,13 +14,15 @@ use lazy_static::lazy_static;
 use mmolb_parsing::enums::{Attribute, Day};
 use mmolb_parsing::feed_event::FeedEvent;
 use mmolb_parsing::player_feed::ParsedPlayerFeedEventText;
+use mmoldb_db::async_db::ProcessingPass;
 use mmoldb_db::models::{
-    NewFeedEventProcessed, NewPlayerAttributeAugment, NewPlayerParadigmShift,
-    NewPlayerRecomposition, NewVersionIngestLog,
+    NewFeedEventFailure, NewFeedEventProcessed, NewPlayerAttributeAugment,
+    NewPlayerParadigmShift, NewPlayerRecomposition, NewVersionIngestLog,
 };
-use mmoldb_db::taxa::Taxa;
+use mmoldb_db::taxa::{Taxa, TaxaIngestErrorCode};
 use mmoldb_db::{AsyncPgConnection, Connection, PgConnection, QueryResult, async_db, db};
 use std::fmt::{Display, Formatter};
+use std::num::NonZero;
 use tracing::error;
 
 lazy_static! {
@@ -434,19 +437,23 @@ impl IngestibleFromVersions for PlayerFeedIngestFromVersions {
         version.clone()
     }
 
-    fn ident_raw(entity: &ChronEntity<serde_json::Value>) -> Self::Ident {
-        let _ = entity; // This will be used if that TODO gets hit
-        todo!("Not sure this will ever get called. If not, can I refactor it away?")
+    fn ident_raw(entity: &ChronEntity<serde_json::Value>) -> Result<Self::Ident, IngestFatalError> {
+        Ok((entity.entity_id.clone(), raw_feed_event_index(entity)?))
     }
 
     fn ident(entity: &ChronEntity<Self::Entity>) -> Self::Ident {
         (entity.entity_id.to_string(), entity.data.feed_event_index)
     }
 
+    fn feed_event_index(entity: &ChronEntity<serde_json::Value>) -> Option<i32> {
+        raw_feed_event_index(entity).ok()
+    }
+
     fn insert_batch(
         conn: &mut PgConnection,
         taxa: &Taxa,
         versions: &Vec<PreparedIngestItem<Self::Ident, Self::Entity>>,
+        _: NonZero<usize>,
     ) -> QueryResult<(usize, usize)> {
         let new_versions = versions
             .iter()
@@ -456,7 +463,7 @@ impl IngestibleFromVersions for PlayerFeedIngestFromVersions {
                         kind: "player_feed", // TODO centralize this
                         entity_id,
                         feed_event_index: *feed_event_index,
-                        valid_from: valid_from.naive_utc(),
+                        valid_from: *valid_from,
                         skipped: true,
                         fatal_error: false,
                     };
@@ -466,6 +473,7 @@ impl IngestibleFromVersions for PlayerFeedIngestFromVersions {
                         None,
                         Vec::new(),
                         Vec::new(),
+                        None,
                     )
                 }
                 PreparedIngestItem::MarkAsFatalError((entity_id, feed_event_index), valid_from) => {
@@ -473,7 +481,7 @@ impl IngestibleFromVersions for PlayerFeedIngestFromVersions {
                         kind: "player_feed", // TODO centralize this
                         entity_id,
                         feed_event_index: *feed_event_index,
-                        valid_from: valid_from.naive_utc(),
+                        valid_from: *valid_from,
                         skipped: false,
                         fatal_error: true,
                     };
@@ -483,6 +491,7 @@ impl IngestibleFromVersions for PlayerFeedIngestFromVersions {
                         None,
                         Vec::new(),
                         Vec::new(),
+                        None,
                     )
                 }
                 PreparedIngestItem::DoIngest(player) => {
@@ -497,14 +506,20 @@ impl IngestibleFromVersions for PlayerFeedIngestFromVersions {
             })
             .collect_vec();
 
-        conn.transaction(|c| db::insert_player_feed_versions(c, &new_versions))
+        let texts = feed_event_texts("player_feed", versions);
+
+        conn.transaction(|c| {
+            db::insert_feed_event_texts(c, &texts)?;
+            db::insert_player_feed_versions(c, &new_versions)
+        })
     }
 
     async fn stream_unprocessed_versions(
         conn: &mut AsyncPgConnection,
         kind: &str,
+        pass: ProcessingPass,
     ) -> QueryResult<impl Stream<Item = QueryResult<ChronEntity<serde_json::Value>>>> {
-        async_db::stream_unprocessed_feed_event_versions(conn, kind).await
+        async_db::stream_unprocessed_feed_event_versions(conn, kind, pass).await
     }
 }
 
@@ -553,9 +568,16 @@ pub fn chron_player_feed_as_new<'a>(
     Option<NewPlayerParadigmShift<'a>>,
     Vec<NewPlayerRecomposition<'a>>,
     Vec<NewVersionIngestLog<'a>>,
+    Option<NewFeedEventFailure<'a>>,
 ) {
     // TODO Can I avoid repeating this string constant?
-    let mut ingest_logs = VersionIngestLogs::new("player_feed", player_id, valid_from);
+    let mut ingest_logs = VersionIngestLogs::for_feed_event(
+        taxa,
+        "player_feed",
+        player_id,
+        event.feed_event_index,
+        valid_from,
+    );
 
     // This is mut so later code can mark a fatal error on this version. 
     // An ideal architecture would not need this to be mut.
@@ -563,7 +585,7 @@ pub fn chron_player_feed_as_new<'a>(
         kind: "player_feed",
         entity_id: player_id,
         feed_event_index: event.feed_event_index,
-        valid_from: valid_from.naive_utc(),
+        valid_from,
         skipped: false,
         fatal_error: false,
     };
@@ -613,16 +635,19 @@ pub fn chron_player_feed_as_new<'a>(
         //     order by fev.valid_from
         ingest_logs.info("Ignoring event version from the Feed Inversion Event");
 
-        return (processed, None, None, Vec::new(), ingest_logs.into_vec());
+        return (processed, None, None, Vec::new(), ingest_logs.into_vec(), None);
     }
 
     if let Some(prev_event) = &event.prev_data {
         if event.prev_valid_from.is_none() {
-            ingest_logs.warn(format!(
-                "Player {} feed event index {} had a previous event, but \
+            ingest_logs.warn(
+                TaxaIngestErrorCode::FeedEventChanged,
+                format!(
+                    "Player {} feed event index {} had a previous event, but \
                 did not have prev_valid_from",
-                player_id, event.feed_event_index,
-            ));
+                    player_id, event.feed_event_index,
+                ),
+            );
         }
 
         if IGNORED_FEED_EVENTS
@@ -648,12 +673,15 @@ pub fn chron_player_feed_as_new<'a>(
                 && prev_valid_from <= FEED_INVERSION_EVENT_END
         }) {
             if event.prev_valid_from.is_none() {
-                ingest_logs.warn(format!(
-                    "Can't check whether player {} feed event index {}'s previous event \
+                ingest_logs.warn(
+                    TaxaIngestErrorCode::FeedEventChanged,
+                    format!(
+                        "Can't check whether player {} feed event index {}'s previous event \
                     was from the Feed Inversion Event because it's missing prev_valid_from. \
                     Assuming it was to avoid losing data.",
-                    player_id, event.feed_event_index,
-                ));
+                        player_id, event.feed_event_index,
+                    ),
+                );
             } else {
                 ingest_logs.info(format!(
                     "Player {} feed event index {} had a previous event, but it was from \
@@ -673,27 +701,30 @@ pub fn chron_player_feed_as_new<'a>(
                 player_id, event.feed_event_index,
             ));
         } else {
-            ingest_logs.error(format!(
-                "Player {} feed event index {} had a previous version without special \
+            ingest_logs.error(
+                TaxaIngestErrorCode::FeedEventChanged,
+                format!(
+                    "Player {} feed event index {} had a previous version without special \
                 handling. Marking this version as a fatal error.\n\
                 previous version text: {}\n\
                 previous version valid_from: {}\n\
                 this version text: {}\n\
                 this version valid_from: {}",
-                player_id,
-                event.feed_event_index,
-                prev_event.text,
-                if let Some(dt) = event.prev_valid_from {
-                    format!("{dt}")
-                } else {
-                    "(missing)".to_string()
-                },
-                event.data.text,
-                valid_from,
-            ));
+                    player_id,
+                    event.feed_event_index,
+                    prev_event.text,
+                    if let Some(dt) = event.prev_valid_from {
+                        format!("{dt}")
+                    } else {
+                        "(missing)".to_string()
+                    },
+                    event.data.text,
+                    valid_from,
+                ),
+            );
 
             processed.fatal_error = true;
-            return (processed, None, None, Vec::new(), ingest_logs.into_vec());
+            return (processed, None, None, Vec::new(), ingest_logs.into_vec(), None);
         }
     }
 
@@ -725,32 +756,44 @@ pub fn chron_player_feed_as_new<'a>(
                     // This is just for a better error message
                     if let Some(n2) = self.known_name {
                         if name_matches(name, n2) {
-                            ingest_logs.error(format!(
-                                "Player name from feed event (\"{name}\") does not match the \
+                            ingest_logs.error(
+                                TaxaIngestErrorCode::PlayerMismatch,
+                                format!(
+                                    "Player name from feed event (\"{name}\") does not match the \
                                 current temporary name override (\"{n}\"). Note: It does match \
                                 the current non-override name (\"{n2}\")."
-                            ));
+                                ),
+                            );
                         } else {
-                            ingest_logs.error(format!(
-                                "Player name from feed event (\"{name}\") does not match the \
+                            ingest_logs.error(
+                                TaxaIngestErrorCode::PlayerMismatch,
+                                format!(
+                                    "Player name from feed event (\"{name}\") does not match the \
                                 current temporary name override (\"{n}\"). Note: It also does \
                                 not match the current non-override name (\"{n2}\")."
-                            ));
+                                ),
+                            );
                         }
                     } else {
-                        ingest_logs.error(format!(
-                            "Player name from feed event (\"{name}\") does not match the \
+                        ingest_logs.error(
+                            TaxaIngestErrorCode::PlayerMismatch,
+                            format!(
+                                "Player name from feed event (\"{name}\") does not match the \
                             current temporary name override (\"{n}\"). Note: The current \
                             non-override name is not known."
-                        ));
+                            ),
+                        );
                     }
                 }
             } else if let Some(n) = self.known_name {
                 if !name_matches(name, n) {
-                    ingest_logs.error(format!(
-                        "Player name from feed event (\"{name}\") does not match the known \
+                    ingest_logs.error(
+                        TaxaIngestErrorCode::PlayerMismatch,
+                        format!(
+                            "Player name from feed event (\"{name}\") does not match the known \
                         player name (\"{n}\")."
-                    ));
+                        ),
+                    );
                 }
             } else {
                 self.known_name = Some(name);
@@ -815,6 +858,7 @@ pub fn chron_player_feed_as_new<'a>(
                 paradigm_shift,
                 recompositions,
                 ingest_logs.into_vec(),
+                None,
             );
         }
     }
@@ -895,23 +939,32 @@ pub fn chron_player_feed_as_new<'a>(
                 // which means it's about to be deleted and become implied.
                 // So we ignore the real version and insert the inferred version
                 if new != player_name_after {
-                    ingest_logs.error(format!(
-                        "The overwritten Recomposed event new player name didn't match: expected \
-                        {player_name_after}, but observed {new}.",
-                    ));
+                    ingest_logs.error(
+                        TaxaIngestErrorCode::FeedEventChanged,
+                        format!(
+                            "The overwritten Recomposed event new player name didn't match: expected \
+                            {player_name_after}, but observed {new}.",
+                        ),
+                    );
                 }
                 if previous != player_name_before {
-                    ingest_logs.error(format!(
-                        "The overwritten Recomposed event previous player name didn't match: \
-                        expected {player_name_before}, but observed {previous}.",
-                    ));
+                    ingest_logs.error(
+                        TaxaIngestErrorCode::FeedEventChanged,
+                        format!(
+                            "The overwritten Recomposed event previous player name didn't match: \
+                            expected {player_name_before}, but observed {previous}.",
+                        ),
+                    );
                 }
                 if *recompose_time != time {
-                    ingest_logs.error(format!(
-                        "The overwritten Recomposed event timestamp didn't match: \
-                        expected {recompose_time}, but observed {}.",
-                        event.data.timestamp.naive_utc(),
-                    ));
+                    ingest_logs.error(
+                        TaxaIngestErrorCode::FeedEventChanged,
+                        format!(
+                            "The overwritten Recomposed event timestamp didn't match: \
+                            expected {recompose_time}, but observed {}.",
+                            event.data.timestamp.naive_utc(),
+                        ),
+                    );
                 }
                 true
             } else {
@@ -968,16 +1021,28 @@ pub fn chron_player_feed_as_new<'a>(
             paradigm_shift,
             recompositions,
             ingest_logs.into_vec(),
+            None,
         );
     }
 
+    let mut failure = None;
     match parsed_event {
         ParsedPlayerFeedEventText::ParseError { error, text } => {
             // TODO Expose player ingest errors on the site
-            ingest_logs.error(format!(
-                "Error {error} parsing {text} from {} ({})'s feed",
-                check_player_name, player_id,
-            ));
+            ingest_logs.error(
+                TaxaIngestErrorCode::FeedEventParseError,
+                format!(
+                    "Error {error} parsing {text} from {} ({})'s feed",
+                    check_player_name, player_id,
+                ),
+            );
+            failure = Some(NewFeedEventFailure {
+                kind: "player_feed",
+                entity_id: player_id,
+                feed_event_index: event.feed_event_index,
+                valid_from,
+                error_message: format!("Error {error} parsing {text}"),
+            });
         }
         ParsedPlayerFeedEventText::Delivery { .. } => {
             // We don't (yet) use this event, but feed events have a timestamp so it
@@ -1011,6 +1076,8 @@ pub fn chron_player_feed_as_new<'a>(
                 superstar_day,
                 attribute: taxa.attribute_id(attribute.into()),
                 value: amount as i32,
+                // insert_batch stores the feed event's text before this
+                feed_event_kind: Some("player_feed"),
             });
         }
         ParsedPlayerFeedEventText::AttributeEquals {
@@ -1167,5 +1234,18 @@ pub fn chron_player_feed_as_new<'a>(
         paradigm_shift,
         recompositions,
         ingest_logs.into_vec(),
+        failure,
     )
 }
+
+/// Re-parses a player feed event from the dead-letter queue. Returns the
+/// error message if it still can't be parsed.
+pub fn reparse_player_feed_event(raw_event: &serde_json::Value) -> Result<(), String> {
+    let event = deserialize_feed_event(raw_event)?;
+    match mmolb_parsing::player_feed::parse_player_feed_event(&event) {
+        ParsedPlayerFeedEventText::ParseError { error, text } => {
+            Err(format!("Error {error} parsing {text}"))
+        }
+        _ => Ok(()),
+    }
+}
@@ -10,15 +10,19 @@ use mmolb_parsing::{
 };
 use std::fmt::Display;
 use std::iter;
+use std::num::NonZero;
 use strum::IntoEnumIterator;
 use thiserror::Error;
-use tracing::{error, warn};
+use tracing::{error, info, warn};
 
-use crate::{IngestibleFromVersions, PreparedIngestItem, VersionIngestLogs};
+use crate::{IngestFatalError, IngestibleFromVersions, PreparedIngestItem, VersionIngestLogs};
 use chron::ChronEntity;
+use mmoldb_db::async_db::ProcessingPass;
 use mmoldb_db::db::NameEmojiTooltip;
 use mmoldb_db::models::{NewPlayerEquipmentEffectVersion, NewPlayerEquipmentVersion, NewPlayerModificationVersion, NewPlayerPitchCategoryBonusVersion, NewPlayerPitchTypeBonusVersion, NewPlayerPitchTypeVersion, NewPlayerReportAttributeVersion, NewPlayerReportVersion, NewPlayerVersion, NewVersionIngestLog, NewVersionProcessed};
-use mmoldb_db::taxa::{Taxa, TaxaAttributeCategory, TaxaDayType, TaxaModificationType, TaxaSlot};
+use mmoldb_db::taxa::{
+    Taxa, TaxaAttributeCategory, TaxaDayType, TaxaIngestErrorCode, TaxaModificationType, TaxaSlot,
+};
 use mmoldb_db::{AsyncPgConnection, PgConnection, QueryResult, async_db, db};
 use crate::ingest_feed_shared::datetime_from_parts;
 
@@ -39,105 +43,129 @@ impl IngestibleFromVersions for PlayerIngestFromVersions {
         }
     }
 
-    fn ident_raw(entity: &ChronEntity<serde_json::Value>) -> Self::Ident {
-        entity.entity_id.to_string()
+    fn ident_raw(entity: &ChronEntity<serde_json::Value>) -> Result<Self::Ident, IngestFatalError> {
+        Ok(entity.entity_id.to_string())
     }
 
     fn ident(entity: &ChronEntity<Self::Entity>) -> Self::Ident {
         entity.entity_id.to_string()
     }
 
+    // Chunks are inserted by db::insert_player_versions_chunked, which
+    // reports progress as it goes
+    const INSERTS_IN_CHUNKS: bool = true;
+
     fn insert_batch(
         conn: &mut PgConnection,
         taxa: &Taxa,
         versions: &Vec<PreparedIngestItem<Self::Ident, Self::Entity>>,
+        insert_chunk_size: NonZero<usize>,
     ) -> QueryResult<(usize, usize)> {
-        // Collect all modifications that appear in this batch so we can ensure they're all added
-        let unique_modifications = versions
-            .iter()
-            .flat_map(|version| match version {
-                PreparedIngestItem::MarkAsSkipped(_, _) => Either::Left(iter::empty()),
-                PreparedIngestItem::MarkAsFatalError(_, _) => Either::Left(iter::empty()),
-                PreparedIngestItem::DoIngest(version) => Either::Right({
-                    version
-                        .data
-                        .modifications
-                        .iter()
-                        .chain(version.data.lesser_boon.iter())
-                        .chain(version.data.greater_boon.iter())
-                        .chain(version.data.lesser_boons.iter().flat_map(|b| b.iter()))
-                        .chain(version.data.greater_boons.iter().flat_map(|b| b.iter()))
-                        .map(|m| {
-                            // TODO Do this for extra_fields in other types
-                            if !m.extra_fields.is_empty() {
-                                warn!(
-                                    "Modification had extra fields that were not captured: {:?}",
-                                    m.extra_fields
-                                );
-                            }
-                            (m.name.as_str(), m.emoji.as_str(), m.description.as_str())
+        // Player rows are big, so they're built and inserted a chunk at a
+        // time instead of all at once. Each chunk looks up the
+        // modifications it needs, so nothing is built for the whole batch.
+        db::insert_player_versions_chunked(
+            conn,
+            versions.iter(),
+            insert_chunk_size,
+            |conn, chunk| {
+                // Collect all modifications that appear in this chunk so we can ensure they're all added
+                let unique_modifications = chunk
+                    .iter()
+                    .flat_map(|version| match *version {
+                        PreparedIngestItem::MarkAsSkipped(_, _) => Either::Left(iter::empty()),
+                        PreparedIngestItem::MarkAsFatalError(_, _) => Either::Left(iter::empty()),
+                        PreparedIngestItem::DoIngest(version) => Either::Right({
+                            version
+                                .data
+                                .modifications
+                                .iter()
+                                .chain(version.data.lesser_boon.iter())
+                                .chain(version.data.greater_boon.iter())
+                                .chain(version.data.lesser_boons.iter().flat_map(|b| b.iter()))
+                                .chain(version.data.greater_boons.iter().flat_map(|b| b.iter()))
+                                .map(|m| {
+                                    // TODO Do this for extra_fields in other types
+                                    if !m.extra_fields.is_empty() {
+                                        warn!(
+                                            "Modification had extra fields that were not captured: {:?}",
+                                            m.extra_fields
+                                        );
+                                    }
+                                    (m.name.as_str(), m.emoji.as_str(), m.description.as_str())
+                                })
                         })
-                })
-            })
-            .unique()
-            .collect_vec();
-
-        let modifications = get_filled_modifications_map(conn, &unique_modifications)?;
-        let new_versions = versions
-            .iter()
-            .map(|item| match item {
-                PreparedIngestItem::MarkAsSkipped(entity_id, valid_from) => {
-                    let processed = NewVersionProcessed {
-                        kind: "player",  // TODO avoid repeating literal
-                        entity_id,
-                        valid_from: valid_from.naive_utc(),
-                        skipped: true,
-                        fatal_error: false,
-                    };
-                    (
-                        processed,
-                        None,
-                        Vec::new(),
-                        Vec::new(),
-                        Vec::new(),
-                        Vec::new(),
-                        Vec::new(),
-                        Vec::new(),
-                        Vec::new(),
-                    )
-                }
-                PreparedIngestItem::MarkAsFatalError(entity_id, valid_from) => {
-                    let processed = NewVersionProcessed {
-                        kind: "player",  // TODO avoid repeating literal
-                        entity_id,
-                        valid_from: valid_from.naive_utc(),
-                        skipped: false,
-                        fatal_error: true,
-                    };
-                    (
-                        processed,
-                        None,
-                        Vec::new(),
-                        Vec::new(),
-                        Vec::new(),
-                        Vec::new(),
-                        Vec::new(),
-                        Vec::new(),
-                        Vec::new(),
-                    )
+                    })
+                    .unique()
+                    .collect_vec();
+
+                let modifications = get_filled_modifications_map(conn, &unique_modifications)?;
+                Ok(chunk
+                    .iter()
+                    .map(|item| match *item {
+                        PreparedIngestItem::MarkAsSkipped(entity_id, valid_from) => {
+                            let processed = NewVersionProcessed {
+                                kind: "player",  // TODO avoid repeating literal
+                                entity_id,
+                                valid_from: *valid_from,
+                                skipped: true,
+                                fatal_error: false,
+                            };
+                            (
+                                processed,
+                                None,
+                                Vec::new(),
+                                Vec::new(),
+                                Vec::new(),
+                                Vec::new(),
+                                Vec::new(),
+                                Vec::new(),
+                                Vec::new(),
+                            )
+                        }
+                        PreparedIngestItem::MarkAsFatalError(entity_id, valid_from) => {
+                            let processed = NewVersionProcessed {
+                                kind: "player",  // TODO avoid repeating literal
+                                entity_id,
+                                valid_from: *valid_from,
+                                skipped: false,
+                                fatal_error: true,
+                            };
+                            (
+                                processed,
+                                None,
+                                Vec::new(),
+                                Vec::new(),
+                                Vec::new(),
+                                Vec::new(),
+                                Vec::new(),
+                                Vec::new(),
+                                Vec::new(),
+                            )
+                        }
+                        PreparedIngestItem::DoIngest(entity) => chron_player_as_new(taxa, entity, &modifications),
+                    })
+                    .collect_vec())
+            },
+            |progress| {
+                if progress.versions_inserted < progress.total_versions {
+                    info!(
+                        "Inserted {}/{} player versions in {} chunks",
+                        progress.versions_inserted,
+                        progress.total_versions,
+                        progress.chunks_inserted,
+                    );
                 }
-                PreparedIngestItem::DoIngest(entity) => chron_player_as_new(taxa, entity, &modifications),
-            })
-            .collect_vec();
-
-        db::insert_player_versions_all(conn, &new_versions)
+            },
+        )
     }
 
     async fn stream_unprocessed_versions(
         conn: &mut AsyncPgConnection,
         kind: &str,
+        pass: ProcessingPass,
     ) -> QueryResult<impl Stream<Item = QueryResult<ChronEntity<serde_json::Value>>>> {
-        async_db::stream_unprocessed_versions(conn, kind).await
+        async_db::stream_unprocessed_versions(conn, kind, pass).await
     }
 }
 
@@ -336,9 +364,10 @@ fn chron_equipment_effect_as_new<'g>(
     let effect = match effect {
         Ok(effect) => effect,
         Err(NotRecognized(value)) => {
-            ingest_logs.error(format!(
-                "Skipping unrecognized equipment effect {value:?}",
-            ));
+            ingest_logs.error(
+                TaxaIngestErrorCode::UnrecognizedValue,
+                format!("Skipping unrecognized equipment effect {value:?}"),
+            );
             return None;
         }
     };
@@ -346,10 +375,13 @@ fn chron_equipment_effect_as_new<'g>(
     let attribute = match &effect.attribute {
         Ok(attribute) => attribute,
         Err(NotRecognized(value)) => {
-            ingest_logs.error(format!(
-                "Skipping unrecognized equipment effect attribute {:?}",
-                value,
-            ));
+            ingest_logs.error(
+                TaxaIngestErrorCode::UnrecognizedValue,
+                format!(
+                    "Skipping unrecognized equipment effect attribute {:?}",
+                    value,
+                ),
+            );
             return None;
         }
     };
@@ -357,10 +389,10 @@ fn chron_equipment_effect_as_new<'g>(
     let effect_type = match &effect.effect_type {
         Ok(effect_type) => effect_type,
         Err(NotRecognized(value)) => {
-            ingest_logs.error(format!(
-                "Skipping unrecognized equipment effect type {:?}",
-                value,
-            ));
+            ingest_logs.error(
+                TaxaIngestErrorCode::UnrecognizedValue,
+                format!("Skipping unrecognized equipment effect type {:?}", value),
+            );
             return None;
         }
     };
@@ -369,7 +401,7 @@ fn chron_equipment_effect_as_new<'g>(
         mmolb_player_id: &entity.entity_id,
         equipment_slot,
         effect_index: *index as i32,
-        valid_from: entity.valid_from.naive_utc(),
+        valid_from: entity.valid_from,
         valid_until: None,
         attribute: taxa.attribute_id((*attribute).into()),
         effect_type: taxa.effect_type_id((*effect_type).into()),
@@ -381,7 +413,10 @@ fn chron_equipment_effect_as_new<'g>(
             None => None,
             Some(Ok(phase)) => Some(taxa.effect_phase_id((*phase).into())),
             Some(Err(err)) => {
-                ingest_logs.error(format!("Unrecognized equipment phase {:?}", err));
+                ingest_logs.error(
+                    TaxaIngestErrorCode::UnrecognizedValue,
+                    format!("Unrecognized equipment phase {:?}", err),
+                );
                 None
             }
         },
@@ -414,12 +449,13 @@ fn chron_player_as_new<'a>(
     Vec<NewVersionIngestLog<'a>>,
 ) {
     // TODO Can I avoid repeating this string constant?
-    let mut ingest_logs = VersionIngestLogs::new("player", &entity.entity_id, entity.valid_from);
+    let mut ingest_logs =
+        VersionIngestLogs::new(taxa, "player", &entity.entity_id, entity.valid_from);
 
     let processed = NewVersionProcessed {
         kind: "player", // TODO Avoid hard-coding this
         entity_id: &entity.entity_id,
-        valid_from: entity.valid_from.naive_utc(),
+        valid_from: entity.valid_from,
         skipped: false,
         fatal_error: false,
     };
@@ -442,7 +478,10 @@ fn chron_player_as_new<'a>(
         match handedness {
             Ok(handedness) => Some(taxa.handedness_id((*handedness).into())),
             Err(err) => {
-                ingest_logs.error(format!("Player had unexpected batting handedness {err}"));
+                ingest_logs.error(
+                    TaxaIngestErrorCode::UnrecognizedValue,
+                    format!("Player had unexpected batting handedness {err}"),
+                );
                 None
             }
         }
@@ -450,7 +489,7 @@ fn chron_player_as_new<'a>(
 
     let make_modification = |i, m, ty| NewPlayerModificationVersion {
         mmolb_player_id: &entity.entity_id,
-        valid_from: entity.valid_from.naive_utc(),
+        valid_from: entity.valid_from,
         valid_until: None,
         modification_index: i as i32,
         modification_id: get_modification_id(m),
@@ -460,21 +499,27 @@ fn chron_player_as_new<'a>(
     let num_lesser_boon_singular = entity.data.lesser_boon.iter().count();
     let num_lesser_boons_plural = entity.data.lesser_boons.as_ref().map_or(0, Vec::len);
     if num_lesser_boon_singular > 0 && num_lesser_boons_plural > 0 {
-        ingest_logs.error(format!(
-            "Player has {num_lesser_boon_singular} boon(s) in lesser_boon (singular) \
+        ingest_logs.error(
+            TaxaIngestErrorCode::UnexpectedVersionData,
+            format!(
+                "Player has {num_lesser_boon_singular} boon(s) in lesser_boon (singular) \
             and {num_lesser_boons_plural} in lesser_boons (plural), expected only \
             one or the other"
-        ));
+            ),
+        );
     }
 
     let num_greater_boon_singular = entity.data.greater_boon.iter().count();
     let num_greater_boons_plural = entity.data.greater_boons.as_ref().map_or(0, Vec::len);
     if num_greater_boon_singular > 0 && num_greater_boons_plural > 0 {
-        ingest_logs.error(format!(
-            "Player has {num_greater_boon_singular} boon(s) in greater_boon (singular) \
+        ingest_logs.error(
+            TaxaIngestErrorCode::UnexpectedVersionData,
+            format!(
+                "Player has {num_greater_boon_singular} boon(s) in greater_boon (singular) \
             and {num_greater_boons_plural} in greater_boons (plural), expected only \
             one or the other"
-        ));
+            ),
+        );
     }
 
     let modifications = entity
@@ -549,7 +594,10 @@ fn chron_player_as_new<'a>(
             Slot::DesignatedHitter => TaxaSlot::DesignatedHitter,
         })),
         Err(err) => {
-            ingest_logs.error(format!("Player position not recognized: {err}"));
+            ingest_logs.error(
+                TaxaIngestErrorCode::UnrecognizedValue,
+                format!("Player position not recognized: {err}"),
+            );
             None
         }
     };
@@ -565,9 +613,12 @@ fn chron_player_as_new<'a>(
                         match equipment_slot_to_str(&slot) {
                             Ok(slot) => Some(slot),
                             Err(err) => {
-                                ingest_logs.error(format!(
-                                    "Error processing player equipment slot: {err}. This slot will be ignored.",
-                                ));
+                                ingest_logs.error(
+                                    TaxaIngestErrorCode::UnrecognizedValue,
+                                    format!(
+                                        "Error processing player equipment slot: {err}. This slot will be ignored.",
+                                    ),
+                                );
                                 None
                             }
                         }
@@ -629,6 +680,7 @@ fn chron_player_as_new<'a>(
                     Some(*priority)
                 } else {
                     ingest_logs.warn(
+                        TaxaIngestErrorCode::UnexpectedVersionData,
                         "Player version had BaseAttributes, but BaseAttributes did not contain the \
                 Priority attribute",
                     );
@@ -642,10 +694,13 @@ fn chron_player_as_new<'a>(
         (Some(priority), Err(AddedLater)) => Some(priority),
         (Some(from_base_attributes), Ok(from_root)) => {
             if from_base_attributes != from_root {
-                ingest_logs.warn(format!(
-                    "Player version had priority {from_base_attributes} in BaseAttributes \
+                ingest_logs.warn(
+                    TaxaIngestErrorCode::UnexpectedVersionData,
+                    format!(
+                        "Player version had priority {from_base_attributes} in BaseAttributes \
                     but priority {from_root} on the root object",
-                ));
+                    ),
+                );
             }
             // Root was added most recently, so I trust it more in the case that they disagree
             Some(from_root)
@@ -668,12 +723,15 @@ fn chron_player_as_new<'a>(
                     for (attr, value) in expected_attributes {
                         match category_talk.stars.get(attr) {
                             None => {
-                                ingest_logs.warn(format!(
-                                    "Attribute {} appeared in the attributes section of the {:?} \
-                                    talk, but not the stars section.",
-                                    <Attribute as Into<&'static str>>::into(*attr),
-                                    <AttributeCategory as Into<&'static str>>::into(category),
-                                ));
+                                ingest_logs.warn(
+                                    TaxaIngestErrorCode::UnexpectedVersionData,
+                                    format!(
+                                        "Attribute {} appeared in the attributes section of the {:?} \
+                                        talk, but not the stars section.",
+                                        <Attribute as Into<&'static str>>::into(*attr),
+                                        <AttributeCategory as Into<&'static str>>::into(category),
+                                    ),
+                                );
                             }
                             // Attributes section of talk page has modified attributes
                             Some(TalkStars::Complex(ComplexTalkStars { total, .. })) => {
@@ -681,18 +739,22 @@ fn chron_player_as_new<'a>(
                                 // how equal the equalities are. It may need to be relaxed in
                                 // the near future.
                                 if total != value {
-                                    ingest_logs.warn(format!(
-                                        "Modified {} value in the attributes section of the talk \
-                                        ({}) did not exactly match the value in the stars section \
-                                        ({})",
-                                        <Attribute as Into<&'static str>>::into(*attr),
-                                        total,
-                                        value,
-                                    ));
+                                    ingest_logs.warn(
+                                        TaxaIngestErrorCode::UnexpectedVersionData,
+                                        format!(
+                                            "Modified {} value in the attributes section of the talk \
+                                            ({}) did not exactly match the value in the stars section \
+                                            ({})",
+                                            <Attribute as Into<&'static str>>::into(*attr),
+                                            total,
+                                            value,
+                                        ),
+                                    );
                                 }
                             }
                             Some(TalkStars::Intermediate { .. }) => {
                                 ingest_logs.warn(
+                                    TaxaIngestErrorCode::UnexpectedVersionData,
                                     "Expected Complex talk objects in all player versions whose \
                                     talk pages have an attributes section, but this is an \
                                     Intermediate talk object",
@@ -700,6 +762,7 @@ fn chron_player_as_new<'a>(
                             }
                             Some(TalkStars::Simple(_)) => {
                                 ingest_logs.warn(
+                                    TaxaIngestErrorCode::UnexpectedVersionData,
                                     "Expected Complex talk objects in all player versions whose \
                                     talk pages have an attributes section, but this is a Simple \
                                     talk object",
@@ -713,12 +776,15 @@ fn chron_player_as_new<'a>(
                     for (attr, _) in &category_talk.stars {
                         match expected_attributes.get(attr) {
                             None => {
-                                ingest_logs.warn(format!(
-                                    "Attribute {} appeared in the stars section of the {:?} \
+                                ingest_logs.warn(
+                                    TaxaIngestErrorCode::UnexpectedVersionData,
+                                    format!(
+                                        "Attribute {} appeared in the stars section of the {:?} \
                                     talk, but not the attributes section.",
-                                    <Attribute as Into<&'static str>>::into(*attr),
-                                    <AttributeCategory as Into<&'static str>>::into(category),
-                                ));
+                                        <Attribute as Into<&'static str>>::into(*attr),
+                                        <AttributeCategory as Into<&'static str>>::into(category),
+                                    ),
+                                );
                             }
                             Some(_) => {}
                         }
@@ -730,12 +796,15 @@ fn chron_player_as_new<'a>(
                         TalkStars::Complex(ComplexTalkStars { attribute, .. }) => {
                             match attribute {
                                 Ok(attribute) => if attribute != attr {
-                                    ingest_logs.warn(format!(
-                                        "Attribute property of a talk page attribute ({}) did not \
-                                        match the object key for this attribute's object ({}).",
-                                        attribute,
-                                        <Attribute as Into<&'static str>>::into(*attr),
-                                    ));
+                                    ingest_logs.warn(
+                                        TaxaIngestErrorCode::UnexpectedVersionData,
+                                        format!(
+                                            "Attribute property of a talk page attribute ({}) did not \
+                                            match the object key for this attribute's object ({}).",
+                                            attribute,
+                                            <Attribute as Into<&'static str>>::into(*attr),
+                                        ),
+                                    );
                                 }
                                 Err(AddedLater) => {
                                     // This is fine, for a while ComplexTalkStars didn't have an attribute
@@ -763,7 +832,7 @@ fn chron_player_as_new<'a>(
                         NewPlayerReportVersion {
                             mmolb_player_id: &entity.entity_id,
                             category: taxa.attribute_category_id(cat.into()),
-                            valid_from: entity.valid_from.naive_utc(),
+                            valid_from: entity.valid_from,
                             valid_until: None,
                             season: None,
                             day_type: None,
@@ -795,10 +864,13 @@ fn chron_player_as_new<'a>(
                         AttributeCategory::Defense
                     }
                     _ => {
-                        ingest_logs.warn(format!(
-                            "Unexpected uncategorized attribute {attr}. This attribute \
+                        ingest_logs.warn(
+                            TaxaIngestErrorCode::UnrecognizedValue,
+                            format!(
+                                "Unexpected uncategorized attribute {attr}. This attribute \
                             will not be stored in MMOLDB.",
-                        ));
+                            ),
+                        );
                         continue;
                     }
                 },
@@ -818,7 +890,7 @@ fn chron_player_as_new<'a>(
                     mmolb_player_id: &entity.entity_id,
                     category: taxa.attribute_category_id(cat.into()),
                     attribute: taxa.attribute_id(attr.into()),
-                    valid_from: entity.valid_from.naive_utc(),
+                    valid_from: entity.valid_from,
                     valid_until: None,
                     base_stars: None,
                     // This one incudes augments
@@ -849,10 +921,13 @@ fn chron_player_as_new<'a>(
                         AttributeCategory::Defense
                     }
                     _ => {
-                        ingest_logs.warn(format!(
-                            "Unexpected uncategorized attribute {attr} for s10+ augment. \
+                        ingest_logs.warn(
+                            TaxaIngestErrorCode::UnrecognizedValue,
+                            format!(
+                                "Unexpected uncategorized attribute {attr} for s10+ augment. \
                             This agument will not be stored in MMOLDB.",
-                        ));
+                            ),
+                        );
                         continue;
                     }
                 },
@@ -887,10 +962,13 @@ fn chron_player_as_new<'a>(
                         // Luck is in the Defense report for historical reasons
                         AttributeCategory::Defense
                     } else {
-                        ingest_logs.warn(format!(
-                            "Unexpected uncategorized attribute in AppliedAugments: {}.",
-                            aug.attribute
-                        ));
+                        ingest_logs.warn(
+                            TaxaIngestErrorCode::UnrecognizedValue,
+                            format!(
+                                "Unexpected uncategorized attribute in AppliedAugments: {}.",
+                                aug.attribute
+                            ),
+                        );
                         continue;
                     };
 
@@ -910,7 +988,10 @@ fn chron_player_as_new<'a>(
                 }
             }
             Err(AddedLater) => {
-                ingest_logs.error("Player in season 11 format must have augment history");
+                ingest_logs.error(
+                    TaxaIngestErrorCode::UnexpectedVersionData,
+                    "Player in season 11 format must have augment history",
+                );
             }
         }
 
@@ -934,7 +1015,7 @@ fn chron_player_as_new<'a>(
             let report_version = NewPlayerReportVersion {
                 mmolb_player_id: &entity.entity_id,
                 category: taxa.attribute_category_id((*category).into()),
-                valid_from: entity.valid_from.naive_utc(),
+                valid_from: entity.valid_from,
                 valid_until: None,
                 season: None,
                 day_type: None,
@@ -969,21 +1050,27 @@ fn chron_player_as_new<'a>(
                                 }
                                 Some(*base_attr)
                             } else {
-                                ingest_logs.warn(format!(
+                                ingest_logs.warn(
+                                    TaxaIngestErrorCode::UnexpectedVersionData,
+                                    format!(
                                     "BaseAttributes field on player entity exists, but does not \
                                 contain an entry for attribute {}. An entry was expected because \
                                 this attribute appears in the {:?} section of AttributeStars.",
                                     <Attribute as Into<&'static str>>::into(*attribute),
                                     <AttributeCategory as Into<&'static str>>::into(*category),
-                                ));
+                                ),
+                            );
                                 None
                             }
                         } else {
-                            ingest_logs.warn(format!(
-                            "AttributeStars field on player entity exists, but BaseAttributes does \
-                            not. base_subtotal will be unexpectededly null for {}.",
-                            <Attribute as Into<&'static str>>::into(*attribute),
-                        ));
+                            ingest_logs.warn(
+                                TaxaIngestErrorCode::UnexpectedVersionData,
+                                format!(
+                                "AttributeStars field on player entity exists, but BaseAttributes does \
+                                not. base_subtotal will be unexpectededly null for {}.",
+                                <Attribute as Into<&'static str>>::into(*attribute),
+                                ),
+                            );
                             None
                         };
 
@@ -1049,9 +1136,12 @@ fn chron_player_as_new<'a>(
                         let equipment_slot = match maybe_recognized_str(slot) {
                             Ok(equipment_slot) => equipment_slot,
                             Err(non_string_value) => {
-                                ingest_logs.error(format!(
-                                    "Ignoring equipment with non-string slot {non_string_value:?}",
-                                ));
+                                ingest_logs.error(
+                                    TaxaIngestErrorCode::UnexpectedVersionData,
+                                    format!(
+                                        "Ignoring equipment with non-string slot {non_string_value:?}",
+                                    ),
+                                );
                                 return None;
                             }
                         };
@@ -1059,9 +1149,12 @@ fn chron_player_as_new<'a>(
                         let name = match maybe_recognized_str(equipment.name) {
                             Ok(name) => name,
                             Err(non_string_value) => {
-                                ingest_logs.error(format!(
-                                    "Ignoring equipment with non-string name {non_string_value:?}",
-                                ));
+                                ingest_logs.error(
+                                    TaxaIngestErrorCode::UnexpectedVersionData,
+                                    format!(
+                                        "Ignoring equipment with non-string name {non_string_value:?}",
+                                    ),
+                                );
                                 return None;
                             }
                         };
@@ -1070,9 +1163,12 @@ fn chron_player_as_new<'a>(
                             Ok(rarity) => match maybe_recognized_str(rarity) {
                                 Ok(rarity) => Some(rarity),
                                 Err(non_string_value) => {
-                                    ingest_logs.error(format!(
-                                        "Ignoring non-string equipment rarity {non_string_value:?}",
-                                    ));
+                                    ingest_logs.error(
+                                        TaxaIngestErrorCode::UnexpectedVersionData,
+                                        format!(
+                                            "Ignoring non-string equipment rarity {non_string_value:?}",
+                                        ),
+                                    );
                                     None
                                 }
                             },
@@ -1104,10 +1200,22 @@ fn chron_player_as_new<'a>(
 
                         if let Some(implicit) = &equipment.implicit {
                             if implicit.source != Ok(ImplicitEquipmentEffectSource::CorruptingOrb) {
-                                ingest_logs.warn(format!("Implicit equipment effect had unknown source {:?}", implicit.source));
+                                ingest_logs.warn(
+                                    TaxaIngestErrorCode::UnrecognizedValue,
+                                    format!(
+                                        "Implicit equipment effect had unknown source {:?}",
+                                        implicit.source
+                                    ),
+                                );
                             }
                             if !implicit.extra_fields.is_empty() {
-                                ingest_logs.warn(format!("Implicit equipment effect had extra fields {:?}", implicit.extra_fields));
+                                ingest_logs.warn(
+                                    TaxaIngestErrorCode::UnexpectedVersionData,
+                                    format!(
+                                        "Implicit equipment effect had extra fields {:?}",
+                                        implicit.extra_fields
+                                    ),
+                                );
                             }
 
                             effects.extend(
@@ -1130,7 +1238,7 @@ fn chron_player_as_new<'a>(
                         let new_equipment = NewPlayerEquipmentVersion {
                             mmolb_player_id: &entity.entity_id,
                             equipment_slot: equipment_slot.clone(),
-                            valid_from: entity.valid_from.naive_utc(),
+                            valid_from: entity.valid_from,
                             valid_until: None,
                             emoji: equipment.emoji.clone(),
                             name,
@@ -1182,9 +1290,12 @@ fn chron_player_as_new<'a>(
                         let root_ty = match root_ty_result {
                             Ok(root_ty) => root_ty,
                             Err(err) => {
-                                ingest_logs.error(format!(
-                                    "{index}th pitch type in root object was unrecognized: {err}",
-                                ));
+                                ingest_logs.error(
+                                    TaxaIngestErrorCode::UnrecognizedValue,
+                                    format!(
+                                        "{index}th pitch type in root object was unrecognized: {err}",
+                                    ),
+                                );
                                 continue;
                             }
                         };
@@ -1192,9 +1303,12 @@ fn chron_player_as_new<'a>(
                         let base_attributes_ty = match base_attributes_ty_result {
                             Ok(base_attributes_ty) => base_attributes_ty,
                             Err(err) => {
-                                ingest_logs.error(format!(
-                                    "{index}th pitch type in BaseAttributes was unrecognized: {err}",
-                                ));
+                                ingest_logs.error(
+                                    TaxaIngestErrorCode::UnrecognizedValue,
+                                    format!(
+                                        "{index}th pitch type in BaseAttributes was unrecognized: {err}",
+                                    ),
+                                );
                                 continue;
                             }
                         };
@@ -1210,11 +1324,14 @@ fn chron_player_as_new<'a>(
                                     index, base_attributes_ty, root_ty,
                                 ));
                             } else {
-                                ingest_logs.warn(format!(
-                                    "{}th pitch type in BaseAttributes ({}) does not match the \
+                                ingest_logs.warn(
+                                    TaxaIngestErrorCode::UnexpectedVersionData,
+                                    format!(
+                                        "{}th pitch type in BaseAttributes ({}) does not match the \
                                     corresponding pitch type in the root object ({})",
-                                    index, base_attributes_ty, root_ty,
-                                ));
+                                        index, base_attributes_ty, root_ty,
+                                    ),
+                                );
                             }
                         }
                     }
@@ -1228,12 +1345,15 @@ fn chron_player_as_new<'a>(
                         pitch_types.len()
                     ));
                 } else  {
-                    ingest_logs.warn(format!(
-                        "PitchTypes in BaseAttributes has length {}, but PitchTypes on the root \
-                        object has length {} (expected equal length)",
-                        base_attributes.pitch_types.len(),
-                        pitch_types.len()
-                    ));
+                    ingest_logs.warn(
+                        TaxaIngestErrorCode::UnexpectedVersionData,
+                        format!(
+                            "PitchTypes in BaseAttributes has length {}, but PitchTypes on the root \
+                            object has length {} (expected equal length)",
+                            base_attributes.pitch_types.len(),
+                            pitch_types.len()
+                        ),
+                    );
                 }
             }
 
@@ -1260,11 +1380,14 @@ fn chron_player_as_new<'a>(
                                     index, base_attributes_freq, root_freq,
                                 ));
                             } else {
-                                ingest_logs.warn(format!(
-                                    "{}th pitch frequency in BaseAttributes ({}) does not match the \
-                                    corresponding pitch frequency in the root object ({})",
-                                    index, base_attributes_freq, root_freq,
-                                ));
+                                ingest_logs.warn(
+                                    TaxaIngestErrorCode::UnexpectedVersionData,
+                                    format!(
+                                        "{}th pitch frequency in BaseAttributes ({}) does not match the \
+                                        corresponding pitch frequency in the root object ({})",
+                                        index, base_attributes_freq, root_freq,
+                                    ),
+                                );
                             }
                         }
                     }
@@ -1278,12 +1401,15 @@ fn chron_player_as_new<'a>(
                         pitch_selection.len()
                     ));
                 } else {
-                    ingest_logs.warn(format!(
-                        "PitchSelection in BaseAttributes has length {}, but PitchSelection on the \
-                        root object has length {} (expected equal length)",
-                        base_attributes.pitch_selection.len(),
-                        pitch_selection.len()
-                    ));
+                    ingest_logs.warn(
+                        TaxaIngestErrorCode::UnexpectedVersionData,
+                        format!(
+                            "PitchSelection in BaseAttributes has length {}, but PitchSelection on the \
+                            root object has length {} (expected equal length)",
+                            base_attributes.pitch_selection.len(),
+                            pitch_selection.len()
+                        ),
+                    );
                 }
             }
 
@@ -1295,7 +1421,7 @@ fn chron_player_as_new<'a>(
             .map(|(index, (ty, freq))| NewPlayerPitchTypeVersion {
                 mmolb_player_id: &entity.entity_id,
                 pitch_type_index: index as i32,
-                valid_from: entity.valid_from.naive_utc(),
+                valid_from: entity.valid_from,
                 valid_until: None,
                 // If this is not ok(), an error should already have been logged while doing
                 // the consistency check above
@@ -1306,12 +1432,15 @@ fn chron_player_as_new<'a>(
             })
             .collect_vec()
         } else {
-            ingest_logs.error(format!(
-                "Can't extract pitch types from BaseAttributes: PitchTypes was length {}, but \
+            ingest_logs.error(
+                TaxaIngestErrorCode::UnexpectedVersionData,
+                format!(
+                    "Can't extract pitch types from BaseAttributes: PitchTypes was length {}, but \
                  PitchSelection was length {} (expected equal length)",
-                base_attributes.pitch_types.len(),
-                base_attributes.pitch_selection.len()
-            ));
+                    base_attributes.pitch_types.len(),
+                    base_attributes.pitch_selection.len()
+                ),
+            );
             Vec::new()
         }
     } else {
@@ -1328,7 +1457,7 @@ fn chron_player_as_new<'a>(
                         .map(|(index, (ty, freq))| NewPlayerPitchTypeVersion {
                             mmolb_player_id: &entity.entity_id,
                             pitch_type_index: index as i32,
-                            valid_from: entity.valid_from.naive_utc(),
+                            valid_from: entity.valid_from,
                             valid_until: None,
                             // If this is not ok(), an error should already have been logged while doing
                             // the consistency check above
@@ -1339,28 +1468,37 @@ fn chron_player_as_new<'a>(
                         })
                         .collect_vec()
                 } else {
-                    ingest_logs.error(format!(
-                        "Can't extract pitch types from object root: PitchTypes was length {}, but \
-                        PitchSelection was length {} (expected equal length)",
-                        pitch_types.len(),
-                        pitch_selection.len()
-                    ));
+                    ingest_logs.error(
+                        TaxaIngestErrorCode::UnexpectedVersionData,
+                        format!(
+                            "Can't extract pitch types from object root: PitchTypes was length {}, but \
+                            PitchSelection was length {} (expected equal length)",
+                            pitch_types.len(),
+                            pitch_selection.len()
+                        ),
+                    );
                     Vec::new()
                 }
             } else {
-                ingest_logs.warn(format!(
-                    "Can't extract pitch types from object root: PitchTypes exists with {} \
+                ingest_logs.warn(
+                    TaxaIngestErrorCode::UnexpectedVersionData,
+                    format!(
+                        "Can't extract pitch types from object root: PitchTypes exists with {} \
                     entries, but PitchSelection does not exist.",
-                    pitch_types.len()
-                ));
+                        pitch_types.len()
+                    ),
+                );
                 Vec::new()
             }
         } else if let Ok(pitch_selection) = &entity.data.pitch_selection {
-            ingest_logs.warn(format!(
-                "Can't extract pitch types from object root: PitchSelection exists with {} \
+            ingest_logs.warn(
+                TaxaIngestErrorCode::UnexpectedVersionData,
+                format!(
+                    "Can't extract pitch types from object root: PitchSelection exists with {} \
                 entries, but PitchTypes does not exist.",
-                pitch_selection.len()
-            ));
+                    pitch_selection.len()
+                ),
+            );
             Vec::new()
         } else {
             Vec::new()
@@ -1376,12 +1514,15 @@ fn chron_player_as_new<'a>(
                 Ok(pitch_type) => Some(NewPlayerPitchTypeBonusVersion {
                     mmolb_player_id: &entity.entity_id,
                     pitch_type: taxa.pitch_type_id((*pitch_type).into()),
-                    valid_from: entity.valid_from.naive_utc(),
+                    valid_from: entity.valid_from,
                     valid_until: None,
                     bonus: *bonus,
                 }),
                 Err(err) => {
-                    ingest_logs.error(format!("Unrecognized pitch type {err} in PitchTypeBonuses"));
+                    ingest_logs.error(
+                        TaxaIngestErrorCode::UnrecognizedValue,
+                        format!("Unrecognized pitch type {err} in PitchTypeBonuses"),
+                    );
                     None
                 }
             })
@@ -1397,12 +1538,15 @@ fn chron_player_as_new<'a>(
                 Ok(pitch_category) => Some(NewPlayerPitchCategoryBonusVersion {
                     mmolb_player_id: &entity.entity_id,
                     pitch_category: taxa.pitch_category_id((*pitch_category).into()),
-                    valid_from: entity.valid_from.naive_utc(),
+                    valid_from: entity.valid_from,
                     valid_until: None,
                     bonus: *bonus,
                 }),
                 Err(err) => {
-                    ingest_logs.error(format!("Unrecognized pitch type {err} in PitchTypeBonuses"));
+                    ingest_logs.error(
+                        TaxaIngestErrorCode::UnrecognizedValue,
+                        format!("Unrecognized pitch type {err} in PitchTypeBonuses"),
+                    );
                     None
                 }
             })
@@ -1413,7 +1557,7 @@ fn chron_player_as_new<'a>(
 
     let player = NewPlayerVersion {
         mmolb_player_id: &entity.entity_id,
-        valid_from: entity.valid_from.naive_utc(),
+        valid_from: entity.valid_from,
         valid_until: None,
         first_name: &entity.data.first_name,
         last_name: &entity.data.last_name,
@@ -1531,7 +1675,7 @@ fn report_from_talk<'e>(
     let report_version = NewPlayerReportVersion {
         mmolb_player_id: &entity.entity_id,
         category: taxa.attribute_category_id(category),
-        valid_from: entity.valid_from.naive_utc(),
+        valid_from: entity.valid_from,
         valid_until: None,
         season,
         day_type,
@@ -1563,7 +1707,7 @@ fn player_report_attribute_version_as_new<'a>(
         mmolb_player_id: &entity.entity_id,
         category: taxa.attribute_category_id(category),
         attribute: taxa.attribute_id((*attribute).into()),
-        valid_from: entity.valid_from.naive_utc(),
+        valid_from: entity.valid_from,
         valid_until: None,
         base_stars: match stars {
             TalkStars::Simple(stars) => Some(*stars as i32),
@@ -1603,7 +1747,7 @@ fn player_report_attribute_version_as_new_from_new_format<'a>(
         mmolb_player_id: &entity.entity_id,
         category: taxa.attribute_category_id(category),
         attribute: taxa.attribute_id((*attribute).into()),
-        valid_from: entity.valid_from.naive_utc(),
+        valid_from: entity.valid_from,
         valid_until: None,
         base_stars: Some(stars.base_stars as i32),
         base_total: Some(stars.base_total),
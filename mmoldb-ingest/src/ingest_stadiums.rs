@@ -0,0 +1,139 @@
+use crate::ingest::{IngestibleFromVersions, VersionIngestLogs};
+use crate::{IngestFatalError, PreparedIngestItem};
+use chron::ChronEntity;
+use chrono::{DateTime, Utc};
+use futures::Stream;
+use itertools::Itertools;
+use mmoldb_db::async_db::ProcessingPass;
+use mmoldb_db::db::NewStadiumVersionExt;
+use mmoldb_db::models::{NewStadiumVersion, NewVersionProcessed};
+use mmoldb_db::taxa::Taxa;
+use mmoldb_db::{AsyncPgConnection, PgConnection, QueryResult, async_db, db};
+use serde::Deserialize;
+use std::num::NonZero;
+
+/// mmolb_parsing doesn't have a stadium type yet, so this only declares
+/// the fields we store. Everything except the name is optional because
+/// older versions may not have it.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ChronStadium {
+    pub name: String,
+    #[serde(default, rename = "TeamID", alias = "Team")]
+    pub team_id: Option<String>,
+    #[serde(default)]
+    pub modifications: Vec<ChronStadiumModification>,
+    #[serde(default)]
+    pub capacity: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ChronStadiumModification {
+    pub name: String,
+}
+
+pub struct StadiumIngestFromVersions;
+
+impl IngestibleFromVersions for StadiumIngestFromVersions {
+    type Entity = ChronStadium;
+    type Ident = String;
+
+    fn trim_unused(version: &serde_json::Value) -> serde_json::Value {
+        version.clone()
+    }
+
+    fn ident_raw(entity: &ChronEntity<serde_json::Value>) -> Result<Self::Ident, IngestFatalError> {
+        Ok(entity.entity_id.to_string())
+    }
+
+    fn ident(entity: &ChronEntity<Self::Entity>) -> Self::Ident {
+        entity.entity_id.to_string()
+    }
+
+    fn insert_batch(
+        conn: &mut PgConnection,
+        taxa: &Taxa,
+        versions: &Vec<PreparedIngestItem<Self::Ident, Self::Entity>>,
+        _: NonZero<usize>,
+    ) -> QueryResult<(usize, usize)> {
+        let new_stadium_versions = versions
+            .iter()
+            .map(|item| match item {
+                PreparedIngestItem::MarkAsSkipped(entity_id, valid_from) => {
+                    let vp = NewVersionProcessed {
+                        kind: "stadium",
+                        entity_id,
+                        valid_from: *valid_from,
+                        skipped: true,
+                        fatal_error: false,
+                    };
+                    (vp, None, Vec::new())
+                }
+                PreparedIngestItem::MarkAsFatalError(entity_id, valid_from) => {
+                    let vp = NewVersionProcessed {
+                        kind: "stadium",
+                        entity_id,
+                        valid_from: *valid_from,
+                        skipped: false,
+                        fatal_error: true,
+                    };
+                    (vp, None, Vec::new())
+                }
+                PreparedIngestItem::DoIngest(stadium) => chron_stadium_as_new(
+                    taxa,
+                    &stadium.entity_id,
+                    stadium.valid_from,
+                    &stadium.data,
+                ),
+            })
+            .collect_vec();
+
+        db::insert_stadium_versions_all(conn, &new_stadium_versions)
+    }
+
+    async fn stream_unprocessed_versions(
+        conn: &mut AsyncPgConnection,
+        kind: &str,
+        pass: ProcessingPass,
+    ) -> QueryResult<impl Stream<Item = QueryResult<ChronEntity<serde_json::Value>>>> {
+        async_db::stream_unprocessed_versions(conn, kind, pass).await
+    }
+}
+
+fn chron_stadium_as_new<'a>(
+    taxa: &Taxa,
+    stadium_id: &'a str,
+    valid_from: DateTime<Utc>,
+    stadium: &'a ChronStadium,
+) -> NewStadiumVersionExt<'a> {
+    let mut ingest_logs = VersionIngestLogs::new(taxa, "stadium", stadium_id, valid_from);
+
+    let new_processed = NewVersionProcessed {
+        kind: "stadium",
+        entity_id: stadium_id,
+        valid_from,
+        skipped: false,
+        fatal_error: false,
+    };
+
+    if stadium.team_id.is_none() {
+        ingest_logs.info("Stadium version has no team");
+    }
+
+    let new_stadium = NewStadiumVersion {
+        mmolb_stadium_id: stadium_id,
+        valid_from,
+        valid_until: None,
+        name: &stadium.name,
+        mmolb_team_id: stadium.team_id.as_deref(),
+        modifications: stadium
+            .modifications
+            .iter()
+            .map(|m| m.name.as_str())
+            .collect(),
+        capacity: stadium.capacity,
+    };
+
+    (new_processed, Some(new_stadium), ingest_logs.into_vec())
+}
@@ -1,7 +1,8 @@
-use crate::{IngestibleFromVersions, PreparedIngestItem};
+use crate::{IngestFatalError, IngestibleFromVersions, PreparedIngestItem};
 use crate::ingest::VersionIngestLogs;
 use crate::ingest_feed_shared::{
     FEED_INVERSION_EVENT_END, FEED_INVERSION_EVENT_START, FeedItemContainer,
+    deserialize_feed_event, feed_event_texts, raw_feed_event_index,
 };
 use chron::ChronEntity;
 use chrono::{DateTime, Utc};
@@ -9,9 +10,15 @@ use futures::Stream;
 use itertools::Itertools;
 use mmolb_parsing::enums::LinkType;
 use mmolb_parsing::team_feed::ParsedTeamFeedEventText;
-use mmoldb_db::models::{NewFeedEventProcessed, NewTeamGamePlayed, NewVersionIngestLog};
-use mmoldb_db::taxa::Taxa;
+use mmoldb_db::async_db::ProcessingPass;
+use mmoldb_db::models::{
+    NewFeedEventFailure, NewFeedEventProcessed, NewTeamGamePlayed, NewTeamTransaction,
+    NewVersionIngestLog,
+};
+use mmoldb_db::taxa::{Taxa, TaxaIngestErrorCode};
 use mmoldb_db::{AsyncPgConnection, Connection, PgConnection, QueryResult, async_db, db};
+use std::num::NonZero;
+use tracing::warn;
 
 pub struct TeamFeedIngestFromVersions;
 
@@ -23,19 +30,23 @@ impl IngestibleFromVersions for TeamFeedIngestFromVersions {
         version.clone()
     }
 
-    fn ident_raw(entity: &ChronEntity<serde_json::Value>) -> Self::Ident {
-        let _ = entity; // This will be used if that TODO gets hit
-        todo!("Not sure this will ever get called. If not, can I refactor it away?")
+    fn ident_raw(entity: &ChronEntity<serde_json::Value>) -> Result<Self::Ident, IngestFatalError> {
+        Ok((entity.entity_id.clone(), raw_feed_event_index(entity)?))
     }
 
     fn ident(entity: &ChronEntity<Self::Entity>) -> Self::Ident {
         (entity.entity_id.to_string(), entity.data.feed_event_index)
     }
 
+    fn feed_event_index(entity: &ChronEntity<serde_json::Value>) -> Option<i32> {
+        raw_feed_event_index(entity).ok()
+    }
+
     fn insert_batch(
         conn: &mut PgConnection,
-        _: &Taxa,
+        taxa: &Taxa,
         versions: &Vec<PreparedIngestItem<Self::Ident, Self::Entity>>,
+        _: NonZero<usize>,
     ) -> QueryResult<(usize, usize)> {
         let new_versions = versions
             .iter()
@@ -45,39 +56,46 @@ impl IngestibleFromVersions for TeamFeedIngestFromVersions {
                         kind: "team_feed", // TODO Don't hard-code this
                         entity_id,
                         feed_event_index: *feed_event_index,
-                        valid_from: valid_from.naive_utc(),
+                        valid_from: *valid_from,
                         skipped: true,
                         fatal_error: false,
                     };
-                    (fep, None, Vec::new())
+                    (fep, None, Vec::new(), None, None)
                 }
                 PreparedIngestItem::MarkAsFatalError((entity_id, feed_event_index), valid_from) => {
                     let fep = NewFeedEventProcessed {
                         kind: "team_feed", // TODO Don't hard-code this
                         entity_id,
                         feed_event_index: *feed_event_index,
-                        valid_from: valid_from.naive_utc(),
+                        valid_from: *valid_from,
                         skipped: false,
                         fatal_error: true,
                     };
-                    (fep, None, Vec::new())
+                    (fep, None, Vec::new(), None, None)
                 }
-                PreparedIngestItem::DoIngest(team) => chron_team_feed_as_new(&team.entity_id, team.valid_from, &team.data),
+                PreparedIngestItem::DoIngest(team) => chron_team_feed_as_new(taxa, &team.entity_id, team.valid_from, &team.data),
             })
             .collect_vec();
 
-        conn.transaction(|c| db::insert_team_feed_versions(c, &new_versions))
+        let texts = feed_event_texts("team_feed", versions);
+
+        conn.transaction(|c| {
+            db::insert_feed_event_texts(c, &texts)?;
+            db::insert_team_feed_versions(c, &new_versions)
+        })
     }
 
     async fn stream_unprocessed_versions(
         conn: &mut AsyncPgConnection,
         kind: &str,
+        pass: ProcessingPass,
     ) -> QueryResult<impl Stream<Item = QueryResult<ChronEntity<serde_json::Value>>>> {
-        async_db::stream_unprocessed_feed_event_versions(conn, kind).await
+        async_db::stream_unprocessed_feed_event_versions(conn, kind, pass).await
     }
 }
 
 pub fn chron_team_feed_as_new<'a>(
+    taxa: &Taxa,
     team_id: &'a str,
     valid_from: DateTime<Utc>,
     item: &'a FeedItemContainer,
@@ -85,9 +103,17 @@ pub fn chron_team_feed_as_new<'a>(
     NewFeedEventProcessed<'a>,
     Option<NewTeamGamePlayed<'a>>,
     Vec<NewVersionIngestLog<'a>>,
+    Option<NewFeedEventFailure<'a>>,
+    Option<NewTeamTransaction<'a>>,
 ) {
     // TODO Can I avoid repeating this string constant?
-    let mut ingest_logs = VersionIngestLogs::new("team_feed", team_id, valid_from);
+    let mut ingest_logs = VersionIngestLogs::for_feed_event(
+        taxa,
+        "team_feed",
+        team_id,
+        item.feed_event_index,
+        valid_from,
+    );
 
     // This is mut so later code can mark a fatal error on this version.
     // An ideal architecture would not need this to be mut.
@@ -95,7 +121,7 @@ pub fn chron_team_feed_as_new<'a>(
         kind: "team_feed",
         entity_id: team_id,
         feed_event_index: item.feed_event_index,
-        valid_from: valid_from.naive_utc(),
+        valid_from,
         skipped: false,
         fatal_error: false,
     };
@@ -105,16 +131,19 @@ pub fn chron_team_feed_as_new<'a>(
         // TODO Apply the same TODOs from ingest_player_feed
         ingest_logs.info("Ignoring event version from the Feed Inversion Event");
 
-        return (processed, None, ingest_logs.into_vec());
+        return (processed, None, ingest_logs.into_vec(), None, None);
     }
 
     if let Some(prev_event) = &item.prev_data {
         if item.prev_valid_from.is_none() {
-            ingest_logs.warn(format!(
-                "Team {} feed event index {} had a previous event, but \
+            ingest_logs.warn(
+                TaxaIngestErrorCode::FeedEventChanged,
+                format!(
+                    "Team {} feed event index {} had a previous event, but \
                 did not have prev_valid_from",
-                team_id, item.feed_event_index,
-            ));
+                    team_id, item.feed_event_index,
+                ),
+            );
         }
 
         if item.prev_valid_from.is_none_or(|prev_valid_from| {
@@ -122,12 +151,15 @@ pub fn chron_team_feed_as_new<'a>(
                 && prev_valid_from <= FEED_INVERSION_EVENT_END
         }) {
             if item.prev_valid_from.is_none() {
-                ingest_logs.warn(format!(
-                    "Can't check whether team {} feed event index {}'s previous event \
+                ingest_logs.warn(
+                    TaxaIngestErrorCode::FeedEventChanged,
+                    format!(
+                        "Can't check whether team {} feed event index {}'s previous event \
                     was from the Feed Inversion Event because it's missing prev_valid_from. \
                     Assuming it was to avoid losing data.",
-                    team_id, item.feed_event_index,
-                ));
+                        team_id, item.feed_event_index,
+                    ),
+                );
             } else {
                 ingest_logs.info(format!(
                     "Team {} feed event index {} had a previous event, but it was from \
@@ -146,49 +178,63 @@ pub fn chron_team_feed_as_new<'a>(
                 team_id, item.feed_event_index,
             ));
         } else {
-            ingest_logs.error(format!(
-                "Team {} feed event index {} had a previous version without special \
+            ingest_logs.error(
+                TaxaIngestErrorCode::FeedEventChanged,
+                format!(
+                    "Team {} feed event index {} had a previous version without special \
                 handling. Marking this version as a fatal error.\n\
                 previous version text: {}\n\
                 previous version valid_from: {}\n\
                 this version text: {}\n\
                 this version valid_from: {}",
-                team_id,
-                item.feed_event_index,
-                prev_event.text,
-                if let Some(dt) = item.prev_valid_from {
-                    format!("{dt}")
-                } else {
-                    "(missing)".to_string()
-                },
-                item.data.text,
-                valid_from,
-            ));
+                    team_id,
+                    item.feed_event_index,
+                    prev_event.text,
+                    if let Some(dt) = item.prev_valid_from {
+                        format!("{dt}")
+                    } else {
+                        "(missing)".to_string()
+                    },
+                    item.data.text,
+                    valid_from,
+                ),
+            );
 
             processed.fatal_error = true;
-            return (processed, None, ingest_logs.into_vec());
+            return (processed, None, ingest_logs.into_vec(), None, None);
         }
     }
 
     // There is a bug in mmolb_parsing that causes a panic when an
     // augment's text is empty
     if item.data.text.is_empty() {
-        return (processed, None, ingest_logs.into_vec());
+        return (processed, None, ingest_logs.into_vec(), None, None);
     }
 
     let parsed_event = mmolb_parsing::team_feed::parse_team_feed_event(&item.data);
 
+    let coins_moved = coins_moved(&parsed_event);
+
     let is_game_result = if let ParsedTeamFeedEventText::GameResult { .. } = &parsed_event {
         true
     } else {
         false
     };
 
+    let mut failure = None;
     let game_outcome = match parsed_event {
         ParsedTeamFeedEventText::ParseError { error, text } => {
             // I'm making this a warning because we don't care about most event types
             // (and we can handle having a game for which we don't know the end time)
-            ingest_logs.warn(format!("Error parsing \"{text}\": {error}"));
+            let message = format!("Error parsing \"{text}\": {error}");
+            ingest_logs.warn(TaxaIngestErrorCode::FeedEventParseError, message.clone());
+            failure = Some(NewFeedEventFailure {
+                kind: "team_feed",
+                entity_id: team_id,
+                feed_event_index: item.feed_event_index,
+                valid_from,
+                error_message: message,
+            });
             None
         }
         // Get game
@@ -223,7 +269,7 @@ pub fn chron_team_feed_as_new<'a>(
                         err.count()
                     );
                     if is_game_result {
-                        ingest_logs.warn(msg);
+                        ingest_logs.warn(TaxaIngestErrorCode::UnexpectedVersionData, msg);
                     } else {
                         ingest_logs.info(msg);
                     }
@@ -283,5 +329,136 @@ pub fn chron_team_feed_as_new<'a>(
         | ParsedTeamFeedEventText::NewRetirement { .. } => None,
     };
 
-    (processed, game_outcome, ingest_logs.into_vec())
+    let transaction = coins_moved.map(|(source, amount)| NewTeamTransaction {
+        mmolb_team_id: team_id,
+        feed_event_index: item.feed_event_index,
+        time: item.data.timestamp,
+        amount,
+        source,
+        mmolb_game_id: game_outcome.as_ref().map(|game| game.mmolb_game_id),
+    });
+
+    (processed, game_outcome, ingest_logs.into_vec(), failure, transaction)
+}
+
+/// What kind of event moved coins, and how many it moved: positive if the
+/// team earned them, negative if it spent them. `None` for events that
+/// don't move coins.
+fn coins_moved<S>(event: &ParsedTeamFeedEventText<S>) -> Option<(&'static str, i32)> {
+    let (source, amount) = match event {
+        // Income
+        ParsedTeamFeedEventText::EndGameIncome { income, .. } => {
+            ("end_game_income", *income as i32)
+        }
+        ParsedTeamFeedEventText::PhotoContest { earned_coins, .. } => {
+            ("photo_contest", *earned_coins as i32)
+        }
+        ParsedTeamFeedEventText::SimulacrumPayout { earned_coins, .. } => {
+            ("simulacrum_payout", *earned_coins as i32)
+        }
+        ParsedTeamFeedEventText::GildedUmpiresPayout { earned_coins, .. } => {
+            ("gilded_umpires_payout", *earned_coins as i32)
+        }
+        ParsedTeamFeedEventText::Prosperous { income, .. } => ("prosperous", *income as i32),
+        ParsedTeamFeedEventText::WonLottery { amount, .. } => ("lottery_winnings", *amount as i32),
+        // Spending
+        ParsedTeamFeedEventText::DonatedToLottery { amount, .. } => {
+            ("lottery_donation", -(*amount as i32))
+        }
+        ParsedTeamFeedEventText::Restyle { cost, .. } => ("restyle", -(*cost as i32)),
+        ParsedTeamFeedEventText::Enchantment { cost, .. } => ("enchantment", -(*cost as i32)),
+        ParsedTeamFeedEventText::PlayerTrained { cost, .. } => ("training", -(*cost as i32)),
+        _ => return None,
+    };
+
+    Some((source, amount))
+}
+
+/// Fills in `data.team_transactions` from the latest version of every team
+/// feed event, without reprocessing the feed. Transactions that are already
+/// recorded are left alone. Each batch is recorded in the admin audit log
+/// under `actor`. Returns the number of transactions added.
+pub fn rebuild_team_transactions(
+    conn: &mut PgConnection,
+    batch_size: i64,
+    actor: &str,
+) -> Result<usize, IngestFatalError> {
+    let mut after: Option<(String, i32)> = None;
+    let mut num_added = 0;
+    loop {
+        let page = db::latest_feed_event_versions_page(
+            conn,
+            "team_feed",
+            after.as_ref().map(|(entity_id, index)| (entity_id.as_str(), *index)),
+            batch_size,
+        )?;
+        let Some((last_entity_id, last_index, _, _)) = page.last() else {
+            break;
+        };
+        let next_after = (last_entity_id.clone(), *last_index);
+
+        let events = page
+            .iter()
+            // See the corresponding check in chron_team_feed_as_new
+            .filter(|(_, _, valid_from, _)| {
+                !(FEED_INVERSION_EVENT_START <= *valid_from
+                    && *valid_from <= FEED_INVERSION_EVENT_END)
+            })
+            .filter_map(|(entity_id, feed_event_index, _, data)| {
+                match deserialize_feed_event(data) {
+                    Ok(event) => Some((entity_id, *feed_event_index, event)),
+                    Err(err) => {
+                        warn!("Skipping team {entity_id} feed event {feed_event_index}: {err}");
+                        None
+                    }
+                }
+            })
+            .collect_vec();
+
+        let transactions = events
+            .iter()
+            // See the corresponding check in chron_team_feed_as_new
+            .filter(|(_, _, event)| !event.text.is_empty())
+            .filter_map(|(entity_id, feed_event_index, event)| {
+                let parsed_event = mmolb_parsing::team_feed::parse_team_feed_event(event);
+                let (source, amount) = coins_moved(&parsed_event)?;
+                let game_link = event
+                    .links
+                    .iter()
+                    .filter(|link| link.link_type == Ok(LinkType::Game))
+                    .exactly_one()
+                    .ok();
+                Some(NewTeamTransaction {
+                    mmolb_team_id: entity_id,
+                    feed_event_index: *feed_event_index,
+                    time: event.timestamp,
+                    amount,
+                    source,
+                    mmolb_game_id: game_link.map(|link| link.id.as_str()),
+                })
+            })
+            .collect_vec();
+
+        num_added += db::backfill_team_transactions(conn, &transactions, actor)?;
+        after = Some(next_after);
+    }
+
+    Ok(num_added)
+}
+
+/// Re-parses a team feed event from the dead-letter queue. Returns the
+/// error message if it still can't be parsed.
+pub fn reparse_team_feed_event(raw_event: &serde_json::Value) -> Result<(), String> {
+    let event = deserialize_feed_event(raw_event)?;
+    // See the corresponding check in chron_team_feed_as_new
+    if event.text.is_empty() {
+        return Ok(());
+    }
+
+    match mmolb_parsing::team_feed::parse_team_feed_event(&event) {
+        ParsedTeamFeedEventText::ParseError { error, text } => {
+            Err(format!("Error parsing \"{text}\": {error}"))
+        }
+        _ => Ok(()),
+    }
 }
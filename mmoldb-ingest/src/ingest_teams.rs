@@ -6,11 +6,13 @@ use itertools::Itertools;
 use mmolb_parsing::{
     AddedLater, AddedLaterResult, MaybeRecognizedResult, NotRecognized, team::TeamPlayerCollection, enums::FullSlot,
 };
+use mmoldb_db::async_db::ProcessingPass;
 use mmoldb_db::models::{NewTeamPlayerVersion, NewTeamVersion, NewVersionIngestLog, NewVersionProcessed};
-use mmoldb_db::taxa::Taxa;
+use mmoldb_db::taxa::{Taxa, TaxaIngestErrorCode};
 use mmoldb_db::{AsyncPgConnection, BestEffortSlot, PgConnection, QueryResult, async_db, db};
+use std::num::NonZero;
 use std::str::FromStr;
-use crate::PreparedIngestItem;
+use crate::{IngestFatalError, PreparedIngestItem};
 
 pub struct TeamIngestFromVersions;
 
@@ -118,8 +120,8 @@ impl IngestibleFromVersions for TeamIngestFromVersions {
         }
     }
 
-    fn ident_raw(entity: &ChronEntity<serde_json::Value>) -> Self::Ident {
-        entity.entity_id.to_string()
+    fn ident_raw(entity: &ChronEntity<serde_json::Value>) -> Result<Self::Ident, IngestFatalError> {
+        Ok(entity.entity_id.to_string())
     }
 
     fn ident(entity: &ChronEntity<Self::Entity>) -> Self::Ident {
@@ -130,6 +132,7 @@ impl IngestibleFromVersions for TeamIngestFromVersions {
         conn: &mut PgConnection,
         taxa: &Taxa,
         versions: &Vec<PreparedIngestItem<Self::Ident, Self::Entity>>,
+        _: NonZero<usize>,
     ) -> QueryResult<(usize, usize)> {
         let new_team_versions = versions
             .iter()
@@ -138,7 +141,7 @@ impl IngestibleFromVersions for TeamIngestFromVersions {
                     let vp = NewVersionProcessed {
                         kind: "team", // TODO Don't hard-code this
                         entity_id,
-                        valid_from: valid_from.naive_utc(),
+                        valid_from: *valid_from,
                         skipped: true,
                         fatal_error: false,
                     };
@@ -148,7 +151,7 @@ impl IngestibleFromVersions for TeamIngestFromVersions {
                     let vp = NewVersionProcessed {
                         kind: "team", // TODO Don't hard-code this
                         entity_id,
-                        valid_from: valid_from.naive_utc(),
+                        valid_from: *valid_from,
                         skipped: false,
                         fatal_error: true,
                     };
@@ -164,8 +167,9 @@ impl IngestibleFromVersions for TeamIngestFromVersions {
     async fn stream_unprocessed_versions(
         conn: &mut AsyncPgConnection,
         kind: &str,
+        pass: ProcessingPass,
     ) -> QueryResult<impl Stream<Item = QueryResult<ChronEntity<serde_json::Value>>>> {
-        async_db::stream_unprocessed_versions(conn, kind).await
+        async_db::stream_unprocessed_versions(conn, kind, pass).await
     }
 }
 
@@ -181,12 +185,12 @@ fn chron_team_as_new<'a>(
     Vec<NewVersionIngestLog<'a>>,
 ) {
     // TODO Can I avoid repeating this string constant?
-    let mut ingest_logs = VersionIngestLogs::new("team", team_id, valid_from);
+    let mut ingest_logs = VersionIngestLogs::new(taxa, "team", team_id, valid_from);
 
     let new_processed = NewVersionProcessed {
         kind: "team", // TODO Avoid hard-coding this
         entity_id: team_id,
-        valid_from: valid_from.naive_utc(),
+        valid_from,
         skipped: false,
         fatal_error: false,
     };
@@ -247,6 +251,7 @@ fn chron_team_as_new<'a>(
                     Ok(None) => {}
                     Err(AddedLater) => {
                         ingest_logs.warn(
+                            TaxaIngestErrorCode::UnexpectedVersionData,
                             "Team version had `ballpark_use_city` but not `ballpark_word_1`, \
                             ballpark name may not be correct",
                         );
@@ -259,6 +264,7 @@ fn chron_team_as_new<'a>(
                     Ok(None) => {}
                     Err(AddedLater) => {
                         ingest_logs.warn(
+                            TaxaIngestErrorCode::UnexpectedVersionData,
                             "Team version had `ballpark_use_city` but not `ballpark_word_2`, \
                             ballpark name may not be correct",
                         );
@@ -272,12 +278,14 @@ fn chron_team_as_new<'a>(
                         ballpark_name_components.push(suffix);
                     }
                     Ok(Err(NotRecognized(non_string))) => {
-                        ingest_logs.error(format!(
-                            "Team ballpark suffix was a non-string value: {non_string:?}",
-                        ));
+                        ingest_logs.error(
+                            TaxaIngestErrorCode::UnrecognizedValue,
+                            format!("Team ballpark suffix was a non-string value: {non_string:?}"),
+                        );
                     }
                     Err(AddedLater) => {
                         ingest_logs.warn(
+                            TaxaIngestErrorCode::UnexpectedVersionData,
                             "Team version had `ballpark_use_city` but not `ballpark_suffix`, \
                             ballpark name may not be correct",
                         );
@@ -299,7 +307,7 @@ fn chron_team_as_new<'a>(
 
     let new_team = NewTeamVersion {
         mmolb_team_id: team_id,
-        valid_from: valid_from.naive_utc(),
+        valid_from,
         valid_until: None,
         name: &team.name,
         emoji: &team.emoji,
@@ -320,7 +328,10 @@ fn chron_team_as_new<'a>(
         .count();
 
     if num_unique != new_team_players.len() {
-        ingest_logs.error("Got a duplicate team player");
+        ingest_logs.error(
+            TaxaIngestErrorCode::UnexpectedVersionData,
+            "Got a duplicate team player",
+        );
     }
 
     (new_processed, Some(new_team), new_team_players, ingest_logs.into_vec())
@@ -340,7 +351,7 @@ pub fn chron_team_player_as_new<'a>(
     NewTeamPlayerVersion {
         mmolb_team_id: team_id,
         team_player_index: idx as i32,
-        valid_from: valid_from.naive_utc(),
+        valid_from,
         valid_until: None,
         first_name: &pl.first_name,
         last_name: &pl.last_name,
@@ -352,17 +363,23 @@ pub fn chron_team_player_as_new<'a>(
         slot: match slot {
             Ok(Ok(FullSlot::Roster(slot))) => Some(taxa.slot_id(BestEffortSlot::from_slot(*slot).into())),
             Ok(Ok(FullSlot::Bench(slot))) => {
-                ingest_logs.error(format!(
-                    "Player on the roster had a bench slot ({})",
-                    slot,
-                ));
+                ingest_logs.error(
+                    TaxaIngestErrorCode::UnexpectedVersionData,
+                    format!(
+                        "Player on the roster had a bench slot ({})",
+                        slot,
+                    ),
+                );
                 None
             },
             Ok(Err(NotRecognized(other))) => {
-                ingest_logs.error(format!(
-                    "Failed to parse {} {}'s slot ({other:?}",
-                    pl.first_name, pl.last_name
-                ));
+                ingest_logs.error(
+                    TaxaIngestErrorCode::UnrecognizedValue,
+                    format!(
+                        "Failed to parse {} {}'s slot ({other:?}",
+                        pl.first_name, pl.last_name
+                    ),
+                );
                 None
             }
             Err(AddedLater) => None,
@@ -372,10 +389,13 @@ pub fn chron_team_player_as_new<'a>(
                 Some(taxa.slot_id(BestEffortSlot::from_position(*position).into()))
             }
             Some(Err(NotRecognized(other))) => {
-                ingest_logs.error(format!(
-                    "Failed to parse {} {}'s position ({other:?}",
-                    pl.first_name, pl.last_name
-                ));
+                ingest_logs.error(
+                    TaxaIngestErrorCode::UnrecognizedValue,
+                    format!(
+                        "Failed to parse {} {}'s position ({other:?}",
+                        pl.first_name, pl.last_name
+                    ),
+                );
                 None
             }
             None => None,
@@ -0,0 +1,182 @@
+use crate::{IngestFatalError, PreparedIngestItem};
+use crate::ingest::{IngestibleFromVersions, VersionIngestLogs};
+use chron::ChronEntity;
+use chrono::{DateTime, Utc};
+use futures::Stream;
+use itertools::Itertools;
+use mmoldb_db::async_db::ProcessingPass;
+use mmoldb_db::db::{NewSeasonPhases, NewTimeVersionExt};
+use mmoldb_db::models::{NewSeasonPhase, NewVersionProcessed};
+use mmoldb_db::taxa::{Taxa, TaxaDayType, TaxaIngestErrorCode};
+use mmoldb_db::{AsyncPgConnection, PgConnection, QueryResult, async_db, db};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::num::NonZero;
+
+/// mmolb_parsing doesn't have a time type yet, so this only declares the
+/// fields we store. Phases that haven't been scheduled yet may be null.
+#[derive(Debug, Deserialize)]
+pub struct ChronTime {
+    #[serde(alias = "SeasonNumber")]
+    pub season_number: i32,
+    #[serde(default, alias = "PhaseTimes")]
+    pub phase_times: HashMap<String, Option<DateTime<Utc>>>,
+}
+
+/// Which day type each key of `phase_times` corresponds to. Phases that
+/// don't have a day type (yet) are `Ok(None)`, and unrecognized phases are
+/// `Err`.
+fn phase_day_type(phase: &str) -> Result<Option<TaxaDayType>, ()> {
+    match phase {
+        "Preseason" => Ok(Some(TaxaDayType::Preseason)),
+        "RegularSeason" => Ok(Some(TaxaDayType::RegularDay)),
+        "SuperstarBreak" => Ok(Some(TaxaDayType::SuperstarBreak)),
+        "SuperstarGame" => Ok(Some(TaxaDayType::SuperstarGame)),
+        "SuperstarDay" => Ok(Some(TaxaDayType::SuperstarDay)),
+        "HomeRunChallenge" => Ok(None),
+        "RegularSeasonResume" => Ok(Some(TaxaDayType::RegularDay)),
+        "PostseasonPreview" => Ok(Some(TaxaDayType::PostseasonPreview)),
+        "PostseasonRound1" => Ok(Some(TaxaDayType::PostseasonRound1)),
+        "PostseasonRound2" => Ok(Some(TaxaDayType::PostseasonRound2)),
+        "PostseasonRound3" => Ok(Some(TaxaDayType::PostseasonRound3)),
+        "Election" => Ok(Some(TaxaDayType::Election)),
+        "Holiday" => Ok(Some(TaxaDayType::Holiday)),
+        "Event" => Ok(Some(TaxaDayType::Event)),
+        "SpecialEvent" => Ok(Some(TaxaDayType::SpecialEvent)),
+        "Offseason" => Ok(Some(TaxaDayType::Offseason)),
+        _ => Err(()),
+    }
+}
+
+pub struct TimeIngestFromVersions;
+
+impl IngestibleFromVersions for TimeIngestFromVersions {
+    type Entity = ChronTime;
+    type Ident = String;
+
+    fn trim_unused(version: &serde_json::Value) -> serde_json::Value {
+        version.clone()
+    }
+
+    fn ident_raw(entity: &ChronEntity<serde_json::Value>) -> Result<Self::Ident, IngestFatalError> {
+        Ok(entity.entity_id.to_string())
+    }
+
+    fn ident(entity: &ChronEntity<Self::Entity>) -> Self::Ident {
+        entity.entity_id.to_string()
+    }
+
+    fn insert_batch(
+        conn: &mut PgConnection,
+        taxa: &Taxa,
+        versions: &Vec<PreparedIngestItem<Self::Ident, Self::Entity>>,
+        _: NonZero<usize>,
+    ) -> QueryResult<(usize, usize)> {
+        let new_time_versions = versions
+            .iter()
+            .map(|item| match item {
+                PreparedIngestItem::MarkAsSkipped(entity_id, valid_from) => {
+                    let vp = NewVersionProcessed {
+                        kind: "time",
+                        entity_id,
+                        valid_from: *valid_from,
+                        skipped: true,
+                        fatal_error: false,
+                    };
+                    (vp, None, Vec::new())
+                }
+                PreparedIngestItem::MarkAsFatalError(entity_id, valid_from) => {
+                    let vp = NewVersionProcessed {
+                        kind: "time",
+                        entity_id,
+                        valid_from: *valid_from,
+                        skipped: false,
+                        fatal_error: true,
+                    };
+                    (vp, None, Vec::new())
+                }
+                PreparedIngestItem::DoIngest(time) => {
+                    chron_time_as_new(taxa, &time.entity_id, time.valid_from, &time.data)
+                }
+            })
+            .collect_vec();
+
+        db::insert_time_versions_all(conn, &new_time_versions)
+    }
+
+    async fn stream_unprocessed_versions(
+        conn: &mut AsyncPgConnection,
+        kind: &str,
+        pass: ProcessingPass,
+    ) -> QueryResult<impl Stream<Item = QueryResult<ChronEntity<serde_json::Value>>>> {
+        async_db::stream_unprocessed_versions(conn, kind, pass).await
+    }
+}
+
+fn chron_time_as_new<'a>(
+    taxa: &Taxa,
+    time_id: &'a str,
+    valid_from: DateTime<Utc>,
+    time: &ChronTime,
+) -> NewTimeVersionExt<'a> {
+    let mut ingest_logs = VersionIngestLogs::new(taxa, "time", time_id, valid_from);
+
+    let new_processed = NewVersionProcessed {
+        kind: "time",
+        entity_id: time_id,
+        valid_from,
+        skipped: false,
+        fatal_error: false,
+    };
+
+    // Each phase ends when the next one starts, including phases that
+    // don't have a day type
+    let scheduled_phases = time
+        .phase_times
+        .iter()
+        .filter_map(|(phase, started_at)| Some((started_at.as_ref()?, phase)))
+        .sorted()
+        .collect_vec();
+
+    let mut phases = Vec::new();
+    for (i, (started_at, phase)) in scheduled_phases.iter().enumerate() {
+        let day_type = match phase_day_type(phase) {
+            Ok(Some(day_type)) => day_type,
+            Ok(None) => continue,
+            Err(()) => {
+                ingest_logs.warn(
+                    TaxaIngestErrorCode::UnrecognizedValue,
+                    format!("Unrecognized phase {phase:?}"),
+                );
+                continue;
+            }
+        };
+
+        phases.push(NewSeasonPhase {
+            season: time.season_number,
+            day_type: taxa.day_type_id(day_type),
+            started_at: **started_at,
+            ended_at: scheduled_phases
+                .get(i + 1)
+                .map(|(next_started_at, _)| **next_started_at),
+            from_version: valid_from,
+        });
+    }
+
+    if phases.is_empty() {
+        // Don't replace the season's existing phases with nothing
+        ingest_logs.info(format!(
+            "Time version has no scheduled phases for season {}",
+            time.season_number
+        ));
+        return (new_processed, None, ingest_logs.into_vec());
+    }
+
+    let new_season_phases = NewSeasonPhases {
+        season: time.season_number,
+        from_version: valid_from,
+        phases,
+    };
+
+    (new_processed, Some(new_season_phases), ingest_logs.into_vec())
+}
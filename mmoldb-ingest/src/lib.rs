@@ -0,0 +1,27 @@
+pub mod analytics_export;
+pub mod async_pool;
+pub mod attribute_distributions;
+pub mod bulk_insert_gate;
+pub mod config;
+mod ingest;
+pub mod ingest_feed_shared;
+mod ingest_games;
+pub mod ingest_lock;
+mod ingest_player_feed;
+mod ingest_players;
+mod ingest_stadiums;
+mod ingest_team_feed;
+mod ingest_teams;
+mod ingest_time;
+pub mod modifier_effects_value;
+pub mod offload;
+mod partitioner;
+pub mod processing_locks;
+pub mod quality_alerts;
+pub mod rebuild;
+pub mod retention;
+pub mod round_trip;
+pub mod season_rollover;
+pub mod version_chains;
+
+pub use ingest::*;
@@ -1,19 +1,18 @@
-mod config;
-mod ingest;
-pub mod ingest_feed_shared;
-mod ingest_games;
-mod ingest_player_feed;
-mod ingest_players;
-mod ingest_team_feed;
-mod ingest_teams;
-mod partitioner;
-mod modifier_effects_value;
-
 use chrono_humanize::{Accuracy, HumanTime, Tense};
-use config::IngestConfig;
 use futures::{FutureExt, StreamExt};
 use miette::{Context, IntoDiagnostic};
-use mmoldb_db::{ConnectionPool, PgConnection, QueryResult, db, taxa::Taxa};
+use mmoldb_db::{ConnectionPool, PgConnection, PoolHealthReporter, QueryResult, db, taxa::Taxa};
+use mmoldb_ingest::bulk_insert_gate::BulkInsertGate;
+use mmoldb_ingest::config::{IngestConfig, SinkConfig};
+use mmoldb_ingest::ingest_lock::{IngestLock, IngestLockAttempt, describe_ingest_lock_holder};
+use mmoldb_ingest::processing_locks::ProcessingLocks;
+use mmoldb_ingest::{
+    IngestFatalError, IngestStage, NdjsonSink, PostgresSink, Sink, analytics_export,
+    attribute_distributions, modifier_effects_value, quality_alerts, retention, season_rollover,
+    version_chains,
+};
+use std::collections::HashSet;
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::signal::unix as tokio_signal;
 use tokio::task::JoinHandle;
@@ -22,11 +21,15 @@ use tracing::{Instrument, Level, error, info, info_span, span, warn};
 
 use cap::Cap;
 use futures::stream::FuturesUnordered;
-pub use ingest::*;
 use std::alloc;
 
 static MEMORY_TRACKING_PERIOD_MS: u64 = 10_000;
 static ITEM_COUNTING_WAIT_MS: u64 = 30_000;
+/// The longest a counting matview refresh will wait for bulk inserts to
+/// finish before running anyway. Without this a busy ingest could put off
+/// the refresh indefinitely.
+static ITEM_COUNTING_MAX_DEFER_MS: u64 = 10 * 60 * 1000;
+static INGEST_LOCK_RETRY_MS: u64 = 30_000;
 
 #[global_allocator]
 static ALLOCATOR: Cap<alloc::System> = Cap::new(alloc::System, usize::MAX);
@@ -48,8 +51,71 @@ async fn memory_tracking_task(shutdown_requested: CancellationToken) {
     }
 }
 
-async fn counting_task(shutdown_requested: CancellationToken, pool: ConnectionPool) {
+async fn pool_health_task(
+    shutdown_requested: CancellationToken,
+    pool_health: PoolHealthReporter,
+    period: Duration,
+) {
+    let mut interval = tokio::time::interval(period);
+    // The first tick completes immediately
+    interval.tick().await;
+    let mut previous = pool_health.health();
+
     loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = shutdown_requested.cancelled() => { break; }
+        }
+
+        let health = pool_health.health();
+        let mean_wait = health
+            .mean_checkout_wait_since(&previous)
+            .map_or_else(|| "n/a".to_string(), |wait| format!("{:.3}s", wait.as_secs_f64()));
+        let oldest = health
+            .oldest_connection_age
+            .map_or_else(|| "n/a".to_string(), |age| format!("{:.0}s", age.as_secs_f64()));
+        let longest_checkout = health
+            .longest_checkout
+            .map_or_else(|| "n/a".to_string(), |age| format!("{:.0}s", age.as_secs_f64()));
+        info!(
+            "Database pool: {}/{} connections in use ({:.0}% saturated), {} idle, oldest \
+            connection {oldest}, longest checkout {longest_checkout}. {} checkouts (mean wait \
+            {mean_wait}), {} timeouts, {} connections opened and {} closed since the last \
+            report.",
+            health.in_use_connections(),
+            health.max_size,
+            health.saturation() * 100.0,
+            health.idle_connections,
+            health.checkouts - previous.checkouts,
+            health.timeouts - previous.timeouts,
+            health.connections_opened - previous.connections_opened,
+            health.connections_closed - previous.connections_closed,
+        );
+        previous = health;
+    }
+}
+
+async fn counting_task(
+    shutdown_requested: CancellationToken,
+    pool: ConnectionPool,
+    bulk_inserts: BulkInsertGate,
+) {
+    loop {
+        // Refreshing competes with bulk inserts for I/O, so wait for any
+        // in-flight inserts to commit first
+        if !bulk_inserts.is_idle() {
+            info!("Deferring entity counting matview refresh until bulk inserts finish");
+            let max_defer = Duration::from_millis(ITEM_COUNTING_MAX_DEFER_MS);
+            tokio::select! {
+                idle = bulk_inserts.wait_until_idle(max_defer) => {
+                    if !idle {
+                        info!("Bulk inserts are still running. Refreshing anyway.");
+                    }
+                }
+                _ = shutdown_requested.cancelled() => { break; }
+            }
+        }
+
         match pool.get() {
             Ok(mut conn) => {
                 info!("Refreshing entity counting matviews");
@@ -91,9 +157,43 @@ async fn main() -> miette::Result<()> {
 
     // Then all other setup tasks in approximate order of how quickly
     // they'll fail if they're going to fail
-    let (sigterm, sigint) = get_signal_listeners()?;
+    let (mut sigterm, mut sigint) = get_signal_listeners()?;
     let config = get_config()?;
-    let pool = mmoldb_db::get_pool(config.db_pool_size).into_diagnostic()?;
+    if let SinkConfig::Ndjson { dir } = &config.sink {
+        info!("Writing fetched data to NDJSON files in {}", dir.display());
+        let sink = Arc::new(NdjsonSink::new(dir)?);
+        return run_fetch_only(config, sink, sigterm, sigint).await;
+    }
+    let source = config.source.as_ref().map(|s| s.name.as_str());
+    // This has to be kept for as long as the ingest runs, because dropping
+    // it releases the lock
+    let Some(_ingest_lock) = acquire_ingest_lock(
+        config.wait_for_ingest_lock,
+        source,
+        &mut sigterm,
+        &mut sigint,
+    )
+    .await?
+    else {
+        return Ok(());
+    };
+    let (pool, pool_health) = mmoldb_db::get_pool_with_query_log(
+        config.db_pool_size,
+        &config.query_log,
+        &config.db_pool_health,
+    )
+    .into_diagnostic()?;
+    if let Some(source) = &config.source {
+        info!(
+            "Fetching from source {} at {}",
+            source.name, source.chron_base_url
+        );
+        // Migrating takes the main ingest's place, and this only holds the
+        // lock for its own source
+        mmoldb_db::require_current_schema()?;
+        let sink = Arc::new(PostgresSink::new(pool, Some(source.name.clone())));
+        return run_fetch_only(config, sink, sigterm, sigint).await;
+    }
     {
         let mut conn = pool.get().into_diagnostic()?;
         set_statement_timeout(&mut conn, config.set_postgres_statement_timeout)
@@ -102,9 +202,12 @@ async fn main() -> miette::Result<()> {
         modifier_effects_value::update_modifier_effects_values(&mut conn, &taxa).into_diagnostic()?;
     }
     mmoldb_db::run_migrations().into_diagnostic()?;
+    let aborted_stages = recover_aborted_stages(&pool)?;
 
     // Task coordination variables
     let shutdown_requested = tokio_util::sync::CancellationToken::new();
+    let bulk_inserts = BulkInsertGate::new();
+    let processing_locks = ProcessingLocks::new();
     // Writing out the full type for better error messages
     // TODO Get rid of errors. Handle all exceptional conditions without exiting.
     let tasks = FuturesUnordered::<JoinHandle<Result<(), IngestFatalError>>>::new();
@@ -116,37 +219,168 @@ async fn main() -> miette::Result<()> {
             .map(Ok)
             .instrument(info_span!("memory_tracking")),
     ));
+    if let Some(interval_secs) = config.db_pool_health.report_interval_secs {
+        info!("Launching background pool health task");
+        tasks.push(tokio::task::spawn(
+            pool_health_task(
+                shutdown_requested.clone(),
+                pool_health,
+                Duration::from_secs(interval_secs),
+            )
+            .map(Ok)
+            .instrument(info_span!("pool_health")),
+        ));
+    }
     info!("Launching background item counting task");
     tasks.push(tokio::task::spawn(
-        counting_task(shutdown_requested.clone(), pool.clone())
+        counting_task(
+            shutdown_requested.clone(),
+            pool.clone(),
+            bulk_inserts.clone(),
+        )
             .map(Ok)
             .instrument(info_span!("counting")),
     ));
 
+    if config.enable_season_rollover {
+        info!("Launching background season rollover task");
+        let game_archive = config
+            .game_offload
+            .as_ref()
+            .map(season_rollover::GameArchive::new)
+            .transpose()?;
+        tasks.push(tokio::task::spawn(
+            season_rollover::season_rollover_task(
+                shutdown_requested.clone(),
+                pool.clone(),
+                game_archive,
+            )
+            .map(Ok)
+            .instrument(info_span!("season_rollover")),
+        ));
+    }
+
+    if config.enable_attribute_distributions {
+        info!("Launching background attribute distribution task");
+        tasks.push(tokio::task::spawn(
+            attribute_distributions::attribute_distribution_task(
+                shutdown_requested.clone(),
+                pool.clone(),
+            )
+            .map(Ok)
+            .instrument(info_span!("attribute_distributions")),
+        ));
+    }
+
+    if config.enable_version_chain_checks {
+        info!("Launching background version chain check task");
+        tasks.push(tokio::task::spawn(
+            version_chains::version_chain_check_task(shutdown_requested.clone(), pool.clone())
+                .map(Ok)
+                .instrument(info_span!("version_chain_checks")),
+        ));
+    }
+
+    if config.enable_quality_alerts {
+        info!("Launching background data quality alert task");
+        tasks.push(tokio::task::spawn(
+            quality_alerts::quality_alert_task(shutdown_requested.clone(), pool.clone())
+                .map(Ok)
+                .instrument(info_span!("quality_alerts")),
+        ));
+    }
+
+    if let Some(retention_config) = &config.version_retention {
+        info!("Launching background version retention task");
+        tasks.push(tokio::task::spawn(
+            retention::version_retention_task(
+                shutdown_requested.clone(),
+                pool.clone(),
+                processing_locks.clone(),
+                retention_config,
+            )
+            .map(Ok)
+            .instrument(info_span!("version_retention")),
+        ));
+    }
+
+    if let Some(export_config) = &config.analytics_export {
+        info!("Launching background analytics export task");
+        tasks.push(tokio::task::spawn(
+            analytics_export::analytics_export_task(
+                shutdown_requested.clone(),
+                pool.clone(),
+                export_config,
+            )
+            .map(Ok)
+            .instrument(info_span!("analytics_export")),
+        ));
+    }
+
     if config.fetch_known_missing_games {
         warn!("Fetching known missing games is not currently implemented");
     }
 
     // Launch ingest tasks
-    let ingest_kinds = ingest::ingest_kinds(&shutdown_requested, &pool, config);
-    for ingest_kind in &ingest_kinds {
-        if ingest_kind.fetch_is_enabled() {
-            info!("Launching fetch task for {}", ingest_kind.kind());
-            let ingest_kind = ingest_kind.clone();
-            let task = tokio::task::spawn(async move { ingest_kind.fetch_task().await });
-            tasks.push(task);
+    let stages = mmoldb_ingest::stage_registry(
+        &shutdown_requested,
+        &pool,
+        &bulk_inserts,
+        &processing_locks,
+        config,
+    )?
+    .into_ordered_stages(config)?;
+    for stage in stages {
+        if !stage.is_enabled() {
+            info!("Ingest stage {} is disabled", stage.name());
+        } else if aborted_stages.contains(&stage.name()) && !config.resume_aborted_stages {
+            warn!(
+                "Not launching ingest stage {} because it was aborted. Set \
+                `resume_aborted_stages` to launch it.",
+                stage.name(),
+            );
         } else {
-            info!("Fetch task for {} is disabled", ingest_kind.kind());
+            info!("Launching ingest stage {}", stage.name());
+            tasks.push(tokio::task::spawn(run_stage_recording_state(
+                pool.clone(),
+                stage,
+            )));
         }
     }
-    for ingest_kind in &ingest_kinds {
-        if ingest_kind.processing_is_enabled() {
-            info!("Launching processing task for {}", ingest_kind.kind());
-            let ingest_kind = ingest_kind.clone();
-            let task = tokio::task::spawn(async move { ingest_kind.processing_task().await });
-            tasks.push(task);
+
+    info!("Running {} task(s)", tasks.len());
+    wait_until_shutdown(tasks, sigterm, sigint, shutdown_requested).await
+}
+
+/// Runs just the fetch stages, writing to `sink`. Nothing is processed, so
+/// none of the background tasks that maintain the processed tables run.
+/// Callers that write to the database must hold the ingest lock and make
+/// sure the schema is current first.
+async fn run_fetch_only(
+    config: &'static IngestConfig,
+    sink: Arc<dyn Sink>,
+    sigterm: tokio_signal::Signal,
+    sigint: tokio_signal::Signal,
+) -> miette::Result<()> {
+    info!("Only fetching, so nothing will be processed");
+    let shutdown_requested = CancellationToken::new();
+    let tasks = FuturesUnordered::<JoinHandle<Result<(), IngestFatalError>>>::new();
+
+    info!("Launching background memory tracking task");
+    tasks.push(tokio::task::spawn(
+        memory_tracking_task(shutdown_requested.clone())
+            .map(Ok)
+            .instrument(info_span!("memory_tracking")),
+    ));
+
+    let stages = mmoldb_ingest::fetch_only_stage_registry(&shutdown_requested, sink, config)?
+        .into_ordered_stages(config)?;
+    for stage in stages {
+        if stage.is_enabled() {
+            info!("Launching ingest stage {}", stage.name());
+            tasks.push(tokio::task::spawn(stage.run()));
         } else {
-            info!("Processing task for {} is disabled", ingest_kind.kind());
+            info!("Ingest stage {} is disabled", stage.name());
         }
     }
 
@@ -154,6 +388,64 @@ async fn main() -> miette::Result<()> {
     wait_until_shutdown(tasks, sigterm, sigint, shutdown_requested).await
 }
 
+/// Marks any stages the previous ingest left running as aborted, and
+/// returns every stage that's currently aborted. This has to run while
+/// holding the ingest lock, or it could abort another ingest's stages.
+fn recover_aborted_stages(pool: &ConnectionPool) -> miette::Result<HashSet<String>> {
+    let mut conn = pool.get().into_diagnostic()?;
+    for stage in db::abort_orphaned_ingest_stages(&mut conn).into_diagnostic()? {
+        warn!("Ingest stage {stage} was still running when the previous ingest exited");
+    }
+    let aborted = db::aborted_ingest_stages(&mut conn).into_diagnostic()?;
+    Ok(aborted.into_iter().collect())
+}
+
+/// Runs `stage`, recording in `info.ingest_task_state` when it starts and
+/// how it exits, including by panicking. If the process dies while this is
+/// running, the stage stays marked as running until the next ingest aborts
+/// it.
+async fn run_stage_recording_state(
+    pool: ConnectionPool,
+    stage: Arc<dyn IngestStage>,
+) -> Result<(), IngestFatalError> {
+    let name = stage.name();
+    record_stage_state(&pool, &name, |conn| {
+        db::record_ingest_stage_started(conn, &name)
+    });
+
+    // The stage runs in a task of its own so that a panic is recorded too
+    let (error, result) = match tokio::task::spawn(stage.run()).await {
+        Ok(result) => (result.as_ref().err().map(|err| err.to_string()), result),
+        Err(join_err) => (
+            Some(format!("The stage's task failed: {join_err}")),
+            Err(IngestFatalError::JoinError(join_err)),
+        ),
+    };
+    record_stage_state(&pool, &name, |conn| {
+        db::record_ingest_stage_exited(conn, &name, error.as_deref())
+    });
+    result
+}
+
+/// Failing to record a stage's state shouldn't stop the ingest, so errors
+/// are only logged
+fn record_stage_state(
+    pool: &ConnectionPool,
+    stage: &str,
+    record: impl FnOnce(&mut PgConnection) -> QueryResult<()>,
+) {
+    let result = match pool.get() {
+        Ok(mut conn) => record(&mut conn),
+        Err(err) => {
+            warn!("Couldn't get connection to record the state of ingest stage {stage}: {err}");
+            return;
+        }
+    };
+    if let Err(err) = result {
+        warn!("Error recording the state of ingest stage {stage}: {err}");
+    }
+}
+
 fn get_signal_listeners() -> miette::Result<(tokio_signal::Signal, tokio_signal::Signal)> {
     let sigterm = tokio_signal::signal(tokio_signal::SignalKind::terminate())
         .into_diagnostic()
@@ -164,6 +456,45 @@ fn get_signal_listeners() -> miette::Result<(tokio_signal::Signal, tokio_signal:
     Ok((sigterm, sigint))
 }
 
+/// Takes the lock that keeps two ingests of `source` from running against
+/// the same database at once. If another ingest holds it, either waits for
+/// it to be released or returns None to tell the caller to exit.
+async fn acquire_ingest_lock(
+    wait: bool,
+    source: Option<&str>,
+    sigterm: &mut tokio_signal::Signal,
+    sigint: &mut tokio_signal::Signal,
+) -> miette::Result<Option<IngestLock>> {
+    loop {
+        let holder = match IngestLock::try_acquire(source, "mmoldb-ingest").into_diagnostic()? {
+            IngestLockAttempt::Acquired(lock) => {
+                info!("Acquired the ingest lock");
+                return Ok(Some(lock));
+            }
+            IngestLockAttempt::Held(holder) => holder,
+        };
+        let held_by = describe_ingest_lock_holder(holder.as_ref());
+
+        if !wait {
+            warn!(
+                "Another mmoldb-ingest is already running against this database. {held_by} \
+                Exiting. Set `wait_for_ingest_lock` to wait for it instead.",
+            );
+            return Ok(None);
+        }
+
+        info!(
+            "Another mmoldb-ingest is already running against this database. {held_by} Waiting \
+            for it to exit.",
+        );
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_millis(INGEST_LOCK_RETRY_MS)) => {}
+            _ = sigterm.recv() => { return Ok(None); }
+            _ = sigint.recv() => { return Ok(None); }
+        }
+    }
+}
+
 fn get_config() -> miette::Result<&'static IngestConfig> {
     let config = IngestConfig::config().into_diagnostic()?;
     let config = Box::new(config);
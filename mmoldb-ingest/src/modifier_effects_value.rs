@@ -33,8 +33,8 @@ pub fn update_modifier_effects_values(
                         .map(|(effect_attribute, effect_value)| {
                             NewModificationEffects {
                                 modification_name,
-                                valid_from: effects_config.valid_from.naive_utc(),
-                                valid_until: effects_config.valid_until.as_ref().map(DateTime::naive_utc),
+                                valid_from: effects_config.valid_from,
+                                valid_until: effects_config.valid_until,
                                 attribute: taxa.attribute_id(*effect_attribute),
                                 effect_type: taxa.effect_type_id(effects_config.bonus_type),
                                 value: *effect_value,
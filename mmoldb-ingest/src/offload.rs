@@ -0,0 +1,291 @@
+//! Moves the raw data of old games out of Postgres and into S3-compatible
+//! object storage. The `data.entities` row stays behind as a pointer, and
+//! anything that needs to reprocess an offloaded game fetches it back.
+
+use crate::IngestFatalError;
+use chron::ChronEntity;
+use chrono::{DateTime, Utc};
+use itertools::Itertools;
+use mmoldb_db::db::OffloadedEntity;
+use mmoldb_db::{ObjectStoreError, PgConnection, QueryError, db};
+use thiserror::Error;
+use tracing::info;
+
+pub use mmoldb_db::ObjectStore;
+
+#[derive(Debug, Error)]
+pub enum OffloadError {
+    #[error(transparent)]
+    ObjectStore(#[from] ObjectStoreError),
+
+    #[error(
+        "{kind} {entity_id} has been offloaded to {key:?}, but no object storage is configured"
+    )]
+    NotConfigured {
+        kind: String,
+        entity_id: String,
+        key: String,
+    },
+
+    #[error(transparent)]
+    Db(#[from] QueryError),
+}
+
+/// Somewhere offloaded entities can be uploaded to and downloaded from
+pub trait EntityStore {
+    /// Where the given version of an entity is stored. The timestamp is part
+    /// of the key so a newer version of the same entity never overwrites an
+    /// older one that's still referenced.
+    fn key_for(&self, kind: &str, entity_id: &str, valid_from: DateTime<Utc>) -> String;
+
+    fn upload_entity(&self, key: &str, data: &serde_json::Value) -> Result<(), OffloadError>;
+
+    fn download_entity(&self, key: &str) -> Result<serde_json::Value, OffloadError>;
+}
+
+fn object_key(key_prefix: &str, kind: &str, entity_id: &str, valid_from: DateTime<Utc>) -> String {
+    format!(
+        "{key_prefix}{kind}/{entity_id}/{}.json",
+        valid_from.format("%Y%m%dT%H%M%S%.6fZ"),
+    )
+}
+
+impl EntityStore for ObjectStore {
+    fn key_for(&self, kind: &str, entity_id: &str, valid_from: DateTime<Utc>) -> String {
+        object_key(self.key_prefix(), kind, entity_id, valid_from)
+    }
+
+    fn upload_entity(&self, key: &str, data: &serde_json::Value) -> Result<(), OffloadError> {
+        Ok(self.upload_json(key, data)?)
+    }
+
+    fn download_entity(&self, key: &str) -> Result<serde_json::Value, OffloadError> {
+        Ok(self.download_json(key)?)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct OffloadStats {
+    pub games_offloaded: usize,
+    /// Games that were replaced by a newer version while they were being
+    /// uploaded. They stay in the database.
+    pub games_changed: usize,
+}
+
+/// Uploads every finished game that's more than `keep_recent_seasons`
+/// seasons older than the latest season, then replaces its data in the
+/// database with a pointer to the upload. Each batch is uploaded before any
+/// of its pointers are written, so an interrupted offload never loses data.
+pub fn offload_old_games(
+    conn: &mut PgConnection,
+    store: &impl EntityStore,
+    keep_recent_seasons: i32,
+    batch_size: usize,
+    actor: &str,
+) -> Result<OffloadStats, IngestFatalError> {
+    let mut stats = OffloadStats::default();
+    let Some(latest_season) = db::latest_game_season(conn)? else {
+        info!("There are no games, so there's nothing to offload");
+        return Ok(stats);
+    };
+    let max_season = latest_season - keep_recent_seasons;
+    info!("Offloading games from season {max_season} and earlier");
+
+    let mut after: Option<String> = None;
+    loop {
+        let entities =
+            db::get_game_entities_to_offload(conn, max_season, after.as_deref(), batch_size)?;
+        let Some(last) = entities.last() else {
+            break;
+        };
+        after = Some(last.entity_id.clone());
+
+        let keys = entities
+            .iter()
+            .map(|entity| store.key_for(&entity.kind, &entity.entity_id, entity.valid_from))
+            .collect_vec();
+        for (entity, key) in entities.iter().zip(&keys) {
+            store.upload_entity(key, &entity.data)?;
+        }
+
+        let offloaded = entities
+            .iter()
+            .zip(&keys)
+            .map(|(entity, key)| OffloadedEntity {
+                kind: &entity.kind,
+                entity_id: &entity.entity_id,
+                valid_from: entity.valid_from,
+                key,
+            })
+            .collect_vec();
+        let marked = db::mark_entities_offloaded(conn, &offloaded, actor)?;
+        stats.games_offloaded += marked;
+        stats.games_changed += offloaded.len() - marked;
+        info!("Offloaded {} games so far", stats.games_offloaded);
+    }
+
+    Ok(stats)
+}
+
+/// Moves every offloaded game back into the database. The objects are left
+/// in storage.
+pub fn restore_offloaded_games(
+    conn: &mut PgConnection,
+    store: &impl EntityStore,
+    batch_size: usize,
+    actor: &str,
+) -> Result<usize, IngestFatalError> {
+    let mut restored = 0;
+    let mut after: Option<String> = None;
+    loop {
+        let keys = db::get_offloaded_game_keys(conn, after.as_deref(), batch_size)?;
+        let Some((last_id, _)) = keys.last() else {
+            break;
+        };
+        after = Some(last_id.clone());
+
+        let games = keys
+            .iter()
+            .map(|(game_id, key)| Ok((game_id.as_str(), key.as_str(), store.download_entity(key)?)))
+            .collect::<Result<Vec<_>, IngestFatalError>>()?;
+        restored += db::restore_offloaded_entities(conn, "game", &games, actor)?;
+        info!("Restored {restored} games so far");
+    }
+
+    Ok(restored)
+}
+
+/// Fills in the data of any of `entities` that have been offloaded, so
+/// callers can treat them like any other stored entity. Entities that
+/// weren't offloaded are left alone.
+pub fn fetch_offloaded_entities<S: EntityStore>(
+    conn: &mut PgConnection,
+    store: Option<&S>,
+    kind: &str,
+    entities: &mut [ChronEntity<serde_json::Value>],
+) -> Result<(), IngestFatalError> {
+    let candidate_ids = entities
+        .iter()
+        .filter(|entity| entity.data.is_null())
+        .map(|entity| entity.entity_id.as_str())
+        .collect_vec();
+    if candidate_ids.is_empty() {
+        return Ok(());
+    }
+
+    let keys = db::get_offloaded_entity_keys(conn, kind, &candidate_ids)?;
+    for entity in entities.iter_mut() {
+        let Some(key) = keys.get(&entity.entity_id) else {
+            continue;
+        };
+        let Some(store) = store else {
+            return Err(OffloadError::NotConfigured {
+                kind: kind.to_string(),
+                entity_id: entity.entity_id.clone(),
+                key: key.clone(),
+            }
+            .into());
+        };
+        entity.data = store.download_entity(key)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mmoldb_db::Connection;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+
+    /// Keeps uploads in memory
+    #[derive(Default)]
+    struct MemoryStore {
+        objects: RefCell<HashMap<String, serde_json::Value>>,
+    }
+
+    impl EntityStore for MemoryStore {
+        fn key_for(&self, kind: &str, entity_id: &str, valid_from: DateTime<Utc>) -> String {
+            object_key("test/", kind, entity_id, valid_from)
+        }
+
+        fn upload_entity(&self, key: &str, data: &serde_json::Value) -> Result<(), OffloadError> {
+            self.objects
+                .borrow_mut()
+                .insert(key.to_string(), data.clone());
+            Ok(())
+        }
+
+        fn download_entity(&self, key: &str) -> Result<serde_json::Value, OffloadError> {
+            Ok(self.objects.borrow()[key].clone())
+        }
+    }
+
+    #[test]
+    fn keys_include_the_version() {
+        let valid_from = DateTime::parse_from_rfc3339("2025-06-01T12:34:56.789Z")
+            .unwrap()
+            .to_utc();
+        assert_eq!(
+            object_key("mmoldb/", "game", "abc", valid_from),
+            "mmoldb/game/abc/20250601T123456.789000Z.json",
+        );
+    }
+
+    #[test]
+    fn offloaded_games_round_trip() {
+        let url = mmoldb_db::postgres_url_from_environment();
+        let mut conn = PgConnection::establish(&url)
+            .expect("postgres_url_from_environment should return a valid connection string");
+
+        conn.test_transaction(|conn| {
+            let game = ChronEntity {
+                kind: "game".to_string(),
+                entity_id: "offload-round-trip-test".to_string(),
+                valid_from: Utc::now(),
+                valid_to: None,
+                data: serde_json::json!({ "State": "Complete" }),
+            };
+            db::insert_entities(conn, vec![game.clone()], None)?;
+
+            let store = MemoryStore::default();
+            let key = store.key_for(&game.kind, &game.entity_id, game.valid_from);
+            store.upload_entity(&key, &game.data)?;
+            let marked = db::mark_entities_offloaded(
+                conn,
+                &[OffloadedEntity {
+                    kind: &game.kind,
+                    entity_id: &game.entity_id,
+                    valid_from: game.valid_from,
+                    key: &key,
+                }],
+                "test",
+            )?;
+            assert_eq!(marked, 1);
+
+            // This is how the offloaded game comes out of the database
+            let offloaded = ChronEntity {
+                data: serde_json::Value::Null,
+                ..game.clone()
+            };
+
+            let mut entities = vec![offloaded.clone()];
+            let result =
+                fetch_offloaded_entities::<MemoryStore>(conn, None, "game", &mut entities);
+            assert!(matches!(
+                result,
+                Err(IngestFatalError::OffloadError(OffloadError::NotConfigured { .. }))
+            ));
+
+            let mut entities = vec![offloaded];
+            fetch_offloaded_entities(conn, Some(&store), "game", &mut entities)?;
+            assert_eq!(entities[0].data, game.data);
+
+            assert_eq!(restore_offloaded_games(conn, &store, 10, "test")?, 1);
+            assert!(db::get_offloaded_entity_keys(conn, "game", &[&game.entity_id])?.is_empty());
+
+            Ok::<_, IngestFatalError>(())
+        });
+    }
+}
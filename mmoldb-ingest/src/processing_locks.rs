@@ -0,0 +1,32 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::OwnedMutexGuard;
+
+/// One lock per kind, held by that kind's processing stage for the whole of
+/// each processing pass. Background maintenance that rewrites or deletes a
+/// kind's raw versions (like version retention) takes the same lock, so it
+/// never runs while that kind is being processed. The process-wide ingest
+/// lock can't do this, since this process already holds it.
+#[derive(Debug, Clone, Default)]
+pub struct ProcessingLocks {
+    locks: Arc<Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>>,
+}
+
+impl ProcessingLocks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Waits until nothing else holds `kind`'s lock, then holds it until the
+    /// guard is dropped
+    pub async fn lock(&self, kind: &str) -> OwnedMutexGuard<()> {
+        let lock = self
+            .locks
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .entry(kind.to_string())
+            .or_default()
+            .clone();
+        lock.lock_owned().await
+    }
+}
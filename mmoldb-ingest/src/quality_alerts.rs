@@ -0,0 +1,76 @@
+use mmoldb_db::db::{QualityStatus, QualitySummary};
+use mmoldb_db::{ConnectionPool, db};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+static QUALITY_CHECK_INTERVAL_MS: u64 = 15 * 60 * 1000;
+
+/// Builds the quality summary that mmoldb-app shows at `/quality`, and
+/// publishes the alerts from comparing it to `previous`
+fn check_quality(
+    pool: &ConnectionPool,
+    previous: Option<&QualitySummary>,
+) -> Option<QualitySummary> {
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            warn!("Couldn't get connection to check data quality: {e}");
+            return None;
+        }
+    };
+
+    let summary = match db::quality_summary(&mut conn) {
+        Ok(summary) => summary,
+        Err(err) => {
+            warn!("Error checking data quality: {err}");
+            return None;
+        }
+    };
+
+    for alert in db::quality_alerts(previous, &summary) {
+        if alert.status > alert.previous.unwrap_or(QualityStatus::Green) {
+            warn!(
+                "Data quality of {} is now {:?}: {}",
+                alert.name, alert.status, alert.summary,
+            );
+        } else {
+            info!(
+                "Data quality of {} is back to {:?}: {}",
+                alert.name, alert.status, alert.summary,
+            );
+        }
+        if let Err(err) = db::publish_quality_alert(&mut conn, &alert) {
+            warn!("Error publishing data quality alert: {err}");
+        }
+    }
+
+    Some(summary)
+}
+
+/// Periodically checks data quality and publishes an alert on
+/// [`db::QUALITY_ALERT_CHANNEL`] whenever a subsystem's status changes, so
+/// notifications can be sent without polling `/api/quality`
+pub async fn quality_alert_task(shutdown_requested: CancellationToken, pool: ConnectionPool) {
+    let mut previous: Option<QualitySummary> = None;
+    loop {
+        let check_pool = pool.clone();
+        let check_previous = previous.clone();
+        match tokio::task::spawn_blocking(move || {
+            check_quality(&check_pool, check_previous.as_ref())
+        })
+        .await
+        {
+            // A failed check keeps the previous summary, so a status change
+            // that happens across it is still reported
+            Ok(Some(summary)) => previous = Some(summary),
+            Ok(None) => {}
+            Err(err) => warn!("Data quality check panicked: {err}"),
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_millis(QUALITY_CHECK_INTERVAL_MS)) => {}
+            _ = shutdown_requested.cancelled() => { break; }
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use crate::IngestFatalError;
+use crate::ingest_games::prepare_game_for_db;
+use crate::offload::{self, ObjectStore};
+use chron::ChronEntity;
+use itertools::{Either, Itertools};
+use mmoldb_db::db::{DerivedTable, GameForDb, RebuildDerivedTableStats};
+use mmoldb_db::taxa::Taxa;
+use mmoldb_db::{Connection, PgConnection, db};
+use serde::de::IntoDeserializer;
+use tracing::{info, warn};
+
+pub use crate::ingest_team_feed::rebuild_team_transactions;
+
+/// Which stored games to work on. Only finished games are ever selected.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StoredGameSelection<'a> {
+    pub season: Option<i32>,
+    pub mmolb_game_ids: Option<&'a [String]>,
+}
+
+/// Loads the selected stored raw games in batches of `batch_size`, prepares
+/// them for the database without contacting Chron, and passes each batch to
+/// `f`. Games that fail to deserialize are logged and skipped. Returns how
+/// many were skipped.
+fn for_each_stored_game_batch(
+    conn: &mut PgConnection,
+    selection: StoredGameSelection,
+    batch_size: usize,
+    offload_store: Option<&ObjectStore>,
+    mut f: impl FnMut(&mut PgConnection, &[GameForDb]) -> Result<(), IngestFatalError>,
+) -> Result<usize, IngestFatalError> {
+    let mut games_skipped = 0;
+    let mut after: Option<String> = None;
+
+    loop {
+        let mut entities = db::get_game_entities_for_rebuild(
+            conn,
+            selection.season,
+            selection.mmolb_game_ids,
+            after.as_deref(),
+            batch_size,
+        )?;
+        let Some(last) = entities.last() else {
+            break;
+        };
+        after = Some(last.entity_id.clone());
+        offload::fetch_offloaded_entities(conn, offload_store, "game", &mut entities)?;
+
+        let (games, deserialize_failures): (Vec<_>, Vec<_>) =
+            entities.into_iter().partition_map(|entity| {
+                let des = entity.data.into_deserializer();
+                match serde_path_to_error::deserialize::<_, mmolb_parsing::Game>(des) {
+                    Ok(data) => Either::Left(ChronEntity {
+                        kind: entity.kind,
+                        entity_id: entity.entity_id,
+                        valid_from: entity.valid_from,
+                        valid_to: entity.valid_to,
+                        data,
+                    }),
+                    Err(err) => Either::Right((entity.entity_id, err)),
+                }
+            });
+
+        for (game_id, err) in &deserialize_failures {
+            warn!("Skipping game {game_id} because it failed to deserialize: {err}");
+        }
+        games_skipped += deserialize_failures.len();
+
+        let games_for_db = games
+            .iter()
+            .map(prepare_game_for_db)
+            .collect::<Result<Vec<GameForDb>, _>>()?;
+
+        f(conn, &games_for_db)?;
+    }
+
+    Ok(games_skipped)
+}
+
+/// Regenerates one derived table from the stored raw games, optionally only
+/// for games from one season. Games are processed in batches of
+/// `batch_size`, each in its own transaction, so an interrupted rebuild
+/// leaves every batch either fully old or fully new. Each batch is recorded
+/// in the admin audit log under `actor`. Offloaded games are fetched back
+/// from `offload_store`.
+pub fn rebuild_table(
+    conn: &mut PgConnection,
+    table: DerivedTable,
+    season: Option<i32>,
+    batch_size: usize,
+    actor: &str,
+    offload_store: Option<&ObjectStore>,
+) -> Result<RebuildDerivedTableStats, IngestFatalError> {
+    let taxa = Taxa::new(conn)?;
+    let mut stats = RebuildDerivedTableStats::default();
+
+    let selection = StoredGameSelection {
+        season,
+        mmolb_game_ids: None,
+    };
+    let deserialize_failures =
+        for_each_stored_game_batch(conn, selection, batch_size, offload_store, |conn, games| {
+            let batch_stats = db::rebuild_derived_table(conn, &taxa, table, games, actor)?;
+            stats.games_rebuilt += batch_stats.games_rebuilt;
+            stats.games_skipped += batch_stats.games_skipped;
+            stats.games_failed += batch_stats.games_failed;
+            info!(
+                "Rebuilt {table} for {} games so far ({} skipped, {} failed)",
+                stats.games_rebuilt, stats.games_skipped, stats.games_failed,
+            );
+            Ok(())
+        })?;
+    stats.games_skipped += deserialize_failures;
+
+    Ok(stats)
+}
+
+/// The derived tables that come out of the same sim pass as the game
+/// metadata, and so are recomputed alongside it
+const RECOMPUTED_TABLES: [DerivedTable; 2] = [DerivedTable::Parties, DerivedTable::Wither];
+
+#[derive(Debug, Default)]
+pub struct RecomputeGamesStats {
+    pub games_recomputed: usize,
+    /// Games whose final scores, coins, photo contest results, or manager or
+    /// stadium names changed
+    pub games_changed: usize,
+    /// Games that failed to deserialize, or whose stored events don't line
+    /// up with the raw game. These need a full reingest.
+    pub games_skipped: usize,
+    /// Games that hit a fatal error when they were run through the sim, so
+    /// nothing could be recomputed for them
+    pub games_failed: usize,
+}
+
+/// Re-runs only the sim over the selected stored raw games and rewrites
+/// what it derives: the metadata columns of `data.games`, parties, and
+/// withers. Nothing is fetched from Chron, except that offloaded games are
+/// fetched back from `offload_store`. Each batch of `batch_size` games is
+/// updated in one transaction and recorded in the admin audit log under
+/// `actor`.
+pub fn recompute_games(
+    conn: &mut PgConnection,
+    selection: StoredGameSelection,
+    batch_size: usize,
+    actor: &str,
+    offload_store: Option<&ObjectStore>,
+) -> Result<RecomputeGamesStats, IngestFatalError> {
+    let taxa = Taxa::new(conn)?;
+    let mut stats = RecomputeGamesStats::default();
+
+    let deserialize_failures =
+        for_each_stored_game_batch(conn, selection, batch_size, offload_store, |conn, games| {
+            conn.transaction::<_, IngestFatalError, _>(|conn| {
+                let metadata_stats = db::recompute_game_metadata(conn, games, actor)?;
+                // Games missing from data.games are skipped by every step, so
+                // counting them once is enough
+                let mut games_skipped = metadata_stats.games_skipped;
+                let mut games_failed = 0;
+                for table in RECOMPUTED_TABLES {
+                    let table_stats = db::rebuild_derived_table(conn, &taxa, table, games, actor)?;
+                    games_skipped = games_skipped.max(table_stats.games_skipped);
+                    games_failed = table_stats.games_failed;
+                }
+
+                stats.games_recomputed += metadata_stats.games_recomputed;
+                stats.games_changed += metadata_stats.games_changed;
+                stats.games_skipped += games_skipped;
+                stats.games_failed += games_failed;
+                Ok(())
+            })?;
+            info!(
+                "Recomputed {} games so far ({} changed, {} skipped, {} failed)",
+                stats.games_recomputed,
+                stats.games_changed,
+                stats.games_skipped,
+                stats.games_failed,
+            );
+            Ok(())
+        })?;
+    stats.games_skipped += deserialize_failures;
+
+    Ok(stats)
+}
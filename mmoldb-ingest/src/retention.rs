@@ -0,0 +1,176 @@
+use crate::config::VersionRetentionConfig;
+use crate::processing_locks::ProcessingLocks;
+use chrono::{DateTime, Utc};
+use mmoldb_db::{Connection, ConnectionPool, PgConnection, QueryResult, db};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+/// Old versions are written once and rarely read again, so it's worth
+/// spending more time compressing them
+const COMPRESSION_LEVEL: i32 = 9;
+
+/// Compresses `kind` versions that have been out of date for at least
+/// `compress_after_days`, until there's nothing left to compress or
+/// shutdown is requested. Returns the number of versions compressed.
+fn compress_old_versions(
+    conn: &mut PgConnection,
+    kind: &str,
+    compress_after_days: u32,
+    config: &VersionRetentionConfig,
+    shutdown_requested: &CancellationToken,
+) -> QueryResult<usize> {
+    let older_than = Utc::now() - chrono::Duration::days(compress_after_days.into());
+    let batch_size = config.batch_size.get() as i64;
+    info!("Compressing {kind} versions that were replaced before {older_than}");
+
+    let mut after: Option<(String, DateTime<Utc>)> = None;
+    let mut num_compressed = 0;
+    let mut uncompressed_bytes = 0;
+    let mut compressed_bytes = 0;
+    while !shutdown_requested.is_cancelled() {
+        let compressed = conn.transaction(|conn| {
+            let after = after
+                .as_ref()
+                .map(|(entity_id, valid_from)| (entity_id.as_str(), *valid_from));
+            db::compress_versions(conn, kind, older_than, after, batch_size, COMPRESSION_LEVEL)
+        })?;
+        num_compressed += compressed.num_versions;
+        uncompressed_bytes += compressed.uncompressed_bytes;
+        compressed_bytes += compressed.compressed_bytes;
+        debug!(
+            "Compressed {} {kind} versions from {} to {} bytes",
+            compressed.num_versions, compressed.uncompressed_bytes, compressed.compressed_bytes,
+        );
+
+        let Some(last_version) = compressed.last_version else {
+            break;
+        };
+        after = Some(last_version);
+    }
+
+    info!(
+        "Compressed {num_compressed} {kind} versions from {uncompressed_bytes} to \
+        {compressed_bytes} bytes",
+    );
+    Ok(num_compressed)
+}
+
+/// Prunes `kind` versions until every entity has been looked at or shutdown
+/// is requested. Returns the number of versions deleted.
+fn prune_old_versions(
+    conn: &mut PgConnection,
+    kind: &str,
+    config: &VersionRetentionConfig,
+    shutdown_requested: &CancellationToken,
+) -> QueryResult<i64> {
+    let older_than = Utc::now() - chrono::Duration::days(config.min_age_days.into());
+    let keep_latest = i64::from(config.keep_latest_versions.get());
+    let batch_size = config.batch_size.get() as i64;
+    info!(
+        "Pruning {kind} versions that were replaced before {older_than}, keeping the \
+        latest {keep_latest} versions of each {kind}",
+    );
+
+    let mut after_entity_id = String::new();
+    let mut kind_pruned = 0;
+    while !shutdown_requested.is_cancelled() {
+        let pruned = db::prune_versions(
+            conn,
+            kind,
+            keep_latest,
+            older_than,
+            &after_entity_id,
+            batch_size,
+        )?;
+
+        // The timestamps are always set when anything was pruned
+        if let (Some(earliest), Some(latest)) =
+            (pruned.earliest_valid_from, pruned.latest_valid_from)
+        {
+            debug!(
+                "Pruned {} {kind} versions of {} entities, valid from {earliest} to {latest}",
+                pruned.num_versions, pruned.num_entities,
+            );
+        }
+        kind_pruned += pruned.num_versions;
+
+        let Some(last_entity_id) = pruned.last_entity_id else {
+            break;
+        };
+        after_entity_id = last_entity_id;
+    }
+
+    info!("Pruned {kind_pruned} {kind} versions");
+    Ok(kind_pruned)
+}
+
+/// Compresses and then prunes one kind's versions. Errors are logged and
+/// otherwise ignored, since the next pass will try again.
+fn apply_retention_policy(
+    pool: &ConnectionPool,
+    kind: &str,
+    config: &VersionRetentionConfig,
+    shutdown_requested: &CancellationToken,
+) {
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(err) => {
+            warn!("Couldn't get connection to apply the {kind} retention policy: {err}");
+            return;
+        }
+    };
+
+    if let Some(compress_after_days) = config.compress_after_days {
+        let result = compress_old_versions(
+            &mut conn,
+            kind,
+            compress_after_days,
+            config,
+            shutdown_requested,
+        );
+        if let Err(err) = result {
+            warn!("Error compressing old {kind} versions: {err}");
+        }
+    }
+
+    if let Err(err) = prune_old_versions(&mut conn, kind, config, shutdown_requested) {
+        warn!("Error pruning old {kind} versions: {err}");
+    }
+}
+
+/// Periodically compresses and deletes raw versions that are older than the
+/// retention policy allows. Each kind is only touched while its processing
+/// stage is idle.
+pub async fn version_retention_task(
+    shutdown_requested: CancellationToken,
+    pool: ConnectionPool,
+    processing_locks: ProcessingLocks,
+    config: &'static VersionRetentionConfig,
+) {
+    loop {
+        for kind in &config.kinds {
+            let processing_lock = tokio::select! {
+                lock = processing_locks.lock(kind) => lock,
+                _ = shutdown_requested.cancelled() => { return; }
+            };
+
+            let pool = pool.clone();
+            let shutdown_requested = shutdown_requested.clone();
+            let result = tokio::task::spawn_blocking(move || {
+                apply_retention_policy(&pool, kind, config, &shutdown_requested)
+            })
+            .await;
+            drop(processing_lock);
+
+            if let Err(err) = result {
+                warn!("Version retention for {kind} panicked: {err}");
+            }
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs(config.interval_minutes * 60)) => {}
+            _ = shutdown_requested.cancelled() => { break; }
+        }
+    }
+}
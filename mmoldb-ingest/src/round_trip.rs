@@ -0,0 +1,149 @@
+use crate::IngestFatalError;
+use crate::ingest_games::{check_round_trips, prepare_game_for_db};
+use crate::offload::{self, ObjectStore};
+use chron::ChronEntity;
+use hashbrown::HashMap;
+use itertools::{Either, Itertools};
+use mmoldb_db::db::{EventInsertMode, GameForDb};
+use mmoldb_db::taxa::Taxa;
+use mmoldb_db::{Connection, IngestLog, PgConnection, QueryError, db};
+use serde::de::IntoDeserializer;
+use tracing::info;
+
+/// A game from the corpus that didn't survive the round trip
+#[derive(Debug)]
+pub enum RoundTripFailure {
+    /// The stored raw game couldn't be deserialized or processed, so it
+    /// never reached the database
+    Unprocessable {
+        game_id: String,
+        error_message: String,
+    },
+    /// The events read back from the database don't match the events that
+    /// were written
+    Mismatch {
+        game_id: String,
+        logs: Vec<IngestLog>,
+    },
+}
+
+#[derive(Debug, Default)]
+pub struct RoundTripReport {
+    pub games_checked: usize,
+    /// Games that aren't completed. They have no events, so there's nothing
+    /// to round-trip.
+    pub games_without_events: usize,
+    pub failures: Vec<RoundTripFailure>,
+}
+
+/// Runs stored raw games through the full ingest path (parse, insert, read
+/// back, reconstruct) and reports every game whose events don't round-trip.
+/// Each batch is inserted in a transaction that is always rolled back, so
+/// this is safe to run against any database, but it's meant for a local
+/// snapshot.
+pub fn check_stored_games(
+    conn: &mut PgConnection,
+    season: Option<i32>,
+    max_games: Option<usize>,
+    batch_size: usize,
+    offload_store: Option<&ObjectStore>,
+) -> Result<RoundTripReport, IngestFatalError> {
+    let taxa = Taxa::new(conn)?;
+    let mut report = RoundTripReport::default();
+    let mut after: Option<String> = None;
+
+    loop {
+        let limit = match max_games {
+            Some(max_games) => batch_size.min(max_games - report.games_checked),
+            None => batch_size,
+        };
+        if limit == 0 {
+            break;
+        }
+
+        let mut entities =
+            db::get_game_entities_for_rebuild(conn, season, None, after.as_deref(), limit)?;
+        let Some(last) = entities.last() else {
+            break;
+        };
+        after = Some(last.entity_id.clone());
+        offload::fetch_offloaded_entities(conn, offload_store, "game", &mut entities)?;
+        report.games_checked += entities.len();
+
+        let (games, deserialize_failures): (Vec<_>, Vec<_>) =
+            entities.into_iter().partition_map(|entity| {
+                let des = entity.data.into_deserializer();
+                match serde_path_to_error::deserialize::<_, mmolb_parsing::Game>(des) {
+                    Ok(data) => Either::Left(ChronEntity {
+                        kind: entity.kind,
+                        entity_id: entity.entity_id,
+                        valid_from: entity.valid_from,
+                        valid_to: entity.valid_to,
+                        data,
+                    }),
+                    Err(err) => Either::Right(RoundTripFailure::Unprocessable {
+                        game_id: entity.entity_id,
+                        error_message: err.to_string(),
+                    }),
+                }
+            });
+        report.failures.extend(deserialize_failures);
+
+        let games_for_db = games
+            .iter()
+            .map(prepare_game_for_db)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut mmolb_game_ids = Vec::new();
+        for game in &games_for_db {
+            match game {
+                GameForDb::Completed { game, .. } => mmolb_game_ids.push(game.id),
+                GameForDb::FatalError {
+                    game_id,
+                    error_message,
+                    ..
+                } => report.failures.push(RoundTripFailure::Unprocessable {
+                    game_id: game_id.to_string(),
+                    error_message: error_message.clone(),
+                }),
+                _ => report.games_without_events += 1,
+            }
+        }
+
+        let mut mismatches = Vec::new();
+        let result = conn.transaction(|conn| {
+            db::insert_games(conn, &taxa, &games_for_db, EventInsertMode::default())?;
+            let (ingested_games, _) = db::events_for_games(conn, &taxa, &mmolb_game_ids)?;
+
+            let mmolb_id_for_game_id: HashMap<i64, &str> = ingested_games
+                .iter()
+                .map(|(game_id, _)| *game_id)
+                .zip(mmolb_game_ids.iter().copied())
+                .collect();
+            mismatches = check_round_trips(&games_for_db, &ingested_games)
+                .into_iter()
+                .map(|(game_id, logs)| RoundTripFailure::Mismatch {
+                    game_id: mmolb_id_for_game_id[&game_id].to_string(),
+                    logs,
+                })
+                .collect();
+
+            // Never keep anything this wrote
+            Err(QueryError::RollbackTransaction)
+        });
+        match result {
+            Err(QueryError::RollbackTransaction) => {}
+            Err(err) => return Err(err.into()),
+            Ok(()) => unreachable!("The round-trip transaction always rolls back"),
+        }
+        report.failures.extend(mismatches);
+
+        info!(
+            "Checked {} games so far, {} failed",
+            report.games_checked,
+            report.failures.len(),
+        );
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,83 @@
+use miette::{IntoDiagnostic, miette};
+use mmoldb_ingest::config::IngestConfig;
+use mmoldb_ingest::offload::ObjectStore;
+use mmoldb_ingest::round_trip::{RoundTripFailure, check_stored_games};
+
+static ROUND_TRIP_BATCH_SIZE: usize = 100;
+
+const USAGE: &str = "Usage: mmoldb-round-trip [--season <season>] [--max-games <count>]";
+
+fn main() -> miette::Result<()> {
+    let filter = tracing_subscriber::EnvFilter::builder()
+        .with_default_directive("mmoldb_ingest=info".parse().into_diagnostic()?)
+        .from_env()
+        .into_diagnostic()?
+        .add_directive("mmolb_parsing=off".parse().into_diagnostic()?);
+
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .compact()
+        .init();
+
+    let mut season = None;
+    let mut max_games = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| miette!("{arg} needs a value"));
+        match arg.as_str() {
+            "--season" => season = Some(value()?.parse::<i32>().into_diagnostic()?),
+            "--max-games" => max_games = Some(value()?.parse::<usize>().into_diagnostic()?),
+            other => return Err(miette!("Unexpected argument {other:?}\n{USAGE}")),
+        }
+    }
+
+    let store = IngestConfig::config()
+        .into_diagnostic()?
+        .game_offload
+        .map(|config| ObjectStore::new(&config.store))
+        .transpose()
+        .into_diagnostic()?;
+
+    let pool = mmoldb_db::get_pool(1).into_diagnostic()?;
+    let mut conn = pool.get().into_diagnostic()?;
+    let report = check_stored_games(
+        &mut conn,
+        season,
+        max_games,
+        ROUND_TRIP_BATCH_SIZE,
+        store.as_ref(),
+    )
+    .into_diagnostic()?;
+
+    for failure in &report.failures {
+        match failure {
+            RoundTripFailure::Unprocessable {
+                game_id,
+                error_message,
+            } => {
+                println!("{game_id}: couldn't be processed: {error_message}");
+            }
+            RoundTripFailure::Mismatch { game_id, logs } => {
+                for log in logs {
+                    println!("{game_id} event {}: {}", log.game_event_index, log.log_text);
+                }
+            }
+        }
+    }
+
+    println!(
+        "Checked {} games ({} without events). {} failed to round-trip.",
+        report.games_checked,
+        report.games_without_events,
+        report.failures.len(),
+    );
+
+    if report.failures.is_empty() {
+        Ok(())
+    } else {
+        Err(miette!(
+            "{} games failed to round-trip",
+            report.failures.len()
+        ))
+    }
+}
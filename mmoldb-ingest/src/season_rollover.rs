@@ -0,0 +1,148 @@
+use crate::IngestFatalError;
+use crate::config::GameOffloadConfig;
+use crate::offload::{self, ObjectStore, OffloadError};
+use mmoldb_db::{ConnectionPool, PgConnection, QueryResult, db};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+static SEASON_ROLLOVER_CHECK_INTERVAL_MS: u64 = 10 * 60 * 1000;
+static ARCHIVE_BATCH_SIZE: usize = 100;
+
+enum RolloverJob {
+    /// Runs in one transaction, together with recording that it ran
+    Db(fn(&mut PgConnection, i32) -> QueryResult<usize>),
+    /// Offloads the games that the finished season pushed out of
+    /// `keep_recent_seasons`. Only runs when game offloading is configured.
+    /// Each batch is committed on its own, so an interrupted archive picks
+    /// up where it left off.
+    ArchiveGames,
+}
+
+/// One-time jobs that run once for every finished season, in this order.
+/// The names are stored in `info.season_rollovers`, so they must never
+/// change. None of mmoldb's tables are partitioned by season, so there are
+/// no partitions to create.
+const SEASON_ROLLOVER_TASKS: &[(&str, RolloverJob)] = &[
+    (
+        "final_standings",
+        RolloverJob::Db(db::snapshot_season_final_standings),
+    ),
+    ("awards", RolloverJob::Db(db::compute_season_awards)),
+    ("archive_games", RolloverJob::ArchiveGames),
+];
+
+/// Where finished seasons' games are archived to, if anywhere
+#[derive(Clone)]
+pub struct GameArchive {
+    pub store: Arc<ObjectStore>,
+    pub config: &'static GameOffloadConfig,
+}
+
+impl GameArchive {
+    pub fn new(config: &'static GameOffloadConfig) -> Result<Self, IngestFatalError> {
+        Ok(Self {
+            store: Arc::new(ObjectStore::new(&config.store).map_err(OffloadError::from)?),
+            config,
+        })
+    }
+}
+
+fn archive_games(
+    conn: &mut PgConnection,
+    archive: &GameArchive,
+    season: i32,
+) -> Result<usize, IngestFatalError> {
+    let stats = offload::offload_old_games(
+        conn,
+        archive.store.as_ref(),
+        archive.config.keep_recent_seasons,
+        ARCHIVE_BATCH_SIZE,
+    )?;
+    db::record_season_rollover_attempt(conn, season, "archive_games", None)?;
+    Ok(stats.games_offloaded)
+}
+
+/// Runs every rollover task that hasn't succeeded for every finished
+/// season. A task that fails is recorded and skipped, and is retried on
+/// the next check.
+fn run_pending_rollovers(
+    conn: &mut PgConnection,
+    archive: Option<&GameArchive>,
+    shutdown_requested: &CancellationToken,
+) -> QueryResult<()> {
+    for (task, job) in SEASON_ROLLOVER_TASKS {
+        for season in db::seasons_pending_rollover(conn, task)? {
+            if shutdown_requested.is_cancelled() {
+                return Ok(());
+            }
+
+            info!("Running season rollover task {task} for season {season}");
+            let result = match job {
+                RolloverJob::Db(job) => db::run_season_rollover_task(conn, season, task, *job)
+                    .map_err(IngestFatalError::from),
+                RolloverJob::ArchiveGames => {
+                    let Some(archive) = archive else {
+                        break;
+                    };
+                    archive_games(conn, archive, season).inspect_err(|err| {
+                        let error = err.to_string();
+                        if let Err(err) =
+                            db::record_season_rollover_attempt(conn, season, task, Some(&error))
+                        {
+                            warn!("Couldn't record failed season rollover task {task}: {err}");
+                        }
+                    })
+                }
+            };
+            match result {
+                Ok(affected) => {
+                    info!("Season rollover task {task} for season {season} wrote {affected} rows");
+                }
+                Err(err) => {
+                    warn!("Season rollover task {task} for season {season} failed: {err}");
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn season_rollover_task(
+    shutdown_requested: CancellationToken,
+    pool: ConnectionPool,
+    archive: Option<GameArchive>,
+) {
+    loop {
+        let rollover_pool = pool.clone();
+        let rollover_archive = archive.clone();
+        let rollover_shutdown_requested = shutdown_requested.clone();
+        let result = tokio::task::spawn_blocking(move || match rollover_pool.get() {
+            Ok(mut conn) => {
+                let result = run_pending_rollovers(
+                    &mut conn,
+                    rollover_archive.as_ref(),
+                    &rollover_shutdown_requested,
+                );
+                if let Err(err) = result {
+                    warn!("Error running season rollover tasks: {err}");
+                }
+            }
+            Err(e) => {
+                warn!("Couldn't get connection to run season rollover tasks: {e}");
+            }
+        })
+        .await;
+        if let Err(err) = result {
+            warn!("Season rollover tasks panicked: {err}");
+        }
+
+        // Seasons end rarely, so there's no need to check often
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_millis(SEASON_ROLLOVER_CHECK_INTERVAL_MS)) => {}
+            _ = shutdown_requested.cancelled() => { break; }
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use mmoldb_db::taxa::Taxa;
+use mmoldb_db::{ConnectionPool, db};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+static VERSION_CHAIN_CHECK_INTERVAL_MS: u64 = 6 * 60 * 60 * 1000;
+
+/// Periodically checks that the player, team, and equipment version chains
+/// have no gaps or overlaps. See [`db::check_version_chains`].
+pub async fn version_chain_check_task(shutdown_requested: CancellationToken, pool: ConnectionPool) {
+    loop {
+        match pool.get() {
+            Ok(mut conn) => match Taxa::new(&mut conn)
+                .and_then(|taxa| db::check_version_chains(&mut conn, &taxa))
+            {
+                Ok(checks) => {
+                    for check in checks {
+                        if check.gaps > 0 || check.overlaps > 0 {
+                            warn!(
+                                "{} has {} version chain gaps and {} overlaps",
+                                check.table, check.gaps, check.overlaps,
+                            );
+                        } else {
+                            info!("{} has no version chain gaps or overlaps", check.table);
+                        }
+                    }
+                }
+                Err(err) => {
+                    warn!("Error checking version chains: {err}");
+                }
+            },
+            Err(e) => {
+                warn!("Couldn't get connection to check version chains: {e}");
+            }
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_millis(VERSION_CHAIN_CHECK_INTERVAL_MS)) => {}
+            _ = shutdown_requested.cancelled() => { break; }
+        }
+    }
+}